@@ -0,0 +1,131 @@
+//! Throughput of storage writes with and without micro-batching
+//!
+//! `per_call_cost` stands in for a disk-backed store that pays a fixed price
+//! (a syscall, an fsync) per call regardless of how much it writes.
+//!
+//! Run with `cargo bench --bench storage_batching`.
+
+use async_trait::async_trait;
+use chaincraft_rust::{
+    batching::{BatchConfig, BatchingStorage},
+    storage::{MemoryStorage, Storage},
+    ChaincraftNode, Result,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const WRITES: usize = 256;
+const CALL_COST: Duration = Duration::from_micros(20);
+
+/// Memory storage charging `CALL_COST` per call
+struct PerCallCost(MemoryStorage);
+
+#[async_trait]
+impl Storage for PerCallCost {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.0.get(key).await
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        std::thread::sleep(CALL_COST);
+        self.0.put(key, value).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        std::thread::sleep(CALL_COST);
+        self.0.delete(key).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.0.exists(key).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.0.clear().await
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn write_batch(&self, batch: Vec<(String, Option<Vec<u8>>)>) -> Result<()> {
+        std::thread::sleep(CALL_COST);
+        self.0.write_batch(batch).await
+    }
+}
+
+async fn backend(name: &str, batched: bool) -> Arc<dyn Storage> {
+    let inner: Arc<dyn Storage> = match name {
+        "memory" => Arc::new(MemoryStorage::new()),
+        _ => Arc::new(PerCallCost(MemoryStorage::new())),
+    };
+    if !batched {
+        return inner;
+    }
+    BatchingStorage::open(inner, BatchConfig::default())
+        .await
+        .unwrap()
+}
+
+async fn write_all(storage: &dyn Storage) {
+    for i in 0..WRITES {
+        storage
+            .put(&format!("key-{}", i), vec![0u8; 256])
+            .await
+            .unwrap();
+    }
+    storage.flush().await.unwrap();
+}
+
+fn bench_puts(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("storage_puts");
+    group.throughput(Throughput::Elements(WRITES as u64));
+    for name in ["memory", "per_call_cost"] {
+        for batched in [false, true] {
+            let id = BenchmarkId::new(name, if batched { "batched" } else { "direct" });
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    runtime.block_on(async {
+                        let storage = backend(name, batched).await;
+                        write_all(storage.as_ref()).await;
+                    })
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_node_pipeline(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("node_messages");
+    group.throughput(Throughput::Elements(100));
+    for batched in [false, true] {
+        let id = if batched { "batched" } else { "direct" };
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    let storage = backend("per_call_cost", batched).await;
+                    let mut node = ChaincraftNode::builder()
+                        .with_storage(storage)
+                        .build()
+                        .unwrap();
+                    for i in 0..100 {
+                        node.create_shared_message_with_data(json!({ "high_freq": i }))
+                            .await
+                            .unwrap();
+                    }
+                    node.close().await.unwrap();
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_puts, bench_node_pipeline);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+//! Ingestion of signed sync batches, one message at a time versus with
+//! signatures verified in parallel
+//!
+//! The receiving node is authenticated, so every message's signature is
+//! checked. `sequential` submits the messages one by one, verifying each on
+//! the way in; `parallel` verifies a whole batch on a CPU pool with one thread
+//! per core before applying it in order; on a machine with 8 or more cores it
+//! should ingest several times faster than `sequential`.
+//!
+//! Run with `cargo bench --bench sync_ingestion`.
+
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, NodeMode, PeerId,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::json;
+use tokio::runtime::Runtime;
+
+const MESSAGES: usize = 2000;
+
+fn signed_messages() -> Vec<SharedMessage> {
+    let keys: Vec<_> = [KeyType::Ed25519, KeyType::Secp256k1]
+        .into_iter()
+        .map(|key_type| utils::generate_keypair(key_type).unwrap().0)
+        .collect();
+    (0..MESSAGES)
+        .map(|n| {
+            let mut message =
+                SharedMessage::new(MessageType::Custom("transfer".to_string()), json!({ "n": n }));
+            message.sign(&keys[n % keys.len()]).unwrap();
+            message
+        })
+        .collect()
+}
+
+fn receiver() -> ChaincraftNode {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    ChaincraftNode::builder()
+        .with_mode(NodeMode::Authenticated)
+        .with_cpu_threads(threads)
+        .build()
+        .unwrap()
+}
+
+fn bench_ingestion(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let messages = signed_messages();
+    let peer = PeerId::new();
+    let mut group = c.benchmark_group("sync_ingestion");
+    group.throughput(Throughput::Elements(MESSAGES as u64));
+    group.sample_size(10);
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            runtime.block_on(async {
+                let node = receiver();
+                for message in messages.clone() {
+                    node.submit_message(message).await.unwrap();
+                }
+            })
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            runtime.block_on(async {
+                let node = receiver();
+                let summary = node.ingest_messages(&peer, messages.clone()).await.unwrap();
+                assert_eq!(summary.applied, MESSAGES);
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_ingestion);
+criterion_main!(benches);
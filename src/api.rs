@@ -0,0 +1,302 @@
+//! HTTP API of a running node
+//!
+//! [`ApiServer`] serves a small JSON API over HTTP/1.1 so dashboards, scripts
+//! and nodes written in other languages can inspect a node and hand it
+//! messages without linking the crate:
+//!
+//! | Method | Path                  | Response                                      |
+//! |--------|-----------------------|-----------------------------------------------|
+//! | GET    | `/status`             | [`ChaincraftNode::get_state`]                 |
+//! | GET    | `/peers`              | The node's [`PeerInfo`](crate::network::PeerInfo) list |
+//! | GET    | `/objects/{id}/state` | Latest published state of an object           |
+//! | GET    | `/receipts/{hash}`    | [`Receipt`](crate::receipt::Receipt) of a message |
+//! | POST   | `/messages`           | Submits the [`SharedMessage`] in the body     |
+//!
+//! Object ids may be abbreviated as in [`ChaincraftNode::resolve_object_id`].
+//! Errors come back as `{"error": "..."}` with a 4xx status. Each connection
+//! carries one request; the server answers and closes it.
+//!
+//! The routes are an [`axum`] [`Router`], see [`router`], served over
+//! HTTP/1.1 by `hyper`. The server is meant for trusted networks; put a
+//! reverse proxy in front of it for TLS or public exposure. Its limits:
+//!
+//! - The request head is at most 16 KiB with 32 headers, else `431`
+//! - Bodies are at most [`ApiConfig::max_body_bytes`], else `413`
+//! - The head must arrive within [`ApiConfig::request_timeout`], else the
+//!   connection is closed, and the body within the same time again, else
+//!   `408`
+//! - At most [`ApiConfig::max_connections`] connections are served at once;
+//!   further ones wait to be accepted
+//! - Malformed requests get `400`
+//!
+//! ```ignore
+//! node.start().await?;
+//! let api = ApiServer::bind(node.handle(), "127.0.0.1:8090".parse()?).await?;
+//! ```
+//!
+//! [`ChaincraftNode::get_state`]: crate::ChaincraftNode::get_state
+//! [`ChaincraftNode::resolve_object_id`]: crate::ChaincraftNode::resolve_object_id
+
+use crate::{
+    error::{ChaincraftError, NetworkError, Result},
+    handle::NodeHandle,
+    shared::SharedMessage,
+};
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use hyper_util::{
+    rt::{TokioIo, TokioTimer},
+    service::TowerToHyperService,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+
+/// Largest request head the server reads
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Headers parsed per request
+const MAX_HEADERS: usize = 32;
+
+/// Longest pause after failing to accept a connection, e.g. when out of file
+/// descriptors
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Limits of the HTTP API server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiConfig {
+    /// Largest request body accepted, larger ones get 413
+    pub max_body_bytes: usize,
+    /// Time a client has to send the request head, and then its body
+    pub request_timeout: Duration,
+    /// Connections served at once
+    pub max_connections: usize,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 1024 * 1024,
+            request_timeout: Duration::from_secs(10),
+            max_connections: 256,
+        }
+    }
+}
+
+/// Response of an API route: a status code and a JSON body
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, error: impl std::fmt::Display) -> Self {
+        Self {
+            status,
+            body: json!({ "error": error.to_string() }),
+        }
+    }
+}
+
+impl IntoResponse for ApiResponse {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, [(header::CONTENT_TYPE, "application/json")], self.body.to_string())
+            .into_response()
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    node: NodeHandle,
+    config: ApiConfig,
+}
+
+/// Routes of the API, to serve `node` with any `axum`-compatible server
+pub fn router(node: NodeHandle, config: ApiConfig) -> Router {
+    Router::new()
+        .route("/status", get(status).fallback(method_not_allowed))
+        .route("/peers", get(peers).fallback(method_not_allowed))
+        .route("/objects/:id/state", get(object_state).fallback(method_not_allowed))
+        .route("/receipts/:hash", get(receipt).fallback(method_not_allowed))
+        .route("/messages", post(submit_message).fallback(method_not_allowed))
+        .fallback(not_found)
+        .with_state(ApiState { node, config })
+}
+
+async fn status(State(state): State<ApiState>) -> ApiResponse {
+    match state.node.get_state().await {
+        Ok(status) => ApiResponse::ok(status),
+        Err(e) => ApiResponse::error(500, e),
+    }
+}
+
+async fn peers(State(state): State<ApiState>) -> ApiResponse {
+    ApiResponse::ok(json!(state.node.get_peers().await))
+}
+
+async fn object_state(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResponse {
+    let id = match state.node.resolve_object_id(&id).await {
+        Ok(id) => id,
+        Err(e) => return ApiResponse::error(404, e),
+    };
+    match state.node.object_directory().await.state(&id).await {
+        Ok(object) => ApiResponse::ok(json!({ "id": id.to_string(), "state": object })),
+        Err(e) => ApiResponse::error(404, e),
+    }
+}
+
+async fn receipt(State(state): State<ApiState>, Path(hash): Path<String>) -> ApiResponse {
+    match state.node.get_receipt(&hash).await {
+        Ok(Some(receipt)) => ApiResponse::ok(json!(receipt)),
+        Ok(None) => ApiResponse::error(404, format!("no receipt for {}", hash)),
+        Err(e) => ApiResponse::error(500, e),
+    }
+}
+
+async fn submit_message(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResponse {
+    let limit = state.config.max_body_bytes;
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limit as u64) {
+        return ApiResponse::error(413, format!("body exceeds {} bytes", limit));
+    }
+    let body =
+        match tokio::time::timeout(state.config.request_timeout, axum::body::to_bytes(body, limit))
+            .await
+        {
+            Err(_) => return ApiResponse::error(408, "request body not received in time"),
+            Ok(Err(e)) => {
+                let too_large = e.into_inner().is::<http_body_util::LengthLimitError>();
+                return match too_large {
+                    true => ApiResponse::error(413, format!("body exceeds {} bytes", limit)),
+                    false => ApiResponse::error(400, "request body could not be read"),
+                };
+            },
+            Ok(Ok(body)) => body,
+        };
+    let message: SharedMessage = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => return ApiResponse::error(400, format!("invalid message: {}", e)),
+    };
+    match state.node.submit_message(message).await {
+        Ok(hash) => ApiResponse {
+            status: 202,
+            body: json!({ "accepted": true, "hash": hash }),
+        },
+        Err(e) => ApiResponse {
+            status: 422,
+            body: json!({ "accepted": false, "reason": e.to_string() }),
+        },
+    }
+}
+
+async fn method_not_allowed(method: Method, uri: Uri) -> ApiResponse {
+    ApiResponse::error(405, format!("{} is not allowed on {}", method, uri.path()))
+}
+
+async fn not_found(uri: Uri) -> ApiResponse {
+    ApiResponse::error(404, format!("no route for {}", uri.path()))
+}
+
+/// HTTP server exposing a node's API
+///
+/// The server runs until it is dropped.
+#[derive(Debug)]
+pub struct ApiServer {
+    local_addr: SocketAddr,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl ApiServer {
+    /// Serve `node` on `addr` (use port 0 for any free port)
+    pub async fn bind(node: NodeHandle, addr: SocketAddr) -> Result<Self> {
+        Self::bind_with_config(node, addr, ApiConfig::default()).await
+    }
+
+    /// Serve `node` on `addr` within the given limits
+    pub async fn bind_with_config(
+        node: NodeHandle,
+        addr: SocketAddr,
+        config: ApiConfig,
+    ) -> Result<Self> {
+        if config.max_connections == 0 {
+            return Err(ChaincraftError::config("the API needs max_connections of at least 1"));
+        }
+        let listener = TcpListener::bind(addr).await.map_err(|source| {
+            ChaincraftError::Network(NetworkError::BindFailed { addr, source })
+        })?;
+        let local_addr = listener.local_addr()?;
+        let mut http = hyper::server::conn::http1::Builder::new();
+        http.timer(TokioTimer::new())
+            .header_read_timeout(config.request_timeout)
+            .keep_alive(false)
+            .max_buf_size(MAX_HEAD_BYTES)
+            .max_headers(MAX_HEADERS);
+        let connections = Arc::new(Semaphore::new(config.max_connections));
+        let service = TowerToHyperService::new(router(node, config));
+
+        let server = tokio::spawn(async move {
+            let mut backoff = Duration::ZERO;
+            loop {
+                // Connections past the limit wait in the listen backlog
+                let Ok(permit) = connections.clone().acquire_owned().await else {
+                    break;
+                };
+                let (stream, peer) = match listener.accept().await {
+                    Ok(accepted) => {
+                        backoff = Duration::ZERO;
+                        accepted
+                    },
+                    Err(e) => {
+                        backoff = (backoff * 2)
+                            .max(Duration::from_millis(10))
+                            .min(MAX_ACCEPT_BACKOFF);
+                        tracing::warn!("API server failed to accept a connection: {}", e);
+                        tokio::time::sleep(backoff).await;
+                        continue;
+                    },
+                };
+                let connection = http.serve_connection(TokioIo::new(stream), service.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        tracing::debug!("API connection from {} failed: {}", peer, e);
+                    }
+                    drop(permit);
+                });
+            }
+        });
+        Ok(Self { local_addr, server })
+    }
+
+    /// Address the server listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
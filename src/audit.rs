@@ -0,0 +1,297 @@
+//! Tamper-evident audit log of node events
+//!
+//! An [`AuditLog`] records what happened to a node — messages accepted and
+//! rejected, peers coming and going, settings changed — as a chain of
+//! [`AuditEntry`]s. Every entry carries the hash of the one before it and a
+//! hash over its own contents, so editing, removing or reordering an entry
+//! anywhere in an exported log breaks the chain from that point on, which
+//! [`verify_chain`] reports.
+//!
+//! The log keeps at most [`AuditConfig::max_entries`] entries in memory.
+//! Older entries are dropped from the front; the first retained entry still
+//! names the hash of the one dropped before it, so what remains verifies on
+//! its own and can be matched against an earlier export.
+
+use crate::{
+    crypto::hash::sha256_hex,
+    error::{ChaincraftError, Result},
+    network::PeerId,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, RwLock};
+
+/// `previous_hash` of the first entry ever appended
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// How many audit entries a node keeps in memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditConfig {
+    pub max_entries: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Something worth auditing that happened to a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditEvent {
+    NodeStarted,
+    NodeStopped,
+    /// A message was stored, from a peer or, without `from`, locally
+    MessageAccepted {
+        hash: String,
+        message_type: String,
+        from: Option<PeerId>,
+    },
+    /// A message was refused, and why
+    MessageRejected {
+        hash: String,
+        message_type: String,
+        from: Option<PeerId>,
+        reason: String,
+    },
+    PeerConnected {
+        peer_id: PeerId,
+        address: String,
+    },
+    PeerDisconnected {
+        peer_id: PeerId,
+        reason: String,
+    },
+    /// A setting of the running node changed
+    ConfigChanged {
+        setting: String,
+        value: String,
+    },
+}
+
+/// One link of the audit chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0 and never reused
+    pub sequence: u64,
+    pub at: DateTime<Utc>,
+    pub event: AuditEvent,
+    /// Hash of the entry before, [`AUDIT_GENESIS_HASH`] for the first one
+    pub previous_hash: String,
+    /// Hash over all the other fields
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn new(
+        sequence: u64,
+        at: DateTime<Utc>,
+        event: AuditEvent,
+        previous_hash: String,
+    ) -> Result<Self> {
+        let mut entry = Self {
+            sequence,
+            at,
+            event,
+            previous_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.calculate_hash()?;
+        Ok(entry)
+    }
+
+    /// Hash of the entry's contents, excluding `hash` itself
+    pub fn calculate_hash(&self) -> Result<String> {
+        let content = serde_json::json!({
+            "sequence": self.sequence,
+            "at": self.at,
+            "event": self.event,
+            "previous_hash": self.previous_hash,
+        });
+        Ok(sha256_hex(serde_json::to_string(&content)?.as_bytes()))
+    }
+}
+
+/// Result of checking an audit chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditVerification {
+    pub entries: usize,
+    /// Hash of the last entry, which commits to the whole chain
+    pub head: Option<String>,
+}
+
+/// Check that every entry's hash matches its contents and links to the
+/// entry before it
+///
+/// Only entry 0 must link to [`AUDIT_GENESIS_HASH`]; a later first entry
+/// links to one dropped from memory, which an earlier export still holds.
+pub fn verify_chain(entries: &[AuditEntry]) -> Result<AuditVerification> {
+    let mut previous: Option<&AuditEntry> = None;
+    for entry in entries {
+        if entry.hash != entry.calculate_hash()? {
+            return Err(ChaincraftError::validation(format!(
+                "audit entry {} does not match its hash",
+                entry.sequence
+            )));
+        }
+        match previous {
+            Some(previous)
+                if entry.previous_hash != previous.hash
+                    || entry.sequence != previous.sequence + 1 =>
+            {
+                return Err(ChaincraftError::validation(format!(
+                    "audit entry {} does not follow entry {}",
+                    entry.sequence, previous.sequence
+                )));
+            },
+            Some(_) => {},
+            None if entry.sequence == 0 && entry.previous_hash != AUDIT_GENESIS_HASH => {
+                return Err(ChaincraftError::validation("audit entry 0 does not start the chain"));
+            },
+            None => {},
+        }
+        previous = Some(entry);
+    }
+    Ok(AuditVerification {
+        entries: entries.len(),
+        head: previous.map(|entry| entry.hash.clone()),
+    })
+}
+
+/// Parse a JSON-lines audit export, see [`AuditLog::to_json_lines`]
+pub fn parse_json_lines(text: &str) -> Result<Vec<AuditEntry>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| ChaincraftError::validation(format!("line {}: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+/// Read and check an export written by
+/// [`ChaincraftNode::export_audit_log`](crate::ChaincraftNode::export_audit_log)
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn verify_export(path: impl AsRef<std::path::Path>) -> Result<AuditVerification> {
+    let text = tokio::fs::read_to_string(path).await?;
+    verify_chain(&parse_json_lines(&text)?)
+}
+
+#[derive(Debug)]
+struct Chain {
+    entries: VecDeque<AuditEntry>,
+    next_sequence: u64,
+    head: String,
+}
+
+/// Append-only, hash-chained log of node events
+#[derive(Debug)]
+pub struct AuditLog {
+    config: RwLock<AuditConfig>,
+    chain: Mutex<Chain>,
+}
+
+impl AuditLog {
+    pub fn new(config: AuditConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            chain: Mutex::new(Chain {
+                entries: VecDeque::new(),
+                next_sequence: 0,
+                head: AUDIT_GENESIS_HASH.to_string(),
+            }),
+        }
+    }
+
+    pub fn config(&self) -> AuditConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the settings, dropping entries beyond a lower `max_entries` now
+    pub fn set_config(&self, config: AuditConfig) {
+        let max_entries = config.max_entries.max(1);
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        let mut chain = self.lock();
+        while chain.entries.len() > max_entries {
+            chain.entries.pop_front();
+        }
+    }
+
+    /// Append an event that happened at `at`, returning its entry
+    pub fn append(&self, event: AuditEvent, at: DateTime<Utc>) -> Result<AuditEntry> {
+        let max_entries = self.config().max_entries.max(1);
+        let mut chain = self.lock();
+        let entry = AuditEntry::new(chain.next_sequence, at, event, chain.head.clone())?;
+        chain.next_sequence += 1;
+        chain.head = entry.hash.clone();
+        chain.entries.push_back(entry.clone());
+        while chain.entries.len() > max_entries {
+            chain.entries.pop_front();
+        }
+        Ok(entry)
+    }
+
+    /// Retained entries, oldest first
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.lock().entries.iter().cloned().collect()
+    }
+
+    /// Retained entries from `sequence` on
+    pub fn entries_since(&self, sequence: u64) -> Vec<AuditEntry> {
+        self.lock()
+            .entries
+            .iter()
+            .filter(|entry| entry.sequence >= sequence)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of entries ever appended
+    pub fn len(&self) -> u64 {
+        self.lock().next_sequence
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hash of the latest entry, [`AUDIT_GENESIS_HASH`] while empty
+    pub fn head(&self) -> String {
+        self.lock().head.clone()
+    }
+
+    /// Retained entries, one JSON object per line
+    pub fn to_json_lines(&self) -> Result<String> {
+        let mut text = String::new();
+        for entry in self.lock().entries.iter() {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    /// Check the retained entries, see [`verify_chain`]
+    pub fn verify(&self) -> Result<AuditVerification> {
+        verify_chain(&self.entries())
+    }
+
+    // Never held across an await; a poisoned lock still holds a valid chain
+    fn lock(&self) -> std::sync::MutexGuard<'_, Chain> {
+        self.chain.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new(AuditConfig::default())
+    }
+}
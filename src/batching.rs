@@ -0,0 +1,342 @@
+//! Micro-batched storage writes
+//!
+//! Storing every message as its own `put` means one lock round trip, and for
+//! disk-backed stores one I/O, per message. [`BatchingStorage`] wraps another
+//! backend and buffers writes in memory, handing them over in one
+//! [`Storage::write_batch`] call once `max_batch` writes are pending or
+//! `max_delay` has passed, whichever comes first. Reads see buffered writes
+//! immediately.
+//!
+//! Buffered writes would be lost if the process died before a flush, so each
+//! one can first be appended to a write-ahead log: a JSON-lines file with one
+//! record per write. Each record is synced to disk before the write returns,
+//! so acknowledged writes survive power loss as well as a crash of the
+//! process. Opening the storage again replays the log into the backend, and
+//! every successful flush empties it once the backend has flushed the batch
+//! to disk. A torn last line, left by a crash in the middle of an append, is
+//! skipped.
+
+use crate::error::Result;
+use crate::storage::Storage;
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+
+/// When buffered writes are handed to the backend
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// Flush once this many distinct keys are pending
+    pub max_batch: usize,
+    /// Flush writes that have been pending this long
+    pub max_delay: Duration,
+    /// Write-ahead log protecting pending writes, if any
+    pub wal_path: Option<PathBuf>,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch: 64,
+            max_delay: Duration::from_millis(5),
+            wal_path: None,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Log pending writes to `path` so they survive a crash
+    pub fn with_wal(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wal_path = Some(path.into());
+        self
+    }
+}
+
+/// Counters describing how writes were batched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Writes accepted, including overwrites of pending keys
+    pub writes: u64,
+    /// Batches handed to the backend
+    pub batches: u64,
+    /// Entries in those batches
+    pub flushed: u64,
+    /// Writes recovered from the log when the storage was opened
+    pub recovered: u64,
+}
+
+/// One logged write; `value` is hex, `None` for a delete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WalRecord {
+    key: String,
+    value: Option<String>,
+}
+
+/// Append-only log of writes not yet handed to the backend
+#[derive(Debug)]
+struct WriteAheadLog {
+    file: tokio::fs::File,
+}
+
+impl WriteAheadLog {
+    /// Read the writes left in a log, stopping at a torn record
+    async fn recover(path: &Path) -> Result<Vec<(String, Option<Vec<u8>>)>> {
+        let file = match tokio::fs::File::open(path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut lines = BufReader::new(file).lines();
+        let mut writes = Vec::new();
+        while let Some(line) = lines.next_line().await? {
+            let record = serde_json::from_str::<WalRecord>(&line)
+                .ok()
+                .and_then(|record| match record.value {
+                    Some(value) => hex::decode(value).ok().map(|v| (record.key, Some(v))),
+                    None => Some((record.key, None)),
+                });
+            match record {
+                Some(write) => writes.push(write),
+                None => {
+                    tracing::warn!("Ignoring torn write-ahead log record in {}", path.display());
+                    break;
+                },
+            }
+        }
+        Ok(writes)
+    }
+
+    /// Start an empty log at `path`
+    async fn create(path: &Path) -> Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.set_len(0).await?;
+        Ok(Self { file })
+    }
+
+    async fn append(&mut self, key: &str, value: Option<&[u8]>) -> Result<()> {
+        let record = WalRecord {
+            key: key.to_string(),
+            value: value.map(hex::encode),
+        };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.flush().await?;
+        self.file.sync_data().await?;
+        Ok(())
+    }
+
+    async fn truncate(&mut self) -> Result<()> {
+        self.file.set_len(0).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    writes: IndexMap<String, Option<Vec<u8>>>,
+    wal: Option<WriteAheadLog>,
+    stats: BatchStats,
+}
+
+/// Storage wrapper batching writes to another backend
+pub struct BatchingStorage {
+    inner: Arc<dyn Storage>,
+    config: BatchConfig,
+    pending: Mutex<Pending>,
+}
+
+impl std::fmt::Debug for BatchingStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchingStorage")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BatchingStorage {
+    /// Wrap `inner`, replaying any write-ahead log left by a previous run
+    ///
+    /// Spawns a task flushing pending writes every `max_delay`; it stops when
+    /// the storage is dropped.
+    pub async fn open(inner: Arc<dyn Storage>, config: BatchConfig) -> Result<Arc<Self>> {
+        let mut pending = Pending::default();
+        if let Some(path) = &config.wal_path {
+            let recovered = WriteAheadLog::recover(path).await?;
+            pending.stats.recovered = recovered.len() as u64;
+            if !recovered.is_empty() {
+                tracing::info!(
+                    "Replaying {} logged writes from {}",
+                    recovered.len(),
+                    path.display()
+                );
+                inner.write_batch(recovered).await?;
+            }
+            pending.wal = Some(WriteAheadLog::create(path).await?);
+        }
+
+        let storage = Arc::new(Self {
+            inner,
+            config,
+            pending: Mutex::new(pending),
+        });
+        tokio::spawn(Self::flush_periodically(Arc::downgrade(&storage)));
+        Ok(storage)
+    }
+
+    pub fn config(&self) -> &BatchConfig {
+        &self.config
+    }
+
+    /// Number of keys waiting to be flushed
+    pub async fn pending(&self) -> usize {
+        self.pending.lock().await.writes.len()
+    }
+
+    pub async fn stats(&self) -> BatchStats {
+        self.pending.lock().await.stats
+    }
+
+    async fn flush_periodically(storage: Weak<Self>) {
+        let Some(delay) = storage.upgrade().map(|s| s.config.max_delay) else {
+            return;
+        };
+        // The first tick is one period out; an immediate one would flush
+        // writes made right after opening without waiting for the delay
+        let period = delay.max(Duration::from_millis(1));
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(storage) = storage.upgrade() else {
+                return;
+            };
+            if let Err(e) = storage.flush().await {
+                tracing::warn!("Failed to flush batched writes: {}", e);
+            }
+        }
+    }
+
+    async fn write(&self, key: &str, value: Option<Vec<u8>>) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        if let Some(wal) = pending.wal.as_mut() {
+            wal.append(key, value.as_deref()).await?;
+        }
+        pending.writes.insert(key.to_string(), value);
+        pending.stats.writes += 1;
+        if pending.writes.len() >= self.config.max_batch {
+            self.flush_locked(&mut pending).await?;
+        }
+        Ok(())
+    }
+
+    /// Hand pending writes to the backend; the lock is held throughout so
+    /// reads never miss a write on its way from the buffer to the backend
+    async fn flush_locked(&self, pending: &mut Pending) -> Result<()> {
+        if pending.writes.is_empty() {
+            return Ok(());
+        }
+        let batch: Vec<(String, Option<Vec<u8>>)> = pending.writes.drain(..).collect();
+        let size = batch.len() as u64;
+        if let Err(e) = self.inner.write_batch(batch.clone()).await {
+            pending.writes.extend(batch);
+            return Err(e);
+        }
+        pending.stats.batches += 1;
+        pending.stats.flushed += size;
+        if let Some(wal) = pending.wal.as_mut() {
+            // The log may only go once the batch is on disk in the backend
+            self.inner.flush().await?;
+            wal.truncate().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for BatchingStorage {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let pending = self.pending.lock().await;
+        match pending.writes.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => self.inner.get(key).await,
+        }
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        self.write(key, Some(value)).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.write(key, None).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        let pending = self.pending.lock().await;
+        match pending.writes.get(key) {
+            Some(value) => Ok(value.is_some()),
+            None => self.inner.exists(key).await,
+        }
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        pending.writes.clear();
+        if let Some(wal) = pending.wal.as_mut() {
+            wal.truncate().await?;
+        }
+        self.inner.clear().await
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        self.inner.initialize().await
+    }
+
+    async fn write_batch(&self, batch: Vec<(String, Option<Vec<u8>>)>) -> Result<()> {
+        for (key, value) in batch {
+            self.write(&key, value).await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        self.flush_locked(&mut pending).await?;
+        self.inner.flush().await
+    }
+
+    async fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let pending = self.pending.lock().await;
+        let mut keys: Vec<String> = self
+            .inner
+            .keys_with_prefix(prefix)
+            .await?
+            .into_iter()
+            .filter(|key| !pending.writes.contains_key(key))
+            .collect();
+        for (key, value) in pending.writes.iter() {
+            if value.is_some() && key.starts_with(prefix) {
+                keys.push(key.clone());
+            }
+        }
+        Ok(keys)
+    }
+}
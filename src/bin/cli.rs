@@ -1,7 +1,11 @@
 //! ChainCraft CLI application
 
+use chaincraft_rust::diagnostics::DiagnosticsConfig;
+use chaincraft_rust::migrate::StorageLocation;
+use chaincraft_rust::network::access::{PeerAccess, PeerPattern};
 use chaincraft_rust::{ChaincraftNode, Result};
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use tracing::{info, Level};
 
 
@@ -29,9 +33,43 @@ struct Cli {
     #[arg(short = 'm', long)]
     memory: bool,
 
+    /// Keep the node's storage here: a JSON dump, saved on shutdown, or sled:<dir>
+    #[arg(long, value_name = "LOCATION")]
+    storage: Option<StorageLocation>,
+
+    /// Keep the node identity in this encrypted key file, created if missing;
+    /// the passphrase comes from CHAINCRAFT_KEY_PASSPHRASE or a prompt
+    #[arg(long, value_name = "PATH")]
+    key_file: Option<PathBuf>,
+
+    /// Write a diagnostic bundle here when the node stops or panics
+    #[arg(long, value_name = "DIR")]
+    diagnostics_dir: Option<PathBuf>,
+
     /// Set verbosity level (0-4)
     #[arg(short = 'v', long, default_value_t = 2)]
     verbosity: u8,
+
+    /// Peer to admit, as an address range (10.0.0.0/24) or hex public key
+    #[arg(long = "allow-peer", value_name = "PEER", global = true)]
+    allow_peers: Vec<PeerPattern>,
+
+    /// Peer to refuse, as an address range or hex public key
+    #[arg(long = "deny-peer", value_name = "PEER", global = true)]
+    deny_peers: Vec<PeerPattern>,
+
+    /// Only admit peers given with --allow-peer
+    #[arg(long, global = true)]
+    private: bool,
+
+    /// Follow the network without creating messages or validating
+    #[arg(long, global = true)]
+    read_replica: bool,
+
+    /// Also serve the gRPC services on this port, e.g. for chaincraft-tui
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc_port: Option<u16>,
 }
 
 #[derive(Subcommand)]
@@ -42,6 +80,105 @@ enum Commands {
     Keygen,
     /// Show version information
     Version,
+    /// Report gossip convergence from first-seen files exported by nodes
+    Convergence {
+        /// One file per node, written by `ChaincraftNode::export_first_seen`
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Copy a node's storage to another backend and check the copy
+    Migrate {
+        /// Storage to copy: a JSON dump, or sled:<dir>
+        #[arg(long)]
+        from: StorageLocation,
+        /// Where to copy it: a JSON dump, or sled:<dir>
+        #[arg(long)]
+        to: StorageLocation,
+        /// Entries copied at a time
+        #[arg(long, default_value_t = 500)]
+        batch_size: usize,
+        /// Entries compared by hash after copying
+        #[arg(long, default_value_t = 64)]
+        samples: usize,
+        /// Replace whatever the target already holds
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Print a diagnostic bundle written by a stopped or crashed node
+    Inspect {
+        /// Bundle directory, under the node's --diagnostics-dir
+        bundle: PathBuf,
+    },
+    /// Print JSON Schemas of the example applications' messages
+    Schemas {
+        /// Only this example, e.g. `chatroom`
+        example: Option<String>,
+    },
+    /// Describe the wire protocol spoken by this build
+    Protocol {
+        #[command(subcommand)]
+        command: ProtocolCommand,
+    },
+    /// Diagnose connectivity, versions and clock skew against a peer
+    #[cfg(feature = "grpc")]
+    Doctor {
+        /// Peer to check, as host:port of its gRPC transport
+        #[arg(long)]
+        peer: String,
+        /// Network the peer should be on
+        #[arg(long, default_value = chaincraft_rust::network::DEFAULT_NETWORK_ID)]
+        network_id: String,
+        /// Handshakes used to measure round-trip time and clock offset
+        #[arg(long, default_value_t = 5)]
+        samples: usize,
+        /// Seconds to wait for each step
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+    },
+    /// Send synthetic signed messages to a node and report how it keeps up
+    #[cfg(feature = "grpc")]
+    Bench {
+        /// Node to load, as host:port of its gRPC transport
+        #[arg(long)]
+        target: String,
+        /// Messages per second
+        #[arg(long, default_value_t = 100)]
+        rate: u32,
+        /// Approximate bytes of JSON data per message
+        #[arg(long, default_value_t = 256)]
+        size: usize,
+        /// Seconds to send for
+        #[arg(long, default_value_t = 10)]
+        duration: u64,
+        /// Submit through the client RPC (rpc) or deliver as a peer (peer)
+        #[arg(long, default_value = "rpc")]
+        via: chaincraft_rust::network::bench::BenchPath,
+        /// Network to announce in the handshake
+        #[arg(long, default_value = chaincraft_rust::network::DEFAULT_NETWORK_ID)]
+        network_id: String,
+    },
+    /// Crawl the overlay from a node and print who is connected to whom
+    #[cfg(feature = "grpc")]
+    Topology {
+        /// Node to start from, as host:port of its gRPC transport
+        #[arg(long)]
+        peer: String,
+        /// Output format: json or dot (Graphviz)
+        #[arg(long, default_value = "json")]
+        format: String,
+        /// Stop after finding this many nodes
+        #[arg(long, default_value_t = 256)]
+        max_nodes: usize,
+        /// Seconds to wait for each node
+        #[arg(long, default_value_t = 5)]
+        timeout: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProtocolCommand {
+    /// Print the envelope, message kinds and flows as JSON
+    Dump,
 }
 
 #[tokio::main]
@@ -57,22 +194,77 @@ async fn main() -> Result<()> {
         _ => Level::TRACE,
     };
 
-    tracing_subscriber::fmt().with_max_level(level).init();
+    // A running node can change the level later, see ChaincraftNode::update_config
+    chaincraft_rust::runtime_config::init_logging(level.into())?;
 
     match &cli.command {
         Some(Commands::Start) | None => {
             info!("Starting ChainCraft node on port {}", cli.port);
 
-            let mut node = ChaincraftNode::builder()
+            let access = PeerAccess {
+                allow: cli.allow_peers.clone(),
+                deny: cli.deny_peers.clone(),
+                private: cli.private,
+            };
+            let mut builder = ChaincraftNode::builder()
                 .port(cli.port)
                 .max_peers(cli.max_peers)
                 .with_persistent_storage(!cli.memory)
-                .build()?;
+                .with_peer_access(access)
+                .with_read_replica(cli.read_replica);
+            let storage = match &cli.storage {
+                Some(location) => {
+                    let storage = location.open().await?;
+                    builder = builder.with_storage(storage.clone());
+                    info!("Using storage at {}", location);
+                    Some((location, storage))
+                },
+                None => None,
+            };
+            if let Some(dir) = &cli.diagnostics_dir {
+                builder = builder.with_diagnostics(DiagnosticsConfig::new(dir));
+            }
+            if let Some(path) = &cli.key_file {
+                let passphrase = match std::env::var("CHAINCRAFT_KEY_PASSPHRASE") {
+                    Ok(passphrase) => passphrase,
+                    Err(_) => rpassword::prompt_password("Key file passphrase: ")?,
+                };
+                builder = builder.with_key_file(path, passphrase);
+            }
+            let mut node = builder.build()?;
+            if let Some(diagnostics) = &node.diagnostics {
+                diagnostics.install_panic_hook();
+            }
 
             info!("Node {} started on port {}", node.id(), node.port());
 
             node.start().await?;
 
+            #[cfg(feature = "grpc")]
+            let _grpc = match cli.grpc_port {
+                Some(port) => {
+                    use chaincraft_rust::network::grpc::GrpcTransport;
+                    use chaincraft_rust::network::transport::Transport;
+
+                    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+                    let transport =
+                        std::sync::Arc::new(GrpcTransport::bind_node(&node, addr).await?);
+                    info!("Serving gRPC on {}", transport.local_addr());
+                    // Hand messages peers deliver to the node
+                    let inbox = transport.clone();
+                    let handle = node.handle();
+                    tokio::spawn(async move {
+                        while let Ok((from, message)) = inbox.recv().await {
+                            if let Err(e) = handle.receive_message(&from, message).await {
+                                tracing::debug!("Refused message from {}: {}", from, e);
+                            }
+                        }
+                    });
+                    Some(transport)
+                },
+                None => None,
+            };
+
             // Keep the node running
             tokio::signal::ctrl_c()
                 .await
@@ -80,6 +272,45 @@ async fn main() -> Result<()> {
 
             info!("Shutting down node...");
             node.stop().await?;
+            if let Some((location, storage)) = storage {
+                location.save(storage.as_ref()).await?;
+                info!("Saved storage to {}", location);
+            }
+        },
+        Some(Commands::Inspect { bundle }) => {
+            use chaincraft_rust::diagnostics::DiagnosticBundle;
+
+            println!("{}", DiagnosticBundle::read(bundle)?);
+        },
+        Some(Commands::Migrate {
+            from,
+            to,
+            batch_size,
+            samples,
+            overwrite,
+        }) => {
+            use chaincraft_rust::migrate::{migrate, MigrationConfig};
+
+            let config = MigrationConfig::default()
+                .with_batch_size(*batch_size)
+                .with_sample_size(*samples)
+                .with_overwrite(*overwrite);
+            let report = migrate(from, to, &config, |progress| {
+                eprintln!(
+                    "{}: {}/{} ({}/{} overall)",
+                    progress.namespace,
+                    progress.copied,
+                    progress.total,
+                    progress.copied_overall,
+                    progress.total_overall
+                );
+            })
+            .await?;
+            println!("{}", report);
+            if !report.is_verified() {
+                eprintln!("Verification of {} failed", to);
+                std::process::exit(1);
+            }
         },
         Some(Commands::Keygen) => {
             use chaincraft_rust::crypto::{utils, KeyType};
@@ -97,6 +328,101 @@ async fn main() -> Result<()> {
         Some(Commands::Version) => {
             println!("ChainCraft Rust v{}", chaincraft_rust::VERSION);
         },
+        Some(Commands::Convergence { files }) => {
+            use chaincraft_rust::convergence::{ConvergenceReport, FirstSeenLog};
+
+            let mut logs = Vec::with_capacity(files.len());
+            for file in files {
+                let log: FirstSeenLog = serde_json::from_slice(&std::fs::read(file)?)?;
+                logs.push(log);
+            }
+            println!("{}", ConvergenceReport::from_logs(&logs));
+        },
+        Some(Commands::Schemas { example }) => {
+            let mut schemas = chaincraft_rust::examples::schemas();
+            let output = match example {
+                Some(name) => match schemas.remove(name.as_str()) {
+                    Some(schemas) => serde_json::to_string_pretty(&schemas)?,
+                    None => {
+                        let known: Vec<_> = schemas.keys().copied().collect();
+                        return Err(chaincraft_rust::ChaincraftError::config(format!(
+                            "unknown example {}, expected one of {}",
+                            name,
+                            known.join(", ")
+                        )));
+                    },
+                },
+                None => serde_json::to_string_pretty(&schemas)?,
+            };
+            println!("{}", output);
+        },
+        Some(Commands::Protocol {
+            command: ProtocolCommand::Dump,
+        }) => {
+            let description = chaincraft_rust::protocol::describe();
+            println!("{}", serde_json::to_string_pretty(&description)?);
+        },
+        #[cfg(feature = "grpc")]
+        Some(Commands::Doctor {
+            peer,
+            network_id,
+            samples,
+            timeout,
+        }) => {
+            use chaincraft_rust::network::doctor::{diagnose, DoctorConfig};
+
+            let config = DoctorConfig::default()
+                .with_network_id(network_id.clone())
+                .with_samples(*samples)
+                .with_timeout(std::time::Duration::from_secs(*timeout));
+            let report = diagnose(peer, &config).await;
+            println!("{}", report);
+            if !report.is_healthy() {
+                std::process::exit(1);
+            }
+        },
+        #[cfg(feature = "grpc")]
+        Some(Commands::Bench {
+            target,
+            rate,
+            size,
+            duration,
+            via,
+            network_id,
+        }) => {
+            use chaincraft_rust::network::bench::{run, BenchConfig};
+
+            let config = BenchConfig::default()
+                .with_rate(*rate)
+                .with_size(*size)
+                .with_duration(std::time::Duration::from_secs(*duration))
+                .with_path(*via)
+                .with_network_id(network_id.clone());
+            println!("{}", run(target, &config).await?);
+        },
+        #[cfg(feature = "grpc")]
+        Some(Commands::Topology {
+            peer,
+            format,
+            max_nodes,
+            timeout,
+        }) => {
+            use chaincraft_rust::network::topology::{crawl, TopologyConfig};
+
+            let config = TopologyConfig::default()
+                .with_max_nodes(*max_nodes)
+                .with_timeout(std::time::Duration::from_secs(*timeout));
+            let snapshot = crawl(peer, &config).await?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&snapshot)?),
+                "dot" => print!("{}", snapshot.to_dot()),
+                other => {
+                    eprintln!("Unknown format '{}', expected json or dot", other);
+                    std::process::exit(2);
+                },
+            }
+            eprintln!("{}", snapshot);
+        },
     }
 
     Ok(())
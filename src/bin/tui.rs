@@ -0,0 +1,487 @@
+//! ChainCraft terminal dashboard
+//!
+//! Connects to a node's gRPC services and shows its peers with their
+//! round-trip times, the state of its application objects (consensus height
+//! and round, beacon rounds, ...), the message flow by type, the latest
+//! messages of its log and a tail of what the dashboard observed.
+
+use chaincraft_rust::{
+    directory::ObjectSnapshot,
+    message_stats::MessageStatsReport,
+    network::{grpc::proto, grpc::GrpcClient, PeerId, DEFAULT_NETWORK_ID},
+    shared::SharedMessage,
+    ChaincraftError, Result,
+};
+use clap::Parser;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, List, ListItem, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(name = "chaincraft-tui")]
+#[command(about = "Live dashboard of a ChainCraft node's peers, objects and messages")]
+#[command(version)]
+struct Cli {
+    /// Node to watch, as host:port of its gRPC transport
+    #[arg(long, default_value = "127.0.0.1:21000")]
+    node: String,
+
+    /// Milliseconds between refreshes
+    #[arg(long, default_value_t = 1000)]
+    interval: u64,
+
+    /// Busiest senders to list
+    #[arg(long, default_value_t = 5)]
+    top: u32,
+
+    /// Seconds to wait for the node and each peer
+    #[arg(long, default_value_t = 2)]
+    timeout: u64,
+}
+
+/// Messages kept for the recent messages panel
+const RECENT_MESSAGES: usize = 100;
+
+/// Lines kept for the log panel
+const LOG_LINES: usize = 200;
+
+/// Log pages fetched per refresh while catching up with a long log
+const PAGES_PER_REFRESH: usize = 16;
+
+const PAGE_SIZE: u32 = 256;
+
+struct PeerRow {
+    id: PeerId,
+    address: SocketAddr,
+    rtt: Option<Duration>,
+}
+
+/// Everything the dashboard knows about the watched node
+struct Dashboard {
+    address: SocketAddr,
+    local_id: PeerId,
+    timeout: Duration,
+    top: u32,
+    remote: Option<proto::Hello>,
+    rtt: Option<Duration>,
+    peers: Vec<PeerRow>,
+    objects: Vec<ObjectSnapshot>,
+    stats: Option<MessageStatsReport>,
+    /// Length of the node's message log as far as it was read
+    height: u64,
+    recent: VecDeque<SharedMessage>,
+    log: VecDeque<String>,
+}
+
+impl Dashboard {
+    fn new(address: SocketAddr, timeout: Duration, top: u32) -> Self {
+        Self {
+            address,
+            local_id: PeerId::new(),
+            timeout,
+            top,
+            remote: None,
+            rtt: None,
+            peers: Vec::new(),
+            objects: Vec::new(),
+            stats: None,
+            height: 0,
+            recent: VecDeque::new(),
+            log: VecDeque::new(),
+        }
+    }
+
+    fn note(&mut self, line: impl Into<String>) {
+        let line = format!("{} {}", chrono::Local::now().format("%H:%M:%S"), line.into());
+        self.log.push_back(line);
+        while self.log.len() > LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    /// Poll every service of the node once, logging what changed
+    async fn refresh(&mut self) {
+        let client =
+            match tokio::time::timeout(self.timeout, GrpcClient::connect(self.address)).await {
+                Ok(Ok(client)) => client,
+                Ok(Err(e)) => return self.disconnected(e.to_string()),
+                Err(_) => return self.disconnected("connection timed out".to_string()),
+            };
+        if let Err(e) = self.poll(client).await {
+            self.disconnected(e.to_string());
+        }
+    }
+
+    fn disconnected(&mut self, reason: String) {
+        if self.remote.take().is_some() || self.log.is_empty() {
+            self.note(format!("cannot reach {}: {}", self.address, reason));
+        }
+        self.rtt = None;
+    }
+
+    async fn poll(&mut self, mut client: GrpcClient) -> Result<()> {
+        let (remote, rtt) = handshake(&mut client, &self.local_id, self.timeout).await?;
+        if self.remote.as_ref().map(|hello| &hello.node_id) != Some(&remote.node_id) {
+            self.note(format!(
+                "connected to node {} (chaincraft {}, network {})",
+                remote.node_id, remote.software_version, remote.network_id
+            ));
+        }
+        self.remote = Some(remote);
+        self.rtt = Some(rtt);
+
+        self.poll_peers(&mut client).await?;
+        self.poll_objects(&mut client).await?;
+        self.poll_log(&mut client).await?;
+        self.stats = Some(client.message_stats(self.top).await?);
+        Ok(())
+    }
+
+    async fn poll_peers(&mut self, client: &mut GrpcClient) -> Result<()> {
+        let peers = client.get_peers(&self.local_id, u32::MAX).await?;
+        let probes = peers
+            .iter()
+            .map(|peer| probe(peer.address, &self.local_id, self.timeout));
+        let rtts = futures::future::join_all(probes).await;
+
+        let known: HashSet<&PeerId> = self.peers.iter().map(|row| &row.id).collect();
+        let joined: Vec<String> = peers
+            .iter()
+            .filter(|peer| !known.contains(&peer.id))
+            .map(|peer| format!("peer {} at {} joined", short(&peer.id.to_string()), peer.address))
+            .collect();
+        let left: Vec<String> = self
+            .peers
+            .iter()
+            .filter(|row| !peers.iter().any(|peer| peer.id == row.id))
+            .map(|row| format!("peer {} left", short(&row.id.to_string())))
+            .collect();
+        for line in joined.into_iter().chain(left) {
+            self.note(line);
+        }
+
+        let mut rows: Vec<PeerRow> = peers
+            .into_iter()
+            .zip(rtts)
+            .map(|(peer, rtt)| PeerRow {
+                id: peer.id,
+                address: peer.address,
+                rtt,
+            })
+            .collect();
+        rows.sort_by_key(|row| row.id.to_string());
+        self.peers = rows;
+        Ok(())
+    }
+
+    async fn poll_objects(&mut self, client: &mut GrpcClient) -> Result<()> {
+        let objects = client.objects("").await?;
+        let mut changes = Vec::new();
+        for object in &objects {
+            let before = self.objects.iter().find(|old| old.id == object.id);
+            for field in ["height", "round", "current_round"] {
+                let now = &object.state[field];
+                if !now.is_null() && before.is_some_and(|old| &old.state[field] != now) {
+                    changes.push(format!("{} {} is now {}", object.type_name, field, now));
+                }
+            }
+        }
+        for line in changes {
+            self.note(line);
+        }
+        self.objects = objects;
+        Ok(())
+    }
+
+    async fn poll_log(&mut self, client: &mut GrpcClient) -> Result<()> {
+        for _ in 0..PAGES_PER_REFRESH {
+            let (messages, next) = client.get_messages(self.height, PAGE_SIZE).await?;
+            if messages.is_empty() {
+                break;
+            }
+            self.height = next;
+            self.recent.extend(messages);
+            while self.recent.len() > RECENT_MESSAGES {
+                self.recent.pop_front();
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn handshake(
+    client: &mut GrpcClient,
+    local_id: &PeerId,
+    timeout: Duration,
+) -> Result<(proto::Hello, Duration)> {
+    let hello = proto::Hello::new(local_id, DEFAULT_NETWORK_ID, chrono::Utc::now());
+    let started = Instant::now();
+    let remote = tokio::time::timeout(timeout, client.handshake(hello))
+        .await
+        .map_err(|_| ChaincraftError::Generic("handshake timed out".to_string()))??;
+    Ok((remote, started.elapsed()))
+}
+
+/// Round-trip time of a handshake with a peer, if it answers in time
+async fn probe(address: SocketAddr, local_id: &PeerId, timeout: Duration) -> Option<Duration> {
+    let mut client = tokio::time::timeout(timeout, GrpcClient::connect(address))
+        .await
+        .ok()?
+        .ok()?;
+    handshake(&mut client, local_id, timeout)
+        .await
+        .ok()
+        .map(|(_, rtt)| rtt)
+}
+
+fn short(text: &str) -> &str {
+    &text[..text.len().min(8)]
+}
+
+fn millis(rtt: Option<Duration>) -> String {
+    rtt.map_or_else(|| "-".to_string(), |rtt| format!("{:.1} ms", rtt.as_secs_f64() * 1000.0))
+}
+
+/// Top-level scalar fields of an object's state, e.g. `height=4 round=0`
+fn summary(state: &serde_json::Value) -> String {
+    let Some(fields) = state.as_object() else {
+        return state.to_string();
+    };
+    fields
+        .iter()
+        .filter(|(name, value)| {
+            name.as_str() != "type"
+                && (value.is_number() || value.is_string() || value.is_boolean())
+        })
+        .map(|(name, value)| match value.as_str() {
+            Some(text) => format!("{}={}", name, short(text)),
+            None => format!("{}={}", name, value),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn titled(title: &str) -> Block<'static> {
+    Block::bordered().title(Line::styled(
+        format!(" {} ", title),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))
+}
+
+fn render(frame: &mut Frame, dashboard: &Dashboard) {
+    let [header, middle, flow, log] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Percentage(35),
+        Constraint::Percentage(35),
+        Constraint::Min(5),
+    ])
+    .areas(frame.area());
+    let [peers, objects] =
+        Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(middle);
+    let [types, senders, recent] = Layout::horizontal([
+        Constraint::Percentage(30),
+        Constraint::Percentage(20),
+        Constraint::Percentage(50),
+    ])
+    .areas(flow);
+
+    render_header(frame, header, dashboard);
+    render_peers(frame, peers, dashboard);
+    render_objects(frame, objects, dashboard);
+    render_types(frame, types, dashboard);
+    render_senders(frame, senders, dashboard);
+    render_recent(frame, recent, dashboard);
+    render_log(frame, log, dashboard);
+}
+
+fn render_header(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let text = match &dashboard.remote {
+        Some(remote) => {
+            let window = dashboard.stats.as_ref().map_or(String::new(), |stats| {
+                format!("  {} msgs in {}s", stats.total, stats.window_secs)
+            });
+            format!(
+                "node {}  network {}  chaincraft {}  rtt {}  log height {}{}",
+                short(&remote.node_id),
+                remote.network_id,
+                remote.software_version,
+                millis(dashboard.rtt),
+                dashboard.height,
+                window
+            )
+        },
+        None => format!("waiting for {} ...", dashboard.address),
+    };
+    let style = match dashboard.remote {
+        Some(_) => Style::default().fg(Color::Green),
+        None => Style::default().fg(Color::Yellow),
+    };
+    let title = format!("ChainCraft {} (q to quit)", dashboard.address);
+    frame.render_widget(Paragraph::new(text).style(style).block(titled(&title)), area);
+}
+
+fn render_peers(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let rows = dashboard.peers.iter().map(|peer| {
+        let style = match peer.rtt {
+            Some(_) => Style::default(),
+            None => Style::default().fg(Color::DarkGray),
+        };
+        Row::new([
+            short(&peer.id.to_string()).to_string(),
+            peer.address.to_string(),
+            millis(peer.rtt),
+        ])
+        .style(style)
+    });
+    let widths = [Constraint::Length(9), Constraint::Min(15), Constraint::Length(10)];
+    let table = Table::new(rows, widths)
+        .header(Row::new(["id", "address", "rtt"]).style(Style::default().fg(Color::Cyan)))
+        .block(titled(&format!("Peers ({})", dashboard.peers.len())));
+    frame.render_widget(table, area);
+}
+
+fn render_objects(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let rows = dashboard.objects.iter().map(|object| {
+        Row::new([object.id.short(), object.type_name.clone(), summary(&object.state)])
+    });
+    let widths = [Constraint::Length(9), Constraint::Length(18), Constraint::Min(20)];
+    let table = Table::new(rows, widths)
+        .header(Row::new(["id", "object", "state"]).style(Style::default().fg(Color::Cyan)))
+        .block(titled("Objects"));
+    frame.render_widget(table, area);
+}
+
+fn render_types(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let Some(stats) = &dashboard.stats else {
+        frame.render_widget(titled("Message flow"), area);
+        return;
+    };
+    let bars: Vec<Bar> = stats
+        .by_type
+        .iter()
+        .map(|count| {
+            Bar::default()
+                .label(Line::from(count.message_type.clone()))
+                .value(count.count)
+        })
+        .collect();
+    let chart = BarChart::default()
+        .data(BarGroup::default().bars(&bars))
+        .direction(ratatui::layout::Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(Color::Magenta))
+        .block(titled(&format!("Message flow, last {}s", stats.window_secs)));
+    frame.render_widget(chart, area);
+}
+
+fn render_senders(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let rows = dashboard.stats.iter().flat_map(|stats| {
+        let unsigned = (stats.unsigned > 0).then(|| {
+            Row::new(["unsigned".to_string(), stats.unsigned.to_string()])
+                .style(Style::default().fg(Color::DarkGray))
+        });
+        stats
+            .top_senders
+            .iter()
+            .map(|sender| {
+                Row::new([
+                    short(&sender.sender).to_string(),
+                    format!("{} ({:.0}%)", sender.count, sender.share * 100.0),
+                ])
+            })
+            .chain(unsigned)
+    });
+    let widths = [Constraint::Length(9), Constraint::Min(8)];
+    let table = Table::new(rows, widths)
+        .header(Row::new(["sender", "messages"]).style(Style::default().fg(Color::Cyan)))
+        .block(titled("Top senders"));
+    frame.render_widget(table, area);
+}
+
+fn render_recent(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = dashboard
+        .recent
+        .iter()
+        .rev()
+        .take(visible)
+        .map(|message| {
+            let sender = message.sender.as_deref().map_or("unsigned", short);
+            ListItem::new(format!(
+                "{} {:<12} {:<8} {}",
+                message.timestamp.format("%H:%M:%S"),
+                message.message_type.to_string(),
+                sender,
+                message.data
+            ))
+        })
+        .collect();
+    frame.render_widget(List::new(items).block(titled("Recent messages")), area);
+}
+
+fn render_log(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let skip = dashboard.log.len().saturating_sub(visible);
+    let items: Vec<ListItem> = dashboard
+        .log
+        .iter()
+        .skip(skip)
+        .map(|line| ListItem::new(line.as_str()))
+        .collect();
+    frame.render_widget(List::new(items).block(titled("Log")), area);
+}
+
+async fn run(
+    terminal: &mut DefaultTerminal,
+    dashboard: &mut Dashboard,
+    interval: Duration,
+) -> Result<()> {
+    let mut next_refresh = Instant::now();
+    loop {
+        if Instant::now() >= next_refresh {
+            dashboard.refresh().await;
+            next_refresh = Instant::now() + interval;
+        }
+        terminal.draw(|frame| render(frame, dashboard))?;
+
+        let wait = next_refresh.saturating_duration_since(Instant::now());
+        let key = tokio::task::spawn_blocking(move || -> std::io::Result<Option<KeyCode>> {
+            if !event::poll(wait)? {
+                return Ok(None);
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => Ok(Some(key.code)),
+                _ => Ok(None),
+            }
+        })
+        .await
+        .map_err(|e| ChaincraftError::Generic(e.to_string()))??;
+        if matches!(key, Some(KeyCode::Char('q') | KeyCode::Esc)) {
+            return Ok(());
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let address = tokio::net::lookup_host(&cli.node)
+        .await?
+        .find(|address| address.is_ipv4())
+        .ok_or_else(|| ChaincraftError::config(format!("{} has no IPv4 address", cli.node)))?;
+
+    let mut dashboard = Dashboard::new(address, Duration::from_secs(cli.timeout), cli.top);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut dashboard, Duration::from_millis(cli.interval)).await;
+    ratatui::restore();
+    result
+}
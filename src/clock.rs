@@ -0,0 +1,66 @@
+//! Injectable wall clock
+//!
+//! The node reads the current time through a [`Clock`]. Normally that is the
+//! system clock; replays and simulations use a mock clock that only moves when
+//! told to, so time-dependent decisions repeat exactly.
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Source of the current time shared by a node and its components
+#[derive(Clone, Default)]
+pub struct Clock {
+    mock: Option<Arc<Mutex<DateTime<Utc>>>>,
+}
+
+impl Clock {
+    /// Clock following the system time
+    pub fn system() -> Self {
+        Self { mock: None }
+    }
+
+    /// Clock frozen at `start` until it is set or advanced
+    pub fn mock(start: DateTime<Utc>) -> Self {
+        Self {
+            mock: Some(Arc::new(Mutex::new(start))),
+        }
+    }
+
+    /// Whether this is a mock clock
+    pub fn is_mock(&self) -> bool {
+        self.mock.is_some()
+    }
+
+    /// Current time
+    pub fn now(&self) -> DateTime<Utc> {
+        match &self.mock {
+            Some(time) => *time.lock().unwrap_or_else(|e| e.into_inner()),
+            None => Utc::now(),
+        }
+    }
+
+    /// Move a mock clock to `time`; no effect on the system clock
+    pub fn set(&self, time: DateTime<Utc>) {
+        if let Some(mock) = &self.mock {
+            *mock.lock().unwrap_or_else(|e| e.into_inner()) = time;
+        }
+    }
+
+    /// Move a mock clock forward; no effect on the system clock
+    pub fn advance(&self, by: Duration) {
+        if let Some(mock) = &self.mock {
+            let mut time = mock.lock().unwrap_or_else(|e| e.into_inner());
+            *time += chrono::Duration::from_std(by).unwrap_or(chrono::Duration::zero());
+        }
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("mock", &self.is_mock())
+            .finish()
+    }
+}
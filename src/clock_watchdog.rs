@@ -0,0 +1,240 @@
+//! Watching the local clock against peers and NTP
+//!
+//! Chat freshness checks, beacon rounds and block timers assume the clocks of
+//! all nodes roughly agree. A [`ClockWatchdog`] collects the offsets between
+//! the local clock and times reported by peers and, optionally, an NTP
+//! server. Its estimate is a median over recent samples: of the NTP samples
+//! when there are any, since a peer's clock may be the wrong one, otherwise
+//! of each peer's median so a chatty peer counts once. When the estimate
+//! leaves [`WatchdogConfig::max_offset`] the watchdog raises a
+//! [`ClockAlert`], and another once it is back in range.
+//!
+//! Offsets are reference time minus local time: a positive offset means the
+//! local clock is behind. Objects holding a clone of the watchdog can correct
+//! their timestamps with [`ClockWatchdog::corrected`].
+
+use crate::network::PeerId;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// When the watchdog warns, and where it takes samples from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    /// Largest tolerated offset of the local clock
+    pub max_offset: Duration,
+    /// Recent samples kept per source
+    pub samples: usize,
+    /// NTP server queried as `host:port`, e.g. `pool.ntp.org:123`
+    pub ntp_server: Option<String>,
+    /// Time between NTP queries
+    pub ntp_interval: Duration,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            max_offset: Duration::from_secs(1),
+            samples: 32,
+            ntp_server: None,
+            ntp_interval: Duration::from_secs(600),
+        }
+    }
+}
+
+impl WatchdogConfig {
+    pub fn new(max_offset: Duration) -> Self {
+        Self {
+            max_offset,
+            ..Self::default()
+        }
+    }
+
+    /// Also query an NTP server every `interval`
+    pub fn with_ntp(mut self, server: impl Into<String>, interval: Duration) -> Self {
+        self.ntp_server = Some(server.into());
+        self.ntp_interval = interval;
+        self
+    }
+}
+
+/// Where an offset sample came from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OffsetSource {
+    /// Time a peer reported, e.g. in a sync announcement
+    Peer(PeerId),
+    Ntp,
+}
+
+/// Change of the estimated offset relative to the tolerated maximum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockAlert {
+    /// The estimate exceeded the maximum
+    Exceeded { offset: chrono::Duration },
+    /// The estimate is back within the maximum
+    Recovered { offset: chrono::Duration },
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    peers: HashMap<PeerId, VecDeque<i64>>,
+    ntp: VecDeque<i64>,
+    alarmed: bool,
+}
+
+/// Estimates the local clock's offset from offsets observed against others
+///
+/// Cloning is cheap and clones share the same samples.
+#[derive(Debug, Clone)]
+pub struct ClockWatchdog {
+    config: WatchdogConfig,
+    samples: Arc<Mutex<Samples>>,
+}
+
+impl ClockWatchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            samples: Arc::new(Mutex::new(Samples::default())),
+        }
+    }
+
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    /// Record that `reported` was the source's time when ours was `local`
+    pub fn observe(
+        &self,
+        source: OffsetSource,
+        reported: DateTime<Utc>,
+        local: DateTime<Utc>,
+    ) -> Option<ClockAlert> {
+        self.record(source, reported - local)
+    }
+
+    /// Record an offset sample; returns an alert if the estimate crossed the maximum
+    pub fn record(&self, source: OffsetSource, offset: chrono::Duration) -> Option<ClockAlert> {
+        let mut samples = self.lock();
+        let window = match source {
+            OffsetSource::Peer(peer) => samples.peers.entry(peer).or_default(),
+            OffsetSource::Ntp => &mut samples.ntp,
+        };
+        window.push_back(offset.num_milliseconds());
+        while window.len() > self.config.samples.max(1) {
+            window.pop_front();
+        }
+
+        let offset = Self::estimate(&samples)?;
+        let max =
+            chrono::Duration::from_std(self.config.max_offset).unwrap_or(chrono::Duration::MAX);
+        let exceeded = offset.abs() > max;
+        if exceeded == samples.alarmed {
+            return None;
+        }
+        samples.alarmed = exceeded;
+        Some(if exceeded {
+            ClockAlert::Exceeded { offset }
+        } else {
+            ClockAlert::Recovered { offset }
+        })
+    }
+
+    /// Estimated offset of the local clock, if any samples were taken
+    pub fn offset(&self) -> Option<chrono::Duration> {
+        Self::estimate(&self.lock())
+    }
+
+    /// Whether the estimate currently exceeds the maximum
+    pub fn is_alarmed(&self) -> bool {
+        self.lock().alarmed
+    }
+
+    /// Local time corrected by the estimated offset
+    pub fn corrected(&self, local: DateTime<Utc>) -> DateTime<Utc> {
+        local + self.offset().unwrap_or_else(chrono::Duration::zero)
+    }
+
+    /// Drop the samples of a peer that left
+    pub fn forget(&self, peer: &PeerId) {
+        self.lock().peers.remove(peer);
+    }
+
+    fn estimate(samples: &Samples) -> Option<chrono::Duration> {
+        let millis = if samples.ntp.is_empty() {
+            let per_peer = samples
+                .peers
+                .values()
+                .filter_map(|window| median(window.iter().copied().collect()))
+                .collect();
+            median(per_peer)?
+        } else {
+            median(samples.ntp.iter().copied().collect())?
+        };
+        Some(chrono::Duration::milliseconds(millis))
+    }
+
+    // Never held across an await; a poisoned lock still holds valid samples
+    fn lock(&self) -> std::sync::MutexGuard<'_, Samples> {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        (values[middle - 1] + values[middle]) / 2
+    } else {
+        values[middle]
+    })
+}
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+/// Offset of `clock` from an NTP server, by a single SNTP exchange
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn query_ntp(
+    server: &str,
+    clock: &crate::clock::Clock,
+    timeout: Duration,
+) -> crate::error::Result<chrono::Duration> {
+    use crate::error::{ChaincraftError, NetworkError};
+
+    let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+    let mut request = [0u8; 48];
+    // Leap indicator 0, version 4, mode 3 (client)
+    request[0] = 0x23;
+
+    let sent = clock.now();
+    socket.send(&request).await?;
+    let mut response = [0u8; 48];
+    let received = tokio::time::timeout(timeout, socket.recv(&mut response))
+        .await
+        .map_err(|_| ChaincraftError::Network(NetworkError::Timeout { duration: timeout }))??;
+    let arrived = clock.now();
+    if received < 48 || response[0] & 0x07 != 4 {
+        return Err(ChaincraftError::Network(NetworkError::InvalidMessage {
+            reason: format!("not an NTP server reply from {}", server),
+        }));
+    }
+
+    let server_received = ntp_time(&response[32..40]);
+    let server_sent = ntp_time(&response[40..48]);
+    Ok(((server_received - sent) + (server_sent - arrived)) / 2)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn ntp_time(bytes: &[u8]) -> DateTime<Utc> {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+    DateTime::from_timestamp(seconds - NTP_UNIX_OFFSET, nanos as u32).unwrap_or_default()
+}
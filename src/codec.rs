@@ -0,0 +1,6 @@
+//! Encodings with guarantees beyond serde's
+//!
+//! [`consensus`] fixes the byte layout of values that feed hashes which every
+//! node must agree on.
+
+pub mod consensus;
@@ -0,0 +1,118 @@
+//! Canonical encoding for consensus-critical hashing
+//!
+//! Hashes that nodes compare with each other must not depend on the
+//! architecture or on incidental formatting. Everything fed to such a hash
+//! goes through this module:
+//!
+//! - integers are fixed-width and big-endian (network byte order); `usize`
+//!   is always widened to 8 bytes, so 32-bit and 64-bit builds agree;
+//! - strings and byte slices are prefixed with their length as a `u32`, so
+//!   `("ab", "c")` and `("a", "bc")` never encode the same.
+
+use sha2::{Digest, Sha256};
+
+/// Encode a `u32` in network byte order
+pub fn encode_u32(value: u32) -> [u8; 4] {
+    value.to_be_bytes()
+}
+
+/// Encode a `u64` in network byte order
+pub fn encode_u64(value: u64) -> [u8; 8] {
+    value.to_be_bytes()
+}
+
+/// Encode an `i64` in network byte order
+pub fn encode_i64(value: i64) -> [u8; 8] {
+    value.to_be_bytes()
+}
+
+/// Encode a `usize` as 8 bytes regardless of the platform's pointer width
+pub fn encode_usize(value: usize) -> [u8; 8] {
+    encode_u64(value as u64)
+}
+
+/// Encode bytes prefixed with their length
+///
+/// # Panics
+///
+/// If `bytes` is longer than `u32::MAX`.
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(bytes.len()).expect("consensus field longer than u32::MAX");
+    let mut out = Vec::with_capacity(4 + bytes.len());
+    out.extend_from_slice(&encode_u32(len));
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Encode a UTF-8 string prefixed with its length
+pub fn encode_str(value: &str) -> Vec<u8> {
+    encode_bytes(value.as_bytes())
+}
+
+/// SHA-256 over canonically encoded fields
+///
+/// ```
+/// use chaincraft_rust::codec::consensus::ConsensusHasher;
+///
+/// let digest = ConsensusHasher::new()
+///     .str("round")
+///     .u64(7)
+///     .finish_hex();
+/// assert_eq!(digest.len(), 64);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConsensusHasher {
+    hasher: Sha256,
+}
+
+impl ConsensusHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u32(mut self, value: u32) -> Self {
+        self.hasher.update(encode_u32(value));
+        self
+    }
+
+    pub fn u64(mut self, value: u64) -> Self {
+        self.hasher.update(encode_u64(value));
+        self
+    }
+
+    pub fn i64(mut self, value: i64) -> Self {
+        self.hasher.update(encode_i64(value));
+        self
+    }
+
+    pub fn usize(mut self, value: usize) -> Self {
+        self.hasher.update(encode_usize(value));
+        self
+    }
+
+    /// Length-prefixed bytes
+    pub fn bytes(mut self, value: &[u8]) -> Self {
+        self.hasher.update(encode_bytes(value));
+        self
+    }
+
+    /// Length-prefixed string
+    pub fn str(mut self, value: &str) -> Self {
+        self.hasher.update(encode_str(value));
+        self
+    }
+
+    /// Timestamp as seconds and nanoseconds since the Unix epoch
+    pub fn timestamp(self, value: &chrono::DateTime<chrono::Utc>) -> Self {
+        self.i64(value.timestamp())
+            .u32(value.timestamp_subsec_nanos())
+    }
+
+    pub fn finish(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+
+    pub fn finish_hex(self) -> String {
+        hex::encode(self.finish())
+    }
+}
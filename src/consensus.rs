@@ -1,6 +1,109 @@
 //! Consensus mechanisms for distributed agreement
 
+use crate::crypto::pow::{BlockTiming, DifficultyAdjuster};
 use crate::error::Result;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+pub mod poa;
+
+/// Number of recent blocks [`BlockRate`] is measured over
+pub const BLOCK_RATE_WINDOW: usize = 20;
+
+/// When a block producer proposes the next block
+///
+/// A short interval lowers latency; skipping empty blocks saves work and
+/// storage while the network is idle, at the price of blocks that no longer
+/// prove the producers are alive. `max_block_interval` bounds that gap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTimeConfig {
+    /// Target time between blocks
+    pub block_interval: Duration,
+    /// Only propose when something is pending or `max_block_interval` passed
+    pub skip_empty_blocks: bool,
+    /// Longest time without a block while empty blocks are skipped
+    pub max_block_interval: Duration,
+}
+
+impl Default for BlockTimeConfig {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5))
+    }
+}
+
+impl BlockTimeConfig {
+    /// Propose a block every `block_interval`, empty or not
+    pub fn new(block_interval: Duration) -> Self {
+        Self {
+            block_interval,
+            skip_empty_blocks: false,
+            max_block_interval: block_interval * 12,
+        }
+    }
+
+    /// Skip empty blocks, but produce one at least every `max_block_interval`
+    pub fn skip_empty_blocks(mut self, max_block_interval: Duration) -> Self {
+        self.skip_empty_blocks = true;
+        self.max_block_interval = max_block_interval;
+        self
+    }
+
+    /// Whether a block is due `since_last` after the previous one with
+    /// `pending` transactions waiting
+    pub fn is_due(&self, since_last: Duration, pending: usize) -> bool {
+        if since_last < self.block_interval {
+            return false;
+        }
+        pending > 0 || !self.skip_empty_blocks || since_last >= self.max_block_interval
+    }
+}
+
+/// Effective block production over the most recent blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BlockRate {
+    /// Blocks measured
+    pub blocks: usize,
+    /// Mean time between consecutive blocks, if at least two were measured
+    pub average_interval: Option<Duration>,
+}
+
+impl BlockRate {
+    /// Rate of blocks produced at `timestamps`, oldest first
+    ///
+    /// Only the last [`BLOCK_RATE_WINDOW`] blocks count.
+    pub fn from_timestamps(timestamps: &[DateTime<Utc>]) -> Self {
+        let recent = &timestamps[timestamps.len().saturating_sub(BLOCK_RATE_WINDOW)..];
+        let average_interval = match (recent.first(), recent.last()) {
+            (Some(first), Some(last)) if recent.len() > 1 => last
+                .signed_duration_since(*first)
+                .to_std()
+                .ok()
+                .map(|span| span / (recent.len() - 1) as u32),
+            _ => None,
+        };
+        Self {
+            blocks: recent.len(),
+            average_interval,
+        }
+    }
+
+    /// Blocks per minute at the average interval, 0 if unknown
+    pub fn blocks_per_minute(&self) -> f64 {
+        match self.average_interval {
+            Some(interval) if !interval.is_zero() => 60.0 / interval.as_secs_f64(),
+            _ => 0.0,
+        }
+    }
+
+    /// JSON summary for `get_state` and dashboards
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "blocks": self.blocks,
+            "average_interval_ms": self.average_interval.map(|i| i.as_millis() as u64),
+            "blocks_per_minute": self.blocks_per_minute(),
+        })
+    }
+}
 
 /// Base trait for consensus mechanisms
 pub trait Consensus: Send + Sync {
@@ -14,12 +117,43 @@ pub trait Consensus: Send + Sync {
 /// Simple proof-of-work consensus
 pub struct ProofOfWorkConsensus {
     difficulty: u32,
+    adjuster: Option<DifficultyAdjuster>,
 }
 
 impl ProofOfWorkConsensus {
     /// Create a new PoW consensus with the given difficulty
     pub fn new(difficulty: u32) -> Self {
-        Self { difficulty }
+        Self {
+            difficulty,
+            adjuster: None,
+        }
+    }
+
+    /// Follow block times with `adjuster` instead of a fixed difficulty
+    pub fn with_adjuster(mut self, adjuster: DifficultyAdjuster) -> Self {
+        self.adjuster = Some(adjuster);
+        self
+    }
+
+    /// Difficulty the next block must be mined at
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// Retarget after `parent` was added
+    ///
+    /// `reference` is the block at the adjuster's
+    /// [`reference_height`](DifficultyAdjuster::reference_height), `None` if
+    /// there is none and `parent`'s difficulty carries over. Returns the
+    /// difficulty of the next block; without an adjuster it never changes.
+    pub fn retarget(&mut self, parent: &BlockTiming, reference: Option<&BlockTiming>) -> u32 {
+        if let Some(adjuster) = &self.adjuster {
+            self.difficulty = match reference {
+                Some(reference) => adjuster.next_difficulty(parent, reference),
+                None => parent.difficulty,
+            };
+        }
+        self.difficulty
     }
 }
 
@@ -0,0 +1,433 @@
+//! Proof-of-authority consensus
+//!
+//! A fixed, ordered set of authorities takes turns sealing blocks: the signer of
+//! block `n` is `authorities[n % authorities.len()]`. Authorities can vote to
+//! add or remove a signer by attaching a vote to the blocks they seal; once more
+//! than half of the current authorities agree, the change is applied.
+//!
+//! Authorities are identified by the hex encoding of their Ed25519 public key.
+//! The sealing key can live in the engine or behind a
+//! [`RemoteSigner`](crate::crypto::signer::RemoteSigner), in which case blocks
+//! are sealed with [`PoaEngine::seal_block_async`].
+//!
+//! Blocks are due every [`PoaConfig::block_period`]. An engine built
+//! [`PoaEngine::with_block_time`] can instead skip empty blocks until
+//! transactions or votes are pending or a maximum interval has passed.
+
+use crate::{
+    consensus::{BlockRate, BlockTimeConfig, Consensus},
+    crypto::{signer::RemoteSigner, KeyType, PrivateKey, PublicKey, Signature},
+    error::{ChaincraftError, Result},
+    index::{IndexKey, IndexSpec},
+    shared::MessageType,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Message type used to carry sealed blocks between nodes
+pub const POA_BLOCK_MESSAGE_TYPE: &str = "POA_BLOCK";
+
+/// Name of the index of stored blocks by number, see [`PoaBlock::height_index`]
+pub const BLOCK_HEIGHT_INDEX: &str = "block_height";
+
+/// Proof-of-authority settings
+#[derive(Debug, Clone)]
+pub struct PoaConfig {
+    /// Initial authority set (hex encoded Ed25519 public keys)
+    pub authorities: Vec<String>,
+    /// Time between blocks
+    pub block_period: Duration,
+}
+
+impl Default for PoaConfig {
+    fn default() -> Self {
+        Self {
+            authorities: Vec::new(),
+            block_period: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Vote to add (`authorize = true`) or remove an authority
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct PoaVote {
+    pub candidate: String,
+    pub authorize: bool,
+}
+
+/// Block sealed by an authority
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PoaBlock {
+    pub number: u64,
+    pub parent_hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub signer: String,
+    pub transactions: Vec<serde_json::Value>,
+    pub vote: Option<PoaVote>,
+    pub hash: String,
+    pub signature: String,
+}
+
+impl PoaBlock {
+    /// Index of block messages by [`PoaBlock::number`]
+    pub fn height_index() -> IndexSpec {
+        IndexSpec::new(BLOCK_HEIGHT_INDEX, |message| match &message.message_type {
+            MessageType::Custom(kind) if kind == POA_BLOCK_MESSAGE_TYPE => message.data["number"]
+                .as_u64()
+                .map(IndexKey::Number)
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    fn genesis() -> Self {
+        let mut block = Self {
+            number: 0,
+            parent_hash: String::new(),
+            timestamp: Utc.timestamp_opt(0, 0).unwrap(),
+            signer: "genesis".to_string(),
+            transactions: Vec::new(),
+            vote: None,
+            hash: String::new(),
+            signature: String::new(),
+        };
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    /// Calculate the hash of the block header and body (excluding the seal)
+    pub fn calculate_hash(&self) -> String {
+        let content = serde_json::json!({
+            "number": self.number,
+            "parent_hash": self.parent_hash,
+            "timestamp": self.timestamp.timestamp_millis(),
+            "signer": self.signer,
+            "transactions": self.transactions,
+            "vote": self.vote,
+        });
+        hex::encode(Sha256::digest(content.to_string().as_bytes()))
+    }
+
+    /// Check the block hash and the signer's seal
+    pub fn verify_seal(&self) -> Result<bool> {
+        if self.hash != self.calculate_hash() {
+            return Ok(false);
+        }
+        let public_key = PublicKey::from_hex(&self.signer, KeyType::Ed25519)?;
+        let signature = Signature::from_hex(&self.signature, KeyType::Ed25519)?;
+        public_key.verify(self.hash.as_bytes(), &signature)
+    }
+}
+
+/// Proof-of-authority engine
+#[derive(Debug)]
+pub struct PoaEngine {
+    config: PoaConfig,
+    authorities: Vec<String>,
+    signer: Option<PrivateKey>,
+    remote_signer: Option<Arc<dyn RemoteSigner>>,
+    chain: Vec<PoaBlock>,
+    pending_transactions: Vec<serde_json::Value>,
+    pending_votes: Vec<PoaVote>,
+    tally: HashMap<PoaVote, HashSet<String>>,
+    block_time: BlockTimeConfig,
+}
+
+impl PoaEngine {
+    /// Create an engine that follows the chain without sealing blocks
+    pub fn new(config: PoaConfig) -> Self {
+        let mut authorities = config.authorities.clone();
+        authorities.sort();
+        authorities.dedup();
+
+        Self {
+            block_time: BlockTimeConfig::new(config.block_period),
+            config,
+            authorities,
+            signer: None,
+            remote_signer: None,
+            chain: vec![PoaBlock::genesis()],
+            pending_transactions: Vec::new(),
+            pending_votes: Vec::new(),
+            tally: HashMap::new(),
+        }
+    }
+
+    /// Seal blocks with the given key when it is this node's turn
+    pub fn with_signer(mut self, private_key: PrivateKey) -> Self {
+        self.signer = Some(private_key);
+        self
+    }
+
+    /// Seal blocks through a signer that holds the key outside the engine
+    ///
+    /// Takes precedence over a key set with [`Self::with_signer`]; blocks must
+    /// then be sealed with the async methods.
+    pub fn with_remote_signer(mut self, signer: Arc<dyn RemoteSigner>) -> Self {
+        self.remote_signer = Some(signer);
+        self
+    }
+
+    /// Produce blocks on this schedule instead of every [`PoaConfig::block_period`]
+    pub fn with_block_time(mut self, block_time: BlockTimeConfig) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    /// Schedule blocks are produced on
+    pub fn block_time(&self) -> &BlockTimeConfig {
+        &self.block_time
+    }
+
+    /// Effective rate of the latest blocks of the chain, whoever sealed them
+    pub fn block_rate(&self) -> BlockRate {
+        let timestamps: Vec<DateTime<Utc>> = self.chain[1..]
+            .iter()
+            .map(|block| block.timestamp)
+            .collect();
+        BlockRate::from_timestamps(&timestamps)
+    }
+
+    /// Transactions and votes waiting for the next block
+    pub fn pending(&self) -> usize {
+        self.pending_transactions.len() + self.pending_votes.len()
+    }
+
+    /// Whether sealing is delegated to a remote signer
+    pub fn has_remote_signer(&self) -> bool {
+        self.remote_signer.is_some()
+    }
+
+    /// Get the engine settings
+    pub fn config(&self) -> &PoaConfig {
+        &self.config
+    }
+
+    /// Current authority set, in signing order
+    pub fn authorities(&self) -> &[String] {
+        &self.authorities
+    }
+
+    /// Identity of the local signer, if any
+    pub fn local_address(&self) -> Option<String> {
+        match (&self.remote_signer, &self.signer) {
+            (Some(remote), _) => Some(remote.public_key().to_hex()),
+            (None, Some(key)) => Some(key.public_key().to_hex()),
+            (None, None) => None,
+        }
+    }
+
+    /// Whether the local signer is currently an authority
+    pub fn is_authority(&self) -> bool {
+        self.local_address()
+            .map(|address| self.authorities.contains(&address))
+            .unwrap_or(false)
+    }
+
+    /// Get the chain, genesis first
+    pub fn chain(&self) -> &[PoaBlock] {
+        &self.chain
+    }
+
+    /// Get the latest block
+    pub fn head(&self) -> &PoaBlock {
+        self.chain.last().expect("chain always contains genesis")
+    }
+
+    /// Authority expected to seal the block with the given number
+    pub fn expected_signer(&self, number: u64) -> Option<&str> {
+        if self.authorities.is_empty() {
+            return None;
+        }
+        let index = (number % self.authorities.len() as u64) as usize;
+        Some(&self.authorities[index])
+    }
+
+    /// Whether the local signer should seal the next block
+    pub fn is_my_turn(&self) -> bool {
+        let next = self.head().number + 1;
+        match (self.local_address(), self.expected_signer(next)) {
+            (Some(address), Some(expected)) => address == expected,
+            _ => false,
+        }
+    }
+
+    /// Queue a transaction for the next block
+    pub fn submit_transaction(&mut self, transaction: serde_json::Value) {
+        self.pending_transactions.push(transaction);
+    }
+
+    /// Queue a vote to add or remove an authority; it is attached to the next
+    /// block sealed by this node
+    pub fn propose_vote(&mut self, candidate: String, authorize: bool) {
+        self.pending_votes.push(PoaVote {
+            candidate,
+            authorize,
+        });
+    }
+
+    /// Current votes for a proposal
+    pub fn vote_count(&self, vote: &PoaVote) -> usize {
+        self.tally.get(vote).map(|voters| voters.len()).unwrap_or(0)
+    }
+
+    /// Seal the next block with pending transactions and the next pending vote
+    pub fn seal_block(&mut self) -> Result<PoaBlock> {
+        if self.remote_signer.is_some() {
+            return Err(ChaincraftError::consensus(
+                "Signing is delegated to a remote signer, use seal_block_async",
+            ));
+        }
+        let Some(private_key) = self.signer.clone() else {
+            return Err(ChaincraftError::consensus("No signing key configured"));
+        };
+        let mut block = self.prepare_block()?;
+        block.signature = private_key.sign(block.hash.as_bytes())?.to_hex();
+        self.apply_sealed(block)
+    }
+
+    /// Seal the next block, asking the remote signer for the seal if one is configured
+    pub async fn seal_block_async(&mut self) -> Result<PoaBlock> {
+        let Some(remote) = self.remote_signer.clone() else {
+            return self.seal_block();
+        };
+        let mut block = self.prepare_block()?;
+        block.signature = remote.sign(block.hash.as_bytes()).await?.to_hex();
+        self.apply_sealed(block)
+    }
+
+    /// Seal a block if it is this node's turn and the next block is due
+    ///
+    /// See [`BlockTimeConfig::is_due`]; only the engine's own pending
+    /// transactions and votes count when empty blocks are skipped.
+    pub fn produce_block_if_due(&mut self) -> Result<Option<PoaBlock>> {
+        if !self.is_due() {
+            return Ok(None);
+        }
+        self.seal_block().map(Some)
+    }
+
+    /// Like [`Self::produce_block_if_due`], sealing through the remote signer if configured
+    pub async fn produce_block_if_due_async(&mut self) -> Result<Option<PoaBlock>> {
+        if !self.is_due() {
+            return Ok(None);
+        }
+        self.seal_block_async().await.map(Some)
+    }
+
+    fn is_due(&self) -> bool {
+        if !self.is_my_turn() {
+            return false;
+        }
+        // A head from the future counts as just produced
+        let elapsed = Utc::now()
+            .signed_duration_since(self.head().timestamp)
+            .to_std()
+            .unwrap_or(Duration::ZERO);
+        self.block_time.is_due(elapsed, self.pending())
+    }
+
+    /// Build the next block without its seal
+    fn prepare_block(&self) -> Result<PoaBlock> {
+        let Some(signer) = self.local_address() else {
+            return Err(ChaincraftError::consensus("No signing key configured"));
+        };
+        if !self.is_my_turn() {
+            return Err(ChaincraftError::consensus("Not this node's turn to seal a block"));
+        }
+
+        let head = self.head();
+        let mut block = PoaBlock {
+            number: head.number + 1,
+            parent_hash: head.hash.clone(),
+            timestamp: Utc::now(),
+            signer,
+            transactions: self.pending_transactions.clone(),
+            vote: self.pending_votes.first().cloned(),
+            hash: String::new(),
+            signature: String::new(),
+        };
+        block.hash = block.calculate_hash();
+        Ok(block)
+    }
+
+    /// Import a block this engine sealed and drop what it included from the queues
+    fn apply_sealed(&mut self, block: PoaBlock) -> Result<PoaBlock> {
+        self.import_block(block.clone())?;
+        self.pending_transactions.clear();
+        if block.vote.is_some() {
+            self.pending_votes.remove(0);
+        }
+        Ok(block)
+    }
+
+    /// Validate a block against the chain head and append it
+    pub fn import_block(&mut self, block: PoaBlock) -> Result<()> {
+        let head = self.head();
+        if block.number != head.number + 1 || block.parent_hash != head.hash {
+            return Err(ChaincraftError::consensus(format!(
+                "Block {} does not extend the chain head {}",
+                block.number, head.number
+            )));
+        }
+        if self.expected_signer(block.number) != Some(block.signer.as_str()) {
+            return Err(ChaincraftError::consensus(format!(
+                "Block {} was sealed out of turn",
+                block.number
+            )));
+        }
+        if !block.verify_seal()? {
+            return Err(ChaincraftError::Crypto(crate::error::CryptoError::InvalidSignature));
+        }
+
+        if let Some(vote) = &block.vote {
+            self.apply_vote(vote.clone(), block.signer.clone());
+        }
+        self.chain.push(block);
+        Ok(())
+    }
+
+    fn apply_vote(&mut self, vote: PoaVote, voter: String) {
+        let is_member = self.authorities.contains(&vote.candidate);
+        if is_member == vote.authorize {
+            // Nothing to change
+            return;
+        }
+
+        let voters = self.tally.entry(vote.clone()).or_default();
+        voters.insert(voter);
+        if voters.len() * 2 <= self.authorities.len() {
+            return;
+        }
+
+        if vote.authorize {
+            self.authorities.push(vote.candidate.clone());
+            self.authorities.sort();
+        } else {
+            self.authorities.retain(|a| a != &vote.candidate);
+            for voters in self.tally.values_mut() {
+                voters.remove(&vote.candidate);
+            }
+        }
+        self.tally.retain(|v, _| v.candidate != vote.candidate);
+    }
+}
+
+impl Consensus for PoaEngine {
+    fn initialize(&self) -> Result<()> {
+        if self.authorities.is_empty() {
+            return Err(ChaincraftError::config("Proof-of-authority needs at least one authority"));
+        }
+        Ok(())
+    }
+
+    fn has_consensus(&self) -> Result<bool> {
+        Ok(!self.authorities.is_empty())
+    }
+}
@@ -0,0 +1,131 @@
+//! Gossip convergence measurement
+//!
+//! Every node records when it first stored each message (see
+//! [`NodeMetrics::record_first_seen`](crate::metrics::NodeMetrics::record_first_seen)).
+//! Putting those tables side by side shows how a message spread: the earliest
+//! sighting is taken as its origin, and the delays of the other nodes give
+//! the time it took to reach half (`p50`) and 95% (`p95`) of the network. The
+//! tables can come from nodes of one simulation or be exported by real nodes
+//! and combined with `chaincraft-cli convergence`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::Duration;
+
+/// When one node first saw each message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FirstSeenLog {
+    /// Node the table belongs to
+    pub node: String,
+    pub first_seen: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// How one message spread over the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct Propagation {
+    pub hash: String,
+    /// Earliest sighting on any node
+    pub origin: DateTime<Utc>,
+    /// Delay after the origin for every node that saw it, shortest first
+    pub delays: Vec<Duration>,
+}
+
+impl Propagation {
+    /// Time until `fraction` of `nodes` had seen the message, if they ever did
+    pub fn time_to_coverage(&self, fraction: f64, nodes: usize) -> Option<Duration> {
+        let needed = ((fraction * nodes as f64).ceil() as usize).max(1);
+        self.delays.get(needed - 1).copied()
+    }
+}
+
+/// Propagation of every message in `logs`, ordered by origin time
+pub fn propagations(logs: &[FirstSeenLog]) -> Vec<Propagation> {
+    let mut sightings: HashMap<&str, Vec<DateTime<Utc>>> = HashMap::new();
+    for log in logs {
+        for (hash, seen) in &log.first_seen {
+            sightings.entry(hash).or_default().push(*seen);
+        }
+    }
+
+    let mut result: Vec<Propagation> = sightings
+        .into_iter()
+        .map(|(hash, mut times)| {
+            times.sort();
+            let origin = times[0];
+            Propagation {
+                hash: hash.to_string(),
+                origin,
+                delays: times
+                    .iter()
+                    .map(|seen| (*seen - origin).to_std().unwrap_or_default())
+                    .collect(),
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.origin.cmp(&b.origin).then_with(|| a.hash.cmp(&b.hash)));
+    result
+}
+
+/// Summary of how fast messages covered the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvergenceReport {
+    pub nodes: usize,
+    pub messages: usize,
+    /// Messages every node saw
+    pub fully_covered: usize,
+    /// Median over messages of the time to reach half of the nodes
+    pub p50: Option<Duration>,
+    /// Median over messages of the time to reach 95% of the nodes
+    pub p95: Option<Duration>,
+    /// Longest time any message took to reach every node
+    pub max: Option<Duration>,
+}
+
+impl ConvergenceReport {
+    /// Build a report from the first-seen tables of all nodes
+    pub fn from_logs(logs: &[FirstSeenLog]) -> Self {
+        let nodes = logs.len();
+        let spread = propagations(logs);
+        let median = |fraction: f64| {
+            let mut times: Vec<Duration> = spread
+                .iter()
+                .filter_map(|p| p.time_to_coverage(fraction, nodes))
+                .collect();
+            times.sort();
+            times.get(times.len().saturating_sub(1) / 2).copied()
+        };
+        let full: Vec<Duration> = spread
+            .iter()
+            .filter_map(|p| p.time_to_coverage(1.0, nodes))
+            .collect();
+
+        Self {
+            nodes,
+            messages: spread.len(),
+            fully_covered: full.len(),
+            p50: median(0.5),
+            p95: median(0.95),
+            max: full.into_iter().max(),
+        }
+    }
+}
+
+impl fmt::Display for ConvergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |time: Option<Duration>| match time {
+            Some(time) => format!("{:.1} ms", time.as_secs_f64() * 1000.0),
+            None => "n/a".to_string(),
+        };
+        writeln!(f, "nodes:         {}", self.nodes)?;
+        writeln!(
+            f,
+            "messages:      {} ({} reached every node)",
+            self.messages, self.fully_covered
+        )?;
+        writeln!(f, "p50 coverage:  {}", show(self.p50))?;
+        writeln!(f, "p95 coverage:  {}", show(self.p95))?;
+        write!(f, "full coverage: {} worst", show(self.max))
+    }
+}
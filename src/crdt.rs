@@ -0,0 +1,469 @@
+//! Conflict-free replicated data types
+//!
+//! [`SimpleSharedNumber`](crate::shared_object::SimpleSharedNumber) sums
+//! whatever integers arrive; the types here converge by construction. Each is
+//! a state with a [`Crdt::merge`] that is commutative, associative and
+//! idempotent, so replicas that saw the same updates agree on the value
+//! whatever order, and however many times, the updates arrived.
+//!
+//! Updates travel as delta states: a mutator such as [`GCounter::increment`]
+//! changes the local state and returns the small state that carries the
+//! change, which [`CrdtObject::message`] wraps as message data. The object
+//! merges every state it receives, so a full state shared by a replica that
+//! catches up is applied the same way as a delta.
+//!
+//! ```ignore
+//! let id = node.add_shared_object(Box::new(GCounterObject::new())).await?;
+//! let delta = GCounter::default().increment("alice", 3);
+//! node.create_shared_message_with_data(GCounterObject::message(&delta)).await?;
+//! ```
+//!
+//! | Type            | Value                   | Concurrent updates                       |
+//! |-----------------|-------------------------|------------------------------------------|
+//! | [`GCounter`]    | Sum of increments       | All count                                |
+//! | [`PnCounter`]   | Increments - decrements | All count                                |
+//! | [`LwwRegister`] | Last value written      | Latest timestamp wins, then replica name |
+//! | [`OrSet`]       | Set of strings          | An add wins over a remove it did not see |
+
+use crate::{
+    error::Result,
+    shared::{DigestAccumulator, DigestHistory, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+
+/// State of a replicated data type that merges without conflicts
+pub trait Crdt:
+    Debug + Clone + Default + PartialEq + Serialize + DeserializeOwned + Send + Sync + 'static
+{
+    /// Name carried in message data as `crdt`
+    const NAME: &'static str;
+
+    /// Type name of the [`CrdtObject`] holding this state
+    const TYPE_NAME: &'static str;
+
+    /// Join another replica's state, or a delta, into this one
+    fn merge(&mut self, other: &Self);
+
+    /// Value the state represents
+    fn value(&self) -> Value;
+}
+
+/// Grow-only counter
+///
+/// Each replica counts its own increments; the value is the sum and a merge
+/// keeps the highest count seen per replica.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GCounter {
+    counts: BTreeMap<String, u64>,
+}
+
+impl GCounter {
+    /// Add `amount` to `replica`'s count and return the delta to share
+    pub fn increment(&mut self, replica: &str, amount: u64) -> Self {
+        let count = self.counts.entry(replica.to_string()).or_default();
+        *count = count.saturating_add(amount);
+        Self {
+            counts: BTreeMap::from([(replica.to_string(), *count)]),
+        }
+    }
+
+    /// Sum of every replica's count
+    pub fn total(&self) -> u64 {
+        self.counts
+            .values()
+            .fold(0, |sum, count| sum.saturating_add(*count))
+    }
+
+    /// Count of one replica
+    pub fn count(&self, replica: &str) -> u64 {
+        self.counts.get(replica).copied().unwrap_or_default()
+    }
+}
+
+impl Crdt for GCounter {
+    const NAME: &'static str = "g_counter";
+    const TYPE_NAME: &'static str = "GCounter";
+
+    fn merge(&mut self, other: &Self) {
+        for (replica, count) in &other.counts {
+            let entry = self.counts.entry(replica.clone()).or_default();
+            *entry = (*entry).max(*count);
+        }
+    }
+
+    fn value(&self) -> Value {
+        json!(self.total())
+    }
+}
+
+/// Counter that goes up and down
+///
+/// A pair of grow-only counters, one for increments and one for decrements.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PnCounter {
+    increments: GCounter,
+    decrements: GCounter,
+}
+
+impl PnCounter {
+    /// Add `amount` on behalf of `replica` and return the delta to share
+    pub fn increment(&mut self, replica: &str, amount: u64) -> Self {
+        Self {
+            increments: self.increments.increment(replica, amount),
+            decrements: GCounter::default(),
+        }
+    }
+
+    /// Subtract `amount` on behalf of `replica` and return the delta to share
+    pub fn decrement(&mut self, replica: &str, amount: u64) -> Self {
+        Self {
+            increments: GCounter::default(),
+            decrements: self.decrements.increment(replica, amount),
+        }
+    }
+
+    /// Increments minus decrements
+    pub fn total(&self) -> i64 {
+        self.increments.total() as i64 - self.decrements.total() as i64
+    }
+}
+
+impl Crdt for PnCounter {
+    const NAME: &'static str = "pn_counter";
+    const TYPE_NAME: &'static str = "PnCounter";
+
+    fn merge(&mut self, other: &Self) {
+        self.increments.merge(&other.increments);
+        self.decrements.merge(&other.decrements);
+    }
+
+    fn value(&self) -> Value {
+        json!(self.total())
+    }
+}
+
+/// A write to a [`LwwRegister`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LwwWrite {
+    pub value: Value,
+    pub timestamp: DateTime<Utc>,
+    /// Breaks ties between writes with the same timestamp
+    pub replica: String,
+}
+
+/// Last-writer-wins register
+///
+/// Holds the write with the latest timestamp; writes with equal timestamps
+/// are ordered by replica name so every replica picks the same one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LwwRegister {
+    write: Option<LwwWrite>,
+}
+
+impl LwwRegister {
+    /// Write `value` at `timestamp` on behalf of `replica` and return the
+    /// delta to share
+    ///
+    /// The write is lost if the register already holds a later one.
+    pub fn set(&mut self, value: Value, timestamp: DateTime<Utc>, replica: &str) -> Self {
+        let delta = Self {
+            write: Some(LwwWrite {
+                value,
+                timestamp,
+                replica: replica.to_string(),
+            }),
+        };
+        self.merge(&delta);
+        delta
+    }
+
+    /// Value of the winning write, if any
+    pub fn get(&self) -> Option<&Value> {
+        self.write.as_ref().map(|write| &write.value)
+    }
+
+    /// The winning write, if any
+    pub fn write(&self) -> Option<&LwwWrite> {
+        self.write.as_ref()
+    }
+}
+
+impl Crdt for LwwRegister {
+    const NAME: &'static str = "lww_register";
+    const TYPE_NAME: &'static str = "LwwRegister";
+
+    fn merge(&mut self, other: &Self) {
+        let Some(theirs) = &other.write else {
+            return;
+        };
+        // The value only decides between writes a replica made twice at once
+        let newer = match &self.write {
+            Some(ours) => {
+                (theirs.timestamp, &theirs.replica, theirs.value.to_string())
+                    > (ours.timestamp, &ours.replica, ours.value.to_string())
+            },
+            None => true,
+        };
+        if newer {
+            self.write = Some(theirs.clone());
+        }
+    }
+
+    fn value(&self) -> Value {
+        self.get().cloned().unwrap_or(Value::Null)
+    }
+}
+
+/// Observed-remove set of strings
+///
+/// Every add is tagged uniquely, and a remove deletes the tags its replica
+/// has seen. An add concurrent with a remove carries a tag the remove did
+/// not see, so the element stays: adds win.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrSet {
+    /// Tags of every add, by element
+    adds: BTreeMap<String, BTreeSet<String>>,
+    /// Tags removed so far
+    removed: BTreeSet<String>,
+}
+
+impl OrSet {
+    /// Add `element` on behalf of `replica` and return the delta to share
+    ///
+    /// Tags are `replica:n`, numbered past the replica's tags seen so far.
+    pub fn add(&mut self, element: &str, replica: &str) -> Self {
+        let prefix = format!("{}:", replica);
+        let next = self
+            .adds
+            .values()
+            .flatten()
+            .chain(&self.removed)
+            .filter_map(|tag| tag.strip_prefix(&prefix)?.parse::<u64>().ok())
+            .max()
+            .map_or(1, |n| n + 1);
+        let tag = format!("{}{}", prefix, next);
+        self.adds
+            .entry(element.to_string())
+            .or_default()
+            .insert(tag.clone());
+        Self {
+            adds: BTreeMap::from([(element.to_string(), BTreeSet::from([tag]))]),
+            removed: BTreeSet::new(),
+        }
+    }
+
+    /// Remove `element` as far as this replica has seen it and return the
+    /// delta to share
+    pub fn remove(&mut self, element: &str) -> Self {
+        let tags: BTreeSet<String> = self
+            .adds
+            .get(element)
+            .map(|tags| tags.difference(&self.removed).cloned().collect())
+            .unwrap_or_default();
+        self.removed.extend(tags.iter().cloned());
+        Self {
+            adds: BTreeMap::new(),
+            removed: tags,
+        }
+    }
+
+    /// Whether an add of `element` has not been removed
+    pub fn contains(&self, element: &str) -> bool {
+        self.adds
+            .get(element)
+            .is_some_and(|tags| tags.iter().any(|tag| !self.removed.contains(tag)))
+    }
+
+    /// Elements in the set, sorted
+    pub fn elements(&self) -> Vec<String> {
+        self.adds
+            .keys()
+            .filter(|element| self.contains(element))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Crdt for OrSet {
+    const NAME: &'static str = "or_set";
+    const TYPE_NAME: &'static str = "OrSet";
+
+    fn merge(&mut self, other: &Self) {
+        for (element, tags) in &other.adds {
+            self.adds
+                .entry(element.clone())
+                .or_default()
+                .extend(tags.iter().cloned());
+        }
+        self.removed.extend(other.removed.iter().cloned());
+    }
+
+    fn value(&self) -> Value {
+        json!(self.elements())
+    }
+}
+
+/// Message data carrying a CRDT state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CrdtMessage<C> {
+    crdt: String,
+    state: C,
+}
+
+/// Application object replicating a [`Crdt`]
+///
+/// Accepts messages whose data is `{"crdt": C::NAME, "state": ...}`, as
+/// built by [`CrdtObject::message`], and merges their state. A message that
+/// adds nothing new is ignored with reason `no_change`.
+#[derive(Debug, Clone)]
+pub struct CrdtObject<C: Crdt> {
+    id: SharedObjectId,
+    state: C,
+    history: DigestHistory,
+    accumulator: DigestAccumulator,
+}
+
+/// Replicated [`GCounter`]
+pub type GCounterObject = CrdtObject<GCounter>;
+/// Replicated [`PnCounter`]
+pub type PnCounterObject = CrdtObject<PnCounter>;
+/// Replicated [`LwwRegister`]
+pub type LwwRegisterObject = CrdtObject<LwwRegister>;
+/// Replicated [`OrSet`]
+pub type OrSetObject = CrdtObject<OrSet>;
+
+impl<C: Crdt> CrdtObject<C> {
+    pub fn new() -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            state: C::default(),
+            history: DigestHistory::new(),
+            accumulator: DigestAccumulator::new(),
+        }
+    }
+
+    /// Merged state of every message applied so far
+    pub fn state(&self) -> &C {
+        &self.state
+    }
+
+    /// Message data sharing `state`, a delta or a full state
+    pub fn message(state: &C) -> Value {
+        json!({ "crdt": C::NAME, "state": state })
+    }
+
+    fn parse(message: &SharedMessage) -> Option<C> {
+        let message: CrdtMessage<C> = serde_json::from_value(message.data.clone()).ok()?;
+        (message.crdt == C::NAME).then_some(message.state)
+    }
+}
+
+impl<C: Crdt> Default for CrdtObject<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<C: Crdt> ApplicationObject for CrdtObject<C> {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        C::TYPE_NAME
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(Self::parse(message).is_some())
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        let Some(state) = Self::parse(&message) else {
+            return Ok(ApplyOutcome::rejected(
+                "invalid_state",
+                format!("data is not a {} state", C::NAME),
+            ));
+        };
+        let before = self.state.clone();
+        self.state.merge(&state);
+        if self.state == before {
+            return Ok(ApplyOutcome::ignored("no_change", "state already merged"));
+        }
+        self.accumulator.apply(&message);
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.accumulator.digest().to_string())
+    }
+
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self.accumulator.contains(digest))
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
+    }
+
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
+    }
+
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.accumulator.messages_since(digest).ok_or_else(|| {
+            crate::error::ChaincraftError::validation(format!("unknown digest {}", digest))
+        })
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({
+            "crdt": C::NAME,
+            "value": self.state.value(),
+            "state": self.state,
+        }))
+    }
+
+    async fn load_state(&mut self, state: Value) -> Result<()> {
+        self.state = serde_json::from_value(state["state"].clone())?;
+        Ok(())
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.state = C::default();
+        self.history.clear();
+        self.accumulator.reset();
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
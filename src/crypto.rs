@@ -1,9 +1,14 @@
 //! Cryptographic primitives for blockchain operations
 
 pub mod address;
+pub mod bls;
 pub mod ecdsa;
 pub mod hash;
+pub mod hd;
+pub mod keystore;
+pub mod pool;
 pub mod pow;
+pub mod signer;
 pub mod vdf;
 pub mod vrf;
 
@@ -11,6 +16,7 @@ use crate::error::{ChaincraftError, CryptoError, Result};
 use async_trait::async_trait;
 use ed25519_dalek::{Signer, Verifier};
 use k256::ecdsa::signature::Verifier as K256Verifier;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -57,6 +63,8 @@ pub trait KeyedCryptoPrimitive: Send + Sync {
 pub enum PublicKey {
     Ed25519(ed25519_dalek::VerifyingKey),
     Secp256k1(k256::PublicKey),
+    /// BLS key in G1, see [`bls`]
+    Bls12_381(bls12_381::G1Affine),
 }
 
 impl Serialize for PublicKey {
@@ -69,6 +77,9 @@ impl Serialize for PublicKey {
             PublicKey::Secp256k1(key) => {
                 serializer.serialize_str(&hex::encode(key.to_sec1_bytes()))
             },
+            PublicKey::Bls12_381(key) => {
+                serializer.serialize_str(&hex::encode(key.to_compressed()))
+            },
         }
     }
 }
@@ -90,6 +101,10 @@ impl<'de> Deserialize<'de> for PublicKey {
             // Try Secp256k1
             let key = k256::PublicKey::from_sec1_bytes(&bytes).map_err(serde::de::Error::custom)?;
             Ok(PublicKey::Secp256k1(key))
+        } else if bytes.len() == 48 {
+            let key = bls::public_key_from_bytes(&bytes)
+                .ok_or_else(|| serde::de::Error::custom("Invalid BLS12-381 key"))?;
+            Ok(PublicKey::Bls12_381(key))
         } else {
             Err(serde::de::Error::custom("Invalid public key length"))
         }
@@ -102,6 +117,7 @@ impl PublicKey {
         match self {
             PublicKey::Ed25519(key) => key.as_bytes().to_vec(),
             PublicKey::Secp256k1(key) => key.to_sec1_bytes().to_vec(),
+            PublicKey::Bls12_381(key) => key.to_compressed().to_vec(),
         }
     }
 
@@ -140,6 +156,14 @@ impl PublicKey {
                 })?;
                 Ok(PublicKey::Secp256k1(key))
             },
+            KeyType::Bls12_381 => {
+                let key = bls::public_key_from_bytes(&bytes).ok_or_else(|| {
+                    ChaincraftError::Crypto(crate::error::CryptoError::InvalidPublicKey {
+                        reason: "Invalid BLS12-381 key".to_string(),
+                    })
+                })?;
+                Ok(PublicKey::Bls12_381(key))
+            },
         }
     }
 
@@ -147,6 +171,15 @@ impl PublicKey {
         match self {
             PublicKey::Ed25519(_) => "Ed25519",
             PublicKey::Secp256k1(_) => "Secp256k1",
+            PublicKey::Bls12_381(_) => "BLS12-381",
+        }
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            PublicKey::Ed25519(_) => KeyType::Ed25519,
+            PublicKey::Secp256k1(_) => KeyType::Secp256k1,
+            PublicKey::Bls12_381(_) => KeyType::Bls12_381,
         }
     }
 
@@ -159,6 +192,9 @@ impl PublicKey {
                 let verifying_key = k256::ecdsa::VerifyingKey::from(pk);
                 Ok(verifying_key.verify(message, sig).is_ok())
             }
+            (PublicKey::Bls12_381(pk), Signature::Bls12_381(sig)) => {
+                Ok(bls::verify(pk, message, sig, bls::SIGNATURE_DST))
+            }
             _ => Err(ChaincraftError::Crypto(CryptoError::InvalidSignature)),
         }
     }
@@ -169,6 +205,8 @@ impl PublicKey {
 pub enum PrivateKey {
     Ed25519(ed25519_dalek::SigningKey),
     Secp256k1(k256::SecretKey),
+    /// BLS scalar, see [`bls`]
+    Bls12_381(bls12_381::Scalar),
 }
 
 impl Serialize for PrivateKey {
@@ -179,6 +217,7 @@ impl Serialize for PrivateKey {
         match self {
             PrivateKey::Ed25519(key) => serializer.serialize_str(&hex::encode(key.as_bytes())),
             PrivateKey::Secp256k1(key) => serializer.serialize_str(&hex::encode(key.to_bytes())),
+            PrivateKey::Bls12_381(key) => serializer.serialize_str(&hex::encode(key.to_bytes())),
         }
     }
 }
@@ -211,6 +250,7 @@ impl PrivateKey {
         match self {
             PrivateKey::Ed25519(key) => PublicKey::Ed25519(key.verifying_key()),
             PrivateKey::Secp256k1(key) => PublicKey::Secp256k1(key.public_key()),
+            PrivateKey::Bls12_381(key) => PublicKey::Bls12_381(bls::public_key(key)),
         }
     }
 
@@ -219,6 +259,7 @@ impl PrivateKey {
         match self {
             PrivateKey::Ed25519(key) => hex::encode(key.as_bytes()),
             PrivateKey::Secp256k1(key) => hex::encode(key.to_bytes()),
+            PrivateKey::Bls12_381(key) => hex::encode(key.to_bytes()),
         }
     }
 
@@ -248,6 +289,14 @@ impl PrivateKey {
                 })?;
                 Ok(PrivateKey::Secp256k1(key))
             },
+            KeyType::Bls12_381 => {
+                let key = bls::secret_from_bytes(&bytes).ok_or_else(|| {
+                    ChaincraftError::Crypto(CryptoError::InvalidPrivateKey {
+                        reason: "Invalid BLS12-381 key".to_string(),
+                    })
+                })?;
+                Ok(PrivateKey::Bls12_381(key))
+            },
         }
     }
 
@@ -255,6 +304,15 @@ impl PrivateKey {
         match self {
             PrivateKey::Ed25519(_) => "Ed25519",
             PrivateKey::Secp256k1(_) => "Secp256k1",
+            PrivateKey::Bls12_381(_) => "BLS12-381",
+        }
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            PrivateKey::Ed25519(_) => KeyType::Ed25519,
+            PrivateKey::Secp256k1(_) => KeyType::Secp256k1,
+            PrivateKey::Bls12_381(_) => KeyType::Bls12_381,
         }
     }
 
@@ -271,6 +329,9 @@ impl PrivateKey {
                 let signature = signing_key.sign(message);
                 Ok(Signature::Secp256k1(signature))
             },
+            PrivateKey::Bls12_381(key) => {
+                Ok(Signature::Bls12_381(bls::sign(key, message, bls::SIGNATURE_DST)))
+            },
         }
     }
 }
@@ -280,6 +341,8 @@ impl PrivateKey {
 pub enum Signature {
     Ed25519(ed25519_dalek::Signature),
     Secp256k1(k256::ecdsa::Signature),
+    /// BLS signature in G2, see [`bls`]
+    Bls12_381(bls12_381::G2Affine),
 }
 
 impl Serialize for Signature {
@@ -290,6 +353,9 @@ impl Serialize for Signature {
         match self {
             Signature::Ed25519(sig) => serializer.serialize_str(&hex::encode(sig.to_bytes())),
             Signature::Secp256k1(sig) => serializer.serialize_str(&hex::encode(sig.to_bytes())),
+            Signature::Bls12_381(sig) => {
+                serializer.serialize_str(&hex::encode(sig.to_compressed()))
+            },
         }
     }
 }
@@ -318,6 +384,10 @@ impl<'de> Deserialize<'de> for Signature {
                 Ok(sig) => Ok(Signature::Secp256k1(sig)),
                 Err(_) => Err(serde::de::Error::custom("Invalid Secp256k1 signature")),
             }
+        } else if bytes.len() == 96 {
+            let sig = bls::signature_from_bytes(&bytes)
+                .ok_or_else(|| serde::de::Error::custom("Invalid BLS12-381 signature"))?;
+            Ok(Signature::Bls12_381(sig))
         } else {
             Err(serde::de::Error::custom("Invalid signature length"))
         }
@@ -330,6 +400,7 @@ impl Signature {
         match self {
             Signature::Ed25519(sig) => sig.to_bytes().to_vec(),
             Signature::Secp256k1(sig) => sig.to_bytes().to_vec(),
+            Signature::Bls12_381(sig) => sig.to_compressed().to_vec(),
         }
     }
 
@@ -357,6 +428,11 @@ impl Signature {
                 })?;
                 Ok(Signature::Secp256k1(sig))
             },
+            KeyType::Bls12_381 => {
+                let sig = bls::signature_from_bytes(&bytes)
+                    .ok_or(ChaincraftError::Crypto(crate::error::CryptoError::InvalidSignature))?;
+                Ok(Signature::Bls12_381(sig))
+            },
         }
     }
 
@@ -364,15 +440,17 @@ impl Signature {
         match self {
             Signature::Ed25519(_) => "Ed25519",
             Signature::Secp256k1(_) => "Secp256k1",
+            Signature::Bls12_381(_) => "BLS12-381",
         }
     }
 }
 
 /// Key types supported by the system
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum KeyType {
     Ed25519,
     Secp256k1,
+    Bls12_381,
 }
 
 impl KeyType {
@@ -381,6 +459,16 @@ impl KeyType {
         match self {
             KeyType::Ed25519 => "ed25519",
             KeyType::Secp256k1 => "secp256k1",
+            KeyType::Bls12_381 => "bls12_381",
+        }
+    }
+
+    /// Key type of a hex encoded public key, told apart by length
+    pub fn of_public_key_hex(public_key: &str) -> Self {
+        match public_key.len() {
+            64 => KeyType::Ed25519,
+            96 => KeyType::Bls12_381,
+            _ => KeyType::Secp256k1,
         }
     }
 }
@@ -392,48 +480,66 @@ impl std::str::FromStr for KeyType {
         match s.to_lowercase().as_str() {
             "ed25519" => Ok(KeyType::Ed25519),
             "secp256k1" => Ok(KeyType::Secp256k1),
+            "bls12_381" | "bls" => Ok(KeyType::Bls12_381),
             _ => Err(()),
         }
     }
 }
 
+/// Verify many `(key, message, signature)` triples on a CPU pool
+///
+/// The batch is split into one chunk per pool thread; results come back in
+/// input order. A key and signature of different algorithms verify as false.
+pub async fn verify_batch(
+    pool: &pool::CpuPool,
+    batch: Vec<(PublicKey, Vec<u8>, Signature)>,
+) -> Result<Vec<bool>> {
+    pool.map_chunks(batch, |(key, message, signature)| {
+        key.verify(&message, &signature).unwrap_or(false)
+    })
+    .await
+}
+
 /// Utility functions for cryptographic operations
 pub mod utils {
     use super::*;
     use rand::rngs::OsRng;
+    use rand::{CryptoRng, RngCore};
 
     /// Generate a new keypair for the specified key type
     pub fn generate_keypair(key_type: KeyType) -> Result<(PrivateKey, PublicKey)> {
-        let mut rng = OsRng;
+        generate_keypair_with_rng(key_type, &mut OsRng)
+    }
 
+    /// Generate a new keypair drawing randomness from the given RNG
+    ///
+    /// Passing a seeded [`crate::rng::RngProvider`] makes the generated keys reproducible.
+    pub fn generate_keypair_with_rng<R: CryptoRng + RngCore>(
+        key_type: KeyType,
+        rng: &mut R,
+    ) -> Result<(PrivateKey, PublicKey)> {
         match key_type {
             KeyType::Ed25519 => {
-                let private_key = ed25519_dalek::SigningKey::generate(&mut rng);
+                let private_key = ed25519_dalek::SigningKey::generate(rng);
                 let public_key = private_key.verifying_key();
                 Ok((PrivateKey::Ed25519(private_key), PublicKey::Ed25519(public_key)))
             },
             KeyType::Secp256k1 => {
-                let private_key = k256::SecretKey::random(&mut rng);
+                let private_key = k256::SecretKey::random(rng);
                 let public_key = private_key.public_key();
                 Ok((PrivateKey::Secp256k1(private_key), PublicKey::Secp256k1(public_key)))
             },
+            KeyType::Bls12_381 => {
+                let private_key = bls::generate(rng);
+                let public_key = bls::public_key(&private_key);
+                Ok((PrivateKey::Bls12_381(private_key), PublicKey::Bls12_381(public_key)))
+            },
         }
     }
 
     /// Sign a message with a private key
     pub fn sign_message(private_key: &PrivateKey, message: &[u8]) -> Result<Signature> {
-        match private_key {
-            PrivateKey::Ed25519(key) => {
-                let signature = key.sign(message);
-                Ok(Signature::Ed25519(signature))
-            },
-            PrivateKey::Secp256k1(key) => {
-                use k256::ecdsa::{signature::Signer, SigningKey};
-                let signing_key = SigningKey::from(key);
-                let signature = signing_key.sign(message);
-                Ok(Signature::Secp256k1(signature))
-            },
-        }
+        private_key.sign(message)
     }
 
     /// Verify a signature with a public key
@@ -442,18 +548,7 @@ pub mod utils {
         message: &[u8],
         signature: &Signature,
     ) -> Result<bool> {
-        match (public_key, signature) {
-            (PublicKey::Ed25519(pk), Signature::Ed25519(sig)) => {
-                use ed25519_dalek::Verifier;
-                Ok(pk.verify(message, sig).is_ok())
-            },
-            (PublicKey::Secp256k1(pk), Signature::Secp256k1(sig)) => {
-                use k256::ecdsa::{signature::Verifier, VerifyingKey};
-                let verifying_key = VerifyingKey::from(pk);
-                Ok(verifying_key.verify(message, sig).is_ok())
-            },
-            _ => Err(ChaincraftError::Crypto(CryptoError::InvalidSignature)),
-        }
+        public_key.verify(message, signature)
     }
 }
 
@@ -0,0 +1,160 @@
+//! BLS signatures over BLS12-381
+//!
+//! Public keys live in G1 (48 bytes compressed), signatures in G2 (96 bytes),
+//! the "minimal public key" variant Ethereum uses. Signatures by many keys
+//! over the same message add up to one signature that checks against the
+//! sum of the keys, so a commit certificate holds one signature however many
+//! validators signed it.
+//!
+//! Summing keys is only safe when every key is known to belong to someone
+//! holding its secret: otherwise a key chosen as the difference of other
+//! keys lets one signer forge an aggregate for all of them. Validators should
+//! publish a [`prove_possession`] proof with their key and have it checked
+//! with [`verify_possession`] before it is aggregated.
+
+use crate::crypto::{PrivateKey, PublicKey, Signature};
+use crate::error::{ChaincraftError, CryptoError, Result};
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use rand::{CryptoRng, RngCore};
+
+/// Domain separation tag of signatures, from the IETF BLS draft's
+/// proof-of-possession ciphersuite
+pub const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Domain separation tag of proofs of possession
+pub const POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Random non-zero secret key
+pub(crate) fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> Scalar {
+    loop {
+        let mut wide = [0u8; 64];
+        rng.fill_bytes(&mut wide);
+        let secret = Scalar::from_bytes_wide(&wide);
+        if secret != Scalar::zero() {
+            return secret;
+        }
+    }
+}
+
+/// Public key of a secret key
+pub(crate) fn public_key(secret: &Scalar) -> G1Affine {
+    G1Affine::from(G1Projective::generator() * secret)
+}
+
+/// Parse a secret key from its 32 little-endian bytes
+pub(crate) fn secret_from_bytes(bytes: &[u8]) -> Option<Scalar> {
+    let bytes: [u8; 32] = bytes.try_into().ok()?;
+    Option::from(Scalar::from_bytes(&bytes)).filter(|secret| *secret != Scalar::zero())
+}
+
+/// Parse a compressed public key, refusing the identity
+pub(crate) fn public_key_from_bytes(bytes: &[u8]) -> Option<G1Affine> {
+    let bytes: [u8; 48] = bytes.try_into().ok()?;
+    Option::<G1Affine>::from(G1Affine::from_compressed(&bytes))
+        .filter(|key| !bool::from(key.is_identity()))
+}
+
+/// Parse a compressed signature
+pub(crate) fn signature_from_bytes(bytes: &[u8]) -> Option<G2Affine> {
+    let bytes: [u8; 96] = bytes.try_into().ok()?;
+    Option::from(G2Affine::from_compressed(&bytes))
+}
+
+fn hash_to_g2(message: &[u8], dst: &[u8]) -> G2Affine {
+    G2Affine::from(<G2Projective as HashToCurve<ExpandMsgXmd<sha2_09::Sha256>>>::hash_to_curve(
+        message, dst,
+    ))
+}
+
+pub(crate) fn sign(secret: &Scalar, message: &[u8], dst: &[u8]) -> G2Affine {
+    G2Affine::from(G2Projective::from(hash_to_g2(message, dst)) * secret)
+}
+
+/// Whether `e(key, H(message)) == e(g1, signature)`
+pub(crate) fn verify(key: &G1Affine, message: &[u8], signature: &G2Affine, dst: &[u8]) -> bool {
+    if bool::from(key.is_identity()) {
+        return false;
+    }
+    let hashed = G2Prepared::from(hash_to_g2(message, dst));
+    let signature = G2Prepared::from(*signature);
+    let generator = -G1Affine::generator();
+    multi_miller_loop(&[(key, &hashed), (&generator, &signature)]).final_exponentiation()
+        == Gt::identity()
+}
+
+fn not_bls() -> ChaincraftError {
+    ChaincraftError::Crypto(CryptoError::InvalidSignature)
+}
+
+/// Sum signatures over the same message into one
+///
+/// Fails if there are none or one is not a BLS signature.
+pub fn aggregate_signatures(signatures: &[Signature]) -> Result<Signature> {
+    if signatures.is_empty() {
+        return Err(not_bls());
+    }
+    let mut sum = G2Projective::identity();
+    for signature in signatures {
+        let Signature::Bls12_381(signature) = signature else {
+            return Err(not_bls());
+        };
+        sum += signature;
+    }
+    Ok(Signature::Bls12_381(G2Affine::from(sum)))
+}
+
+/// Sum public keys into the key an aggregate signature checks against
+///
+/// Fails if there are none or one is not a BLS key.
+pub fn aggregate_public_keys(keys: &[PublicKey]) -> Result<PublicKey> {
+    if keys.is_empty() {
+        return Err(invalid_key("no keys to aggregate"));
+    }
+    let mut sum = G1Projective::identity();
+    for key in keys {
+        let PublicKey::Bls12_381(key) = key else {
+            return Err(invalid_key("not a BLS12-381 key"));
+        };
+        sum += key;
+    }
+    Ok(PublicKey::Bls12_381(G1Affine::from(sum)))
+}
+
+fn invalid_key(reason: &str) -> ChaincraftError {
+    ChaincraftError::Crypto(CryptoError::InvalidPublicKey {
+        reason: reason.to_string(),
+    })
+}
+
+/// Whether `signature` is the aggregate of signatures by every one of
+/// `keys` over `message`
+///
+/// Only sound for keys whose possession was proven.
+pub fn verify_aggregate(keys: &[PublicKey], message: &[u8], signature: &Signature) -> Result<bool> {
+    aggregate_public_keys(keys)?.verify(message, signature)
+}
+
+/// Proof that the holder of `key` knows its secret: a signature over the
+/// public key itself, under its own domain
+pub fn prove_possession(key: &PrivateKey) -> Result<Signature> {
+    let PrivateKey::Bls12_381(secret) = key else {
+        return Err(ChaincraftError::Crypto(CryptoError::InvalidPrivateKey {
+            reason: "not a BLS12-381 key".to_string(),
+        }));
+    };
+    let public = public_key(secret).to_compressed();
+    Ok(Signature::Bls12_381(sign(secret, &public, POSSESSION_DST)))
+}
+
+/// Check a [`prove_possession`] proof
+pub fn verify_possession(key: &PublicKey, proof: &Signature) -> bool {
+    match (key, proof) {
+        (PublicKey::Bls12_381(key), Signature::Bls12_381(proof)) => {
+            verify(key, &key.to_compressed(), proof, POSSESSION_DST)
+        },
+        _ => false,
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::crypto::{KeyType, KeyedCryptoPrimitive, PrivateKey, PublicKey, Signature};
 use crate::error::{ChaincraftError, CryptoError, Result};
+use crate::rng::RngProvider;
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
@@ -98,6 +99,16 @@ impl KeyedCryptoPrimitive for EcdsaSignature {
                 let verifying_key = VerifyingKey::from(pk);
                 Ok(verifying_key.verify(&input, &signature).is_ok())
             },
+            PublicKey::Bls12_381(pk) => {
+                let signature = crate::crypto::bls::signature_from_bytes(output)
+                    .ok_or(ChaincraftError::Crypto(CryptoError::InvalidSignature))?;
+                Ok(crate::crypto::bls::verify(
+                    pk,
+                    &input,
+                    &signature,
+                    crate::crypto::bls::SIGNATURE_DST,
+                ))
+            },
         }
     }
 }
@@ -125,7 +136,7 @@ impl ECDSASignature {
 }
 
 /// High-level ECDSA signer
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ECDSASigner {
     private_key: PrivateKey,
     public_key: PublicKey,
@@ -145,12 +156,46 @@ impl ECDSASigner {
         })
     }
 
+    /// Create a signer whose key is drawn from the given RNG provider
+    pub fn new_with_rng(rng: &RngProvider) -> Result<Self> {
+        let provider = EcdsaSignature::ed25519();
+        let (private_key, public_key) =
+            crate::crypto::utils::generate_keypair_with_rng(provider.key_type, &mut rng.clone())?;
+
+        Ok(Self {
+            private_key,
+            public_key,
+            provider,
+        })
+    }
+
+    /// Create a signer from an existing private key
+    pub fn from_private_key(private_key: PrivateKey) -> Self {
+        let provider = EcdsaSignature::new(private_key.key_type());
+        let public_key = private_key.public_key();
+
+        Self {
+            private_key,
+            public_key,
+            provider,
+        }
+    }
+
     pub fn sign(&self, message: &[u8]) -> Result<ECDSASignature> {
         let signature =
             futures::executor::block_on(self.provider.sign(&self.private_key, &message.to_vec()))?;
         Ok(ECDSASignature::new(signature.to_bytes()))
     }
 
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Key the signer signs with, for primitives other than signatures
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.private_key
+    }
+
     pub fn get_public_key_pem(&self) -> Result<String> {
         match &self.public_key {
             PublicKey::Ed25519(pk) => {
@@ -166,6 +211,10 @@ impl ECDSASigner {
                 let b64 = general_purpose::STANDARD.encode(point.as_bytes());
                 Ok(format!("-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----", b64))
             },
+            PublicKey::Bls12_381(pk) => {
+                let b64 = general_purpose::STANDARD.encode(pk.to_compressed());
+                Ok(format!("-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----", b64))
+            },
         }
     }
 }
@@ -199,7 +248,8 @@ impl ECDSAVerifier {
         ))
     }
 
-    fn parse_public_key_pem(&self, pem: &str) -> Result<PublicKey> {
+    /// Parse a key in the format of [`ECDSASigner::get_public_key_pem`]
+    pub fn parse_public_key_pem(&self, pem: &str) -> Result<PublicKey> {
         // Remove PEM headers and whitespace
         let cleaned_pem = pem
             .replace("-----BEGIN PUBLIC KEY-----", "")
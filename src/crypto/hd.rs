@@ -0,0 +1,370 @@
+//! Hierarchical deterministic keys
+//!
+//! One seed stands for a whole tree of keys. A BIP-39 mnemonic, twelve to
+//! twenty-four words from a fixed list, encodes random entropy with a
+//! checksum and is stretched into a 64-byte seed, optionally salted with a
+//! passphrase. BIP-32 turns the seed into a master key and chain code, and
+//! each child key is derived from its parent's by HMAC-SHA512, so a path like
+//! `m/44'/60'/0'/0/7` always leads to the same key. BIP-44 fixes what the
+//! levels of that path mean: purpose, coin type, account, change and address
+//! index.
+//!
+//! Secp256k1 keys follow BIP-32. Ed25519 keys follow SLIP-10, which only
+//! defines hardened children (`'`): their derivation needs the parent's
+//! private key, so a leaked child key and chain code reveal nothing above.
+
+use crate::crypto::{PrivateKey, PublicKey};
+use crate::error::{ChaincraftError, CryptoError, Result};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use std::fmt;
+use std::str::FromStr;
+
+/// Indices at or above this are hardened
+pub const HARDENED: u32 = 1 << 31;
+
+/// BIP-44 purpose level
+pub const BIP44_PURPOSE: u32 = 44;
+
+/// SLIP-44 coin type of Ether, which the ledger example's addresses follow
+pub const ETHEREUM_COIN_TYPE: u32 = 60;
+
+/// Curve of the keys a wallet derives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HdCurve {
+    Secp256k1,
+    Ed25519,
+}
+
+impl HdCurve {
+    /// Key of the HMAC producing the master key
+    fn seed_key(&self) -> &'static [u8] {
+        match self {
+            HdCurve::Secp256k1 => b"Bitcoin seed",
+            HdCurve::Ed25519 => b"ed25519 seed",
+        }
+    }
+}
+
+fn derivation_failed(reason: impl Into<String>) -> ChaincraftError {
+    ChaincraftError::Crypto(CryptoError::KeyGenerationFailed {
+        reason: reason.into(),
+    })
+}
+
+/// Random BIP-39 mnemonic of `words` English words: 12, 15, 18, 21 or 24
+pub fn generate_mnemonic(words: usize) -> Result<String> {
+    if !matches!(words, 12 | 15 | 18 | 21 | 24) {
+        return Err(ChaincraftError::validation(format!(
+            "a mnemonic has 12 to 24 words in steps of 3, not {}",
+            words
+        )));
+    }
+    let mut entropy = vec![0u8; words * 4 / 3];
+    rand::thread_rng().fill_bytes(&mut entropy);
+    let mnemonic =
+        bip39::Mnemonic::from_entropy(&entropy).map_err(|e| derivation_failed(e.to_string()))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Seed of a BIP-39 mnemonic, checking its words and checksum
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64]> {
+    let mnemonic = bip39::Mnemonic::parse(phrase)
+        .map_err(|e| ChaincraftError::validation(format!("invalid mnemonic: {}", e)))?;
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+/// Path from the master key to a descendant, such as `m/44'/60'/0'/0/0`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The master key itself
+    pub fn master() -> Self {
+        Self::default()
+    }
+
+    /// `m/44'/coin_type'/account'/change/index`
+    pub fn bip44(coin_type: u32, account: u32, change: u32, index: u32) -> Self {
+        Self(vec![
+            BIP44_PURPOSE | HARDENED,
+            coin_type | HARDENED,
+            account | HARDENED,
+            change,
+            index,
+        ])
+    }
+
+    /// Child indices from the master key down, hardened ones offset by
+    /// [`HARDENED`]
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// The same path with every level hardened, as Ed25519 requires
+    pub fn hardened(&self) -> Self {
+        Self(self.0.iter().map(|index| index | HARDENED).collect())
+    }
+
+    /// This path extended by one child
+    pub fn child(&self, index: u32) -> Self {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        Self(indices)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = ChaincraftError;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let invalid = || ChaincraftError::validation(format!("invalid derivation path '{}'", path));
+        let mut levels = path.split('/');
+        if levels.next() != Some("m") {
+            return Err(invalid());
+        }
+        let indices = levels
+            .map(|level| {
+                let (number, hardened) = match level.strip_suffix(['\'', 'h', 'H']) {
+                    Some(number) => (number, HARDENED),
+                    None => (level, 0),
+                };
+                match number.parse::<u32>() {
+                    Ok(index) if index < HARDENED => Ok(index | hardened),
+                    _ => Err(invalid()),
+                }
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if index & HARDENED != 0 {
+                write!(f, "/{}'", index & !HARDENED)?;
+            } else {
+                write!(f, "/{}", index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for DerivationPath {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DerivationPath {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        path.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Private key with the chain code its children are derived with
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    curve: HdCurve,
+    secret: [u8; 32],
+    chain_code: [u8; 32],
+    depth: u8,
+}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the key or chain code
+        f.debug_struct("ExtendedPrivateKey")
+            .field("curve", &self.curve)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data);
+    let output = mac.finalize().into_bytes();
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    (left, right)
+}
+
+impl ExtendedPrivateKey {
+    /// Master key of a seed, e.g. from [`mnemonic_to_seed`]
+    pub fn master(seed: &[u8], curve: HdCurve) -> Result<Self> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(ChaincraftError::validation("a seed has 16 to 64 bytes"));
+        }
+        let (secret, chain_code) = hmac_sha512(curve.seed_key(), seed);
+        let master = Self {
+            curve,
+            secret,
+            chain_code,
+            depth: 0,
+        };
+        // Invalid for secp256k1 with a probability below 2^-127
+        master.private_key()?;
+        Ok(master)
+    }
+
+    pub fn curve(&self) -> HdCurve {
+        self.curve
+    }
+
+    /// Levels below the master key
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn private_key(&self) -> Result<PrivateKey> {
+        match self.curve {
+            HdCurve::Secp256k1 => k256::SecretKey::from_bytes(&self.secret.into())
+                .map(PrivateKey::Secp256k1)
+                .map_err(|_| derivation_failed("derived key is out of range")),
+            HdCurve::Ed25519 => {
+                Ok(PrivateKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&self.secret)))
+            },
+        }
+    }
+
+    pub fn public_key(&self) -> Result<PublicKey> {
+        Ok(self.private_key()?.public_key())
+    }
+
+    /// Child at `index`, hardened when `index >= HARDENED`
+    ///
+    /// Fails for a non-hardened Ed25519 child, and for the rare secp256k1
+    /// index whose key falls out of range; BIP-32 wallets skip to the next
+    /// index then.
+    pub fn derive_child(&self, index: u32) -> Result<Self> {
+        let hardened = index & HARDENED != 0;
+        let mut data = Vec::with_capacity(37);
+        if hardened {
+            data.push(0);
+            data.extend_from_slice(&self.secret);
+        } else {
+            match self.private_key()? {
+                PrivateKey::Secp256k1(secret) => {
+                    use k256::elliptic_curve::sec1::ToEncodedPoint;
+                    data.extend_from_slice(secret.public_key().to_encoded_point(true).as_bytes());
+                },
+                _ => return Err(derivation_failed("Ed25519 keys only have hardened children")),
+            }
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+        let (tweak, chain_code) = hmac_sha512(&self.chain_code, &data);
+
+        let secret = match self.curve {
+            HdCurve::Ed25519 => tweak,
+            HdCurve::Secp256k1 => {
+                use k256::elliptic_curve::PrimeField;
+                let tweak = Option::<k256::Scalar>::from(k256::Scalar::from_repr(tweak.into()))
+                    .ok_or_else(|| derivation_failed("derived key is out of range"))?;
+                let parent =
+                    Option::<k256::Scalar>::from(k256::Scalar::from_repr(self.secret.into()))
+                        .ok_or_else(|| derivation_failed("derived key is out of range"))?;
+                let child = tweak + parent;
+                if bool::from(child.is_zero()) {
+                    return Err(derivation_failed("derived key is zero"));
+                }
+                child.to_bytes().into()
+            },
+        };
+        Ok(Self {
+            curve: self.curve,
+            secret,
+            chain_code,
+            depth: self.depth.saturating_add(1),
+        })
+    }
+
+    /// Descendant along `path`, taken from this key
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self> {
+        path.indices()
+            .iter()
+            .try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+}
+
+/// Keys derived from one mnemonic along BIP-44 paths
+#[derive(Debug, Clone)]
+pub struct HdWallet {
+    master: ExtendedPrivateKey,
+    coin_type: u32,
+}
+
+impl HdWallet {
+    /// Wallet of a seed
+    pub fn from_seed(seed: &[u8], curve: HdCurve) -> Result<Self> {
+        Ok(Self {
+            master: ExtendedPrivateKey::master(seed, curve)?,
+            coin_type: ETHEREUM_COIN_TYPE,
+        })
+    }
+
+    /// Wallet of a mnemonic and optional passphrase
+    pub fn from_mnemonic(phrase: &str, passphrase: &str, curve: HdCurve) -> Result<Self> {
+        Self::from_seed(&mnemonic_to_seed(phrase, passphrase)?, curve)
+    }
+
+    /// New wallet with a random 24-word mnemonic, returned for backup
+    pub fn generate(curve: HdCurve) -> Result<(Self, String)> {
+        let phrase = generate_mnemonic(24)?;
+        Ok((Self::from_mnemonic(&phrase, "", curve)?, phrase))
+    }
+
+    /// Derive under another SLIP-44 coin type than
+    /// [`ETHEREUM_COIN_TYPE`]
+    pub fn with_coin_type(mut self, coin_type: u32) -> Self {
+        self.coin_type = coin_type;
+        self
+    }
+
+    pub fn master(&self) -> &ExtendedPrivateKey {
+        &self.master
+    }
+
+    /// BIP-44 path of the `index`th receiving address of `account`
+    ///
+    /// Every level is hardened for Ed25519.
+    pub fn path(&self, account: u32, index: u32) -> DerivationPath {
+        let path = DerivationPath::bip44(self.coin_type, account, 0, index);
+        match self.master.curve {
+            HdCurve::Secp256k1 => path,
+            HdCurve::Ed25519 => path.hardened(),
+        }
+    }
+
+    /// Key at an arbitrary path
+    pub fn derive(&self, path: &DerivationPath) -> Result<PrivateKey> {
+        self.master.derive_path(path)?.private_key()
+    }
+
+    /// Key of the `index`th address of the first account
+    pub fn key(&self, index: u32) -> Result<PrivateKey> {
+        self.derive(&self.path(0, index))
+    }
+
+    /// Keys of the first `count` addresses of the first account
+    pub fn keys(&self, count: u32) -> Result<Vec<PrivateKey>> {
+        (0..count).map(|index| self.key(index)).collect()
+    }
+}
@@ -0,0 +1,528 @@
+//! Named key storage with roles
+//!
+//! Validators need long-lived consensus keys that are separate from the key
+//! identifying the node on the network. The [`KeyStore`] keeps named private
+//! keys in a [`Storage`] backend, each tagged with the [`KeyRole`] it may be
+//! used for, so configuration can refer to keys by name and the same key
+//! survives restarts.
+//!
+//! Keys are kept in the clear unless the store has a passphrase
+//! ([`KeyStore::with_passphrase`]): then they are sealed with AES-256-GCM
+//! under a key stretched from the passphrase with scrypt. A [`KeyFile`] holds
+//! one key sealed the same way in a file of its own, for a node identity
+//! that lives outside the node's storage. The async methods stretch the
+//! passphrase on the [`CpuPool`], off the async executor.
+
+use crate::crypto::{ecdsa::ECDSASigner, pool::CpuPool, utils, KeyType, PrivateKey};
+use crate::error::{ChaincraftError, CryptoError, Result};
+use crate::storage::Storage;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+const INDEX_KEY: &str = "keystore:index";
+
+/// What a stored key may be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRole {
+    /// Transport identity of the node
+    Node,
+    /// Signs consensus votes and blocks
+    ValidatorConsensus,
+    /// Produces verifiable random outputs
+    ValidatorVrf,
+}
+
+impl fmt::Display for KeyRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyRole::Node => write!(f, "node"),
+            KeyRole::ValidatorConsensus => write!(f, "validator-consensus"),
+            KeyRole::ValidatorVrf => write!(f, "validator-vrf"),
+        }
+    }
+}
+
+/// A key as persisted in storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredKey {
+    role: KeyRole,
+    key_type: KeyType,
+    /// Hex encoded key, empty when the key is encrypted
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    private_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted: Option<EncryptedSecret>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Cost of stretching a passphrase with scrypt
+///
+/// The defaults take about 32 MiB and a tenth of a second; tests may lower
+/// `log_n`. The parameters are stored with each sealed key, so keys sealed
+/// with different costs can be opened alike, up to [`KdfParams::MAX`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Base two logarithm of the scrypt cost `N`
+    pub log_n: u8,
+    /// Block size
+    pub r: u32,
+    /// Parallelism
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Costliest parameters accepted, about 1 GiB per derivation, so a
+    /// crafted key file cannot make opening it exhaust the machine
+    pub const MAX: KdfParams = KdfParams {
+        log_n: 20,
+        r: 8,
+        p: 4,
+    };
+
+    fn derive_key(&self, passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+        let max = Self::MAX;
+        if self.log_n > max.log_n || self.r > max.r || self.p > max.p {
+            return Err(ChaincraftError::config(format!(
+                "scrypt parameters {:?} exceed {:?}",
+                self, max
+            )));
+        }
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, 32)
+            .map_err(|e| ChaincraftError::config(format!("invalid scrypt parameters: {}", e)))?;
+        let mut key = [0u8; 32];
+        scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key).map_err(|e| {
+            ChaincraftError::Crypto(CryptoError::EncryptionFailed {
+                reason: e.to_string(),
+            })
+        })?;
+        Ok(key)
+    }
+}
+
+/// Secret sealed with AES-256-GCM under a passphrase
+///
+/// The key type and role are authenticated along with the ciphertext, so a
+/// sealed key cannot be relabelled without the passphrase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedSecret {
+    pub kdf: KdfParams,
+    /// Hex encoded scrypt salt
+    pub salt: String,
+    /// Hex encoded AES-GCM nonce
+    pub nonce: String,
+    /// Hex encoded ciphertext and tag
+    pub ciphertext: String,
+}
+
+impl EncryptedSecret {
+    fn seal(secret: &[u8], associated: &str, passphrase: &str, kdf: KdfParams) -> Result<Self> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let cipher = Aes256Gcm::new(&kdf.derive_key(passphrase, &salt)?.into());
+        let payload = Payload {
+            msg: secret,
+            aad: associated.as_bytes(),
+        };
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|e| {
+                ChaincraftError::Crypto(CryptoError::EncryptionFailed {
+                    reason: e.to_string(),
+                })
+            })?;
+        Ok(Self {
+            kdf,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Fails with [`CryptoError::DecryptionFailed`] on a wrong passphrase or
+    /// a tampered secret
+    fn open(&self, associated: &str, passphrase: &str) -> Result<Vec<u8>> {
+        let failed = |reason: &str| {
+            ChaincraftError::Crypto(CryptoError::DecryptionFailed {
+                reason: reason.to_string(),
+            })
+        };
+        let salt = hex::decode(&self.salt).map_err(|_| failed("malformed salt"))?;
+        let nonce: [u8; 12] = hex::decode(&self.nonce)
+            .ok()
+            .and_then(|nonce| nonce.try_into().ok())
+            .ok_or_else(|| failed("malformed nonce"))?;
+        let ciphertext =
+            hex::decode(&self.ciphertext).map_err(|_| failed("malformed ciphertext"))?;
+        let cipher = Aes256Gcm::new(&self.kdf.derive_key(passphrase, &salt)?.into());
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: associated.as_bytes(),
+        };
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| failed("wrong passphrase or corrupted key"))
+    }
+}
+
+/// Data authenticated with a sealed key
+fn associated_data(role: KeyRole, key_type: KeyType) -> String {
+    format!("chaincraft-key:{}:{}", role, key_type.as_str())
+}
+
+/// One private key sealed under a passphrase, stored in a file of its own
+///
+/// The public key is kept in the clear so a file can be identified without
+/// its passphrase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub role: KeyRole,
+    pub key_type: KeyType,
+    /// Hex encoded public key
+    pub public_key: String,
+    pub secret: EncryptedSecret,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl KeyFile {
+    pub const VERSION: u32 = 1;
+
+    /// Seal `key` under `passphrase`
+    pub fn seal(key: &PrivateKey, role: KeyRole, passphrase: &str, kdf: KdfParams) -> Result<Self> {
+        let key_type = key.key_type();
+        let secret = hex::decode(key.to_hex()).map_err(|e| {
+            ChaincraftError::Crypto(CryptoError::EncryptionFailed {
+                reason: e.to_string(),
+            })
+        })?;
+        Ok(Self {
+            version: Self::VERSION,
+            role,
+            key_type,
+            public_key: key.public_key().to_hex(),
+            secret: EncryptedSecret::seal(
+                &secret,
+                &associated_data(role, key_type),
+                passphrase,
+                kdf,
+            )?,
+            created_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Decrypt the key, checking it matches the recorded public key
+    pub fn open(&self, passphrase: &str) -> Result<PrivateKey> {
+        let secret = self
+            .secret
+            .open(&associated_data(self.role, self.key_type), passphrase)?;
+        let key = PrivateKey::from_hex(&hex::encode(secret), self.key_type)?;
+        if key.public_key().to_hex() != self.public_key {
+            return Err(ChaincraftError::Crypto(CryptoError::DecryptionFailed {
+                reason: "key does not match the recorded public key".to_string(),
+            }));
+        }
+        Ok(key)
+    }
+
+    /// Read a key file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn load(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file: Self = serde_json::from_slice(&tokio::fs::read(path).await?)?;
+        if file.version != Self::VERSION {
+            return Err(ChaincraftError::config(format!(
+                "unsupported key file version {}",
+                file.version
+            )));
+        }
+        Ok(file)
+    }
+
+    /// Write the key file, readable by the owner only on Unix
+    ///
+    /// The file is written next to `path` and renamed over it, so an
+    /// interrupted save never leaves a truncated key behind.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn save(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = std::path::PathBuf::from(partial);
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&partial).await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &serde_json::to_vec_pretty(self)?).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&partial, path).await?;
+        Ok(())
+    }
+
+    /// Open the key at `path`, generating and saving a new one when the
+    /// file does not exist yet
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn open_or_generate(
+        path: impl AsRef<std::path::Path>,
+        passphrase: &str,
+        role: KeyRole,
+        key_type: KeyType,
+    ) -> Result<PrivateKey> {
+        let path = path.as_ref();
+        if tokio::fs::try_exists(path).await? {
+            let file = Self::load(path).await?;
+            if file.role != role {
+                return Err(ChaincraftError::config(format!(
+                    "key file {} has role {}, expected {}",
+                    path.display(),
+                    file.role,
+                    role
+                )));
+            }
+            let passphrase = passphrase.to_string();
+            return CpuPool::global()
+                .run(move || file.open(&passphrase))
+                .await?;
+        }
+        let (key, _) = utils::generate_keypair(key_type)?;
+        let (sealed, passphrase) = (key.clone(), passphrase.to_string());
+        CpuPool::global()
+            .run(move || Self::seal(&sealed, role, &passphrase, KdfParams::default()))
+            .await??
+            .save(path)
+            .await?;
+        Ok(key)
+    }
+}
+
+/// Path and passphrase of a [`KeyFile`]
+#[derive(Clone)]
+pub struct KeyFileConfig {
+    pub path: std::path::PathBuf,
+    pub passphrase: String,
+}
+
+impl fmt::Debug for KeyFileConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the passphrase
+        f.debug_struct("KeyFileConfig")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Names of the keys a node uses for each role
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyConfig {
+    /// Key for the node identity
+    pub node: Option<String>,
+    /// Key for signing consensus messages
+    pub consensus: Option<String>,
+    /// Key for VRF outputs
+    pub vrf: Option<String>,
+}
+
+/// Persistent store of named, role-tagged private keys
+#[derive(Clone)]
+pub struct KeyStore {
+    storage: Arc<dyn Storage>,
+    passphrase: Option<Arc<str>>,
+    kdf: KdfParams,
+}
+
+impl fmt::Debug for KeyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyStore").finish_non_exhaustive()
+    }
+}
+
+impl KeyStore {
+    /// Create a key store on top of a storage backend
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            passphrase: None,
+            kdf: KdfParams::default(),
+        }
+    }
+
+    /// Encrypt keys stored from now on under `passphrase`, and decrypt
+    /// encrypted keys with it
+    ///
+    /// Keys stored before in the clear still load.
+    pub fn with_passphrase(mut self, passphrase: impl Into<String>) -> Self {
+        self.passphrase = Some(passphrase.into().into());
+        self
+    }
+
+    /// Stretch the passphrase with these scrypt parameters
+    pub fn with_kdf(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    fn storage_key(name: &str) -> String {
+        format!("keystore:key:{}", name)
+    }
+
+    /// Names of all stored keys
+    pub async fn names(&self) -> Result<Vec<String>> {
+        match self.storage.get(INDEX_KEY).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Stored keys with their roles
+    pub async fn list(&self) -> Result<Vec<(String, KeyRole)>> {
+        let mut keys = Vec::new();
+        for name in self.names().await? {
+            if let Some(stored) = self.load(&name).await? {
+                keys.push((name, stored.role));
+            }
+        }
+        Ok(keys)
+    }
+
+    async fn load(&self, name: &str) -> Result<Option<StoredKey>> {
+        match self.storage.get(&Self::storage_key(name)).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store an existing private key under `name`
+    ///
+    /// Fails if a key with that name already exists.
+    pub async fn import(&self, name: &str, role: KeyRole, key: &PrivateKey) -> Result<()> {
+        if self.load(name).await?.is_some() {
+            return Err(ChaincraftError::config(format!("key '{}' already exists", name)));
+        }
+        let key_type = key.key_type();
+        let mut stored = StoredKey {
+            role,
+            key_type,
+            private_key: key.to_hex(),
+            encrypted: None,
+            created_at: chrono::Utc::now(),
+        };
+        if let Some(passphrase) = self.passphrase.clone() {
+            let secret = hex::decode(std::mem::take(&mut stored.private_key))
+                .map_err(|e| ChaincraftError::config(e.to_string()))?;
+            let kdf = self.kdf;
+            stored.encrypted = Some(
+                CpuPool::global()
+                    .run(move || {
+                        EncryptedSecret::seal(
+                            &secret,
+                            &associated_data(role, key_type),
+                            &passphrase,
+                            kdf,
+                        )
+                    })
+                    .await??,
+            );
+        }
+        self.storage
+            .put(&Self::storage_key(name), serde_json::to_vec(&stored)?)
+            .await?;
+
+        let mut names = self.names().await?;
+        names.push(name.to_string());
+        self.storage
+            .put(INDEX_KEY, serde_json::to_vec(&names)?)
+            .await
+    }
+
+    /// Generate and store a new key
+    pub async fn generate(
+        &self,
+        name: &str,
+        role: KeyRole,
+        key_type: KeyType,
+    ) -> Result<PrivateKey> {
+        let (private_key, _) = utils::generate_keypair(key_type)?;
+        self.import(name, role, &private_key).await?;
+        Ok(private_key)
+    }
+
+    /// Load the key stored under `name`, checking it has the expected role
+    pub async fn get(&self, name: &str, role: KeyRole) -> Result<Option<PrivateKey>> {
+        let Some(stored) = self.load(name).await? else {
+            return Ok(None);
+        };
+        if stored.role != role {
+            return Err(ChaincraftError::config(format!(
+                "key '{}' has role {}, expected {}",
+                name, stored.role, role
+            )));
+        }
+        let Some(encrypted) = stored.encrypted else {
+            return PrivateKey::from_hex(&stored.private_key, stored.key_type).map(Some);
+        };
+        let passphrase = self.passphrase.clone().ok_or_else(|| {
+            ChaincraftError::config(format!("key '{}' is encrypted and no passphrase is set", name))
+        })?;
+        let associated = associated_data(stored.role, stored.key_type);
+        let secret = CpuPool::global()
+            .run(move || encrypted.open(&associated, &passphrase))
+            .await??;
+        PrivateKey::from_hex(&hex::encode(secret), stored.key_type).map(Some)
+    }
+
+    /// Load the key stored under `name`, generating it on first use
+    pub async fn get_or_generate(
+        &self,
+        name: &str,
+        role: KeyRole,
+        key_type: KeyType,
+    ) -> Result<PrivateKey> {
+        match self.get(name, role).await? {
+            Some(key) => Ok(key),
+            None => self.generate(name, role, key_type).await,
+        }
+    }
+
+    /// Signer for the key stored under `name`
+    pub async fn signer(&self, name: &str, role: KeyRole) -> Result<ECDSASigner> {
+        self.get(name, role)
+            .await?
+            .map(ECDSASigner::from_private_key)
+            .ok_or_else(|| ChaincraftError::config(format!("no key named '{}'", name)))
+    }
+
+    /// Delete the key stored under `name`
+    pub async fn remove(&self, name: &str) -> Result<bool> {
+        if self.load(name).await?.is_none() {
+            return Ok(false);
+        }
+        self.storage.delete(&Self::storage_key(name)).await?;
+        let names: Vec<String> = self
+            .names()
+            .await?
+            .into_iter()
+            .filter(|n| n != name)
+            .collect();
+        self.storage
+            .put(INDEX_KEY, serde_json::to_vec(&names)?)
+            .await?;
+        Ok(true)
+    }
+}
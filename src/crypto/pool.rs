@@ -0,0 +1,207 @@
+//! Bounded pool for CPU-bound cryptography
+//!
+//! Mining proof of work, checking a batch of signatures or evaluating a delay
+//! function can keep a core busy for a long time. Running that work on the
+//! async executor starves every other task, so it goes to tokio's blocking
+//! threads instead, through a [`CpuPool`] that admits at most `threads` jobs
+//! at once and queues the rest. The pool also keeps counters on queue depth
+//! and job durations, see [`CpuPoolMetrics`].
+//!
+//! Components use [`CpuPool::global`] unless handed a pool of their own; call
+//! [`CpuPool::configure_global`] early to size it.
+
+use crate::error::{ChaincraftError, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Size of a CPU pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuPoolConfig {
+    /// Jobs allowed to run at the same time
+    pub threads: usize,
+}
+
+impl Default for CpuPoolConfig {
+    fn default() -> Self {
+        Self {
+            threads: num_cpus::get(),
+        }
+    }
+}
+
+impl CpuPoolConfig {
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+        }
+    }
+}
+
+/// Snapshot of a pool's counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuPoolMetrics {
+    pub threads: usize,
+    /// Jobs waiting for a free thread
+    pub queued: u64,
+    /// Jobs currently running
+    pub running: u64,
+    /// Jobs finished since the pool was created
+    pub completed: u64,
+    /// Time spent running finished jobs
+    pub busy: Duration,
+    /// Time finished jobs spent waiting for a thread
+    pub waited: Duration,
+    /// Longest single job
+    pub longest: Duration,
+}
+
+impl CpuPoolMetrics {
+    /// Average run time of a finished job
+    pub fn mean_duration(&self) -> Option<Duration> {
+        (self.completed > 0).then(|| self.busy / self.completed as u32)
+    }
+}
+
+#[derive(Debug)]
+struct PoolState {
+    threads: usize,
+    permits: Semaphore,
+    queued: AtomicU64,
+    running: AtomicU64,
+    completed: AtomicU64,
+    busy_micros: AtomicU64,
+    waited_micros: AtomicU64,
+    longest_micros: AtomicU64,
+}
+
+/// Runs CPU-bound jobs off the async executor, a bounded number at a time
+///
+/// Cloning is cheap and clones share the same threads and counters.
+#[derive(Debug, Clone)]
+pub struct CpuPool {
+    state: Arc<PoolState>,
+}
+
+static GLOBAL_POOL: OnceLock<CpuPool> = OnceLock::new();
+
+impl Default for CpuPool {
+    fn default() -> Self {
+        Self::new(CpuPoolConfig::default())
+    }
+}
+
+impl CpuPool {
+    pub fn new(config: CpuPoolConfig) -> Self {
+        let threads = config.threads.max(1);
+        Self {
+            state: Arc::new(PoolState {
+                threads,
+                permits: Semaphore::new(threads),
+                queued: AtomicU64::new(0),
+                running: AtomicU64::new(0),
+                completed: AtomicU64::new(0),
+                busy_micros: AtomicU64::new(0),
+                waited_micros: AtomicU64::new(0),
+                longest_micros: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Process-wide pool, sized to the number of cores unless configured
+    pub fn global() -> &'static CpuPool {
+        GLOBAL_POOL.get_or_init(CpuPool::default)
+    }
+
+    /// Size the process-wide pool; fails once it has been used or configured
+    pub fn configure_global(config: CpuPoolConfig) -> Result<()> {
+        GLOBAL_POOL
+            .set(CpuPool::new(config))
+            .map_err(|_| ChaincraftError::config("global CPU pool is already initialized"))
+    }
+
+    /// Jobs allowed to run at the same time
+    pub fn threads(&self) -> usize {
+        self.state.threads
+    }
+
+    /// Run `job` on a blocking thread once one of the pool's slots is free
+    pub async fn run<F, R>(&self, job: F) -> Result<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let state = &self.state;
+        let enqueued = Instant::now();
+        state.queued.fetch_add(1, Ordering::Relaxed);
+        let permit = state.permits.acquire().await;
+        state.queued.fetch_sub(1, Ordering::Relaxed);
+        let _permit =
+            permit.map_err(|_| ChaincraftError::Generic("CPU pool is closed".to_string()))?;
+        let waited = enqueued.elapsed();
+
+        state.running.fetch_add(1, Ordering::Relaxed);
+        let result = tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+            let output = job();
+            (output, started.elapsed())
+        })
+        .await;
+        state.running.fetch_sub(1, Ordering::Relaxed);
+
+        let (output, took) =
+            result.map_err(|e| ChaincraftError::Generic(format!("CPU job failed: {}", e)))?;
+        let micros = |d: Duration| d.as_micros().min(u64::MAX as u128) as u64;
+        state.completed.fetch_add(1, Ordering::Relaxed);
+        state.busy_micros.fetch_add(micros(took), Ordering::Relaxed);
+        state
+            .waited_micros
+            .fetch_add(micros(waited), Ordering::Relaxed);
+        state
+            .longest_micros
+            .fetch_max(micros(took), Ordering::Relaxed);
+        Ok(output)
+    }
+
+    /// Run `job` over `items` split into one chunk per thread, keeping order
+    pub async fn map_chunks<T, R, F>(&self, items: Vec<T>, job: F) -> Result<Vec<R>>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+        let chunk_size = items.len().div_ceil(self.threads());
+        let job = Arc::new(job);
+        let mut chunks = Vec::new();
+        let mut items = items.into_iter().peekable();
+        while items.peek().is_some() {
+            let chunk: Vec<T> = items.by_ref().take(chunk_size).collect();
+            let job = job.clone();
+            chunks.push(self.run(move || chunk.into_iter().map(|item| job(item)).collect()));
+        }
+
+        let mut results = Vec::new();
+        for chunk in futures::future::join_all(chunks).await {
+            let chunk: Vec<R> = chunk?;
+            results.extend(chunk);
+        }
+        Ok(results)
+    }
+
+    pub fn metrics(&self) -> CpuPoolMetrics {
+        let state = &self.state;
+        CpuPoolMetrics {
+            threads: state.threads,
+            queued: state.queued.load(Ordering::Relaxed),
+            running: state.running.load(Ordering::Relaxed),
+            completed: state.completed.load(Ordering::Relaxed),
+            busy: Duration::from_micros(state.busy_micros.load(Ordering::Relaxed)),
+            waited: Duration::from_micros(state.waited_micros.load(Ordering::Relaxed)),
+            longest: Duration::from_micros(state.longest_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
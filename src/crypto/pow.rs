@@ -1,13 +1,17 @@
 //! Proof of Work implementation
 
+use crate::codec::consensus::ConsensusHasher;
+use crate::crypto::pool::CpuPool;
 use crate::crypto::KeylessCryptoPrimitive;
 use crate::error::{ChaincraftError, CryptoError, Result};
+use crate::shared::{MessageType, SharedMessage};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::task;
 
 /// Proof of Work parameters and configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,40 +60,45 @@ impl PoWProof {
 }
 
 /// High-performance Proof of Work implementation
+///
+/// Mining and verification run on a [`CpuPool`], the global one by default.
 #[derive(Debug, Clone)]
 pub struct ProofOfWork {
     config: ProofOfWorkConfig,
+    pool: CpuPool,
 }
 
 impl ProofOfWork {
     /// Create a new Proof of Work instance with default configuration
     pub fn new() -> Self {
-        Self {
-            config: ProofOfWorkConfig::default(),
-        }
+        Self::with_config(ProofOfWorkConfig::default())
     }
 
     /// Create a new Proof of Work instance with custom configuration
     pub fn with_config(config: ProofOfWorkConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            pool: CpuPool::global().clone(),
+        }
     }
 
     /// Create a simple PoW with specified difficulty
     pub fn with_difficulty(difficulty: u32) -> Self {
-        Self {
-            config: ProofOfWorkConfig {
-                difficulty,
-                ..ProofOfWorkConfig::default()
-            },
-        }
+        Self::with_config(ProofOfWorkConfig {
+            difficulty,
+            ..ProofOfWorkConfig::default()
+        })
+    }
+
+    /// Mine and verify on `pool` instead of the global pool
+    pub fn with_pool(mut self, pool: CpuPool) -> Self {
+        self.pool = pool;
+        self
     }
 
     /// Calculate hash for given data and nonce
     fn calculate_hash(data: &str, nonce: u64) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        hasher.update(nonce.to_le_bytes());
-        hex::encode(hasher.finalize())
+        ConsensusHasher::new().str(data).u64(nonce).finish_hex()
     }
 
     /// Check if hash meets difficulty requirement
@@ -98,7 +107,7 @@ impl ProofOfWork {
     }
 
     /// Mine a single block using CPU-bound work
-    async fn mine_worker(
+    fn mine_worker(
         data: String,
         difficulty: u32,
         start_nonce: u64,
@@ -106,8 +115,8 @@ impl ProofOfWork {
         max_nonce: u64,
         should_stop: Arc<AtomicBool>,
         best_nonce: Arc<AtomicU64>,
-    ) -> Option<PoWProof> {
-        task::spawn_blocking(move || {
+    ) -> impl FnOnce() -> Option<PoWProof> {
+        move || {
             let mut nonce = start_nonce;
 
             while nonce < max_nonce && !should_stop.load(Ordering::Relaxed) {
@@ -129,9 +138,7 @@ impl ProofOfWork {
             }
 
             None
-        })
-        .await
-        .unwrap_or(None)
+        }
     }
 
     /// Verify proof efficiently
@@ -171,6 +178,265 @@ impl Default for ProofOfWork {
     }
 }
 
+/// Most leading zero hex digits a SHA-256 hash can have
+pub const MAX_DIFFICULTY: u32 = 64;
+
+/// How a [`DifficultyAdjuster`] reacts to block times
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+pub enum RetargetAlgorithm {
+    /// Bitcoin-style: every `interval` blocks, compare how long the last
+    /// `interval` blocks took with the target and move at most one digit
+    Window { interval: u64 },
+    /// ASERT: every block, move the difficulty by how far the chain is ahead
+    /// of or behind the schedule set at an anchor block, one hex digit per
+    /// four half-lives
+    Asert {
+        anchor_height: u64,
+        half_life_secs: u64,
+    },
+}
+
+/// Height, time and difficulty of a block, all retargeting looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockTiming {
+    pub height: u64,
+    pub timestamp: DateTime<Utc>,
+    pub difficulty: u32,
+}
+
+/// Difficulty retargeting towards a target block time
+///
+/// Difficulty counts leading zero hex digits, so one step multiplies the
+/// expected work by 16. The adjuster only takes a step once block times are
+/// off by 4x or more, the geometric middle of a step; smaller deviations are
+/// closer to the current difficulty than to either neighbour.
+///
+/// Callers ask [`Self::reference_height`] which earlier block a child of
+/// `parent` is measured against, then pass both to
+/// [`Self::next_difficulty`]. Everything is integer arithmetic, so every node
+/// computes the same difficulty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DifficultyAdjuster {
+    pub target_block_time_secs: u64,
+    pub algorithm: RetargetAlgorithm,
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+}
+
+impl DifficultyAdjuster {
+    /// Retarget every `interval` blocks, Bitcoin-style
+    pub fn window(target_block_time_secs: u64, interval: u64) -> Self {
+        Self::new(
+            target_block_time_secs,
+            RetargetAlgorithm::Window {
+                interval: interval.max(1),
+            },
+        )
+    }
+
+    /// Retarget every block against the schedule set at `anchor_height`
+    pub fn asert(target_block_time_secs: u64, anchor_height: u64, half_life_secs: u64) -> Self {
+        Self::new(
+            target_block_time_secs,
+            RetargetAlgorithm::Asert {
+                anchor_height,
+                half_life_secs: half_life_secs.max(1),
+            },
+        )
+    }
+
+    fn new(target_block_time_secs: u64, algorithm: RetargetAlgorithm) -> Self {
+        Self {
+            target_block_time_secs,
+            algorithm,
+            min_difficulty: 1,
+            max_difficulty: MAX_DIFFICULTY,
+        }
+    }
+
+    /// Keep the difficulty within `min..=max`
+    pub fn with_bounds(mut self, min: u32, max: u32) -> Self {
+        self.min_difficulty = min;
+        self.max_difficulty = max.max(min);
+        self
+    }
+
+    /// Height of the block a child of the block at `parent_height` is
+    /// measured against, or `None` if it keeps its parent's difficulty
+    ///
+    /// Windows never start at genesis, whose timestamp is usually fixed
+    /// rather than mined.
+    pub fn reference_height(&self, parent_height: u64) -> Option<u64> {
+        match self.algorithm {
+            RetargetAlgorithm::Window { interval } => {
+                let height = parent_height + 1;
+                (height % interval == 0 && parent_height > interval)
+                    .then(|| parent_height - interval)
+            },
+            RetargetAlgorithm::Asert { anchor_height, .. } => {
+                (parent_height >= anchor_height).then_some(anchor_height)
+            },
+        }
+    }
+
+    /// Difficulty of the child of `parent`, measured against `reference`,
+    /// the block at [`Self::reference_height`]
+    pub fn next_difficulty(&self, parent: &BlockTiming, reference: &BlockTiming) -> u32 {
+        let target = self.target_block_time_secs as i64;
+        let elapsed = (parent.timestamp - reference.timestamp).num_seconds();
+        let blocks = parent.height.saturating_sub(reference.height) as i64;
+        let difficulty = match self.algorithm {
+            RetargetAlgorithm::Window { .. } => {
+                let actual = elapsed.max(0);
+                let expected = target * blocks;
+                if actual * 4 <= expected {
+                    parent.difficulty as i64 + 1
+                } else if actual >= expected * 4 {
+                    parent.difficulty as i64 - 1
+                } else {
+                    parent.difficulty as i64
+                }
+            },
+            RetargetAlgorithm::Asert { half_life_secs, .. } => {
+                // Seconds behind schedule; a digit is 16x, four half-lives
+                let behind = elapsed - target * blocks;
+                let step = 4 * half_life_secs as i64;
+                reference.difficulty as i64 - (2 * behind + step).div_euclid(2 * step)
+            },
+        };
+        difficulty.clamp(self.min_difficulty as i64, self.max_difficulty as i64) as u32
+    }
+}
+
+/// Highest difficulty a [`PowPolicy`] may require, about four billion hashes
+///
+/// Message work is solved by every sender on every message, so anything
+/// costlier is a denial of service on the senders rather than spam control.
+pub const MAX_MESSAGE_POW_DIFFICULTY: u32 = 8;
+
+/// Proof of work required on messages, by message type
+///
+/// Used as an anti-spam captcha: a node with a policy only processes messages
+/// whose `pow_nonce` solves a puzzle over the message hash. A difficulty of 0
+/// means no work is required; at most [`MAX_MESSAGE_POW_DIFFICULTY`] is
+/// accepted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowPolicy {
+    /// Difficulty for message types without an override
+    pub default_difficulty: u32,
+    /// Difficulty overrides keyed by message type name
+    pub per_type: HashMap<String, u32>,
+}
+
+impl PowPolicy {
+    /// Require `difficulty` on every message
+    pub fn new(default_difficulty: u32) -> Result<Self> {
+        check_message_difficulty(default_difficulty)?;
+        Ok(Self {
+            default_difficulty,
+            per_type: HashMap::new(),
+        })
+    }
+
+    /// Override the difficulty for one message type
+    pub fn with_type_difficulty(
+        mut self,
+        message_type: MessageType,
+        difficulty: u32,
+    ) -> Result<Self> {
+        check_message_difficulty(difficulty)?;
+        self.per_type.insert(message_type.to_string(), difficulty);
+        Ok(self)
+    }
+
+    /// Check every difficulty is within [`MAX_MESSAGE_POW_DIFFICULTY`], for
+    /// policies built field by field or deserialized
+    pub fn validate(&self) -> Result<()> {
+        check_message_difficulty(self.default_difficulty)?;
+        self.per_type
+            .values()
+            .try_for_each(|difficulty| check_message_difficulty(*difficulty))
+    }
+
+    /// Difficulty required for a message type
+    pub fn difficulty_for(&self, message_type: &MessageType) -> u32 {
+        self.per_type
+            .get(&message_type.to_string())
+            .copied()
+            .unwrap_or(self.default_difficulty)
+    }
+
+    /// Whether a message carries enough work for its type
+    pub fn is_satisfied_by(&self, message: &SharedMessage) -> bool {
+        verify_message_pow(message, self.difficulty_for(&message.message_type))
+    }
+}
+
+fn check_message_difficulty(difficulty: u32) -> Result<()> {
+    if difficulty > MAX_MESSAGE_POW_DIFFICULTY {
+        return Err(ChaincraftError::config(format!(
+            "message proof of work difficulty {} is above {}",
+            difficulty, MAX_MESSAGE_POW_DIFFICULTY
+        )));
+    }
+    Ok(())
+}
+
+/// Solve the puzzle over the message hash and store the nonce in the message
+///
+/// The nonce is covered by the signature, so attach work before signing.
+pub fn attach_pow(message: &mut SharedMessage, difficulty: u32) -> Result<()> {
+    if difficulty == 0 {
+        return Ok(());
+    }
+    message.pow_nonce = Some(solve_message_pow(&message.hash, difficulty)?);
+    Ok(())
+}
+
+/// [`attach_pow`] with the puzzle solved on `pool`, off the async executor
+pub async fn attach_pow_on(
+    pool: &CpuPool,
+    message: &mut SharedMessage,
+    difficulty: u32,
+) -> Result<()> {
+    if difficulty == 0 {
+        return Ok(());
+    }
+    let hash = message.hash.clone();
+    message.pow_nonce = Some(
+        pool.run(move || solve_message_pow(&hash, difficulty))
+            .await??,
+    );
+    Ok(())
+}
+
+/// Search 64 times the expected number of nonces, giving up on a puzzle that
+/// is too hard or, with odds of `e^-64`, merely unlucky
+fn solve_message_pow(hash: &str, difficulty: u32) -> Result<u64> {
+    check_message_difficulty(difficulty)?;
+    let attempts = 16u64.pow(difficulty).saturating_mul(64);
+    (0..attempts)
+        .find(|nonce| {
+            ProofOfWork::meets_difficulty(&ProofOfWork::calculate_hash(hash, *nonce), difficulty)
+        })
+        .ok_or(ChaincraftError::Crypto(CryptoError::ProofOfWorkFailed))
+}
+
+/// Check that a message's `pow_nonce` meets `difficulty`
+pub fn verify_message_pow(message: &SharedMessage, difficulty: u32) -> bool {
+    if difficulty == 0 {
+        return true;
+    }
+    match message.pow_nonce {
+        Some(nonce) => ProofOfWork::meets_difficulty(
+            &ProofOfWork::calculate_hash(&message.hash, nonce),
+            difficulty,
+        ),
+        None => false,
+    }
+}
+
 #[async_trait]
 impl KeylessCryptoPrimitive for ProofOfWork {
     type Input = String;
@@ -190,7 +456,8 @@ impl KeylessCryptoPrimitive for ProofOfWork {
         let best_nonce = Arc::new(AtomicU64::new(0));
         let mut handles = Vec::new();
 
-        let threads = self.config.threads;
+        // Workers beyond the pool size would only queue behind the others
+        let threads = self.config.threads.min(self.pool.threads()).max(1);
         let nonce_step = threads as u64;
 
         // Spawn multiple worker tasks for parallel mining
@@ -202,7 +469,7 @@ impl KeylessCryptoPrimitive for ProofOfWork {
             let should_stop_clone = should_stop.clone();
             let best_nonce_clone = best_nonce.clone();
 
-            let handle = task::spawn(Self::mine_worker(
+            let worker = Self::mine_worker(
                 data,
                 difficulty,
                 start_nonce,
@@ -210,7 +477,9 @@ impl KeylessCryptoPrimitive for ProofOfWork {
                 max_nonce,
                 should_stop_clone,
                 best_nonce_clone,
-            ));
+            );
+            let pool = self.pool.clone();
+            let handle = tokio::spawn(async move { pool.run(worker).await.unwrap_or(None) });
 
             handles.push(handle);
         }
@@ -229,15 +498,15 @@ impl KeylessCryptoPrimitive for ProofOfWork {
     async fn verify_proof(&self, challenge: Self::Challenge, proof: Self::Proof) -> Result<bool> {
         // Verification is fast, but we'll make it async for consistency
         let difficulty = self.config.difficulty;
-        task::spawn_blocking(move || {
-            let calculated_hash = Self::calculate_hash(&challenge.data, proof.nonce);
-            if calculated_hash != proof.hash {
-                return false;
-            }
-            Self::meets_difficulty(&proof.hash, difficulty)
-        })
-        .await
-        .map_err(|_| ChaincraftError::Generic("Task join error".to_string()))
+        self.pool
+            .run(move || {
+                let calculated_hash = Self::calculate_hash(&challenge.data, proof.nonce);
+                if calculated_hash != proof.hash {
+                    return false;
+                }
+                Self::meets_difficulty(&proof.hash, difficulty)
+            })
+            .await
     }
 }
 
@@ -0,0 +1,229 @@
+//! Delegated signing
+//!
+//! A validator does not have to hold its consensus key in the node process.
+//! Anything that signs consensus payloads goes through a [`RemoteSigner`]:
+//! [`LocalSigner`] wraps an in-process key, while [`SocketSigner`] forwards
+//! each payload to a separate signing process (an HSM bridge, a KMS proxy, a
+//! hardened host) over TCP and only ever sees the public key. Signatures
+//! coming back from a remote signer are checked against the expected public
+//! key before they are used.
+//!
+//! The socket protocol is one JSON object per line: the node sends
+//! `{"payload": "<hex>"}` and the signer answers `{"signature": "<hex>"}` or
+//! `{"error": "<reason>"}`. [`serve_signer`] implements the signer side.
+
+use crate::crypto::{KeyType, PrivateKey, PublicKey, Signature};
+use crate::error::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Signs payloads with a key that may live outside the node
+#[async_trait]
+pub trait RemoteSigner: Send + Sync + fmt::Debug {
+    /// Public key the signatures verify against
+    fn public_key(&self) -> &PublicKey;
+
+    /// Sign a payload
+    async fn sign(&self, payload: &[u8]) -> Result<Signature>;
+}
+
+/// Signer backed by a private key held in this process
+#[derive(Clone)]
+pub struct LocalSigner {
+    private_key: PrivateKey,
+    public_key: PublicKey,
+}
+
+impl LocalSigner {
+    pub fn new(private_key: PrivateKey) -> Self {
+        let public_key = private_key.public_key();
+        Self {
+            private_key,
+            public_key,
+        }
+    }
+}
+
+impl fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the private key
+        f.debug_struct("LocalSigner")
+            .field("public_key", &self.public_key.to_hex())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl RemoteSigner for LocalSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    async fn sign(&self, payload: &[u8]) -> Result<Signature> {
+        self.private_key.sign(payload)
+    }
+}
+
+/// Request sent to a socket signer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignRequest {
+    /// Hex encoded payload
+    pub payload: String,
+}
+
+/// Response from a socket signer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignResponse {
+    /// Hex encoded signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn key_type_of(public_key: &PublicKey) -> KeyType {
+    public_key.key_type()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use socket::{serve_signer, SocketSigner};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod socket {
+    use super::*;
+    use crate::error::{ChaincraftError, CryptoError, NetworkError};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Signer reached over a TCP socket
+    #[derive(Debug, Clone)]
+    pub struct SocketSigner {
+        addr: SocketAddr,
+        public_key: PublicKey,
+        timeout: Duration,
+    }
+
+    impl SocketSigner {
+        /// Signer listening on `addr` that signs with the key behind `public_key`
+        pub fn new(addr: SocketAddr, public_key: PublicKey) -> Self {
+            Self {
+                addr,
+                public_key,
+                timeout: Duration::from_secs(5),
+            }
+        }
+
+        /// Give up on a signing request after `timeout`
+        pub fn with_timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+
+        /// Address of the signing process
+        pub fn addr(&self) -> SocketAddr {
+            self.addr
+        }
+
+        async fn request(&self, payload: &[u8]) -> Result<SignResponse> {
+            let stream = TcpStream::connect(self.addr).await.map_err(|source| {
+                NetworkError::ConnectionFailed {
+                    addr: self.addr,
+                    source,
+                }
+            })?;
+            let (reader, mut writer) = stream.into_split();
+
+            let mut line = serde_json::to_string(&SignRequest {
+                payload: hex::encode(payload),
+            })?;
+            line.push('\n');
+            writer.write_all(line.as_bytes()).await?;
+
+            let mut response = String::new();
+            BufReader::new(reader).read_line(&mut response).await?;
+            serde_json::from_str(&response).map_err(|e| {
+                ChaincraftError::Network(NetworkError::InvalidMessage {
+                    reason: format!("bad signer response: {}", e),
+                })
+            })
+        }
+    }
+
+    #[async_trait]
+    impl RemoteSigner for SocketSigner {
+        fn public_key(&self) -> &PublicKey {
+            &self.public_key
+        }
+
+        async fn sign(&self, payload: &[u8]) -> Result<Signature> {
+            let response = tokio::time::timeout(self.timeout, self.request(payload))
+                .await
+                .map_err(|_| NetworkError::Timeout {
+                    duration: self.timeout,
+                })??;
+
+            if let Some(reason) = response.error {
+                return Err(NetworkError::RpcFailed { reason }.into());
+            }
+            let hex_signature = response.signature.ok_or_else(|| NetworkError::RpcFailed {
+                reason: "signer returned no signature".to_string(),
+            })?;
+            let signature = Signature::from_hex(&hex_signature, key_type_of(&self.public_key))?;
+            // Don't trust the signer to have used the expected key
+            if !self.public_key.verify(payload, &signature)? {
+                return Err(ChaincraftError::Crypto(CryptoError::InvalidSignature));
+            }
+            Ok(signature)
+        }
+    }
+
+    /// Answer signing requests on `listener` until it fails
+    pub async fn serve_signer(listener: TcpListener, signer: Arc<dyn RemoteSigner>) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let signer = signer.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, signer.as_ref()).await {
+                    tracing::debug!("Signer connection failed: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(stream: TcpStream, signer: &dyn RemoteSigner) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str::<SignRequest>(&line) {
+                Ok(request) => match hex::decode(&request.payload) {
+                    Ok(payload) => match signer.sign(&payload).await {
+                        Ok(signature) => SignResponse {
+                            signature: Some(signature.to_hex()),
+                            error: None,
+                        },
+                        Err(e) => SignResponse {
+                            signature: None,
+                            error: Some(e.to_string()),
+                        },
+                    },
+                    Err(e) => SignResponse {
+                        signature: None,
+                        error: Some(format!("payload is not hex: {}", e)),
+                    },
+                },
+                Err(e) => SignResponse {
+                    signature: None,
+                    error: Some(format!("malformed request: {}", e)),
+                },
+            };
+            let mut out = serde_json::to_string(&response)?;
+            out.push('\n');
+            writer.write_all(out.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,32 @@
 //! Verifiable Random Function implementation
+//!
+//! An elliptic curve VRF after RFC 9381: the holder of a secret key maps any
+//! input to an output no one else can predict, together with a proof anyone
+//! holding the public key can check. The output is fixed by the key and the
+//! input, so unlike a signature the prover cannot grind for a favourable one.
+//!
+//! Ed25519 keys use the ECVRF-EDWARDS25519-SHA512-TAI suite of RFC 9381
+//! (80-byte proofs, 64-byte outputs). Secp256k1 keys, for which the RFC
+//! defines no suite, use the same try-and-increment construction with SHA-256
+//! and suite byte `0xFE` (81-byte proofs, 32-byte outputs).
 
-/// Placeholder VRF implementation
+use crate::crypto::{PrivateKey, PublicKey};
+use crate::error::{ChaincraftError, CryptoError, Result};
+
+/// Domain separators of RFC 9381, section 5
+const ENCODE_TO_CURVE_FRONT: u8 = 0x01;
+const CHALLENGE_FRONT: u8 = 0x02;
+const PROOF_TO_HASH_FRONT: u8 = 0x03;
+const BACK: u8 = 0x00;
+
+/// Length of the challenge in a proof
+const CHALLENGE_LEN: usize = 16;
+
+fn failed() -> ChaincraftError {
+    ChaincraftError::Crypto(CryptoError::VrfVerificationFailed)
+}
+
+/// ECVRF prover and verifier over the crate's key types
 #[derive(Debug, Clone)]
 pub struct VerifiableRandomFunction;
 
@@ -8,6 +34,56 @@ impl VerifiableRandomFunction {
     pub fn new() -> Self {
         Self
     }
+
+    /// Proof of the VRF output on `alpha` under `key`
+    pub fn prove(key: &PrivateKey, alpha: &[u8]) -> Result<Vec<u8>> {
+        match key {
+            PrivateKey::Ed25519(key) => edwards25519::prove(key, alpha),
+            PrivateKey::Secp256k1(key) => secp256k1::prove(key, alpha),
+            // BLS signatures are unique too, but no VRF suite is defined on them
+            PrivateKey::Bls12_381(_) => {
+                Err(ChaincraftError::Crypto(CryptoError::InvalidPrivateKey {
+                    reason: "no VRF over BLS12-381 keys".to_string(),
+                }))
+            },
+        }
+    }
+
+    /// Output a proof commits to, without checking it
+    pub fn proof_to_hash(proof: &[u8]) -> Result<Vec<u8>> {
+        match proof.len() {
+            edwards25519::PROOF_LEN => edwards25519::proof_to_hash(proof),
+            secp256k1::PROOF_LEN => secp256k1::proof_to_hash(proof),
+            _ => Err(failed()),
+        }
+    }
+
+    /// Check `proof` for `alpha` under `key` and return the output
+    ///
+    /// Fails with [`CryptoError::VrfVerificationFailed`] when the proof was
+    /// not made with the secret key behind `key` for this input.
+    pub fn verify(key: &PublicKey, alpha: &[u8], proof: &[u8]) -> Result<Vec<u8>> {
+        match key {
+            PublicKey::Ed25519(key) => edwards25519::verify(key, alpha, proof),
+            PublicKey::Secp256k1(key) => secp256k1::verify(key, alpha, proof),
+            PublicKey::Bls12_381(_) => Err(failed()),
+        }
+    }
+
+    /// Hex encoded proof and output on `alpha` under `key`
+    pub fn evaluate_hex(key: &PrivateKey, alpha: &[u8]) -> Result<(String, String)> {
+        let proof = Self::prove(key, alpha)?;
+        let output = Self::proof_to_hash(&proof)?;
+        Ok((hex::encode(proof), hex::encode(output)))
+    }
+
+    /// Whether hex encoded `proof` and `output` are the evaluation on `alpha` under `key`
+    pub fn is_evaluation_hex(key: &PublicKey, alpha: &[u8], proof: &str, output: &str) -> bool {
+        let Ok(proof) = hex::decode(proof) else {
+            return false;
+        };
+        Self::verify(key, alpha, &proof).is_ok_and(|beta| hex::encode(beta) == output)
+    }
 }
 
 impl Default for VerifiableRandomFunction {
@@ -15,3 +91,243 @@ impl Default for VerifiableRandomFunction {
         Self::new()
     }
 }
+
+/// ECVRF-EDWARDS25519-SHA512-TAI (RFC 9381, section 5.5)
+mod edwards25519 {
+    use super::*;
+    use curve25519_dalek::{
+        edwards::{CompressedEdwardsY, EdwardsPoint},
+        scalar::{clamp_integer, Scalar},
+    };
+    use sha2::{Digest, Sha512};
+
+    const SUITE: u8 = 0x03;
+    pub const PROOF_LEN: usize = 32 + CHALLENGE_LEN + 32;
+
+    /// Hash to a point of the prime order subgroup by try-and-increment
+    fn encode_to_curve(public: &[u8; 32], alpha: &[u8]) -> Result<EdwardsPoint> {
+        for ctr in 0..=u8::MAX {
+            let hash = Sha512::new()
+                .chain_update([SUITE, ENCODE_TO_CURVE_FRONT])
+                .chain_update(public)
+                .chain_update(alpha)
+                .chain_update([ctr, BACK])
+                .finalize();
+            let mut candidate = [0u8; 32];
+            candidate.copy_from_slice(&hash[..32]);
+            if let Some(point) = CompressedEdwardsY(candidate).decompress() {
+                return Ok(point.mul_by_cofactor());
+            }
+        }
+        Err(failed())
+    }
+
+    fn challenge(points: [&EdwardsPoint; 5]) -> [u8; CHALLENGE_LEN] {
+        let mut hasher = Sha512::new().chain_update([SUITE, CHALLENGE_FRONT]);
+        for point in points {
+            hasher.update(point.compress().as_bytes());
+        }
+        let hash = hasher.chain_update([BACK]).finalize();
+        let mut c = [0u8; CHALLENGE_LEN];
+        c.copy_from_slice(&hash[..CHALLENGE_LEN]);
+        c
+    }
+
+    fn challenge_scalar(c: &[u8]) -> Scalar {
+        let mut bytes = [0u8; 32];
+        bytes[..CHALLENGE_LEN].copy_from_slice(c);
+        Scalar::from_bytes_mod_order(bytes)
+    }
+
+    pub fn prove(key: &ed25519_dalek::SigningKey, alpha: &[u8]) -> Result<Vec<u8>> {
+        let expanded = Sha512::digest(key.as_bytes());
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&expanded[..32]);
+        let x = Scalar::from_bytes_mod_order(clamp_integer(secret));
+        let public = key.verifying_key().to_bytes();
+        let y = EdwardsPoint::mul_base(&x);
+
+        let h = encode_to_curve(&public, alpha)?;
+        let gamma = x * h;
+        // Nonce as in RFC 8032, section 5.1.6
+        let k = Scalar::from_hash(
+            Sha512::new()
+                .chain_update(&expanded[32..])
+                .chain_update(h.compress().as_bytes()),
+        );
+        let c = challenge([&y, &h, &gamma, &EdwardsPoint::mul_base(&k), &(k * h)]);
+        let s = k + challenge_scalar(&c) * x;
+
+        let mut proof = Vec::with_capacity(PROOF_LEN);
+        proof.extend_from_slice(gamma.compress().as_bytes());
+        proof.extend_from_slice(&c);
+        proof.extend_from_slice(s.as_bytes());
+        Ok(proof)
+    }
+
+    fn decode_proof(proof: &[u8]) -> Result<(EdwardsPoint, &[u8], Scalar)> {
+        if proof.len() != PROOF_LEN {
+            return Err(failed());
+        }
+        let gamma = CompressedEdwardsY::from_slice(&proof[..32])
+            .ok()
+            .and_then(|point| point.decompress())
+            .ok_or_else(failed)?;
+        let c = &proof[32..32 + CHALLENGE_LEN];
+        let mut s = [0u8; 32];
+        s.copy_from_slice(&proof[32 + CHALLENGE_LEN..]);
+        let s = Option::from(Scalar::from_canonical_bytes(s)).ok_or_else(failed)?;
+        Ok((gamma, c, s))
+    }
+
+    pub fn proof_to_hash(proof: &[u8]) -> Result<Vec<u8>> {
+        let (gamma, _, _) = decode_proof(proof)?;
+        Ok(Sha512::new()
+            .chain_update([SUITE, PROOF_TO_HASH_FRONT])
+            .chain_update(gamma.mul_by_cofactor().compress().as_bytes())
+            .chain_update([BACK])
+            .finalize()
+            .to_vec())
+    }
+
+    pub fn verify(
+        key: &ed25519_dalek::VerifyingKey,
+        alpha: &[u8],
+        proof: &[u8],
+    ) -> Result<Vec<u8>> {
+        let public = key.to_bytes();
+        let y = CompressedEdwardsY(public).decompress().ok_or_else(failed)?;
+        if y.is_small_order() {
+            return Err(failed());
+        }
+        let (gamma, c, s) = decode_proof(proof)?;
+        let h = encode_to_curve(&public, alpha)?;
+        let c_scalar = challenge_scalar(c);
+        let u = EdwardsPoint::mul_base(&s) - c_scalar * y;
+        let v = s * h - c_scalar * gamma;
+        if challenge([&y, &h, &gamma, &u, &v]) != c {
+            return Err(failed());
+        }
+        proof_to_hash(proof)
+    }
+}
+
+/// Try-and-increment ECVRF over secp256k1 with SHA-256
+mod secp256k1 {
+    use super::*;
+    use k256::{
+        elliptic_curve::{
+            ops::Reduce,
+            sec1::{FromEncodedPoint, ToEncodedPoint},
+            PrimeField,
+        },
+        AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar, U256,
+    };
+    use sha2::{Digest, Sha256};
+
+    const SUITE: u8 = 0xFE;
+    pub const PROOF_LEN: usize = 33 + CHALLENGE_LEN + 32;
+
+    fn encode(point: &ProjectivePoint) -> Vec<u8> {
+        point.to_affine().to_encoded_point(true).as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<ProjectivePoint> {
+        let encoded = EncodedPoint::from_bytes(bytes).ok()?;
+        Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded))
+            .map(ProjectivePoint::from)
+    }
+
+    /// Hash to a point by try-and-increment, reading the hash as an x-coordinate
+    fn encode_to_curve(public: &[u8], alpha: &[u8]) -> Result<ProjectivePoint> {
+        for ctr in 0..=u8::MAX {
+            let hash = Sha256::new()
+                .chain_update([SUITE, ENCODE_TO_CURVE_FRONT])
+                .chain_update(public)
+                .chain_update(alpha)
+                .chain_update([ctr, BACK])
+                .finalize();
+            let mut candidate = vec![0x02];
+            candidate.extend_from_slice(&hash);
+            if let Some(point) = decode(&candidate) {
+                return Ok(point);
+            }
+        }
+        Err(failed())
+    }
+
+    fn challenge(points: [&ProjectivePoint; 5]) -> [u8; CHALLENGE_LEN] {
+        let mut hasher = Sha256::new().chain_update([SUITE, CHALLENGE_FRONT]);
+        for point in points {
+            hasher.update(encode(point));
+        }
+        let hash = hasher.chain_update([BACK]).finalize();
+        let mut c = [0u8; CHALLENGE_LEN];
+        c.copy_from_slice(&hash[..CHALLENGE_LEN]);
+        c
+    }
+
+    fn challenge_scalar(c: &[u8]) -> Scalar {
+        let mut bytes = FieldBytes::default();
+        bytes[32 - CHALLENGE_LEN..].copy_from_slice(c);
+        <Scalar as Reduce<U256>>::reduce_bytes(&bytes)
+    }
+
+    pub fn prove(key: &k256::SecretKey, alpha: &[u8]) -> Result<Vec<u8>> {
+        let x = *key.to_nonzero_scalar();
+        let y = ProjectivePoint::GENERATOR * x;
+        let public = encode(&y);
+
+        let h = encode_to_curve(&public, alpha)?;
+        let gamma = h * x;
+        // Deterministic nonce bound to the secret key and the input point
+        let k = <Scalar as Reduce<U256>>::reduce_bytes(
+            &Sha256::new()
+                .chain_update(key.to_bytes())
+                .chain_update(encode(&h))
+                .finalize(),
+        );
+        let c = challenge([&y, &h, &gamma, &(ProjectivePoint::GENERATOR * k), &(h * k)]);
+        let s = k + challenge_scalar(&c) * x;
+
+        let mut proof = encode(&gamma);
+        proof.extend_from_slice(&c);
+        proof.extend_from_slice(&s.to_bytes());
+        Ok(proof)
+    }
+
+    fn decode_proof(proof: &[u8]) -> Result<(ProjectivePoint, &[u8], Scalar)> {
+        if proof.len() != PROOF_LEN {
+            return Err(failed());
+        }
+        let gamma = decode(&proof[..33]).ok_or_else(failed)?;
+        let c = &proof[33..33 + CHALLENGE_LEN];
+        let s = *FieldBytes::from_slice(&proof[33 + CHALLENGE_LEN..]);
+        let s = Option::from(Scalar::from_repr(s)).ok_or_else(failed)?;
+        Ok((gamma, c, s))
+    }
+
+    pub fn proof_to_hash(proof: &[u8]) -> Result<Vec<u8>> {
+        let (gamma, _, _) = decode_proof(proof)?;
+        Ok(Sha256::new()
+            .chain_update([SUITE, PROOF_TO_HASH_FRONT])
+            .chain_update(encode(&gamma))
+            .chain_update([BACK])
+            .finalize()
+            .to_vec())
+    }
+
+    pub fn verify(key: &k256::PublicKey, alpha: &[u8], proof: &[u8]) -> Result<Vec<u8>> {
+        let y = key.to_projective();
+        let public = encode(&y);
+        let (gamma, c, s) = decode_proof(proof)?;
+        let h = encode_to_curve(&public, alpha)?;
+        let c_scalar = challenge_scalar(c);
+        let u = ProjectivePoint::GENERATOR * s - y * c_scalar;
+        let v = h * s - gamma * c_scalar;
+        if challenge([&y, &h, &gamma, &u, &v]) != c {
+            return Err(failed());
+        }
+        proof_to_hash(proof)
+    }
+}
@@ -0,0 +1,463 @@
+//! Diagnostic bundles for post-mortem analysis
+//!
+//! "My node stopped working" is hard to answer from a description. A node
+//! built with a [`DiagnosticsConfig`] writes a bundle when it stops, and,
+//! once [`Diagnostics::install_panic_hook`] ran, when a thread panics: its
+//! configuration, peer table, sync status, registered objects and their
+//! bookkeeping, recurring tasks, metrics and last events, one file per
+//! section in a timestamped directory. `chaincraft-cli inspect <bundle>`
+//! prints it back.
+//!
+//! Sections are collected without waiting on locks, so a panic raised while
+//! a lock is held still produces a bundle; sections that could not be read
+//! are listed in [`DiagnosticBundle::unavailable`].
+
+use crate::error::Result;
+use crate::events::NodeEvent;
+use crate::message_log::MessageLog;
+use crate::metrics::NodeMetrics;
+use crate::network::{PeerId, PeerInfo};
+use crate::shared_object::{ApplicationObjectRegistry, ObjectHealth, ObjectInfo};
+use crate::sync::{SyncStatus, SyncTracker};
+use crate::tasks::TaskScheduler;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, RwLock};
+
+/// Where bundles go and how much history they hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticsConfig {
+    /// Directory bundles are written under, one subdirectory each
+    pub dir: PathBuf,
+    /// Events kept for the next bundle
+    pub recent_events: usize,
+}
+
+impl DiagnosticsConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            recent_events: 100,
+        }
+    }
+
+    pub fn with_recent_events(mut self, recent_events: usize) -> Self {
+        self.recent_events = recent_events;
+        self
+    }
+}
+
+/// Why a bundle was written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum DumpReason {
+    /// The node was stopped
+    Shutdown,
+    /// A thread panicked
+    Panic {
+        message: String,
+        location: Option<String>,
+    },
+    /// Asked for while the node was running
+    Requested,
+}
+
+impl fmt::Display for DumpReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpReason::Shutdown => write!(f, "shutdown"),
+            DumpReason::Panic {
+                message,
+                location: Some(location),
+            } => write!(f, "panic at {}: {}", location, message),
+            DumpReason::Panic { message, .. } => write!(f, "panic: {}", message),
+            DumpReason::Requested => write!(f, "requested"),
+        }
+    }
+}
+
+/// Last events published by a node
+///
+/// Events are taken off the node's broadcast channel when read, so keeping
+/// the history costs nothing until a bundle is written. Events the channel
+/// dropped in between are lost, as they would be for a slow subscriber.
+#[derive(Debug)]
+pub struct EventHistory {
+    receiver: Mutex<broadcast::Receiver<NodeEvent>>,
+    recent: Mutex<VecDeque<NodeEvent>>,
+    capacity: usize,
+}
+
+impl EventHistory {
+    pub fn new(events: &broadcast::Sender<NodeEvent>, capacity: usize) -> Self {
+        Self {
+            receiver: Mutex::new(events.subscribe()),
+            recent: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Up to the configured number of events, oldest first
+    pub fn recent(&self) -> Vec<NodeEvent> {
+        let mut receiver = self.receiver.lock().unwrap_or_else(|e| e.into_inner());
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            match receiver.try_recv() {
+                Ok(event) => {
+                    if recent.len() == self.capacity {
+                        recent.pop_front();
+                    }
+                    if self.capacity > 0 {
+                        recent.push_back(event);
+                    }
+                },
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+        recent.iter().cloned().collect()
+    }
+}
+
+/// A registered object and its bookkeeping
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectEntry {
+    pub id: String,
+    pub type_name: String,
+    pub info: Option<ObjectInfo>,
+    /// Processing timeouts, if the node enforces a deadline
+    #[serde(default)]
+    pub health: Option<ObjectHealth>,
+}
+
+/// A recurring task and when it runs next
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskEntry {
+    pub object_id: String,
+    pub name: String,
+    pub next_run: DateTime<Utc>,
+}
+
+/// Sync status of the node against each peer that announced its sequence
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncEntry {
+    pub local_sequence: u64,
+    /// Status by peer id, e.g. `behind by 3`
+    pub peers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BundleSummary {
+    node_id: String,
+    version: String,
+    reason: DumpReason,
+    written_at: DateTime<Utc>,
+    unavailable: Vec<String>,
+}
+
+/// Everything written to a bundle directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticBundle {
+    pub node_id: String,
+    /// Crate version of the node
+    pub version: String,
+    pub reason: DumpReason,
+    pub written_at: DateTime<Utc>,
+    /// Node configuration, as debug output
+    pub config: String,
+    pub peers: Vec<PeerInfo>,
+    pub sync: SyncEntry,
+    pub objects: Vec<ObjectEntry>,
+    pub tasks: Vec<TaskEntry>,
+    pub metrics: BTreeMap<String, u64>,
+    /// Last events, oldest first, as debug output
+    pub events: Vec<String>,
+    /// Sections left empty because they were locked when collected
+    pub unavailable: Vec<String>,
+}
+
+const SUMMARY_FILE: &str = "summary.json";
+const CONFIG_FILE: &str = "config.txt";
+
+impl DiagnosticBundle {
+    /// Write the bundle into `dir`, one file per section
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let summary = BundleSummary {
+            node_id: self.node_id.clone(),
+            version: self.version.clone(),
+            reason: self.reason.clone(),
+            written_at: self.written_at,
+            unavailable: self.unavailable.clone(),
+        };
+        write_json(dir, SUMMARY_FILE, &summary)?;
+        std::fs::write(dir.join(CONFIG_FILE), &self.config)?;
+        write_json(dir, "peers.json", &self.peers)?;
+        write_json(dir, "sync.json", &self.sync)?;
+        write_json(dir, "objects.json", &self.objects)?;
+        write_json(dir, "tasks.json", &self.tasks)?;
+        write_json(dir, "metrics.json", &self.metrics)?;
+        write_json(dir, "events.json", &self.events)?;
+        Ok(())
+    }
+
+    /// Read a bundle written by [`Self::write_to`]
+    pub fn read(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let summary: BundleSummary = read_json(dir, SUMMARY_FILE)?;
+        Ok(Self {
+            node_id: summary.node_id,
+            version: summary.version,
+            reason: summary.reason,
+            written_at: summary.written_at,
+            config: std::fs::read_to_string(dir.join(CONFIG_FILE))?,
+            peers: read_json(dir, "peers.json")?,
+            sync: read_json(dir, "sync.json")?,
+            objects: read_json(dir, "objects.json")?,
+            tasks: read_json(dir, "tasks.json")?,
+            metrics: read_json(dir, "metrics.json")?,
+            events: read_json(dir, "events.json")?,
+            unavailable: summary.unavailable,
+        })
+    }
+}
+
+fn write_json(dir: &Path, file: &str, value: &impl Serialize) -> Result<()> {
+    std::fs::write(dir.join(file), serde_json::to_vec_pretty(value)?)?;
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(dir: &Path, file: &str) -> Result<T> {
+    Ok(serde_json::from_slice(&std::fs::read(dir.join(file))?)?)
+}
+
+impl fmt::Display for DiagnosticBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Node {} (chaincraft {})", self.node_id, self.version)?;
+        writeln!(f, "Written at {} on {}", self.written_at.to_rfc3339(), self.reason)?;
+        if !self.unavailable.is_empty() {
+            writeln!(f, "Locked, not collected: {}", self.unavailable.join(", "))?;
+        }
+
+        writeln!(f, "\nPeers ({}):", self.peers.len())?;
+        for peer in &self.peers {
+            writeln!(f, "  {} at {}", peer.id, peer.address)?;
+        }
+
+        writeln!(f, "\nSync: {} messages in the local log", self.sync.local_sequence)?;
+        for (peer, status) in &self.sync.peers {
+            writeln!(f, "  {}: {}", peer, status)?;
+        }
+
+        writeln!(f, "\nObjects ({}):", self.objects.len())?;
+        for object in &self.objects {
+            write!(f, "  {} {}", object.type_name, object.id)?;
+            if let Some(info) = &object.info {
+                write!(f, ": {} applied, {} rejected", info.applied, info.rejected)?;
+                if let Some(at) = info.last_applied {
+                    write!(f, ", last at {}", at.to_rfc3339())?;
+                }
+            }
+            if let Some(health) = &object.health {
+                write!(f, ", {} timeouts", health.timeouts)?;
+                if let Some(since) = health.unhealthy_since {
+                    write!(f, ", unhealthy since {}", since.to_rfc3339())?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "\nTasks ({}):", self.tasks.len())?;
+        for task in &self.tasks {
+            writeln!(
+                f,
+                "  {} of {}, next at {}",
+                task.name,
+                task.object_id,
+                task.next_run.to_rfc3339()
+            )?;
+        }
+
+        writeln!(f, "\nMetrics:")?;
+        for (name, value) in &self.metrics {
+            writeln!(f, "  {} = {}", name, value)?;
+        }
+
+        writeln!(f, "\nLast events ({}):", self.events.len())?;
+        for event in &self.events {
+            writeln!(f, "  {}", event)?;
+        }
+
+        write!(f, "\nConfiguration:\n{}", self.config)
+    }
+}
+
+fn describe_sync(status: SyncStatus) -> String {
+    match status {
+        SyncStatus::Behind { missing } => format!("behind by {}", missing),
+        SyncStatus::InSync => "in sync".to_string(),
+        SyncStatus::Ahead => "ahead".to_string(),
+    }
+}
+
+/// Collects and writes the bundles of a node
+///
+/// Cloning is cheap and clones read the same node.
+#[derive(Clone)]
+pub struct Diagnostics {
+    pub(crate) config: DiagnosticsConfig,
+    pub(crate) node_id: PeerId,
+    pub(crate) node_config: String,
+    pub(crate) peers: Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+    pub(crate) app_objects: Arc<RwLock<ApplicationObjectRegistry>>,
+    pub(crate) message_log: Arc<RwLock<MessageLog>>,
+    pub(crate) sync: Arc<SyncTracker>,
+    pub(crate) tasks: TaskScheduler,
+    pub(crate) metrics: Arc<NodeMetrics>,
+    pub(crate) events: Arc<EventHistory>,
+    pub(crate) clock: crate::clock::Clock,
+}
+
+impl fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Diagnostics")
+            .field("config", &self.config)
+            .field("node_id", &self.node_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Diagnostics {
+    pub fn config(&self) -> &DiagnosticsConfig {
+        &self.config
+    }
+
+    /// Gather the bundle without writing it
+    pub fn collect(&self, reason: DumpReason) -> DiagnosticBundle {
+        let mut unavailable = Vec::new();
+
+        let peers = match self.peers.try_read() {
+            Ok(peers) => {
+                let mut peers: Vec<PeerInfo> = peers.values().cloned().collect();
+                peers.sort_by_key(|peer| peer.id.to_string());
+                peers
+            },
+            Err(_) => {
+                unavailable.push("peers".to_string());
+                Vec::new()
+            },
+        };
+
+        let sync = match self.message_log.try_read() {
+            Ok(log) => {
+                let summary = self.sync.summary(log.len() as u64);
+                SyncEntry {
+                    local_sequence: summary.local_sequence,
+                    peers: summary
+                        .peers
+                        .into_iter()
+                        .map(|(peer, status)| (peer.to_string(), describe_sync(status)))
+                        .collect(),
+                }
+            },
+            Err(_) => {
+                unavailable.push("sync".to_string());
+                SyncEntry::default()
+            },
+        };
+
+        let objects = match self.app_objects.try_read() {
+            Ok(registry) => registry
+                .ids()
+                .into_iter()
+                .filter_map(|id| {
+                    let object = registry.get(&id)?;
+                    Some(ObjectEntry {
+                        id: id.to_string(),
+                        type_name: object.type_name().to_string(),
+                        info: registry.info(&id).cloned(),
+                        health: registry.health(&id).cloned(),
+                    })
+                })
+                .collect(),
+            Err(_) => {
+                unavailable.push("objects".to_string());
+                Vec::new()
+            },
+        };
+
+        let tasks = self
+            .tasks
+            .tasks()
+            .into_iter()
+            .map(|task| TaskEntry {
+                object_id: task.object_id.to_string(),
+                name: task.spec.name,
+                next_run: task.next_run,
+            })
+            .collect();
+
+        DiagnosticBundle {
+            node_id: self.node_id.to_string(),
+            version: crate::VERSION.to_string(),
+            reason,
+            written_at: self.clock.now(),
+            config: self.node_config.clone(),
+            peers,
+            sync,
+            objects,
+            tasks,
+            metrics: self.metrics.snapshot(),
+            events: self
+                .events
+                .recent()
+                .iter()
+                .map(|event| format!("{:?}", event))
+                .collect(),
+            unavailable,
+        }
+    }
+
+    /// Write a bundle to a new directory under the configured one
+    ///
+    /// Returns the bundle's directory, named after the node and the time.
+    pub fn write(&self, reason: DumpReason) -> Result<PathBuf> {
+        let bundle = self.collect(reason);
+        let id = bundle.node_id.get(..8).unwrap_or(&bundle.node_id);
+        let dir = self.config.dir.join(format!(
+            "{}-{}",
+            id,
+            bundle.written_at.format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        bundle.write_to(&dir)?;
+        Ok(dir)
+    }
+
+    /// Write a bundle whenever a thread panics, then run the previous hook
+    pub fn install_panic_hook(&self) {
+        let diagnostics = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            let reason = DumpReason::Panic {
+                message,
+                location: info.location().map(|location| location.to_string()),
+            };
+            match diagnostics.write(reason) {
+                Ok(dir) => eprintln!("Diagnostic bundle written to {}", dir.display()),
+                Err(e) => eprintln!("Failed to write a diagnostic bundle: {}", e),
+            }
+            previous(info);
+        }));
+    }
+}
@@ -0,0 +1,171 @@
+//! Read-only view of the objects in a registry
+//!
+//! Application objects are isolated: each one only sees the messages it
+//! accepts. An [`ObjectDirectory`] lets an object read what its siblings
+//! currently hold, for instance an auction drawing from a randomness beacon,
+//! without being able to change them.
+//!
+//! The directory never touches the registry itself. The registry publishes a
+//! snapshot of an object's state and digest when the object is registered and
+//! after every message it applies, and reads are served from those snapshots.
+//! An object can therefore look up any sibling, itself included, while the
+//! registry is busy applying a message to it, and two objects reading each
+//! other cannot deadlock. The price is that a sibling is seen as of its last
+//! applied message, never half-way through one.
+
+use crate::error::{ChaincraftError, Result};
+use crate::shared::SharedObjectId;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Published state of one object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectSnapshot {
+    pub id: SharedObjectId,
+    pub type_name: String,
+    pub state: Value,
+    pub digest: String,
+}
+
+/// Read-only handle on the latest state of every registered object
+///
+/// Cloning is cheap and every clone sees the same objects.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectDirectory {
+    snapshots: Arc<RwLock<HashMap<SharedObjectId, ObjectSnapshot>>>,
+}
+
+impl ObjectDirectory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Latest snapshot of an object
+    pub async fn snapshot(&self, id: &SharedObjectId) -> Option<ObjectSnapshot> {
+        self.read().get(id).cloned()
+    }
+
+    /// Latest state of an object, as returned by its `get_state`
+    pub async fn state(&self, id: &SharedObjectId) -> Result<Value> {
+        self.snapshot(id)
+            .await
+            .map(|snapshot| snapshot.state)
+            .ok_or_else(|| unknown(id))
+    }
+
+    /// Latest digest of an object
+    pub async fn digest(&self, id: &SharedObjectId) -> Result<String> {
+        self.snapshot(id)
+            .await
+            .map(|snapshot| snapshot.digest)
+            .ok_or_else(|| unknown(id))
+    }
+
+    /// Ids of the objects of a type, sorted
+    pub async fn ids_by_type(&self, type_name: &str) -> Vec<SharedObjectId> {
+        let mut ids: Vec<SharedObjectId> = self
+            .read()
+            .values()
+            .filter(|snapshot| snapshot.type_name == type_name)
+            .map(|snapshot| snapshot.id.clone())
+            .collect();
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    }
+
+    /// Snapshot of the only object of a type
+    ///
+    /// Returns `None` if there is no such object or more than one.
+    pub async fn find_by_type(&self, type_name: &str) -> Option<ObjectSnapshot> {
+        let snapshots = self.read();
+        let mut matching = snapshots
+            .values()
+            .filter(|snapshot| snapshot.type_name == type_name);
+        match (matching.next(), matching.next()) {
+            (Some(snapshot), None) => Some(snapshot.clone()),
+            _ => None,
+        }
+    }
+
+    /// Id of the object named by a full id or an unambiguous prefix of one,
+    /// such as the [`short`](SharedObjectId::short) form shown in logs
+    pub async fn resolve(&self, text: &str) -> Result<SharedObjectId> {
+        if let Ok(id) = text.parse::<SharedObjectId>() {
+            return match self.read().contains_key(&id) {
+                true => Ok(id),
+                false => Err(unknown(&id)),
+            };
+        }
+        let prefix = text.replace('-', "").to_ascii_lowercase();
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ChaincraftError::validation(format!("invalid object id {}", text)));
+        }
+        let mut matches: Vec<SharedObjectId> = self
+            .read()
+            .keys()
+            .filter(|id| id.as_uuid().simple().to_string().starts_with(&prefix))
+            .cloned()
+            .collect();
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(ChaincraftError::validation(format!("no object matches id {}", text))),
+            n => {
+                matches.sort_by_key(|id| id.to_string());
+                let candidates: Vec<String> = matches.iter().map(|id| id.to_string()).collect();
+                Err(ChaincraftError::validation(format!(
+                    "object id {} is ambiguous, {} objects match: {}",
+                    text,
+                    n,
+                    candidates.join(", ")
+                )))
+            },
+        }
+    }
+
+    /// Snapshots of every object, sorted by id
+    pub async fn snapshots(&self) -> Vec<ObjectSnapshot> {
+        let mut snapshots: Vec<ObjectSnapshot> = self.read().values().cloned().collect();
+        snapshots.sort_by_key(|snapshot| snapshot.id.to_string());
+        snapshots
+    }
+
+    /// Number of published objects
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    pub(crate) fn publish(&self, snapshot: ObjectSnapshot) {
+        self.write().insert(snapshot.id.clone(), snapshot);
+    }
+
+    pub(crate) fn remove(&self, id: &SharedObjectId) {
+        self.write().remove(id);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.write().clear();
+    }
+
+    // Locks are never held across an await, so a poisoned lock only means a
+    // panic elsewhere; the map itself is still consistent.
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<SharedObjectId, ObjectSnapshot>> {
+        self.snapshots
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<SharedObjectId, ObjectSnapshot>> {
+        self.snapshots
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn unknown(id: &SharedObjectId) -> ChaincraftError {
+    ChaincraftError::validation(format!("no published state for object {}", id.short()))
+}
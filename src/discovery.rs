@@ -1,9 +1,30 @@
 //! Peer discovery system for Chaincraft
+//!
+//! Announcements are signed with the announcing node's identity key and
+//! expire after [`DiscoveryConfig::announcement_ttl`], so a stale or forged
+//! announcement cannot point a node id at someone else's address. A node id is
+//! pinned to the key of the first signed announcement that arrives from the
+//! address it announces; later announcements for that id must be signed with
+//! the same key. Unsigned announcements from older nodes are still accepted
+//! unless [`DiscoveryConfig::strict_announcements`] is set.
+//!
+//! Peer responses relay the signed announcements they list, and each entry is
+//! checked like an announcement of its own. A response never moves a peer we
+//! already know and never pins a key.
+//!
+//! With [`NodeConfig::discovery`](crate::node::NodeConfig::discovery) set, a
+//! started node runs discovery over its UDP gossip socket: every
+//! [`DiscoveryConfig::announce_interval`] it sends an announcement and a peer
+//! request to each known peer, starting from
+//! [`NodeConfig::bootstrap_peers`](crate::node::NodeConfig::bootstrap_peers),
+//! and adds the peers named in responses to its peer list.
 
 use crate::{
-    error::{ChaincraftError, Result},
-    network::{PeerId, PeerInfo},
+    crypto::{KeyType, PrivateKey, PublicKey, Signature},
+    error::{ChaincraftError, NetworkError, Result},
+    network::{PeerAdmission, PeerId, PeerInfo},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
@@ -12,13 +33,22 @@ use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 
 /// Discovery message types for peer-to-peer discovery
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum DiscoveryMessage {
     /// Announce this node to others
     Announce {
         node_id: PeerId,
         socket_addr: SocketAddr,
         timestamp: u64,
+        /// Unix time after which the announcement is refused
+        #[serde(default)]
+        expires_at: u64,
+        /// Hex encoded identity key, empty if unsigned
+        #[serde(default)]
+        public_key: String,
+        /// Hex encoded signature over the other fields
+        #[serde(default)]
+        signature: String,
     },
     /// Request known peers from a node
     PeerRequest {
@@ -37,12 +67,43 @@ pub enum DiscoveryMessage {
 }
 
 /// Peer announcement structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Carries the peer's own signed announcement, if we had one, so that it can
+/// be relayed in peer responses and checked by whoever receives it.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PeerAnnouncement {
     pub node_id: PeerId,
     pub socket_addr: SocketAddr,
     pub last_seen: u64,
+    /// Timestamp of the announcement, which its signature covers
     pub announced_at: u64,
+    /// Unix time after which the announcement is refused
+    #[serde(default)]
+    pub expires_at: u64,
+    /// Hex encoded identity key, empty if unsigned
+    #[serde(default)]
+    pub public_key: String,
+    /// Hex encoded signature over the announcement
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl PeerAnnouncement {
+    fn unsigned(node_id: PeerId, socket_addr: SocketAddr, now: u64) -> Self {
+        Self {
+            node_id,
+            socket_addr,
+            last_seen: now,
+            announced_at: now,
+            expires_at: 0,
+            public_key: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        !self.public_key.is_empty()
+    }
 }
 
 /// Discovery configuration
@@ -58,6 +119,15 @@ pub struct DiscoveryConfig {
     pub announce_interval: u64,
     /// Enable discovery protocol
     pub enabled: bool,
+    /// Score at or below which a peer is no longer shared in peer exchange
+    pub ban_score: i64,
+    /// How long our announcements stay valid (seconds)
+    pub announcement_ttl: u64,
+    /// Refuse announcements that are not signed
+    pub strict_announcements: bool,
+    /// Whether a new peer is refused or evicts the stalest once
+    /// `max_peers` are known
+    pub admission: PeerAdmission,
 }
 
 impl Default for DiscoveryConfig {
@@ -68,11 +138,18 @@ impl Default for DiscoveryConfig {
             peer_timeout: 120,
             announce_interval: 60,
             enabled: true,
+            ban_score: -100,
+            announcement_ttl: 300,
+            strict_announcements: false,
+            admission: PeerAdmission::EvictLeastRecentlySeen,
         }
     }
 }
 
 /// Peer discovery manager
+///
+/// Clones share the peer table, scores and pinned keys.
+#[derive(Clone)]
 pub struct DiscoveryManager {
     /// This node's ID
     node_id: PeerId,
@@ -86,6 +163,12 @@ pub struct DiscoveryManager {
     config: DiscoveryConfig,
     /// Last announcement time
     last_announce: Arc<RwLock<Option<Instant>>>,
+    /// Reputation scores (peers start at 0, abuse reports lower the score)
+    scores: Arc<RwLock<HashMap<PeerId, i64>>>,
+    /// Key our announcements are signed with
+    identity: Option<PrivateKey>,
+    /// Identity key each node id is pinned to
+    announced_keys: Arc<RwLock<HashMap<PeerId, String>>>,
 }
 
 impl DiscoveryManager {
@@ -98,46 +181,100 @@ impl DiscoveryManager {
             connected_peers: Arc::new(RwLock::new(HashSet::new())),
             config,
             last_announce: Arc::new(RwLock::new(None)),
+            scores: Arc::new(RwLock::new(HashMap::new())),
+            identity: None,
+            announced_keys: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// Add a peer to the known peers list
-    pub async fn add_peer(&self, peer_info: PeerInfo) -> Result<()> {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let announcement = PeerAnnouncement {
-            node_id: peer_info.id.clone(),
-            socket_addr: peer_info.address,
-            last_seen: now,
-            announced_at: now,
-        };
-
-        let mut peers = self.peers.write().await;
-        peers.insert(peer_info.id, announcement);
+    /// Sign our announcements with a node identity key
+    pub fn with_identity(mut self, private_key: PrivateKey) -> Self {
+        self.identity = Some(private_key);
+        self
+    }
 
-        // If we have too many peers, remove the oldest ones
-        if peers.len() > self.config.max_peers {
-            let mut peer_last_seen: Vec<(PeerId, u64)> = peers
-                .iter()
-                .map(|(id, ann)| (id.clone(), ann.last_seen))
-                .collect();
+    /// Identity key a node id is pinned to, once it announced signed
+    pub async fn announced_key(&self, peer_id: &PeerId) -> Option<String> {
+        self.announced_keys.read().await.get(peer_id).cloned()
+    }
 
-            peer_last_seen.sort_by_key(|(_, last_seen)| *last_seen);
+    /// Pin a node id to an identity key known out of band, such as from
+    /// configuration
+    ///
+    /// Fails with [`NetworkError::InvalidMessage`] if the id is already
+    /// pinned to another key.
+    pub async fn pin_key(&self, peer_id: &PeerId, public_key: &str) -> Result<()> {
+        let mut keys = self.announced_keys.write().await;
+        match keys.get(peer_id) {
+            Some(pinned) if pinned != public_key => {
+                Err(ChaincraftError::Network(NetworkError::InvalidMessage {
+                    reason: format!("{} is pinned to a different key", peer_id),
+                }))
+            },
+            Some(_) => Ok(()),
+            None => {
+                keys.insert(peer_id.clone(), public_key.to_string());
+                Ok(())
+            },
+        }
+    }
 
-            let oldest_peers: Vec<PeerId> = peer_last_seen
-                .into_iter()
-                .take(peers.len() - self.config.max_peers)
-                .map(|(id, _)| id)
-                .collect();
+    /// Add a peer to the known peers list
+    ///
+    /// Once `max_peers` are known, a new peer is refused with
+    /// [`NetworkError::PeerRefused`] or evicts the peer seen least recently,
+    /// as [`DiscoveryConfig::admission`] says. Known peers are refreshed.
+    pub async fn add_peer(&self, peer_info: PeerInfo) -> Result<()> {
+        let announcement = PeerAnnouncement::unsigned(peer_info.id, peer_info.address, unix_now());
+        self.insert_peer(announcement, true).await
+    }
 
-            for peer_id in oldest_peers {
-                peers.remove(&peer_id);
+    /// Add or, with `replace`, update a known peer
+    ///
+    /// A known peer seen at the same address keeps its signed announcement.
+    async fn insert_peer(&self, announcement: PeerAnnouncement, replace: bool) -> Result<()> {
+        let mut peers = self.peers.write().await;
+        if let Some(known) = peers.get_mut(&announcement.node_id) {
+            if !replace {
+                return Ok(());
+            }
+            if known.socket_addr == announcement.socket_addr && !announcement.is_signed() {
+                known.last_seen = announcement.last_seen;
+            } else {
+                *known = announcement;
+            }
+            return Ok(());
+        }
+        let mut evicted = None;
+        if peers.len() >= self.config.max_peers {
+            match self.config.admission {
+                PeerAdmission::RejectNew => {
+                    return Err(ChaincraftError::Network(NetworkError::PeerRefused {
+                        peer: announcement.socket_addr.to_string(),
+                        reason: format!("max_peers of {} reached", self.config.max_peers),
+                    }));
+                },
+                PeerAdmission::EvictLeastRecentlySeen => {
+                    let stalest = peers
+                        .values()
+                        .min_by_key(|peer| peer.last_seen)
+                        .map(|peer| peer.node_id.clone());
+                    match stalest {
+                        Some(stalest) => {
+                            peers.remove(&stalest);
+                            evicted = Some(stalest);
+                        },
+                        None => return Ok(()),
+                    }
+                },
             }
         }
+        peers.insert(announcement.node_id.clone(), announcement);
+        drop(peers);
 
+        if let Some(evicted) = evicted {
+            self.connected_peers.write().await.remove(&evicted);
+        }
         Ok(())
     }
 
@@ -190,7 +327,27 @@ impl DiscoveryManager {
         connected.iter().cloned().collect()
     }
 
-    /// Get peers for discovery response (excluding requester and already connected)
+    /// Lower a peer's score after it misbehaved
+    pub async fn report_abuse(&self, peer_id: &PeerId, penalty: i64) -> i64 {
+        let mut scores = self.scores.write().await;
+        let score = scores.entry(peer_id.clone()).or_insert(0);
+        *score = score.saturating_sub(penalty);
+        *score
+    }
+
+    /// Get a peer's current score
+    pub async fn peer_score(&self, peer_id: &PeerId) -> i64 {
+        let scores = self.scores.read().await;
+        scores.get(peer_id).copied().unwrap_or(0)
+    }
+
+    /// Check if a peer's score has dropped to the ban threshold
+    pub async fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peer_score(peer_id).await <= self.config.ban_score
+    }
+
+    /// Get peers for discovery response (excluding requester, already connected and
+    /// banned peers), best scored first
     pub async fn get_peers_for_discovery(
         &self,
         requester_id: &PeerId,
@@ -198,13 +355,17 @@ impl DiscoveryManager {
     ) -> Vec<PeerAnnouncement> {
         let peers = self.peers.read().await;
         let connected = self.connected_peers.read().await;
+        let scores = self.scores.read().await;
+        let score_of = |id: &PeerId| scores.get(id).copied().unwrap_or(0);
 
-        peers
+        let mut candidates: Vec<&PeerAnnouncement> = peers
             .values()
             .filter(|peer| &peer.node_id != requester_id && !connected.contains(&peer.node_id))
-            .take(max_peers)
-            .cloned()
-            .collect()
+            .filter(|peer| score_of(&peer.node_id) > self.config.ban_score)
+            .collect();
+        candidates.sort_by_key(|peer| std::cmp::Reverse(score_of(&peer.node_id)));
+
+        candidates.into_iter().take(max_peers).cloned().collect()
     }
 
     /// Handle discovery message
@@ -217,11 +378,24 @@ impl DiscoveryManager {
             DiscoveryMessage::Announce {
                 node_id,
                 socket_addr,
-                timestamp: _,
+                timestamp,
+                expires_at,
+                public_key,
+                signature,
             } => {
-                // Add the announcing peer to our known peers
-                let peer_info = PeerInfo::new(node_id, socket_addr);
-                self.add_peer(peer_info).await?;
+                let announcement = PeerAnnouncement {
+                    node_id,
+                    socket_addr,
+                    last_seen: unix_now(),
+                    announced_at: timestamp,
+                    expires_at,
+                    public_key,
+                    signature,
+                };
+                // Only the address the announcement names can pin a key to it
+                self.check_announcement(&announcement, socket_addr == sender_addr)
+                    .await?;
+                self.insert_peer(announcement, true).await?;
                 Ok(None)
             },
 
@@ -235,11 +409,24 @@ impl DiscoveryManager {
             },
 
             DiscoveryMessage::PeerResponse { peers } => {
-                // Add all peers from the response
-                for peer_announcement in peers {
-                    let peer_info =
-                        PeerInfo::new(peer_announcement.node_id, peer_announcement.socket_addr);
-                    self.add_peer(peer_info).await?;
+                // Add new peers from the response until no more are admitted
+                for announcement in peers {
+                    if announcement.node_id == self.node_id
+                        || self.peers.read().await.contains_key(&announcement.node_id)
+                    {
+                        continue;
+                    }
+                    if let Err(e) = self.check_announcement(&announcement, false).await {
+                        tracing::debug!("Skipping peer from {}: {}", sender_addr, e);
+                        continue;
+                    }
+                    let announcement = PeerAnnouncement {
+                        last_seen: unix_now(),
+                        ..announcement
+                    };
+                    if self.insert_peer(announcement, false).await.is_err() {
+                        break;
+                    }
                 }
                 Ok(None)
             },
@@ -270,18 +457,97 @@ impl DiscoveryManager {
         }
     }
 
-    /// Create an announcement message
-    pub fn create_announcement(&self) -> DiscoveryMessage {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    /// Verify an announcement before its address is trusted
+    ///
+    /// Refuses expired announcements, bad signatures, keys other than the one
+    /// pinned for the node id, and unsigned announcements in strict mode or
+    /// for a pinned node id. With `pin`, the key of a valid announcement for
+    /// an id not pinned yet becomes its pinned key.
+    async fn check_announcement(&self, announcement: &PeerAnnouncement, pin: bool) -> Result<()> {
+        let PeerAnnouncement {
+            node_id,
+            socket_addr,
+            announced_at: timestamp,
+            expires_at,
+            public_key,
+            signature,
+            ..
+        } = announcement;
+        let refuse = |reason: &str| {
+            Err(ChaincraftError::Network(NetworkError::InvalidMessage {
+                reason: format!("announcement of {}: {}", node_id, reason),
+            }))
+        };
+        let pinned = self.announced_key(node_id).await;
+        if public_key.is_empty() {
+            if self.config.strict_announcements {
+                return refuse("not signed");
+            }
+            if pinned.is_some() {
+                return refuse("not signed by its identity key");
+            }
+            return Ok(());
+        }
+
+        if *expires_at < unix_now() {
+            return refuse("expired");
+        }
+        let bytes = announcement_bytes(node_id, *socket_addr, *timestamp, *expires_at, public_key);
+        let key_type = KeyType::of_public_key_hex(public_key);
+        let verified = match (
+            PublicKey::from_hex(public_key, key_type),
+            Signature::from_hex(signature, key_type),
+        ) {
+            (Ok(key), Ok(signature)) => key.verify(&bytes, &signature).unwrap_or(false),
+            _ => false,
+        };
+        if !verified {
+            return refuse("invalid signature");
+        }
+        match pinned {
+            Some(pinned) if pinned != *public_key => refuse("signed by a different key"),
+            Some(_) => Ok(()),
+            None if pin => {
+                let mut keys = self.announced_keys.write().await;
+                let pinned = keys
+                    .entry(node_id.clone())
+                    .or_insert_with(|| public_key.to_string());
+                if pinned != public_key {
+                    return refuse("signed by a different key");
+                }
+                Ok(())
+            },
+            None => Ok(()),
+        }
+    }
 
-        DiscoveryMessage::Announce {
+    /// Create an announcement message, signed if we have an identity key
+    pub fn create_announcement(&self) -> Result<DiscoveryMessage> {
+        let timestamp = unix_now();
+        let expires_at = timestamp + self.config.announcement_ttl;
+        let (public_key, signature) = match &self.identity {
+            Some(private_key) => {
+                let public_key = private_key.public_key().to_hex();
+                let bytes = announcement_bytes(
+                    &self.node_id,
+                    self.socket_addr,
+                    timestamp,
+                    expires_at,
+                    &public_key,
+                );
+                (public_key, private_key.sign(&bytes)?.to_hex())
+            },
+            None => (String::new(), String::new()),
+        };
+
+        Ok(DiscoveryMessage::Announce {
             node_id: self.node_id.clone(),
             socket_addr: self.socket_addr,
-            timestamp: now,
-        }
+            timestamp,
+            expires_at,
+            public_key,
+            signature,
+        })
     }
 
     /// Create a peer request message
@@ -365,6 +631,28 @@ impl DiscoveryManager {
     }
 }
 
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Bytes an announcement signature covers: every signed field, one per line
+fn announcement_bytes(
+    node_id: &PeerId,
+    socket_addr: SocketAddr,
+    timestamp: u64,
+    expires_at: u64,
+    public_key: &str,
+) -> Vec<u8> {
+    format!(
+        "announce\n{}\n{}\n{}\n{}\n{}",
+        node_id, socket_addr, timestamp, expires_at, public_key
+    )
+    .into_bytes()
+}
+
 /// Discovery statistics
 #[derive(Debug, Clone)]
 pub struct DiscoveryStats {
@@ -8,6 +8,7 @@ pub type Result<T> = std::result::Result<T, ChaincraftError>;
 
 /// Main error type for Chaincraft operations
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ChaincraftError {
     /// Network-related errors
     #[error("Network error: {0}")]
@@ -33,6 +34,36 @@ pub enum ChaincraftError {
     #[error("Consensus error: {0}")]
     Consensus(String),
 
+    /// An application object rejected a message
+    #[error("Message rejected by object {object_id}: {code}: {detail}")]
+    Rejected {
+        object_id: String,
+        code: String,
+        detail: String,
+    },
+
+    /// A configured resource limit would be exceeded
+    #[error("Resource exhausted: {requested} {resource} requested, limit is {limit}")]
+    ResourceExhausted {
+        resource: crate::resources::Resource,
+        requested: u64,
+        limit: u64,
+    },
+
+    /// Saved object state has a schema version this build cannot load
+    #[error("{type_name} state has schema version {found}, this build uses {expected}: {hint}")]
+    SchemaVersion {
+        type_name: String,
+        found: u32,
+        expected: u32,
+        /// What to implement or change to load the state
+        hint: String,
+    },
+
+    /// A read replica was asked to originate messages or take validator duties
+    #[error("Read replica cannot {operation}")]
+    ReadReplica { operation: String },
+
     /// Configuration errors
     #[error("Configuration error: {0}")]
     Config(String),
@@ -85,6 +116,22 @@ pub enum NetworkError {
     /// No peers available
     #[error("No peers available for operation")]
     NoPeersAvailable,
+
+    /// Peer exceeded its inbound message quota
+    #[error("Peer {peer_id} exceeded its inbound message quota")]
+    PeerThrottled { peer_id: String },
+
+    /// Remote procedure call failed
+    #[error("RPC failed: {reason}")]
+    RpcFailed { reason: String },
+
+    /// Peer was disconnected for abusive behaviour
+    #[error("Peer {peer_id} was disconnected: {reason}")]
+    PeerDisconnected { peer_id: String, reason: String },
+
+    /// Peer is not admitted by the node's access list
+    #[error("Peer {peer} refused: {reason}")]
+    PeerRefused { peer: String, reason: String },
 }
 
 /// Cryptographic error types
@@ -102,6 +149,10 @@ pub enum CryptoError {
     #[error("Invalid private key: {reason}")]
     InvalidPrivateKey { reason: String },
 
+    /// Message carries no signature that verifies under its sender
+    #[error("Message is not signed")]
+    MissingSignature,
+
     /// Hash verification failed
     #[error("Hash verification failed")]
     HashVerificationFailed,
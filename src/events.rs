@@ -0,0 +1,72 @@
+//! Node lifecycle events
+//!
+//! Nodes publish [`NodeEvent`]s on a broadcast channel so applications and
+//! tests can react to things happening inside the node without polling.
+
+use crate::{
+    network::PeerId,
+    resources::Resource,
+    shared::SharedObjectId,
+    shared_object::ApplyOutcome,
+    sync::{ForkReport, SyncStatus},
+};
+
+/// Why an application object left the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalReason {
+    /// Removed on request
+    Deregistered,
+    /// Collected after exceeding the idle timeout
+    Idle,
+    /// Replaced by a new implementation, see [`NodeEvent::ObjectReplaced`]
+    Replaced,
+}
+
+/// Something that happened inside a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NodeEvent {
+    /// An application object was removed and its final snapshot persisted
+    ObjectRemoved {
+        id: SharedObjectId,
+        type_name: String,
+        reason: RemovalReason,
+    },
+    /// An application object was hot-swapped for a new implementation
+    ObjectReplaced {
+        old_id: SharedObjectId,
+        new_id: SharedObjectId,
+        /// Messages queued during the swap and applied to the replacement
+        replayed: usize,
+    },
+    /// An application object handled a stored message
+    MessageProcessed {
+        hash: String,
+        object_id: SharedObjectId,
+        outcome: ApplyOutcome,
+    },
+    /// The local node's sync status against a peer changed
+    SyncStatusChanged { peer_id: PeerId, status: SyncStatus },
+    /// An operation was refused because it would exceed a resource limit
+    ResourceExhausted { resource: Resource, limit: u64 },
+    /// A peer's object digest differs from ours at the same message count
+    ForkDetected(ForkReport),
+    /// The estimated offset of the local clock exceeded the tolerated maximum
+    ClockOffsetExceeded { offset: chrono::Duration },
+    /// The estimated offset of the local clock is back within the maximum
+    ClockOffsetRecovered { offset: chrono::Duration },
+    /// An application object did not handle a message within the
+    /// [`ProcessingTimeout`](crate::shared_object::ProcessingTimeout)
+    ObjectTimedOut {
+        object_id: SharedObjectId,
+        hash: String,
+        /// Timeouts in a row, this one included
+        consecutive: u32,
+    },
+    /// An application object timed out too often in a row and no longer
+    /// receives messages
+    ObjectUnhealthy {
+        object_id: SharedObjectId,
+        type_name: String,
+    },
+}
@@ -6,27 +6,38 @@
 //! - Requesting to join chatrooms
 //! - Admin approval of new members
 //! - Posting messages to chatrooms
+//! - Delivery and read receipts for posts, linked by `in_reply_to`
 //! - Message validation and signature verification
 
 use crate::{
+    codec::consensus::ConsensusHasher,
     crypto::{
         ecdsa::{ECDSASignature, ECDSAVerifier},
         KeyType, PrivateKey, PublicKey, Signature,
     },
     error::{ChaincraftError, Result},
-    shared::{MessageType, SharedMessage, SharedObjectId},
-    shared_object::ApplicationObject,
+    index::{IndexKey, IndexSpec},
+    retention::Compaction,
+    shared::{DigestAccumulator, DigestHistory, MessageType, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, RelayDecision},
+    storage::Storage,
 };
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Node index of posted messages by chatroom name
+pub const CHATROOM_POSTS_INDEX: &str = "chatroom_posts";
+
 /// Chatroom message types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "message_type")]
 pub enum ChatroomMessageType {
     #[serde(rename = "CREATE_CHATROOM")]
@@ -67,6 +78,57 @@ pub enum ChatroomMessageType {
         #[serde(default)]
         signature: String,
     },
+    /// A member received a post, given by its message hash
+    #[serde(rename = "DELIVERY_RECEIPT")]
+    DeliveryReceipt {
+        chatroom_name: String,
+        public_key_pem: String,
+        in_reply_to: String,
+        #[serde(default)]
+        timestamp: f64,
+        #[serde(default)]
+        signature: String,
+    },
+    /// A member read a post; implies delivery
+    #[serde(rename = "READ_RECEIPT")]
+    ReadReceipt {
+        chatroom_name: String,
+        public_key_pem: String,
+        in_reply_to: String,
+        #[serde(default)]
+        timestamp: f64,
+        #[serde(default)]
+        signature: String,
+    },
+}
+
+/// JSON Schemas of the chatroom's messages, by type name
+pub fn schemas() -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([("ChatroomMessageType", super::schema_of::<ChatroomMessageType>())])
+}
+
+/// Kind of receipt a member sends for a post
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptKind {
+    Delivered,
+    Read,
+}
+
+impl ReceiptKind {
+    fn message_type(self) -> &'static str {
+        match self {
+            ReceiptKind::Delivered => "DELIVERY_RECEIPT",
+            ReceiptKind::Read => "READ_RECEIPT",
+        }
+    }
+}
+
+/// Members that acknowledged a post
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostReceipts {
+    /// Members the post reached, including those who read it
+    pub delivered: BTreeSet<String>,
+    pub read: BTreeSet<String>,
 }
 
 /// A chatroom structure
@@ -75,7 +137,52 @@ pub struct Chatroom {
     pub name: String,
     pub admin: String,              // Admin's public key
     pub members: Vec<String>,       // Member public keys
-    pub messages: Vec<ChatMessage>, // All messages including metadata
+    pub messages: Vec<ChatMessage>, // Retained messages including metadata
+    /// Number of older messages pruned from `messages`
+    #[serde(default)]
+    pub pruned_count: u64,
+    /// Rolling hash over every message ever appended, unaffected by pruning
+    #[serde(default)]
+    pub history_digest: String,
+    /// Receipts of retained posts, by post message hash
+    #[serde(default)]
+    pub receipts: HashMap<String, PostReceipts>,
+}
+
+impl Chatroom {
+    fn new(name: String, admin: String) -> Self {
+        Self {
+            name,
+            members: vec![admin.clone()], // Admin is automatically a member
+            admin,
+            messages: Vec::new(),
+            pruned_count: 0,
+            history_digest: String::new(),
+            receipts: HashMap::new(),
+        }
+    }
+
+    /// Whether a retained message carries this signature, i.e. a replay of it
+    fn contains_signature(&self, signature: &str) -> bool {
+        self.messages
+            .iter()
+            .any(|message| message.signature == signature)
+    }
+
+    /// Total number of messages ever appended, including pruned ones
+    pub fn total_messages(&self) -> u64 {
+        self.pruned_count + self.messages.len() as u64
+    }
+
+    fn append(&mut self, message: ChatMessage) {
+        self.history_digest = ConsensusHasher::new()
+            .str(&self.history_digest)
+            .str(&message.message_type)
+            .str(&message.public_key_pem)
+            .str(&message.signature)
+            .finish_hex();
+        self.messages.push(message);
+    }
 }
 
 /// A chat message with metadata
@@ -88,25 +195,150 @@ pub struct ChatMessage {
     pub requester_key_pem: Option<String>,
     pub timestamp: f64,
     pub signature: String,
+    /// Hash of the node message that carried a post, which receipts refer to
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+/// Retention settings for chatroom history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatroomConfig {
+    /// Maximum number of messages kept in memory per room (`None` keeps everything)
+    pub max_messages_per_room: Option<usize>,
 }
 
 /// Chatroom application object
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ChatroomObject {
     id: SharedObjectId,
     chatrooms: HashMap<String, Chatroom>,
     users: HashMap<String, String>,
     verifier: ECDSAVerifier,
+    config: ChatroomConfig,
+    archive: Option<Arc<dyn Storage>>,
+    history: DigestHistory,
+    accumulator: DigestAccumulator,
 }
 
 impl ChatroomObject {
     pub fn new() -> Self {
+        Self::with_config(ChatroomConfig::default())
+    }
+
+    /// Create a chatroom object with the given retention settings
+    pub fn with_config(config: ChatroomConfig) -> Self {
+        // Keep as many messages for sync as a room retains
+        let accumulator = match config.max_messages_per_room {
+            Some(limit) => DigestAccumulator::with_retention(limit),
+            None => DigestAccumulator::new(),
+        };
         Self {
             id: SharedObjectId::new(),
             chatrooms: HashMap::new(),
             users: HashMap::new(),
             verifier: ECDSAVerifier::new(),
+            config,
+            archive: None,
+            history: DigestHistory::new(),
+            accumulator,
+        }
+    }
+
+    /// Archive pruned messages to the given storage instead of discarding them
+    pub fn with_archive(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.archive = Some(storage);
+        self
+    }
+
+    /// Get the retention settings
+    pub fn config(&self) -> &ChatroomConfig {
+        &self.config
+    }
+
+    /// Get a page of messages from a room.
+    ///
+    /// `offset` is the sequence number of a message in the room's history (0 is the first
+    /// message ever posted), so pages stay put when older messages are pruned. Only the
+    /// retained messages of `offset..offset + limit` are returned: a page reaching into the
+    /// pruned part comes back short, and [`Self::get_archived_messages`] has the rest.
+    pub fn get_messages(&self, room: &str, offset: usize, limit: usize) -> Vec<ChatMessage> {
+        let Some(chatroom) = self.chatrooms.get(room) else {
+            return Vec::new();
+        };
+        let end = (offset as u64).saturating_add(limit as u64);
+        let start = (offset as u64).max(chatroom.pruned_count);
+        chatroom
+            .messages
+            .iter()
+            .skip((start - chatroom.pruned_count) as usize)
+            .take(end.saturating_sub(start) as usize)
+            .cloned()
+            .collect()
+    }
+
+    /// Get a page of archived (pruned) messages from a room
+    pub async fn get_archived_messages(
+        &self,
+        room: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ChatMessage>> {
+        let (Some(archive), Some(chatroom)) = (&self.archive, self.chatrooms.get(room)) else {
+            return Ok(Vec::new());
+        };
+
+        let end = (offset as u64)
+            .saturating_add(limit as u64)
+            .min(chatroom.pruned_count);
+        let mut messages = Vec::new();
+        for position in offset as u64..end {
+            if let Some(bytes) = archive.get(&Self::archive_key(room, position)).await? {
+                messages.push(serde_json::from_slice(&bytes)?);
+            }
         }
+        Ok(messages)
+    }
+
+    fn archive_key(room: &str, position: u64) -> String {
+        format!("chatroom_archive:{}:{:020}", room, position)
+    }
+
+    /// Trim a room down to the configured retention, archiving what is removed
+    async fn enforce_retention(&mut self, room: &str) -> Result<()> {
+        let Some(max) = self.config.max_messages_per_room else {
+            return Ok(());
+        };
+        let Some(chatroom) = self.chatrooms.get_mut(room) else {
+            return Ok(());
+        };
+        if chatroom.messages.len() <= max {
+            return Ok(());
+        }
+        let excess = chatroom.messages.len() - max;
+        self.prune_room(room, excess).await
+    }
+
+    /// Remove the `count` oldest messages of a room, archiving them
+    async fn prune_room(&mut self, room: &str, count: usize) -> Result<()> {
+        let Some(chatroom) = self.chatrooms.get_mut(room) else {
+            return Ok(());
+        };
+        let pruned: Vec<ChatMessage> = chatroom.messages.drain(..count).collect();
+        let first_position = chatroom.pruned_count;
+        chatroom.pruned_count += pruned.len() as u64;
+        for hash in pruned.iter().filter_map(|message| message.hash.as_ref()) {
+            chatroom.receipts.remove(hash);
+        }
+
+        if let Some(archive) = &self.archive {
+            for (i, message) in pruned.iter().enumerate() {
+                let key = Self::archive_key(room, first_position + i as u64);
+                archive.put(&key, serde_json::to_vec(message)?).await?;
+            }
+        }
+
+        tracing::debug!("Pruned {} messages from chatroom '{}'", count, room);
+        Ok(())
     }
 
     /// Get all chatrooms
@@ -119,8 +351,47 @@ impl ChatroomObject {
         self.chatrooms.get(name)
     }
 
-    /// Validate message signature
-    fn validate_signature(
+    /// Receipts of a retained post in a room
+    pub fn get_receipts(&self, room: &str, post_hash: &str) -> Option<&PostReceipts> {
+        self.chatrooms.get(room)?.receipts.get(post_hash)
+    }
+
+    /// Retained posts by others that `member` has not sent a read receipt for
+    ///
+    /// Covers every room the member belongs to, oldest post first per room.
+    pub fn get_unread(&self, member: &str) -> Vec<ChatMessage> {
+        let mut rooms: Vec<&Chatroom> = self
+            .chatrooms
+            .values()
+            .filter(|chatroom| chatroom.members.iter().any(|m| m == member))
+            .collect();
+        rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        rooms
+            .into_iter()
+            .flat_map(|chatroom| {
+                chatroom.messages.iter().filter(|message| {
+                    let read = message
+                        .hash
+                        .as_ref()
+                        .and_then(|hash| chatroom.receipts.get(hash))
+                        .is_some_and(|receipts| receipts.read.contains(member));
+                    message.message_type == "POST_MESSAGE"
+                        && message.public_key_pem != member
+                        && !read
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the message is signed by `public_key_pem`
+    ///
+    /// Signatures or keys that do not decode count as invalid.
+    fn validate_signature(&self, msg_data: &Value, signature: &str, public_key_pem: &str) -> bool {
+        matches!(self.verify_signature(msg_data, signature, public_key_pem), Ok(true))
+    }
+
+    fn verify_signature(
         &self,
         msg_data: &Value,
         signature: &str,
@@ -148,6 +419,80 @@ impl ChatroomObject {
             .verify(payload.as_bytes(), &ecdsa_sig, public_key_pem)
     }
 
+    /// Cheap checks deciding whether a message is gossiped on
+    ///
+    /// Only the signature and the room's membership are looked at; messages
+    /// for rooms this node does not know are kept local.
+    fn relay_check(&self, msg: &ChatroomMessageType, msg_data: &Value) -> RelayDecision {
+        let (chatroom_name, public_key_pem, signature) = match msg {
+            ChatroomMessageType::CreateChatroom {
+                chatroom_name,
+                public_key_pem,
+                signature,
+                ..
+            }
+            | ChatroomMessageType::RequestJoin {
+                chatroom_name,
+                public_key_pem,
+                signature,
+                ..
+            }
+            | ChatroomMessageType::AcceptMember {
+                chatroom_name,
+                public_key_pem,
+                signature,
+                ..
+            }
+            | ChatroomMessageType::PostMessage {
+                chatroom_name,
+                public_key_pem,
+                signature,
+                ..
+            }
+            | ChatroomMessageType::DeliveryReceipt {
+                chatroom_name,
+                public_key_pem,
+                signature,
+                ..
+            }
+            | ChatroomMessageType::ReadReceipt {
+                chatroom_name,
+                public_key_pem,
+                signature,
+                ..
+            } => (chatroom_name, public_key_pem, signature),
+        };
+
+        if !self.validate_signature(msg_data, signature, public_key_pem) {
+            return RelayDecision::veto(
+                "invalid_signature",
+                "signature does not match the sender key",
+            );
+        }
+        if let ChatroomMessageType::CreateChatroom { .. } = msg {
+            return RelayDecision::Relay;
+        }
+        let Some(chatroom) = self.chatrooms.get(chatroom_name) else {
+            return RelayDecision::veto(
+                "unknown_chatroom",
+                format!("chatroom '{}' is not hosted here", chatroom_name),
+            );
+        };
+        match msg {
+            ChatroomMessageType::AcceptMember { .. } if &chatroom.admin != public_key_pem => {
+                RelayDecision::veto("not_admin", "only the chatroom admin can accept members")
+            },
+            ChatroomMessageType::PostMessage { .. }
+            | ChatroomMessageType::DeliveryReceipt { .. }
+            | ChatroomMessageType::ReadReceipt { .. }
+                if !chatroom.members.contains(public_key_pem) =>
+            {
+                RelayDecision::veto("not_member", "only chatroom members can post messages")
+            },
+            _ => RelayDecision::Relay,
+        }
+    }
+
     /// Check if timestamp is recent (within 15 seconds)
     fn is_timestamp_recent(&self, timestamp: f64) -> bool {
         let now = SystemTime::now()
@@ -164,7 +509,7 @@ impl ChatroomObject {
         &mut self,
         msg: ChatroomMessageType,
         msg_data: &Value,
-    ) -> Result<bool> {
+    ) -> Result<ApplyOutcome> {
         if let ChatroomMessageType::CreateChatroom {
             chatroom_name,
             public_key_pem,
@@ -173,34 +518,38 @@ impl ChatroomObject {
         } = msg
         {
             // Validate signature
-            if !self.validate_signature(msg_data, &signature, &public_key_pem)? {
-                return Ok(false);
+            if !self.validate_signature(msg_data, &signature, &public_key_pem) {
+                return Ok(ApplyOutcome::rejected(
+                    "invalid_signature",
+                    "signature does not match the sender key",
+                ));
             }
 
             // Check timestamp
             if !self.is_timestamp_recent(timestamp) {
-                return Ok(false);
+                return Ok(ApplyOutcome::rejected(
+                    "stale_timestamp",
+                    "timestamp is not within the accepted window",
+                ));
             }
 
             // Check if chatroom already exists
             if self.chatrooms.contains_key(&chatroom_name) {
-                return Ok(false);
+                return Ok(ApplyOutcome::ignored(
+                    "chatroom_exists",
+                    format!("chatroom '{}' already exists", chatroom_name),
+                ));
             }
 
             // Create new chatroom
-            let chatroom = Chatroom {
-                name: chatroom_name.clone(),
-                admin: public_key_pem.clone(),
-                members: vec![public_key_pem.clone()], // Admin is automatically a member
-                messages: Vec::new(),
-            };
+            let chatroom = Chatroom::new(chatroom_name.clone(), public_key_pem.clone());
 
             self.chatrooms.insert(chatroom_name, chatroom);
             tracing::info!("Created chatroom with admin: {}", public_key_pem);
 
-            Ok(true)
+            Ok(ApplyOutcome::Applied)
         } else {
-            Ok(false)
+            Ok(unexpected_message())
         }
     }
 
@@ -209,7 +558,7 @@ impl ChatroomObject {
         &mut self,
         msg: ChatroomMessageType,
         msg_data: &Value,
-    ) -> Result<bool> {
+    ) -> Result<ApplyOutcome> {
         if let ChatroomMessageType::RequestJoin {
             chatroom_name,
             public_key_pem,
@@ -218,18 +567,27 @@ impl ChatroomObject {
         } = msg
         {
             // Validate signature
-            if !self.validate_signature(msg_data, &signature, &public_key_pem)? {
-                return Ok(false);
+            if !self.validate_signature(msg_data, &signature, &public_key_pem) {
+                return Ok(ApplyOutcome::rejected(
+                    "invalid_signature",
+                    "signature does not match the sender key",
+                ));
             }
 
             // Check timestamp
             if !self.is_timestamp_recent(timestamp) {
-                return Ok(false);
+                return Ok(ApplyOutcome::rejected(
+                    "stale_timestamp",
+                    "timestamp is not within the accepted window",
+                ));
             }
 
             // Check if chatroom exists
-            if !self.chatrooms.contains_key(&chatroom_name) {
-                return Ok(false);
+            let Some(chatroom) = self.chatrooms.get(&chatroom_name) else {
+                return Ok(unknown_chatroom(&chatroom_name));
+            };
+            if chatroom.contains_signature(&signature) {
+                return Ok(replayed_message());
             }
 
             // Add to pending requests (for now, just log)
@@ -249,13 +607,15 @@ impl ChatroomObject {
                     requester_key_pem: None,
                     timestamp,
                     signature,
+                    hash: None,
                 };
-                chatroom.messages.push(chat_msg);
+                chatroom.append(chat_msg);
             }
+            self.enforce_retention(&chatroom_name).await?;
 
-            Ok(true)
+            Ok(ApplyOutcome::Applied)
         } else {
-            Ok(false)
+            Ok(unexpected_message())
         }
     }
 
@@ -264,7 +624,7 @@ impl ChatroomObject {
         &mut self,
         msg: ChatroomMessageType,
         msg_data: &Value,
-    ) -> Result<bool> {
+    ) -> Result<ApplyOutcome> {
         if let ChatroomMessageType::AcceptMember {
             chatroom_name,
             public_key_pem,
@@ -274,19 +634,31 @@ impl ChatroomObject {
         } = msg
         {
             // Validate signature
-            if !self.validate_signature(msg_data, &signature, &public_key_pem)? {
-                return Ok(false);
+            if !self.validate_signature(msg_data, &signature, &public_key_pem) {
+                return Ok(ApplyOutcome::rejected(
+                    "invalid_signature",
+                    "signature does not match the sender key",
+                ));
             }
 
             // Check timestamp
             if !self.is_timestamp_recent(timestamp) {
-                return Ok(false);
+                return Ok(ApplyOutcome::rejected(
+                    "stale_timestamp",
+                    "timestamp is not within the accepted window",
+                ));
             }
 
             // Check if chatroom exists and sender is admin
             if let Some(chatroom) = self.chatrooms.get_mut(&chatroom_name) {
                 if chatroom.admin != public_key_pem {
-                    return Ok(false); // Only admin can accept members
+                    return Ok(ApplyOutcome::rejected(
+                        "not_admin",
+                        "only the chatroom admin can accept members",
+                    ));
+                }
+                if chatroom.contains_signature(&signature) {
+                    return Ok(replayed_message());
                 }
 
                 // Add member if not already present
@@ -308,15 +680,17 @@ impl ChatroomObject {
                     requester_key_pem: Some(requester_key_pem),
                     timestamp,
                     signature,
+                    hash: None,
                 };
-                chatroom.messages.push(chat_msg);
+                chatroom.append(chat_msg);
+                self.enforce_retention(&chatroom_name).await?;
 
-                Ok(true)
+                Ok(ApplyOutcome::Applied)
             } else {
-                Ok(false)
+                Ok(unknown_chatroom(&chatroom_name))
             }
         } else {
-            Ok(false)
+            Ok(unexpected_message())
         }
     }
 
@@ -325,7 +699,8 @@ impl ChatroomObject {
         &mut self,
         msg: ChatroomMessageType,
         msg_data: &Value,
-    ) -> Result<bool> {
+        hash: &str,
+    ) -> Result<ApplyOutcome> {
         if let ChatroomMessageType::PostMessage {
             chatroom_name,
             public_key_pem,
@@ -335,19 +710,31 @@ impl ChatroomObject {
         } = msg
         {
             // Validate signature
-            if !self.validate_signature(msg_data, &signature, &public_key_pem)? {
-                return Ok(false);
+            if !self.validate_signature(msg_data, &signature, &public_key_pem) {
+                return Ok(ApplyOutcome::rejected(
+                    "invalid_signature",
+                    "signature does not match the sender key",
+                ));
             }
 
             // Check timestamp
             if !self.is_timestamp_recent(timestamp) {
-                return Ok(false);
+                return Ok(ApplyOutcome::rejected(
+                    "stale_timestamp",
+                    "timestamp is not within the accepted window",
+                ));
             }
 
             // Check if chatroom exists and sender is a member
             if let Some(chatroom) = self.chatrooms.get_mut(&chatroom_name) {
                 if !chatroom.members.contains(&public_key_pem) {
-                    return Ok(false); // Only members can post messages
+                    return Ok(ApplyOutcome::rejected(
+                        "not_member",
+                        "only chatroom members can post messages",
+                    ));
+                }
+                if chatroom.contains_signature(&signature) {
+                    return Ok(replayed_message());
                 }
 
                 // Add the message
@@ -359,18 +746,115 @@ impl ChatroomObject {
                     requester_key_pem: None,
                     timestamp,
                     signature,
+                    hash: Some(hash.to_string()),
                 };
-                chatroom.messages.push(chat_msg);
+                chatroom.append(chat_msg);
+                chatroom
+                    .receipts
+                    .insert(hash.to_string(), PostReceipts::default());
 
                 tracing::info!("Message posted to '{}' by: {}", chatroom_name, public_key_pem);
+                self.enforce_retention(&chatroom_name).await?;
 
-                Ok(true)
+                Ok(ApplyOutcome::Applied)
             } else {
-                Ok(false)
+                Ok(unknown_chatroom(&chatroom_name))
             }
         } else {
-            Ok(false)
+            Ok(unexpected_message())
+        }
+    }
+
+    /// Process a delivery or read receipt for a post
+    async fn process_receipt(
+        &mut self,
+        msg: ChatroomMessageType,
+        msg_data: &Value,
+    ) -> Result<ApplyOutcome> {
+        let (kind, chatroom_name, public_key_pem, in_reply_to, timestamp, signature) = match msg {
+            ChatroomMessageType::DeliveryReceipt {
+                chatroom_name,
+                public_key_pem,
+                in_reply_to,
+                timestamp,
+                signature,
+            } => (
+                ReceiptKind::Delivered,
+                chatroom_name,
+                public_key_pem,
+                in_reply_to,
+                timestamp,
+                signature,
+            ),
+            ChatroomMessageType::ReadReceipt {
+                chatroom_name,
+                public_key_pem,
+                in_reply_to,
+                timestamp,
+                signature,
+            } => (
+                ReceiptKind::Read,
+                chatroom_name,
+                public_key_pem,
+                in_reply_to,
+                timestamp,
+                signature,
+            ),
+            _ => return Ok(unexpected_message()),
+        };
+
+        // Validate signature
+        if !self.validate_signature(msg_data, &signature, &public_key_pem) {
+            return Ok(ApplyOutcome::rejected(
+                "invalid_signature",
+                "signature does not match the sender key",
+            ));
+        }
+
+        // Check timestamp
+        if !self.is_timestamp_recent(timestamp) {
+            return Ok(ApplyOutcome::rejected(
+                "stale_timestamp",
+                "timestamp is not within the accepted window",
+            ));
+        }
+
+        let Some(chatroom) = self.chatrooms.get_mut(&chatroom_name) else {
+            return Ok(unknown_chatroom(&chatroom_name));
+        };
+        if !chatroom.members.contains(&public_key_pem) {
+            return Ok(ApplyOutcome::rejected(
+                "not_member",
+                "only chatroom members can acknowledge posts",
+            ));
         }
+        let Some(receipts) = chatroom.receipts.get_mut(&in_reply_to) else {
+            return Ok(ApplyOutcome::rejected(
+                "unknown_post",
+                format!("no retained post {} in '{}'", in_reply_to, chatroom_name),
+            ));
+        };
+
+        let delivered = receipts.delivered.insert(public_key_pem.clone());
+        let read = kind == ReceiptKind::Read && receipts.read.insert(public_key_pem);
+        if !delivered && !read {
+            return Ok(ApplyOutcome::ignored(
+                "duplicate_receipt",
+                format!("post {} was already acknowledged", in_reply_to),
+            ));
+        }
+        Ok(ApplyOutcome::Applied)
+    }
+}
+
+impl std::fmt::Debug for ChatroomObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChatroomObject")
+            .field("id", &self.id)
+            .field("chatrooms", &self.chatrooms)
+            .field("config", &self.config)
+            .field("archived", &self.archive.is_some())
+            .finish()
     }
 }
 
@@ -380,6 +864,22 @@ impl Default for ChatroomObject {
     }
 }
 
+fn unknown_chatroom(chatroom_name: &str) -> ApplyOutcome {
+    ApplyOutcome::rejected(
+        "unknown_chatroom",
+        format!("chatroom '{}' does not exist", chatroom_name),
+    )
+}
+
+/// Signed content seen before; the timestamp makes repeated text differ
+fn replayed_message() -> ApplyOutcome {
+    ApplyOutcome::ignored("replayed_message", "the message was already applied")
+}
+
+fn unexpected_message() -> ApplyOutcome {
+    ApplyOutcome::rejected("unexpected_message", "message kind does not match handler")
+}
+
 #[async_trait]
 impl ApplicationObject for ChatroomObject {
     fn id(&self) -> &SharedObjectId {
@@ -397,13 +897,13 @@ impl ApplicationObject for ChatroomObject {
         Ok(msg_result.is_ok())
     }
 
-    async fn add_message(&mut self, message: SharedMessage) -> Result<()> {
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
         let msg: ChatroomMessageType =
             serde_json::from_value(message.data.clone()).map_err(|e| {
                 ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
             })?;
 
-        let processed = match &msg {
+        let outcome = match &msg {
             ChatroomMessageType::CreateChatroom { .. } => {
                 self.process_create_chatroom(msg.clone(), &message.data)
                     .await?
@@ -417,18 +917,31 @@ impl ApplicationObject for ChatroomObject {
                     .await?
             },
             ChatroomMessageType::PostMessage { .. } => {
-                self.process_post_message(msg.clone(), &message.data)
+                self.process_post_message(msg.clone(), &message.data, &message.hash)
                     .await?
             },
+            ChatroomMessageType::DeliveryReceipt { .. }
+            | ChatroomMessageType::ReadReceipt { .. } => {
+                self.process_receipt(msg.clone(), &message.data).await?
+            },
         };
 
-        if processed {
-            tracing::debug!("Successfully processed chatroom message: {:?}", msg);
-        } else {
-            tracing::warn!("Failed to process chatroom message: {:?}", msg);
+        if outcome.is_applied() {
+            self.accumulator.apply(&message);
+        }
+        match outcome.reason() {
+            None => tracing::debug!("Successfully processed chatroom message: {:?}", msg),
+            Some(reason) => tracing::debug!("Chatroom message not applied ({}): {:?}", reason, msg),
         }
 
-        Ok(())
+        Ok(outcome)
+    }
+
+    async fn relay_policy(&self, message: &SharedMessage) -> Result<RelayDecision> {
+        match serde_json::from_value::<ChatroomMessageType>(message.data.clone()) {
+            Ok(msg) => Ok(self.relay_check(&msg, &message.data)),
+            Err(_) => Ok(RelayDecision::Relay),
+        }
     }
 
     fn is_merkleized(&self) -> bool {
@@ -436,47 +949,81 @@ impl ApplicationObject for ChatroomObject {
     }
 
     async fn get_latest_digest(&self) -> Result<String> {
-        let mut hasher = Sha256::new();
-
-        for (room_name, chatroom) in &self.chatrooms {
-            hasher.update(room_name.as_bytes());
-            hasher.update(chatroom.messages.len().to_le_bytes());
-        }
-
-        Ok(hex::encode(hasher.finalize()))
+        Ok(self.accumulator.digest().to_string())
     }
 
-    async fn has_digest(&self, _digest: &str) -> Result<bool> {
-        Ok(false) // Simplified implementation
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self.accumulator.contains(digest))
     }
 
     async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
         Ok(true)
     }
 
-    async fn add_digest(&mut self, _digest: String) -> Result<bool> {
-        Ok(true)
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
     }
 
-    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new()) // Simplified implementation
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
     }
 
-    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new()) // Simplified implementation
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.accumulator
+            .messages_since(digest)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown digest {}", digest)))
     }
 
     async fn get_state(&self) -> Result<Value> {
         let state = serde_json::json!({
             "chatroom_count": self.chatrooms.len(),
             "chatrooms": self.chatrooms.keys().collect::<Vec<_>>(),
-            "total_messages": self.chatrooms.values().map(|c| c.messages.len()).sum::<usize>()
+            "total_messages": self.chatrooms.values().map(|c| c.messages.len()).sum::<usize>(),
+            "pruned_messages": self.chatrooms.values().map(|c| c.pruned_count).sum::<u64>()
         });
         Ok(state)
     }
 
+    /// Posts by chatroom name, so history survives pruning of the in-memory rooms
+    fn indexes(&self) -> Vec<IndexSpec> {
+        vec![IndexSpec::new(CHATROOM_POSTS_INDEX, |message| {
+            match serde_json::from_value(message.data.clone()) {
+                Ok(ChatroomMessageType::PostMessage { chatroom_name, .. }) => {
+                    vec![IndexKey::Text(chatroom_name)]
+                },
+                _ => Vec::new(),
+            }
+        })]
+    }
+
+    /// Prune the oldest messages of every room, archiving them as the
+    /// configured retention does
+    async fn compact(&mut self, compaction: &Compaction) -> Result<usize> {
+        let mut dropped = 0;
+        let rooms: Vec<String> = self.chatrooms.keys().cloned().collect();
+        for room in rooms {
+            let count = compaction.prefix_to_drop(self.chatrooms[&room].messages.iter().map(
+                |message| {
+                    DateTime::<Utc>::from_timestamp_millis((message.timestamp * 1000.0) as i64)
+                        .unwrap_or_default()
+                },
+            ));
+            if count > 0 {
+                self.prune_room(&room, count).await?;
+                dropped += count;
+            }
+        }
+        Ok(dropped)
+    }
+
     async fn reset(&mut self) -> Result<()> {
         self.chatrooms.clear();
+        self.history.clear();
+        self.accumulator.reset();
         Ok(())
     }
 
@@ -500,29 +1047,13 @@ pub mod helpers {
 
     /// Create a create chatroom message
     pub fn create_chatroom_message(chatroom_name: String, signer: &ECDSASigner) -> Result<Value> {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-
-        let public_key_pem = signer.get_public_key_pem()?;
-
-        let mut msg = serde_json::json!({
+        let msg = serde_json::json!({
             "message_type": "CREATE_CHATROOM",
             "chatroom_name": chatroom_name,
-            "public_key_pem": public_key_pem,
-            "timestamp": timestamp
+            "public_key_pem": signer.get_public_key_pem()?,
+            "timestamp": now(),
         });
-
-        // Sign the message
-        let payload = serde_json::to_string(&msg).map_err(|e| {
-            ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
-        })?;
-        let signature = signer.sign(payload.as_bytes())?;
-
-        msg["signature"] = serde_json::Value::String(hex::encode(signature.to_bytes()));
-
-        Ok(msg)
+        sign(msg, signer)
     }
 
     /// Create a post message
@@ -531,29 +1062,63 @@ pub mod helpers {
         text: String,
         signer: &ECDSASigner,
     ) -> Result<Value> {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-
-        let public_key_pem = signer.get_public_key_pem()?;
-
-        let mut msg = serde_json::json!({
+        let msg = serde_json::json!({
             "message_type": "POST_MESSAGE",
             "chatroom_name": chatroom_name,
-            "public_key_pem": public_key_pem,
+            "public_key_pem": signer.get_public_key_pem()?,
             "text": text,
-            "timestamp": timestamp
+            "timestamp": now(),
         });
+        sign(msg, signer)
+    }
 
-        // Sign the message
+    /// Create an admin's message accepting a member into a chatroom
+    pub fn create_accept_member_message(
+        chatroom_name: String,
+        requester_key_pem: String,
+        signer: &ECDSASigner,
+    ) -> Result<Value> {
+        let msg = serde_json::json!({
+            "message_type": "ACCEPT_MEMBER",
+            "chatroom_name": chatroom_name,
+            "public_key_pem": signer.get_public_key_pem()?,
+            "requester_key_pem": requester_key_pem,
+            "timestamp": now(),
+        });
+        sign(msg, signer)
+    }
+
+    /// Create a delivery or read receipt for the post with message hash `in_reply_to`
+    pub fn create_receipt_message(
+        chatroom_name: String,
+        in_reply_to: String,
+        kind: ReceiptKind,
+        signer: &ECDSASigner,
+    ) -> Result<Value> {
+        let msg = serde_json::json!({
+            "message_type": kind.message_type(),
+            "chatroom_name": chatroom_name,
+            "public_key_pem": signer.get_public_key_pem()?,
+            "in_reply_to": in_reply_to,
+            "timestamp": now(),
+        });
+        sign(msg, signer)
+    }
+
+    fn now() -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64()
+    }
+
+    /// Add the signature over the message without it
+    fn sign(mut msg: Value, signer: &ECDSASigner) -> Result<Value> {
         let payload = serde_json::to_string(&msg).map_err(|e| {
             ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
         })?;
         let signature = signer.sign(payload.as_bytes())?;
-
         msg["signature"] = serde_json::Value::String(hex::encode(signature.to_bytes()));
-
         Ok(msg)
     }
 }
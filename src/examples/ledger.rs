@@ -0,0 +1,529 @@
+//! Account ledger in the style of Ethereum
+//!
+//! Accounts are addressed by `0x` and the last 20 bytes of the Keccak-256 hash
+//! of their public key, and hold a balance and a nonce. Balances start from a
+//! genesis allocation; afterwards coins only move through [`Transfer`]s, each
+//! signed by the sending account's key and carrying the sender's next nonce,
+//! so a transfer applies at most once and a sender's transfers apply in the
+//! order they were made.
+//!
+//! Gossip may deliver a sender's transfers out of order. A transfer with a
+//! nonce ahead of the account's is held and applied once the gap is filled;
+//! its message is reported ignored with reason `future_nonce` meanwhile.
+//! Only transfers the sender's balance covers, together with its other held
+//! transfers, are held, at most [`MAX_PENDING_PER_ACCOUNT`] per sender and
+//! [`MAX_PENDING_TRANSFERS`] in all.
+//!
+//! The state root is a [`MerkleTree`] over the accounts in address order, so
+//! one account's balance can be proven against the root with
+//! [`LedgerObject::account_proof`] and [`Account::is_proven_by`].
+//!
+//! Every applied transfer is charged [`TRANSFER_GAS`] and emits a `Transfer`
+//! event in the message's [`crate::receipt::Receipt`]. Held transfers are
+//! charged to the message whose nonce let them apply.
+//!
+//! Test accounts can come from one mnemonic with [`wallet_accounts`], the
+//! same keys on every run.
+//!
+//! ```ignore
+//! let ledger = LedgerObject::with_genesis([(address_of(&alice.public_key()), 100)]);
+//! let id = node.add_shared_object(Box::new(ledger)).await?;
+//! let transfer = Transfer::signed(&alice, &bob_address, 25, 0)?;
+//! node.create_shared_message_with_data(transfer.to_message()).await?;
+//! ```
+
+use crate::{
+    codec::consensus::ConsensusHasher,
+    crypto::{hash::keccak256, hd::HdWallet, KeyType, PrivateKey, PublicKey, Signature},
+    error::{ChaincraftError, Result},
+    receipt::Execution,
+    shared::{DigestHistory, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, ConfigurableObject, RelayDecision},
+    sync::merkle::{MerkleProof, MerkleTree, MerkleizedObject},
+};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Transfers held per sender while waiting for an earlier nonce
+pub const MAX_PENDING_PER_ACCOUNT: usize = 64;
+
+/// Transfers held across all senders
+pub const MAX_PENDING_TRANSFERS: usize = 4096;
+
+/// Gas charged for each applied transfer
+pub const TRANSFER_GAS: u64 = 21_000;
+
+/// Ledger message types
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum LedgerMessage {
+    /// Move coins from the signing account to another
+    Transfer(Transfer),
+}
+
+/// JSON Schemas of the ledger's messages, by type name
+pub fn schemas() -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([("LedgerMessage", super::schema_of::<LedgerMessage>())])
+}
+
+/// Address of the account controlled by `public_key`
+pub fn address_of(public_key: &PublicKey) -> String {
+    let hash = keccak256(&public_key.as_bytes());
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// Keys and addresses of the first `count` accounts of an HD wallet
+pub fn wallet_accounts(wallet: &HdWallet, count: u32) -> Result<Vec<(PrivateKey, String)>> {
+    Ok(wallet
+        .keys(count)?
+        .into_iter()
+        .map(|key| {
+            let address = address_of(&key.public_key());
+            (key, address)
+        })
+        .collect())
+}
+
+fn is_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Signed order to move `amount` from `from` to `to`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Transfer {
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    /// Number of transfers `from` made before this one
+    pub nonce: u64,
+    /// Hex public key behind `from`
+    pub public_key: String,
+    /// Hex signature over [`Transfer::signing_bytes`]
+    pub signature: String,
+}
+
+impl Transfer {
+    /// Create a transfer from the account of `private_key`, signed
+    pub fn signed(private_key: &PrivateKey, to: &str, amount: u64, nonce: u64) -> Result<Self> {
+        let public_key = private_key.public_key();
+        let mut transfer = Self {
+            from: address_of(&public_key),
+            to: to.to_string(),
+            amount,
+            nonce,
+            public_key: public_key.to_hex(),
+            signature: String::new(),
+        };
+        transfer.signature = private_key.sign(&transfer.signing_bytes())?.to_hex();
+        Ok(transfer)
+    }
+
+    /// Bytes the signature covers: every other field, canonically encoded
+    pub fn signing_bytes(&self) -> [u8; 32] {
+        ConsensusHasher::new()
+            .str("ledger-transfer")
+            .str(&self.from)
+            .str(&self.to)
+            .u64(self.amount)
+            .u64(self.nonce)
+            .str(&self.public_key)
+            .finish()
+    }
+
+    /// Check the addresses and the signature
+    ///
+    /// Returns the reason code and detail of the first check that fails.
+    pub fn verify(&self) -> std::result::Result<(), (&'static str, String)> {
+        if !is_address(&self.to) {
+            return Err(("invalid_address", format!("{} is not an address", self.to)));
+        }
+        let key_type = KeyType::of_public_key_hex(&self.public_key);
+        let public_key = PublicKey::from_hex(&self.public_key, key_type)
+            .map_err(|e| ("invalid_public_key", e.to_string()))?;
+        if address_of(&public_key) != self.from {
+            return Err(("wrong_sender", format!("key does not control {}", self.from)));
+        }
+        let verified = Signature::from_hex(&self.signature, key_type)
+            .and_then(|signature| public_key.verify(&self.signing_bytes(), &signature))
+            .unwrap_or(false);
+        if !verified {
+            return Err(("invalid_signature", "signature does not verify".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Message data carrying the transfer
+    pub fn to_message(&self) -> Value {
+        serde_json::to_value(LedgerMessage::Transfer(self.clone()))
+            .expect("transfers serialize to JSON")
+    }
+}
+
+/// Balance and nonce of an account
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    pub balance: u64,
+    /// Nonce the account's next transfer must carry
+    pub nonce: u64,
+}
+
+impl Account {
+    /// Hash of the account at `address`, a leaf of the state root
+    pub fn leaf(&self, address: &str) -> String {
+        ConsensusHasher::new()
+            .str("account")
+            .str(address)
+            .u64(self.balance)
+            .u64(self.nonce)
+            .finish_hex()
+    }
+
+    /// Whether `proof` shows this account is at `address` under `state_root`
+    pub fn is_proven_by(&self, address: &str, proof: &MerkleProof, state_root: &str) -> bool {
+        proof.proves(&self.leaf(address)) && proof.verify(state_root)
+    }
+}
+
+/// Settings a ledger is built from, see [`ConfigurableObject`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LedgerConfig {
+    /// Balance of each address at genesis
+    pub genesis: BTreeMap<String, u64>,
+}
+
+/// Account ledger object
+#[derive(Debug, Clone)]
+pub struct LedgerObject {
+    pub id: SharedObjectId,
+    genesis: BTreeMap<String, Account>,
+    accounts: BTreeMap<String, Account>,
+    /// Transfers waiting for an earlier nonce, by sender and nonce
+    pending: BTreeMap<String, BTreeMap<u64, (Transfer, SharedMessage)>>,
+    merkle: MerkleizedObject,
+    history: DigestHistory,
+    /// Gas and events of the message being applied
+    execution: Execution,
+}
+
+impl LedgerObject {
+    /// Create a ledger without any coins
+    pub fn new() -> Self {
+        Self::with_genesis([])
+    }
+
+    /// Create a ledger that starts with the given balances
+    pub fn with_genesis(balances: impl IntoIterator<Item = (String, u64)>) -> Self {
+        let genesis: BTreeMap<String, Account> = balances
+            .into_iter()
+            .map(|(address, balance)| (address, Account { balance, nonce: 0 }))
+            .collect();
+        Self {
+            id: SharedObjectId::new(),
+            accounts: genesis.clone(),
+            genesis,
+            pending: BTreeMap::new(),
+            merkle: MerkleizedObject::new(),
+            history: DigestHistory::new(),
+            execution: Execution::default(),
+        }
+    }
+
+    /// Account at `address`; unknown addresses are empty accounts
+    pub fn account(&self, address: &str) -> Account {
+        self.accounts.get(address).copied().unwrap_or_default()
+    }
+
+    pub fn balance(&self, address: &str) -> u64 {
+        self.account(address).balance
+    }
+
+    /// Nonce the next transfer from `address` must carry
+    pub fn nonce(&self, address: &str) -> u64 {
+        self.account(address).nonce
+    }
+
+    /// Sum of all balances, which transfers never change
+    pub fn total_supply(&self) -> u64 {
+        self.accounts.values().map(|account| account.balance).sum()
+    }
+
+    /// Accounts with a balance or a transfer made, by address
+    pub fn accounts(&self) -> &BTreeMap<String, Account> {
+        &self.accounts
+    }
+
+    /// Number of transfers held for an earlier nonce
+    pub fn pending_count(&self) -> usize {
+        self.pending.values().map(BTreeMap::len).sum()
+    }
+
+    fn state_tree(&self) -> MerkleTree {
+        let mut tree = MerkleTree::new();
+        for (address, account) in &self.accounts {
+            tree.push(&account.leaf(address));
+        }
+        tree
+    }
+
+    /// Merkle root over every account in address order
+    pub fn state_root(&self) -> String {
+        self.state_tree().root().to_string()
+    }
+
+    /// Proof that the current state of `address` is part of the state root
+    ///
+    /// `None` for an address the ledger holds no account for.
+    pub fn account_proof(&self, address: &str) -> Option<MerkleProof> {
+        let index = self.accounts.keys().position(|a| a == address)?;
+        self.state_tree().proof(index)
+    }
+
+    /// Move the coins of a verified transfer carrying the sender's nonce
+    fn execute(&mut self, transfer: &Transfer) -> ApplyOutcome {
+        if transfer.amount == 0 {
+            return ApplyOutcome::rejected("zero_amount", "nothing to transfer");
+        }
+        let sender = self.account(&transfer.from);
+        if sender.balance < transfer.amount {
+            return ApplyOutcome::rejected(
+                "insufficient_balance",
+                format!("{} holds {}", transfer.from, sender.balance),
+            );
+        }
+        let mut accounts = self.accounts.clone();
+        let from = accounts.entry(transfer.from.clone()).or_default();
+        from.balance -= transfer.amount;
+        from.nonce += 1;
+        let to = accounts.entry(transfer.to.clone()).or_default();
+        let Some(balance) = to.balance.checked_add(transfer.amount) else {
+            return ApplyOutcome::rejected("balance_overflow", "recipient balance overflows");
+        };
+        to.balance = balance;
+        self.accounts = accounts;
+        self.execution.charge(TRANSFER_GAS);
+        self.execution.emit(
+            "Transfer",
+            serde_json::json!({
+                "from": transfer.from,
+                "to": transfer.to,
+                "amount": transfer.amount,
+                "nonce": transfer.nonce,
+            }),
+        );
+        ApplyOutcome::Applied
+    }
+
+    /// Apply held transfers of `sender` whose turn has come
+    fn apply_pending(&mut self, sender: &str) {
+        loop {
+            let nonce = self.nonce(sender);
+            let Some((transfer, message)) = self
+                .pending
+                .get_mut(sender)
+                .and_then(|held| held.remove(&nonce))
+            else {
+                break;
+            };
+            match self.execute(&transfer) {
+                ApplyOutcome::Applied => {
+                    self.merkle.apply(&message);
+                },
+                outcome => {
+                    tracing::debug!("Dropping held transfer {}: {:?}", message.hash, outcome);
+                    break;
+                },
+            }
+        }
+        if self.pending.get(sender).is_some_and(BTreeMap::is_empty) {
+            self.pending.remove(sender);
+        }
+    }
+}
+
+impl Default for LedgerObject {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigurableObject for LedgerObject {
+    type Config = LedgerConfig;
+
+    fn from_config(config: LedgerConfig) -> Result<Self> {
+        if let Some(address) = config.genesis.keys().find(|address| !is_address(address)) {
+            return Err(ChaincraftError::config(format!(
+                "genesis address {} is not an address",
+                address
+            )));
+        }
+        Ok(Self::with_genesis(config.genesis))
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for LedgerObject {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Ledger"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(serde_json::from_value::<LedgerMessage>(message.data.clone()).is_ok())
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        let LedgerMessage::Transfer(transfer) =
+            match serde_json::from_value::<LedgerMessage>(message.data.clone()) {
+                Ok(msg) => msg,
+                Err(e) => return Ok(ApplyOutcome::rejected("malformed", e.to_string())),
+            };
+        if let Err((code, detail)) = transfer.verify() {
+            return Ok(ApplyOutcome::rejected(code, detail));
+        }
+
+        let expected = self.nonce(&transfer.from);
+        if transfer.nonce < expected {
+            return Ok(ApplyOutcome::ignored(
+                "stale_nonce",
+                format!("{} is at nonce {}", transfer.from, expected),
+            ));
+        }
+        if transfer.nonce > expected {
+            let balance = self.balance(&transfer.from);
+            let total = self.pending_count();
+            let held = self.pending.entry(transfer.from.clone()).or_default();
+            let replaces = held.contains_key(&transfer.nonce);
+            if held.len() >= MAX_PENDING_PER_ACCOUNT && !replaces {
+                return Ok(ApplyOutcome::rejected(
+                    "too_many_pending",
+                    format!("{} transfers of {} already held", held.len(), transfer.from),
+                ));
+            }
+            if total >= MAX_PENDING_TRANSFERS && !replaces {
+                return Ok(ApplyOutcome::rejected(
+                    "pending_full",
+                    format!("{} transfers already held", total),
+                ));
+            }
+            let committed = held
+                .iter()
+                .filter(|(nonce, _)| **nonce != transfer.nonce)
+                .fold(transfer.amount, |sum, (_, (other, _))| sum.saturating_add(other.amount));
+            if committed > balance {
+                if held.is_empty() {
+                    self.pending.remove(&transfer.from);
+                }
+                return Ok(ApplyOutcome::rejected(
+                    "insufficient_balance",
+                    format!(
+                        "{} holds {} but its held transfers need {}",
+                        transfer.from, balance, committed
+                    ),
+                ));
+            }
+            held.insert(transfer.nonce, (transfer, message));
+            return Ok(ApplyOutcome::ignored(
+                "future_nonce",
+                format!("held until nonce {} arrives", expected),
+            ));
+        }
+
+        let outcome = self.execute(&transfer);
+        if outcome.is_applied() {
+            self.merkle.apply(&message);
+            self.apply_pending(&transfer.from);
+        }
+        Ok(outcome)
+    }
+
+    async fn relay_policy(&self, message: &SharedMessage) -> Result<RelayDecision> {
+        match serde_json::from_value::<LedgerMessage>(message.data.clone()) {
+            Ok(LedgerMessage::Transfer(transfer)) => Ok(match transfer.verify() {
+                Ok(()) => RelayDecision::Relay,
+                Err((code, detail)) => RelayDecision::veto(code, detail),
+            }),
+            Err(_) => Ok(RelayDecision::Relay),
+        }
+    }
+
+    fn take_execution(&mut self) -> Execution {
+        std::mem::take(&mut self.execution)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        true
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.merkle.digest().to_string())
+    }
+
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self.merkle.contains(digest))
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
+    }
+
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
+    }
+
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.merkle.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.merkle.messages_since_or_err(digest)
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "accounts": self.accounts,
+            "state_root": self.state_root(),
+            "total_supply": self.total_supply(),
+            "pending_transfers": self.pending_count(),
+        }))
+    }
+
+    /// Restores the accounts; held transfers are not part of the state
+    async fn load_state(&mut self, state: Value) -> Result<()> {
+        self.accounts = serde_json::from_value(state["accounts"].clone())?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.accounts = self.genesis.clone();
+        self.pending.clear();
+        self.merkle.reset();
+        self.history.clear();
+        self.execution = Execution::default();
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
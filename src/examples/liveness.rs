@@ -0,0 +1,159 @@
+//! Validator liveness tracking shared by the consensus examples
+//!
+//! Each duty a validator was expected to perform (proposing or voting on a
+//! Tendermint height, contributing to a beacon round) is recorded as done or
+//! missed in a sliding window. A validator that missed more than the
+//! configured fraction of a full window is jailed: the consensus objects stop
+//! counting it towards proposer rotation and thresholds until it sends an
+//! unjail message, which is only accepted once the cooldown has passed.
+//!
+//! Positions (`at`, `now`) are heights for Tendermint and rounds for the
+//! beacon, so the cooldown is measured in the object's own progress.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// When validators get jailed and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LivenessConfig {
+    /// Number of most recent duties considered
+    pub window: usize,
+    /// Jail when more than this fraction of a full window was missed
+    pub max_missed_fraction: f64,
+    /// Heights or rounds a jailed validator must wait before unjailing
+    pub jail_cooldown: u64,
+}
+
+impl LivenessConfig {
+    pub fn new(window: usize, max_missed_fraction: f64, jail_cooldown: u64) -> Self {
+        Self {
+            window: window.max(1),
+            max_missed_fraction: max_missed_fraction.clamp(0.0, 1.0),
+            jail_cooldown,
+        }
+    }
+}
+
+impl Default for LivenessConfig {
+    fn default() -> Self {
+        Self::new(100, 0.5, 10)
+    }
+}
+
+/// Participation record of one validator
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorLiveness {
+    /// Most recent duties, `true` where the validator performed them
+    pub recent: VecDeque<bool>,
+    /// Duties missed since tracking started
+    pub missed: u64,
+    /// Position at which the validator was jailed
+    pub jailed_at: Option<u64>,
+}
+
+impl ValidatorLiveness {
+    fn missed_in_window(&self) -> usize {
+        self.recent.iter().filter(|done| !**done).count()
+    }
+}
+
+/// Sliding-window participation of every validator
+#[derive(Debug, Clone, Default)]
+pub struct LivenessTracker {
+    pub config: LivenessConfig,
+    validators: HashMap<String, ValidatorLiveness>,
+}
+
+impl LivenessTracker {
+    pub fn new(config: LivenessConfig) -> Self {
+        Self {
+            config,
+            validators: HashMap::new(),
+        }
+    }
+
+    /// Record whether `validator` performed a duty at position `at`
+    ///
+    /// Returns `true` if this record got the validator jailed. Duties of
+    /// validators already in jail are not recorded.
+    pub fn record(&mut self, validator: &str, performed: bool, at: u64) -> bool {
+        let config = self.config;
+        let entry = self.validators.entry(validator.to_string()).or_default();
+        if entry.jailed_at.is_some() {
+            return false;
+        }
+        if !performed {
+            entry.missed += 1;
+        }
+        entry.recent.push_back(performed);
+        while entry.recent.len() > config.window {
+            entry.recent.pop_front();
+        }
+
+        let missed = entry.missed_in_window() as f64;
+        if entry.recent.len() == config.window
+            && missed > config.max_missed_fraction * config.window as f64
+        {
+            entry.jailed_at = Some(at);
+            entry.recent.clear();
+            tracing::info!(
+                "Jailed validator {} at {} for missing {} duties",
+                validator,
+                at,
+                missed
+            );
+            return true;
+        }
+        false
+    }
+
+    pub fn is_jailed(&self, validator: &str) -> bool {
+        self.jailed_until(validator).is_some()
+    }
+
+    /// First position at which a jailed validator may unjail
+    pub fn jailed_until(&self, validator: &str) -> Option<u64> {
+        self.validators
+            .get(validator)?
+            .jailed_at
+            .map(|at| at + self.config.jail_cooldown)
+    }
+
+    /// Release a jailed validator once the cooldown is over
+    ///
+    /// Returns `false` if the validator is not jailed or still cooling down.
+    /// The validator starts over with an empty window.
+    pub fn unjail(&mut self, validator: &str, now: u64) -> bool {
+        match self.jailed_until(validator) {
+            Some(until) if now >= until => {
+                if let Some(entry) = self.validators.get_mut(validator) {
+                    entry.jailed_at = None;
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Participation record of a validator, if it ever had a duty
+    pub fn validator(&self, validator: &str) -> Option<&ValidatorLiveness> {
+        self.validators.get(validator)
+    }
+
+    /// Currently jailed validators, sorted
+    pub fn jailed(&self) -> Vec<String> {
+        let mut jailed: Vec<String> = self
+            .validators
+            .iter()
+            .filter(|(_, v)| v.jailed_at.is_some())
+            .map(|(address, _)| address.clone())
+            .collect();
+        jailed.sort();
+        jailed
+    }
+
+    /// Forget all participation and release every validator
+    pub fn clear(&mut self) {
+        self.validators.clear();
+    }
+}
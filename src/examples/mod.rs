@@ -1,3 +1,30 @@
+//! Example applications built on shared objects
+//!
+//! Each example's message enum has a JSON Schema so clients written in other
+//! languages can build valid messages; [`schemas`] collects them.
+
+use std::collections::BTreeMap;
+
 pub mod chatroom;
+pub mod ledger;
+pub mod liveness;
+pub mod pow_chain;
 pub mod randomness_beacon;
 pub mod tendermint;
+
+/// JSON Schemas of every example's messages, by example and type name
+///
+/// The output is deterministic, so it can be committed and diffed.
+pub fn schemas() -> BTreeMap<&'static str, BTreeMap<&'static str, serde_json::Value>> {
+    BTreeMap::from([
+        ("chatroom", chatroom::schemas()),
+        ("ledger", ledger::schemas()),
+        ("pow_chain", pow_chain::schemas()),
+        ("randomness_beacon", randomness_beacon::schemas()),
+        ("tendermint", tendermint::schemas()),
+    ])
+}
+
+fn schema_of<T: schemars::JsonSchema>() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(T)).expect("schemas serialize to JSON")
+}
@@ -0,0 +1,670 @@
+//! Proof-of-work blockchain with Nakamoto consensus
+//!
+//! The counterpart of the Tendermint example: anyone may extend the chain by
+//! mining a block, a hash over the block header below the difficulty target
+//! found with [`ProofOfWork`]. Forks happen when two miners find a block at
+//! the same height; every node follows the branch with the most accumulated
+//! work, the first one seen on a tie, and switches branches (a reorg) as soon
+//! as another one overtakes it.
+//!
+//! Difficulty is the number of leading zero hex digits of the block hash, so
+//! a block at difficulty `d` stands for `16^d` hashes of work. It follows
+//! block times with a [`DifficultyAdjuster`]: Bitcoin-style every
+//! [`PowChainConfig::retarget_interval`] blocks, or every block with ASERT
+//! when [`PowChainConfig::asert_half_life_secs`] is set.
+//!
+//! Blocks whose parent is not known yet are held as orphans and connected
+//! once the parent arrives, so gossip may deliver a branch in any order. When
+//! [`PowChainConfig::max_orphans`] are held the oldest orphan makes room.
+//!
+//! A block's timestamp must be later than the median of recent blocks and at
+//! most [`PowChainConfig::max_time_ahead_blocks`] target block times past it,
+//! so a miner cannot lower the difficulty with a timestamp far in the future.
+
+use crate::{
+    codec::consensus::ConsensusHasher,
+    crypto::pow::{
+        BlockTiming, DifficultyAdjuster, PoWChallenge, PoWProof, ProofOfWork, MAX_DIFFICULTY,
+    },
+    crypto::KeylessCryptoPrimitive,
+    error::{ChaincraftError, Result},
+    shared::{DigestAccumulator, DigestHistory, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, ConfigurableObject},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Number of recent blocks whose median timestamp a new block must exceed
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Proof-of-work chain message types
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum PowChainMessage {
+    /// A mined block
+    Block(PowBlock),
+}
+
+/// JSON Schemas of the chain's messages, by type name
+pub fn schemas() -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([("PowChainMessage", super::schema_of::<PowChainMessage>())])
+}
+
+/// Mined block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct PowBlock {
+    pub height: u64,
+    /// Hash of the parent block
+    pub parent: String,
+    pub timestamp: DateTime<Utc>,
+    /// Leading zero hex digits the hash must have
+    pub difficulty: u32,
+    pub miner: String,
+    /// Application payloads the block orders
+    pub transactions: Vec<Value>,
+    /// Nonce that solves the header puzzle
+    pub nonce: u64,
+    /// Hash of the header and nonce
+    pub hash: String,
+}
+
+impl PowBlock {
+    /// First block of every chain built with `config`
+    pub fn genesis(config: &PowChainConfig) -> Self {
+        let mut block = Self {
+            height: 0,
+            parent: String::new(),
+            timestamp: DateTime::<Utc>::UNIX_EPOCH,
+            difficulty: config.initial_difficulty,
+            miner: String::new(),
+            transactions: Vec::new(),
+            nonce: 0,
+            hash: String::new(),
+        };
+        // Genesis is not mined; its hash only has to be the same everywhere
+        block.hash = ConsensusHasher::new()
+            .str("genesis")
+            .str(&block.header())
+            .finish_hex();
+        block
+    }
+
+    /// Data the proof of work is computed over: every field but the nonce
+    /// and the hash
+    pub fn header(&self) -> String {
+        let mut hasher = ConsensusHasher::new()
+            .str("pow-block")
+            .u64(self.height)
+            .str(&self.parent)
+            .timestamp(&self.timestamp)
+            .u32(self.difficulty)
+            .str(&self.miner)
+            .usize(self.transactions.len());
+        for transaction in &self.transactions {
+            hasher = hasher.str(&transaction.to_string());
+        }
+        hasher.finish_hex()
+    }
+
+    /// Whether the nonce solves the header puzzle at the block's difficulty
+    pub fn has_valid_pow(&self) -> bool {
+        ProofOfWork::with_difficulty(self.difficulty)
+            .verify_sync(
+                &PoWChallenge::new(self.header()),
+                &PoWProof::new(self.nonce, self.hash.clone()),
+            )
+            .unwrap_or(false)
+    }
+
+    /// What the difficulty adjuster needs to know about the block
+    pub fn timing(&self) -> BlockTiming {
+        BlockTiming {
+            height: self.height,
+            timestamp: self.timestamp,
+            difficulty: self.difficulty,
+        }
+    }
+
+    /// Expected number of hashes it took to mine the block
+    pub fn work(&self) -> u128 {
+        1u128.checked_shl(4 * self.difficulty).unwrap_or(u128::MAX)
+    }
+}
+
+/// Parameters every node of a chain must agree on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowChainConfig {
+    /// Difficulty of the first blocks
+    pub initial_difficulty: u32,
+    /// Time blocks should take to mine
+    pub target_block_time_secs: u64,
+    /// Blocks between difficulty adjustments
+    pub retarget_interval: u64,
+    /// Retarget every block with ASERT and this half-life instead
+    pub asert_half_life_secs: Option<u64>,
+    /// Difficulty never drops below this
+    pub min_difficulty: u32,
+    /// Orphan blocks held while their parents are missing
+    pub max_orphans: usize,
+    /// Target block times a timestamp may lie past the median of recent blocks
+    pub max_time_ahead_blocks: u64,
+}
+
+impl Default for PowChainConfig {
+    fn default() -> Self {
+        Self {
+            initial_difficulty: 2,
+            target_block_time_secs: 10,
+            retarget_interval: 10,
+            asert_half_life_secs: None,
+            min_difficulty: 1,
+            max_orphans: 256,
+            max_time_ahead_blocks: 72,
+        }
+    }
+}
+
+impl PowChainConfig {
+    /// Retargeting the configuration describes
+    ///
+    /// ASERT is anchored at block 1, the first mined block.
+    pub fn adjuster(&self) -> DifficultyAdjuster {
+        let adjuster = match self.asert_half_life_secs {
+            Some(half_life) => DifficultyAdjuster::asert(self.target_block_time_secs, 1, half_life),
+            None => DifficultyAdjuster::window(self.target_block_time_secs, self.retarget_interval),
+        };
+        adjuster.with_bounds(self.min_difficulty, MAX_DIFFICULTY)
+    }
+}
+
+/// Block with the work of its branch up to and including it
+#[derive(Debug, Clone)]
+struct ChainEntry {
+    block: PowBlock,
+    total_work: u128,
+}
+
+/// A switch of the best chain to another branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reorg {
+    pub old_tip: String,
+    pub new_tip: String,
+    /// Last block both branches share
+    pub common_ancestor: String,
+    /// Blocks of the old branch that left the best chain
+    pub depth: u64,
+}
+
+/// Proof-of-work chain object
+#[derive(Debug, Clone)]
+pub struct PowChainObject {
+    pub id: SharedObjectId,
+    pub config: PowChainConfig,
+    blocks: HashMap<String, ChainEntry>,
+    genesis: String,
+    tip: String,
+    /// Blocks waiting for their parent, by parent hash
+    orphans: HashMap<String, Vec<(PowBlock, SharedMessage)>>,
+    /// Parent and hash of every orphan, oldest first
+    orphan_order: VecDeque<(String, String)>,
+    reorgs: Vec<Reorg>,
+    history: DigestHistory,
+    accumulator: DigestAccumulator,
+}
+
+impl PowChainObject {
+    pub fn new() -> Self {
+        Self::with_config(PowChainConfig::default())
+    }
+
+    pub fn with_config(config: PowChainConfig) -> Self {
+        let genesis = PowBlock::genesis(&config);
+        let hash = genesis.hash.clone();
+        Self {
+            id: SharedObjectId::new(),
+            config,
+            blocks: HashMap::from([(
+                hash.clone(),
+                ChainEntry {
+                    block: genesis,
+                    total_work: 0,
+                },
+            )]),
+            genesis: hash.clone(),
+            tip: hash,
+            orphans: HashMap::new(),
+            orphan_order: VecDeque::new(),
+            reorgs: Vec::new(),
+            history: DigestHistory::new(),
+            accumulator: DigestAccumulator::new(),
+        }
+    }
+
+    /// Last block of the best chain
+    pub fn tip(&self) -> &PowBlock {
+        &self.blocks[&self.tip].block
+    }
+
+    pub fn height(&self) -> u64 {
+        self.tip().height
+    }
+
+    /// Work accumulated along the best chain
+    pub fn total_work(&self) -> u128 {
+        self.blocks[&self.tip].total_work
+    }
+
+    pub fn block(&self, hash: &str) -> Option<&PowBlock> {
+        self.blocks.get(hash).map(|entry| &entry.block)
+    }
+
+    /// Blocks known, on any branch, genesis included
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.values().map(Vec::len).sum()
+    }
+
+    /// Branch switches so far, oldest first
+    pub fn reorgs(&self) -> &[Reorg] {
+        &self.reorgs
+    }
+
+    /// Best chain from genesis to the tip
+    pub fn main_chain(&self) -> Vec<&PowBlock> {
+        let mut chain = self.branch(&self.tip);
+        chain.reverse();
+        chain
+    }
+
+    /// Whether a block is part of the best chain
+    pub fn is_on_main_chain(&self, hash: &str) -> bool {
+        self.block(hash)
+            .is_some_and(|block| self.ancestor_at(&self.tip, block.height) == Some(hash))
+    }
+
+    /// Blocks on top of a best-chain block, itself included; 0 off the best chain
+    pub fn confirmations(&self, hash: &str) -> u64 {
+        match self.block(hash) {
+            Some(block) if self.is_on_main_chain(hash) => self.height() - block.height + 1,
+            _ => 0,
+        }
+    }
+
+    /// Blocks from `hash` back to genesis
+    fn branch(&self, hash: &str) -> Vec<&PowBlock> {
+        let mut branch = Vec::new();
+        let mut current = self.block(hash);
+        while let Some(block) = current {
+            branch.push(block);
+            current = (block.height > 0)
+                .then(|| self.block(&block.parent))
+                .flatten();
+        }
+        branch
+    }
+
+    /// Hash of the block at `height` on the branch ending at `hash`
+    fn ancestor_at(&self, hash: &str, height: u64) -> Option<&str> {
+        let mut block = self.block(hash)?;
+        while block.height > height {
+            block = self.block(&block.parent)?;
+        }
+        (block.height == height).then_some(block.hash.as_str())
+    }
+
+    /// Difficulty a child of `parent` must be mined at
+    pub fn next_difficulty(&self, parent: &PowBlock) -> u32 {
+        let adjuster = self.config.adjuster();
+        let reference = adjuster
+            .reference_height(parent.height)
+            .and_then(|height| self.ancestor_at(&parent.hash, height))
+            .and_then(|hash| self.block(hash));
+        match reference {
+            Some(reference) => adjuster.next_difficulty(&parent.timing(), &reference.timing()),
+            None => parent.difficulty,
+        }
+    }
+
+    /// Median timestamp of the last [`MEDIAN_TIME_SPAN`] blocks up to `hash`
+    fn median_time_past(&self, hash: &str) -> DateTime<Utc> {
+        let mut times: Vec<DateTime<Utc>> = self
+            .branch(hash)
+            .into_iter()
+            .take(MEDIAN_TIME_SPAN)
+            .map(|block| block.timestamp)
+            .collect();
+        times.sort();
+        times[times.len() / 2]
+    }
+
+    /// Mine a block on the best chain
+    ///
+    /// The block is not added; share it with [`Self::block_message`].
+    pub async fn mine_next(
+        &self,
+        miner: &str,
+        transactions: Vec<Value>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<PowBlock> {
+        self.mine_on(&self.tip, miner, transactions, timestamp)
+            .await
+    }
+
+    /// Mine a block on top of any known block, e.g. to start a fork
+    pub async fn mine_on(
+        &self,
+        parent: &str,
+        miner: &str,
+        transactions: Vec<Value>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<PowBlock> {
+        let parent = self
+            .block(parent)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown block {}", parent)))?;
+        let mut block = PowBlock {
+            height: parent.height + 1,
+            parent: parent.hash.clone(),
+            timestamp,
+            difficulty: self.next_difficulty(parent),
+            miner: miner.to_string(),
+            transactions,
+            nonce: 0,
+            hash: String::new(),
+        };
+        let proof = ProofOfWork::with_difficulty(block.difficulty)
+            .create_proof(PoWChallenge::new(block.header()))
+            .await?;
+        block.nonce = proof.nonce;
+        block.hash = proof.hash;
+        Ok(block)
+    }
+
+    /// Message data carrying a block
+    pub fn block_message(block: &PowBlock) -> Value {
+        serde_json::to_value(PowChainMessage::Block(block.clone()))
+            .expect("blocks serialize to JSON")
+    }
+
+    /// Check a block whose parent is known against it
+    fn check_block(&self, block: &PowBlock, parent: &PowBlock) -> Option<ApplyOutcome> {
+        if block.height != parent.height + 1 {
+            return Some(ApplyOutcome::rejected(
+                "wrong_height",
+                format!("child of block {} must be at {}", parent.height, parent.height + 1),
+            ));
+        }
+        let expected = self.next_difficulty(parent);
+        if block.difficulty != expected {
+            return Some(ApplyOutcome::rejected(
+                "wrong_difficulty",
+                format!("expected difficulty {}, got {}", expected, block.difficulty),
+            ));
+        }
+        let median = self.median_time_past(&parent.hash);
+        if block.timestamp <= median {
+            return Some(ApplyOutcome::rejected(
+                "timestamp_too_early",
+                "block is not newer than the median of recent blocks",
+            ));
+        }
+        // The genesis timestamp is a placeholder, so the first block is free
+        let ahead = self
+            .config
+            .max_time_ahead_blocks
+            .saturating_mul(self.config.target_block_time_secs);
+        let latest = median + chrono::Duration::seconds(i64::try_from(ahead).unwrap_or(i64::MAX));
+        if parent.height > 0 && block.timestamp > latest {
+            return Some(ApplyOutcome::rejected(
+                "timestamp_too_late",
+                format!("block is more than {}s past the median of recent blocks", ahead),
+            ));
+        }
+        None
+    }
+
+    /// Add a block whose parent is known and move the tip if its branch
+    /// now has the most work
+    fn connect(&mut self, block: PowBlock, message: &SharedMessage) -> ApplyOutcome {
+        let parent = &self.blocks[&block.parent];
+        if let Some(outcome) = self.check_block(&block, &parent.block) {
+            return outcome;
+        }
+        let total_work = parent.total_work.saturating_add(block.work());
+        let hash = block.hash.clone();
+        self.blocks.insert(
+            hash.clone(),
+            ChainEntry {
+                block: block.clone(),
+                total_work,
+            },
+        );
+        self.accumulator.apply(message);
+
+        // Ties keep the branch seen first
+        if total_work > self.total_work() {
+            if block.parent != self.tip {
+                self.record_reorg(&hash);
+            }
+            self.tip = hash;
+        }
+        ApplyOutcome::Applied
+    }
+
+    fn record_reorg(&mut self, new_tip: &str) {
+        let old = self.tip().clone();
+        let mut ancestor = self.block(new_tip).cloned();
+        while let Some(block) = ancestor {
+            if self.ancestor_at(&old.hash, block.height) == Some(block.hash.as_str()) {
+                let reorg = Reorg {
+                    old_tip: old.hash.clone(),
+                    new_tip: new_tip.to_string(),
+                    common_ancestor: block.hash.clone(),
+                    depth: old.height - block.height,
+                };
+                tracing::info!(
+                    "PoW chain reorganized {} blocks deep onto {}",
+                    reorg.depth,
+                    new_tip
+                );
+                self.reorgs.push(reorg);
+                return;
+            }
+            ancestor = self.block(&block.parent).cloned();
+        }
+    }
+
+    /// Connect the orphans waiting on `hash`, and theirs in turn
+    fn connect_orphans(&mut self, hash: &str) {
+        let mut ready = vec![hash.to_string()];
+        while let Some(parent) = ready.pop() {
+            for (block, message) in self.orphans.remove(&parent).unwrap_or_default() {
+                let hash = block.hash.clone();
+                if self.connect(block, &message).is_applied() {
+                    ready.push(hash);
+                }
+            }
+        }
+        let orphans = &self.orphans;
+        self.orphan_order
+            .retain(|(parent, _)| orphans.contains_key(parent));
+    }
+
+    /// Drop the oldest orphan
+    fn evict_orphan(&mut self) {
+        let Some((parent, hash)) = self.orphan_order.pop_front() else {
+            return;
+        };
+        if let Some(waiting) = self.orphans.get_mut(&parent) {
+            waiting.retain(|(block, _)| block.hash != hash);
+            if waiting.is_empty() {
+                self.orphans.remove(&parent);
+            }
+        }
+    }
+}
+
+impl Default for PowChainObject {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigurableObject for PowChainObject {
+    type Config = PowChainConfig;
+
+    fn from_config(config: PowChainConfig) -> Result<Self> {
+        if config.target_block_time_secs == 0
+            || config.retarget_interval == 0
+            || config.asert_half_life_secs == Some(0)
+        {
+            return Err(ChaincraftError::config(
+                "target_block_time_secs, retarget_interval and asert_half_life_secs must be positive",
+            ));
+        }
+        Ok(Self::with_config(config))
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for PowChainObject {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "PowChain"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(serde_json::from_value::<PowChainMessage>(message.data.clone()).is_ok())
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        let PowChainMessage::Block(block) =
+            match serde_json::from_value::<PowChainMessage>(message.data.clone()) {
+                Ok(msg) => msg,
+                Err(e) => return Ok(ApplyOutcome::rejected("malformed", e.to_string())),
+            };
+        if self.blocks.contains_key(&block.hash) {
+            return Ok(ApplyOutcome::ignored("duplicate", "block already known"));
+        }
+        if !block.has_valid_pow() {
+            return Ok(ApplyOutcome::rejected(
+                "invalid_pow",
+                "hash does not solve the header at the block's difficulty",
+            ));
+        }
+        if !self.blocks.contains_key(&block.parent) {
+            // Cheap blocks could never connect and would only crowd the pool
+            if block.difficulty < self.config.min_difficulty {
+                return Ok(ApplyOutcome::rejected(
+                    "wrong_difficulty",
+                    format!(
+                        "difficulty {} is below the minimum {}",
+                        block.difficulty, self.config.min_difficulty
+                    ),
+                ));
+            }
+            let parent = block.parent.clone();
+            let held = self
+                .orphans
+                .get(&parent)
+                .is_some_and(|waiting| waiting.iter().any(|(held, _)| held.hash == block.hash));
+            if !held && self.config.max_orphans > 0 {
+                while self.orphan_count() >= self.config.max_orphans
+                    && !self.orphan_order.is_empty()
+                {
+                    self.evict_orphan();
+                }
+                self.orphan_order
+                    .push_back((parent.clone(), block.hash.clone()));
+                self.orphans
+                    .entry(parent.clone())
+                    .or_default()
+                    .push((block, message));
+            }
+            return Ok(ApplyOutcome::ignored("orphan", format!("waiting for parent {}", parent)));
+        }
+
+        let hash = block.hash.clone();
+        let outcome = self.connect(block, &message);
+        if outcome.is_applied() {
+            self.connect_orphans(&hash);
+        }
+        Ok(outcome)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.accumulator.digest().to_string())
+    }
+
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self.accumulator.contains(digest))
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
+    }
+
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
+    }
+
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.accumulator
+            .messages_since(digest)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown digest {}", digest)))
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        let tip = self.tip();
+        Ok(serde_json::json!({
+            "tip": tip.hash,
+            "height": tip.height,
+            "total_work": self.total_work().to_string(),
+            "next_difficulty": self.next_difficulty(tip),
+            "blocks": self.blocks.len(),
+            "orphans": self.orphan_count(),
+            "reorgs": self.reorgs.len(),
+        }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        *self = Self {
+            id: self.id.clone(),
+            ..Self::with_config(self.config.clone())
+        };
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
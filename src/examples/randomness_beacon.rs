@@ -1,20 +1,25 @@
+use super::liveness::{LivenessConfig, LivenessTracker};
 use crate::{
+    codec::consensus::ConsensusHasher,
     crypto::{
-        ecdsa::{ECDSASigner, ECDSAVerifier},
+        ecdsa::{ECDSASignature, ECDSASigner, ECDSAVerifier},
+        vdf::{VdfProof, VerifiableDelayFunction, MAX_VDF_ITERATIONS},
+        vrf::VerifiableRandomFunction,
         KeyType, PrivateKey, PublicKey, Signature,
     },
     error::{ChaincraftError, Result},
-    shared::{MessageType, SharedMessage, SharedObjectId},
-    shared_object::ApplicationObject,
+    shared::{DigestAccumulator, DigestHistory, MessageType, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, ConfigurableObject},
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Randomness beacon message types
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum BeaconMessageType {
     /// VRF proof submission
     VrfProof {
@@ -51,6 +56,16 @@ pub enum BeaconMessageType {
         stake: u64,
         signature: String,
     },
+    /// Validator leaving beacon participation
+    ValidatorExit {
+        validator: String,
+        signature: String,
+    },
+    /// Jailed validator asking to contribute again
+    Unjail {
+        validator: String,
+        signature: String,
+    },
     /// Challenge for bias resistance
     BiasChallenge {
         round: u64,
@@ -59,10 +74,35 @@ pub enum BeaconMessageType {
         challenge_data: String,
         signature: String,
     },
+    /// Delay function evaluated over a round's seed
+    ///
+    /// Unsigned: anyone may publish it and the proof speaks for itself.
+    VdfEvaluation {
+        round: u64,
+        output: String,
+        midpoints: Vec<String>,
+    },
+    /// Accused validator contesting an upheld bias challenge
+    ChallengeAppeal {
+        /// Hash of the challenge message
+        challenge: String,
+        validator: String,
+        signature: String,
+    },
+}
+
+/// JSON Schemas of the beacon's messages, by type name
+///
+/// `BiasEvidence` is the content of a `BiasChallenge`'s `challenge_data`.
+pub fn schemas() -> BTreeMap<&'static str, serde_json::Value> {
+    BTreeMap::from([
+        ("BeaconMessageType", super::schema_of::<BeaconMessageType>()),
+        ("BiasEvidence", super::schema_of::<BiasEvidence>()),
+    ])
 }
 
 /// VRF (Verifiable Random Function) proof
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct VrfProof {
     pub validator: String,
     pub input: String,
@@ -95,8 +135,188 @@ pub struct BeaconRound {
     pub bias_challenges: Vec<String>,
 }
 
+/// Evidence a bias challenge carries, JSON-encoded in its `challenge_data`
+///
+/// Different outputs for the same round are grounds for a challenge but not
+/// conclusive on their own: a validator that evaluated the VRF on two inputs
+/// also reveals two outputs. The accused clears itself on appeal when both
+/// proofs are correct evaluations; an output that does not match the
+/// evaluation was made up and the challenge stands.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum BiasEvidence {
+    /// Two VRF proofs the target signed for the challenged round with different outputs
+    ConflictingVrfOutputs { first: VrfProof, second: VrfProof },
+}
+
+/// How upheld bias challenges are punished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashingConfig {
+    /// Share of the offender's stake taken, in percent
+    pub slash_percent: u64,
+    /// Rounds after an offence during which it can be challenged
+    pub evidence_max_age: u64,
+    /// Rounds the offender has to appeal before its stake is cut
+    pub appeal_rounds: u64,
+}
+
+impl SlashingConfig {
+    pub fn new(slash_percent: u64, evidence_max_age: u64, appeal_rounds: u64) -> Self {
+        Self {
+            slash_percent: slash_percent.min(100),
+            evidence_max_age,
+            appeal_rounds,
+        }
+    }
+}
+
+impl Default for SlashingConfig {
+    fn default() -> Self {
+        Self::new(50, 16, 4)
+    }
+}
+
+/// Where an upheld bias challenge stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaseStatus {
+    /// The offender is excluded and may still appeal
+    Pending,
+    /// Overturned on appeal
+    Dismissed,
+    /// The appeal window passed and the stake was cut
+    Slashed,
+}
+
+/// Bias challenge whose evidence checked out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BiasCase {
+    /// Hash of the challenge message
+    pub id: String,
+    pub round: u64,
+    pub challenger: String,
+    pub offender: String,
+    pub evidence: BiasEvidence,
+    pub status: CaseStatus,
+    /// Round from which the slash is carried out unless appealed
+    pub appeal_deadline: u64,
+    /// Stake taken from the offender
+    pub slashed: u64,
+}
+
+/// Validator set frozen for an epoch, with its participation so far
+///
+/// With epochs enabled the beacon snapshots the active validators and their
+/// stakes at every epoch boundary. Only snapshot members may contribute to the
+/// epoch's rounds and the finalization threshold is derived from the snapshot,
+/// so registrations and exits never change the count a round is waiting for.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BeaconEpoch {
+    pub number: u64,
+    /// First round of the epoch
+    pub start_round: u64,
+    /// Stake of each participating validator
+    pub stakes: BTreeMap<String, u64>,
+    /// Contributions needed to finalize a round
+    pub threshold: u64,
+    pub rounds_finalized: u64,
+    /// Rounds each validator contributed a VRF proof to
+    pub participation: BTreeMap<String, u64>,
+}
+
+impl BeaconEpoch {
+    /// Freeze a validator set; the threshold is a two-thirds supermajority of it
+    pub fn snapshot(number: u64, start_round: u64, stakes: BTreeMap<String, u64>) -> Self {
+        let threshold = stakes.len() as u64 * 2 / 3 + 1;
+        let participation = stakes.keys().map(|v| (v.clone(), 0)).collect();
+        Self {
+            number,
+            start_round,
+            stakes,
+            threshold,
+            rounds_finalized: 0,
+            participation,
+        }
+    }
+
+    pub fn contains(&self, validator: &str) -> bool {
+        self.stakes.contains_key(validator)
+    }
+
+    pub fn total_stake(&self) -> u64 {
+        self.stakes.values().sum()
+    }
+
+    /// Fraction of finalized rounds the validator contributed to
+    pub fn liveness(&self, validator: &str) -> Option<f64> {
+        let rounds = *self.participation.get(validator)?;
+        if self.rounds_finalized == 0 {
+            return Some(0.0);
+        }
+        Some(rounds as f64 / self.rounds_finalized as f64)
+    }
+
+    /// Stake-weighted participation across finalized rounds
+    pub fn participation_rate(&self) -> f64 {
+        let possible = self.total_stake() * self.rounds_finalized;
+        if possible == 0 {
+            return 0.0;
+        }
+        let contributed: u64 = self
+            .participation
+            .iter()
+            .map(|(v, rounds)| self.stakes.get(v).copied().unwrap_or(0) * rounds)
+            .sum();
+        contributed as f64 / possible as f64
+    }
+
+    /// Validators that missed every finalized round of the epoch
+    pub fn absent_validators(&self) -> Vec<String> {
+        if self.rounds_finalized == 0 {
+            return Vec::new();
+        }
+        self.participation
+            .iter()
+            .filter(|(_, rounds)| **rounds == 0)
+            .map(|(v, _)| v.clone())
+            .collect()
+    }
+}
+
+/// Settings a beacon is built from, see [`ConfigurableObject`]
+///
+/// Missing fields take their defaults; unknown ones are refused so a typo in
+/// an experiment file does not go unnoticed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BeaconConfig {
+    pub round_duration_secs: u64,
+    /// Contributions needed to finalize a round, replaced per epoch with epochs
+    pub threshold: u64,
+    /// Rounds per epoch; `None` keeps the validator set open
+    pub rounds_per_epoch: Option<u64>,
+    /// Jail validators that miss rounds; `None` disables jailing
+    pub liveness: Option<LivenessConfig>,
+    pub slashing: SlashingConfig,
+    pub bias_resistance: bool,
+    /// Delay each round's randomness behind this many VDF squarings
+    pub vdf_iterations: Option<u64>,
+}
+
+impl Default for BeaconConfig {
+    fn default() -> Self {
+        Self {
+            round_duration_secs: 60,
+            threshold: 3,
+            rounds_per_epoch: None,
+            liveness: None,
+            slashing: SlashingConfig::default(),
+            bias_resistance: true,
+            vdf_iterations: None,
+        }
+    }
+}
+
 /// Randomness beacon implementation
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RandomnessBeaconObject {
     pub id: SharedObjectId,
     pub validators: HashMap<String, BeaconValidator>,
@@ -109,15 +329,48 @@ pub struct RandomnessBeaconObject {
     pub threshold: u64, // Minimum number of participants needed
     pub my_validator_address: String,
     pub signer: ECDSASigner,
+    /// Key behind this node's VRF outputs
+    pub vrf_signer: ECDSASigner,
     pub verifier: ECDSAVerifier,
     pub messages: Vec<BeaconMessageType>,
+    /// Digests passed through, maintained by the registry
+    pub history: DigestHistory,
+    /// Rolling digest over applied messages
+    pub accumulator: DigestAccumulator,
     pub bias_resistance_enabled: bool,
     pub challenges: HashMap<u64, Vec<BeaconMessageType>>,
+    /// Rounds per epoch, `None` when the validator set is not epoch-based
+    pub epoch_length: Option<u64>,
+    /// Current epoch (unused without epochs)
+    pub epoch: BeaconEpoch,
+    /// Completed epochs, oldest first
+    pub epoch_history: Vec<BeaconEpoch>,
+    /// Missed VRF rounds, `None` when jailing is disabled
+    pub liveness: Option<LivenessTracker>,
+    pub slashing: SlashingConfig,
+    /// Upheld bias challenges by challenge hash
+    pub cases: BTreeMap<String, BiasCase>,
+    /// VDF squarings between a round's seed and its randomness, `None` to
+    /// publish the seed as the randomness
+    pub vdf_iterations: Option<u64>,
+    /// Rounds closed but waiting for a VDF evaluation, with their seed as
+    /// randomness
+    pub awaiting_delay: BTreeMap<u64, BeaconRound>,
 }
 
 impl RandomnessBeaconObject {
     pub fn new(round_duration_secs: u64, threshold: u64) -> Result<Self> {
         let signer = ECDSASigner::new()?;
+        Self::with_keys(round_duration_secs, threshold, signer.clone(), signer)
+    }
+
+    /// Create a beacon with externally managed consensus and VRF keys
+    pub fn with_keys(
+        round_duration_secs: u64,
+        threshold: u64,
+        signer: ECDSASigner,
+        vrf_signer: ECDSASigner,
+    ) -> Result<Self> {
         let my_validator_address = signer.get_public_key_pem()?;
 
         Ok(Self {
@@ -132,17 +385,308 @@ impl RandomnessBeaconObject {
             threshold,
             my_validator_address,
             signer,
+            vrf_signer,
             verifier: ECDSAVerifier::new(),
             messages: Vec::new(),
+            history: DigestHistory::new(),
+            accumulator: DigestAccumulator::new(),
             bias_resistance_enabled: true,
             challenges: HashMap::new(),
+            epoch_length: None,
+            epoch: BeaconEpoch::default(),
+            epoch_history: Vec::new(),
+            liveness: None,
+            slashing: SlashingConfig::default(),
+            cases: BTreeMap::new(),
+            vdf_iterations: None,
+            awaiting_delay: BTreeMap::new(),
+        })
+    }
+
+    /// Delay each round's randomness behind a VDF of `iterations` squarings
+    ///
+    /// A validator that sees the other VRF outputs could withhold its own to
+    /// pick between two outcomes. With a delay longer than the round, nobody
+    /// knows the outcome until contributions are closed, so there is nothing
+    /// to choose between. Any node may then publish the evaluation with
+    /// [`helpers::create_vdf_evaluation`].
+    pub fn with_vdf(mut self, iterations: u64) -> Self {
+        self.vdf_iterations = Some(iterations);
+        self
+    }
+
+    /// Seed of a closed round waiting for its VDF evaluation
+    pub fn delay_seed(&self, round: u64) -> Option<&str> {
+        self.awaiting_delay
+            .get(&round)
+            .map(|pending| pending.randomness.as_str())
+    }
+
+    /// Randomness of a round from the output of the VDF over its seed
+    fn delayed_randomness(round: u64, output: &str) -> String {
+        ConsensusHasher::new()
+            .str("vdf")
+            .u64(round)
+            .str(output)
+            .finish_hex()
+    }
+
+    /// Explain why a VDF evaluation is not accepted, if it is not
+    fn check_vdf_evaluation(
+        &self,
+        round: u64,
+        output: &str,
+        midpoints: &[String],
+    ) -> Option<ApplyOutcome> {
+        let (Some(iterations), Some(seed)) = (self.vdf_iterations, self.delay_seed(round)) else {
+            return Some(if self.rounds.contains_key(&round) {
+                ApplyOutcome::ignored(
+                    "already_finalized",
+                    format!("round {} already has its randomness", round),
+                )
+            } else {
+                ApplyOutcome::ignored(
+                    "no_pending_seed",
+                    format!("round {} has no seed waiting for a delay", round),
+                )
+            });
+        };
+        let proof = VdfProof {
+            output: output.to_string(),
+            midpoints: midpoints.to_vec(),
+        };
+        (!VerifiableDelayFunction::verify_proof(seed.as_bytes(), iterations, &proof)).then(|| {
+            ApplyOutcome::rejected(
+                "invalid_vdf_proof",
+                format!("proof does not show {} squarings of round {}'s seed", iterations, round),
+            )
         })
     }
 
+    /// Turn a round's seed into randomness with a checked VDF evaluation
+    pub fn process_vdf_evaluation(&mut self, msg: BeaconMessageType) -> Result<bool> {
+        let BeaconMessageType::VdfEvaluation { round, output, .. } = &msg else {
+            return Ok(false);
+        };
+        let Some(mut finalized) = self.awaiting_delay.remove(round) else {
+            return Ok(false);
+        };
+        finalized.randomness = Self::delayed_randomness(*round, output);
+        self.rounds.insert(*round, finalized);
+        self.messages.push(msg);
+        Ok(true)
+    }
+
+    /// Jail validators that miss too many rounds
+    ///
+    /// With epochs, a jailed validator stops counting towards the current
+    /// epoch's threshold at once instead of at the next boundary.
+    pub fn with_liveness(mut self, config: LivenessConfig) -> Self {
+        self.liveness = Some(LivenessTracker::new(config));
+        self
+    }
+
+    /// Whether a validator is jailed and may not contribute
+    pub fn is_jailed(&self, validator: &str) -> bool {
+        self.liveness
+            .as_ref()
+            .is_some_and(|liveness| liveness.is_jailed(validator))
+    }
+
+    /// Punish upheld bias challenges according to `config`
+    ///
+    /// The offender is excluded from rounds as soon as a challenge is upheld.
+    /// Unless an appeal clears it within `appeal_rounds`, its stake is cut and
+    /// it stays out for good.
+    pub fn with_slashing(mut self, config: SlashingConfig) -> Self {
+        self.slashing = config;
+        self
+    }
+
+    /// Whether a validator is excluded for an upheld bias challenge
+    pub fn is_excluded(&self, validator: &str) -> bool {
+        self.cases
+            .values()
+            .any(|case| case.offender == validator && case.status != CaseStatus::Dismissed)
+    }
+
+    /// Upheld bias challenges against a validator, oldest first
+    pub fn cases_against(&self, validator: &str) -> Vec<&BiasCase> {
+        let mut cases: Vec<_> = self
+            .cases
+            .values()
+            .filter(|case| case.offender == validator)
+            .collect();
+        cases.sort_by_key(|case| case.round);
+        cases
+    }
+
+    /// Two-thirds supermajority of the epoch members not in jail or excluded
+    fn refresh_threshold(&mut self) {
+        if self.epoch_length.is_none() {
+            return;
+        }
+        let members = self
+            .epoch
+            .stakes
+            .keys()
+            .filter(|v| !self.is_jailed(v) && !self.is_excluded(v))
+            .count() as u64;
+        self.threshold = members * 2 / 3 + 1;
+    }
+
+    /// Record which participants contributed to the round being finalized
+    fn record_liveness(&mut self, participants: &[String]) {
+        let expected: Vec<String> = match self.epoch_length {
+            Some(_) => self.epoch.stakes.keys().cloned().collect(),
+            None => self.active_stakes().into_keys().collect(),
+        };
+        let expected: Vec<String> = expected
+            .into_iter()
+            .filter(|v| !self.is_excluded(v))
+            .collect();
+        let round = self.current_round;
+        let Some(liveness) = self.liveness.as_mut() else {
+            return;
+        };
+        let mut jailed_any = false;
+        for validator in expected {
+            jailed_any |= liveness.record(&validator, participants.contains(&validator), round);
+        }
+        if jailed_any {
+            self.refresh_threshold();
+        }
+    }
+
+    /// Release a jailed validator after its cooldown
+    pub fn unjail_validator(&mut self, validator: &str) -> bool {
+        let round = self.current_round;
+        let released = self
+            .liveness
+            .as_mut()
+            .is_some_and(|liveness| liveness.unjail(validator, round));
+        if released {
+            self.refresh_threshold();
+        }
+        released
+    }
+
+    /// Rotate the validator set in epochs of `rounds_per_epoch` rounds
+    ///
+    /// The threshold passed to the constructor is replaced by the one derived
+    /// from each epoch's snapshot.
+    pub fn with_epochs(mut self, rounds_per_epoch: u64) -> Self {
+        self.epoch_length = Some(rounds_per_epoch.max(1));
+        self.epoch = BeaconEpoch::snapshot(0, self.current_round, self.active_stakes());
+        self.threshold = self.epoch.threshold;
+        self
+    }
+
+    /// Active validators and their stakes
+    fn active_stakes(&self) -> BTreeMap<String, u64> {
+        self.validators
+            .values()
+            .filter(|v| v.active)
+            .map(|v| (v.address.clone(), v.stake))
+            .collect()
+    }
+
+    /// Whether a validator may contribute to the current round
+    pub fn is_participant(&self, validator: &str) -> bool {
+        if self.is_jailed(validator) || self.is_excluded(validator) {
+            return false;
+        }
+        if self.epoch_length.is_some() {
+            self.epoch.contains(validator)
+        } else {
+            self.validators
+                .get(validator)
+                .map(|v| v.active)
+                .unwrap_or(false)
+        }
+    }
+
+    /// Snapshot the validator set for a new epoch
+    fn start_epoch(&mut self, number: u64) {
+        let next = BeaconEpoch::snapshot(number, self.current_round, self.active_stakes());
+        let finished = std::mem::replace(&mut self.epoch, next);
+        self.epoch_history.push(finished);
+        self.threshold = self.epoch.threshold;
+        self.refresh_threshold();
+        tracing::debug!(
+            "Beacon epoch {} starts at round {} with {} validators, threshold {}",
+            number,
+            self.current_round,
+            self.epoch.stakes.len(),
+            self.threshold
+        );
+    }
+
+    /// Record a finished round against the epoch and roll over at the boundary
+    fn advance_epoch(&mut self, participants: &[String]) {
+        let Some(length) = self.epoch_length else {
+            return;
+        };
+        self.epoch.rounds_finalized += 1;
+        for validator in participants {
+            if let Some(rounds) = self.epoch.participation.get_mut(validator) {
+                *rounds += 1;
+            }
+        }
+        if self.current_round - self.epoch.start_round >= length {
+            self.start_epoch(self.epoch.number + 1);
+        }
+    }
+
+    /// Apply joins and exits to the genesis epoch before anything was counted
+    ///
+    /// Nothing can be finalized before the first validator is known, so the
+    /// first epoch's set keeps forming until a contribution arrives.
+    fn bootstrap_epoch(&mut self) {
+        let counting = self.epoch.rounds_finalized > 0
+            || self.pending_vrf_proofs.contains_key(&self.current_round)
+            || self.pending_partial_sigs.contains_key(&self.current_round);
+        if self.epoch_length.is_some() && self.epoch.number == 0 && !counting {
+            self.epoch = BeaconEpoch::snapshot(0, self.epoch.start_round, self.active_stakes());
+            self.threshold = self.epoch.threshold;
+        }
+    }
+
+    /// Current epoch number, if epochs are enabled
+    pub fn current_epoch(&self) -> Option<u64> {
+        self.epoch_length.map(|_| self.epoch.number)
+    }
+
+    /// Statistics for an epoch, current or completed
+    pub fn epoch_stats(&self, number: u64) -> Option<&BeaconEpoch> {
+        self.epoch_length?;
+        if self.epoch.number == number {
+            return Some(&self.epoch);
+        }
+        self.epoch_history.iter().find(|e| e.number == number)
+    }
+
+    /// Stop a validator from participating; with epochs this takes effect at the
+    /// next epoch boundary
+    pub fn deactivate_validator(&mut self, address: &str) -> bool {
+        let Some(validator) = self.validators.get_mut(address) else {
+            return false;
+        };
+        if !validator.active {
+            return false;
+        }
+        validator.active = false;
+        self.bootstrap_epoch();
+        true
+    }
+
     /// Register a validator for beacon participation
+    ///
+    /// Registration messages are checked before they get here; this is also
+    /// how a node sets up its initial validators.
     pub fn register_validator(&mut self, validator: BeaconValidator) -> Result<()> {
-        // Verify validator signature (simplified)
         self.validators.insert(validator.address.clone(), validator);
+        self.bootstrap_epoch();
         Ok(())
     }
 
@@ -152,20 +696,24 @@ impl RandomnessBeaconObject {
         elapsed.num_seconds() >= self.round_duration_secs as i64
     }
 
+    /// VRF input of `input` in `round`
+    fn vrf_alpha(round: u64, input: &str) -> [u8; 32] {
+        ConsensusHasher::new()
+            .str("vrf")
+            .u64(round)
+            .str(input)
+            .finish()
+    }
+
     /// Generate VRF proof for current round
     pub fn generate_vrf_proof(&self, input: &str) -> Result<VrfProof> {
-        // Simplified VRF implementation
-        let vrf_input = format!("round_{}_{}", self.current_round, input);
-        let mut hasher = Sha256::new();
-        hasher.update(vrf_input.as_bytes());
-        hasher.update(self.my_validator_address.as_bytes());
-        let hash = hasher.finalize();
-
-        let proof = hex::encode(&hash[0..16]); // First 16 bytes as proof
-        let output = hex::encode(&hash[16..32]); // Last 16 bytes as output
+        let (proof, output) = VerifiableRandomFunction::evaluate_hex(
+            self.vrf_signer.private_key(),
+            &Self::vrf_alpha(self.current_round, input),
+        )?;
 
         let signature_data = format!("vrf:{}:{}:{}:{}", self.current_round, input, proof, output);
-        let signature = self.signer.sign(signature_data.as_bytes())?;
+        let signature = self.vrf_signer.sign(signature_data.as_bytes())?;
 
         Ok(VrfProof {
             validator: self.my_validator_address.clone(),
@@ -193,8 +741,7 @@ impl RandomnessBeaconObject {
                 return Ok(false);
             }
 
-            // Verify validator is registered
-            if !self.validators.contains_key(&validator) {
+            if !self.is_participant(&validator) {
                 return Ok(false);
             }
 
@@ -241,7 +788,7 @@ impl RandomnessBeaconObject {
                 return Ok(false);
             }
 
-            if !self.validators.contains_key(&validator) {
+            if !self.is_participant(&validator) {
                 return Ok(false);
             }
 
@@ -281,6 +828,9 @@ impl RandomnessBeaconObject {
     }
 
     /// Finalize the current round and generate randomness
+    ///
+    /// With a VDF, the round waits in [`Self::awaiting_delay`] and the value
+    /// returned is its seed; the randomness comes with the evaluation.
     pub fn finalize_round(&mut self) -> Result<String> {
         if !self.can_finalize_round() {
             return Err(ChaincraftError::validation(
@@ -301,15 +851,11 @@ impl RandomnessBeaconObject {
             .unwrap_or_default();
 
         // Combine VRF outputs to create final randomness
-        let mut combined_randomness = String::new();
-        for proof in &vrf_proofs {
-            combined_randomness.push_str(&proof.output);
-        }
-
-        let mut hasher = Sha256::new();
-        hasher.update(combined_randomness.as_bytes());
-        hasher.update(self.current_round.to_string().as_bytes());
-        let final_randomness = hex::encode(hasher.finalize());
+        let final_randomness = vrf_proofs
+            .iter()
+            .fold(ConsensusHasher::new(), |hasher, proof| hasher.str(&proof.output))
+            .u64(self.current_round)
+            .finish_hex();
 
         // Create threshold signature (simplified)
         let mut threshold_sig = String::new();
@@ -333,19 +879,314 @@ impl RandomnessBeaconObject {
                 .unwrap_or_default(),
         };
 
-        self.rounds.insert(self.current_round, beacon_round);
+        if self.vdf_iterations.is_some() {
+            self.awaiting_delay.insert(self.current_round, beacon_round);
+        } else {
+            self.rounds.insert(self.current_round, beacon_round);
+        }
+        for validator in &participants {
+            if let Some(info) = self.validators.get_mut(validator) {
+                info.last_participation = Some(self.current_round);
+            }
+        }
 
         // Clean up and advance to next round
         self.pending_vrf_proofs.remove(&self.current_round);
         self.pending_partial_sigs.remove(&self.current_round);
         self.challenges.remove(&self.current_round);
 
+        self.record_liveness(&participants);
         self.current_round += 1;
         self.last_round_time = Utc::now();
+        self.execute_slashes();
+        self.advance_epoch(&participants);
 
         Ok(final_randomness)
     }
 
+    /// Explain why a message's signature is not accepted, if it is not
+    ///
+    /// VRF proofs are signed with the validator's VRF key, registrations with
+    /// the key they register and everything else with the consensus key of the
+    /// validator named as sender. The payloads are the ones [`helpers`] sign.
+    fn check_signature(&self, msg: &BeaconMessageType) -> Option<ApplyOutcome> {
+        let (signer, payload, signature) = match msg {
+            BeaconMessageType::VrfProof {
+                round,
+                input,
+                proof,
+                output,
+                validator,
+                signature,
+                ..
+            } => (validator, format!("vrf:{}:{}:{}:{}", round, input, proof, output), signature),
+            BeaconMessageType::PartialSignature {
+                round,
+                validator,
+                partial_sig,
+                signature,
+                ..
+            } => (
+                validator,
+                format!("partial_sig:{}:{}:{}", round, validator, partial_sig),
+                signature,
+            ),
+            BeaconMessageType::ValidatorRegistration {
+                validator,
+                public_key,
+                vrf_key,
+                stake,
+                signature,
+            } => {
+                let payload =
+                    format!("register:{}:{}:{}:{}", validator, public_key, vrf_key, stake);
+                return (!self.is_signed(&payload, signature, public_key)).then(|| {
+                    ApplyOutcome::rejected(
+                        "invalid_signature",
+                        "registration is not signed with the registered key",
+                    )
+                });
+            },
+            BeaconMessageType::ValidatorExit {
+                validator,
+                signature,
+            } => (validator, format!("exit:{}", validator), signature),
+            BeaconMessageType::Unjail {
+                validator,
+                signature,
+            } => (validator, format!("unjail:{}", validator), signature),
+            BeaconMessageType::BiasChallenge {
+                round,
+                challenger,
+                target_validator,
+                challenge_data,
+                signature,
+            } => (
+                challenger,
+                format!(
+                    "challenge:{}:{}:{}:{}",
+                    round, challenger, target_validator, challenge_data
+                ),
+                signature,
+            ),
+            BeaconMessageType::ChallengeAppeal {
+                challenge,
+                validator,
+                signature,
+            } => (validator, format!("appeal:{}:{}", challenge, validator), signature),
+            BeaconMessageType::FinalizedBeacon { .. } | BeaconMessageType::VdfEvaluation { .. } => {
+                return None
+            },
+        };
+
+        let Some(registered) = self.validators.get(signer) else {
+            return Some(ApplyOutcome::rejected(
+                "unknown_validator",
+                format!("{} is not a registered validator", signer),
+            ));
+        };
+        let key = match msg {
+            BeaconMessageType::VrfProof { .. } => &registered.vrf_key,
+            _ => &registered.public_key,
+        };
+        if !self.is_signed(&payload, signature, key) {
+            return Some(ApplyOutcome::rejected(
+                "invalid_signature",
+                format!("signature does not match {}'s key", signer),
+            ));
+        }
+        None
+    }
+
+    /// Explain why a VRF proof is not accepted, if it is not
+    ///
+    /// The proof must verify under the sender's registered VRF key for the
+    /// round and input it names, and the output must be the one it proves, so
+    /// a validator cannot pick its contribution. Expects a checked signature.
+    fn check_vrf_proof(&self, msg: &BeaconMessageType) -> Option<ApplyOutcome> {
+        let BeaconMessageType::VrfProof {
+            round,
+            input,
+            proof,
+            output,
+            validator,
+            ..
+        } = msg
+        else {
+            return None;
+        };
+        let registered = self.validators.get(validator)?;
+        (!self.is_vrf_evaluation(*round, input, proof, output, &registered.vrf_key)).then(|| {
+            ApplyOutcome::rejected(
+                "invalid_vrf_proof",
+                format!("proof does not verify under {}'s VRF key", validator),
+            )
+        })
+    }
+
+    /// Whether `signature` is a hex encoded signature of `payload` by `key_pem`
+    fn is_signed(&self, payload: &str, signature: &str, key_pem: &str) -> bool {
+        let Ok(bytes) = hex::decode(signature) else {
+            return false;
+        };
+        let Ok(signature) = ECDSASignature::from_bytes(&bytes) else {
+            return false;
+        };
+        self.verifier
+            .verify(payload.as_bytes(), &signature, key_pem)
+            .unwrap_or(false)
+    }
+
+    /// Explain why a message would not be processed, if it would not
+    fn precheck(&self, msg: &BeaconMessageType) -> Option<ApplyOutcome> {
+        let (round, validator) = match msg {
+            BeaconMessageType::VrfProof {
+                round, validator, ..
+            }
+            | BeaconMessageType::PartialSignature {
+                round, validator, ..
+            } => (*round, validator),
+            BeaconMessageType::BiasChallenge { .. } if !self.bias_resistance_enabled => {
+                return Some(ApplyOutcome::ignored(
+                    "bias_resistance_disabled",
+                    "bias challenges are not accepted",
+                ));
+            },
+            BeaconMessageType::BiasChallenge {
+                round,
+                target_validator,
+                challenge_data,
+                ..
+            } => return self.check_challenge(*round, target_validator, challenge_data),
+            BeaconMessageType::ChallengeAppeal {
+                challenge,
+                validator,
+                ..
+            } => return self.check_appeal(challenge, validator),
+            BeaconMessageType::VdfEvaluation {
+                round,
+                output,
+                midpoints,
+            } => return self.check_vdf_evaluation(*round, output, midpoints),
+            BeaconMessageType::ValidatorRegistration {
+                validator,
+                public_key,
+                vrf_key,
+                stake,
+                ..
+            } => {
+                return match self.validators.get(validator) {
+                    Some(registered) if &registered.public_key != public_key => {
+                        Some(ApplyOutcome::rejected(
+                            "address_taken",
+                            format!("{} is registered with another key", validator),
+                        ))
+                    },
+                    Some(registered)
+                        if registered.active
+                            && &registered.vrf_key == vrf_key
+                            && registered.stake == *stake =>
+                    {
+                        Some(ApplyOutcome::ignored(
+                            "already_registered",
+                            format!("{} is registered as given", validator),
+                        ))
+                    },
+                    _ => None,
+                };
+            },
+            BeaconMessageType::FinalizedBeacon {
+                round, randomness, ..
+            } => {
+                return match self.rounds.get(round) {
+                    Some(finalized) if &finalized.randomness == randomness => {
+                        Some(ApplyOutcome::ignored(
+                            "already_finalized",
+                            format!("round {} is finalized with this randomness", round),
+                        ))
+                    },
+                    Some(_) => Some(ApplyOutcome::rejected(
+                        "conflicting_randomness",
+                        format!("round {} was finalized with other randomness", round),
+                    )),
+                    None if self.messages.contains(msg) => Some(ApplyOutcome::ignored(
+                        "duplicate",
+                        format!("round {} was already announced", round),
+                    )),
+                    None => None,
+                };
+            },
+            BeaconMessageType::Unjail { validator, .. } => {
+                let Some(liveness) = &self.liveness else {
+                    return Some(ApplyOutcome::ignored(
+                        "jailing_disabled",
+                        "validators are never jailed",
+                    ));
+                };
+                return match liveness.jailed_until(validator) {
+                    None => Some(ApplyOutcome::ignored(
+                        "not_jailed",
+                        format!("{} is not jailed", validator),
+                    )),
+                    Some(until) if self.current_round < until => Some(ApplyOutcome::ignored(
+                        "jail_cooldown",
+                        format!("{} may unjail from round {}", validator, until),
+                    )),
+                    Some(_) => None,
+                };
+            },
+            _ => return None,
+        };
+
+        if round != self.current_round {
+            return Some(ApplyOutcome::ignored(
+                "wrong_round",
+                format!("expected round {}, got {}", self.current_round, round),
+            ));
+        }
+        if !self.validators.contains_key(validator) {
+            return Some(ApplyOutcome::rejected(
+                "unknown_validator",
+                format!("{} is not a registered validator", validator),
+            ));
+        }
+        if self.is_jailed(validator) {
+            return Some(ApplyOutcome::ignored(
+                "jailed",
+                format!("{} is jailed for missing too many rounds", validator),
+            ));
+        }
+        if self.is_excluded(validator) {
+            return Some(ApplyOutcome::ignored(
+                "excluded",
+                format!("{} was challenged for biasing the beacon", validator),
+            ));
+        }
+        if !self.is_participant(validator) {
+            return Some(ApplyOutcome::ignored(
+                "not_participating",
+                format!("{} does not take part in the current epoch", validator),
+            ));
+        }
+        let contributed = match msg {
+            BeaconMessageType::VrfProof { .. } => self
+                .pending_vrf_proofs
+                .get(&round)
+                .is_some_and(|proofs| proofs.iter().any(|p| &p.validator == validator)),
+            _ => self
+                .pending_partial_sigs
+                .get(&round)
+                .is_some_and(|sigs| sigs.contains_key(validator)),
+        };
+        if contributed {
+            return Some(ApplyOutcome::ignored(
+                "duplicate",
+                format!("{} already contributed to round {}", validator, round),
+            ));
+        }
+        None
+    }
+
     /// Process bias challenge
     pub fn process_bias_challenge(&mut self, msg: BeaconMessageType) -> Result<bool> {
         if !self.bias_resistance_enabled {
@@ -361,6 +1202,197 @@ impl RandomnessBeaconObject {
         Ok(false)
     }
 
+    /// Explain why a bias challenge is not upheld, if it is not
+    fn check_challenge(&self, round: u64, target: &str, data: &str) -> Option<ApplyOutcome> {
+        if round > self.current_round {
+            return Some(ApplyOutcome::ignored(
+                "wrong_round",
+                format!("round {} has not started", round),
+            ));
+        }
+        if self.current_round - round > self.slashing.evidence_max_age {
+            return Some(ApplyOutcome::ignored(
+                "evidence_expired",
+                format!("offences in round {} can no longer be challenged", round),
+            ));
+        }
+        if self
+            .cases
+            .values()
+            .any(|case| case.offender == target && case.round == round)
+        {
+            return Some(ApplyOutcome::ignored(
+                "already_challenged",
+                format!("{} was already challenged for round {}", target, round),
+            ));
+        }
+        if self.is_excluded(target) {
+            return Some(ApplyOutcome::ignored(
+                "already_excluded",
+                format!("{} is already excluded", target),
+            ));
+        }
+        let evidence: BiasEvidence = match serde_json::from_str(data) {
+            Ok(evidence) => evidence,
+            Err(e) => return Some(ApplyOutcome::rejected("invalid_evidence", e.to_string())),
+        };
+        self.verify_evidence(round, target, &evidence)
+            .err()
+            .map(|reason| ApplyOutcome::rejected("invalid_evidence", reason))
+    }
+
+    /// Check that the evidence shows what it claims about `target`
+    fn verify_evidence(
+        &self,
+        round: u64,
+        target: &str,
+        evidence: &BiasEvidence,
+    ) -> std::result::Result<(), String> {
+        let validator = self
+            .validators
+            .get(target)
+            .ok_or_else(|| format!("{} is not a registered validator", target))?;
+        match evidence {
+            BiasEvidence::ConflictingVrfOutputs { first, second } => {
+                if first.validator != target || second.validator != target {
+                    return Err(format!("the proofs are not both by {}", target));
+                }
+                if first.output == second.output {
+                    return Err("the outputs do not differ".to_string());
+                }
+                for proof in [first, second] {
+                    if !self.is_signed_vrf_proof(round, proof, &validator.vrf_key) {
+                        return Err(format!("a proof is not signed with {}'s VRF key", target));
+                    }
+                }
+                Ok(())
+            },
+        }
+    }
+
+    fn is_signed_vrf_proof(&self, round: u64, proof: &VrfProof, vrf_key: &str) -> bool {
+        let signature_data =
+            format!("vrf:{}:{}:{}:{}", round, proof.input, proof.proof, proof.output);
+        self.is_signed(&signature_data, &proof.signature, vrf_key)
+    }
+
+    /// Whether `proof` and `output` are the VRF evaluation on `input` in `round` under `vrf_key`
+    fn is_vrf_evaluation(
+        &self,
+        round: u64,
+        input: &str,
+        proof: &str,
+        output: &str,
+        vrf_key: &str,
+    ) -> bool {
+        let Ok(key) = self.verifier.parse_public_key_pem(vrf_key) else {
+            return false;
+        };
+        VerifiableRandomFunction::is_evaluation_hex(
+            &key,
+            &Self::vrf_alpha(round, input),
+            proof,
+            output,
+        )
+    }
+
+    /// Explain why an appeal does not clear the offender, if it does not
+    fn check_appeal(&self, challenge: &str, validator: &str) -> Option<ApplyOutcome> {
+        let Some(case) = self.cases.get(challenge) else {
+            return Some(ApplyOutcome::rejected(
+                "unknown_case",
+                format!("no upheld challenge {}", challenge),
+            ));
+        };
+        if case.offender != validator {
+            return Some(ApplyOutcome::rejected(
+                "not_accused",
+                format!("{} is not the accused of {}", validator, challenge),
+            ));
+        }
+        if case.status != CaseStatus::Pending {
+            return Some(ApplyOutcome::ignored(
+                "case_closed",
+                format!("challenge {} was already decided", challenge),
+            ));
+        }
+        if self.current_round >= case.appeal_deadline {
+            return Some(ApplyOutcome::ignored(
+                "appeal_window_closed",
+                format!("appeals were due before round {}", case.appeal_deadline),
+            ));
+        }
+        let vrf_key = self
+            .validators
+            .get(validator)
+            .map(|v| v.vrf_key.as_str())
+            .unwrap_or_default();
+        let BiasEvidence::ConflictingVrfOutputs { first, second } = &case.evidence;
+        let evaluated = [first, second]
+            .iter()
+            .all(|p| self.is_vrf_evaluation(case.round, &p.input, &p.proof, &p.output, vrf_key));
+        if !evaluated {
+            return Some(ApplyOutcome::rejected(
+                "appeal_denied",
+                "an output does not match the VRF evaluation",
+            ));
+        }
+        None
+    }
+
+    /// Record an upheld challenge and exclude the offender at once
+    fn open_case(&mut self, case: BiasCase) {
+        let round = self.current_round;
+        if let Some(proofs) = self.pending_vrf_proofs.get_mut(&round) {
+            proofs.retain(|proof| proof.validator != case.offender);
+        }
+        if let Some(sigs) = self.pending_partial_sigs.get_mut(&round) {
+            sigs.remove(&case.offender);
+        }
+        tracing::warn!(
+            "Bias challenge {} upheld against {} for round {}; appeals due before round {}",
+            case.id,
+            case.offender,
+            case.round,
+            case.appeal_deadline
+        );
+        self.cases.insert(case.id.clone(), case);
+        self.refresh_threshold();
+    }
+
+    /// Clear the offender of a case overturned on appeal
+    fn dismiss_case(&mut self, challenge: &str) -> bool {
+        let Some(case) = self.cases.get_mut(challenge) else {
+            return false;
+        };
+        case.status = CaseStatus::Dismissed;
+        tracing::info!("Bias challenge {} against {} dismissed", challenge, case.offender);
+        self.refresh_threshold();
+        true
+    }
+
+    /// Cut the stake of offenders whose appeal window has passed
+    fn execute_slashes(&mut self) {
+        let round = self.current_round;
+        for case in self.cases.values_mut() {
+            if case.status != CaseStatus::Pending || round < case.appeal_deadline {
+                continue;
+            }
+            if let Some(validator) = self.validators.get_mut(&case.offender) {
+                case.slashed = validator.stake * self.slashing.slash_percent / 100;
+                validator.stake -= case.slashed;
+                validator.active = false;
+            }
+            case.status = CaseStatus::Slashed;
+            tracing::warn!(
+                "Slashed {} of {}'s stake for biasing round {}",
+                case.slashed,
+                case.offender,
+                case.round
+            );
+        }
+    }
+
     /// Get beacon statistics
     pub fn get_beacon_stats(&self) -> serde_json::Value {
         let current_vrf_count = self
@@ -385,19 +1417,26 @@ impl RandomnessBeaconObject {
             "can_finalize": self.can_finalize_round(),
             "should_advance": self.should_advance_round(),
             "bias_resistance": self.bias_resistance_enabled,
-            "total_challenges": self.challenges.values().map(|c| c.len()).sum::<usize>()
+            "total_challenges": self.challenges.values().map(|c| c.len()).sum::<usize>(),
+            "pending_cases": self.cases.values().filter(|c| c.status == CaseStatus::Pending).count(),
+            "slashed": self.cases.values().filter(|c| c.status == CaseStatus::Slashed).count(),
+            "epoch": self.current_epoch(),
+            "epoch_validators": self.epoch_length.map(|_| self.epoch.stakes.len()),
+            "epoch_participation": self.epoch_length.map(|_| self.epoch.participation_rate()),
+            "jailed": self.liveness.as_ref().map(LivenessTracker::jailed),
+            "vdf_iterations": self.vdf_iterations,
+            "awaiting_delay": self.awaiting_delay.keys().collect::<Vec<_>>(),
         })
     }
 
     /// Get latest randomness
+    ///
+    /// With a VDF this is the last round whose evaluation was accepted.
     pub fn get_latest_randomness(&self) -> Option<String> {
-        if self.current_round > 1 {
-            self.rounds
-                .get(&(self.current_round - 1))
-                .map(|round| round.randomness.clone())
-        } else {
-            None
-        }
+        self.rounds
+            .iter()
+            .max_by_key(|(round, _)| **round)
+            .map(|(_, round)| round.randomness.clone())
     }
 
     /// Get randomness history
@@ -412,6 +1451,41 @@ impl RandomnessBeaconObject {
     }
 }
 
+/// Builds a beacon with freshly generated keys
+impl ConfigurableObject for RandomnessBeaconObject {
+    type Config = BeaconConfig;
+
+    fn from_config(config: BeaconConfig) -> Result<Self> {
+        let mut beacon = Self::new(config.round_duration_secs, config.threshold)?;
+        beacon.bias_resistance_enabled = config.bias_resistance;
+        let slashing = config.slashing;
+        beacon = beacon.with_slashing(SlashingConfig::new(
+            slashing.slash_percent,
+            slashing.evidence_max_age,
+            slashing.appeal_rounds,
+        ));
+        if let Some(liveness) = config.liveness {
+            beacon = beacon.with_liveness(LivenessConfig::new(
+                liveness.window,
+                liveness.max_missed_fraction,
+                liveness.jail_cooldown,
+            ));
+        }
+        if let Some(rounds) = config.rounds_per_epoch {
+            beacon = beacon.with_epochs(rounds);
+        }
+        if let Some(iterations) = config.vdf_iterations {
+            if iterations > MAX_VDF_ITERATIONS {
+                return Err(ChaincraftError::config(format!(
+                    "vdf_iterations {iterations} is above {MAX_VDF_ITERATIONS}"
+                )));
+            }
+            beacon = beacon.with_vdf(iterations);
+        }
+        Ok(beacon)
+    }
+}
+
 #[async_trait]
 impl ApplicationObject for RandomnessBeaconObject {
     fn id(&self) -> &SharedObjectId {
@@ -428,12 +1502,20 @@ impl ApplicationObject for RandomnessBeaconObject {
         Ok(msg_result.is_ok())
     }
 
-    async fn add_message(&mut self, message: SharedMessage) -> Result<()> {
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
         let beacon_msg: BeaconMessageType =
             serde_json::from_value(message.data.clone()).map_err(|e| {
                 ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
             })?;
 
+        if let Some(outcome) = self
+            .check_signature(&beacon_msg)
+            .or_else(|| self.check_vrf_proof(&beacon_msg))
+            .or_else(|| self.precheck(&beacon_msg))
+        {
+            return Ok(outcome);
+        }
+
         let processed = match &beacon_msg {
             BeaconMessageType::VrfProof { .. } => self.process_vrf_proof(beacon_msg.clone())?,
             BeaconMessageType::PartialSignature { .. } => {
@@ -457,8 +1539,42 @@ impl ApplicationObject for RandomnessBeaconObject {
                 self.register_validator(beacon_validator)?;
                 true
             },
-            BeaconMessageType::BiasChallenge { .. } => {
-                self.process_bias_challenge(beacon_msg.clone())?
+            BeaconMessageType::ValidatorExit { validator, .. } => {
+                self.deactivate_validator(validator)
+            },
+            BeaconMessageType::Unjail { validator, .. } => {
+                self.messages.push(beacon_msg.clone());
+                self.unjail_validator(validator)
+            },
+            BeaconMessageType::BiasChallenge {
+                round,
+                challenger,
+                target_validator,
+                challenge_data,
+                ..
+            } => {
+                let evidence = serde_json::from_str(challenge_data).map_err(|e| {
+                    ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
+                })?;
+                self.process_bias_challenge(beacon_msg.clone())?;
+                self.open_case(BiasCase {
+                    id: message.hash.clone(),
+                    round: *round,
+                    challenger: challenger.clone(),
+                    offender: target_validator.clone(),
+                    evidence,
+                    status: CaseStatus::Pending,
+                    appeal_deadline: self.current_round + self.slashing.appeal_rounds,
+                    slashed: 0,
+                });
+                true
+            },
+            BeaconMessageType::ChallengeAppeal { challenge, .. } => {
+                self.messages.push(beacon_msg.clone());
+                self.dismiss_case(challenge)
+            },
+            BeaconMessageType::VdfEvaluation { .. } => {
+                self.process_vdf_evaluation(beacon_msg.clone())?
             },
             BeaconMessageType::FinalizedBeacon { .. } => {
                 // Already finalized beacon rounds are informational
@@ -467,22 +1583,25 @@ impl ApplicationObject for RandomnessBeaconObject {
             },
         };
 
-        if processed {
-            tracing::debug!("Successfully processed beacon message: {:?}", beacon_msg);
+        if !processed {
+            return Ok(ApplyOutcome::ignored("not_processed", "beacon state unchanged"));
+        }
 
-            // Check if we can finalize the current round
-            if self.can_finalize_round() {
-                if let Ok(randomness) = self.finalize_round() {
-                    tracing::info!(
-                        "Finalized beacon round {} with randomness: {}",
-                        self.current_round - 1,
-                        randomness
-                    );
-                }
+        tracing::debug!("Successfully processed beacon message: {:?}", beacon_msg);
+        self.accumulator.apply(&message);
+
+        // Check if we can finalize the current round
+        if self.can_finalize_round() {
+            if let Ok(randomness) = self.finalize_round() {
+                tracing::info!(
+                    "Finalized beacon round {} with randomness: {}",
+                    self.current_round - 1,
+                    randomness
+                );
             }
         }
 
-        Ok(())
+        Ok(ApplyOutcome::Applied)
     }
 
     fn is_merkleized(&self) -> bool {
@@ -490,28 +1609,33 @@ impl ApplicationObject for RandomnessBeaconObject {
     }
 
     async fn get_latest_digest(&self) -> Result<String> {
-        Ok(format!("beacon_round:{}", self.current_round))
+        Ok(self.accumulator.digest().to_string())
     }
 
     async fn has_digest(&self, digest: &str) -> Result<bool> {
-        let current_digest = format!("beacon_round:{}", self.current_round);
-        Ok(digest == current_digest)
+        Ok(self.accumulator.contains(digest))
     }
 
     async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
         Ok(true)
     }
 
-    async fn add_digest(&mut self, _digest: String) -> Result<bool> {
-        Ok(true)
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
     }
 
-    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new())
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
     }
 
-    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new())
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.accumulator
+            .messages_since(digest)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown digest {}", digest)))
     }
 
     async fn get_state(&self) -> Result<serde_json::Value> {
@@ -534,36 +1658,44 @@ impl ApplicationObject for RandomnessBeaconObject {
         self.pending_vrf_proofs.clear();
         self.pending_partial_sigs.clear();
         self.challenges.clear();
+        self.cases.clear();
+        self.awaiting_delay.clear();
         self.messages.clear();
+        self.history.clear();
+        self.accumulator.reset();
+        if let Some(liveness) = self.liveness.as_mut() {
+            liveness.clear();
+        }
+        if self.epoch_length.is_some() {
+            self.epoch_history.clear();
+            self.epoch = BeaconEpoch::snapshot(0, self.current_round, self.active_stakes());
+            self.threshold = self.epoch.threshold;
+        }
         Ok(())
     }
 
     fn clone_box(&self) -> Box<dyn ApplicationObject> {
-        // Create a new instance with same configuration
-        let new_obj = RandomnessBeaconObject::new(self.round_duration_secs, self.threshold)
-            .unwrap_or_else(|_| {
-                // Fallback if creation fails
-                let signer = ECDSASigner::new().unwrap();
-                let my_validator_address = signer.get_public_key_pem().unwrap();
-                RandomnessBeaconObject {
-                    id: SharedObjectId::new(),
-                    validators: HashMap::new(),
-                    rounds: HashMap::new(),
-                    current_round: 1,
-                    round_duration_secs: 60,
-                    last_round_time: Utc::now(),
-                    pending_vrf_proofs: HashMap::new(),
-                    pending_partial_sigs: HashMap::new(),
-                    threshold: 3,
-                    my_validator_address,
-                    signer,
-                    verifier: ECDSAVerifier::new(),
-                    messages: Vec::new(),
-                    bias_resistance_enabled: true,
-                    challenges: HashMap::new(),
-                }
-            });
-        Box::new(new_obj)
+        // Create a new instance with the same configuration and keys
+        let new_obj = RandomnessBeaconObject::with_keys(
+            self.round_duration_secs,
+            self.threshold,
+            self.signer.clone(),
+            self.vrf_signer.clone(),
+        )
+        .expect("signer already produced a public key");
+        let new_obj = match self.epoch_length {
+            Some(length) => new_obj.with_epochs(length),
+            None => new_obj,
+        };
+        let new_obj = match &self.liveness {
+            Some(liveness) => new_obj.with_liveness(liveness.config),
+            None => new_obj,
+        };
+        let new_obj = match self.vdf_iterations {
+            Some(iterations) => new_obj.with_vdf(iterations),
+            None => new_obj,
+        };
+        Box::new(new_obj.with_slashing(self.slashing))
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -601,14 +1733,17 @@ pub mod helpers {
             .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
     }
 
+    /// Evaluate the VRF on `input` in `round` with `signer`'s key and sign the proof with it
     pub fn create_vrf_proof_message(
         round: u64,
         input: String,
-        proof: String,
-        output: String,
         validator: String,
         signer: &ECDSASigner,
     ) -> Result<serde_json::Value> {
+        let (proof, output) = VerifiableRandomFunction::evaluate_hex(
+            signer.private_key(),
+            &RandomnessBeaconObject::vrf_alpha(round, &input),
+        )?;
         let signature_data = format!("vrf:{}:{}:{}:{}", round, input, proof, output);
         let signature = signer.sign(signature_data.as_bytes())?;
 
@@ -647,6 +1782,35 @@ pub mod helpers {
             .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
     }
 
+    pub fn create_validator_exit(
+        validator: String,
+        signer: &ECDSASigner,
+    ) -> Result<serde_json::Value> {
+        let signature_data = format!("exit:{}", validator);
+        let signature = signer.sign(signature_data.as_bytes())?;
+
+        let exit = BeaconMessageType::ValidatorExit {
+            validator,
+            signature: hex::encode(signature.to_bytes()),
+        };
+
+        serde_json::to_value(exit)
+            .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
+    }
+
+    pub fn create_unjail(validator: String, signer: &ECDSASigner) -> Result<serde_json::Value> {
+        let signature_data = format!("unjail:{}", validator);
+        let signature = signer.sign(signature_data.as_bytes())?;
+
+        let unjail = BeaconMessageType::Unjail {
+            validator,
+            signature: hex::encode(signature.to_bytes()),
+        };
+
+        serde_json::to_value(unjail)
+            .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
+    }
+
     pub fn create_bias_challenge(
         round: u64,
         challenger: String,
@@ -669,4 +1833,61 @@ pub mod helpers {
         serde_json::to_value(challenge)
             .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
     }
+
+    /// Challenge a validator with two VRF proofs it signed for `round` with different outputs
+    pub fn create_conflicting_outputs_challenge(
+        round: u64,
+        challenger: String,
+        target_validator: String,
+        first: VrfProof,
+        second: VrfProof,
+        signer: &ECDSASigner,
+    ) -> Result<serde_json::Value> {
+        let evidence = BiasEvidence::ConflictingVrfOutputs { first, second };
+        let challenge_data = serde_json::to_string(&evidence).map_err(|e| {
+            ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
+        })?;
+        create_bias_challenge(round, challenger, target_validator, challenge_data, signer)
+    }
+
+    pub fn create_challenge_appeal(
+        challenge: String,
+        validator: String,
+        signer: &ECDSASigner,
+    ) -> Result<serde_json::Value> {
+        let signature_data = format!("appeal:{}:{}", challenge, validator);
+        let signature = signer.sign(signature_data.as_bytes())?;
+
+        let appeal = BeaconMessageType::ChallengeAppeal {
+            challenge,
+            validator,
+            signature: hex::encode(signature.to_bytes()),
+        };
+
+        serde_json::to_value(appeal)
+            .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
+    }
+
+    /// Evaluate the VDF over a round's seed, off the async executor
+    ///
+    /// Takes as long as the delay itself; any node may do it once the round
+    /// is waiting in [`RandomnessBeaconObject::awaiting_delay`].
+    pub async fn create_vdf_evaluation(
+        round: u64,
+        seed: &str,
+        iterations: u64,
+    ) -> Result<serde_json::Value> {
+        let proof = VerifiableDelayFunction::new()
+            .prove(seed.as_bytes().to_vec(), iterations)
+            .await?;
+
+        let evaluation = BeaconMessageType::VdfEvaluation {
+            round,
+            output: proof.output,
+            midpoints: proof.midpoints,
+        };
+
+        serde_json::to_value(evaluation)
+            .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
+    }
 }
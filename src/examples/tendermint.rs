@@ -1,20 +1,29 @@
+use super::liveness::{LivenessConfig, LivenessTracker};
 use crate::{
+    codec::consensus::ConsensusHasher,
+    consensus::{BlockRate, BlockTimeConfig},
     crypto::{
+        bls,
         ecdsa::{ECDSASigner, ECDSAVerifier},
         KeyType, PrivateKey, PublicKey, Signature,
     },
     error::{ChaincraftError, Result},
-    shared::{MessageType, SharedMessage, SharedObjectId},
-    shared_object::ApplicationObject,
+    retention::Compaction,
+    rng::RngProvider,
+    shared::{DigestAccumulator, DigestHistory, MessageType, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    storage::Storage,
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
 
 /// Tendermint consensus message types
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum TendermintMessageType {
     Proposal {
         height: u64,
@@ -23,6 +32,9 @@ pub enum TendermintMessageType {
         proposer: String,
         timestamp: DateTime<Utc>,
         signature: String,
+        /// Transactions of the proposed block
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        transactions: Vec<serde_json::Value>,
     },
     Prevote {
         height: u64,
@@ -37,6 +49,9 @@ pub enum TendermintMessageType {
         block_hash: Option<String>, // None for nil vote
         validator: String,
         signature: String,
+        /// Application data attached to a precommit for a block
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        extension: Option<serde_json::Value>,
     },
     ValidatorSet {
         validators: Vec<ValidatorInfo>,
@@ -48,15 +63,289 @@ pub enum TendermintMessageType {
         commit_signatures: Vec<String>,
         timestamp: DateTime<Utc>,
     },
+    /// Jailed validator asking to be counted again
+    Unjail {
+        validator: String,
+        height: u64,
+        signature: String,
+    },
+    /// Proof that a validator signed votes for two different blocks
+    Evidence { evidence: DoubleSignEvidence },
+}
+
+/// JSON Schemas of the consensus messages, by type name
+pub fn schemas() -> BTreeMap<&'static str, serde_json::Value> {
+    BTreeMap::from([("TendermintMessageType", super::schema_of::<TendermintMessageType>())])
+}
+
+/// Kind of vote a validator signed twice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum VoteKind {
+    Prevote,
+    Precommit,
+}
+
+/// One of two conflicting votes, as signed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SignedVote {
+    pub block_hash: Option<String>,
+    pub signature: String,
+    /// Extension of a precommit, part of what it signs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<serde_json::Value>,
+}
+
+/// Votes of one validator for two different blocks at the same height and round
+///
+/// Both votes carry the validator's signature, so the evidence convinces
+/// anyone who knows its key, whoever reports it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DoubleSignEvidence {
+    pub kind: VoteKind,
+    pub height: u64,
+    pub round: u32,
+    pub validator: String,
+    pub first: SignedVote,
+    pub second: SignedVote,
+}
+
+impl DoubleSignEvidence {
+    /// Evidence from two votes of the same kind, if they conflict
+    pub fn from_votes(
+        first: &TendermintMessageType,
+        second: &TendermintMessageType,
+    ) -> Option<Self> {
+        let (kind, height, round, validator, first) = Self::split(first)?;
+        let (second_kind, second_height, second_round, second_validator, second) =
+            Self::split(second)?;
+        let same_slot = (kind, height, round, validator)
+            == (second_kind, second_height, second_round, second_validator);
+        (same_slot && first.block_hash != second.block_hash).then(|| Self {
+            kind,
+            height,
+            round,
+            validator: validator.to_string(),
+            first,
+            second,
+        })
+    }
+
+    fn split(vote: &TendermintMessageType) -> Option<(VoteKind, u64, u32, &str, SignedVote)> {
+        match vote {
+            TendermintMessageType::Prevote {
+                height,
+                round,
+                block_hash,
+                validator,
+                signature,
+            } => Some((
+                VoteKind::Prevote,
+                *height,
+                *round,
+                validator,
+                SignedVote {
+                    block_hash: block_hash.clone(),
+                    signature: signature.clone(),
+                    extension: None,
+                },
+            )),
+            TendermintMessageType::Precommit {
+                height,
+                round,
+                block_hash,
+                validator,
+                signature,
+                extension,
+            } => Some((
+                VoteKind::Precommit,
+                *height,
+                *round,
+                validator,
+                SignedVote {
+                    block_hash: block_hash.clone(),
+                    signature: signature.clone(),
+                    extension: extension.clone(),
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    /// The two votes as they were sent
+    pub fn votes(&self) -> [TendermintMessageType; 2] {
+        [&self.first, &self.second].map(|vote| match self.kind {
+            VoteKind::Prevote => TendermintMessageType::Prevote {
+                height: self.height,
+                round: self.round,
+                block_hash: vote.block_hash.clone(),
+                validator: self.validator.clone(),
+                signature: vote.signature.clone(),
+            },
+            VoteKind::Precommit => TendermintMessageType::Precommit {
+                height: self.height,
+                round: self.round,
+                block_hash: vote.block_hash.clone(),
+                validator: self.validator.clone(),
+                signature: vote.signature.clone(),
+                extension: vote.extension.clone(),
+            },
+        })
+    }
+
+    /// Whether the votes name different blocks and are both signed with `key`
+    pub fn verify(&self, key: &ValidatorKey) -> bool {
+        self.first.block_hash != self.second.block_hash
+            && self.votes().iter().all(|vote| {
+                signed_payload(vote).is_some_and(|(_, payload, signature)| {
+                    matches!(key.verify(payload.as_bytes(), signature), Ok(true))
+                })
+            })
+    }
+}
+
+/// Double-signing a validator was caught at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Equivocation {
+    pub evidence: DoubleSignEvidence,
+    /// Voting power taken from the validator
+    pub slashed_power: u64,
+}
+
+/// Consensus key of a validator, tagged with its curve
+///
+/// Encoded as `{"key_type": "Ed25519", "key": "<hex>"}`, so the key decodes
+/// the same way whatever its length and a signature is checked with the
+/// algorithm the tag names. One validator set may mix curves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "EncodedValidatorKey", try_from = "EncodedValidatorKey")]
+pub struct ValidatorKey(PublicKey);
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "ValidatorKey")]
+struct EncodedValidatorKey {
+    key_type: KeyType,
+    key: String,
+}
+
+impl From<ValidatorKey> for EncodedValidatorKey {
+    fn from(key: ValidatorKey) -> Self {
+        Self {
+            key_type: key.key_type(),
+            key: key.to_hex(),
+        }
+    }
+}
+
+impl TryFrom<EncodedValidatorKey> for ValidatorKey {
+    type Error = ChaincraftError;
+
+    fn try_from(encoded: EncodedValidatorKey) -> Result<Self> {
+        Self::from_hex(encoded.key_type, &encoded.key)
+    }
+}
+
+impl JsonSchema for ValidatorKey {
+    fn schema_name() -> String {
+        EncodedValidatorKey::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        EncodedValidatorKey::json_schema(gen)
+    }
+}
+
+impl From<PublicKey> for ValidatorKey {
+    fn from(key: PublicKey) -> Self {
+        Self(key)
+    }
+}
+
+impl ValidatorKey {
+    pub fn new(key: PublicKey) -> Self {
+        Self(key)
+    }
+
+    /// Parse a hex encoded key of the given curve
+    pub fn from_hex(key_type: KeyType, hex_key: &str) -> Result<Self> {
+        PublicKey::from_hex(hex_key, key_type).map(Self)
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        self.0.key_type()
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.to_hex()
+    }
+
+    /// Check a hex encoded signature with the key's own algorithm
+    pub fn verify(&self, message: &[u8], signature: &str) -> Result<bool> {
+        let signature = Signature::from_hex(signature, self.key_type())?;
+        self.0.verify(message, &signature)
+    }
 }
 
 /// Validator information
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct ValidatorInfo {
     pub address: String,
-    pub public_key: String,
+    pub public_key: ValidatorKey,
     pub voting_power: u64,
     pub active: bool,
+    /// Hex encoded [`bls::prove_possession`] proof, required of BLS keys
+    /// joining through a validator set message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub possession: Option<String>,
+}
+
+impl ValidatorInfo {
+    /// Active validator with the key of `private_key`, carrying a proof of
+    /// possession if it is a BLS key
+    pub fn with_possession(
+        address: String,
+        private_key: &PrivateKey,
+        voting_power: u64,
+    ) -> Result<Self> {
+        let possession = match private_key.key_type() {
+            KeyType::Bls12_381 => Some(bls::prove_possession(private_key)?.to_hex()),
+            _ => None,
+        };
+        Ok(Self {
+            address,
+            public_key: ValidatorKey::new(private_key.public_key()),
+            voting_power,
+            active: true,
+            possession,
+        })
+    }
+
+    /// Check that a BLS key comes with a valid proof of possession
+    ///
+    /// Keys of other curves are never aggregated and need none.
+    pub fn check_possession(&self) -> std::result::Result<(), (&'static str, String)> {
+        if self.public_key.key_type() != KeyType::Bls12_381 {
+            return Ok(());
+        }
+        let Some(possession) = &self.possession else {
+            return Err((
+                "missing_possession",
+                format!("BLS key of {} comes without a proof of possession", self.address),
+            ));
+        };
+        let valid = Signature::from_hex(possession, KeyType::Bls12_381)
+            .is_ok_and(|proof| bls::verify_possession(self.public_key.public_key(), &proof));
+        if !valid {
+            return Err((
+                "invalid_possession",
+                format!("proof of possession of {} does not verify", self.address),
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Block data structure
@@ -69,6 +358,211 @@ pub struct Block {
     pub proposer: String,
     pub transactions: Vec<serde_json::Value>,
     pub commit_signatures: Vec<String>,
+    /// Vote extensions from the commit of the previous height
+    #[serde(default)]
+    pub vote_extensions: Vec<VoteExtension>,
+    /// Application digest after executing the transactions, `None` without
+    /// an application
+    #[serde(default)]
+    pub app_state_root: Option<String>,
+    /// Aggregate of the commit's precommits, in place of
+    /// `commit_signatures` when every signer has a BLS12-381 key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_certificate: Option<CommitCertificate>,
+}
+
+/// One BLS signature standing in for every precommit that committed a block
+///
+/// Only built when all those precommits came from BLS12-381 validators and
+/// carried no extension, so that they all signed the same payload. Checked
+/// with [`TendermintObject::verify_commit_certificate`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommitCertificate {
+    pub round: u32,
+    /// Validators whose precommits were aggregated, sorted
+    pub signers: Vec<String>,
+    /// Hex encoded aggregate signature
+    pub signature: String,
+}
+
+/// Application data a validator attached to its precommit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VoteExtension {
+    pub validator: String,
+    pub height: u64,
+    pub extension: serde_json::Value,
+}
+
+/// Application hook producing and checking vote extensions
+///
+/// Validators attach what [`extend_vote`](Self::extend_vote) returns to their
+/// precommits, e.g. a price observation or a beacon partial signature. Peers
+/// reject precommits whose extension fails
+/// [`verify_vote_extension`](Self::verify_vote_extension). The extensions of a
+/// commit are aggregated into the next block.
+pub trait VoteExtensionHandler: Send + Sync {
+    /// Data to attach to our precommit for `block_hash`, if any
+    fn extend_vote(&self, height: u64, block_hash: &str) -> Option<serde_json::Value>;
+
+    /// Whether `extension` from `validator` is acceptable
+    fn verify_vote_extension(
+        &self,
+        height: u64,
+        validator: &str,
+        block_hash: &str,
+        extension: &serde_json::Value,
+    ) -> bool;
+}
+
+impl Block {
+    /// Get the header of this block
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            height: self.height,
+            hash: self.hash.clone(),
+            previous_hash: self.previous_hash.clone(),
+            timestamp: self.timestamp,
+            proposer: self.proposer.clone(),
+        }
+    }
+}
+
+/// Block header, kept after the block body has been pruned
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub hash: String,
+    pub previous_hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub proposer: String,
+}
+
+/// Trusted snapshot of the chain at a committed height
+///
+/// A fresh node can start from a checkpoint instead of replaying every block
+/// since genesis.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TendermintCheckpoint {
+    pub height: u64,
+    pub header: BlockHeader,
+    pub app_digest: String,
+    pub validators: Vec<ValidatorInfo>,
+    pub created_at: DateTime<Utc>,
+    pub hash: String,
+}
+
+impl TendermintCheckpoint {
+    const LATEST_KEY: &'static str = "tendermint_checkpoint:latest";
+
+    fn storage_key(height: u64) -> String {
+        format!("tendermint_checkpoint:{:020}", height)
+    }
+
+    /// Calculate the hash committing to the checkpoint contents
+    pub fn calculate_hash(&self) -> String {
+        let content = serde_json::json!({
+            "height": self.height,
+            "header": self.header,
+            "app_digest": self.app_digest,
+            "validators": self.validators,
+        });
+        hex::encode(Sha256::digest(content.to_string().as_bytes()))
+    }
+
+    /// Check that the checkpoint has not been tampered with
+    pub fn verify(&self) -> bool {
+        self.height == self.header.height && self.hash == self.calculate_hash()
+    }
+
+    /// Persist the checkpoint and mark it as the latest one
+    pub async fn save(&self, storage: &dyn Storage) -> Result<()> {
+        let bytes = serde_json::to_vec(self)?;
+        storage
+            .put(&Self::storage_key(self.height), bytes.clone())
+            .await?;
+        storage.put(Self::LATEST_KEY, bytes).await
+    }
+
+    /// Load the checkpoint stored for a given height
+    pub async fn load(storage: &dyn Storage, height: u64) -> Result<Option<Self>> {
+        Self::load_key(storage, &Self::storage_key(height)).await
+    }
+
+    /// Load the most recently saved checkpoint
+    pub async fn load_latest(storage: &dyn Storage) -> Result<Option<Self>> {
+        Self::load_key(storage, Self::LATEST_KEY).await
+    }
+
+    async fn load_key(storage: &dyn Storage, key: &str) -> Result<Option<Self>> {
+        match storage.get(key).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Where a replayed block's state differs from the committed one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayDiff {
+    pub height: u64,
+    /// `app_state_root` committed in the block
+    pub expected_root: String,
+    /// Application digest the replay arrived at
+    pub actual_root: String,
+    /// Whether the state before the block already differed from the parent's
+    /// root, i.e. an earlier block diverged first
+    pub diverged_earlier: bool,
+    /// Outcome of each transaction in the replay
+    pub outcomes: Vec<ApplyOutcome>,
+    /// Fields of the application state that differ, only known when the
+    /// replayed block is the latest executed one
+    pub differences: Vec<StateDifference>,
+}
+
+/// A field of the application state with different values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StateDifference {
+    /// JSON pointer to the field
+    pub path: String,
+    pub committed: serde_json::Value,
+    pub replayed: serde_json::Value,
+}
+
+fn state_differences(
+    path: &str,
+    committed: &serde_json::Value,
+    replayed: &serde_json::Value,
+    differences: &mut Vec<StateDifference>,
+) {
+    match (committed, replayed) {
+        (serde_json::Value::Object(a), serde_json::Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let null = serde_json::Value::Null;
+                state_differences(
+                    &format!("{}/{}", path, key),
+                    a.get(key).unwrap_or(&null),
+                    b.get(key).unwrap_or(&null),
+                    differences,
+                );
+            }
+        },
+        _ if committed != replayed => differences.push(StateDifference {
+            path: path.to_string(),
+            committed: committed.clone(),
+            replayed: replayed.clone(),
+        }),
+        _ => {},
+    }
+}
+
+/// Application object executing committed transactions
+struct BlockApplication(Box<dyn ApplicationObject>);
+
+impl Clone for BlockApplication {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
 }
 
 /// Tendermint consensus state
@@ -87,10 +581,11 @@ pub struct Vote {
     pub block_hash: Option<String>,
     pub signature: String,
     pub timestamp: DateTime<Utc>,
+    pub extension: Option<serde_json::Value>,
 }
 
 /// Tendermint BFT consensus object
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct TendermintObject {
     pub id: SharedObjectId,
     pub validators: HashMap<String, ValidatorInfo>,
@@ -107,11 +602,140 @@ pub struct TendermintObject {
     pub signer: ECDSASigner,
     pub verifier: ECDSAVerifier,
     pub messages: Vec<TendermintMessageType>,
+    /// Digests passed through, maintained by the registry
+    pub history: DigestHistory,
+    /// Rolling digest over applied messages
+    pub accumulator: DigestAccumulator,
+    /// Rolling digest over all committed block hashes
+    pub app_digest: String,
+    /// Headers of blocks whose bodies were pruned
+    pub pruned_headers: Vec<BlockHeader>,
+    /// Checkpoints created so far, oldest first
+    pub checkpoints: Vec<TendermintCheckpoint>,
+    /// Create a checkpoint every this many committed heights
+    pub checkpoint_interval: Option<u64>,
+    checkpoint_store: Option<Arc<dyn Storage>>,
+    /// Missed proposals and precommits, `None` when jailing is disabled
+    pub liveness: Option<LivenessTracker>,
+    /// When the proposer should propose, see [`TendermintObject::should_propose`]
+    pub block_time: BlockTimeConfig,
+    /// Vote extensions of the last commit, going into the next block
+    pub pending_extensions: Vec<VoteExtension>,
+    vote_extensions: Option<Arc<dyn VoteExtensionHandler>>,
+    application: Option<BlockApplication>,
+    /// Height of the last block the application executed
+    pub executed_height: u64,
+    /// Validators caught double-signing, which lose their voting power for good
+    pub equivocations: Vec<Equivocation>,
+}
+
+fn chain_digest(previous: &str, block_hash: &str) -> String {
+    ConsensusHasher::new()
+        .str(previous)
+        .str(block_hash)
+        .finish_hex()
+}
+
+/// Message handing the transaction at `index` of a block to the application
+///
+/// Its id and timestamp come from the block, so executing the block again
+/// produces the same message hashes and the same digest.
+pub fn transaction_message(
+    block: &Block,
+    index: usize,
+    transaction: &serde_json::Value,
+) -> SharedMessage {
+    let rng = RngProvider::seeded(
+        block
+            .height
+            .wrapping_mul(1 << 32)
+            .wrapping_add(index as u64),
+    );
+    let mut message = SharedMessage::new_with_rng(
+        &rng,
+        MessageType::Custom("tendermint_transaction".to_string()),
+        transaction.clone(),
+    );
+    message.timestamp = block.timestamp;
+    message.hash = message.calculate_hash();
+    message
+}
+
+/// Signer, signed bytes and signature of a signed message
+///
+/// The payloads are the ones [`helpers`] sign.
+fn signed_payload(message: &TendermintMessageType) -> Option<(&str, String, &str)> {
+    match message {
+        TendermintMessageType::Proposal {
+            height,
+            round,
+            block_hash,
+            proposer,
+            signature,
+            ..
+        } => Some((proposer, format!("proposal:{}:{}:{}", height, round, block_hash), signature)),
+        TendermintMessageType::Prevote {
+            height,
+            round,
+            block_hash,
+            validator,
+            signature,
+        } => Some((validator, format!("prevote:{}:{}:{:?}", height, round, block_hash), signature)),
+        TendermintMessageType::Precommit {
+            height,
+            round,
+            block_hash,
+            validator,
+            signature,
+            extension,
+        } => {
+            Some((validator, precommit_payload(*height, *round, block_hash, extension), signature))
+        },
+        TendermintMessageType::Unjail {
+            validator,
+            height,
+            signature,
+        } => Some((validator, format!("unjail:{}:{}", validator, height), signature)),
+        TendermintMessageType::ValidatorSet { .. }
+        | TendermintMessageType::BlockCommit { .. }
+        | TendermintMessageType::Evidence { .. } => None,
+    }
+}
+
+/// Bytes signed by a precommit; without an extension the same as before
+/// extensions existed
+fn precommit_payload(
+    height: u64,
+    round: u32,
+    block_hash: &Option<String>,
+    extension: &Option<serde_json::Value>,
+) -> String {
+    let payload = format!("precommit:{}:{}:{:?}", height, round, block_hash);
+    match extension {
+        Some(extension) => format!("{}:{}", payload, extension),
+        None => payload,
+    }
+}
+
+fn message_height(message: &TendermintMessageType) -> u64 {
+    match message {
+        TendermintMessageType::Proposal { height, .. }
+        | TendermintMessageType::Prevote { height, .. }
+        | TendermintMessageType::Precommit { height, .. }
+        | TendermintMessageType::ValidatorSet { height, .. }
+        | TendermintMessageType::BlockCommit { height, .. }
+        | TendermintMessageType::Unjail { height, .. } => *height,
+        TendermintMessageType::Evidence { evidence } => evidence.height,
+    }
 }
 
 impl TendermintObject {
     pub fn new() -> Result<Self> {
-        let signer = ECDSASigner::new()?;
+        Self::with_signer(ECDSASigner::new()?)
+    }
+
+    /// Create an object signing with an externally managed consensus key
+    pub fn with_signer(signer: ECDSASigner) -> Result<Self> {
         let my_validator_address = signer.get_public_key_pem()?;
 
         // Create genesis block
@@ -123,8 +747,13 @@ impl TendermintObject {
             proposer: "genesis".to_string(),
             transactions: vec![],
             commit_signatures: vec![],
+            vote_extensions: vec![],
+            app_state_root: None,
+            commit_certificate: None,
         };
 
+        let app_digest = chain_digest("", &genesis_block.hash);
+
         Ok(Self {
             id: SharedObjectId::new(),
             validators: HashMap::new(),
@@ -141,25 +770,458 @@ impl TendermintObject {
             signer,
             verifier: ECDSAVerifier::new(),
             messages: Vec::new(),
+            history: DigestHistory::new(),
+            accumulator: DigestAccumulator::new(),
+            app_digest,
+            pruned_headers: Vec::new(),
+            checkpoints: Vec::new(),
+            checkpoint_interval: None,
+            checkpoint_store: None,
+            liveness: None,
+            block_time: BlockTimeConfig::default(),
+            pending_extensions: Vec::new(),
+            vote_extensions: None,
+            application: None,
+            executed_height: 0,
+            equivocations: Vec::new(),
         })
     }
 
+    /// Execute the transactions of committed blocks against `application`
+    ///
+    /// The application should be fresh: [`Self::replay_block`] rebuilds its
+    /// state from a reset copy.
+    pub fn with_application(mut self, application: Box<dyn ApplicationObject>) -> Self {
+        self.application = Some(BlockApplication(application));
+        self
+    }
+
+    /// Application executing committed transactions, if any
+    pub fn application(&self) -> Option<&dyn ApplicationObject> {
+        self.application.as_ref().map(|app| app.0.as_ref())
+    }
+
+    /// Hand the transactions of newly committed blocks to the application and
+    /// record its digest as the blocks' `app_state_root`
+    async fn execute_committed(&mut self) -> Result<()> {
+        let Some(BlockApplication(application)) = self.application.as_mut() else {
+            return Ok(());
+        };
+        let executed_height = self.executed_height;
+        for block in self
+            .blocks
+            .iter_mut()
+            .filter(|b| b.height > executed_height)
+        {
+            for (index, transaction) in block.transactions.iter().enumerate() {
+                application
+                    .add_message(transaction_message(block, index, transaction))
+                    .await?;
+            }
+            block.app_state_root = Some(application.get_latest_digest().await?);
+            self.executed_height = block.height;
+        }
+        Ok(())
+    }
+
+    /// Execute a block's transactions again on the state at the previous
+    /// height and check the result against its `app_state_root`
+    ///
+    /// The state is rebuilt by replaying every earlier block on a reset copy
+    /// of the application. Returns `None` when the digests match.
+    pub async fn replay_block(&self, height: u64) -> Result<Option<ReplayDiff>> {
+        let Some(BlockApplication(application)) = &self.application else {
+            return Err(ChaincraftError::validation("No application executes the transactions"));
+        };
+        let position = self
+            .blocks
+            .iter()
+            .position(|b| b.height == height && height > 0)
+            .ok_or_else(|| ChaincraftError::validation(format!("No block {} to replay", height)))?;
+        if self.blocks[0].height != 0 {
+            return Err(ChaincraftError::validation(format!(
+                "Blocks before height {} were pruned, the state at height {} cannot be rebuilt",
+                self.blocks[0].height,
+                height - 1
+            )));
+        }
+        let block = &self.blocks[position];
+        let expected_root = block.app_state_root.clone().ok_or_else(|| {
+            ChaincraftError::validation(format!("Block {} was not executed", height))
+        })?;
+
+        let mut replica = application.clone_box();
+        replica.reset().await?;
+        for earlier in &self.blocks[1..position] {
+            for (index, transaction) in earlier.transactions.iter().enumerate() {
+                replica
+                    .add_message(transaction_message(earlier, index, transaction))
+                    .await?;
+            }
+        }
+        let parent_root = self.blocks[position - 1].app_state_root.as_deref();
+        let pre_state_root = replica.get_latest_digest().await?;
+        let diverged_earlier = parent_root.is_some_and(|root| root != pre_state_root);
+
+        let mut outcomes = Vec::with_capacity(block.transactions.len());
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            outcomes.push(
+                replica
+                    .add_message(transaction_message(block, index, transaction))
+                    .await?,
+            );
+        }
+        let actual_root = replica.get_latest_digest().await?;
+        if actual_root == expected_root {
+            return Ok(None);
+        }
+
+        let mut differences = Vec::new();
+        if height == self.executed_height {
+            let committed = application.get_state().await?;
+            let replayed = replica.get_state().await?;
+            state_differences("", &committed, &replayed, &mut differences);
+        }
+        Ok(Some(ReplayDiff {
+            height,
+            expected_root,
+            actual_root,
+            diverged_earlier,
+            outcomes,
+            differences,
+        }))
+    }
+
+    /// Attach and verify vote extensions produced by `handler`
+    pub fn with_vote_extensions(mut self, handler: Arc<dyn VoteExtensionHandler>) -> Self {
+        self.vote_extensions = Some(handler);
+        self
+    }
+
+    /// Jail validators that miss too many proposals or precommits
+    pub fn with_liveness(mut self, config: LivenessConfig) -> Self {
+        self.liveness = Some(LivenessTracker::new(config));
+        self
+    }
+
+    /// Whether a validator is jailed and no longer counted
+    pub fn is_jailed(&self, address: &str) -> bool {
+        self.liveness
+            .as_ref()
+            .is_some_and(|liveness| liveness.is_jailed(address))
+    }
+
+    /// Whether a validator was caught double-signing
+    pub fn is_tombstoned(&self, address: &str) -> bool {
+        self.equivocations
+            .iter()
+            .any(|e| e.evidence.validator == address)
+    }
+
+    /// Evidence against the sender of `vote` if it already voted for another
+    /// block at the same height and round
+    pub fn double_sign_of(&self, vote: &TendermintMessageType) -> Option<DoubleSignEvidence> {
+        let (kind, height, round, validator, second) = DoubleSignEvidence::split(vote)?;
+        let votes = match kind {
+            VoteKind::Prevote => &self.prevotes,
+            VoteKind::Precommit => &self.precommits,
+        };
+        let earlier = votes.get(&(height, round))?.get(validator)?;
+        (earlier.block_hash != second.block_hash).then(|| DoubleSignEvidence {
+            kind,
+            height,
+            round,
+            validator: validator.to_string(),
+            first: SignedVote {
+                block_hash: earlier.block_hash.clone(),
+                signature: earlier.signature.clone(),
+                extension: earlier.extension.clone(),
+            },
+            second,
+        })
+    }
+
+    /// Tombstone the validator the evidence is against
+    ///
+    /// Its voting power drops to zero and it is no longer eligible, so its
+    /// votes stop counting towards any majority. The evidence is expected to
+    /// be verified.
+    pub fn punish_double_sign(&mut self, evidence: DoubleSignEvidence) {
+        let slashed_power = self
+            .validators
+            .get_mut(&evidence.validator)
+            .map(|v| std::mem::take(&mut v.voting_power))
+            .unwrap_or_default();
+        tracing::warn!(
+            "{} double-signed at height {} round {}, removing {} voting power",
+            evidence.validator,
+            evidence.height,
+            evidence.round,
+            slashed_power
+        );
+        self.equivocations.push(Equivocation {
+            evidence,
+            slashed_power,
+        });
+    }
+
+    /// Active, unjailed validators sorted by address
+    pub fn eligible_validators(&self) -> Vec<&ValidatorInfo> {
+        let mut eligible: Vec<&ValidatorInfo> = self
+            .validators
+            .values()
+            .filter(|v| v.active && !self.is_jailed(&v.address) && !self.is_tombstoned(&v.address))
+            .collect();
+        eligible.sort_by(|a, b| a.address.cmp(&b.address));
+        eligible
+    }
+
+    /// Validator expected to propose at a height and round, round-robin over
+    /// the eligible validators
+    pub fn proposer_for(&self, height: u64, round: u32) -> Option<String> {
+        let eligible = self.eligible_validators();
+        if eligible.is_empty() {
+            return None;
+        }
+        let slot = (height + round as u64) % eligible.len() as u64;
+        Some(eligible[slot as usize].address.clone())
+    }
+
+    /// Record who proposed and precommitted at the height being committed
+    fn record_liveness(&mut self) {
+        if self.liveness.is_none() {
+            return;
+        }
+        let key = (self.current_height, self.current_round);
+        let expected_proposer = self.proposer_for(key.0, key.1);
+        let proposed_by = match self.proposals.get(&key) {
+            Some(TendermintMessageType::Proposal { proposer, .. }) => Some(proposer.clone()),
+            _ => None,
+        };
+        let voters: Vec<(String, bool)> = self
+            .eligible_validators()
+            .iter()
+            .map(|v| {
+                let voted = self
+                    .precommits
+                    .get(&key)
+                    .is_some_and(|votes| votes.contains_key(&v.address));
+                (v.address.clone(), voted)
+            })
+            .collect();
+
+        let Some(liveness) = self.liveness.as_mut() else {
+            return;
+        };
+        if let Some(proposer) = expected_proposer {
+            let proposed = proposed_by.as_ref() == Some(&proposer);
+            liveness.record(&proposer, proposed, key.0);
+        }
+        for (validator, voted) in voters {
+            liveness.record(&validator, voted, key.0);
+        }
+    }
+
+    /// Release a jailed validator after its cooldown
+    pub fn unjail_validator(&mut self, address: &str) -> bool {
+        let height = self.current_height;
+        self.liveness
+            .as_mut()
+            .is_some_and(|liveness| liveness.unjail(address, height))
+    }
+
+    /// Propose on this schedule, e.g. skipping empty blocks
+    pub fn with_block_time(mut self, block_time: BlockTimeConfig) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    /// Whether the proposer should propose now, with `pending` transactions
+    /// waiting to go into the block
+    pub fn should_propose(&self, pending: usize) -> bool {
+        let since_last = self
+            .blocks
+            .last()
+            .and_then(|block| {
+                Utc::now()
+                    .signed_duration_since(block.timestamp)
+                    .to_std()
+                    .ok()
+            })
+            .unwrap_or_default();
+        self.block_time.is_due(since_last, pending)
+    }
+
+    /// Effective rate of the latest committed blocks
+    pub fn block_rate(&self) -> BlockRate {
+        let timestamps: Vec<DateTime<Utc>> = self
+            .blocks
+            .iter()
+            .filter(|block| block.height > 0)
+            .map(|block| block.timestamp)
+            .collect();
+        BlockRate::from_timestamps(&timestamps)
+    }
+
+    /// Create a checkpoint automatically every `interval` committed heights
+    pub fn with_checkpoint_interval(mut self, interval: u64) -> Self {
+        self.checkpoint_interval = Some(interval.max(1));
+        self
+    }
+
+    /// Persist automatically created checkpoints to the given storage
+    pub fn with_checkpoint_store(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.checkpoint_store = Some(storage);
+        self
+    }
+
+    /// Start from a trusted checkpoint instead of replaying from genesis
+    pub fn restore_from_checkpoint(checkpoint: TendermintCheckpoint) -> Result<Self> {
+        if !checkpoint.verify() {
+            return Err(ChaincraftError::validation("Checkpoint hash does not match its contents"));
+        }
+
+        let mut object = Self::new()?;
+        object.blocks = vec![Block {
+            height: checkpoint.header.height,
+            hash: checkpoint.header.hash.clone(),
+            previous_hash: checkpoint.header.previous_hash.clone(),
+            timestamp: checkpoint.header.timestamp,
+            proposer: checkpoint.header.proposer.clone(),
+            transactions: vec![],
+            commit_signatures: vec![],
+            vote_extensions: vec![],
+            app_state_root: None,
+            commit_certificate: None,
+        }];
+        object.validators = checkpoint
+            .validators
+            .iter()
+            .map(|v| (v.address.clone(), v.clone()))
+            .collect();
+        object.current_height = checkpoint.height + 1;
+        object.executed_height = checkpoint.height;
+        object.app_digest = checkpoint.app_digest.clone();
+        object.checkpoints = vec![checkpoint];
+        Ok(object)
+    }
+
+    /// Snapshot the latest committed block, app digest and validator set
+    pub fn create_checkpoint(&mut self) -> Result<TendermintCheckpoint> {
+        let header = self
+            .blocks
+            .last()
+            .map(Block::header)
+            .ok_or_else(|| ChaincraftError::validation("No committed block to checkpoint"))?;
+
+        let mut validators: Vec<ValidatorInfo> = self.validators.values().cloned().collect();
+        validators.sort_by(|a, b| a.address.cmp(&b.address));
+
+        let mut checkpoint = TendermintCheckpoint {
+            height: header.height,
+            header,
+            app_digest: self.app_digest.clone(),
+            validators,
+            created_at: Utc::now(),
+            hash: String::new(),
+        };
+        checkpoint.hash = checkpoint.calculate_hash();
+
+        self.checkpoints.push(checkpoint.clone());
+        Ok(checkpoint)
+    }
+
+    /// Get the most recent checkpoint
+    pub fn latest_checkpoint(&self) -> Option<&TendermintCheckpoint> {
+        self.checkpoints.last()
+    }
+
+    /// Drop block bodies, votes and messages older than the latest checkpoint,
+    /// keeping the headers. Returns the number of pruned blocks.
+    pub fn prune_to_checkpoint(&mut self) -> usize {
+        let Some(height) = self.latest_checkpoint().map(|c| c.height) else {
+            return 0;
+        };
+
+        let keep_from = self.blocks.partition_point(|b| b.height < height);
+        let pruned: Vec<Block> = self.blocks.drain(..keep_from).collect();
+        self.pruned_headers.extend(pruned.iter().map(Block::header));
+
+        self.prevotes.retain(|&(h, _), _| h >= height);
+        self.precommits.retain(|&(h, _), _| h >= height);
+        self.proposals.retain(|&(h, _), _| h >= height);
+        self.messages.retain(|m| message_height(m) >= height);
+
+        pruned.len()
+    }
+
+    /// Headers of every known block, including pruned ones
+    pub fn headers(&self) -> Vec<BlockHeader> {
+        self.pruned_headers
+            .iter()
+            .cloned()
+            .chain(self.blocks.iter().map(Block::header))
+            .collect()
+    }
+
+    /// Create (and persist) a checkpoint if the interval has been reached
+    async fn maybe_checkpoint(&mut self) -> Result<()> {
+        let Some(interval) = self.checkpoint_interval else {
+            return Ok(());
+        };
+        let committed = self.current_height - 1;
+        if committed == 0 || committed % interval != 0 {
+            return Ok(());
+        }
+        if self.latest_checkpoint().map(|c| c.height) == Some(committed) {
+            return Ok(());
+        }
+
+        let checkpoint = self.create_checkpoint()?;
+        if let Some(store) = &self.checkpoint_store {
+            checkpoint.save(store.as_ref()).await?;
+        }
+        Ok(())
+    }
+
+    /// Key this object signs proposals and votes with
+    pub fn validator_key(&self) -> ValidatorKey {
+        ValidatorKey::new(self.signer.public_key().clone())
+    }
+
+    /// Check a signature made by a validator of the set
+    pub fn verify_validator_signature(
+        &self,
+        address: &str,
+        message: &[u8],
+        signature: &str,
+    ) -> Result<bool> {
+        let validator = self.validators.get(address).ok_or_else(|| {
+            ChaincraftError::validation(format!("{} is not a validator", address))
+        })?;
+        validator.public_key.verify(message, signature)
+    }
+
     /// Add a validator to the set
-    pub fn add_validator(&mut self, address: String, public_key: String, voting_power: u64) {
+    ///
+    /// The caller vouches for the key. BLS keys learnt from the network must
+    /// pass [`ValidatorInfo::check_possession`] first, as they do when they
+    /// arrive in a validator set message.
+    pub fn add_validator(&mut self, address: String, public_key: ValidatorKey, voting_power: u64) {
         let validator = ValidatorInfo {
             address: address.clone(),
             public_key,
             voting_power,
             active: true,
+            possession: None,
         };
         self.validators.insert(address, validator);
     }
 
-    /// Get total voting power of active validators
+    /// Get total voting power of active, unjailed validators
     pub fn total_voting_power(&self) -> u64 {
-        self.validators
-            .values()
-            .filter(|v| v.active)
+        self.eligible_validators()
+            .iter()
             .map(|v| v.voting_power)
             .sum()
     }
@@ -197,6 +1259,7 @@ impl TendermintObject {
                     block_hash: block_hash.clone(),
                     signature: signature.clone(),
                     timestamp: Utc::now(),
+                    extension: None,
                 };
 
                 self.prevotes
@@ -219,6 +1282,7 @@ impl TendermintObject {
             block_hash,
             validator,
             signature,
+            extension,
         } = &precommit
         {
             if *height == self.current_height && *round == self.current_round {
@@ -227,6 +1291,7 @@ impl TendermintObject {
                     block_hash: block_hash.clone(),
                     signature: signature.clone(),
                     timestamp: Utc::now(),
+                    extension: extension.clone(),
                 };
 
                 self.precommits
@@ -241,6 +1306,20 @@ impl TendermintObject {
         Ok(false)
     }
 
+    /// Process a prevote or precommit, punishing the sender instead if the
+    /// vote conflicts with one it already sent
+    pub fn process_vote(&mut self, vote: TendermintMessageType) -> Result<bool> {
+        if let Some(evidence) = self.double_sign_of(&vote) {
+            self.punish_double_sign(evidence);
+            self.messages.push(vote);
+            return Ok(true);
+        }
+        match vote {
+            TendermintMessageType::Prevote { .. } => self.process_prevote(vote),
+            _ => self.process_precommit(vote),
+        }
+    }
+
     /// Check if we can commit a block
     pub fn can_commit(&self) -> Option<String> {
         if let Some(precommits) = self
@@ -250,6 +1329,9 @@ impl TendermintObject {
             let mut vote_counts: HashMap<Option<String>, u64> = HashMap::new();
 
             for vote in precommits.values() {
+                if self.is_jailed(&vote.validator) {
+                    continue;
+                }
                 if let Some(validator) = self.validators.get(&vote.validator) {
                     *vote_counts.entry(vote.block_hash.clone()).or_insert(0) +=
                         validator.voting_power;
@@ -269,20 +1351,56 @@ impl TendermintObject {
 
     /// Commit a block
     pub fn commit_block(&mut self, block_hash: String) -> Result<()> {
+        let votes = self
+            .precommits
+            .get(&(self.current_height, self.current_round));
+        let mut extensions: Vec<VoteExtension> = votes
+            .into_iter()
+            .flat_map(|votes| votes.values())
+            .filter(|vote| vote.block_hash.as_deref() == Some(block_hash.as_str()))
+            .filter_map(|vote| {
+                Some(VoteExtension {
+                    validator: vote.validator.clone(),
+                    height: self.current_height,
+                    extension: vote.extension.clone()?,
+                })
+            })
+            .collect();
+        extensions.sort_by(|a, b| a.validator.cmp(&b.validator));
+        let certificate = self.commit_certificate(&block_hash);
+
         let block = Block {
             height: self.current_height,
             hash: block_hash.clone(),
             previous_hash: self.blocks.last().unwrap().hash.clone(),
             timestamp: Utc::now(),
             proposer: self.my_validator_address.clone(),
-            transactions: vec![], // Would contain actual transactions
-            commit_signatures: self
-                .precommits
+            transactions: match self
+                .proposals
                 .get(&(self.current_height, self.current_round))
-                .map(|votes| votes.values().map(|v| v.signature.clone()).collect())
-                .unwrap_or_default(),
+            {
+                Some(TendermintMessageType::Proposal {
+                    block_hash: proposed,
+                    transactions,
+                    ..
+                }) if *proposed == block_hash => transactions.clone(),
+                _ => vec![],
+            },
+            commit_signatures: match certificate {
+                Some(_) => vec![],
+                None => self
+                    .precommits
+                    .get(&(self.current_height, self.current_round))
+                    .map(|votes| votes.values().map(|v| v.signature.clone()).collect())
+                    .unwrap_or_default(),
+            },
+            vote_extensions: std::mem::replace(&mut self.pending_extensions, extensions),
+            app_state_root: None,
+            commit_certificate: certificate,
         };
 
+        self.record_liveness();
+        self.app_digest = chain_digest(&self.app_digest, &block.hash);
         self.blocks.push(block);
         self.current_height += 1;
         self.current_round = 0;
@@ -299,6 +1417,67 @@ impl TendermintObject {
         Ok(())
     }
 
+    /// Aggregate the precommits for `block_hash` in the current round, if
+    /// every one of them is a BLS signature over the same payload
+    fn commit_certificate(&self, block_hash: &str) -> Option<CommitCertificate> {
+        let votes: Vec<&Vote> = self
+            .precommits
+            .get(&(self.current_height, self.current_round))?
+            .values()
+            .filter(|vote| vote.block_hash.as_deref() == Some(block_hash))
+            .collect();
+        if votes.is_empty() || votes.iter().any(|vote| vote.extension.is_some()) {
+            return None;
+        }
+        let mut signers = Vec::new();
+        let mut signatures = Vec::new();
+        for vote in votes {
+            let key = &self.validators.get(&vote.validator)?.public_key;
+            if key.key_type() != KeyType::Bls12_381 {
+                return None;
+            }
+            signatures.push(Signature::from_hex(&vote.signature, KeyType::Bls12_381).ok()?);
+            signers.push(vote.validator.clone());
+        }
+        signers.sort();
+        Some(CommitCertificate {
+            round: self.current_round,
+            signers,
+            signature: bls::aggregate_signatures(&signatures).ok()?.to_hex(),
+        })
+    }
+
+    /// Whether the block's commit certificate holds precommits from a
+    /// +2/3 majority of the current validator set
+    ///
+    /// Aggregation lets a validator whose key is crafted from the others'
+    /// forge their share, which is why BLS validators joining through a
+    /// validator set message must prove possession of their key.
+    pub fn verify_commit_certificate(&self, block: &Block) -> bool {
+        let Some(certificate) = &block.commit_certificate else {
+            return false;
+        };
+        if !certificate.signers.windows(2).all(|pair| pair[0] < pair[1]) {
+            return false;
+        }
+        let mut keys = Vec::new();
+        let mut voting_power = 0;
+        for signer in &certificate.signers {
+            let Some(validator) = self.validators.get(signer) else {
+                return false;
+            };
+            keys.push(validator.public_key.public_key().clone());
+            voting_power += validator.voting_power;
+        }
+        let Ok(signature) = Signature::from_hex(&certificate.signature, KeyType::Bls12_381) else {
+            return false;
+        };
+        let payload =
+            precommit_payload(block.height, certificate.round, &Some(block.hash.clone()), &None);
+        self.has_majority(voting_power)
+            && matches!(bls::verify_aggregate(&keys, payload.as_bytes(), &signature), Ok(true))
+    }
+
     /// Create a proposal for the current height/round
     pub fn create_proposal(
         &self,
@@ -309,6 +1488,7 @@ impl TendermintObject {
             "round": self.current_round,
             "previous_hash": self.blocks.last().unwrap().hash,
             "transactions": transactions,
+            "vote_extensions": self.pending_extensions,
             "timestamp": Utc::now().to_rfc3339()
         });
 
@@ -324,9 +1504,181 @@ impl TendermintObject {
             proposer: self.my_validator_address.clone(),
             timestamp: Utc::now(),
             signature: hex::encode(signature.to_bytes()),
+            transactions,
         })
     }
 
+    /// Create our precommit for the current height/round, extended by the
+    /// vote extension handler when voting for a block
+    pub fn create_precommit(&self, block_hash: Option<String>) -> Result<TendermintMessageType> {
+        let extension = match (&self.vote_extensions, &block_hash) {
+            (Some(handler), Some(hash)) => handler.extend_vote(self.current_height, hash),
+            _ => None,
+        };
+        let signature_data =
+            precommit_payload(self.current_height, self.current_round, &block_hash, &extension);
+        let signature = self.signer.sign(signature_data.as_bytes())?;
+
+        Ok(TendermintMessageType::Precommit {
+            height: self.current_height,
+            round: self.current_round,
+            block_hash,
+            validator: self.my_validator_address.clone(),
+            signature: hex::encode(signature.to_bytes()),
+            extension,
+        })
+    }
+
+    /// Explain why a precommit's extension is unacceptable, if it is
+    fn check_extension(&self, msg: &TendermintMessageType) -> Option<ApplyOutcome> {
+        let TendermintMessageType::Precommit {
+            height,
+            block_hash,
+            validator,
+            extension: Some(extension),
+            ..
+        } = msg
+        else {
+            return None;
+        };
+        let Some(handler) = &self.vote_extensions else {
+            return Some(ApplyOutcome::rejected(
+                "unexpected_extension",
+                "vote extensions are not enabled",
+            ));
+        };
+        let Some(block_hash) = block_hash else {
+            return Some(ApplyOutcome::rejected(
+                "unexpected_extension",
+                "nil precommits carry no extension",
+            ));
+        };
+        if !handler.verify_vote_extension(*height, validator, block_hash, extension) {
+            return Some(ApplyOutcome::rejected(
+                "invalid_extension",
+                format!("{}'s vote extension failed verification", validator),
+            ));
+        }
+        None
+    }
+
+    /// Explain why a message would not be processed, if it would not
+    fn precheck(&self, msg: &TendermintMessageType) -> Option<ApplyOutcome> {
+        if let Some((signer, payload, signature)) = signed_payload(msg) {
+            let Some(validator) = self.validators.get(signer) else {
+                return Some(ApplyOutcome::rejected(
+                    "unknown_validator",
+                    format!("{} is not a validator", signer),
+                ));
+            };
+            if !matches!(validator.public_key.verify(payload.as_bytes(), signature), Ok(true)) {
+                return Some(ApplyOutcome::rejected(
+                    "invalid_signature",
+                    format!("signature does not match {}'s key", signer),
+                ));
+            }
+        }
+        if let Some(outcome) = self.check_extension(msg) {
+            return Some(outcome);
+        }
+
+        let sender = match msg {
+            TendermintMessageType::Proposal { proposer, .. } => proposer,
+            TendermintMessageType::Prevote { validator, .. }
+            | TendermintMessageType::Precommit { validator, .. } => validator,
+            TendermintMessageType::Unjail { validator, .. } => {
+                let Some(liveness) = &self.liveness else {
+                    return Some(ApplyOutcome::ignored(
+                        "jailing_disabled",
+                        "validators are never jailed",
+                    ));
+                };
+                return match liveness.jailed_until(validator) {
+                    None => Some(ApplyOutcome::ignored(
+                        "not_jailed",
+                        format!("{} is not jailed", validator),
+                    )),
+                    Some(until) if self.current_height < until => Some(ApplyOutcome::ignored(
+                        "jail_cooldown",
+                        format!("{} may unjail from height {}", validator, until),
+                    )),
+                    Some(_) => None,
+                };
+            },
+            TendermintMessageType::Evidence { evidence } => return self.check_evidence(evidence),
+            _ => return None,
+        };
+        if self.is_tombstoned(sender) {
+            return Some(ApplyOutcome::ignored(
+                "tombstoned",
+                format!("{} was caught double-signing", sender),
+            ));
+        }
+        if self.is_jailed(sender) {
+            return Some(ApplyOutcome::ignored(
+                "jailed",
+                format!("{} is jailed for missing too many duties", sender),
+            ));
+        }
+        let already = match msg {
+            TendermintMessageType::Proposal { height, round, .. } => {
+                self.proposals.contains_key(&(*height, *round))
+            },
+            // A vote for another block is double-signing, not a duplicate
+            TendermintMessageType::Prevote {
+                height,
+                round,
+                block_hash,
+                ..
+            } => self
+                .prevotes
+                .get(&(*height, *round))
+                .and_then(|votes| votes.get(sender))
+                .is_some_and(|vote| &vote.block_hash == block_hash),
+            TendermintMessageType::Precommit {
+                height,
+                round,
+                block_hash,
+                ..
+            } => self
+                .precommits
+                .get(&(*height, *round))
+                .and_then(|votes| votes.get(sender))
+                .is_some_and(|vote| &vote.block_hash == block_hash),
+            _ => false,
+        };
+        if already {
+            return Some(ApplyOutcome::ignored(
+                "duplicate",
+                format!("{} already sent this for the round", sender),
+            ));
+        }
+        None
+    }
+
+    /// Explain why evidence is not acted on, if it is not
+    fn check_evidence(&self, evidence: &DoubleSignEvidence) -> Option<ApplyOutcome> {
+        let Some(validator) = self.validators.get(&evidence.validator) else {
+            return Some(ApplyOutcome::rejected(
+                "unknown_validator",
+                format!("{} is not a validator", evidence.validator),
+            ));
+        };
+        if self.is_tombstoned(&evidence.validator) {
+            return Some(ApplyOutcome::ignored(
+                "already_tombstoned",
+                format!("{} was already caught double-signing", evidence.validator),
+            ));
+        }
+        if !evidence.verify(&validator.public_key) {
+            return Some(ApplyOutcome::rejected(
+                "invalid_evidence",
+                format!("the votes do not conflict or are not both {}'s", evidence.validator),
+            ));
+        }
+        None
+    }
+
     /// Get current consensus state info
     pub fn get_consensus_info(&self) -> serde_json::Value {
         serde_json::json!({
@@ -337,7 +1689,13 @@ impl TendermintObject {
             "blocks_count": self.blocks.len(),
             "locked_block": self.locked_block,
             "locked_round": self.locked_round,
-            "total_voting_power": self.total_voting_power()
+            "total_voting_power": self.total_voting_power(),
+            "jailed": self.liveness.as_ref().map(LivenessTracker::jailed),
+            "tombstoned": self
+                .equivocations
+                .iter()
+                .map(|e| e.evidence.validator.clone())
+                .collect::<Vec<_>>()
         })
     }
 
@@ -380,48 +1738,98 @@ impl ApplicationObject for TendermintObject {
         Ok(msg_result.is_ok())
     }
 
-    async fn add_message(&mut self, message: SharedMessage) -> Result<()> {
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
         let tendermint_msg: TendermintMessageType = serde_json::from_value(message.data.clone())
             .map_err(|e| {
                 ChaincraftError::Serialization(crate::error::SerializationError::Json(e))
             })?;
 
+        if let Some(outcome) = self.precheck(&tendermint_msg) {
+            return Ok(outcome);
+        }
+
         let processed = match &tendermint_msg {
             TendermintMessageType::Proposal { .. } => {
                 self.process_proposal(tendermint_msg.clone())?
             },
-            TendermintMessageType::Prevote { .. } => {
-                self.process_prevote(tendermint_msg.clone())?
-            },
-            TendermintMessageType::Precommit { .. } => {
-                self.process_precommit(tendermint_msg.clone())?
+            TendermintMessageType::Prevote { .. } | TendermintMessageType::Precommit { .. } => {
+                self.process_vote(tendermint_msg.clone())?
             },
             TendermintMessageType::ValidatorSet { validators, .. } => {
-                for validator in validators {
-                    self.add_validator(
-                        validator.address.clone(),
-                        validator.public_key.clone(),
-                        validator.voting_power,
-                    );
+                // Tombstoned validators stay out whatever the set says
+                let listed: Vec<&ValidatorInfo> = validators
+                    .iter()
+                    .filter(|v| !self.is_tombstoned(&v.address))
+                    .collect();
+                for validator in &listed {
+                    if let Err((code, detail)) = validator.check_possession() {
+                        return Ok(ApplyOutcome::rejected(code, detail));
+                    }
+                }
+                if listed
+                    .iter()
+                    .all(|v| self.validators.get(&v.address) == Some(*v))
+                {
+                    return Ok(ApplyOutcome::ignored(
+                        "validator_set_unchanged",
+                        "every validator is already in the set",
+                    ));
+                }
+                for validator in listed {
+                    // Keeps the proof, so an unchanged set is recognised
+                    let validator = ValidatorInfo {
+                        active: true,
+                        ..validator.clone()
+                    };
+                    self.validators.insert(validator.address.clone(), validator);
                 }
                 true
             },
-            TendermintMessageType::BlockCommit { block_hash, .. } => {
+            TendermintMessageType::BlockCommit {
+                height, block_hash, ..
+            } => {
+                // A commit of an earlier height would append the block again
+                if *height != self.current_height {
+                    return Ok(ApplyOutcome::ignored(
+                        "wrong_height",
+                        format!("expected height {}, got {}", self.current_height, height),
+                    ));
+                }
                 self.commit_block(block_hash.clone())?;
                 true
             },
+            TendermintMessageType::Unjail { validator, .. } => {
+                self.unjail_validator(validator);
+                self.messages.push(tendermint_msg.clone());
+                true
+            },
+            TendermintMessageType::Evidence { evidence } => {
+                self.punish_double_sign(evidence.clone());
+                self.messages.push(tendermint_msg.clone());
+                true
+            },
         };
 
-        if processed {
-            tracing::debug!("Successfully processed Tendermint message: {:?}", tendermint_msg);
+        if !processed {
+            // Votes and proposals are only accepted for the current height and round
+            return Ok(ApplyOutcome::ignored(
+                "wrong_round",
+                format!("expected height {} round {}", self.current_height, self.current_round),
+            ));
+        }
 
-            // Check if we can advance consensus
-            if let Some(commit_hash) = self.can_commit() {
-                self.commit_block(commit_hash)?;
-            }
+        tracing::debug!("Successfully processed Tendermint message: {:?}", tendermint_msg);
+        self.accumulator.apply(&message);
+
+        // Check if we can advance consensus
+        if let Some(commit_hash) = self.can_commit() {
+            self.commit_block(commit_hash)?;
         }
 
-        Ok(())
+        self.execute_committed().await?;
+        self.maybe_checkpoint().await?;
+
+        Ok(ApplyOutcome::Applied)
     }
 
     fn is_merkleized(&self) -> bool {
@@ -429,28 +1837,33 @@ impl ApplicationObject for TendermintObject {
     }
 
     async fn get_latest_digest(&self) -> Result<String> {
-        Ok(format!("{}:{}", self.current_height, self.current_round))
+        Ok(self.accumulator.digest().to_string())
     }
 
     async fn has_digest(&self, digest: &str) -> Result<bool> {
-        let current_digest = format!("{}:{}", self.current_height, self.current_round);
-        Ok(digest == current_digest)
+        Ok(self.accumulator.contains(digest))
     }
 
     async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
         Ok(true)
     }
 
-    async fn add_digest(&mut self, _digest: String) -> Result<bool> {
-        Ok(true)
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
     }
 
-    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new())
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
     }
 
-    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new())
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.accumulator
+            .messages_since(digest)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown digest {}", digest)))
     }
 
     async fn get_state(&self) -> Result<serde_json::Value> {
@@ -462,11 +1875,44 @@ impl ApplicationObject for TendermintObject {
             "validators": self.validators.len(),
             "blocks": self.blocks.len(),
             "messages": self.messages.len(),
+            "pruned_headers": self.pruned_headers.len(),
+            "checkpoint_height": self.latest_checkpoint().map(|c| c.height),
+            "app_state_root": self.blocks.last().and_then(|b| b.app_state_root.clone()),
+            "block_rate": self.block_rate().to_json(),
             "consensus_info": self.get_consensus_info(),
             "voting_stats": self.get_voting_stats()
         }))
     }
 
+    /// Drop logged messages of decided heights, oldest first
+    ///
+    /// Votes carry no time, so a height's messages expire with the time its
+    /// block was committed. The height in progress keeps all of its messages.
+    async fn compact(&mut self, compaction: &Compaction) -> Result<usize> {
+        let committed_at: HashMap<u64, DateTime<Utc>> = self
+            .pruned_headers
+            .iter()
+            .map(|header| (header.height, header.timestamp))
+            .chain(self.blocks.iter().map(|block| (block.height, block.timestamp)))
+            .collect();
+        let excess = compaction.excess(self.messages.len());
+        let dropped = self
+            .messages
+            .iter()
+            .enumerate()
+            .take_while(|(i, message)| {
+                let height = message_height(message);
+                height < self.current_height
+                    && (*i < excess
+                        || committed_at
+                            .get(&height)
+                            .is_some_and(|committed| compaction.is_expired(*committed)))
+            })
+            .count();
+        self.messages.drain(..dropped);
+        Ok(dropped)
+    }
+
     async fn reset(&mut self) -> Result<()> {
         self.current_height = 1;
         self.current_round = 0;
@@ -477,33 +1923,36 @@ impl ApplicationObject for TendermintObject {
         self.locked_block = None;
         self.locked_round = None;
         self.messages.clear();
+        self.pending_extensions.clear();
+        self.executed_height = 0;
+        if let Some(BlockApplication(application)) = self.application.as_mut() {
+            application.reset().await?;
+        }
+        self.history.clear();
+        self.accumulator.reset();
+        if let Some(liveness) = self.liveness.as_mut() {
+            liveness.clear();
+        }
+        for equivocation in self.equivocations.drain(..) {
+            if let Some(validator) = self.validators.get_mut(&equivocation.evidence.validator) {
+                validator.voting_power += equivocation.slashed_power;
+            }
+        }
         Ok(())
     }
 
     fn clone_box(&self) -> Box<dyn ApplicationObject> {
-        // Create a new instance with same configuration
-        let new_obj = TendermintObject::new().unwrap_or_else(|_| {
-            // Fallback if creation fails
-            let signer = ECDSASigner::new().unwrap();
-            let my_validator_address = signer.get_public_key_pem().unwrap();
-            TendermintObject {
-                id: SharedObjectId::new(),
-                validators: HashMap::new(),
-                blocks: vec![],
-                current_height: 1,
-                current_round: 0,
-                state: ConsensusState::Propose,
-                proposals: HashMap::new(),
-                prevotes: HashMap::new(),
-                precommits: HashMap::new(),
-                locked_block: None,
-                locked_round: None,
-                my_validator_address,
-                signer,
-                verifier: ECDSAVerifier::new(),
-                messages: Vec::new(),
-            }
-        });
+        // Create a new instance with the same consensus key
+        let new_obj = TendermintObject::with_signer(self.signer.clone())
+            .expect("signer already produced a public key");
+        let new_obj = match &self.liveness {
+            Some(liveness) => new_obj.with_liveness(liveness.config),
+            None => new_obj,
+        };
+        let new_obj = match &self.vote_extensions {
+            Some(handler) => new_obj.with_vote_extensions(handler.clone()),
+            None => new_obj,
+        };
         Box::new(new_obj)
     }
 
@@ -516,6 +1965,33 @@ impl ApplicationObject for TendermintObject {
     }
 }
 
+impl std::fmt::Debug for TendermintObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TendermintObject")
+            .field("id", &self.id)
+            .field("validators", &self.validators)
+            .field("blocks", &self.blocks)
+            .field("current_height", &self.current_height)
+            .field("current_round", &self.current_round)
+            .field("state", &self.state)
+            .field("locked_block", &self.locked_block)
+            .field("locked_round", &self.locked_round)
+            .field("my_validator_address", &self.my_validator_address)
+            .field("messages", &self.messages.len())
+            .field("app_digest", &self.app_digest)
+            .field("pruned_headers", &self.pruned_headers.len())
+            .field("checkpoints", &self.checkpoints.len())
+            .field("checkpoint_store", &self.checkpoint_store.is_some())
+            .field("liveness", &self.liveness)
+            .field("pending_extensions", &self.pending_extensions.len())
+            .field("vote_extensions", &self.vote_extensions.is_some())
+            .field("application", &self.application().map(|app| app.type_name()))
+            .field("executed_height", &self.executed_height)
+            .field("equivocations", &self.equivocations.len())
+            .finish()
+    }
+}
+
 /// Helper functions for creating Tendermint messages
 pub mod helpers {
     use super::*;
@@ -535,6 +2011,18 @@ pub mod helpers {
         block_hash: String,
         proposer: String,
         signer: &ECDSASigner,
+    ) -> Result<serde_json::Value> {
+        create_block_proposal_message(height, round, block_hash, proposer, vec![], signer)
+    }
+
+    /// Proposal of a block carrying transactions
+    pub fn create_block_proposal_message(
+        height: u64,
+        round: u32,
+        block_hash: String,
+        proposer: String,
+        transactions: Vec<serde_json::Value>,
+        signer: &ECDSASigner,
     ) -> Result<serde_json::Value> {
         let signature_data = format!("proposal:{}:{}:{}", height, round, block_hash);
         let signature = signer.sign(signature_data.as_bytes())?;
@@ -546,6 +2034,7 @@ pub mod helpers {
             proposer,
             timestamp: Utc::now(),
             signature: hex::encode(signature.to_bytes()),
+            transactions,
         };
 
         serde_json::to_value(proposal)
@@ -581,7 +2070,19 @@ pub mod helpers {
         validator: String,
         signer: &ECDSASigner,
     ) -> Result<serde_json::Value> {
-        let signature_data = format!("precommit:{}:{}:{:?}", height, round, block_hash);
+        create_extended_precommit_message(height, round, block_hash, validator, None, signer)
+    }
+
+    /// Precommit carrying a vote extension
+    pub fn create_extended_precommit_message(
+        height: u64,
+        round: u32,
+        block_hash: Option<String>,
+        validator: String,
+        extension: Option<serde_json::Value>,
+        signer: &ECDSASigner,
+    ) -> Result<serde_json::Value> {
+        let signature_data = precommit_payload(height, round, &block_hash, &extension);
         let signature = signer.sign(signature_data.as_bytes())?;
 
         let precommit = TendermintMessageType::Precommit {
@@ -590,9 +2091,34 @@ pub mod helpers {
             block_hash,
             validator,
             signature: hex::encode(signature.to_bytes()),
+            extension,
         };
 
         serde_json::to_value(precommit)
             .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
     }
+
+    /// Report a validator's conflicting votes
+    pub fn create_evidence_message(evidence: DoubleSignEvidence) -> Result<serde_json::Value> {
+        serde_json::to_value(TendermintMessageType::Evidence { evidence })
+            .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
+    }
+
+    pub fn create_unjail_message(
+        validator: String,
+        height: u64,
+        signer: &ECDSASigner,
+    ) -> Result<serde_json::Value> {
+        let signature_data = format!("unjail:{}:{}", validator, height);
+        let signature = signer.sign(signature_data.as_bytes())?;
+
+        let unjail = TendermintMessageType::Unjail {
+            validator,
+            height,
+            signature: hex::encode(signature.to_bytes()),
+        };
+
+        serde_json::to_value(unjail)
+            .map_err(|e| ChaincraftError::Serialization(crate::error::SerializationError::Json(e)))
+    }
 }
@@ -0,0 +1,221 @@
+//! Fan-in quorum for gossiped facts
+//!
+//! A message heard from a single peer is a rumor: that peer may have made it
+//! up. With a [`FanInConfig`] a node holds messages of chosen types as
+//! pending facts and only stores and applies them once they are confirmed,
+//! either relayed by enough distinct peers or signed by enough distinct keys.
+//! Copies are matched by content (message type and data), so the same fact
+//! signed by different validators counts once per signer. The first copy
+//! received is the one applied; later copies of a confirmed fact are dropped.
+//!
+//! Only gossip goes through the quorum. Locally created messages and sync
+//! batches, which replay history peers already applied, are applied directly.
+
+use crate::codec::consensus::ConsensusHasher;
+use crate::network::PeerId;
+use crate::shared::SharedMessage;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// What confirms a fact of a message type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confirmation {
+    /// Received from this many distinct peers
+    Peers(usize),
+    /// Signed by this many distinct keys; unsigned copies do not count
+    Signers(usize),
+}
+
+impl Confirmation {
+    /// Confirmations needed
+    pub fn required(&self) -> usize {
+        match self {
+            Confirmation::Peers(k) | Confirmation::Signers(k) => *k,
+        }
+    }
+}
+
+/// Message types held until confirmed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanInConfig {
+    /// Confirmation required, by message type as displayed
+    pub rules: HashMap<String, Confirmation>,
+    /// Pending facts not confirmed within this long are dropped, and
+    /// confirmed ones are remembered this long to drop their late copies
+    pub pending_ttl: Duration,
+}
+
+impl Default for FanInConfig {
+    fn default() -> Self {
+        Self {
+            rules: HashMap::new(),
+            pending_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+impl FanInConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold messages of `message_type` until `confirmation` is met
+    pub fn with_rule(
+        mut self,
+        message_type: impl Into<String>,
+        confirmation: Confirmation,
+    ) -> Self {
+        self.rules.insert(message_type.into(), confirmation);
+        self
+    }
+
+    pub fn with_pending_ttl(mut self, ttl: Duration) -> Self {
+        self.pending_ttl = ttl;
+        self
+    }
+}
+
+/// A fact waiting for confirmation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingFact {
+    /// Hash of the message type and data
+    pub fact: String,
+    pub message_type: String,
+    /// Hash of the first copy received, the one applied once confirmed
+    pub message_hash: String,
+    /// Peers or keys that vouched for the fact so far
+    pub witnesses: Vec<String>,
+    pub required: Confirmation,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// What to do with a received message
+#[derive(Debug, Clone, PartialEq)]
+pub enum FanInDecision {
+    /// Its type needs no confirmation
+    NotRequired,
+    /// Held until more witnesses vouch for it
+    Pending { witnesses: usize, required: usize },
+    /// Just confirmed: apply this, the first copy received
+    Confirmed(SharedMessage),
+    /// A copy of a fact already confirmed
+    AlreadyConfirmed,
+}
+
+#[derive(Debug)]
+struct Pending {
+    first: SharedMessage,
+    witnesses: BTreeSet<String>,
+    required: Confirmation,
+    first_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct FanInState {
+    pending: HashMap<String, Pending>,
+    confirmed: HashMap<String, DateTime<Utc>>,
+}
+
+/// Pending facts and their witnesses
+#[derive(Debug)]
+pub struct FanInTracker {
+    config: FanInConfig,
+    state: Mutex<FanInState>,
+}
+
+impl FanInTracker {
+    pub fn new(config: FanInConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(FanInState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &FanInConfig {
+        &self.config
+    }
+
+    /// Identity of the fact a message states, shared by all its copies
+    pub fn fact_id(message: &SharedMessage) -> String {
+        ConsensusHasher::new()
+            .str(&message.message_type.to_string())
+            .str(&message.data.to_string())
+            .finish_hex()
+    }
+
+    /// Record that `from` relayed `message` at `now`
+    pub fn observe(
+        &self,
+        from: &PeerId,
+        message: &SharedMessage,
+        now: DateTime<Utc>,
+    ) -> FanInDecision {
+        let Some(&required) = self.config.rules.get(&message.message_type.to_string()) else {
+            return FanInDecision::NotRequired;
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let ttl =
+            chrono::Duration::from_std(self.config.pending_ttl).unwrap_or(chrono::Duration::MAX);
+        state
+            .pending
+            .retain(|_, pending| now - pending.first_seen < ttl);
+        state.confirmed.retain(|_, at| now - *at < ttl);
+
+        let fact = Self::fact_id(message);
+        if state.confirmed.contains_key(&fact) {
+            return FanInDecision::AlreadyConfirmed;
+        }
+        let pending = state
+            .pending
+            .entry(fact.clone())
+            .or_insert_with(|| Pending {
+                first: message.clone(),
+                witnesses: BTreeSet::new(),
+                required,
+                first_seen: now,
+            });
+        let witness = match required {
+            Confirmation::Peers(_) => Some(from.to_string()),
+            Confirmation::Signers(_) => message.verified_sender().map(str::to_string),
+        };
+        if let Some(witness) = witness {
+            pending.witnesses.insert(witness);
+        }
+        if pending.witnesses.len() < required.required() {
+            return FanInDecision::Pending {
+                witnesses: pending.witnesses.len(),
+                required: required.required(),
+            };
+        }
+
+        let confirmed = state
+            .pending
+            .remove(&fact)
+            .expect("the fact was just inserted");
+        state.confirmed.insert(fact, now);
+        FanInDecision::Confirmed(confirmed.first)
+    }
+
+    /// Facts waiting for confirmation, oldest first
+    pub fn pending(&self) -> Vec<PendingFact> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut facts: Vec<PendingFact> = state
+            .pending
+            .iter()
+            .map(|(fact, pending)| PendingFact {
+                fact: fact.clone(),
+                message_type: pending.first.message_type.to_string(),
+                message_hash: pending.first.hash.clone(),
+                witnesses: pending.witnesses.iter().cloned().collect(),
+                required: pending.required,
+                first_seen: pending.first_seen,
+            })
+            .collect();
+        facts.sort_by(|a, b| (a.first_seen, &a.fact).cmp(&(b.first_seen, &b.fact)));
+        facts
+    }
+}
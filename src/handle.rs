@@ -0,0 +1,223 @@
+//! Shared access to a running node
+//!
+//! Lifecycle methods such as [`ChaincraftNode::start`] take `&mut self`, so a
+//! node cannot simply be handed to an RPC server, a timer task and a test at
+//! the same time. [`ChaincraftNode::handle`] returns a [`NodeHandle`] instead:
+//! it is cheap to clone, can be moved into tasks, and reaches the same
+//! storage, objects and event channel as the node it came from. The owned
+//! node keeps control of starting, stopping and connecting to peers, and of
+//! discovery.
+
+use crate::{
+    audit::AuditLog,
+    directory::ObjectDirectory,
+    error::Result,
+    events::NodeEvent,
+    message_stats::MessageStatsReport,
+    metrics::NodeMetrics,
+    network::{PeerId, PeerInfo},
+    node::ChaincraftNode,
+    outbox::OutboxEntry,
+    query::MessageFilter,
+    receipt::Receipt,
+    runtime_config::{ConfigUpdate, RuntimeConfig},
+    shared::{SharedMessage, SharedObjectId},
+    shared_object::{
+        ApplicationObject, ConfigurableObject, HistoryCursor, HistoryEntry, ObjectInfo,
+    },
+    sync::SyncSummary,
+    sync_batch::IngestSummary,
+};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Cloneable handle to a node's message and state API
+#[derive(Clone)]
+pub struct NodeHandle {
+    node: Arc<ChaincraftNode>,
+}
+
+impl NodeHandle {
+    pub(crate) fn new(node: ChaincraftNode) -> Self {
+        Self {
+            node: Arc::new(node),
+        }
+    }
+
+    /// Id of the node
+    pub fn id(&self) -> &PeerId {
+        self.node.id()
+    }
+
+    /// Whether the node has been started and not stopped since
+    pub async fn is_running(&self) -> bool {
+        self.node.is_running_async().await
+    }
+
+    /// Create a message with `data`, store it and process it
+    ///
+    /// Same as [`ChaincraftNode::create_shared_message_with_data`].
+    pub async fn create_message(&self, data: serde_json::Value) -> Result<String> {
+        self.node.originate(data).await
+    }
+
+    /// See [`ChaincraftNode::submit_message`]
+    pub async fn submit_message(&self, message: SharedMessage) -> Result<String> {
+        self.node.submit_message(message).await
+    }
+
+    /// See [`ChaincraftNode::receive_message`]
+    pub async fn receive_message(&self, from: &PeerId, message: SharedMessage) -> Result<String> {
+        self.node.receive_message(from, message).await
+    }
+
+    /// See [`ChaincraftNode::ingest_messages`]
+    pub async fn ingest_messages(
+        &self,
+        from: &PeerId,
+        messages: Vec<SharedMessage>,
+    ) -> Result<IngestSummary> {
+        self.node.ingest_messages(from, messages).await
+    }
+
+    /// See [`ChaincraftNode::schedule_message`]
+    pub async fn schedule_message(
+        &self,
+        data: serde_json::Value,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<String> {
+        self.node.schedule_message(data, at).await
+    }
+
+    pub async fn get_message(&self, hash: &str) -> Result<Option<SharedMessage>> {
+        self.node.get_message(hash).await
+    }
+
+    pub async fn get_receipt(&self, tx_hash: &str) -> Result<Option<Receipt>> {
+        self.node.get_receipt(tx_hash).await
+    }
+
+    pub async fn query_messages(&self, filter: &MessageFilter) -> Result<Vec<SharedMessage>> {
+        self.node.query_messages(filter).await
+    }
+
+    /// See [`ChaincraftNode::add_shared_object`]
+    pub async fn add_shared_object(
+        &self,
+        object: Box<dyn ApplicationObject>,
+    ) -> Result<SharedObjectId> {
+        self.node.add_shared_object(object).await
+    }
+
+    /// See [`ChaincraftNode::confirm_delivery`]
+    pub async fn confirm_delivery(&self, peer_id: &PeerId, hash: &str) -> Result<bool> {
+        self.node.confirm_delivery(peer_id, hash).await
+    }
+
+    /// See [`ChaincraftNode::outbox_pending`]
+    pub async fn outbox_pending(&self) -> Result<Vec<OutboxEntry>> {
+        self.node.outbox_pending().await
+    }
+
+    /// See [`ChaincraftNode::register_object_with_config`]
+    pub async fn register_object_with_config<T: ConfigurableObject>(
+        &self,
+        config: serde_json::Value,
+    ) -> Result<SharedObjectId> {
+        self.node.register_object_with_config::<T>(config).await
+    }
+
+    /// See [`ChaincraftNode::object_history`]
+    pub async fn object_history(
+        &self,
+        id: &SharedObjectId,
+        cursor: Option<HistoryCursor>,
+        limit: usize,
+    ) -> Option<(Vec<HistoryEntry>, Option<HistoryCursor>)> {
+        self.node.object_history(id, cursor, limit).await
+    }
+
+    /// See [`ChaincraftNode::object_info`]
+    pub async fn object_info(&self, id: &SharedObjectId) -> Option<ObjectInfo> {
+        self.node.object_info(id).await
+    }
+
+    /// See [`ChaincraftNode::remove_shared_object`]
+    pub async fn remove_shared_object(&self, id: &SharedObjectId) -> Result<bool> {
+        self.node.remove_shared_object(id).await
+    }
+
+    /// Run `f` on a registered object of type `T` under the registry lock
+    pub async fn with_typed<T: ApplicationObject + 'static, R>(
+        &self,
+        id: &SharedObjectId,
+        f: impl FnOnce(&T) -> R,
+    ) -> Option<R> {
+        self.node.with_typed(id, f).await
+    }
+
+    /// Read-only view of the latest state of every registered object
+    pub async fn object_directory(&self) -> ObjectDirectory {
+        self.node.object_directory().await
+    }
+
+    /// See [`ChaincraftNode::resolve_object_id`]
+    pub async fn resolve_object_id(&self, text: &str) -> Result<SharedObjectId> {
+        self.node.resolve_object_id(text).await
+    }
+
+    /// Node state for testing and debugging, see [`ChaincraftNode::get_state`]
+    pub async fn get_state(&self) -> Result<serde_json::Value> {
+        self.node.get_state().await
+    }
+
+    pub async fn sync_status(&self) -> SyncSummary {
+        self.node.sync_status().await
+    }
+
+    pub async fn get_peers(&self) -> Vec<PeerInfo> {
+        self.node.get_peers().await
+    }
+
+    /// Subscribe to node lifecycle events
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.node.subscribe()
+    }
+
+    pub fn metrics(&self) -> &NodeMetrics {
+        self.node.metrics()
+    }
+
+    /// See [`ChaincraftNode::audit_log`]
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.node.audit_log()
+    }
+
+    /// See [`ChaincraftNode::export_audit_log`]
+    pub async fn export_audit_log(&self, path: impl AsRef<std::path::Path>) -> Result<usize> {
+        self.node.export_audit_log(path).await
+    }
+
+    /// See [`ChaincraftNode::runtime_config`]
+    pub fn runtime_config(&self) -> RuntimeConfig {
+        self.node.runtime_config()
+    }
+
+    /// See [`ChaincraftNode::update_config`]
+    pub fn update_config(&self, update: ConfigUpdate) -> Result<RuntimeConfig> {
+        self.node.update_config(update)
+    }
+
+    /// See [`ChaincraftNode::message_stats`]
+    pub fn message_stats(&self, top: usize) -> MessageStatsReport {
+        self.node.message_stats(top)
+    }
+}
+
+impl std::fmt::Debug for NodeHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeHandle")
+            .field("id", self.node.id())
+            .finish()
+    }
+}
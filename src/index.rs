@@ -0,0 +1,423 @@
+//! Secondary indexes over stored messages
+//!
+//! An index is declared with an [`IndexSpec`]: a name and an extractor that
+//! maps a message to zero or more [`IndexKey`]s. The node updates every
+//! declared index under the same lock that appends to its message log, so an
+//! index never lists a message the log does not hold. Indexes are ordered by
+//! key, which makes lookups and range scans (chat history of one room, the
+//! transfers of one account, blocks between two heights) cheap without
+//! decoding the log.
+//!
+//! [`MessageIndex`] always maintains the built-in indexes behind
+//! [`MessageFilter`]; application objects add their own through
+//! [`crate::shared_object::ApplicationObject::indexes`] and other code through
+//! [`crate::ChaincraftNode::declare_index`].
+
+use crate::error::{ChaincraftError, Result};
+use crate::query::MessageFilter;
+use crate::shared::SharedMessage;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+/// Ordered key of a secondary index
+///
+/// Keys of different kinds order numbers first, then text, then times.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IndexKey {
+    Number(u64),
+    Text(String),
+    Time(DateTime<Utc>),
+}
+
+impl From<u64> for IndexKey {
+    fn from(value: u64) -> Self {
+        IndexKey::Number(value)
+    }
+}
+
+impl From<&str> for IndexKey {
+    fn from(value: &str) -> Self {
+        IndexKey::Text(value.to_string())
+    }
+}
+
+impl From<String> for IndexKey {
+    fn from(value: String) -> Self {
+        IndexKey::Text(value)
+    }
+}
+
+impl From<DateTime<Utc>> for IndexKey {
+    fn from(value: DateTime<Utc>) -> Self {
+        IndexKey::Time(value)
+    }
+}
+
+type KeyExtractor = Arc<dyn Fn(&SharedMessage) -> Vec<IndexKey> + Send + Sync>;
+
+/// Declaration of a secondary index
+#[derive(Clone)]
+pub struct IndexSpec {
+    name: String,
+    extractor: KeyExtractor,
+}
+
+impl IndexSpec {
+    /// Index keyed by whatever `extractor` returns for each message
+    pub fn new(
+        name: impl Into<String>,
+        extractor: impl Fn(&SharedMessage) -> Vec<IndexKey> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            extractor: Arc::new(extractor),
+        }
+    }
+
+    /// Index keyed by the field at a JSON `pointer` into the message data
+    ///
+    /// Unsigned integers become [`IndexKey::Number`] and strings
+    /// [`IndexKey::Text`]; messages without such a field are not indexed.
+    pub fn field(name: impl Into<String>, pointer: impl Into<String>) -> Self {
+        let pointer = pointer.into();
+        Self::new(name, move |message| match message.data.pointer(&pointer) {
+            Some(serde_json::Value::String(text)) => vec![IndexKey::Text(text.clone())],
+            Some(value) => value.as_u64().map(IndexKey::Number).into_iter().collect(),
+            None => Vec::new(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Keys of `message` in this index
+    pub fn keys(&self, message: &SharedMessage) -> Vec<IndexKey> {
+        (self.extractor)(message)
+    }
+}
+
+impl fmt::Debug for IndexSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexSpec")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct SecondaryIndex {
+    spec: IndexSpec,
+    /// Keys with the log positions of the messages carrying them
+    entries: BTreeSet<(IndexKey, usize)>,
+}
+
+impl SecondaryIndex {
+    fn insert(&mut self, message: &SharedMessage, position: usize) {
+        for key in self.spec.keys(message) {
+            self.entries.insert((key, position));
+        }
+    }
+
+    fn range(
+        &self,
+        range: &impl RangeBounds<IndexKey>,
+    ) -> impl Iterator<Item = &(IndexKey, usize)> {
+        let start = match range.start_bound() {
+            Bound::Included(key) => Bound::Included((key.clone(), 0)),
+            Bound::Excluded(key) => Bound::Excluded((key.clone(), usize::MAX)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => Bound::Included((key.clone(), usize::MAX)),
+            Bound::Excluded(key) => Bound::Excluded((key.clone(), 0)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let empty = match (&start, &end) {
+            (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+                s > e
+            },
+            _ => false,
+        };
+        (!empty)
+            .then(|| self.entries.range((start, end)))
+            .into_iter()
+            .flatten()
+    }
+}
+
+/// The message log's secondary indexes
+#[derive(Debug)]
+pub struct MessageIndex {
+    /// Hashes of indexed messages not pruned yet, in log order
+    hashes: VecDeque<String>,
+    /// Log positions before this were pruned; `hashes` starts here
+    pruned: usize,
+    indexes: BTreeMap<String, SecondaryIndex>,
+}
+
+impl MessageIndex {
+    /// Built-in index on [`SharedMessage::message_type`]
+    pub const MESSAGE_TYPE: &'static str = "message_type";
+    /// Built-in index on [`SharedMessage::target_id`]
+    pub const TARGET: &'static str = "target_id";
+    /// Built-in index on [`SharedMessage::verified_sender`]
+    pub const SENDER: &'static str = "sender";
+    /// Built-in index on [`SharedMessage::timestamp`]
+    pub const TIMESTAMP: &'static str = "timestamp";
+
+    /// Index holding only the built-in indexes
+    pub fn new() -> Self {
+        let mut index = Self {
+            hashes: VecDeque::new(),
+            pruned: 0,
+            indexes: BTreeMap::new(),
+        };
+        for spec in [
+            IndexSpec::new(Self::MESSAGE_TYPE, |m| vec![m.message_type.to_string().into()]),
+            IndexSpec::new(Self::TARGET, |m| {
+                m.target_id.iter().map(|id| id.to_string().into()).collect()
+            }),
+            IndexSpec::new(Self::SENDER, |m| {
+                m.verified_sender()
+                    .map(IndexKey::from)
+                    .into_iter()
+                    .collect()
+            }),
+            IndexSpec::new(Self::TIMESTAMP, |m| vec![m.timestamp.into()]),
+        ] {
+            index.add(spec);
+        }
+        index
+    }
+
+    fn add(&mut self, spec: IndexSpec) {
+        self.indexes.insert(
+            spec.name.clone(),
+            SecondaryIndex {
+                spec,
+                entries: BTreeSet::new(),
+            },
+        );
+    }
+
+    /// Declare an index, building it over `existing`, the messages already in the log
+    ///
+    /// `existing` must hold every indexed message not yet pruned, in log
+    /// order. Returns `false` without rebuilding if an index of that name
+    /// already exists.
+    pub fn declare(&mut self, spec: IndexSpec, existing: &[SharedMessage]) -> Result<bool> {
+        if self.indexes.contains_key(spec.name()) {
+            return Ok(false);
+        }
+        if existing.len() != self.hashes.len()
+            || existing
+                .iter()
+                .zip(&self.hashes)
+                .any(|(m, hash)| m.hash != *hash)
+        {
+            return Err(ChaincraftError::validation(format!(
+                "cannot build index {}: messages do not match the log",
+                spec.name()
+            )));
+        }
+
+        let mut index = SecondaryIndex {
+            spec,
+            entries: BTreeSet::new(),
+        };
+        for (offset, message) in existing.iter().enumerate() {
+            index.insert(message, self.pruned + offset);
+        }
+        self.indexes.insert(index.spec.name.clone(), index);
+        Ok(true)
+    }
+
+    /// Whether an index of that name exists
+    pub fn has_index(&self, name: &str) -> bool {
+        self.indexes.contains_key(name)
+    }
+
+    /// Names of all indexes, built-in ones included
+    pub fn index_names(&self) -> Vec<&str> {
+        self.indexes.keys().map(String::as_str).collect()
+    }
+
+    /// Add a newly stored message to every index
+    pub fn insert(&mut self, message: &SharedMessage) {
+        let position = self.len();
+        for index in self.indexes.values_mut() {
+            index.insert(message, position);
+        }
+        self.hashes.push_back(message.hash.clone());
+    }
+
+    /// Like [`Self::insert`], with the message's
+    /// [`SharedMessage::verified_sender`] already known
+    ///
+    /// Saves checking the signature again, e.g. after checking a whole batch
+    /// of signatures in parallel.
+    pub fn insert_verified(&mut self, message: &SharedMessage, verified_sender: Option<&str>) {
+        let position = self.len();
+        for (name, index) in self.indexes.iter_mut() {
+            if name == Self::SENDER {
+                index
+                    .entries
+                    .extend(verified_sender.map(|sender| (IndexKey::from(sender), position)));
+            } else {
+                index.insert(message, position);
+            }
+        }
+        self.hashes.push_back(message.hash.clone());
+    }
+
+    /// Take the next log position for a message that is no longer stored
+    ///
+    /// Keeps the positions in step with the log when it is restored with
+    /// messages deleted since.
+    pub fn insert_missing(&mut self, hash: String) {
+        self.hashes.push_back(hash);
+    }
+
+    /// Start an empty index at log position `position`, the base of a
+    /// restored log
+    pub fn start_at(&mut self, position: usize) {
+        if self.hashes.is_empty() {
+            self.pruned = self.pruned.max(position);
+        }
+    }
+
+    /// Drop the entries of messages before log position `position`
+    pub fn prune_before(&mut self, position: usize) {
+        let position = position.min(self.len());
+        if position <= self.pruned {
+            return;
+        }
+        for index in self.indexes.values_mut() {
+            index.entries.retain(|(_, p)| *p >= position);
+        }
+        self.hashes.drain(..position - self.pruned);
+        self.pruned = position;
+    }
+
+    /// Log position of the oldest message not pruned
+    pub fn pruned(&self) -> usize {
+        self.pruned
+    }
+
+    /// Number of messages indexed since the log started, pruned ones included
+    pub fn len(&self) -> usize {
+        self.pruned + self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hash at log position `position`, which must not be pruned
+    fn hash_at(&self, position: usize) -> &String {
+        &self.hashes[position - self.pruned]
+    }
+
+    fn index(&self, name: &str) -> Result<&SecondaryIndex> {
+        self.indexes
+            .get(name)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown index {}", name)))
+    }
+
+    /// Hashes of messages whose key in index `name` falls in `range`
+    ///
+    /// Ordered by key, then by log order. A message with several keys in the
+    /// range is listed once per key.
+    pub fn scan(
+        &self,
+        name: &str,
+        range: impl RangeBounds<IndexKey>,
+    ) -> Result<Vec<(IndexKey, String)>> {
+        Ok(self
+            .index(name)?
+            .range(&range)
+            .map(|(key, position)| (key.clone(), self.hash_at(*position).clone()))
+            .collect())
+    }
+
+    /// Hashes of messages with `key` in index `name`, in log order
+    pub fn lookup(&self, name: &str, key: &IndexKey) -> Result<Vec<String>> {
+        Ok(self
+            .scan(name, key.clone()..=key.clone())?
+            .into_iter()
+            .map(|(_, hash)| hash)
+            .collect())
+    }
+
+    /// Sorted log positions with a key of index `name` in `range`
+    fn positions(&self, name: &str, range: impl RangeBounds<IndexKey>) -> Vec<usize> {
+        let Ok(index) = self.index(name) else {
+            return Vec::new();
+        };
+        let mut positions: Vec<usize> =
+            index.range(&range).map(|(_, position)| *position).collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+
+    /// Hashes of the messages matching `filter`, in log order
+    ///
+    /// Intersects the built-in indexes for every criterion the filter sets,
+    /// starting from the smallest.
+    pub fn query(&self, filter: &MessageFilter) -> Vec<String> {
+        let mut criteria = Vec::new();
+        if let Some(message_type) = &filter.message_type {
+            let key = IndexKey::from(message_type.to_string());
+            criteria.push(self.positions(Self::MESSAGE_TYPE, key.clone()..=key));
+        }
+        if let Some(target) = &filter.target_id {
+            let key = IndexKey::from(target.to_string());
+            criteria.push(self.positions(Self::TARGET, key.clone()..=key));
+        }
+        if let Some(sender) = &filter.sender {
+            let key = IndexKey::from(sender.as_str());
+            criteria.push(self.positions(Self::SENDER, key.clone()..=key));
+        }
+        if filter.since.is_some() || filter.until.is_some() {
+            let start = filter
+                .since
+                .map_or(Bound::Unbounded, |t| Bound::Included(IndexKey::Time(t)));
+            let end = filter
+                .until
+                .map_or(Bound::Unbounded, |t| Bound::Included(IndexKey::Time(t)));
+            criteria.push(self.positions(Self::TIMESTAMP, (start, end)));
+        }
+
+        criteria.sort_by_key(Vec::len);
+        let mut criteria = criteria.into_iter();
+        let positions = match criteria.next() {
+            Some(smallest) => {
+                let rest: Vec<Vec<usize>> = criteria.collect();
+                smallest
+                    .into_iter()
+                    .filter(|position| {
+                        rest.iter()
+                            .all(|other| other.binary_search(position).is_ok())
+                    })
+                    .collect()
+            },
+            None => (self.pruned..self.len()).collect::<Vec<_>>(),
+        };
+        positions
+            .into_iter()
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .map(|position| self.hash_at(position).clone())
+            .collect()
+    }
+}
+
+impl Default for MessageIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
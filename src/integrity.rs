@@ -0,0 +1,162 @@
+//! Integrity check of stored messages
+//!
+//! Messages are stored under their hash. A storage entry that no longer
+//! decodes, whose message hashes to something other than its key, or whose
+//! claimed sender did not sign it was corrupted on disk or edited by hand.
+//! [`check_storage`] finds such entries and moves them out of the way, under
+//! [`QUARANTINE_PREFIX`], so the node never serves them to peers. A node built
+//! with [`NodeConfig::integrity_check`](crate::node::NodeConfig::integrity_check)
+//! runs the check when it starts.
+//!
+//! Only signatures of messages naming a `sender` are checked: unsigned
+//! messages are a matter of [`NodeMode`](crate::node::NodeMode), not of
+//! integrity.
+
+use crate::error::Result;
+use crate::storage::{MessageEncoding, Storage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Storage namespace holding the original bytes of quarantined entries
+pub const QUARANTINE_PREFIX: &str = "quarantine:";
+
+/// What is wrong with a stored message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corruption {
+    /// The bytes are not a message in any known encoding
+    Undecodable,
+    /// The message's hash does not match its contents or its key
+    HashMismatch,
+    /// The message names a sender whose signature does not verify
+    BadSignature,
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Corruption::Undecodable => "undecodable",
+            Corruption::HashMismatch => "hash_mismatch",
+            Corruption::BadSignature => "bad_signature",
+        })
+    }
+}
+
+/// A storage entry moved to the quarantine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantinedMessage {
+    /// Key the entry was stored under, the message hash
+    pub key: String,
+    pub corruption: Corruption,
+}
+
+/// Summary of an integrity check
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Stored messages examined
+    pub checked: usize,
+    pub quarantined: Vec<QuarantinedMessage>,
+}
+
+impl IntegrityReport {
+    /// Whether every stored message was intact
+    pub fn is_clean(&self) -> bool {
+        self.quarantined.is_empty()
+    }
+
+    /// Quarantined entries by what was wrong with them
+    pub fn counts(&self) -> BTreeMap<Corruption, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in &self.quarantined {
+            *counts.entry(entry.corruption).or_default() += 1;
+        }
+        counts
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} stored messages checked, {} quarantined",
+            self.checked,
+            self.quarantined.len()
+        )?;
+        let counts: Vec<String> = self
+            .counts()
+            .iter()
+            .map(|(corruption, n)| format!("{} {}", n, corruption))
+            .collect();
+        if !counts.is_empty() {
+            write!(f, " ({})", counts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `key` is where a message is stored: a SHA-256 hash in hex
+pub fn is_message_key(key: &str) -> bool {
+    key.len() == 64
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Key a quarantined entry is kept under
+pub fn quarantine_key(key: &str) -> String {
+    format!("{}{}", QUARANTINE_PREFIX, key)
+}
+
+/// What is wrong with the message stored under `key`, `None` if it is intact
+pub fn inspect(key: &str, bytes: &[u8]) -> Option<Corruption> {
+    let Ok(message) = MessageEncoding::decode(bytes) else {
+        return Some(Corruption::Undecodable);
+    };
+    if message.hash != key || !message.verify_hash() {
+        return Some(Corruption::HashMismatch);
+    }
+    if message.sender.is_some() && message.verified_sender().is_none() {
+        return Some(Corruption::BadSignature);
+    }
+    None
+}
+
+/// Check every message in `storage`, quarantining the corrupt ones
+///
+/// A quarantined entry is deleted from its key and kept byte for byte under
+/// [`quarantine_key`] for inspection. Entries outside the message namespace,
+/// such as receipts and object bookkeeping, are left alone.
+pub async fn check_storage(storage: &dyn Storage) -> Result<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+    let mut keys = storage.keys_with_prefix("").await?;
+    keys.retain(|key| is_message_key(key));
+    keys.sort();
+    for key in keys {
+        let Some(bytes) = storage.get(&key).await? else {
+            continue;
+        };
+        report.checked += 1;
+        if let Some(corruption) = inspect(&key, &bytes) {
+            storage
+                .write_batch(vec![(quarantine_key(&key), Some(bytes)), (key.clone(), None)])
+                .await?;
+            report
+                .quarantined
+                .push(QuarantinedMessage { key, corruption });
+        }
+    }
+    Ok(report)
+}
+
+/// Keys of the entries quarantined so far, in the message namespace
+pub async fn quarantined_keys(storage: &dyn Storage) -> Result<Vec<String>> {
+    let mut keys: Vec<String> = storage
+        .keys_with_prefix(QUARANTINE_PREFIX)
+        .await?
+        .into_iter()
+        .map(|key| key[QUARANTINE_PREFIX.len()..].to_string())
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
@@ -2,6 +2,12 @@
 //!
 //! This library provides a clean, well-documented implementation of core blockchain concepts
 //! with a focus on performance, security, and educational value.
+//!
+//! The [`prelude`] is the stable API: the node, its builder and handle,
+//! messages, the [`ApplicationObject`] trait and the error types follow
+//! semantic versioning. The other modules are public so labs can reach into
+//! any layer, but may change in a minor release. Experimental modules are
+//! only built with the `unstable` feature and may change in any release.
 
 #![allow(incomplete_features)]
 #![allow(dead_code)]
@@ -9,27 +15,78 @@
 #![allow(unused_variables)]
 
 // Modules
+#[cfg(all(feature = "api", not(target_arch = "wasm32")))]
+pub mod api;
+pub mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batching;
+pub mod clock;
+pub mod clock_watchdog;
+pub mod codec;
 pub mod consensus;
+pub mod convergence;
+pub mod crdt;
 pub mod crypto;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod diagnostics;
+pub mod directory;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod discovery;
 pub mod error;
+pub mod events;
 pub mod examples;
+pub mod fan_in;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod handle;
+pub mod index;
+pub mod integrity;
+pub mod message_cache;
+pub mod message_log;
+pub mod message_stats;
+pub mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod migrate;
 pub mod network;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod node;
+pub mod outbox;
+pub mod prelude;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod protocol;
+pub mod query;
+pub mod receipt;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod recorder;
+pub mod resources;
+pub mod retention;
+pub mod rng;
+pub mod runtime_config;
+pub mod scheduler;
 pub mod shared;
 pub mod shared_object;
 pub mod storage;
+pub mod sync;
+pub mod sync_batch;
+pub mod tasks;
+pub mod testkit;
+#[cfg(feature = "unstable")]
 pub mod types;
 pub mod utils;
 
 // Re-exports
 pub use error::{ChaincraftError, Result};
-pub use network::{PeerId, PeerInfo};
-pub use node::ChaincraftNode;
+#[cfg(not(target_arch = "wasm32"))]
+pub use handle::NodeHandle;
+pub use network::{PeerId, PeerInfo, PeerStatus};
+#[cfg(not(target_arch = "wasm32"))]
+pub use node::{ChaincraftNode, NodeMode};
+pub use rng::RngProvider;
 pub use shared::{SharedMessage, SharedObject, SharedObjectId, SharedObjectRegistry};
 
 // Application object re-exports
-pub use shared_object::{ApplicationObject, ApplicationObjectRegistry, SimpleSharedNumber};
+pub use shared_object::{
+    ApplicationObject, ApplicationObjectRegistry, ApplyOutcome, RelayDecision, SimpleSharedNumber,
+};
 
 // Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,149 @@
+//! Cache of decoded messages
+//!
+//! Reading a stored message means fetching its bytes and decoding them, and
+//! the same recent messages tend to be read over and over: by queries, by
+//! RPC handlers, by sync. [`MessageCache`] keeps decoded messages keyed by
+//! hash and evicts the least recently used ones once their combined size
+//! passes a byte budget. A message's size is taken to be the length of its
+//! stored encoding, a cheap stand-in for the memory it occupies.
+
+use crate::shared::SharedMessage;
+use lru::LruCache;
+use std::sync::{Arc, Mutex};
+
+/// Default byte budget of the cache
+pub const DEFAULT_MESSAGE_CACHE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Hit, miss and eviction counts of a cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Messages currently cached
+    pub entries: usize,
+    /// Combined size of the cached messages
+    pub bytes: usize,
+}
+
+#[derive(Debug)]
+struct Entries {
+    lru: LruCache<String, (Arc<SharedMessage>, usize)>,
+    bytes: usize,
+    stats: CacheStats,
+}
+
+/// Size-bounded LRU cache of decoded messages
+#[derive(Debug)]
+pub struct MessageCache {
+    max_bytes: usize,
+    entries: Mutex<Entries>,
+}
+
+impl Default for MessageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MESSAGE_CACHE_BYTES)
+    }
+}
+
+impl MessageCache {
+    /// Cache holding up to `max_bytes` of messages; 0 disables caching
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            entries: Mutex::new(Entries {
+                lru: LruCache::unbounded(),
+                bytes: 0,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Look a message up, counting a hit or a miss
+    pub fn get(&self, hash: &str) -> Option<SharedMessage> {
+        let mut entries = self.lock();
+        match entries.lru.get(hash).map(|(message, _)| message.clone()) {
+            Some(message) => {
+                entries.stats.hits += 1;
+                Some(message.as_ref().clone())
+            },
+            None => {
+                entries.stats.misses += 1;
+                None
+            },
+        }
+    }
+
+    /// Whether a message is cached, without touching its recency or the stats
+    pub fn contains(&self, hash: &str) -> bool {
+        self.lock().lru.contains(hash)
+    }
+
+    /// Cache a message of `size` bytes, evicting older ones to make room
+    ///
+    /// Returns the number of evicted messages. A message larger than the
+    /// whole budget is not cached.
+    pub fn insert(&self, message: &SharedMessage, size: usize) -> usize {
+        if size > self.max_bytes {
+            return 0;
+        }
+        let mut entries = self.lock();
+        if let Some((_, previous)) = entries
+            .lru
+            .put(message.hash.clone(), (Arc::new(message.clone()), size))
+        {
+            entries.bytes -= previous;
+        }
+        entries.bytes += size;
+
+        let mut evicted = 0;
+        while entries.bytes > self.max_bytes {
+            match entries.lru.pop_lru() {
+                Some((_, (_, size))) => {
+                    entries.bytes -= size;
+                    evicted += 1;
+                },
+                None => break,
+            }
+        }
+        entries.stats.evictions += evicted as u64;
+        evicted
+    }
+
+    /// Drop a message, e.g. after it was deleted from storage
+    pub fn invalidate(&self, hash: &str) -> bool {
+        let mut entries = self.lock();
+        match entries.lru.pop(hash) {
+            Some((_, size)) => {
+                entries.bytes -= size;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Drop every message; the stats are kept
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.lru.clear();
+        entries.bytes = 0;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.lock();
+        CacheStats {
+            entries: entries.lru.len(),
+            bytes: entries.bytes,
+            ..entries.stats
+        }
+    }
+
+    // Never held across an await; a poisoned lock still holds a consistent cache
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,130 @@
+//! The node's log of stored messages
+//!
+//! Every stored message takes the next sequence number, and peers sync by
+//! those numbers. Pruning drops the oldest hashes from the front of the log
+//! but keeps counting where it left off, so the messages that remain keep
+//! their sequence numbers and the log length peers were told stays valid.
+//!
+//! The node keeps the log in storage too, one [`entry_key`] per sequence
+//! number and the base under [`BASE_KEY`], and [`MessageLog::load`]s it when
+//! it starts, so a restarted node serves and prunes the messages it stored
+//! before.
+
+use crate::error::{ChaincraftError, Result, StorageError};
+use crate::storage::Storage;
+use std::collections::{HashSet, VecDeque};
+
+/// Storage key of the sequence number of the oldest retained hash
+pub const BASE_KEY: &str = "message_log:base";
+
+/// Storage key of the hash at `sequence`
+pub fn entry_key(sequence: usize) -> String {
+    format!("message_log:{:020}", sequence)
+}
+
+/// Hashes of stored messages in the order they were stored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageLog {
+    /// Sequence number of the oldest retained hash
+    base: usize,
+    hashes: VecDeque<String>,
+    /// Retained hashes whose message was deleted or quarantined since
+    missing: HashSet<String>,
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the log kept in `storage`, empty if there is none
+    pub async fn load(storage: &dyn Storage) -> Result<Self> {
+        let base = match storage.get(BASE_KEY).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => 0,
+        };
+        let mut log = Self {
+            base,
+            ..Self::default()
+        };
+        while let Some(bytes) = storage.get(&entry_key(log.len())).await? {
+            let hash = String::from_utf8(bytes).map_err(|_| {
+                ChaincraftError::Storage(StorageError::DatabaseOperation {
+                    reason: format!("message log entry {} is not a hash", log.len()),
+                })
+            })?;
+            log.hashes.push_back(hash);
+        }
+        Ok(log)
+    }
+
+    /// Messages logged since the log started, pruned ones included
+    ///
+    /// This is also the sequence number the next message takes.
+    pub fn len(&self) -> usize {
+        self.base + self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sequence number of the oldest message still in the log
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Number of messages still in the log
+    pub fn retained(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Number of retained hashes whose message is still stored
+    pub fn stored(&self) -> usize {
+        self.hashes.len() - self.missing.len()
+    }
+
+    /// Note that the message of a retained hash is no longer stored
+    pub fn mark_missing(&mut self, hash: &str) {
+        if !self.missing.contains(hash) && self.hashes.iter().any(|h| h == hash) {
+            self.missing.insert(hash.to_string());
+        }
+    }
+
+    /// Append a hash; returns its sequence number
+    pub fn push(&mut self, hash: String) -> usize {
+        self.hashes.push_back(hash);
+        self.len() - 1
+    }
+
+    /// Hash at `sequence`, `None` if it was pruned or is not logged yet
+    pub fn get(&self, sequence: usize) -> Option<&String> {
+        self.hashes.get(sequence.checked_sub(self.base)?)
+    }
+
+    /// Retained hashes from `sequence` on, in log order
+    ///
+    /// Starts at [`Self::base`] if `sequence` was pruned.
+    pub fn since(&self, sequence: usize) -> impl Iterator<Item = &String> {
+        self.hashes.iter().skip(sequence.saturating_sub(self.base))
+    }
+
+    /// Retained hashes in log order
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.hashes.iter()
+    }
+
+    /// Retained hashes in log order
+    pub fn hashes(&self) -> Vec<String> {
+        self.hashes.iter().cloned().collect()
+    }
+
+    /// Drop the hashes before `sequence`
+    pub fn prune_before(&mut self, sequence: usize) {
+        let count = sequence.saturating_sub(self.base).min(self.hashes.len());
+        for hash in self.hashes.drain(..count) {
+            self.missing.remove(&hash);
+        }
+        self.base += count;
+    }
+}
@@ -0,0 +1,203 @@
+//! Recent traffic by message type and sender
+//!
+//! On a shared network it helps to see at a glance which application or
+//! which node produces most of the traffic. [`MessageStats`] counts stored
+//! messages per type and per sender public key over a sliding window, kept
+//! as [`MessageStatsConfig::buckets`] slices of equal length so old traffic
+//! drops out without remembering every message. A [`MessageStatsReport`]
+//! lists the types and the top senders of the window, busiest first.
+
+use crate::shared::SharedMessage;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Length and resolution of the statistics window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageStatsConfig {
+    /// Traffic older than this is forgotten
+    pub window: Duration,
+    /// Number of slices the window is kept in
+    pub buckets: usize,
+}
+
+impl Default for MessageStatsConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(300),
+            buckets: 30,
+        }
+    }
+}
+
+impl MessageStatsConfig {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Bucket {
+    /// Start of the slice, in slice lengths since the epoch
+    slot: i64,
+    types: HashMap<String, u64>,
+    senders: HashMap<String, u64>,
+    unsigned: u64,
+}
+
+/// Messages of one type within the window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeCount {
+    pub message_type: String,
+    pub count: u64,
+}
+
+/// Messages signed by one sender within the window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SenderCount {
+    /// Sender public key
+    pub sender: String,
+    pub count: u64,
+    /// Fraction of all messages in the window
+    pub share: f64,
+}
+
+/// Traffic of the window, busiest first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MessageStatsReport {
+    pub window_secs: u64,
+    pub total: u64,
+    pub by_type: Vec<TypeCount>,
+    pub top_senders: Vec<SenderCount>,
+    /// Messages without a sender key
+    pub unsigned: u64,
+}
+
+/// Windowed message counts per type and sender
+#[derive(Debug)]
+pub struct MessageStats {
+    config: MessageStatsConfig,
+    buckets: Mutex<VecDeque<Bucket>>,
+}
+
+impl MessageStats {
+    pub fn new(config: MessageStatsConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn config(&self) -> &MessageStatsConfig {
+        &self.config
+    }
+
+    /// Count a message stored at `at`
+    pub fn record(&self, message: &SharedMessage, at: DateTime<Utc>) {
+        let slot = self.slot(at);
+        let mut buckets = self.lock();
+        self.expire(&mut buckets, slot);
+        // Late timestamps count in the latest slice
+        if buckets.back().is_none_or(|bucket| bucket.slot < slot) {
+            buckets.push_back(Bucket {
+                slot,
+                ..Bucket::default()
+            });
+        }
+        let Some(bucket) = buckets.back_mut() else {
+            return;
+        };
+        *bucket
+            .types
+            .entry(message.message_type.to_string())
+            .or_default() += 1;
+        match &message.sender {
+            Some(sender) => *bucket.senders.entry(sender.clone()).or_default() += 1,
+            None => bucket.unsigned += 1,
+        }
+    }
+
+    /// Counts of the window ending at `now`, with the `top` busiest senders
+    pub fn report(&self, now: DateTime<Utc>, top: usize) -> MessageStatsReport {
+        let mut buckets = self.lock();
+        self.expire(&mut buckets, self.slot(now));
+
+        let mut types: HashMap<&str, u64> = HashMap::new();
+        let mut senders: HashMap<&str, u64> = HashMap::new();
+        let mut unsigned = 0;
+        for bucket in buckets.iter() {
+            for (message_type, count) in &bucket.types {
+                *types.entry(message_type).or_default() += count;
+            }
+            for (sender, count) in &bucket.senders {
+                *senders.entry(sender).or_default() += count;
+            }
+            unsigned += bucket.unsigned;
+        }
+        let total: u64 = types.values().sum();
+
+        let mut by_type: Vec<TypeCount> = types
+            .into_iter()
+            .map(|(message_type, count)| TypeCount {
+                message_type: message_type.to_string(),
+                count,
+            })
+            .collect();
+        by_type.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.message_type.cmp(&b.message_type))
+        });
+        let mut top_senders: Vec<SenderCount> = senders
+            .into_iter()
+            .map(|(sender, count)| SenderCount {
+                sender: sender.to_string(),
+                count,
+                share: count as f64 / total as f64,
+            })
+            .collect();
+        top_senders.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.sender.cmp(&b.sender)));
+        top_senders.truncate(top);
+
+        MessageStatsReport {
+            window_secs: self.config.window.as_secs(),
+            total,
+            by_type,
+            top_senders,
+            unsigned,
+        }
+    }
+
+    fn slot_millis(&self) -> i64 {
+        let millis = self.config.window.as_millis() / self.config.buckets.max(1) as u128;
+        (millis as i64).max(1)
+    }
+
+    fn slot(&self, at: DateTime<Utc>) -> i64 {
+        at.timestamp_millis().div_euclid(self.slot_millis())
+    }
+
+    /// Drop slices that ended before the window reaching to `slot`
+    fn expire(&self, buckets: &mut VecDeque<Bucket>, slot: i64) {
+        let oldest = slot - self.config.buckets.max(1) as i64;
+        while buckets.front().is_some_and(|bucket| bucket.slot <= oldest) {
+            buckets.pop_front();
+        }
+    }
+
+    // Never held across an await; a poisoned lock still holds valid counts
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Bucket>> {
+        self.buckets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MessageStats {
+    fn default() -> Self {
+        Self::new(MessageStatsConfig::default())
+    }
+}
@@ -0,0 +1,97 @@
+//! Lightweight node metrics
+//!
+//! Counters and gauges are addressed by name so subsystems can record what they
+//! do without a central schema. A [`NodeMetrics::snapshot`] returns all values
+//! sorted by name, ready to be logged or served over an API.
+//!
+//! Metrics also keep the time each message was first seen, the raw data for
+//! [`crate::convergence`].
+
+use chrono::{DateTime, Utc};
+use dashmap::{mapref::entry::Entry, DashMap};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Named counters and gauges for a node
+#[derive(Debug, Default)]
+pub struct NodeMetrics {
+    values: DashMap<String, AtomicU64>,
+    first_seen: DashMap<String, DateTime<Utc>>,
+}
+
+impl NodeMetrics {
+    /// Create an empty metrics set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Increment a counter by one
+    pub fn incr(&self, name: &str) {
+        self.add(name, 1);
+    }
+
+    /// Increment a counter by the given amount
+    pub fn add(&self, name: &str, value: u64) {
+        if let Some(counter) = self.values.get(name) {
+            counter.fetch_add(value, Ordering::Relaxed);
+            return;
+        }
+        self.values
+            .entry(name.to_string())
+            .or_default()
+            .fetch_add(value, Ordering::Relaxed);
+    }
+
+    /// Set a gauge to the given value
+    pub fn set(&self, name: &str, value: u64) {
+        self.values
+            .entry(name.to_string())
+            .or_default()
+            .store(value, Ordering::Relaxed);
+    }
+
+    /// Read a single value (0 if never recorded)
+    pub fn get(&self, name: &str) -> u64 {
+        self.values
+            .get(name)
+            .map(|value| value.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Get all recorded values sorted by name
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.values
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Record that message `hash` was seen at `at`, unless it was seen before
+    ///
+    /// Returns whether this was the first sighting.
+    pub fn record_first_seen(&self, hash: &str, at: DateTime<Utc>) -> bool {
+        if self.first_seen.contains_key(hash) {
+            return false;
+        }
+        match self.first_seen.entry(hash.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(at);
+                true
+            },
+        }
+    }
+
+    /// When message `hash` was first seen
+    pub fn first_seen(&self, hash: &str) -> Option<DateTime<Utc>> {
+        self.first_seen.get(hash).map(|seen| *seen)
+    }
+
+    /// First sightings of all messages, by hash
+    pub fn first_seen_times(&self) -> BTreeMap<String, DateTime<Utc>> {
+        self.first_seen
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+}
@@ -0,0 +1,401 @@
+//! Moving a node's storage between backends
+//!
+//! A lab usually starts on [`MemoryStorage`] and later needs the same state on
+//! disk. [`migrate`] copies every entry from one [`StorageLocation`] to
+//! another, namespace by namespace and in batches, reporting progress as it
+//! goes, then checks the copy: the number of entries in each namespace must
+//! match, and a sample of entries spread over the key space must hash the
+//! same on both sides.
+//!
+//! A location is either a JSON dump, as written by [`write_dump`] or by a
+//! node started with `chaincraft-cli --storage lab.json`, or `sled:<dir>`, a
+//! [`SledStorage`](crate::storage::SledStorage) database, available with the
+//! `persistent` feature. `chaincraft-cli migrate --from lab.json --to
+//! sled:./data` runs a migration from the command line.
+
+use crate::error::{ChaincraftError, Result};
+use crate::integrity::is_message_key;
+use crate::storage::{MemoryStorage, Storage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Version of the dump format written by [`write_dump`]
+pub const DUMP_VERSION: u32 = 1;
+
+/// Namespace of stored messages, whose keys are bare hashes
+pub const MESSAGES_NAMESPACE: &str = "messages";
+
+/// Where a node's storage lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageLocation {
+    /// JSON dump of every entry, loaded into memory
+    Dump(PathBuf),
+    /// sled database directory
+    Sled(PathBuf),
+}
+
+impl FromStr for StorageLocation {
+    type Err = ChaincraftError;
+
+    /// `sled:<dir>` for a sled database; `json:<file>` or a bare path for a dump
+    fn from_str(s: &str) -> Result<Self> {
+        let location = match s.split_once(':') {
+            Some(("sled", path)) => StorageLocation::Sled(PathBuf::from(path)),
+            Some(("json", path)) => StorageLocation::Dump(PathBuf::from(path)),
+            _ => StorageLocation::Dump(PathBuf::from(s)),
+        };
+        match &location {
+            StorageLocation::Dump(path) | StorageLocation::Sled(path)
+                if path.as_os_str().is_empty() =>
+            {
+                Err(ChaincraftError::config(format!("storage location '{}' has no path", s)))
+            },
+            _ => Ok(location),
+        }
+    }
+}
+
+impl fmt::Display for StorageLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageLocation::Dump(path) => write!(f, "{}", path.display()),
+            StorageLocation::Sled(path) => write!(f, "sled:{}", path.display()),
+        }
+    }
+}
+
+impl StorageLocation {
+    /// Open the storage, starting empty if a dump does not exist yet
+    pub async fn open(&self) -> Result<Arc<dyn Storage>> {
+        match self {
+            StorageLocation::Dump(path) if !path.exists() => Ok(Arc::new(MemoryStorage::new())),
+            StorageLocation::Dump(path) => Ok(Arc::new(read_dump(path).await?)),
+            #[cfg(feature = "persistent")]
+            StorageLocation::Sled(path) => Ok(Arc::new(crate::storage::SledStorage::open(path)?)),
+            #[cfg(not(feature = "persistent"))]
+            StorageLocation::Sled(_) => Err(ChaincraftError::config(
+                "sled storage needs a build with the `persistent` feature",
+            )),
+        }
+    }
+
+    /// Make writes to `storage`, opened from this location, durable
+    ///
+    /// A dump is rewritten from the storage; a database is flushed.
+    pub async fn save(&self, storage: &dyn Storage) -> Result<()> {
+        match self {
+            StorageLocation::Dump(path) => write_dump(storage, path).await.map(|_| ()),
+            StorageLocation::Sled(_) => storage.flush().await,
+        }
+    }
+}
+
+/// Every entry of a storage, as written to a JSON dump
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageDump {
+    pub version: u32,
+    /// Hex-encoded value, by key
+    pub entries: BTreeMap<String, String>,
+}
+
+/// Write every entry of `storage` to `path` as a JSON dump
+///
+/// Returns the number of entries written.
+pub async fn write_dump(storage: &dyn Storage, path: impl AsRef<Path>) -> Result<usize> {
+    let mut dump = StorageDump {
+        version: DUMP_VERSION,
+        entries: BTreeMap::new(),
+    };
+    for key in storage.keys_with_prefix("").await? {
+        if let Some(value) = storage.get(&key).await? {
+            dump.entries.insert(key, hex::encode(value));
+        }
+    }
+    tokio::fs::write(path, serde_json::to_vec_pretty(&dump)?).await?;
+    Ok(dump.entries.len())
+}
+
+/// Load a JSON dump written by [`write_dump`] into memory
+pub async fn read_dump(path: impl AsRef<Path>) -> Result<MemoryStorage> {
+    let path = path.as_ref();
+    let dump: StorageDump = serde_json::from_slice(&tokio::fs::read(path).await?)?;
+    if dump.version != DUMP_VERSION {
+        return Err(ChaincraftError::validation(format!(
+            "{}: dump version {} is not supported, expected {}",
+            path.display(),
+            dump.version,
+            DUMP_VERSION
+        )));
+    }
+    let mut batch = Vec::with_capacity(dump.entries.len());
+    for (key, value) in dump.entries {
+        let value = hex::decode(&value).map_err(|e| {
+            ChaincraftError::validation(format!("{}: entry '{}': {}", path.display(), key, e))
+        })?;
+        batch.push((key, Some(value)));
+    }
+    let storage = MemoryStorage::new();
+    storage.write_batch(batch).await?;
+    Ok(storage)
+}
+
+/// Namespace a key belongs to: the text before its first `:`
+///
+/// Message keys, bare hashes, belong to [`MESSAGES_NAMESPACE`].
+pub fn namespace(key: &str) -> &str {
+    match key.split_once(':') {
+        Some((namespace, _)) => namespace,
+        None if is_message_key(key) => MESSAGES_NAMESPACE,
+        None => "",
+    }
+}
+
+/// How to copy and how thoroughly to check the copy
+#[derive(Debug, Clone)]
+pub struct MigrationConfig {
+    /// Entries read and written at a time
+    pub batch_size: usize,
+    /// Entries compared by hash after copying
+    pub sample_size: usize,
+    /// Clear a target that already holds entries instead of refusing it
+    pub overwrite: bool,
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            sample_size: 64,
+            overwrite: false,
+        }
+    }
+}
+
+impl MigrationConfig {
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_sample_size(mut self, sample_size: usize) -> Self {
+        self.sample_size = sample_size;
+        self
+    }
+
+    pub fn with_overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+}
+
+/// How far a migration got, reported after each batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Namespace being copied
+    pub namespace: String,
+    /// Entries of the namespace copied so far
+    pub copied: usize,
+    /// Entries in the namespace
+    pub total: usize,
+    /// Entries of every namespace copied so far
+    pub copied_overall: usize,
+    /// Entries in the source
+    pub total_overall: usize,
+}
+
+/// Entries of a namespace on each side
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceCount {
+    pub source: usize,
+    pub target: usize,
+}
+
+/// Result of a migration and of checking its copy
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Entries copied
+    pub copied: usize,
+    /// Entries by namespace in the source and, after copying, the target
+    pub namespaces: BTreeMap<String, NamespaceCount>,
+    /// Entries compared by hash
+    pub sampled: usize,
+    /// Sampled keys missing from the target or holding different bytes
+    pub mismatched: Vec<String>,
+}
+
+impl MigrationReport {
+    /// Whether every namespace count matches and every sample hashed the same
+    pub fn is_verified(&self) -> bool {
+        self.mismatched.is_empty()
+            && self
+                .namespaces
+                .values()
+                .all(|count| count.source == count.target)
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} entries copied", self.copied)?;
+        for (namespace, count) in &self.namespaces {
+            let name = if namespace.is_empty() {
+                "(none)"
+            } else {
+                namespace
+            };
+            write!(f, "  {}: {} -> {}", name, count.source, count.target)?;
+            if count.source != count.target {
+                write!(f, " MISMATCH")?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "{} sampled entries checked, {} mismatched",
+            self.sampled,
+            self.mismatched.len()
+        )
+    }
+}
+
+/// Copy every entry of `from` to `to` and check the copy
+///
+/// A target that does not exist yet is created. Fails if the target already
+/// holds entries, unless [`MigrationConfig::overwrite`] is set, and when the
+/// copy cannot be read back; a copy that reads back differently is reported
+/// in [`MigrationReport::mismatched`] and the namespace counts.
+pub async fn migrate(
+    from: &StorageLocation,
+    to: &StorageLocation,
+    config: &MigrationConfig,
+    progress: impl FnMut(&MigrationProgress),
+) -> Result<MigrationReport> {
+    if from == to {
+        return Err(ChaincraftError::config(format!("cannot migrate {} onto itself", from)));
+    }
+    if let StorageLocation::Dump(path) = from {
+        if !path.exists() {
+            return Err(ChaincraftError::config(format!(
+                "no dump to migrate at {}",
+                path.display()
+            )));
+        }
+    }
+    let source = from.open().await?;
+    let target = to.open().await?;
+    let copied = copy_storage(source.as_ref(), target.as_ref(), config, progress).await?;
+    to.save(target.as_ref()).await?;
+    drop(target);
+
+    // Check what a node opening the target would actually find
+    let target = to.open().await?;
+    let mut report = verify_copy(source.as_ref(), target.as_ref(), config.sample_size).await?;
+    report.copied = copied;
+    Ok(report)
+}
+
+/// Copy every entry of `source` into `target`, by namespace and in batches
+///
+/// Returns the number of entries copied.
+pub async fn copy_storage(
+    source: &dyn Storage,
+    target: &dyn Storage,
+    config: &MigrationConfig,
+    mut progress: impl FnMut(&MigrationProgress),
+) -> Result<usize> {
+    if !target.keys_with_prefix("").await?.is_empty() {
+        if !config.overwrite {
+            return Err(ChaincraftError::config(
+                "migration target already holds entries; pass overwrite to replace them",
+            ));
+        }
+        target.clear().await?;
+    }
+
+    let namespaces = keys_by_namespace(source).await?;
+    let total_overall = namespaces.values().map(Vec::len).sum();
+    let mut copied_overall = 0;
+    for (namespace, keys) in namespaces {
+        let mut copied = 0;
+        for chunk in keys.chunks(config.batch_size.max(1)) {
+            let mut batch = Vec::with_capacity(chunk.len());
+            for key in chunk {
+                if let Some(value) = source.get(key).await? {
+                    batch.push((key.clone(), Some(value)));
+                }
+            }
+            target.write_batch(batch).await?;
+            copied += chunk.len();
+            copied_overall += chunk.len();
+            progress(&MigrationProgress {
+                namespace: namespace.clone(),
+                copied,
+                total: keys.len(),
+                copied_overall,
+                total_overall,
+            });
+        }
+    }
+    target.flush().await?;
+    Ok(copied_overall)
+}
+
+/// Compare namespace counts and a sample of entries of `source` and `target`
+///
+/// The sample is spread evenly over the sorted keys of the source, so
+/// repeated checks compare the same entries.
+pub async fn verify_copy(
+    source: &dyn Storage,
+    target: &dyn Storage,
+    sample_size: usize,
+) -> Result<MigrationReport> {
+    let source_keys = keys_by_namespace(source).await?;
+    let target_keys = keys_by_namespace(target).await?;
+    let mut report = MigrationReport::default();
+    for (namespace, keys) in &source_keys {
+        report
+            .namespaces
+            .entry(namespace.clone())
+            .or_default()
+            .source = keys.len();
+    }
+    for (namespace, keys) in &target_keys {
+        report
+            .namespaces
+            .entry(namespace.clone())
+            .or_default()
+            .target = keys.len();
+    }
+
+    let keys: Vec<&String> = source_keys.values().flatten().collect();
+    let sample_size = sample_size.min(keys.len());
+    for i in 0..sample_size {
+        let key = keys[i * keys.len() / sample_size];
+        let expected = source.get(key).await?.map(Sha256::digest);
+        let actual = target.get(key).await?.map(Sha256::digest);
+        if actual.is_none() || actual != expected {
+            report.mismatched.push(key.clone());
+        }
+    }
+    report.sampled = sample_size;
+    Ok(report)
+}
+
+async fn keys_by_namespace(storage: &dyn Storage) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut namespaces: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for key in storage.keys_with_prefix("").await? {
+        namespaces
+            .entry(namespace(&key).to_string())
+            .or_default()
+            .push(key);
+    }
+    for keys in namespaces.values_mut() {
+        keys.sort();
+    }
+    Ok(namespaces)
+}
@@ -1,19 +1,57 @@
 //! Networking module for peer-to-peer communication
 
+pub mod access;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod bench;
+pub mod bridge;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod doctor;
+pub mod fanout;
+pub mod framing;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod grpc;
+pub mod keepalive;
+#[cfg(all(feature = "p2p", not(target_arch = "wasm32")))]
+pub mod libp2p;
+pub mod link;
+pub mod quota;
+pub mod reliable;
+pub mod sharding;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod topology;
+pub mod transport;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod udp;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub mod websocket;
+
+use crate::rng::RngProvider;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::net::SocketAddr;
 use uuid::Uuid;
 
+/// Version of the peer protocol spoken by this build
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Network nodes join unless configured otherwise
+pub const DEFAULT_NETWORK_ID: &str = "chaincraft";
+
 /// Unique identifier for a peer
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct PeerId(Uuid);
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct PeerId(#[schemars(with = "String")] Uuid);
 
 impl PeerId {
     pub fn new() -> Self {
         Self(Uuid::new_v4())
     }
 
+    /// Create a peer identifier drawn from the given RNG provider
+    pub fn new_with_rng(rng: &RngProvider) -> Self {
+        Self(rng.next_uuid())
+    }
+
     pub fn from_uuid(uuid: Uuid) -> Self {
         Self(uuid)
     }
@@ -25,18 +63,75 @@ impl fmt::Display for PeerId {
     }
 }
 
+impl std::str::FromStr for PeerId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
 impl Default for PeerId {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Whether a peer answers keepalives, see [`keepalive`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerStatus {
+    /// Heard from since the last keepalive, or not pinged yet
+    #[default]
+    Alive,
+    /// Missed at least one keepalive
+    Suspect,
+    /// Missed too many keepalives; left out of gossip
+    Dead,
+}
+
+impl fmt::Display for PeerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerStatus::Alive => write!(f, "alive"),
+            PeerStatus::Suspect => write!(f, "suspect"),
+            PeerStatus::Dead => write!(f, "dead"),
+        }
+    }
+}
+
+/// What happens to a new peer once `max_peers` peers are known
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerAdmission {
+    /// Refuse the new peer and keep the current ones
+    #[default]
+    RejectNew,
+    /// Drop the peer heard from least recently to make room
+    EvictLeastRecentlySeen,
+}
+
+impl fmt::Display for PeerAdmission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerAdmission::RejectNew => write!(f, "reject_new"),
+            PeerAdmission::EvictLeastRecentlySeen => write!(f, "evict_least_recently_seen"),
+        }
+    }
+}
+
 /// Information about a peer
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerInfo {
     pub id: PeerId,
     pub address: SocketAddr,
     pub last_seen: chrono::DateTime<chrono::Utc>,
+    /// Hex public key the peer proved it holds, if known
+    #[serde(default)]
+    pub public_key: Option<String>,
+    /// Liveness as last seen by keepalives
+    #[serde(default)]
+    pub status: PeerStatus,
 }
 
 impl PeerInfo {
@@ -45,6 +140,13 @@ impl PeerInfo {
             id,
             address,
             last_seen: chrono::Utc::now(),
+            public_key: None,
+            status: PeerStatus::Alive,
         }
     }
+
+    pub fn with_public_key(mut self, public_key: impl Into<String>) -> Self {
+        self.public_key = Some(public_key.into());
+        self
+    }
 }
@@ -0,0 +1,169 @@
+//! Static peer allowlists and denylists
+//!
+//! A [`PeerAccess`] lists peers by public key or by address range. Peers
+//! matching the denylist are always refused. In a private network only peers
+//! matching the allowlist are admitted, which keeps a classroom network
+//! isolated from other nodes on the same shared Wi-Fi; otherwise everyone
+//! not denied is.
+//!
+//! Nodes check the list when a peer is added and when a peer introduces
+//! itself in a handshake. A key only counts if the peer proved it holds it,
+//! e.g. by signing its [`crate::network::grpc::proto::Hello`].
+
+use crate::error::{ChaincraftError, Result};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// A peer or range of peers named in an access list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerPattern {
+    /// Hex public key of the peer's node key
+    PublicKey(String),
+    /// Addresses sharing the first `prefix` bits of `network`
+    Cidr { network: IpAddr, prefix: u8 },
+}
+
+impl PeerPattern {
+    /// Whether a peer seen at `address` and proven to hold `public_key` matches
+    pub fn matches(&self, address: Option<IpAddr>, public_key: Option<&str>) -> bool {
+        match self {
+            PeerPattern::PublicKey(key) => {
+                public_key.is_some_and(|candidate| candidate.eq_ignore_ascii_case(key))
+            },
+            PeerPattern::Cidr { network, prefix } => {
+                address.is_some_and(|address| in_range(address.to_canonical(), *network, *prefix))
+            },
+        }
+    }
+}
+
+fn in_range(address: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        },
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        },
+        _ => false,
+    }
+}
+
+impl FromStr for PeerPattern {
+    type Err = ChaincraftError;
+
+    /// Parse `10.0.0.0/8`, `fd00::/8`, a single address, or a hex public key
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let invalid = |reason: &str| ChaincraftError::config(format!("peer '{}': {}", s, reason));
+        if let Some((network, prefix)) = s.split_once('/') {
+            let network: IpAddr = network.parse().map_err(|_| invalid("invalid address"))?;
+            let max = if network.is_ipv4() { 32 } else { 128 };
+            let prefix = prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| invalid("invalid prefix length"))?;
+            return Ok(PeerPattern::Cidr {
+                network: network.to_canonical(),
+                prefix,
+            });
+        }
+        if let Ok(address) = s.parse::<IpAddr>() {
+            let address = address.to_canonical();
+            let prefix = if address.is_ipv4() { 32 } else { 128 };
+            return Ok(PeerPattern::Cidr {
+                network: address,
+                prefix,
+            });
+        }
+        match hex::decode(s) {
+            Ok(key) if !key.is_empty() => Ok(PeerPattern::PublicKey(s.to_ascii_lowercase())),
+            _ => Err(invalid("expected an address range or a hex public key")),
+        }
+    }
+}
+
+impl fmt::Display for PeerPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerPattern::PublicKey(key) => write!(f, "{}", key),
+            PeerPattern::Cidr { network, prefix } => write!(f, "{}/{}", network, prefix),
+        }
+    }
+}
+
+/// Why a peer was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    /// The peer matches the denylist
+    Denied,
+    /// The network is private and the peer is not on the allowlist
+    NotAllowed,
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::Denied => write!(f, "peer is denylisted"),
+            Refusal::NotAllowed => write!(f, "peer is not allowlisted on this private network"),
+        }
+    }
+}
+
+/// Which peers a node admits
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerAccess {
+    /// Peers admitted to a private network
+    pub allow: Vec<PeerPattern>,
+    /// Peers always refused
+    pub deny: Vec<PeerPattern>,
+    /// Admit only allowlisted peers
+    pub private: bool,
+}
+
+impl PeerAccess {
+    /// Access list admitting every peer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow(mut self, pattern: PeerPattern) -> Self {
+        self.allow.push(pattern);
+        self
+    }
+
+    pub fn deny(mut self, pattern: PeerPattern) -> Self {
+        self.deny.push(pattern);
+        self
+    }
+
+    /// Refuse every peer not on the allowlist
+    pub fn private_network(mut self) -> Self {
+        self.private = true;
+        self
+    }
+
+    /// Whether a peer seen at `address` and proven to hold `public_key` is admitted
+    pub fn check(
+        &self,
+        address: Option<IpAddr>,
+        public_key: Option<&str>,
+    ) -> std::result::Result<(), Refusal> {
+        let matches = |patterns: &[PeerPattern]| {
+            patterns
+                .iter()
+                .any(|pattern| pattern.matches(address, public_key))
+        };
+        if matches(&self.deny) {
+            Err(Refusal::Denied)
+        } else if self.private && !matches(&self.allow) {
+            Err(Refusal::NotAllowed)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Synthetic load against a running node
+//!
+//! [`run`] submits signed messages of a chosen size to a node at a fixed rate
+//! for a fixed time, then reports how many the node accepted, the latency
+//! percentiles of the submissions and how the node's metrics and resource
+//! usage moved meanwhile. `chaincraft-cli bench --target host:port` prints the
+//! report, so throughput figures quoted for the crate can be reproduced on
+//! other machines.
+//!
+//! Messages go through `Messages/Submit` by default: the node validates and
+//! stores each one before answering, so latency covers the whole admission
+//! path. With [`BenchPath::Peer`] they are delivered as a peer would send
+//! them, through `Peer/Deliver`, which answers once the hash is checked and
+//! leaves processing to the node afterwards.
+
+use crate::{
+    crypto::{utils::generate_keypair, KeyType, PrivateKey},
+    error::{ChaincraftError, NetworkError, Result},
+    network::{grpc::proto, grpc::GrpcClient, PeerId, DEFAULT_NETWORK_ID},
+    shared::{MessageType, SharedMessage},
+};
+use chrono::Utc;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Custom message type of the synthetic messages
+pub const BENCH_MESSAGE_TYPE: &str = "BENCH";
+
+/// Which service the synthetic messages are sent through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchPath {
+    /// `Messages/Submit`, answered once the node stored the message
+    Rpc,
+    /// `Peer/Deliver`, answered once the transport queued the message
+    Peer,
+}
+
+impl FromStr for BenchPath {
+    type Err = ChaincraftError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rpc" => Ok(BenchPath::Rpc),
+            "peer" => Ok(BenchPath::Peer),
+            _ => Err(ChaincraftError::config(format!(
+                "unknown bench path '{}', expected rpc or peer",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for BenchPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchPath::Rpc => write!(f, "rpc"),
+            BenchPath::Peer => write!(f, "peer"),
+        }
+    }
+}
+
+/// Shape of the load to generate
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Messages sent per second
+    pub rate: u32,
+    /// Approximate size of each message's JSON data, in bytes
+    pub size: usize,
+    /// How long messages are sent for
+    pub duration: Duration,
+    pub path: BenchPath,
+    /// Network announced in the handshake
+    pub network_id: String,
+    /// Limit on connecting and on each submission
+    pub timeout: Duration,
+    /// Key the synthetic messages are signed with
+    pub key_type: KeyType,
+}
+
+impl BenchConfig {
+    pub fn with_rate(mut self, rate: u32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_path(mut self, path: BenchPath) -> Self {
+        self.path = path;
+        self
+    }
+
+    pub fn with_network_id(mut self, network_id: impl Into<String>) -> Self {
+        self.network_id = network_id.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Number of messages a run sends
+    pub fn message_count(&self) -> u64 {
+        (self.rate as f64 * self.duration.as_secs_f64()).round() as u64
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.rate == 0 {
+            return Err(ChaincraftError::config("bench rate must be at least 1 message/s"));
+        }
+        if self.message_count() == 0 {
+            return Err(ChaincraftError::config(format!(
+                "a duration of {:?} at {} message/s sends nothing",
+                self.duration, self.rate
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            rate: 100,
+            size: 256,
+            duration: Duration::from_secs(10),
+            path: BenchPath::Rpc,
+            network_id: DEFAULT_NETWORK_ID.to_string(),
+            timeout: Duration::from_secs(5),
+            key_type: KeyType::Ed25519,
+        }
+    }
+}
+
+/// What happened to one message
+enum Outcome {
+    Accepted(Duration),
+    Rejected(Duration, String),
+    Failed(String),
+}
+
+/// Results of a run
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    /// Node as given, `host:port`
+    pub target: String,
+    /// Id the node gave in its handshake
+    pub node_id: String,
+    pub path: BenchPath,
+    pub sent: u64,
+    pub accepted: u64,
+    /// Messages the node answered with a refusal
+    pub rejected: u64,
+    /// Submissions that failed or timed out without an answer
+    pub failed: u64,
+    /// Refusal and failure reasons with their counts
+    pub reasons: BTreeMap<String, u64>,
+    /// Answered submissions' latencies, shortest first
+    pub latencies: Vec<Duration>,
+    /// Time from the first submission to the last answer
+    pub elapsed: Duration,
+    /// Node metrics before the run, empty if the node does not serve them
+    pub metrics_before: BTreeMap<String, u64>,
+    /// Node metrics after the run
+    pub metrics_after: BTreeMap<String, u64>,
+}
+
+impl BenchReport {
+    /// Share of sent messages the node accepted
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        self.accepted as f64 / self.sent as f64
+    }
+
+    /// Accepted messages per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.accepted as f64 / secs
+    }
+
+    /// Latency below which a `quantile` (0 to 1) of the answers came
+    pub fn latency(&self, quantile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let rank = (quantile.clamp(0.0, 1.0) * self.latencies.len() as f64).ceil() as usize;
+        self.latencies.get(rank.max(1) - 1).copied()
+    }
+
+    /// Metrics that changed during the run, with their values before and after
+    pub fn metric_changes(&self) -> BTreeMap<String, (u64, u64)> {
+        self.metrics_after
+            .iter()
+            .filter_map(|(name, &after)| {
+                let before = self.metrics_before.get(name).copied().unwrap_or(0);
+                (before != after).then(|| (name.clone(), (before, after)))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "target {} (node {}) via {}", self.target, self.node_id, self.path)?;
+        writeln!(
+            f,
+            "sent {} in {:.2} s: {} accepted ({:.1}%), {} rejected, {} failed",
+            self.sent,
+            self.elapsed.as_secs_f64(),
+            self.accepted,
+            self.acceptance_rate() * 100.0,
+            self.rejected,
+            self.failed
+        )?;
+        writeln!(f, "throughput {:.1} msg/s", self.throughput())?;
+        let show = |quantile: f64| match self.latency(quantile) {
+            Some(latency) => millis(latency),
+            None => "-".to_string(),
+        };
+        write!(
+            f,
+            "latency p50 {}, p90 {}, p99 {}, max {}",
+            show(0.5),
+            show(0.9),
+            show(0.99),
+            show(1.0)
+        )?;
+        for (reason, count) in &self.reasons {
+            write!(f, "\n  {} x {}", count, reason)?;
+        }
+        let changes = self.metric_changes();
+        if !changes.is_empty() {
+            write!(f, "\nnode metrics:")?;
+            for (name, (before, after)) in changes {
+                write!(f, "\n  {:<28}{} -> {}", name, before, after)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Signed message of `sequence` whose data is about `size` bytes of JSON
+pub fn synthetic_message(
+    private_key: &PrivateKey,
+    sequence: u64,
+    size: usize,
+) -> Result<SharedMessage> {
+    let empty = json!({ "seq": sequence, "payload": "" }).to_string().len();
+    let payload = "x".repeat(size.saturating_sub(empty));
+    let mut message = SharedMessage::new(
+        MessageType::Custom(BENCH_MESSAGE_TYPE.to_string()),
+        json!({ "seq": sequence, "payload": payload }),
+    );
+    message.sign(private_key)?;
+    Ok(message)
+}
+
+/// Load the node at `target` (`host:port` of its gRPC transport)
+pub async fn run(target: &str, config: &BenchConfig) -> Result<BenchReport> {
+    config.validate()?;
+    let timed_out = || {
+        ChaincraftError::Network(NetworkError::Timeout {
+            duration: config.timeout,
+        })
+    };
+    let address = resolve(target).await?;
+    let mut client = tokio::time::timeout(config.timeout, GrpcClient::connect(address))
+        .await
+        .map_err(|_| timed_out())??;
+    let local_id = PeerId::new();
+    let hello = proto::Hello::new(&local_id, &config.network_id, Utc::now());
+    let remote = tokio::time::timeout(config.timeout, client.handshake(hello))
+        .await
+        .map_err(|_| timed_out())??;
+    let metrics_before = client.node_metrics().await.unwrap_or_default();
+
+    let (private_key, _) = generate_keypair(config.key_type)?;
+    let private_key = Arc::new(private_key);
+    let sent = config.message_count();
+    let mut ticks = tokio::time::interval(Duration::from_secs_f64(1.0 / config.rate as f64));
+    let mut submissions = JoinSet::new();
+    let started = Instant::now();
+    for sequence in 0..sent {
+        ticks.tick().await;
+        let mut client = client.clone();
+        let private_key = private_key.clone();
+        let (path, size, timeout) = (config.path, config.size, config.timeout);
+        let (from, to) = (local_id.to_string(), remote.node_id.clone());
+        submissions.spawn(async move {
+            let message = match synthetic_message(&private_key, sequence, size) {
+                Ok(message) => message,
+                Err(e) => return Outcome::Failed(e.to_string()),
+            };
+            let submitted = Instant::now();
+            let answer = match path {
+                BenchPath::Rpc => tokio::time::timeout(timeout, client.submit(&message)).await,
+                BenchPath::Peer => {
+                    let envelope = proto::Envelope {
+                        from,
+                        to,
+                        message: Some(proto::SharedMessage::from(&message)),
+                    };
+                    tokio::time::timeout(timeout, client.deliver(envelope)).await
+                },
+            };
+            let latency = submitted.elapsed();
+            match answer {
+                Ok(Ok(ack)) if ack.accepted => Outcome::Accepted(latency),
+                Ok(Ok(ack)) => Outcome::Rejected(latency, ack.reason),
+                Ok(Err(e)) => Outcome::Failed(e.to_string()),
+                Err(_) => Outcome::Failed(format!("no answer within {:?}", timeout)),
+            }
+        });
+    }
+
+    let mut report = BenchReport {
+        target: target.to_string(),
+        node_id: remote.node_id,
+        path: config.path,
+        sent,
+        accepted: 0,
+        rejected: 0,
+        failed: 0,
+        reasons: BTreeMap::new(),
+        latencies: Vec::with_capacity(sent as usize),
+        elapsed: Duration::ZERO,
+        metrics_before,
+        metrics_after: BTreeMap::new(),
+    };
+    while let Some(outcome) = submissions.join_next().await {
+        match outcome {
+            Ok(Outcome::Accepted(latency)) => {
+                report.accepted += 1;
+                report.latencies.push(latency);
+            },
+            Ok(Outcome::Rejected(latency, reason)) => {
+                report.rejected += 1;
+                report.latencies.push(latency);
+                *report.reasons.entry(reason).or_default() += 1;
+            },
+            Ok(Outcome::Failed(reason)) => {
+                report.failed += 1;
+                *report.reasons.entry(reason).or_default() += 1;
+            },
+            Err(e) => {
+                report.failed += 1;
+                *report.reasons.entry(e.to_string()).or_default() += 1;
+            },
+        }
+    }
+    report.elapsed = started.elapsed();
+    report.latencies.sort();
+    report.metrics_after = client.node_metrics().await.unwrap_or_default();
+    Ok(report)
+}
+
+async fn resolve(target: &str) -> Result<SocketAddr> {
+    let invalid = |reason: String| {
+        ChaincraftError::config(format!("cannot resolve bench target {}: {}", target, reason))
+    };
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host(target)
+        .await
+        .map_err(|e| invalid(e.to_string()))?
+        .collect();
+    // Prefer IPv4 like the doctor does
+    addresses
+        .iter()
+        .find(|a| a.is_ipv4())
+        .or(addresses.first())
+        .copied()
+        .ok_or_else(|| invalid("no addresses".to_string()))
+}
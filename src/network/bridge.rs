@@ -0,0 +1,337 @@
+//! Relaying messages between separate networks
+//!
+//! A [`Bridge`] holds one endpoint on each of two networks and forwards
+//! whitelisted message types from one to the other. Relayed messages travel
+//! inside a [`BRIDGE_MESSAGE_TYPE`] envelope recording the network they came
+//! from and every network they have crossed, so the original hash and
+//! signature stay verifiable on the far side. A bridge never delivers an
+//! envelope into a network already on its path, which keeps chains and
+//! cycles of bridges from bouncing a message around forever.
+
+use crate::{
+    error::{ChaincraftError, Result},
+    network::{transport::Transport, PeerId},
+    shared::{MessageType, SharedMessage},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex, RwLock,
+};
+
+/// Custom message type of relay envelopes
+pub const BRIDGE_MESSAGE_TYPE: &str = "BRIDGE_RELAY";
+
+/// Number of relayed message hashes remembered for duplicate suppression
+const SEEN_CAPACITY: usize = 4096;
+
+/// A message relayed across one or more bridges
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BridgedMessage {
+    /// Network the message was first published on
+    pub origin_network: String,
+    /// Networks the message has been delivered into, in order
+    pub path: Vec<String>,
+    /// The original message, unchanged
+    pub message: SharedMessage,
+}
+
+impl BridgedMessage {
+    /// Unwrap a relay envelope, or `None` if `message` is not one
+    pub fn from_message(message: &SharedMessage) -> Option<Self> {
+        if message.message_type != MessageType::Custom(BRIDGE_MESSAGE_TYPE.to_string()) {
+            return None;
+        }
+        serde_json::from_value(message.data.clone()).ok()
+    }
+
+    /// Wrap into an envelope message
+    pub fn to_message(&self) -> Result<SharedMessage> {
+        SharedMessage::custom(BRIDGE_MESSAGE_TYPE, self)
+    }
+
+    /// Whether the message has already been delivered into `network_id`
+    pub fn has_visited(&self, network_id: &str) -> bool {
+        self.origin_network == network_id || self.path.iter().any(|n| n == network_id)
+    }
+}
+
+/// Side of a bridge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BridgeSide {
+    Left,
+    Right,
+}
+
+impl BridgeSide {
+    /// The opposite side
+    pub fn other(self) -> Self {
+        match self {
+            BridgeSide::Left => BridgeSide::Right,
+            BridgeSide::Right => BridgeSide::Left,
+        }
+    }
+}
+
+/// What happened to a message received by the bridge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayOutcome {
+    /// Forwarded to this many peers on the other network
+    Relayed { peers: usize },
+    /// The message type is not whitelisted
+    NotAllowed,
+    /// The message already passed through the destination network
+    Loop,
+    /// The message was already relayed by this bridge
+    Duplicate,
+}
+
+/// Relay counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeStats {
+    pub relayed: u64,
+    pub not_allowed: u64,
+    pub loops: u64,
+    pub duplicates: u64,
+}
+
+/// Bridge settings
+#[derive(Debug, Clone, Default)]
+pub struct BridgeConfig {
+    /// Message types relayed in either direction, by their display name
+    pub allowed_types: HashSet<String>,
+}
+
+impl BridgeConfig {
+    /// Create a config that relays nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow a message type to cross the bridge
+    pub fn allow(mut self, message_type: MessageType) -> Self {
+        self.allowed_types.insert(message_type.to_string());
+        self
+    }
+
+    /// Whether a message type may cross the bridge
+    pub fn is_allowed(&self, message_type: &MessageType) -> bool {
+        self.allowed_types.contains(&message_type.to_string())
+    }
+}
+
+#[derive(Debug)]
+struct Endpoint<T> {
+    network_id: String,
+    transport: T,
+    peers: RwLock<Vec<PeerId>>,
+}
+
+#[derive(Debug, Default)]
+struct SeenHashes {
+    order: VecDeque<String>,
+    set: HashSet<String>,
+}
+
+impl SeenHashes {
+    /// Record a hash, returning false if it was already seen
+    fn insert(&mut self, hash: &str) -> bool {
+        if !self.set.insert(hash.to_string()) {
+            return false;
+        }
+        self.order.push_back(hash.to_string());
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Relay between two networks
+#[derive(Debug)]
+pub struct Bridge<A, B> {
+    config: BridgeConfig,
+    left: Endpoint<A>,
+    right: Endpoint<B>,
+    seen: Mutex<SeenHashes>,
+    relayed: AtomicU64,
+    not_allowed: AtomicU64,
+    loops: AtomicU64,
+    duplicates: AtomicU64,
+}
+
+impl<A: Transport, B: Transport> Bridge<A, B> {
+    /// Create a bridge from an endpoint on each network
+    pub fn new(
+        left_network: impl Into<String>,
+        left: A,
+        right_network: impl Into<String>,
+        right: B,
+        config: BridgeConfig,
+    ) -> Self {
+        Self {
+            config,
+            left: Endpoint {
+                network_id: left_network.into(),
+                transport: left,
+                peers: RwLock::new(Vec::new()),
+            },
+            right: Endpoint {
+                network_id: right_network.into(),
+                transport: right,
+                peers: RwLock::new(Vec::new()),
+            },
+            seen: Mutex::new(SeenHashes::default()),
+            relayed: AtomicU64::new(0),
+            not_allowed: AtomicU64::new(0),
+            loops: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+        }
+    }
+
+    /// Get the bridge settings
+    pub fn config(&self) -> &BridgeConfig {
+        &self.config
+    }
+
+    /// Network id of one side
+    pub fn network_id(&self, side: BridgeSide) -> &str {
+        match side {
+            BridgeSide::Left => &self.left.network_id,
+            BridgeSide::Right => &self.right.network_id,
+        }
+    }
+
+    /// Side connected to `network_id`, if any
+    pub fn side_of(&self, network_id: &str) -> Option<BridgeSide> {
+        if self.left.network_id == network_id {
+            Some(BridgeSide::Left)
+        } else if self.right.network_id == network_id {
+            Some(BridgeSide::Right)
+        } else {
+            None
+        }
+    }
+
+    /// Local endpoint id on one side
+    pub fn local_id(&self, side: BridgeSide) -> &PeerId {
+        match side {
+            BridgeSide::Left => self.left.transport.local_id(),
+            BridgeSide::Right => self.right.transport.local_id(),
+        }
+    }
+
+    fn peers(&self, side: BridgeSide) -> &RwLock<Vec<PeerId>> {
+        match side {
+            BridgeSide::Left => &self.left.peers,
+            BridgeSide::Right => &self.right.peers,
+        }
+    }
+
+    /// Add a peer on `network_id` that relayed messages are delivered to
+    pub fn add_peer(&self, network_id: &str, peer_id: PeerId) -> Result<()> {
+        let side = self.side_of(network_id).ok_or_else(|| {
+            ChaincraftError::config(format!("bridge is not connected to '{}'", network_id))
+        })?;
+        let mut peers = self.peers(side).write().unwrap();
+        if !peers.contains(&peer_id) {
+            peers.push(peer_id);
+        }
+        Ok(())
+    }
+
+    /// Stop delivering to a peer
+    pub fn remove_peer(&self, peer_id: &PeerId) {
+        self.left.peers.write().unwrap().retain(|p| p != peer_id);
+        self.right.peers.write().unwrap().retain(|p| p != peer_id);
+    }
+
+    /// Relay counters so far
+    pub fn stats(&self) -> BridgeStats {
+        BridgeStats {
+            relayed: self.relayed.load(Ordering::Relaxed),
+            not_allowed: self.not_allowed.load(Ordering::Relaxed),
+            loops: self.loops.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Wait for the next message on one side and relay it to the other
+    pub async fn relay_next(&self, from: BridgeSide) -> Result<RelayOutcome> {
+        let (_, message) = match from {
+            BridgeSide::Left => self.left.transport.recv().await?,
+            BridgeSide::Right => self.right.transport.recv().await?,
+        };
+        self.relay(from, message).await
+    }
+
+    /// Relay a message received on one side to the peers on the other
+    pub async fn relay(&self, from: BridgeSide, message: SharedMessage) -> Result<RelayOutcome> {
+        let to = from.other();
+        let mut envelope =
+            BridgedMessage::from_message(&message).unwrap_or_else(|| BridgedMessage {
+                origin_network: self.network_id(from).to_string(),
+                path: Vec::new(),
+                message,
+            });
+
+        if !self.config.is_allowed(&envelope.message.message_type) {
+            self.not_allowed.fetch_add(1, Ordering::Relaxed);
+            return Ok(RelayOutcome::NotAllowed);
+        }
+        if envelope.has_visited(self.network_id(to)) {
+            self.loops.fetch_add(1, Ordering::Relaxed);
+            return Ok(RelayOutcome::Loop);
+        }
+        if !self.seen.lock().unwrap().insert(&envelope.message.hash) {
+            self.duplicates.fetch_add(1, Ordering::Relaxed);
+            return Ok(RelayOutcome::Duplicate);
+        }
+
+        envelope.path.push(self.network_id(to).to_string());
+        let relayed = envelope.to_message()?;
+        let peers = self.peers(to).read().unwrap().clone();
+        let mut delivered = 0;
+        for peer in &peers {
+            let result = match to {
+                BridgeSide::Left => self.left.transport.send(peer, &relayed).await,
+                BridgeSide::Right => self.right.transport.send(peer, &relayed).await,
+            };
+            match result {
+                Ok(()) => delivered += 1,
+                Err(e) => tracing::debug!("Bridge delivery to {} failed: {}", peer, e),
+            }
+        }
+        self.relayed.fetch_add(1, Ordering::Relaxed);
+        Ok(RelayOutcome::Relayed { peers: delivered })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<A, B> Bridge<A, B>
+where
+    A: Transport + Send + Sync + 'static,
+    B: Transport + Send + Sync + 'static,
+{
+    /// Relay in both directions until either endpoint closes
+    pub fn spawn(self: std::sync::Arc<Self>) -> Vec<tokio::task::JoinHandle<()>> {
+        [BridgeSide::Left, BridgeSide::Right]
+            .into_iter()
+            .map(|side| {
+                let bridge = self.clone();
+                tokio::spawn(async move {
+                    loop {
+                        if let Err(e) = bridge.relay_next(side).await {
+                            tracing::debug!("Bridge endpoint {:?} stopped: {}", side, e);
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,454 @@
+//! Connectivity diagnostics against a remote peer
+//!
+//! [`diagnose`] walks through everything that has to work for two nodes to
+//! talk: the address resolves, a TCP connection opens, the peer answers the
+//! gRPC handshake, runs the same protocol version on the same network, and
+//! keeps a clock close to ours. Each check becomes a [`Finding`] carrying
+//! advice when it fails; checks that depend on a failed one are skipped.
+//! `chaincraft-cli doctor --peer host:port` prints the resulting report.
+//!
+//! Round-trip time and clock offset are measured over several handshakes and
+//! taken from the fastest one, as NTP does: the remote timestamp is compared
+//! with the local time half-way through that round trip.
+
+use crate::{
+    error::{ChaincraftError, NetworkError, Result},
+    network::{grpc::proto, grpc::GrpcClient, PeerId, DEFAULT_NETWORK_ID, PROTOCOL_VERSION},
+};
+use chrono::Utc;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// What to compare the peer against and how patient to be
+#[derive(Debug, Clone)]
+pub struct DoctorConfig {
+    /// Network the peer is expected to be on
+    pub network_id: String,
+    /// Handshakes used to measure round-trip time and clock offset
+    pub samples: usize,
+    /// Limit on resolving, connecting and each handshake
+    pub timeout: Duration,
+    /// Round-trip time above which latency is reported
+    pub max_rtt: Duration,
+    /// Clock offset above which a warning is reported
+    pub max_clock_offset: Duration,
+    /// Clock offset above which the peer is considered broken
+    pub clock_offset_error: Duration,
+}
+
+impl DoctorConfig {
+    pub fn with_network_id(mut self, network_id: impl Into<String>) -> Self {
+        self.network_id = network_id.into();
+        self
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Default for DoctorConfig {
+    fn default() -> Self {
+        Self {
+            network_id: DEFAULT_NETWORK_ID.to_string(),
+            samples: 5,
+            timeout: Duration::from_secs(5),
+            max_rtt: Duration::from_millis(500),
+            max_clock_offset: Duration::from_secs(1),
+            clock_offset_error: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How bad a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Ok => write!(f, "ok"),
+            Severity::Warning => write!(f, "warn"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Short name of the check, e.g. `"clock"`
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// What to do about it, for warnings and errors
+    pub advice: Option<String>,
+}
+
+impl Finding {
+    fn ok(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            severity: Severity::Ok,
+            message: message.into(),
+            advice: None,
+        }
+    }
+
+    fn problem(
+        check: &'static str,
+        severity: Severity,
+        message: impl Into<String>,
+        advice: impl Into<String>,
+    ) -> Self {
+        Self {
+            check,
+            severity,
+            message: message.into(),
+            advice: Some(advice.into()),
+        }
+    }
+}
+
+/// Everything learned about a peer
+#[derive(Debug, Clone)]
+pub struct DoctorReport {
+    /// Peer as given, `host:port`
+    pub peer: String,
+    /// Address the peer resolved to
+    pub address: Option<SocketAddr>,
+    /// The peer's answer to the handshake
+    pub remote: Option<proto::Hello>,
+    /// Fastest handshake round trip
+    pub rtt: Option<Duration>,
+    /// Peer clock minus local clock
+    pub clock_offset: Option<chrono::Duration>,
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    /// Most severe finding
+    pub fn severity(&self) -> Severity {
+        self.findings
+            .iter()
+            .map(|f| f.severity)
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+
+    /// Whether nothing stops the nodes from talking
+    pub fn is_healthy(&self) -> bool {
+        self.severity() < Severity::Error
+    }
+
+    /// Finding of a check, if it ran
+    pub fn finding(&self, check: &str) -> Option<&Finding> {
+        self.findings.iter().find(|f| f.check == check)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            Some(address) if address.to_string() != self.peer => {
+                writeln!(f, "peer {} ({})", self.peer, address)?
+            },
+            _ => writeln!(f, "peer {}", self.peer)?,
+        }
+        for finding in &self.findings {
+            let label = format!("[{}]", finding.severity);
+            writeln!(f, "  {:<8}{:<10}{}", label, finding.check, finding.message)?;
+            if let Some(advice) = &finding.advice {
+                writeln!(f, "  {:<18}-> {}", "", advice)?;
+            }
+        }
+        write!(
+            f,
+            "{} error(s), {} warning(s)",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Check whether this build can talk to the node at `peer` (`host:port`)
+///
+/// Failures are reported as findings rather than errors.
+pub async fn diagnose(peer: &str, config: &DoctorConfig) -> DoctorReport {
+    let mut report = DoctorReport {
+        peer: peer.to_string(),
+        address: None,
+        remote: None,
+        rtt: None,
+        clock_offset: None,
+        findings: Vec::new(),
+    };
+
+    let Some(address) = resolve(peer, config, &mut report.findings).await else {
+        return report;
+    };
+    report.address = Some(address);
+    if !connect(address, config, &mut report.findings).await {
+        return report;
+    }
+
+    let (remote, rtt, offset) = match handshakes(address, config).await {
+        Ok(result) => result,
+        Err(e) => {
+            report.findings.push(Finding::problem(
+                "handshake",
+                Severity::Error,
+                format!("port is open but the handshake failed: {}", e),
+                "make sure the port belongs to a Chaincraft node serving gRPC; nodes \
+                 older than the handshake cannot be diagnosed",
+            ));
+            return report;
+        },
+    };
+    report.findings.push(Finding::ok(
+        "handshake",
+        format!("node {} running chaincraft {}", remote.node_id, remote.software_version),
+    ));
+    compare(&remote, config, &mut report.findings);
+    check_rtt(rtt, config, &mut report.findings);
+    check_clock(offset, config, &mut report.findings);
+
+    report.remote = Some(remote);
+    report.rtt = Some(rtt);
+    report.clock_offset = Some(offset);
+    report
+}
+
+async fn resolve(
+    peer: &str,
+    config: &DoctorConfig,
+    findings: &mut Vec<Finding>,
+) -> Option<SocketAddr> {
+    let advice = "give the peer as host:port, e.g. 192.168.1.20:21000, and check the host name";
+    let lookup = tokio::time::timeout(config.timeout, tokio::net::lookup_host(peer)).await;
+    let addresses: Vec<SocketAddr> = match lookup {
+        Ok(Ok(addresses)) => addresses.collect(),
+        Ok(Err(e)) => {
+            findings.push(Finding::problem(
+                "resolve",
+                Severity::Error,
+                format!("cannot resolve {}: {}", peer, e),
+                advice,
+            ));
+            return None;
+        },
+        Err(_) => {
+            findings.push(Finding::problem(
+                "resolve",
+                Severity::Error,
+                format!("resolving {} timed out", peer),
+                "check the DNS configuration or use an IP address",
+            ));
+            return None;
+        },
+    };
+    // Prefer IPv4: lab networks rarely route IPv6
+    let address = addresses
+        .iter()
+        .find(|a| a.is_ipv4())
+        .or(addresses.first())
+        .copied();
+    match address {
+        Some(address) => {
+            findings.push(Finding::ok("resolve", format!("{} resolves to {}", peer, address)));
+            Some(address)
+        },
+        None => {
+            findings.push(Finding::problem(
+                "resolve",
+                Severity::Error,
+                format!("{} has no addresses", peer),
+                advice,
+            ));
+            None
+        },
+    }
+}
+
+async fn connect(address: SocketAddr, config: &DoctorConfig, findings: &mut Vec<Finding>) -> bool {
+    let started = Instant::now();
+    match tokio::time::timeout(config.timeout, tokio::net::TcpStream::connect(address)).await {
+        Ok(Ok(_)) => {
+            findings.push(Finding::ok(
+                "connect",
+                format!("TCP connection opened in {}", millis(started.elapsed())),
+            ));
+            true
+        },
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            findings.push(Finding::problem(
+                "connect",
+                Severity::Error,
+                "connection refused",
+                format!(
+                    "nothing listens on port {}: check the port and that the node binds its \
+                     gRPC transport to an address reachable from here, not 127.0.0.1",
+                    address.port()
+                ),
+            ));
+            false
+        },
+        Ok(Err(e)) => {
+            findings.push(Finding::problem(
+                "connect",
+                Severity::Error,
+                format!("cannot connect: {}", e),
+                "check that both machines are on a network that routes to each other",
+            ));
+            false
+        },
+        Err(_) => {
+            findings.push(Finding::problem(
+                "connect",
+                Severity::Error,
+                format!("no answer within {:?}", config.timeout),
+                format!("the host is down or a firewall drops traffic to port {}", address.port()),
+            ));
+            false
+        },
+    }
+}
+
+/// Hello of the peer with the fastest round trip and the clock offset seen on it
+async fn handshakes(
+    address: SocketAddr,
+    config: &DoctorConfig,
+) -> Result<(proto::Hello, Duration, chrono::Duration)> {
+    let timed_out = || {
+        ChaincraftError::Network(NetworkError::Timeout {
+            duration: config.timeout,
+        })
+    };
+    let mut client = tokio::time::timeout(config.timeout, GrpcClient::connect(address))
+        .await
+        .map_err(|_| timed_out())??;
+    let local_id = PeerId::new();
+
+    let mut best: Option<(proto::Hello, Duration, chrono::Duration)> = None;
+    for _ in 0..config.samples.max(1) {
+        let sent_at = Utc::now();
+        let started = Instant::now();
+        let hello = proto::Hello::new(&local_id, &config.network_id, sent_at);
+        let remote = tokio::time::timeout(config.timeout, client.handshake(hello))
+            .await
+            .map_err(|_| timed_out())??;
+        let rtt = started.elapsed();
+        let midpoint = sent_at + chrono::Duration::from_std(rtt / 2).unwrap_or_default();
+        let offset = remote.timestamp()? - midpoint;
+        if best.as_ref().is_none_or(|(_, fastest, _)| rtt < *fastest) {
+            best = Some((remote, rtt, offset));
+        }
+    }
+    best.ok_or_else(timed_out)
+}
+
+fn compare(remote: &proto::Hello, config: &DoctorConfig, findings: &mut Vec<Finding>) {
+    if remote.protocol_version == PROTOCOL_VERSION {
+        findings.push(Finding::ok("protocol", format!("both speak version {}", PROTOCOL_VERSION)));
+    } else {
+        let older = if remote.protocol_version < PROTOCOL_VERSION {
+            "the peer"
+        } else {
+            "this node"
+        };
+        findings.push(Finding::problem(
+            "protocol",
+            Severity::Error,
+            format!(
+                "peer speaks protocol {}, this node {}",
+                remote.protocol_version, PROTOCOL_VERSION
+            ),
+            format!("upgrade {} so both run the same protocol version", older),
+        ));
+    }
+
+    if remote.network_id == config.network_id {
+        findings.push(Finding::ok("network", format!("both on network '{}'", config.network_id)));
+    } else {
+        findings.push(Finding::problem(
+            "network",
+            Severity::Error,
+            format!("peer is on network '{}', expected '{}'", remote.network_id, config.network_id),
+            "start every node with the same network id",
+        ));
+    }
+
+    if remote.software_version != crate::VERSION {
+        findings.push(Finding::problem(
+            "version",
+            Severity::Warning,
+            format!(
+                "peer runs chaincraft {}, this node {}",
+                remote.software_version,
+                crate::VERSION
+            ),
+            "the protocol matches, but running the same release avoids surprises",
+        ));
+    }
+}
+
+fn check_rtt(rtt: Duration, config: &DoctorConfig, findings: &mut Vec<Finding>) {
+    let message = format!("round trip {} (fastest of {})", millis(rtt), config.samples.max(1));
+    if rtt <= config.max_rtt {
+        findings.push(Finding::ok("latency", message));
+    } else {
+        findings.push(Finding::problem(
+            "latency",
+            Severity::Warning,
+            message,
+            "high latency slows gossip and consensus rounds; prefer a wired link or a closer peer",
+        ));
+    }
+}
+
+fn check_clock(offset: chrono::Duration, config: &DoctorConfig, findings: &mut Vec<Finding>) {
+    let magnitude = offset.abs().to_std().unwrap_or_default();
+    let direction = if offset > chrono::Duration::zero() {
+        "ahead of"
+    } else {
+        "behind"
+    };
+    let message = format!("peer clock is {} {} ours", millis(magnitude), direction);
+    let severity = if magnitude > config.clock_offset_error {
+        Severity::Error
+    } else if magnitude > config.max_clock_offset {
+        Severity::Warning
+    } else {
+        findings.push(Finding::ok("clock", message));
+        return;
+    };
+    findings.push(Finding::problem(
+        "clock",
+        severity,
+        message,
+        "enable time synchronisation (NTP) on both machines; message timestamps and round \
+         timers assume clocks agree",
+    ));
+}
@@ -0,0 +1,210 @@
+//! Reputation-weighted gossip fanout
+//!
+//! When a node forwards a new message it picks a subset of its peers. To make
+//! it harder for a swarm of fresh or misbehaving identities to capture the
+//! gossip, [`GossipFanout`] fills the fanout with well-scored peers first and
+//! only admits peers below the trusted score with a configurable probability.
+//! Banned peers are never chosen. With tracing enabled every decision is kept
+//! in a bounded log so the policy can be inspected after the fact.
+
+use crate::network::PeerId;
+use crate::rng::RngProvider;
+use std::collections::VecDeque;
+use std::sync::{Mutex, RwLock};
+
+/// Gossip fanout settings
+#[derive(Debug, Clone)]
+pub struct FanoutConfig {
+    /// Maximum number of peers a message is forwarded to
+    pub fanout: usize,
+    /// Peers scoring at or above this are preferred
+    pub trusted_score: i64,
+    /// Chance that a peer below the trusted score fills a free slot
+    pub low_score_probability: f64,
+    /// Record every decision in the trace log
+    pub trace: bool,
+    /// Number of decisions kept in the trace log
+    pub trace_capacity: usize,
+}
+
+impl Default for FanoutConfig {
+    fn default() -> Self {
+        Self {
+            fanout: 8,
+            trusted_score: 0,
+            low_score_probability: 0.25,
+            trace: false,
+            trace_capacity: 256,
+        }
+    }
+}
+
+/// A peer considered for forwarding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanoutCandidate {
+    pub peer_id: PeerId,
+    /// Current discovery score
+    pub score: i64,
+    /// Whether discovery considers the peer banned
+    pub banned: bool,
+}
+
+impl FanoutCandidate {
+    pub fn new(peer_id: PeerId, score: i64, banned: bool) -> Self {
+        Self {
+            peer_id,
+            score,
+            banned,
+        }
+    }
+}
+
+/// Peers chosen to receive one message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FanoutDecision {
+    /// Hash of the forwarded message
+    pub message_hash: String,
+    /// Trusted peers, best scored first
+    pub preferred: Vec<PeerId>,
+    /// Low-score peers admitted by chance
+    pub sampled: Vec<PeerId>,
+    /// Eligible peers left out of this round
+    pub skipped: Vec<PeerId>,
+    /// Banned peers, never forwarded to
+    pub banned: Vec<PeerId>,
+}
+
+impl FanoutDecision {
+    /// All chosen peers, preferred ones first
+    pub fn targets(&self) -> Vec<PeerId> {
+        self.preferred
+            .iter()
+            .chain(self.sampled.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Whether `peer_id` was chosen
+    pub fn includes(&self, peer_id: &PeerId) -> bool {
+        self.preferred.contains(peer_id) || self.sampled.contains(peer_id)
+    }
+}
+
+/// Chooses gossip targets by reputation
+#[derive(Debug, Default)]
+pub struct GossipFanout {
+    config: RwLock<FanoutConfig>,
+    trace: Mutex<VecDeque<FanoutDecision>>,
+}
+
+impl GossipFanout {
+    /// Create a selector with the given settings
+    pub fn new(config: FanoutConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            trace: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Get the fanout settings
+    pub fn config(&self) -> FanoutConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the settings; later selections use them
+    pub fn set_config(&self, config: FanoutConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// Choose the peers a message is forwarded to
+    pub fn select(
+        &self,
+        message_hash: &str,
+        candidates: &[FanoutCandidate],
+        rng: &RngProvider,
+    ) -> FanoutDecision {
+        let config = self.config();
+        let mut decision = FanoutDecision {
+            message_hash: message_hash.to_string(),
+            ..Default::default()
+        };
+
+        let mut eligible: Vec<&FanoutCandidate> = Vec::new();
+        for candidate in candidates {
+            if candidate.banned {
+                decision.banned.push(candidate.peer_id.clone());
+            } else {
+                eligible.push(candidate);
+            }
+        }
+        // Best scored first; ties broken by id so seeded runs are reproducible
+        eligible.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.peer_id.to_string().cmp(&b.peer_id.to_string()))
+        });
+
+        let mut low_score = Vec::new();
+        for candidate in eligible {
+            if candidate.score < config.trusted_score {
+                low_score.push(candidate);
+            } else if decision.preferred.len() < config.fanout {
+                decision.preferred.push(candidate.peer_id.clone());
+            } else {
+                decision.skipped.push(candidate.peer_id.clone());
+            }
+        }
+        for candidate in low_score {
+            let free = decision.preferred.len() + decision.sampled.len() < config.fanout;
+            if free && Self::roll(config.low_score_probability, rng) {
+                decision.sampled.push(candidate.peer_id.clone());
+            } else {
+                decision.skipped.push(candidate.peer_id.clone());
+            }
+        }
+
+        if config.trace {
+            tracing::trace!(
+                "Gossip fanout for {}: preferred={:?} sampled={:?} skipped={} banned={}",
+                message_hash,
+                decision.preferred,
+                decision.sampled,
+                decision.skipped.len(),
+                decision.banned.len()
+            );
+            let mut trace = self.trace.lock().unwrap_or_else(|e| e.into_inner());
+            trace.push_back(decision.clone());
+            while trace.len() > config.trace_capacity {
+                trace.pop_front();
+            }
+        }
+
+        decision
+    }
+
+    /// Traced decisions, oldest first (empty unless tracing is enabled)
+    pub fn trace(&self) -> Vec<FanoutDecision> {
+        let trace = self.trace.lock().unwrap_or_else(|e| e.into_inner());
+        trace.iter().cloned().collect()
+    }
+
+    /// Drop all traced decisions
+    pub fn clear_trace(&self) {
+        self.trace.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    fn roll(p: f64, rng: &RngProvider) -> bool {
+        if p <= 0.0 {
+            return false;
+        }
+        if p >= 1.0 {
+            return true;
+        }
+        // 53 random bits give a uniform float in [0, 1)
+        let sample = (rng.next_u64_value() >> 11) as f64 / (1u64 << 53) as f64;
+        sample < p
+    }
+}
@@ -0,0 +1,239 @@
+//! Outbound gossip batched into frames
+//!
+//! A busy node gossips many small messages, and sending each on its own pays
+//! for a syscall and the transport's headers every time. [`FramingTransport`]
+//! wraps any [`Transport`] and queues outbound messages per peer. A peer's
+//! queue goes out as one frame every [`FramingConfig::interval`], or early
+//! once it reaches [`FramingConfig::max_frame_bytes`].
+//!
+//! On the wire a frame is a message of type [`FRAME_MESSAGE_TYPE`] listing the
+//! framed messages in its data; the receiving wrapper unpacks it and hands the
+//! messages out one at a time, in the order they were sent. Receivers need the
+//! wrapper to understand frames; a queue holding a single message is sent as
+//! that message.
+//!
+//! The counters `gossip_frames` and `gossip_framed_messages` give the average
+//! messages per frame, see [`FramingTransport::messages_per_frame`]. Pass a
+//! node's metrics with [`FramingTransport::with_metrics`] to report them there.
+
+use crate::{
+    error::Result,
+    metrics::NodeMetrics,
+    network::{transport::Transport, PeerId},
+    shared::{MessageType, SharedMessage},
+};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Custom message type carrying a frame of messages
+pub const FRAME_MESSAGE_TYPE: &str = "FRAME";
+
+/// Data of a [`FRAME_MESSAGE_TYPE`] message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Frame {
+    /// Framed messages, in the order they were sent
+    pub messages: Vec<SharedMessage>,
+}
+
+/// When queued messages are flushed
+#[derive(Debug, Clone)]
+pub struct FramingConfig {
+    /// Time between flushes of every queue
+    pub interval: Duration,
+    /// Encoded size a frame may reach before its queue is flushed early
+    pub max_frame_bytes: usize,
+}
+
+impl Default for FramingConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(50),
+            // Leaves room for headers in a 64 KiB datagram
+            max_frame_bytes: 60 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    messages: Vec<SharedMessage>,
+    bytes: usize,
+}
+
+/// Transport wrapper sending queued messages in frames
+#[derive(Debug)]
+pub struct FramingTransport<T> {
+    inner: T,
+    config: FramingConfig,
+    queues: Mutex<HashMap<PeerId, Queue>>,
+    /// Messages unpacked from received frames, not yet handed out
+    inbox: Mutex<VecDeque<(PeerId, SharedMessage)>>,
+    metrics: Arc<NodeMetrics>,
+}
+
+impl<T: Transport> FramingTransport<T> {
+    /// Wrap a transport with default settings
+    pub fn new(inner: T) -> Self {
+        Self::with_config(inner, FramingConfig::default())
+    }
+
+    /// Wrap a transport with custom settings
+    pub fn with_config(inner: T, config: FramingConfig) -> Self {
+        Self {
+            inner,
+            config,
+            queues: Mutex::new(HashMap::new()),
+            inbox: Mutex::new(VecDeque::new()),
+            metrics: Arc::new(NodeMetrics::new()),
+        }
+    }
+
+    /// Record frame counters in `metrics`, e.g. a node's
+    pub fn with_metrics(mut self, metrics: Arc<NodeMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Get the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the framing settings
+    pub fn config(&self) -> &FramingConfig {
+        &self.config
+    }
+
+    /// Number of messages waiting for the next flush
+    pub fn queued_count(&self) -> usize {
+        self.queues
+            .lock()
+            .unwrap()
+            .values()
+            .map(|queue| queue.messages.len())
+            .sum()
+    }
+
+    /// Average number of messages per frame sent so far
+    pub fn messages_per_frame(&self) -> f64 {
+        let frames = self.metrics.get("gossip_frames");
+        if frames == 0 {
+            return 0.0;
+        }
+        self.metrics.get("gossip_framed_messages") as f64 / frames as f64
+    }
+
+    /// Send every queued message, one frame per peer
+    ///
+    /// Returns the number of frames sent. Call this periodically, or use
+    /// [`FramingTransport::spawn_flushes`] on native targets. A frame that
+    /// fails to send is dropped and counted as `gossip_frames_failed`.
+    pub async fn flush(&self) -> usize {
+        let queues = std::mem::take(&mut *self.queues.lock().unwrap());
+        let mut sent = 0;
+        for (to, queue) in queues {
+            match self.send_frame(&to, queue.messages).await {
+                Ok(()) => sent += 1,
+                Err(e) => {
+                    self.metrics.incr("gossip_frames_failed");
+                    tracing::debug!("Frame to {} failed: {}", to, e);
+                },
+            }
+        }
+        sent
+    }
+
+    async fn send_frame(&self, to: &PeerId, mut messages: Vec<SharedMessage>) -> Result<()> {
+        let count = messages.len() as u64;
+        let frame = match messages.len() {
+            0 => return Ok(()),
+            1 => messages.remove(0),
+            _ => SharedMessage::custom(FRAME_MESSAGE_TYPE, Frame { messages })?,
+        };
+        self.inner.send(to, &frame).await?;
+        self.metrics.incr("gossip_frames");
+        self.metrics.add("gossip_framed_messages", count);
+        Ok(())
+    }
+
+    fn unpack(&self, from: &PeerId, frame: &SharedMessage) {
+        let messages = match serde_json::from_value::<Frame>(frame.data.clone()) {
+            Ok(frame) => frame.messages,
+            Err(_) => {
+                tracing::debug!("Dropping malformed frame {} from {}", frame.hash, from);
+                return;
+            },
+        };
+        self.inbox
+            .lock()
+            .unwrap()
+            .extend(messages.into_iter().map(|message| (from.clone(), message)));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Transport + Send + Sync + 'static> FramingTransport<T> {
+    /// Run [`FramingTransport::flush`] every [`FramingConfig::interval`]
+    pub fn spawn_flushes(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+            loop {
+                ticker.tick().await;
+                self.flush().await;
+            }
+        })
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: Transport + Send + Sync> Transport for FramingTransport<T> {
+    fn local_id(&self) -> &PeerId {
+        self.inner.local_id()
+    }
+
+    /// Queue a message for the peer's next frame
+    ///
+    /// If the message would take the frame past the size limit, the frame
+    /// queued so far is sent first; a frame that reaches the limit is sent
+    /// at once.
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        let size = serde_json::to_vec(message)?.len();
+        let mut full = Vec::new();
+        {
+            let mut queues = self.queues.lock().unwrap();
+            let queue = queues.entry(to.clone()).or_default();
+            if !queue.messages.is_empty() && queue.bytes + size > self.config.max_frame_bytes {
+                full.push(std::mem::take(queue).messages);
+            }
+            queue.messages.push(message.clone());
+            queue.bytes += size;
+            if queue.bytes >= self.config.max_frame_bytes {
+                full.push(std::mem::take(queue).messages);
+            }
+        }
+        for messages in full {
+            self.send_frame(to, messages).await?;
+        }
+        Ok(())
+    }
+
+    /// Receive the next message, unpacking frames on the way
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        loop {
+            if let Some(next) = self.inbox.lock().unwrap().pop_front() {
+                return Ok(next);
+            }
+            let (from, message) = self.inner.recv().await?;
+            if message.message_type == MessageType::Custom(FRAME_MESSAGE_TYPE.to_string()) {
+                self.unpack(&from, &message);
+                continue;
+            }
+            return Ok((from, message));
+        }
+    }
+}
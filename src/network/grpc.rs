@@ -0,0 +1,1739 @@
+//! gRPC peer protocol
+//!
+//! Serves and calls the services defined in `proto/chaincraft.proto` with tonic,
+//! so nodes written in other languages can exchange messages, peers and message
+//! logs with Rust nodes. The protobuf types in [`proto`] mirror the schema file
+//! and must be kept in sync with it.
+
+use crate::{
+    clock::Clock,
+    crypto::{KeyType, PrivateKey, PublicKey, Signature},
+    directory::ObjectSnapshot,
+    error::{ChaincraftError, NetworkError, Result},
+    handle::NodeHandle,
+    index::MessageIndex,
+    message_log::MessageLog,
+    message_stats::{MessageStats, MessageStatsReport, SenderCount, TypeCount},
+    network::{
+        access::PeerAccess, transport::Transport, PeerId, PeerInfo, DEFAULT_NETWORK_ID,
+        PROTOCOL_VERSION,
+    },
+    node::ChaincraftNode,
+    query::MessageFilter,
+    runtime_config::{ConfigUpdate, RuntimeConfig},
+    shared::{MessageType, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObjectRegistry, HistoryCursor, HistoryEntry, ObjectInfo},
+    storage::{MessageEncoding, Storage},
+};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::{http, BoxFuture, StdError};
+use tonic::transport::{server::TcpIncoming, Channel, Endpoint, Server};
+use tonic::{Request, Response, Status};
+
+/// Protobuf types for the `chaincraft.v1` package
+pub mod proto {
+    /// A message shared between nodes
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SharedMessage {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub message_type: String,
+        #[prost(string, tag = "3")]
+        pub target_id: String,
+        #[prost(string, tag = "4")]
+        pub data_json: String,
+        #[prost(string, tag = "5")]
+        pub timestamp: String,
+        #[prost(bytes = "vec", tag = "6")]
+        pub signature: Vec<u8>,
+        #[prost(string, tag = "7")]
+        pub hash: String,
+        #[prost(uint64, optional, tag = "8")]
+        pub pow_nonce: Option<u64>,
+        #[prost(string, tag = "9")]
+        pub sender: String,
+    }
+
+    /// Message addressed from one peer to another
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Envelope {
+        #[prost(string, tag = "1")]
+        pub from: String,
+        #[prost(string, tag = "2")]
+        pub to: String,
+        #[prost(message, optional, tag = "3")]
+        pub message: Option<SharedMessage>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Ack {
+        #[prost(bool, tag = "1")]
+        pub accepted: bool,
+        #[prost(string, tag = "2")]
+        pub reason: String,
+    }
+
+    /// Identity and versions a node introduces itself with
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Hello {
+        #[prost(string, tag = "1")]
+        pub node_id: String,
+        #[prost(uint32, tag = "2")]
+        pub protocol_version: u32,
+        #[prost(string, tag = "3")]
+        pub network_id: String,
+        #[prost(string, tag = "4")]
+        pub software_version: String,
+        #[prost(string, tag = "5")]
+        pub time: String,
+        #[prost(string, tag = "6")]
+        pub public_key: String,
+        #[prost(bytes = "vec", tag = "7")]
+        pub signature: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PeerAnnouncement {
+        #[prost(string, tag = "1")]
+        pub node_id: String,
+        #[prost(string, tag = "2")]
+        pub address: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PeerRequest {
+        #[prost(string, tag = "1")]
+        pub requester_id: String,
+        #[prost(uint32, tag = "2")]
+        pub max_peers: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PeerList {
+        #[prost(message, repeated, tag = "1")]
+        pub peers: Vec<PeerAnnouncement>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TopologyRequest {}
+
+    /// A node's identity and every peer it is connected to
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TopologyReport {
+        #[prost(string, tag = "1")]
+        pub node_id: String,
+        #[prost(message, repeated, tag = "2")]
+        pub peers: Vec<PeerAnnouncement>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SyncRequest {
+        #[prost(uint64, tag = "1")]
+        pub offset: u64,
+        #[prost(uint32, tag = "2")]
+        pub limit: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SyncResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub messages: Vec<SharedMessage>,
+        #[prost(uint64, tag = "2")]
+        pub next_offset: u64,
+    }
+
+    /// Filter over stored messages; empty fields match everything
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MessageQuery {
+        #[prost(string, tag = "1")]
+        pub message_type: String,
+        #[prost(string, tag = "2")]
+        pub target_id: String,
+        #[prost(string, tag = "3")]
+        pub sender: String,
+        #[prost(string, tag = "4")]
+        pub since: String,
+        #[prost(string, tag = "5")]
+        pub until: String,
+        #[prost(uint32, tag = "6")]
+        pub limit: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MessageList {
+        #[prost(message, repeated, tag = "1")]
+        pub messages: Vec<SharedMessage>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StatsRequest {
+        #[prost(uint32, tag = "1")]
+        pub top: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TypeCount {
+        #[prost(string, tag = "1")]
+        pub message_type: String,
+        #[prost(uint64, tag = "2")]
+        pub count: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SenderCount {
+        #[prost(string, tag = "1")]
+        pub sender: String,
+        #[prost(uint64, tag = "2")]
+        pub count: u64,
+    }
+
+    /// Traffic of the server's statistics window, busiest first
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MessageStats {
+        #[prost(uint64, tag = "1")]
+        pub window_secs: u64,
+        #[prost(uint64, tag = "2")]
+        pub total: u64,
+        #[prost(message, repeated, tag = "3")]
+        pub by_type: Vec<TypeCount>,
+        #[prost(message, repeated, tag = "4")]
+        pub top_senders: Vec<SenderCount>,
+        #[prost(uint64, tag = "5")]
+        pub unsigned: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MetricsRequest {}
+
+    /// Counters and gauges of a node by name, with its resource usage
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NodeMetrics {
+        #[prost(map = "string, uint64", tag = "1")]
+        pub values: std::collections::HashMap<String, u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ObjectsRequest {
+        /// Only objects of this type, empty for all
+        #[prost(string, tag = "1")]
+        pub type_name: String,
+    }
+
+    /// Latest published state of an application object
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ObjectState {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub type_name: String,
+        /// JSON returned by the object's `get_state`
+        #[prost(string, tag = "3")]
+        pub state_json: String,
+        #[prost(string, tag = "4")]
+        pub digest: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ObjectList {
+        #[prost(message, repeated, tag = "1")]
+        pub objects: Vec<ObjectState>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HistoryRequest {
+        #[prost(string, tag = "1")]
+        pub object_id: String,
+        /// Entries before this sequence, 0 for the newest
+        #[prost(uint64, tag = "2")]
+        pub cursor: u64,
+        #[prost(uint32, tag = "3")]
+        pub limit: u32,
+    }
+
+    /// A message an object handled
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HistoryEntry {
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+        #[prost(string, tag = "2")]
+        pub hash: String,
+        #[prost(string, tag = "3")]
+        pub message_type: String,
+        /// RFC 3339
+        #[prost(string, tag = "4")]
+        pub timestamp: String,
+        #[prost(string, tag = "5")]
+        pub outcome_json: String,
+        #[prost(string, tag = "6")]
+        pub digest: String,
+    }
+
+    /// A page of an object's history, newest first
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HistoryPage {
+        #[prost(message, repeated, tag = "1")]
+        pub entries: Vec<HistoryEntry>,
+        /// Cursor of the next page, 0 if there is none
+        #[prost(uint64, tag = "2")]
+        pub next_cursor: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ObjectInfoRequest {
+        #[prost(string, tag = "1")]
+        pub object_id: String,
+    }
+
+    /// Bookkeeping a node keeps for every object, whatever its type
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ObjectInfo {
+        #[prost(uint64, tag = "1")]
+        pub applied: u64,
+        #[prost(uint64, tag = "2")]
+        pub rejected: u64,
+        /// RFC 3339, empty until the object applied a message
+        #[prost(string, tag = "3")]
+        pub last_applied: String,
+        /// Empty until the object applied a message
+        #[prost(string, tag = "4")]
+        pub last_digest: String,
+    }
+
+    /// Settings of a running node to change; unset fields keep their value
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ConfigUpdate {
+        #[prost(uint64, optional, tag = "1")]
+        pub gossip_interval_ms: Option<u64>,
+        #[prost(uint32, optional, tag = "2")]
+        pub fanout: Option<u32>,
+        /// Messages accepted from one peer per quota window
+        #[prost(uint32, optional, tag = "3")]
+        pub rate_limit: Option<u32>,
+        /// off, error, warn, info, debug or trace
+        #[prost(string, optional, tag = "4")]
+        pub log_level: Option<String>,
+        #[prost(uint64, optional, tag = "5")]
+        pub audit_retention: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ConfigRequest {}
+
+    /// Settings in effect; 0 or empty where the subsystem is not enabled
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RuntimeConfig {
+        #[prost(uint64, tag = "1")]
+        pub gossip_interval_ms: u64,
+        #[prost(uint32, tag = "2")]
+        pub fanout: u32,
+        #[prost(uint32, tag = "3")]
+        pub rate_limit: u32,
+        #[prost(string, tag = "4")]
+        pub log_level: String,
+        #[prost(uint64, tag = "5")]
+        pub audit_retention: u64,
+    }
+}
+
+impl From<&SharedMessage> for proto::SharedMessage {
+    fn from(message: &SharedMessage) -> Self {
+        Self {
+            id: message.id.to_string(),
+            message_type: message.message_type.to_string(),
+            target_id: message
+                .target_id
+                .as_ref()
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            data_json: message.data.to_string(),
+            timestamp: message.timestamp.to_rfc3339(),
+            signature: message.signature.clone().unwrap_or_default(),
+            hash: message.hash.clone(),
+            pow_nonce: message.pow_nonce,
+            sender: message.sender.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<proto::SharedMessage> for SharedMessage {
+    type Error = ChaincraftError;
+
+    fn try_from(message: proto::SharedMessage) -> Result<Self> {
+        let parse_id = |field: &str, value: &str| {
+            value
+                .parse::<SharedObjectId>()
+                .map_err(|e| invalid(format!("{}: {}", field, e)))
+        };
+
+        Ok(Self {
+            id: parse_id("id", &message.id)?,
+            message_type: serde_json::from_value::<MessageType>(serde_json::Value::String(
+                message.message_type,
+            ))?,
+            target_id: match message.target_id.as_str() {
+                "" => None,
+                id => Some(parse_id("target_id", id)?),
+            },
+            data: serde_json::from_str(&message.data_json)?,
+            timestamp: chrono::DateTime::parse_from_rfc3339(&message.timestamp)
+                .map_err(|e| invalid(format!("timestamp: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            signature: (!message.signature.is_empty()).then_some(message.signature),
+            sender: (!message.sender.is_empty()).then_some(message.sender),
+            hash: message.hash,
+            pow_nonce: message.pow_nonce,
+            simulated: false,
+        })
+    }
+}
+
+impl From<&MessageFilter> for proto::MessageQuery {
+    fn from(filter: &MessageFilter) -> Self {
+        Self {
+            message_type: filter
+                .message_type
+                .as_ref()
+                .map(|t| t.to_string())
+                .unwrap_or_default(),
+            target_id: filter
+                .target_id
+                .as_ref()
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            sender: filter.sender.clone().unwrap_or_default(),
+            since: filter.since.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            until: filter.until.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            limit: filter
+                .limit
+                .map_or(0, |limit| limit.min(u32::MAX as usize) as u32),
+        }
+    }
+}
+
+impl TryFrom<proto::MessageQuery> for MessageFilter {
+    type Error = ChaincraftError;
+
+    fn try_from(query: proto::MessageQuery) -> Result<Self> {
+        let parse_time = |field: &str, value: &str| match value {
+            "" => Ok(None),
+            time => chrono::DateTime::parse_from_rfc3339(time)
+                .map(|t| Some(t.with_timezone(&chrono::Utc)))
+                .map_err(|e| invalid(format!("{}: {}", field, e))),
+        };
+
+        Ok(Self {
+            message_type: match query.message_type.as_str() {
+                "" => None,
+                _ => Some(serde_json::from_value::<MessageType>(serde_json::Value::String(
+                    query.message_type,
+                ))?),
+            },
+            target_id: match query.target_id.as_str() {
+                "" => None,
+                id => Some(
+                    id.parse::<SharedObjectId>()
+                        .map_err(|e| invalid(format!("target_id: {}", e)))?,
+                ),
+            },
+            sender: (!query.sender.is_empty()).then_some(query.sender),
+            since: parse_time("since", &query.since)?,
+            until: parse_time("until", &query.until)?,
+            limit: (query.limit > 0).then_some(query.limit as usize),
+        })
+    }
+}
+
+impl From<&PeerInfo> for proto::PeerAnnouncement {
+    fn from(peer: &PeerInfo) -> Self {
+        Self {
+            node_id: peer.id.to_string(),
+            address: peer.address.to_string(),
+        }
+    }
+}
+
+impl TryFrom<&proto::PeerAnnouncement> for PeerInfo {
+    type Error = ChaincraftError;
+
+    fn try_from(announcement: &proto::PeerAnnouncement) -> Result<Self> {
+        let id = announcement
+            .node_id
+            .parse()
+            .map_err(|e| invalid(format!("node_id: {}", e)))?;
+        let address = announcement
+            .address
+            .parse()
+            .map_err(|e| invalid(format!("address: {}", e)))?;
+        Ok(PeerInfo::new(id, address))
+    }
+}
+
+impl proto::Hello {
+    /// Hello from this build of the node, stamped with `now`
+    pub fn new(
+        node_id: &PeerId,
+        network_id: impl Into<String>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            network_id: network_id.into(),
+            software_version: crate::VERSION.to_string(),
+            time: now.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            public_key: String::new(),
+            signature: Vec::new(),
+        }
+    }
+
+    /// Sign the hello with a node key, proving the sender holds it
+    pub fn signed(mut self, private_key: &PrivateKey) -> Result<Self> {
+        self.public_key = private_key.public_key().to_hex();
+        self.signature = private_key.sign(&self.signing_bytes())?.to_bytes();
+        Ok(self)
+    }
+
+    /// Public key the sender proved it holds, if the hello is signed
+    pub fn verified_key(&self) -> Option<&str> {
+        if self.public_key.is_empty() {
+            return None;
+        }
+        let key_type = KeyType::of_public_key_hex(&self.public_key);
+        let public_key = PublicKey::from_hex(&self.public_key, key_type).ok()?;
+        let signature = Signature::from_hex(&hex::encode(&self.signature), key_type).ok()?;
+        public_key
+            .verify(&self.signing_bytes(), &signature)
+            .unwrap_or(false)
+            .then_some(self.public_key.as_str())
+    }
+
+    /// Bytes covered by the signature: every field before it, one per line
+    fn signing_bytes(&self) -> Vec<u8> {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            self.node_id,
+            self.protocol_version,
+            self.network_id,
+            self.software_version,
+            self.time,
+            self.public_key
+        )
+        .into_bytes()
+    }
+
+    /// Sender's clock when the hello was built
+    pub fn timestamp(&self) -> Result<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&self.time)
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .map_err(|e| invalid(format!("time: {}", e)))
+    }
+}
+
+impl From<&MessageStatsReport> for proto::MessageStats {
+    fn from(report: &MessageStatsReport) -> Self {
+        Self {
+            window_secs: report.window_secs,
+            total: report.total,
+            by_type: report
+                .by_type
+                .iter()
+                .map(|t| proto::TypeCount {
+                    message_type: t.message_type.clone(),
+                    count: t.count,
+                })
+                .collect(),
+            top_senders: report
+                .top_senders
+                .iter()
+                .map(|s| proto::SenderCount {
+                    sender: s.sender.clone(),
+                    count: s.count,
+                })
+                .collect(),
+            unsigned: report.unsigned,
+        }
+    }
+}
+
+impl From<proto::MessageStats> for MessageStatsReport {
+    fn from(stats: proto::MessageStats) -> Self {
+        let total = stats.total;
+        Self {
+            window_secs: stats.window_secs,
+            total,
+            by_type: stats
+                .by_type
+                .into_iter()
+                .map(|t| TypeCount {
+                    message_type: t.message_type,
+                    count: t.count,
+                })
+                .collect(),
+            top_senders: stats
+                .top_senders
+                .into_iter()
+                .map(|s| SenderCount {
+                    sender: s.sender,
+                    count: s.count,
+                    share: s.count as f64 / total.max(1) as f64,
+                })
+                .collect(),
+            unsigned: stats.unsigned,
+        }
+    }
+}
+
+impl From<&ObjectSnapshot> for proto::ObjectState {
+    fn from(snapshot: &ObjectSnapshot) -> Self {
+        Self {
+            id: snapshot.id.to_string(),
+            type_name: snapshot.type_name.clone(),
+            state_json: snapshot.state.to_string(),
+            digest: snapshot.digest.clone(),
+        }
+    }
+}
+
+impl TryFrom<proto::ObjectState> for ObjectSnapshot {
+    type Error = ChaincraftError;
+
+    fn try_from(object: proto::ObjectState) -> Result<Self> {
+        let id = object
+            .id
+            .parse()
+            .map_err(|e| invalid(format!("invalid object id {}: {}", object.id, e)))?;
+        Ok(Self {
+            id,
+            type_name: object.type_name,
+            state: serde_json::from_str(&object.state_json)?,
+            digest: object.digest,
+        })
+    }
+}
+
+impl From<&HistoryEntry> for proto::HistoryEntry {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            hash: entry.hash.clone(),
+            message_type: entry.message_type.clone(),
+            timestamp: entry.timestamp.to_rfc3339(),
+            outcome_json: serde_json::to_string(&entry.outcome).unwrap_or_default(),
+            digest: entry.digest.clone(),
+        }
+    }
+}
+
+impl TryFrom<proto::HistoryEntry> for HistoryEntry {
+    type Error = ChaincraftError;
+
+    fn try_from(entry: proto::HistoryEntry) -> Result<Self> {
+        Ok(Self {
+            sequence: entry.sequence,
+            hash: entry.hash,
+            message_type: entry.message_type,
+            timestamp: chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                .map_err(|e| invalid(format!("timestamp: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            outcome: serde_json::from_str(&entry.outcome_json)?,
+            digest: entry.digest,
+        })
+    }
+}
+
+impl From<&ConfigUpdate> for proto::ConfigUpdate {
+    fn from(update: &ConfigUpdate) -> Self {
+        Self {
+            gossip_interval_ms: update.gossip_interval.map(|i| i.as_millis() as u64),
+            fanout: update.fanout.map(|n| n as u32),
+            rate_limit: update.rate_limit,
+            log_level: update.log_level.map(|level| level.to_string()),
+            audit_retention: update.audit_retention.map(|n| n as u64),
+        }
+    }
+}
+
+impl TryFrom<proto::ConfigUpdate> for ConfigUpdate {
+    type Error = ChaincraftError;
+
+    fn try_from(update: proto::ConfigUpdate) -> Result<Self> {
+        let log_level = match update.log_level {
+            Some(level) => Some(
+                level
+                    .parse()
+                    .map_err(|e| invalid(format!("log_level: {}", e)))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            gossip_interval: update.gossip_interval_ms.map(Duration::from_millis),
+            fanout: update.fanout.map(|n| n as usize),
+            rate_limit: update.rate_limit,
+            log_level,
+            audit_retention: update.audit_retention.map(|n| n as usize),
+        })
+    }
+}
+
+impl From<&RuntimeConfig> for proto::RuntimeConfig {
+    fn from(config: &RuntimeConfig) -> Self {
+        Self {
+            gossip_interval_ms: config.gossip_interval.map_or(0, |i| i.as_millis() as u64),
+            fanout: config.fanout as u32,
+            rate_limit: config.rate_limit,
+            log_level: config
+                .log_level
+                .map(|level| level.to_string())
+                .unwrap_or_default(),
+            audit_retention: config.audit_retention.unwrap_or(0) as u64,
+        }
+    }
+}
+
+impl TryFrom<proto::RuntimeConfig> for RuntimeConfig {
+    type Error = ChaincraftError;
+
+    fn try_from(config: proto::RuntimeConfig) -> Result<Self> {
+        let log_level = match config.log_level.as_str() {
+            "" => None,
+            level => Some(
+                level
+                    .parse()
+                    .map_err(|e| invalid(format!("log_level: {}", e)))?,
+            ),
+        };
+        Ok(Self {
+            gossip_interval: (config.gossip_interval_ms > 0)
+                .then(|| Duration::from_millis(config.gossip_interval_ms)),
+            fanout: config.fanout as usize,
+            rate_limit: config.rate_limit,
+            log_level,
+            audit_retention: (config.audit_retention > 0)
+                .then_some(config.audit_retention as usize),
+        })
+    }
+}
+
+impl From<&ObjectInfo> for proto::ObjectInfo {
+    fn from(info: &ObjectInfo) -> Self {
+        Self {
+            applied: info.applied,
+            rejected: info.rejected,
+            last_applied: info
+                .last_applied
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default(),
+            last_digest: info.last_digest.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<proto::ObjectInfo> for ObjectInfo {
+    type Error = ChaincraftError;
+
+    fn try_from(info: proto::ObjectInfo) -> Result<Self> {
+        let last_applied = match info.last_applied.as_str() {
+            "" => None,
+            at => Some(
+                chrono::DateTime::parse_from_rfc3339(at)
+                    .map_err(|e| invalid(format!("last_applied: {}", e)))?
+                    .with_timezone(&chrono::Utc),
+            ),
+        };
+        Ok(Self {
+            applied: info.applied,
+            rejected: info.rejected,
+            last_applied,
+            last_digest: (!info.last_digest.is_empty()).then_some(info.last_digest),
+        })
+    }
+}
+
+fn invalid(reason: String) -> ChaincraftError {
+    ChaincraftError::Network(NetworkError::InvalidMessage { reason })
+}
+
+fn rpc_error(error: impl std::fmt::Display) -> ChaincraftError {
+    ChaincraftError::Network(NetworkError::RpcFailed {
+        reason: error.to_string(),
+    })
+}
+
+/// Handler for the `chaincraft.v1.Peer` service
+#[async_trait]
+pub trait PeerService: Send + Sync + 'static {
+    async fn deliver(
+        &self,
+        request: Request<proto::Envelope>,
+    ) -> std::result::Result<Response<proto::Ack>, Status>;
+
+    async fn handshake(
+        &self,
+        request: Request<proto::Hello>,
+    ) -> std::result::Result<Response<proto::Hello>, Status>;
+}
+
+/// Handler for the `chaincraft.v1.Discovery` service
+#[async_trait]
+pub trait DiscoveryService: Send + Sync + 'static {
+    async fn announce(
+        &self,
+        request: Request<proto::PeerAnnouncement>,
+    ) -> std::result::Result<Response<proto::PeerList>, Status>;
+
+    async fn get_peers(
+        &self,
+        request: Request<proto::PeerRequest>,
+    ) -> std::result::Result<Response<proto::PeerList>, Status>;
+
+    async fn topology(
+        &self,
+        request: Request<proto::TopologyRequest>,
+    ) -> std::result::Result<Response<proto::TopologyReport>, Status>;
+}
+
+/// Handler for the `chaincraft.v1.Sync` service
+#[async_trait]
+pub trait SyncService: Send + Sync + 'static {
+    async fn get_messages(
+        &self,
+        request: Request<proto::SyncRequest>,
+    ) -> std::result::Result<Response<proto::SyncResponse>, Status>;
+
+    async fn query_messages(
+        &self,
+        request: Request<proto::MessageQuery>,
+    ) -> std::result::Result<Response<proto::MessageList>, Status>;
+}
+
+/// Handler for the `chaincraft.v1.Stats` service
+#[async_trait]
+pub trait StatsService: Send + Sync + 'static {
+    async fn messages(
+        &self,
+        request: Request<proto::StatsRequest>,
+    ) -> std::result::Result<Response<proto::MessageStats>, Status>;
+
+    async fn metrics(
+        &self,
+        request: Request<proto::MetricsRequest>,
+    ) -> std::result::Result<Response<proto::NodeMetrics>, Status>;
+}
+
+/// Handler for the `chaincraft.v1.Objects` service
+#[async_trait]
+pub trait ObjectsService: Send + Sync + 'static {
+    async fn list(
+        &self,
+        request: Request<proto::ObjectsRequest>,
+    ) -> std::result::Result<Response<proto::ObjectList>, Status>;
+
+    async fn history(
+        &self,
+        request: Request<proto::HistoryRequest>,
+    ) -> std::result::Result<Response<proto::HistoryPage>, Status>;
+
+    async fn info(
+        &self,
+        request: Request<proto::ObjectInfoRequest>,
+    ) -> std::result::Result<Response<proto::ObjectInfo>, Status>;
+}
+
+/// Handler for the `chaincraft.v1.Config` service
+#[async_trait]
+pub trait ConfigService: Send + Sync + 'static {
+    async fn get(
+        &self,
+        request: Request<proto::ConfigRequest>,
+    ) -> std::result::Result<Response<proto::RuntimeConfig>, Status>;
+
+    async fn update(
+        &self,
+        request: Request<proto::ConfigUpdate>,
+    ) -> std::result::Result<Response<proto::RuntimeConfig>, Status>;
+}
+
+/// Handler for the `chaincraft.v1.Messages` service
+#[async_trait]
+pub trait MessagesService: Send + Sync + 'static {
+    async fn submit(
+        &self,
+        request: Request<proto::SharedMessage>,
+    ) -> std::result::Result<Response<proto::Ack>, Status>;
+}
+
+/// Adapts an async closure to a tonic unary method
+struct UnaryMethod<F>(F);
+
+impl<F, Fut, Req, Resp> tonic::server::UnaryService<Req> for UnaryMethod<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Fut: Future<Output = std::result::Result<Response<Resp>, Status>> + Send + 'static,
+{
+    type Response = Resp;
+    type Future = BoxFuture<Response<Resp>, Status>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        Box::pin((self.0)(request))
+    }
+}
+
+fn unimplemented_response() -> http::Response<BoxBody> {
+    let mut response = http::Response::new(tonic::body::empty_body());
+    let headers = response.headers_mut();
+    headers.insert("grpc-status", (tonic::Code::Unimplemented as i32).into());
+    headers.insert(http::header::CONTENT_TYPE, http::HeaderValue::from_static("application/grpc"));
+    response
+}
+
+/// Defines a tonic server routing each method path to a handler trait method
+macro_rules! grpc_server {
+    (
+        $(#[$meta:meta])*
+        $server:ident<$service:ident> = $name:literal {
+            $($path:literal => $method:ident($req:ty) -> $resp:ty;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $server<T> {
+            inner: Arc<T>,
+        }
+
+        impl<T> $server<T> {
+            /// Wrap a service handler
+            pub fn new(inner: T) -> Self {
+                Self {
+                    inner: Arc::new(inner),
+                }
+            }
+        }
+
+        impl<T> Clone for $server<T> {
+            fn clone(&self) -> Self {
+                Self {
+                    inner: self.inner.clone(),
+                }
+            }
+        }
+
+        impl<T: $service> tonic::server::NamedService for $server<T> {
+            const NAME: &'static str = $name;
+        }
+
+        impl<T, B> tonic::codegen::Service<http::Request<B>> for $server<T>
+        where
+            T: $service,
+            B: tonic::codegen::Body + Send + 'static,
+            B::Error: Into<StdError> + Send + 'static,
+        {
+            type Response = http::Response<BoxBody>;
+            type Error = Infallible;
+            type Future = BoxFuture<Self::Response, Self::Error>;
+
+            fn poll_ready(
+                &mut self,
+                _cx: &mut Context<'_>,
+            ) -> Poll<std::result::Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, req: http::Request<B>) -> Self::Future {
+                let inner = self.inner.clone();
+                match req.uri().path() {
+                    $(
+                        $path => Box::pin(async move {
+                            let method = UnaryMethod(move |request: Request<$req>| {
+                                let inner = inner.clone();
+                                async move { inner.$method(request).await }
+                            });
+                            let codec = ProstCodec::<$resp, $req>::default();
+                            Ok(tonic::server::Grpc::new(codec).unary(method, req).await)
+                        }),
+                    )*
+                    _ => Box::pin(async move { Ok(unimplemented_response()) }),
+                }
+            }
+        }
+    };
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Peer` service
+    PeerServer<PeerService> = "chaincraft.v1.Peer" {
+        "/chaincraft.v1.Peer/Deliver" => deliver(proto::Envelope) -> proto::Ack;
+        "/chaincraft.v1.Peer/Handshake" => handshake(proto::Hello) -> proto::Hello;
+    }
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Discovery` service
+    DiscoveryServer<DiscoveryService> = "chaincraft.v1.Discovery" {
+        "/chaincraft.v1.Discovery/Announce" => announce(proto::PeerAnnouncement) -> proto::PeerList;
+        "/chaincraft.v1.Discovery/GetPeers" => get_peers(proto::PeerRequest) -> proto::PeerList;
+        "/chaincraft.v1.Discovery/Topology" => topology(proto::TopologyRequest) -> proto::TopologyReport;
+    }
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Sync` service
+    SyncServer<SyncService> = "chaincraft.v1.Sync" {
+        "/chaincraft.v1.Sync/GetMessages" => get_messages(proto::SyncRequest) -> proto::SyncResponse;
+        "/chaincraft.v1.Sync/QueryMessages" => query_messages(proto::MessageQuery) -> proto::MessageList;
+    }
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Stats` service
+    StatsServer<StatsService> = "chaincraft.v1.Stats" {
+        "/chaincraft.v1.Stats/Messages" => messages(proto::StatsRequest) -> proto::MessageStats;
+        "/chaincraft.v1.Stats/Metrics" => metrics(proto::MetricsRequest) -> proto::NodeMetrics;
+    }
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Objects` service
+    ObjectsServer<ObjectsService> = "chaincraft.v1.Objects" {
+        "/chaincraft.v1.Objects/List" => list(proto::ObjectsRequest) -> proto::ObjectList;
+        "/chaincraft.v1.Objects/History" => history(proto::HistoryRequest) -> proto::HistoryPage;
+        "/chaincraft.v1.Objects/Info" => info(proto::ObjectInfoRequest) -> proto::ObjectInfo;
+    }
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Config` service
+    ConfigServer<ConfigService> = "chaincraft.v1.Config" {
+        "/chaincraft.v1.Config/Get" => get(proto::ConfigRequest) -> proto::RuntimeConfig;
+        "/chaincraft.v1.Config/Update" => update(proto::ConfigUpdate) -> proto::RuntimeConfig;
+    }
+}
+
+grpc_server! {
+    /// tonic server for the `chaincraft.v1.Messages` service
+    MessagesServer<MessagesService> = "chaincraft.v1.Messages" {
+        "/chaincraft.v1.Messages/Submit" => submit(proto::SharedMessage) -> proto::Ack;
+    }
+}
+
+/// Client for all Chaincraft gRPC services of a remote node
+#[derive(Debug, Clone)]
+pub struct GrpcClient {
+    inner: tonic::client::Grpc<Channel>,
+}
+
+impl GrpcClient {
+    /// Connect to a node serving the gRPC peer protocol
+    pub async fn connect(addr: SocketAddr) -> Result<Self> {
+        let channel = Endpoint::from_shared(format!("http://{}", addr))
+            .map_err(rpc_error)?
+            .connect()
+            .await
+            .map_err(|e| {
+                ChaincraftError::Network(NetworkError::ConnectionFailed {
+                    addr,
+                    source: std::io::Error::other(e),
+                })
+            })?;
+        Ok(Self {
+            inner: tonic::client::Grpc::new(channel),
+        })
+    }
+
+    async fn unary<Req, Resp>(&mut self, path: &'static str, request: Req) -> Result<Resp>
+    where
+        Req: prost::Message + 'static,
+        Resp: prost::Message + Default + 'static,
+    {
+        self.inner.ready().await.map_err(rpc_error)?;
+        let codec = ProstCodec::<Req, Resp>::default();
+        let response = self
+            .inner
+            .unary(Request::new(request), http::uri::PathAndQuery::from_static(path), codec)
+            .await
+            .map_err(|status| rpc_error(status.message()))?;
+        Ok(response.into_inner())
+    }
+
+    /// Deliver a message to the remote node
+    pub async fn deliver(&mut self, envelope: proto::Envelope) -> Result<proto::Ack> {
+        self.unary("/chaincraft.v1.Peer/Deliver", envelope).await
+    }
+
+    /// Introduce ourselves and learn the remote node's identity and versions
+    pub async fn handshake(&mut self, hello: proto::Hello) -> Result<proto::Hello> {
+        self.unary("/chaincraft.v1.Peer/Handshake", hello).await
+    }
+
+    /// Announce ourselves and learn the peers the remote node knows
+    pub async fn announce(
+        &mut self,
+        node_id: &PeerId,
+        address: SocketAddr,
+    ) -> Result<Vec<PeerInfo>> {
+        let announcement = proto::PeerAnnouncement {
+            node_id: node_id.to_string(),
+            address: address.to_string(),
+        };
+        let list: proto::PeerList = self
+            .unary("/chaincraft.v1.Discovery/Announce", announcement)
+            .await?;
+        list.peers.iter().map(PeerInfo::try_from).collect()
+    }
+
+    /// Ask the remote node for peers
+    pub async fn get_peers(
+        &mut self,
+        requester_id: &PeerId,
+        max_peers: u32,
+    ) -> Result<Vec<PeerInfo>> {
+        let request = proto::PeerRequest {
+            requester_id: requester_id.to_string(),
+            max_peers,
+        };
+        let list: proto::PeerList = self
+            .unary("/chaincraft.v1.Discovery/GetPeers", request)
+            .await?;
+        list.peers.iter().map(PeerInfo::try_from).collect()
+    }
+
+    /// Learn the remote node's id and all of its peers
+    pub async fn topology(&mut self) -> Result<(PeerId, Vec<PeerInfo>)> {
+        let report: proto::TopologyReport = self
+            .unary("/chaincraft.v1.Discovery/Topology", proto::TopologyRequest {})
+            .await?;
+        let node_id = report
+            .node_id
+            .parse()
+            .map_err(|e| invalid(format!("node_id: {}", e)))?;
+        let peers = report
+            .peers
+            .iter()
+            .map(PeerInfo::try_from)
+            .collect::<Result<_>>()?;
+        Ok((node_id, peers))
+    }
+
+    /// Fetch a page of the remote message log; returns the messages and the next offset
+    pub async fn get_messages(
+        &mut self,
+        offset: u64,
+        limit: u32,
+    ) -> Result<(Vec<SharedMessage>, u64)> {
+        let response: proto::SyncResponse = self
+            .unary("/chaincraft.v1.Sync/GetMessages", proto::SyncRequest { offset, limit })
+            .await?;
+        let messages = response
+            .messages
+            .into_iter()
+            .map(SharedMessage::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        Ok((messages, response.next_offset))
+    }
+
+    /// Fetch the remote node's stored messages matching `filter`
+    pub async fn query_messages(&mut self, filter: &MessageFilter) -> Result<Vec<SharedMessage>> {
+        let list: proto::MessageList = self
+            .unary("/chaincraft.v1.Sync/QueryMessages", proto::MessageQuery::from(filter))
+            .await?;
+        list.messages
+            .into_iter()
+            .map(SharedMessage::try_from)
+            .collect()
+    }
+
+    /// Fetch the remote node's traffic statistics with its `top` busiest senders
+    pub async fn message_stats(&mut self, top: u32) -> Result<MessageStatsReport> {
+        let stats: proto::MessageStats = self
+            .unary("/chaincraft.v1.Stats/Messages", proto::StatsRequest { top })
+            .await?;
+        Ok(stats.into())
+    }
+
+    /// Fetch the remote node's metrics and resource usage, by name
+    pub async fn node_metrics(&mut self) -> Result<BTreeMap<String, u64>> {
+        let metrics: proto::NodeMetrics = self
+            .unary("/chaincraft.v1.Stats/Metrics", proto::MetricsRequest {})
+            .await?;
+        Ok(metrics.values.into_iter().collect())
+    }
+
+    /// Fetch the settings in effect on the remote node, see
+    /// [`ChaincraftNode::runtime_config`]
+    pub async fn runtime_config(&mut self) -> Result<RuntimeConfig> {
+        let config: proto::RuntimeConfig = self
+            .unary("/chaincraft.v1.Config/Get", proto::ConfigRequest {})
+            .await?;
+        RuntimeConfig::try_from(config)
+    }
+
+    /// Change settings of the remote node, see [`ChaincraftNode::update_config`]
+    pub async fn update_config(&mut self, update: &ConfigUpdate) -> Result<RuntimeConfig> {
+        let config: proto::RuntimeConfig = self
+            .unary("/chaincraft.v1.Config/Update", proto::ConfigUpdate::from(update))
+            .await?;
+        RuntimeConfig::try_from(config)
+    }
+
+    /// Submit a message for the remote node to validate and store, see
+    /// [`ChaincraftNode::submit_message`]
+    pub async fn submit(&mut self, message: &SharedMessage) -> Result<proto::Ack> {
+        self.unary("/chaincraft.v1.Messages/Submit", proto::SharedMessage::from(message))
+            .await
+    }
+
+    /// Fetch the latest state of the remote node's objects, of one type if
+    /// `type_name` is not empty
+    pub async fn objects(&mut self, type_name: &str) -> Result<Vec<ObjectSnapshot>> {
+        let request = proto::ObjectsRequest {
+            type_name: type_name.to_string(),
+        };
+        let list: proto::ObjectList = self.unary("/chaincraft.v1.Objects/List", request).await?;
+        list.objects
+            .into_iter()
+            .map(ObjectSnapshot::try_from)
+            .collect()
+    }
+
+    /// Fetch a page of a remote object's history, see
+    /// [`ApplicationObject::history`](crate::shared_object::ApplicationObject::history)
+    pub async fn object_history(
+        &mut self,
+        id: &SharedObjectId,
+        cursor: Option<HistoryCursor>,
+        limit: u32,
+    ) -> Result<(Vec<HistoryEntry>, Option<HistoryCursor>)> {
+        let request = proto::HistoryRequest {
+            object_id: id.to_string(),
+            cursor: cursor.map_or(0, |cursor| cursor.0),
+            limit,
+        };
+        let page: proto::HistoryPage = self
+            .unary("/chaincraft.v1.Objects/History", request)
+            .await?;
+        let entries = page
+            .entries
+            .into_iter()
+            .map(HistoryEntry::try_from)
+            .collect::<Result<_>>()?;
+        let next = (page.next_cursor > 0).then_some(HistoryCursor(page.next_cursor));
+        Ok((entries, next))
+    }
+
+    /// Fetch the bookkeeping of a remote object, see [`ChaincraftNode::object_info`]
+    pub async fn object_info(&mut self, id: &SharedObjectId) -> Result<ObjectInfo> {
+        let request = proto::ObjectInfoRequest {
+            object_id: id.to_string(),
+        };
+        let info: proto::ObjectInfo = self.unary("/chaincraft.v1.Objects/Info", request).await?;
+        ObjectInfo::try_from(info)
+    }
+}
+
+/// Peer service that queues delivered messages for a [`GrpcTransport`]
+struct GrpcInbox {
+    local_id: PeerId,
+    network_id: String,
+    clock: Clock,
+    access: PeerAccess,
+    sender: mpsc::UnboundedSender<(PeerId, SharedMessage)>,
+}
+
+#[async_trait]
+impl PeerService for GrpcInbox {
+    async fn deliver(
+        &self,
+        request: Request<proto::Envelope>,
+    ) -> std::result::Result<Response<proto::Ack>, Status> {
+        let envelope = request.into_inner();
+        let reject = |reason: &str| {
+            Response::new(proto::Ack {
+                accepted: false,
+                reason: reason.to_string(),
+            })
+        };
+
+        if envelope.to != self.local_id.to_string() {
+            return Ok(reject("wrong recipient"));
+        }
+        let Ok(from) = envelope.from.parse::<PeerId>() else {
+            return Ok(reject("invalid sender id"));
+        };
+        let Some(Ok(message)) = envelope.message.map(SharedMessage::try_from) else {
+            return Ok(reject("invalid message"));
+        };
+        if !message.verify_hash() {
+            return Ok(reject("message hash does not match its contents"));
+        }
+        if self.sender.send((from, message)).is_err() {
+            return Err(Status::unavailable("transport closed"));
+        }
+
+        Ok(Response::new(proto::Ack {
+            accepted: true,
+            reason: String::new(),
+        }))
+    }
+
+    async fn handshake(
+        &self,
+        request: Request<proto::Hello>,
+    ) -> std::result::Result<Response<proto::Hello>, Status> {
+        let address = request.remote_addr().map(|addr| addr.ip());
+        let hello = request.into_inner();
+        tracing::debug!(
+            "Handshake from {} (protocol {}, network {})",
+            hello.node_id,
+            hello.protocol_version,
+            hello.network_id
+        );
+        if let Err(refusal) = self.access.check(address, hello.verified_key()) {
+            tracing::info!("Refused handshake from {}: {}", hello.node_id, refusal);
+            return Err(Status::permission_denied(refusal.to_string()));
+        }
+        // Answer even on a mismatch so the caller can tell what differs
+        Ok(Response::new(proto::Hello::new(
+            &self.local_id,
+            &self.network_id,
+            self.clock.now(),
+        )))
+    }
+}
+
+/// Discovery, sync, stats, objects and messages services backed by a node
+#[derive(Clone)]
+pub struct NodeServices {
+    node: NodeHandle,
+    local_id: PeerId,
+    peers: Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+    storage: Arc<dyn Storage>,
+    message_log: Arc<RwLock<MessageLog>>,
+    index: Arc<RwLock<MessageIndex>>,
+    access: PeerAccess,
+    max_batch: u32,
+    message_stats: Arc<MessageStats>,
+    app_objects: Arc<RwLock<ApplicationObjectRegistry>>,
+    clock: Clock,
+}
+
+impl NodeServices {
+    /// Maximum number of messages returned by one sync request
+    pub const DEFAULT_MAX_BATCH: u32 = 256;
+
+    /// Number of senders listed when a stats request asks for none
+    pub const DEFAULT_TOP_SENDERS: u32 = 10;
+
+    /// Serve the peers and message log of the given node
+    pub fn from_node(node: &ChaincraftNode) -> Self {
+        Self {
+            node: node.handle(),
+            local_id: node.id.clone(),
+            peers: node.peers.clone(),
+            storage: node.storage.clone(),
+            message_log: node.message_log.clone(),
+            index: node.index.clone(),
+            access: node.config.access.clone(),
+            max_batch: Self::DEFAULT_MAX_BATCH,
+            message_stats: node.message_stats.clone(),
+            app_objects: node.app_objects.clone(),
+            clock: node.clock.clone(),
+        }
+    }
+
+    async fn load_messages(
+        &self,
+        hashes: &[String],
+    ) -> std::result::Result<Vec<proto::SharedMessage>, Status> {
+        let mut messages = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            let bytes = self
+                .storage
+                .get(hash)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?
+                .ok_or_else(|| Status::internal(format!("missing message {}", hash)))?;
+            let message =
+                MessageEncoding::decode(&bytes).map_err(|e| Status::internal(e.to_string()))?;
+            messages.push(proto::SharedMessage::from(&message));
+        }
+        Ok(messages)
+    }
+
+    async fn peer_list(&self, exclude: &str, max_peers: usize) -> proto::PeerList {
+        let peers = self.peers.read().await;
+        proto::PeerList {
+            peers: peers
+                .values()
+                .filter(|peer| peer.id.to_string() != exclude)
+                .take(max_peers)
+                .map(proto::PeerAnnouncement::from)
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl DiscoveryService for NodeServices {
+    async fn announce(
+        &self,
+        request: Request<proto::PeerAnnouncement>,
+    ) -> std::result::Result<Response<proto::PeerList>, Status> {
+        let announcement = request.into_inner();
+        let peer = PeerInfo::try_from(&announcement)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        // Announcements carry no proof of a key, so only address rules apply
+        if let Err(refusal) = self.access.check(Some(peer.address.ip()), None) {
+            return Err(Status::permission_denied(refusal.to_string()));
+        }
+        if peer.id != self.local_id {
+            self.peers.write().await.insert(peer.id.clone(), peer);
+        }
+        Ok(Response::new(self.peer_list(&announcement.node_id, usize::MAX).await))
+    }
+
+    async fn get_peers(
+        &self,
+        request: Request<proto::PeerRequest>,
+    ) -> std::result::Result<Response<proto::PeerList>, Status> {
+        let request = request.into_inner();
+        Ok(Response::new(
+            self.peer_list(&request.requester_id, request.max_peers as usize)
+                .await,
+        ))
+    }
+
+    async fn topology(
+        &self,
+        _request: Request<proto::TopologyRequest>,
+    ) -> std::result::Result<Response<proto::TopologyReport>, Status> {
+        Ok(Response::new(proto::TopologyReport {
+            node_id: self.local_id.to_string(),
+            peers: self.peer_list("", usize::MAX).await.peers,
+        }))
+    }
+}
+
+#[async_trait]
+impl SyncService for NodeServices {
+    async fn get_messages(
+        &self,
+        request: Request<proto::SyncRequest>,
+    ) -> std::result::Result<Response<proto::SyncResponse>, Status> {
+        let request = request.into_inner();
+        let limit = request.limit.clamp(1, self.max_batch) as usize;
+        let (offset, hashes) = {
+            let log = self.message_log.read().await;
+            let hashes: Vec<String> = log
+                .since(request.offset as usize)
+                .take(limit)
+                .cloned()
+                .collect();
+            (request.offset.max(log.base() as u64), hashes)
+        };
+
+        Ok(Response::new(proto::SyncResponse {
+            next_offset: offset + hashes.len() as u64,
+            messages: self.load_messages(&hashes).await?,
+        }))
+    }
+
+    async fn query_messages(
+        &self,
+        request: Request<proto::MessageQuery>,
+    ) -> std::result::Result<Response<proto::MessageList>, Status> {
+        let mut filter = MessageFilter::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let max = self.max_batch as usize;
+        filter.limit = Some(filter.limit.map_or(max, |limit| limit.min(max)));
+        let hashes = self.index.read().await.query(&filter);
+
+        Ok(Response::new(proto::MessageList {
+            messages: self.load_messages(&hashes).await?,
+        }))
+    }
+}
+
+#[async_trait]
+impl StatsService for NodeServices {
+    async fn messages(
+        &self,
+        request: Request<proto::StatsRequest>,
+    ) -> std::result::Result<Response<proto::MessageStats>, Status> {
+        let top = match request.into_inner().top {
+            0 => Self::DEFAULT_TOP_SENDERS,
+            top => top,
+        };
+        let report = self.message_stats.report(self.clock.now(), top as usize);
+        Ok(Response::new(proto::MessageStats::from(&report)))
+    }
+
+    async fn metrics(
+        &self,
+        _request: Request<proto::MetricsRequest>,
+    ) -> std::result::Result<Response<proto::NodeMetrics>, Status> {
+        let mut values: HashMap<String, u64> = self.node.metrics().snapshot().into_iter().collect();
+        // Gauges of the resources ResourceLimits caps, under their serde names
+        let usage = [
+            ("cached_messages", self.message_log.read().await.retained()),
+            ("objects", self.app_objects.read().await.len()),
+            ("peers", self.peers.read().await.len()),
+        ];
+        for (name, used) in usage {
+            values.insert(name.to_string(), used as u64);
+        }
+        if let Some(rss) = resident_memory_bytes() {
+            values.insert("process_rss_bytes".to_string(), rss);
+        }
+        Ok(Response::new(proto::NodeMetrics { values }))
+    }
+}
+
+#[async_trait]
+impl MessagesService for NodeServices {
+    async fn submit(
+        &self,
+        request: Request<proto::SharedMessage>,
+    ) -> std::result::Result<Response<proto::Ack>, Status> {
+        let message = SharedMessage::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let ack = match self.node.submit_message(message).await {
+            Ok(_) => proto::Ack {
+                accepted: true,
+                reason: String::new(),
+            },
+            Err(e) => proto::Ack {
+                accepted: false,
+                reason: e.to_string(),
+            },
+        };
+        Ok(Response::new(ack))
+    }
+}
+
+/// Resident memory of this process, where the OS reports it
+fn resident_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[async_trait]
+impl ObjectsService for NodeServices {
+    async fn list(
+        &self,
+        request: Request<proto::ObjectsRequest>,
+    ) -> std::result::Result<Response<proto::ObjectList>, Status> {
+        let type_name = request.into_inner().type_name;
+        let directory = self.app_objects.read().await.directory();
+        let objects = directory
+            .snapshots()
+            .await
+            .iter()
+            .filter(|snapshot| type_name.is_empty() || snapshot.type_name == type_name)
+            .map(proto::ObjectState::from)
+            .collect();
+        Ok(Response::new(proto::ObjectList { objects }))
+    }
+
+    async fn history(
+        &self,
+        request: Request<proto::HistoryRequest>,
+    ) -> std::result::Result<Response<proto::HistoryPage>, Status> {
+        let request = request.into_inner();
+        let id = request
+            .object_id
+            .parse::<SharedObjectId>()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let cursor = (request.cursor > 0).then_some(HistoryCursor(request.cursor));
+        let limit = request.limit.clamp(1, self.max_batch) as usize;
+        let registry = self.app_objects.read().await;
+        let object = registry
+            .get(&id)
+            .ok_or_else(|| Status::not_found(format!("unknown object {}", id)))?;
+        let (entries, next) = object.history(cursor, limit);
+        Ok(Response::new(proto::HistoryPage {
+            entries: entries.iter().map(proto::HistoryEntry::from).collect(),
+            next_cursor: next.map_or(0, |cursor| cursor.0),
+        }))
+    }
+
+    async fn info(
+        &self,
+        request: Request<proto::ObjectInfoRequest>,
+    ) -> std::result::Result<Response<proto::ObjectInfo>, Status> {
+        let object_id = request.into_inner().object_id;
+        let id = object_id
+            .parse::<SharedObjectId>()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let registry = self.app_objects.read().await;
+        let info = registry
+            .info(&id)
+            .ok_or_else(|| Status::not_found(format!("unknown object {}", id)))?;
+        Ok(Response::new(proto::ObjectInfo::from(info)))
+    }
+}
+
+#[async_trait]
+impl ConfigService for NodeServices {
+    async fn get(
+        &self,
+        _request: Request<proto::ConfigRequest>,
+    ) -> std::result::Result<Response<proto::RuntimeConfig>, Status> {
+        Ok(Response::new(proto::RuntimeConfig::from(&self.node.runtime_config())))
+    }
+
+    async fn update(
+        &self,
+        request: Request<proto::ConfigUpdate>,
+    ) -> std::result::Result<Response<proto::RuntimeConfig>, Status> {
+        let update = ConfigUpdate::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let config = self
+            .node
+            .update_config(update)
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+        Ok(Response::new(proto::RuntimeConfig::from(&config)))
+    }
+}
+
+/// Transport exchanging messages over the gRPC peer protocol
+pub struct GrpcTransport {
+    local_id: PeerId,
+    local_addr: SocketAddr,
+    addresses: DashMap<PeerId, SocketAddr>,
+    inbox: Mutex<mpsc::UnboundedReceiver<(PeerId, SharedMessage)>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl GrpcTransport {
+    /// Serve the peer service on `addr` (use port 0 for any free port)
+    pub async fn bind(local_id: PeerId, addr: SocketAddr) -> Result<Self> {
+        let network_id = DEFAULT_NETWORK_ID.to_string();
+        Self::serve(local_id, addr, network_id, Clock::system(), PeerAccess::default(), None).await
+    }
+
+    /// Serve the peer, discovery, sync, stats, objects, config and messages
+    /// services of a node on `addr`
+    pub async fn bind_node(node: &ChaincraftNode, addr: SocketAddr) -> Result<Self> {
+        let services = NodeServices::from_node(node);
+        let network_id = node.config.network_id.clone();
+        let access = node.config.access.clone();
+        let clock = node.clock.clone();
+        Self::serve(node.id.clone(), addr, network_id, clock, access, Some(services)).await
+    }
+
+    async fn serve(
+        local_id: PeerId,
+        addr: SocketAddr,
+        network_id: String,
+        clock: Clock,
+        access: PeerAccess,
+        services: Option<NodeServices>,
+    ) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|source| {
+                ChaincraftError::Network(NetworkError::BindFailed { addr, source })
+            })?;
+        let local_addr = listener.local_addr()?;
+        let incoming = TcpIncoming::from_listener(listener, true, None).map_err(rpc_error)?;
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let inbox = GrpcInbox {
+            local_id: local_id.clone(),
+            network_id,
+            clock,
+            access,
+            sender,
+        };
+        let router = Server::builder()
+            .add_service(PeerServer::new(inbox))
+            .add_optional_service(services.clone().map(DiscoveryServer::new))
+            .add_optional_service(services.clone().map(SyncServer::new))
+            .add_optional_service(services.clone().map(StatsServer::new))
+            .add_optional_service(services.clone().map(ObjectsServer::new))
+            .add_optional_service(services.clone().map(ConfigServer::new))
+            .add_optional_service(services.map(MessagesServer::new));
+
+        let server = tokio::spawn(async move {
+            if let Err(e) = router.serve_with_incoming(incoming).await {
+                tracing::warn!("gRPC server stopped: {}", e);
+            }
+        });
+
+        Ok(Self {
+            local_id,
+            local_addr,
+            addresses: DashMap::new(),
+            inbox: Mutex::new(receiver),
+            server,
+        })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Remember where a peer can be reached
+    pub fn add_peer(&self, peer_id: PeerId, addr: SocketAddr) {
+        self.addresses.insert(peer_id, addr);
+    }
+}
+
+impl Drop for GrpcTransport {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[async_trait]
+impl Transport for GrpcTransport {
+    fn local_id(&self) -> &PeerId {
+        &self.local_id
+    }
+
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        let addr = self
+            .addresses
+            .get(to)
+            .map(|entry| *entry.value())
+            .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))?;
+
+        let ack = GrpcClient::connect(addr)
+            .await?
+            .deliver(proto::Envelope {
+                from: self.local_id.to_string(),
+                to: to.to_string(),
+                message: Some(proto::SharedMessage::from(message)),
+            })
+            .await?;
+
+        if ack.accepted {
+            Ok(())
+        } else {
+            Err(rpc_error(ack.reason))
+        }
+    }
+
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        self.inbox
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))
+    }
+}
@@ -0,0 +1,306 @@
+//! Connection-level keepalives and dead-peer detection
+//!
+//! [`KeepaliveTransport`] wraps any [`Transport`] and pings every tracked peer
+//! once per [`KeepaliveConfig::interval`]. Receivers answer pings with pongs
+//! without handing either to the application. Any frame from a peer counts as
+//! a sign of life; a peer that lets a ping go unanswered until the next round
+//! has missed one keepalive, is [`PeerStatus::Suspect`] from then on and
+//! [`PeerStatus::Dead`] after [`KeepaliveConfig::max_missed`] in a row.
+//!
+//! Liveness is kept in a [`PeerLiveness`] table that can be shared with a
+//! node, see [`KeepaliveTransport::with_liveness`]: the node tracks its peers
+//! there, reports their status in [`PeerInfo`](crate::network::PeerInfo) and
+//! leaves dead peers out of gossip. Dead peers are still pinged, so one that
+//! answers again comes back to life.
+
+use crate::{
+    error::Result,
+    metrics::NodeMetrics,
+    network::{transport::Transport, PeerId, PeerStatus},
+    shared::{MessageType, SharedMessage},
+};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Custom message type of pings and pongs
+pub const KEEPALIVE_MESSAGE_TYPE: &str = "KEEPALIVE";
+
+/// Keepalive settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Time between pings to every tracked peer
+    pub interval: Duration,
+    /// Consecutive unanswered pings after which a peer is dead
+    pub max_missed: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            max_missed: 3,
+        }
+    }
+}
+
+impl KeepaliveConfig {
+    /// Status of a peer that missed `missed` keepalives in a row
+    pub fn status(&self, missed: u32) -> PeerStatus {
+        if missed == 0 {
+            PeerStatus::Alive
+        } else if missed < self.max_missed.max(1) {
+            PeerStatus::Suspect
+        } else {
+            PeerStatus::Dead
+        }
+    }
+}
+
+/// Keepalive frame, carried in the data of a [`KEEPALIVE_MESSAGE_TYPE`] message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Keepalive {
+    Ping { nonce: u64 },
+    Pong { nonce: u64 },
+}
+
+impl Keepalive {
+    /// Read a keepalive frame, or `None` if `message` is not one
+    pub fn from_message(message: &SharedMessage) -> Option<Self> {
+        if message.message_type != MessageType::Custom(KEEPALIVE_MESSAGE_TYPE.to_string()) {
+            return None;
+        }
+        serde_json::from_value(message.data.clone()).ok()
+    }
+
+    /// Wrap into a message
+    pub fn to_message(&self) -> Result<SharedMessage> {
+        SharedMessage::custom(KEEPALIVE_MESSAGE_TYPE, self)
+    }
+}
+
+/// Peers to ping in one keepalive round
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeepaliveRound {
+    /// Every tracked peer, dead ones included
+    pub ping: Vec<PeerId>,
+    /// Peers that turned dead with this round
+    pub died: Vec<PeerId>,
+}
+
+#[derive(Debug, Default)]
+struct Liveness {
+    /// Consecutive keepalives missed
+    missed: u32,
+    /// Whether the last ping is still unanswered
+    awaiting: bool,
+}
+
+/// Liveness of tracked peers, shared between a transport and a node
+#[derive(Debug, Default)]
+pub struct PeerLiveness {
+    config: KeepaliveConfig,
+    peers: Mutex<HashMap<PeerId, Liveness>>,
+}
+
+impl PeerLiveness {
+    pub fn new(config: KeepaliveConfig) -> Self {
+        Self {
+            config,
+            peers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &KeepaliveConfig {
+        &self.config
+    }
+
+    /// Start tracking a peer, alive until it misses a keepalive
+    ///
+    /// A peer already tracked keeps its state.
+    pub fn track(&self, peer_id: &PeerId) {
+        self.lock().entry(peer_id.clone()).or_default();
+    }
+
+    /// Stop tracking a peer
+    pub fn forget(&self, peer_id: &PeerId) {
+        self.lock().remove(peer_id);
+    }
+
+    /// Record a sign of life from a peer, which makes it alive again
+    ///
+    /// Untracked peers are ignored.
+    pub fn heard_from(&self, peer_id: &PeerId) {
+        if let Some(liveness) = self.lock().get_mut(peer_id) {
+            *liveness = Liveness::default();
+        }
+    }
+
+    /// Status of a tracked peer
+    pub fn status(&self, peer_id: &PeerId) -> Option<PeerStatus> {
+        self.lock()
+            .get(peer_id)
+            .map(|liveness| self.config.status(liveness.missed))
+    }
+
+    /// Whether a tracked peer is dead
+    pub fn is_dead(&self, peer_id: &PeerId) -> bool {
+        self.status(peer_id) == Some(PeerStatus::Dead)
+    }
+
+    /// Tracked peers currently dead
+    pub fn dead_peers(&self) -> Vec<PeerId> {
+        self.lock()
+            .iter()
+            .filter(|(_, liveness)| self.config.status(liveness.missed) == PeerStatus::Dead)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect()
+    }
+
+    /// Count the pings left unanswered since the last round and start a new one
+    pub fn next_round(&self) -> KeepaliveRound {
+        let mut round = KeepaliveRound::default();
+        for (peer_id, liveness) in self.lock().iter_mut() {
+            if liveness.awaiting {
+                let was_dead = self.config.status(liveness.missed) == PeerStatus::Dead;
+                liveness.missed = liveness.missed.saturating_add(1);
+                if !was_dead && self.config.status(liveness.missed) == PeerStatus::Dead {
+                    round.died.push(peer_id.clone());
+                }
+            }
+            liveness.awaiting = true;
+            round.ping.push(peer_id.clone());
+        }
+        round
+    }
+
+    // Never held across an await; a poisoned lock still holds valid counters
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PeerId, Liveness>> {
+        self.peers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Transport wrapper pinging peers and tracking their liveness
+#[derive(Debug)]
+pub struct KeepaliveTransport<T> {
+    inner: T,
+    liveness: Arc<PeerLiveness>,
+    next_nonce: AtomicU64,
+    metrics: Arc<NodeMetrics>,
+}
+
+impl<T: Transport> KeepaliveTransport<T> {
+    /// Wrap a transport with default settings
+    pub fn new(inner: T) -> Self {
+        Self::with_config(inner, KeepaliveConfig::default())
+    }
+
+    /// Wrap a transport with custom settings
+    pub fn with_config(inner: T, config: KeepaliveConfig) -> Self {
+        Self::with_liveness(inner, Arc::new(PeerLiveness::new(config)))
+    }
+
+    /// Wrap a transport recording liveness in `liveness`, e.g. a node's
+    pub fn with_liveness(inner: T, liveness: Arc<PeerLiveness>) -> Self {
+        Self {
+            inner,
+            liveness,
+            next_nonce: AtomicU64::new(0),
+            metrics: Arc::new(NodeMetrics::new()),
+        }
+    }
+
+    /// Record keepalive counters in `metrics`, e.g. a node's
+    pub fn with_metrics(mut self, metrics: Arc<NodeMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Get the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the liveness table
+    pub fn liveness(&self) -> &Arc<PeerLiveness> {
+        &self.liveness
+    }
+
+    /// Run one keepalive round: count missed pings and ping every tracked peer
+    ///
+    /// Returns the peers that turned dead with this round. Call this every
+    /// [`KeepaliveConfig::interval`], or use
+    /// [`KeepaliveTransport::spawn_keepalives`] on native targets. A ping that
+    /// fails to send goes unanswered like a lost one.
+    pub async fn ping_peers(&self) -> Vec<PeerId> {
+        let round = self.liveness.next_round();
+        for peer_id in &round.died {
+            self.metrics.incr("keepalive_peers_dead");
+            tracing::info!("Peer {} missed too many keepalives, marking it dead", peer_id);
+        }
+        for peer_id in &round.ping {
+            let nonce = self.next_nonce.fetch_add(1, Ordering::Relaxed);
+            if let Err(e) = self
+                .send_keepalive(peer_id, Keepalive::Ping { nonce })
+                .await
+            {
+                tracing::debug!("Keepalive to {} failed: {}", peer_id, e);
+            }
+        }
+        round.died
+    }
+
+    async fn send_keepalive(&self, to: &PeerId, keepalive: Keepalive) -> Result<()> {
+        self.inner.send(to, &keepalive.to_message()?).await?;
+        self.metrics.incr("keepalives_sent");
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Transport + Send + Sync + 'static> KeepaliveTransport<T> {
+    /// Run [`KeepaliveTransport::ping_peers`] every [`KeepaliveConfig::interval`]
+    pub fn spawn_keepalives(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.liveness.config().interval);
+            loop {
+                ticker.tick().await;
+                self.ping_peers().await;
+            }
+        })
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: Transport + Send + Sync> Transport for KeepaliveTransport<T> {
+    fn local_id(&self) -> &PeerId {
+        self.inner.local_id()
+    }
+
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        self.inner.send(to, message).await
+    }
+
+    /// Receive the next application message, answering pings on the way
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        loop {
+            let (from, message) = self.inner.recv().await?;
+            self.liveness.heard_from(&from);
+            match Keepalive::from_message(&message) {
+                Some(Keepalive::Ping { nonce }) => {
+                    if let Err(e) = self.send_keepalive(&from, Keepalive::Pong { nonce }).await {
+                        tracing::debug!("Failed to answer keepalive from {}: {}", from, e);
+                    }
+                },
+                Some(Keepalive::Pong { .. }) => {},
+                None => return Ok((from, message)),
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! rust-libp2p network backend
+//!
+//! An alternative to the hand-rolled stack: messages travel over gossipsub,
+//! peers are found through Kademlia, and connections are encrypted with noise
+//! and multiplexed with yamux. The swarm runs on its own task; the
+//! [`Libp2pTransport`] handle talks to it over channels and implements the same
+//! [`Transport`] trait as the other backends, so application objects do not
+//! care which stack carries their messages.
+
+use crate::{
+    error::{ChaincraftError, NetworkError, Result},
+    network::{transport::Transport, PeerId},
+    shared::SharedMessage,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use libp2p::{
+    gossipsub, identify, identity, kad, noise, ping, swarm::SwarmEvent, tcp, yamux, Multiaddr,
+    Swarm,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+/// Libp2p backend settings
+#[derive(Debug, Clone)]
+pub struct Libp2pConfig {
+    /// Address to listen on
+    pub listen_addr: Multiaddr,
+    /// Gossipsub topic carrying Chaincraft messages
+    pub topic: String,
+    /// Gossipsub heartbeat interval
+    pub heartbeat_interval: Duration,
+    /// Close connections idle for this long
+    pub idle_connection_timeout: Duration,
+}
+
+impl Default for Libp2pConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: "/ip4/0.0.0.0/tcp/0".parse().expect("valid multiaddr"),
+            topic: "chaincraft/messages/1".to_string(),
+            heartbeat_interval: Duration::from_secs(1),
+            idle_connection_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Frame published on the gossip topic; `to` is `None` for broadcasts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GossipFrame {
+    from: PeerId,
+    to: Option<PeerId>,
+    message: SharedMessage,
+}
+
+// Kept in its own module: the derive expands to code naming `Result`, which
+// would otherwise resolve to the crate alias
+mod behaviour {
+    use libp2p::{gossipsub, identify, kad, ping, swarm::NetworkBehaviour};
+
+    #[derive(NetworkBehaviour)]
+    pub(super) struct ChaincraftBehaviour {
+        pub(super) gossipsub: gossipsub::Behaviour,
+        pub(super) kademlia: kad::Behaviour<kad::store::MemoryStore>,
+        pub(super) identify: identify::Behaviour,
+        pub(super) ping: ping::Behaviour,
+    }
+}
+
+use behaviour::{ChaincraftBehaviour, ChaincraftBehaviourEvent};
+
+enum Command {
+    Dial(Multiaddr, oneshot::Sender<Result<()>>),
+    Publish(Vec<u8>, oneshot::Sender<Result<()>>),
+    ConnectedPeers(oneshot::Sender<Vec<libp2p::PeerId>>),
+    RoutingTable(oneshot::Sender<Vec<libp2p::PeerId>>),
+    Bootstrap(oneshot::Sender<Result<()>>),
+}
+
+fn libp2p_error(error: impl std::fmt::Display) -> ChaincraftError {
+    ChaincraftError::Network(NetworkError::InvalidMessage {
+        reason: format!("libp2p: {}", error),
+    })
+}
+
+/// Handle to a running libp2p swarm
+pub struct Libp2pTransport {
+    local_id: PeerId,
+    libp2p_id: libp2p::PeerId,
+    listen_addrs: Vec<Multiaddr>,
+    commands: mpsc::UnboundedSender<Command>,
+    inbox: Mutex<mpsc::UnboundedReceiver<(PeerId, SharedMessage)>>,
+    swarm_task: tokio::task::JoinHandle<()>,
+}
+
+impl Libp2pTransport {
+    /// Start a swarm with a fresh libp2p identity
+    pub async fn start(local_id: PeerId, config: Libp2pConfig) -> Result<Self> {
+        Self::start_with_keypair(local_id, identity::Keypair::generate_ed25519(), config).await
+    }
+
+    /// Start a swarm with the given libp2p identity
+    pub async fn start_with_keypair(
+        local_id: PeerId,
+        keypair: identity::Keypair,
+        config: Libp2pConfig,
+    ) -> Result<Self> {
+        let mut swarm = build_swarm(keypair, &config)?;
+        let topic = gossipsub::IdentTopic::new(config.topic.clone());
+        swarm
+            .behaviour_mut()
+            .gossipsub
+            .subscribe(&topic)
+            .map_err(libp2p_error)?;
+        swarm
+            .listen_on(config.listen_addr.clone())
+            .map_err(libp2p_error)?;
+
+        // Wait until the listener is up so callers can hand out our address
+        let listen_addr = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let SwarmEvent::NewListenAddr { address, .. } = swarm.select_next_some().await {
+                    return address;
+                }
+            }
+        })
+        .await
+        .map_err(|_| {
+            ChaincraftError::Network(NetworkError::Timeout {
+                duration: Duration::from_secs(5),
+            })
+        })?;
+
+        let libp2p_id = *swarm.local_peer_id();
+        let (command_sender, command_receiver) = mpsc::unbounded_channel();
+        let (inbox_sender, inbox_receiver) = mpsc::unbounded_channel();
+        let swarm_task =
+            tokio::spawn(run_swarm(swarm, topic, local_id.clone(), command_receiver, inbox_sender));
+
+        Ok(Self {
+            local_id,
+            libp2p_id,
+            listen_addrs: vec![listen_addr],
+            commands: command_sender,
+            inbox: Mutex::new(inbox_receiver),
+            swarm_task,
+        })
+    }
+
+    /// libp2p identity of this endpoint
+    pub fn libp2p_peer_id(&self) -> libp2p::PeerId {
+        self.libp2p_id
+    }
+
+    /// Addresses other swarms can dial, including our libp2p identity
+    pub fn listen_addrs(&self) -> Vec<Multiaddr> {
+        self.listen_addrs
+            .iter()
+            .map(|addr| {
+                addr.clone()
+                    .with(libp2p::multiaddr::Protocol::P2p(self.libp2p_id))
+            })
+            .collect()
+    }
+
+    async fn request<T>(&self, command: impl FnOnce(oneshot::Sender<T>) -> Command) -> Result<T> {
+        let (sender, receiver) = oneshot::channel();
+        self.commands
+            .send(command(sender))
+            .map_err(|_| libp2p_error("swarm stopped"))?;
+        receiver.await.map_err(|_| libp2p_error("swarm stopped"))
+    }
+
+    /// Connect to another swarm
+    pub async fn dial(&self, addr: Multiaddr) -> Result<()> {
+        self.request(|reply| Command::Dial(addr, reply)).await?
+    }
+
+    /// Publish a message to every peer on the topic
+    pub async fn broadcast(&self, message: &SharedMessage) -> Result<()> {
+        self.publish(GossipFrame {
+            from: self.local_id.clone(),
+            to: None,
+            message: message.clone(),
+        })
+        .await
+    }
+
+    async fn publish(&self, frame: GossipFrame) -> Result<()> {
+        let bytes = serde_json::to_vec(&frame)?;
+        self.request(|reply| Command::Publish(bytes, reply)).await?
+    }
+
+    /// libp2p peers we currently hold a connection to
+    pub async fn connected_peers(&self) -> Result<Vec<libp2p::PeerId>> {
+        self.request(Command::ConnectedPeers).await
+    }
+
+    /// Peers known to the Kademlia routing table
+    pub async fn known_peers(&self) -> Result<Vec<libp2p::PeerId>> {
+        self.request(Command::RoutingTable).await
+    }
+
+    /// Start a Kademlia bootstrap to discover more peers
+    pub async fn bootstrap(&self) -> Result<()> {
+        self.request(Command::Bootstrap).await?
+    }
+}
+
+impl Drop for Libp2pTransport {
+    fn drop(&mut self) {
+        self.swarm_task.abort();
+    }
+}
+
+#[async_trait]
+impl Transport for Libp2pTransport {
+    fn local_id(&self) -> &PeerId {
+        &self.local_id
+    }
+
+    /// Gossipsub has no unicast, so the frame is published and only the
+    /// addressed peer delivers it
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        self.publish(GossipFrame {
+            from: self.local_id.clone(),
+            to: Some(to.clone()),
+            message: message.clone(),
+        })
+        .await
+    }
+
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        self.inbox
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))
+    }
+}
+
+fn build_swarm(
+    keypair: identity::Keypair,
+    config: &Libp2pConfig,
+) -> Result<Swarm<ChaincraftBehaviour>> {
+    let heartbeat_interval = config.heartbeat_interval;
+    let swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
+        .with_tokio()
+        .with_tcp(tcp::Config::default(), noise::Config::new, yamux::Config::default)
+        .map_err(libp2p_error)?
+        .with_behaviour(|key| {
+            let peer_id = key.public().to_peer_id();
+            let gossipsub_config = gossipsub::ConfigBuilder::default()
+                .heartbeat_interval(heartbeat_interval)
+                .validation_mode(gossipsub::ValidationMode::Strict)
+                .build()?;
+            let gossipsub = gossipsub::Behaviour::new(
+                gossipsub::MessageAuthenticity::Signed(key.clone()),
+                gossipsub_config,
+            )?;
+
+            let mut kademlia = kad::Behaviour::new(peer_id, kad::store::MemoryStore::new(peer_id));
+            kademlia.set_mode(Some(kad::Mode::Server));
+
+            Ok(ChaincraftBehaviour {
+                gossipsub,
+                kademlia,
+                identify: identify::Behaviour::new(identify::Config::new(
+                    "/chaincraft/1.0.0".to_string(),
+                    key.public(),
+                )),
+                ping: ping::Behaviour::default(),
+            })
+        })
+        .map_err(libp2p_error)?
+        .with_swarm_config(|c| c.with_idle_connection_timeout(config.idle_connection_timeout))
+        .build();
+    Ok(swarm)
+}
+
+async fn run_swarm(
+    mut swarm: Swarm<ChaincraftBehaviour>,
+    topic: gossipsub::IdentTopic,
+    local_id: PeerId,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    inbox: mpsc::UnboundedSender<(PeerId, SharedMessage)>,
+) {
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(command) => handle_command(&mut swarm, &topic, command),
+                None => break,
+            },
+            event = swarm.select_next_some() => match event {
+                SwarmEvent::Behaviour(ChaincraftBehaviourEvent::Gossipsub(
+                    gossipsub::Event::Message { message, .. },
+                )) => {
+                    let Ok(frame) = serde_json::from_slice::<GossipFrame>(&message.data) else {
+                        tracing::debug!("Dropping undecodable gossip frame");
+                        continue;
+                    };
+                    let for_us = frame.to.as_ref().is_none_or(|to| to == &local_id);
+                    if for_us && frame.message.verify_hash() {
+                        let _ = inbox.send((frame.from, frame.message));
+                    }
+                },
+                SwarmEvent::Behaviour(ChaincraftBehaviourEvent::Identify(
+                    identify::Event::Received { peer_id, info, .. },
+                )) => {
+                    for addr in info.listen_addrs {
+                        swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
+                    }
+                },
+                _ => {},
+            },
+        }
+    }
+}
+
+fn handle_command(
+    swarm: &mut Swarm<ChaincraftBehaviour>,
+    topic: &gossipsub::IdentTopic,
+    command: Command,
+) {
+    match command {
+        Command::Dial(addr, reply) => {
+            let _ = reply.send(swarm.dial(addr).map_err(libp2p_error));
+        },
+        Command::Publish(bytes, reply) => {
+            let result = swarm
+                .behaviour_mut()
+                .gossipsub
+                .publish(topic.clone(), bytes)
+                .map(|_| ())
+                .map_err(libp2p_error);
+            let _ = reply.send(result);
+        },
+        Command::ConnectedPeers(reply) => {
+            let _ = reply.send(swarm.connected_peers().copied().collect());
+        },
+        Command::RoutingTable(reply) => {
+            let peers = swarm
+                .behaviour_mut()
+                .kademlia
+                .kbuckets()
+                .flat_map(|bucket| {
+                    bucket
+                        .iter()
+                        .map(|entry| *entry.node.key.preimage())
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let _ = reply.send(peers);
+        },
+        Command::Bootstrap(reply) => {
+            let result = swarm
+                .behaviour_mut()
+                .kademlia
+                .bootstrap()
+                .map(|_| ())
+                .map_err(libp2p_error);
+            let _ = reply.send(result);
+        },
+    }
+}
@@ -0,0 +1,181 @@
+//! Simulated link conditions for the in-memory transport
+//!
+//! Every directed link of an [`InMemoryNetwork`](super::transport::InMemoryNetwork)
+//! follows a [`LinkProfile`]: a one-way latency distribution, extra uniform
+//! jitter, a drop rate and a reorder rate. Profiles can be set per peer pair
+//! and changed while the network is running.
+//!
+//! Frames on a link arrive in the order they were sent unless they are picked
+//! for reordering, in which case they skip the queue like `netem`'s reorder:
+//! they are delivered at once, ahead of frames still in flight. Each link
+//! draws from its own RNG, derived from the network seed and the two peer
+//! ids, so a seeded simulation makes the same decisions whatever order the
+//! tasks sending on different links run in.
+//!
+//! Delays need timers, so in the browser build links only drop frames.
+
+use crate::network::PeerId;
+use crate::rng::RngProvider;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// One-way latency of a link
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Latency {
+    Constant(Duration),
+    /// Uniform between the two bounds, inclusive
+    Uniform {
+        min: Duration,
+        max: Duration,
+    },
+    /// Normal distribution, cut off at zero
+    Normal {
+        mean: Duration,
+        std_dev: Duration,
+    },
+}
+
+impl Latency {
+    fn sample(&self, rng: &RngProvider) -> Duration {
+        match *self {
+            Latency::Constant(latency) => latency,
+            Latency::Uniform { min, max } if max > min => min + (max - min).mul_f64(unit(rng)),
+            Latency::Uniform { min, .. } => min,
+            Latency::Normal { mean, std_dev } => {
+                // Box-Muller transform
+                let u1 = 1.0 - unit(rng);
+                let u2 = unit(rng);
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                Duration::from_secs_f64((mean.as_secs_f64() + z * std_dev.as_secs_f64()).max(0.0))
+            },
+        }
+    }
+}
+
+/// Conditions applied to frames on one directed link
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkProfile {
+    pub latency: Latency,
+    /// Extra delay drawn uniformly from zero to this value, per frame
+    pub jitter: Duration,
+    /// Probability of losing a frame
+    pub drop_rate: f64,
+    /// Probability of a frame skipping ahead of frames sent before it
+    pub reorder_rate: f64,
+}
+
+impl LinkProfile {
+    /// Instant, lossless, ordered delivery
+    pub fn perfect() -> Self {
+        Self {
+            latency: Latency::Constant(Duration::ZERO),
+            jitter: Duration::ZERO,
+            drop_rate: 0.0,
+            reorder_rate: 0.0,
+        }
+    }
+
+    /// Constant one-way latency
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Latency::Constant(latency);
+        self
+    }
+
+    /// Constant latency of half the given round-trip time each way
+    pub fn with_rtt(self, rtt: Duration) -> Self {
+        self.with_latency(rtt / 2)
+    }
+
+    pub fn with_latency_distribution(mut self, latency: Latency) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_drop_rate(mut self, drop_rate: f64) -> Self {
+        self.drop_rate = drop_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_reorder_rate(mut self, reorder_rate: f64) -> Self {
+        self.reorder_rate = reorder_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Whether frames go through untouched
+    pub fn is_perfect(&self) -> bool {
+        *self == Self::perfect()
+    }
+
+    /// Decide what happens to the next frame
+    pub(crate) fn sample(&self, rng: &RngProvider) -> LinkFate {
+        if self.drop_rate > 0.0 && unit(rng) < self.drop_rate {
+            return LinkFate::Dropped;
+        }
+        if self.reorder_rate > 0.0 && unit(rng) < self.reorder_rate {
+            return LinkFate::Delivered {
+                delay: Duration::ZERO,
+                reordered: true,
+            };
+        }
+        let mut delay = self.latency.sample(rng);
+        if !self.jitter.is_zero() {
+            delay += self.jitter.mul_f64(unit(rng));
+        }
+        LinkFate::Delivered {
+            delay,
+            reordered: false,
+        }
+    }
+}
+
+impl Default for LinkProfile {
+    fn default() -> Self {
+        Self::perfect()
+    }
+}
+
+/// What a link does with one frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LinkFate {
+    Dropped,
+    Delivered { delay: Duration, reordered: bool },
+}
+
+/// Frame counters of one directed link
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Frames handed to the link
+    pub sent: u64,
+    /// Frames the link lost
+    pub dropped: u64,
+    /// Frames allowed to overtake earlier ones
+    pub reordered: u64,
+}
+
+/// Randomness for the link from `from` to `to`
+pub(crate) fn link_rng(seed: Option<u64>, from: &PeerId, to: &PeerId) -> RngProvider {
+    match seed {
+        Some(seed) => {
+            let digest = Sha256::new()
+                .chain_update(seed.to_le_bytes())
+                .chain_update(from.to_string())
+                .chain_update(to.to_string())
+                .finalize();
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&digest[..8]);
+            RngProvider::seeded(u64::from_le_bytes(bytes))
+        },
+        None => RngProvider::os(),
+    }
+}
+
+/// Uniform float in [0, 1)
+fn unit(rng: &RngProvider) -> f64 {
+    // 53 random bits give a uniform float in [0, 1)
+    (rng.next_u64_value() >> 11) as f64 / (1u64 << 53) as f64
+}
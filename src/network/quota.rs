@@ -0,0 +1,201 @@
+//! Per-peer inbound message quotas
+//!
+//! Every message received from a peer is admitted through an [`InboundQuota`],
+//! which tracks the peer's message rate over a sliding window and the ratio of
+//! invalid messages it has sent. Peers exceeding their rate are throttled;
+//! peers that keep sending invalid data are disconnected.
+
+use crate::clock::Clock;
+use crate::network::PeerId;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// Inbound quota settings
+#[derive(Debug, Clone)]
+pub struct QuotaConfig {
+    /// Maximum messages accepted from a single peer per window
+    pub max_messages_per_window: u32,
+    /// Length of the rate window
+    pub window: Duration,
+    /// Invalid/total ratio above which a peer is disconnected
+    pub max_invalid_ratio: f64,
+    /// Minimum messages seen before the invalid ratio is enforced
+    pub min_messages_for_ratio: u64,
+    /// Number of throttled windows tolerated before disconnecting
+    pub max_throttled_windows: u32,
+    /// Discovery score penalty applied when a peer is throttled
+    pub throttle_penalty: i64,
+    /// Discovery score penalty applied when a peer is disconnected
+    pub disconnect_penalty: i64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_messages_per_window: 200,
+            window: Duration::from_secs(1),
+            max_invalid_ratio: 0.5,
+            min_messages_for_ratio: 10,
+            max_throttled_windows: 3,
+            throttle_penalty: 5,
+            disconnect_penalty: 100,
+        }
+    }
+}
+
+/// Enforcement decision for an inbound message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaDecision {
+    /// Process the message
+    Accept,
+    /// Drop the message, the peer is over its rate
+    Throttle,
+    /// Drop the message and disconnect the peer
+    Disconnect,
+}
+
+/// Traffic counters for one peer
+#[derive(Debug, Clone)]
+pub struct PeerTraffic {
+    /// Start of the current rate window
+    pub window_start: DateTime<Utc>,
+    /// Messages received in the current window
+    pub window_count: u32,
+    /// Messages received overall
+    pub total: u64,
+    /// Invalid messages received overall
+    pub invalid: u64,
+    /// Windows in which the peer exceeded its rate
+    pub throttled_windows: u32,
+}
+
+impl PeerTraffic {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            window_start: now,
+            window_count: 0,
+            total: 0,
+            invalid: 0,
+            throttled_windows: 0,
+        }
+    }
+
+    /// Fraction of messages from this peer that were invalid
+    pub fn invalid_ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.invalid as f64 / self.total as f64
+        }
+    }
+}
+
+/// Tracks inbound traffic per peer and decides how to treat it
+#[derive(Debug)]
+pub struct InboundQuota {
+    config: RwLock<QuotaConfig>,
+    peers: Mutex<HashMap<PeerId, PeerTraffic>>,
+    clock: Clock,
+}
+
+impl Default for InboundQuota {
+    fn default() -> Self {
+        Self::new(QuotaConfig::default())
+    }
+}
+
+impl InboundQuota {
+    /// Create a quota tracker with the given settings
+    pub fn new(config: QuotaConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            peers: Mutex::new(HashMap::new()),
+            clock: Clock::system(),
+        }
+    }
+
+    /// Get the quota settings
+    pub fn config(&self) -> QuotaConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the settings; counters of the current windows are kept
+    pub fn set_config(&self, config: QuotaConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// Measure rate windows on `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Count a message from `peer` against its rate and decide whether to process it
+    pub fn admit(&self, peer: &PeerId) -> QuotaDecision {
+        let config = self.config();
+        let now = self.clock.now();
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        let traffic = peers
+            .entry(peer.clone())
+            .or_insert_with(|| PeerTraffic::new(now));
+
+        let elapsed = (now - traffic.window_start).to_std().unwrap_or_default();
+        if elapsed >= config.window {
+            traffic.window_start = now;
+            traffic.window_count = 0;
+        }
+
+        traffic.window_count += 1;
+        traffic.total += 1;
+
+        if traffic.window_count == config.max_messages_per_window + 1 {
+            traffic.throttled_windows += 1;
+        }
+        if traffic.window_count > config.max_messages_per_window {
+            if traffic.throttled_windows > config.max_throttled_windows {
+                return QuotaDecision::Disconnect;
+            }
+            return QuotaDecision::Throttle;
+        }
+
+        QuotaDecision::Accept
+    }
+
+    /// Record whether an admitted message from `peer` turned out to be valid
+    pub fn report(&self, peer: &PeerId, valid: bool) -> QuotaDecision {
+        let config = self.config();
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(traffic) = peers.get_mut(peer) else {
+            return QuotaDecision::Accept;
+        };
+
+        if !valid {
+            traffic.invalid += 1;
+        }
+
+        if traffic.total >= config.min_messages_for_ratio
+            && traffic.invalid_ratio() > config.max_invalid_ratio
+        {
+            QuotaDecision::Disconnect
+        } else {
+            QuotaDecision::Accept
+        }
+    }
+
+    /// Get the traffic counters for a peer
+    pub fn traffic(&self, peer: &PeerId) -> Option<PeerTraffic> {
+        let peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        peers.get(peer).cloned()
+    }
+
+    /// Forget a peer's counters (e.g. after it has been disconnected)
+    pub fn forget(&self, peer: &PeerId) {
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        peers.remove(peer);
+    }
+}
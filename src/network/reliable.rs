@@ -0,0 +1,292 @@
+//! Reliable point-to-point delivery
+//!
+//! [`ReliableTransport`] wraps any [`Transport`] and adds acknowledgements:
+//! every message sent to a peer stays queued until the peer acks its hash.
+//! Unacknowledged messages are resent with exponential backoff, and once the
+//! attempt limit is reached the message is dropped and reported as
+//! [`DeliveryEvent::DeliveryFailed`]. Receivers ack every message, including
+//! retransmissions, but only hand each one to the application once.
+
+use crate::{
+    error::Result,
+    network::{transport::Transport, PeerId},
+    shared::{MessageType, SharedMessage},
+};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Custom message type used for acknowledgements
+pub const ACK_MESSAGE_TYPE: &str = "ACK";
+
+/// Data of an [`ACK_MESSAGE_TYPE`] message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Ack {
+    /// Hash of the message received
+    pub hash: String,
+}
+
+/// Number of delivered message hashes remembered for duplicate suppression
+const SEEN_CAPACITY: usize = 4096;
+
+/// Reliable delivery settings
+#[derive(Debug, Clone)]
+pub struct ReliableConfig {
+    /// Delay before the first retransmission
+    pub initial_backoff: Duration,
+    /// Upper bound for the retransmission delay
+    pub max_backoff: Duration,
+    /// Total send attempts before a message is reported as failed
+    pub max_attempts: u32,
+}
+
+impl Default for ReliableConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            max_attempts: 5,
+        }
+    }
+}
+
+impl ReliableConfig {
+    /// Delay before the next attempt after `attempts` sends
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Outcome of a reliable send
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryEvent {
+    /// The peer acknowledged the message
+    Delivered { peer_id: PeerId, hash: String },
+    /// The message was not acknowledged within the attempt limit
+    DeliveryFailed {
+        peer_id: PeerId,
+        hash: String,
+        attempts: u32,
+    },
+}
+
+#[derive(Debug)]
+struct PendingDelivery {
+    message: SharedMessage,
+    attempts: u32,
+    next_attempt: Instant,
+}
+
+#[derive(Debug, Default)]
+struct SeenMessages {
+    order: VecDeque<(PeerId, String)>,
+    set: HashSet<(PeerId, String)>,
+}
+
+impl SeenMessages {
+    /// Record a message, returning false if it was already seen
+    fn insert(&mut self, key: (PeerId, String)) -> bool {
+        if !self.set.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Transport wrapper adding acknowledgements and retransmission
+#[derive(Debug)]
+pub struct ReliableTransport<T> {
+    inner: T,
+    config: ReliableConfig,
+    pending: Mutex<HashMap<(PeerId, String), PendingDelivery>>,
+    seen: Mutex<SeenMessages>,
+    events: broadcast::Sender<DeliveryEvent>,
+}
+
+impl<T: Transport> ReliableTransport<T> {
+    /// Wrap a transport with default settings
+    pub fn new(inner: T) -> Self {
+        Self::with_config(inner, ReliableConfig::default())
+    }
+
+    /// Wrap a transport with custom settings
+    pub fn with_config(inner: T, config: ReliableConfig) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            inner,
+            config,
+            pending: Mutex::new(HashMap::new()),
+            seen: Mutex::new(SeenMessages::default()),
+            events,
+        }
+    }
+
+    /// Get the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the delivery settings
+    pub fn config(&self) -> &ReliableConfig {
+        &self.config
+    }
+
+    /// Subscribe to delivery outcomes
+    pub fn subscribe(&self) -> broadcast::Receiver<DeliveryEvent> {
+        self.events.subscribe()
+    }
+
+    /// Number of messages awaiting acknowledgement
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Whether a message to a peer is still awaiting acknowledgement
+    pub fn is_pending(&self, to: &PeerId, hash: &str) -> bool {
+        self.pending
+            .lock()
+            .unwrap()
+            .contains_key(&(to.clone(), hash.to_string()))
+    }
+
+    /// Resend messages whose backoff has elapsed and fail those out of attempts
+    ///
+    /// Returns the number of messages resent. Call this periodically, or use
+    /// [`ReliableTransport::spawn_retries`] on native targets.
+    pub async fn process_retries(&self) -> usize {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            let mut failed = Vec::new();
+            for (key, entry) in pending.iter_mut() {
+                if entry.next_attempt > now {
+                    continue;
+                }
+                if entry.attempts >= self.config.max_attempts {
+                    failed.push(key.clone());
+                    continue;
+                }
+                entry.attempts += 1;
+                entry.next_attempt = now + self.config.backoff(entry.attempts);
+                due.push((key.0.clone(), entry.message.clone()));
+            }
+            for key in failed {
+                if let Some(entry) = pending.remove(&key) {
+                    let _ = self.events.send(DeliveryEvent::DeliveryFailed {
+                        peer_id: key.0,
+                        hash: key.1,
+                        attempts: entry.attempts,
+                    });
+                }
+            }
+        }
+
+        for (to, message) in &due {
+            if let Err(e) = self.inner.send(to, message).await {
+                tracing::debug!("Retransmission to {} failed: {}", to, e);
+            }
+        }
+        due.len()
+    }
+
+    fn acknowledge(&self, from: &PeerId, ack: &SharedMessage) {
+        let Ok(Ack { hash }) = serde_json::from_value(ack.data.clone()) else {
+            return;
+        };
+        let key = (from.clone(), hash);
+        if self.pending.lock().unwrap().remove(&key).is_some() {
+            let _ = self.events.send(DeliveryEvent::Delivered {
+                peer_id: key.0,
+                hash: key.1,
+            });
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Transport + Send + Sync + 'static> ReliableTransport<T> {
+    /// Run [`ReliableTransport::process_retries`] on an interval
+    pub fn spawn_retries(
+        self: std::sync::Arc<Self>,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.process_retries().await;
+            }
+        })
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: Transport + Send + Sync> Transport for ReliableTransport<T> {
+    fn local_id(&self) -> &PeerId {
+        self.inner.local_id()
+    }
+
+    /// Queue a message until it is acknowledged
+    ///
+    /// A failed first attempt is not an error: the message stays queued and
+    /// is retried, with the final outcome reported through [`Self::subscribe`].
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        self.pending.lock().unwrap().insert(
+            (to.clone(), message.hash.clone()),
+            PendingDelivery {
+                message: message.clone(),
+                attempts: 1,
+                next_attempt: Instant::now() + self.config.backoff(1),
+            },
+        );
+        if let Err(e) = self.inner.send(to, message).await {
+            tracing::debug!("Send to {} failed, will retry: {}", to, e);
+        }
+        Ok(())
+    }
+
+    /// Receive the next application message, handling acks on the way
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        loop {
+            let (from, message) = self.inner.recv().await?;
+            if message.message_type == MessageType::Custom(ACK_MESSAGE_TYPE.to_string()) {
+                self.acknowledge(&from, &message);
+                continue;
+            }
+
+            let ack = SharedMessage::custom(
+                ACK_MESSAGE_TYPE,
+                Ack {
+                    hash: message.hash.clone(),
+                },
+            )?;
+            if let Err(e) = self.inner.send(&from, &ack).await {
+                tracing::debug!("Failed to ack {} to {}: {}", message.hash, from, e);
+            }
+
+            let first_delivery = self
+                .seen
+                .lock()
+                .unwrap()
+                .insert((from.clone(), message.hash.clone()));
+            if first_delivery {
+                return Ok((from, message));
+            }
+        }
+    }
+}
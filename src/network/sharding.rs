@@ -0,0 +1,157 @@
+//! Gossip topic sharding
+//!
+//! Under full replication every node receives and stores every message. For
+//! scalability experiments an application's messages can instead be split
+//! over `shards` gossip topics by a key in the message data, such as a
+//! chatroom name or the first characters of an account. Each node subscribes
+//! to some of the shards: it is only sent, and only stores, the messages of
+//! those shards. Messages without the key go to an unsharded topic that every
+//! node follows.
+//!
+//! Nodes learn which shards a peer follows from its sync announcements.
+//! [`ShardingStats`] compares the bytes a node relays with what full
+//! replication would have sent.
+
+use crate::index::{IndexKey, IndexSpec};
+use crate::shared::SharedMessage;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Name of the message index on shard numbers, declared by sharded nodes
+pub const SHARD_INDEX: &str = "gossip_shard";
+
+/// How an application's messages are split over gossip topics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardingConfig {
+    /// Application name, used in topic names
+    pub app: String,
+    /// Number of shard topics
+    pub shards: u32,
+    /// JSON pointer to the shard key in the message data, e.g. `/room`
+    pub key: String,
+    /// Shard by the first `prefix` characters of the key only
+    pub prefix: Option<usize>,
+}
+
+impl ShardingConfig {
+    pub fn new(app: impl Into<String>, shards: u32, key: impl Into<String>) -> Self {
+        Self {
+            app: app.into(),
+            shards: shards.max(1),
+            key: key.into(),
+            prefix: None,
+        }
+    }
+
+    /// Keep keys sharing their first `len` characters in one shard
+    pub fn with_prefix(mut self, len: usize) -> Self {
+        self.prefix = Some(len);
+        self
+    }
+
+    /// Topic a message is gossiped on
+    pub fn topic(&self, message: &SharedMessage) -> GossipTopic {
+        let key = match message.data.pointer(&self.key) {
+            Some(Value::String(key)) => key.clone(),
+            Some(Value::Number(key)) => key.to_string(),
+            _ => return GossipTopic::Unsharded,
+        };
+        let key = match self.prefix {
+            Some(len) => key.chars().take(len).collect(),
+            None => key,
+        };
+        GossipTopic::Shard(self.shard_of(&key))
+    }
+
+    /// Shard of a key, stable across nodes and runs
+    pub fn shard_of(&self, key: &str) -> u32 {
+        let digest = Sha256::digest(key.as_bytes());
+        let mut head = [0u8; 8];
+        head.copy_from_slice(&digest[..8]);
+        (u64::from_be_bytes(head) % self.shards as u64) as u32
+    }
+
+    /// Index of stored messages by shard number
+    pub fn index(&self) -> IndexSpec {
+        let config = self.clone();
+        IndexSpec::new(SHARD_INDEX, move |message| match config.topic(message) {
+            GossipTopic::Shard(shard) => vec![IndexKey::Number(shard as u64)],
+            GossipTopic::Unsharded => Vec::new(),
+        })
+    }
+}
+
+/// Gossip topic of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GossipTopic {
+    /// Followed by every node
+    Unsharded,
+    Shard(u32),
+}
+
+impl GossipTopic {
+    /// Topic name for a pub/sub transport, e.g. `chat/shard/3`
+    pub fn name(&self, app: &str) -> String {
+        format!("{}/{}", app, self)
+    }
+}
+
+impl fmt::Display for GossipTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GossipTopic::Unsharded => write!(f, "all"),
+            GossipTopic::Shard(shard) => write!(f, "shard/{}", shard),
+        }
+    }
+}
+
+/// Shards a node follows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ShardSubscription {
+    /// Every shard, i.e. full replication
+    #[default]
+    All,
+    Only(BTreeSet<u32>),
+}
+
+impl ShardSubscription {
+    pub fn only(shards: impl IntoIterator<Item = u32>) -> Self {
+        ShardSubscription::Only(shards.into_iter().collect())
+    }
+
+    /// Whether messages on `topic` are followed
+    pub fn includes(&self, topic: GossipTopic) -> bool {
+        match (self, topic) {
+            (ShardSubscription::All, _) | (_, GossipTopic::Unsharded) => true,
+            (ShardSubscription::Only(shards), GossipTopic::Shard(shard)) => shards.contains(&shard),
+        }
+    }
+
+    /// Followed shards, `None` for all of them
+    pub fn shards(&self) -> Option<Vec<u32>> {
+        match self {
+            ShardSubscription::All => None,
+            ShardSubscription::Only(shards) => Some(shards.iter().copied().collect()),
+        }
+    }
+}
+
+/// Relay traffic of a sharded node compared to full replication
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardingStats {
+    /// Bytes relays would have sent to every peer
+    pub full_bytes: u64,
+    /// Bytes sent to the peers following each message's shard
+    pub sharded_bytes: u64,
+    /// Inbound messages on shards the node does not follow
+    pub dropped: u64,
+}
+
+impl ShardingStats {
+    /// Fraction of full replication traffic saved, if anything was relayed
+    pub fn reduction(&self) -> Option<f64> {
+        (self.full_bytes > 0).then(|| 1.0 - self.sharded_bytes as f64 / self.full_bytes as f64)
+    }
+}
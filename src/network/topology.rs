@@ -0,0 +1,229 @@
+//! Snapshots of the overlay network
+//!
+//! The overlay students build rarely matches the one they drew: peers fail to
+//! connect, discovery links the wrong nodes, a node ends up isolated. [`crawl`]
+//! starts from one node and asks every node it can reach for its peers over
+//! `Discovery/Topology`, following the reported addresses until no new node
+//! turns up. The [`TopologySnapshot`] it returns holds the adjacency as each
+//! node reported it and serializes to JSON or, with
+//! [`TopologySnapshot::to_dot`], to Graphviz. `chaincraft-cli topology --peer
+//! host:port` prints either.
+//!
+//! Links are directed: an edge from A to B means A counts B among its peers.
+//! Nodes that were reported but could not be asked are kept, marked
+//! unreachable, with the error.
+
+use crate::{
+    error::{ChaincraftError, NetworkError, Result},
+    network::grpc::GrpcClient,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// How far and how patiently to crawl
+#[derive(Debug, Clone)]
+pub struct TopologyConfig {
+    /// Stop asking new nodes once this many were found
+    pub max_nodes: usize,
+    /// Limit on connecting to and asking each node
+    pub timeout: Duration,
+}
+
+impl Default for TopologyConfig {
+    fn default() -> Self {
+        Self {
+            max_nodes: 256,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl TopologyConfig {
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes.max(1);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// A node of the overlay and the peers it reported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TopologyNode {
+    /// Address of its gRPC transport
+    pub address: String,
+    /// Ids of its peers, sorted; empty if it could not be asked
+    pub peers: Vec<String>,
+    /// Why it could not be asked, if it could not
+    pub error: Option<String>,
+}
+
+impl TopologyNode {
+    pub fn is_reachable(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Adjacency of the overlay as its nodes reported it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TopologySnapshot {
+    pub taken_at: DateTime<Utc>,
+    /// Every node found, by id
+    pub nodes: BTreeMap<String, TopologyNode>,
+}
+
+impl TopologySnapshot {
+    /// Directed links, from a node to each peer it reported
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        self.nodes
+            .iter()
+            .flat_map(|(id, node)| {
+                node.peers
+                    .iter()
+                    .map(move |peer| (id.as_str(), peer.as_str()))
+            })
+            .collect()
+    }
+
+    /// Nodes that were reported but could not be asked
+    pub fn unreachable(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| !node.is_reachable())
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    /// Graphviz description of the overlay; unreachable nodes are dashed
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph chaincraft {\n");
+        for (id, node) in &self.nodes {
+            let style = if node.is_reachable() {
+                "solid"
+            } else {
+                "dashed"
+            };
+            dot.push_str(&format!(
+                "  \"{}\" [label=\"{}\\n{}\", style={}];\n",
+                id,
+                short_id(id),
+                node.address,
+                style
+            ));
+        }
+        for (from, to) in self.edges() {
+            dot.push_str(&format!("  \"{}\" -> \"{}\";\n", from, to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl fmt::Display for TopologySnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} nodes, {} links", self.nodes.len(), self.edges().len())?;
+        let unreachable = self.unreachable().len();
+        if unreachable > 0 {
+            write!(f, ", {} unreachable", unreachable)?;
+        }
+        Ok(())
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+/// Crawl the overlay from the node at `seed` (`host:port` of its gRPC transport)
+///
+/// Fails only if the seed itself cannot be asked.
+pub async fn crawl(seed: &str, config: &TopologyConfig) -> Result<TopologySnapshot> {
+    let taken_at = Utc::now();
+    let seed_address = resolve(seed).await?;
+    let (seed_id, peers) = ask(seed_address, config).await?;
+
+    let mut found = BTreeSet::from([seed_id.clone()]);
+    let mut queue = VecDeque::new();
+    let mut nodes = BTreeMap::new();
+    let mut answer: (String, SocketAddr, Result<_>) = (seed_id, seed_address, Ok(peers));
+    loop {
+        let (id, address, result) = answer;
+        let node = match result {
+            Ok(peers) => {
+                for (peer_id, peer_address) in &peers {
+                    if found.len() < config.max_nodes && found.insert(peer_id.clone()) {
+                        queue.push_back((peer_id.clone(), *peer_address));
+                    }
+                }
+                let peers: BTreeSet<String> =
+                    peers.into_iter().map(|(peer_id, _)| peer_id).collect();
+                TopologyNode {
+                    address: address.to_string(),
+                    peers: peers.into_iter().collect(),
+                    error: None,
+                }
+            },
+            Err(e) => TopologyNode {
+                address: address.to_string(),
+                peers: Vec::new(),
+                error: Some(e.to_string()),
+            },
+        };
+        nodes.insert(id, node);
+
+        let Some((id, address)) = queue.pop_front() else {
+            break;
+        };
+        let result = ask(address, config).await.map(|(_, peers)| peers);
+        answer = (id, address, result);
+    }
+    Ok(TopologySnapshot { taken_at, nodes })
+}
+
+/// Ask the node at `address` for its id and peers
+async fn ask(
+    address: SocketAddr,
+    config: &TopologyConfig,
+) -> Result<(String, Vec<(String, SocketAddr)>)> {
+    let timed_out = || {
+        ChaincraftError::Network(NetworkError::Timeout {
+            duration: config.timeout,
+        })
+    };
+    let mut client = tokio::time::timeout(config.timeout, GrpcClient::connect(address))
+        .await
+        .map_err(|_| timed_out())??;
+    let (id, peers) = tokio::time::timeout(config.timeout, client.topology())
+        .await
+        .map_err(|_| timed_out())??;
+    Ok((
+        id.to_string(),
+        peers
+            .into_iter()
+            .map(|peer| (peer.id.to_string(), peer.address))
+            .collect(),
+    ))
+}
+
+async fn resolve(target: &str) -> Result<SocketAddr> {
+    let invalid = |reason: String| {
+        ChaincraftError::config(format!("cannot resolve topology seed {}: {}", target, reason))
+    };
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host(target)
+        .await
+        .map_err(|e| invalid(e.to_string()))?
+        .collect();
+    addresses
+        .iter()
+        .find(|a| a.is_ipv4())
+        .or(addresses.first())
+        .copied()
+        .ok_or_else(|| invalid("no address".to_string()))
+}
@@ -0,0 +1,435 @@
+//! Message transports
+//!
+//! A [`Transport`] moves [`SharedMessage`]s between peers. The in-memory
+//! transport connects nodes living in the same process (tests, simulations and
+//! the browser playground) and can simulate latency and loss per link, see
+//! [`crate::network::link`]; other transports put the same [`TransportFrame`]s
+//! on a real wire.
+//!
+//! A frame can be signed with the sending node's identity key, see
+//! [`TransportFrame::sign`]. The signature covers both peer ids and the
+//! message hash, so a relay cannot pass another node's frame off as its own.
+
+use crate::{
+    crypto::{KeyType, PrivateKey, PublicKey, Signature},
+    error::{ChaincraftError, CryptoError, NetworkError, Result},
+    network::link::{link_rng, LinkFate, LinkProfile, LinkStats},
+    network::PeerId,
+    rng::RngProvider,
+    shared::SharedMessage,
+};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// When a delayed frame becomes deliverable
+#[cfg(not(target_arch = "wasm32"))]
+type Due = tokio::time::Instant;
+/// Browsers have no timers here, so every frame is due at once
+#[cfg(target_arch = "wasm32")]
+type Due = ();
+
+#[cfg(not(target_arch = "wasm32"))]
+fn due_after(delay: Duration) -> Due {
+    tokio::time::Instant::now() + delay
+}
+
+#[cfg(target_arch = "wasm32")]
+fn due_after(_delay: Duration) -> Due {}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_due(due: &Due) -> bool {
+    *due <= tokio::time::Instant::now()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_due(_due: &Due) -> bool {
+    true
+}
+
+/// Envelope carried by every transport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransportFrame {
+    pub from: PeerId,
+    pub to: PeerId,
+    pub message: SharedMessage,
+    /// Hex identity key of the sender, if the frame is signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Hex signature of the sender over the frame, see [`Self::sign`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl TransportFrame {
+    /// Unsigned frame carrying `message` from one peer to another
+    pub fn new(from: PeerId, to: PeerId, message: SharedMessage) -> Self {
+        Self {
+            from,
+            to,
+            message,
+            signer: None,
+            signature: None,
+        }
+    }
+
+    /// Sign the frame with the sender's identity key
+    pub fn sign(&mut self, private_key: &PrivateKey) -> Result<()> {
+        let signer = private_key.public_key().to_hex();
+        let signature = private_key.sign(&self.signed_bytes(&signer))?;
+        self.signer = Some(signer);
+        self.signature = Some(signature.to_hex());
+        Ok(())
+    }
+
+    /// Identity key that signed the frame, `None` if it is unsigned
+    ///
+    /// Fails with [`CryptoError::InvalidSignature`] when the signature does
+    /// not verify or the message does not match its hash.
+    pub fn verified_signer(&self) -> Result<Option<&str>> {
+        let (signer, signature) = match (&self.signer, &self.signature) {
+            (None, None) => return Ok(None),
+            (Some(signer), Some(signature)) => (signer, signature),
+            _ => return Err(ChaincraftError::Crypto(CryptoError::InvalidSignature)),
+        };
+        let key_type = KeyType::of_public_key_hex(signer);
+        let verified =
+            match (PublicKey::from_hex(signer, key_type), Signature::from_hex(signature, key_type))
+            {
+                (Ok(key), Ok(signature)) => key
+                    .verify(&self.signed_bytes(signer), &signature)
+                    .unwrap_or(false),
+                _ => false,
+            };
+        if !verified || !self.message.verify_hash() {
+            return Err(ChaincraftError::Crypto(CryptoError::InvalidSignature));
+        }
+        Ok(Some(signer))
+    }
+
+    /// Bytes a frame signature covers: every signed field, one per line
+    fn signed_bytes(&self, signer: &str) -> Vec<u8> {
+        format!("frame\n{}\n{}\n{}\n{}", self.from, self.to, self.message.hash, signer).into_bytes()
+    }
+
+    /// Encode the frame for the wire
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Decode a frame received from the wire
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes).map_err(|e| {
+            ChaincraftError::Network(NetworkError::InvalidMessage {
+                reason: e.to_string(),
+            })
+        })
+    }
+}
+
+/// Point-to-point message delivery between peers
+///
+/// Browser transports are not `Send`, so the trait only requires it on native
+/// targets.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Transport {
+    /// Identifier of the local endpoint
+    fn local_id(&self) -> &PeerId;
+
+    /// Send a message to a peer
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()>;
+
+    /// Wait for the next message addressed to this endpoint
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)>;
+}
+
+/// Frame in flight to an in-memory endpoint
+#[derive(Debug)]
+struct Delivery {
+    due: Due,
+    bytes: Vec<u8>,
+}
+
+/// State of one directed link
+#[derive(Debug)]
+struct LinkState {
+    /// Profile set for this link; the network default applies otherwise
+    profile: Option<LinkProfile>,
+    rng: RngProvider,
+    stats: LinkStats,
+    /// Due time of the last in-order frame, which later frames may not overtake
+    last_due: Option<Due>,
+}
+
+#[derive(Debug, Default)]
+struct LinkTable {
+    seed: Option<u64>,
+    default: LinkProfile,
+    links: HashMap<(PeerId, PeerId), LinkState>,
+}
+
+impl LinkTable {
+    fn link(&mut self, from: &PeerId, to: &PeerId) -> &mut LinkState {
+        let seed = self.seed;
+        self.links
+            .entry((from.clone(), to.clone()))
+            .or_insert_with(|| LinkState {
+                profile: None,
+                rng: link_rng(seed, from, to),
+                stats: LinkStats::default(),
+                last_due: None,
+            })
+    }
+}
+
+/// Hub connecting in-memory transports
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryNetwork {
+    endpoints: Arc<DashMap<PeerId, mpsc::UnboundedSender<Delivery>>>,
+    links: Arc<std::sync::Mutex<LinkTable>>,
+}
+
+impl InMemoryNetwork {
+    /// Create an empty network
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty network whose links draw their randomness from `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        let network = Self::default();
+        network.lock_links().seed = Some(seed);
+        network
+    }
+
+    fn lock_links(&self) -> std::sync::MutexGuard<'_, LinkTable> {
+        self.links.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Attach a new endpoint with the given identifier
+    pub fn connect(&self, peer_id: PeerId) -> InMemoryTransport {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.endpoints.insert(peer_id.clone(), sender);
+        InMemoryTransport {
+            local_id: peer_id,
+            network: self.clone(),
+            inbox: Mutex::new(Inbox {
+                receiver,
+                pending: BinaryHeap::new(),
+                next_seq: 0,
+            }),
+        }
+    }
+
+    /// Detach an endpoint; messages sent to it will fail
+    pub fn disconnect(&self, peer_id: &PeerId) {
+        self.endpoints.remove(peer_id);
+    }
+
+    /// Identifiers of all attached endpoints
+    pub fn peers(&self) -> Vec<PeerId> {
+        self.endpoints.iter().map(|e| e.key().clone()).collect()
+    }
+
+    /// Profile used by links without one of their own
+    pub fn set_default_link(&self, profile: LinkProfile) {
+        self.lock_links().default = profile;
+    }
+
+    /// Set the conditions of the link from `from` to `to`
+    ///
+    /// Takes effect for the next frame; frames already in flight keep their delay.
+    pub fn set_link(&self, from: &PeerId, to: &PeerId, profile: LinkProfile) {
+        self.lock_links().link(from, to).profile = Some(profile);
+    }
+
+    /// Set the conditions of the links between `a` and `b`, both ways
+    pub fn set_link_between(&self, a: &PeerId, b: &PeerId, profile: LinkProfile) {
+        let mut links = self.lock_links();
+        links.link(a, b).profile = Some(profile);
+        links.link(b, a).profile = Some(profile);
+    }
+
+    /// Return the link from `from` to `to` to the default profile
+    pub fn reset_link(&self, from: &PeerId, to: &PeerId) {
+        if let Some(link) = self.lock_links().links.get_mut(&(from.clone(), to.clone())) {
+            link.profile = None;
+        }
+    }
+
+    /// Conditions currently applied to the link from `from` to `to`
+    pub fn link_profile(&self, from: &PeerId, to: &PeerId) -> LinkProfile {
+        let links = self.lock_links();
+        links
+            .links
+            .get(&(from.clone(), to.clone()))
+            .and_then(|link| link.profile)
+            .unwrap_or(links.default)
+    }
+
+    /// Frame counters of the link from `from` to `to`
+    pub fn link_stats(&self, from: &PeerId, to: &PeerId) -> LinkStats {
+        self.lock_links()
+            .links
+            .get(&(from.clone(), to.clone()))
+            .map(|link| link.stats)
+            .unwrap_or_default()
+    }
+
+    fn deliver(&self, frame: &TransportFrame) -> Result<()> {
+        let bytes = frame.to_bytes()?;
+        let endpoint = self
+            .endpoints
+            .get(&frame.to)
+            .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))?;
+
+        let due = {
+            let mut links = self.lock_links();
+            let default = links.default;
+            let link = links.link(&frame.from, &frame.to);
+            link.stats.sent += 1;
+            match link.profile.unwrap_or(default).sample(&link.rng) {
+                LinkFate::Dropped => {
+                    link.stats.dropped += 1;
+                    return Ok(());
+                },
+                LinkFate::Delivered {
+                    delay,
+                    reordered: true,
+                } => {
+                    link.stats.reordered += 1;
+                    due_after(delay)
+                },
+                LinkFate::Delivered { delay, .. } => {
+                    let due = match link.last_due {
+                        Some(last) => due_after(delay).max(last),
+                        None => due_after(delay),
+                    };
+                    link.last_due = Some(due);
+                    due
+                },
+            }
+        };
+
+        endpoint
+            .send(Delivery { due, bytes })
+            .map_err(|_| ChaincraftError::Network(NetworkError::NoPeersAvailable))
+    }
+}
+
+/// Frame waiting in an endpoint until it is due
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Pending {
+    due: Due,
+    /// Arrival order, which breaks ties between frames due at the same time
+    seq: u64,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Inbox {
+    receiver: mpsc::UnboundedReceiver<Delivery>,
+    pending: BinaryHeap<Reverse<Pending>>,
+    next_seq: u64,
+}
+
+impl Inbox {
+    fn push(&mut self, delivery: Delivery) {
+        self.pending.push(Reverse(Pending {
+            due: delivery.due,
+            seq: self.next_seq,
+            bytes: delivery.bytes,
+        }));
+        self.next_seq += 1;
+    }
+
+    fn pop_due(&mut self) -> Option<Vec<u8>> {
+        if !is_due(&self.pending.peek()?.0.due) {
+            return None;
+        }
+        self.pending.pop().map(|Reverse(pending)| pending.bytes)
+    }
+
+    /// Wait for the next due frame
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if let Some(bytes) = self.pop_due() {
+                return Some(bytes);
+            }
+            let Some(next_due) = self.pending.peek().map(|p| p.0.due) else {
+                let delivery = self.receiver.recv().await?;
+                self.push(delivery);
+                continue;
+            };
+            let received = tokio::select! {
+                delivery = self.receiver.recv() => delivery,
+                _ = tokio::time::sleep_until(next_due) => continue,
+            };
+            match received {
+                Some(delivery) => self.push(delivery),
+                // Detached, but frames already in flight still arrive
+                None => tokio::time::sleep_until(next_due).await,
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn next(&mut self) -> Option<Vec<u8>> {
+        if let Some(bytes) = self.pop_due() {
+            return Some(bytes);
+        }
+        let delivery = self.receiver.recv().await?;
+        self.push(delivery);
+        self.pop_due()
+    }
+}
+
+/// Transport endpoint attached to an [`InMemoryNetwork`]
+#[derive(Debug)]
+pub struct InMemoryTransport {
+    local_id: PeerId,
+    network: InMemoryNetwork,
+    inbox: Mutex<Inbox>,
+}
+
+impl InMemoryTransport {
+    /// Get the network this endpoint is attached to
+    pub fn network(&self) -> &InMemoryNetwork {
+        &self.network
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for InMemoryTransport {
+    fn local_id(&self) -> &PeerId {
+        &self.local_id
+    }
+
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        self.network.deliver(&TransportFrame::new(
+            self.local_id.clone(),
+            to.clone(),
+            message.clone(),
+        ))
+    }
+
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        let bytes = self
+            .inbox
+            .lock()
+            .await
+            .next()
+            .await
+            .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))?;
+        let frame = TransportFrame::from_bytes(&bytes)?;
+        Ok((frame.from, frame.message))
+    }
+}
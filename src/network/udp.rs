@@ -0,0 +1,193 @@
+//! Gossip over UDP datagrams
+//!
+//! [`UdpTransport`] puts one [`TransportFrame`] per datagram on the wire,
+//! JSON-encoded, so a message must fit in [`MAX_DATAGRAM_BYTES`] once framed.
+//! Nothing is retransmitted; wrap the transport in a
+//! [`ReliableTransport`](crate::network::reliable::ReliableTransport) where
+//! loss matters, or rely on sync to catch up.
+//!
+//! A peer is known by the address its datagrams come from. Frames are
+//! accepted whatever their `to` field says, since a peer added by address
+//! only has a placeholder id until its first frame arrives; see
+//! [`UdpGossipConfig`] for how a node learns the real one.
+//!
+//! With [`UdpTransport::with_identity`] every frame sent is signed with the
+//! node's identity key. Received frames with a signature that does not
+//! verify are dropped like undecodable datagrams; which key a peer may sign
+//! with is up to the node, see [`UdpGossipConfig`].
+
+use crate::{
+    crypto::PrivateKey,
+    error::{ChaincraftError, NetworkError, Result},
+    network::{
+        transport::{Transport, TransportFrame},
+        PeerId,
+    },
+    shared::SharedMessage,
+};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// Largest payload of a UDP datagram over IPv4
+pub const MAX_DATAGRAM_BYTES: usize = 65_507;
+
+/// Settings of the UDP gossip a started node runs
+///
+/// With [`NodeConfig::udp_gossip`](crate::node::NodeConfig::udp_gossip) set,
+/// [`ChaincraftNode::start`](crate::ChaincraftNode::start) binds a
+/// [`UdpTransport`] on the node's port (0 picks a free one, which
+/// [`ChaincraftNode::port`](crate::ChaincraftNode::port) then reports). Every
+/// message the node stores, whether created here or received, is relayed to
+/// the peers [`ChaincraftNode::select_relay_targets`](crate::ChaincraftNode::select_relay_targets)
+/// picks; received messages go through
+/// [`ChaincraftNode::receive_message`](crate::ChaincraftNode::receive_message),
+/// and copies already stored are dropped. A peer added by address is re-keyed
+/// to the id its first frame carries.
+///
+/// A node with an identity key, see
+/// [`NodeConfig::identity_key`](crate::node::NodeConfig::identity_key), signs
+/// every frame. The first key a peer signs with is pinned, on the peer and in
+/// discovery, and frames from that peer signed with another key or unsigned
+/// are dropped from then on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpGossipConfig {
+    /// Time between relay rounds over newly stored messages
+    pub relay_interval: Duration,
+}
+
+impl Default for UdpGossipConfig {
+    fn default() -> Self {
+        Self {
+            relay_interval: Duration::from_millis(20),
+        }
+    }
+}
+
+/// Transport exchanging frames as UDP datagrams
+#[derive(Debug)]
+pub struct UdpTransport {
+    local_id: PeerId,
+    socket: UdpSocket,
+    local_addr: SocketAddr,
+    addresses: DashMap<PeerId, SocketAddr>,
+    identity: Option<PrivateKey>,
+}
+
+impl UdpTransport {
+    /// Bind a socket on `addr` (use port 0 for any free port)
+    pub async fn bind(local_id: PeerId, addr: SocketAddr) -> Result<Self> {
+        let socket = UdpSocket::bind(addr).await.map_err(|source| {
+            ChaincraftError::Network(NetworkError::BindFailed { addr, source })
+        })?;
+        let local_addr = socket.local_addr()?;
+        Ok(Self {
+            local_id,
+            socket,
+            local_addr,
+            addresses: DashMap::new(),
+            identity: None,
+        })
+    }
+
+    /// Sign every frame sent with a node identity key
+    pub fn with_identity(mut self, private_key: PrivateKey) -> Self {
+        self.identity = Some(private_key);
+        self
+    }
+
+    /// Address the socket is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Remember where a peer can be reached
+    pub fn add_peer(&self, peer_id: PeerId, addr: SocketAddr) {
+        self.addresses.insert(peer_id, addr);
+    }
+
+    /// Address a peer was added with or last heard from
+    pub fn peer_addr(&self, peer_id: &PeerId) -> Option<SocketAddr> {
+        self.addresses.get(peer_id).map(|entry| *entry.value())
+    }
+
+    /// Send a message to a peer at `addr`
+    ///
+    /// Fails with [`NetworkError::MessageTooLarge`] when the frame does not
+    /// fit in one datagram.
+    pub async fn send_to_addr(
+        &self,
+        to: &PeerId,
+        addr: SocketAddr,
+        message: &SharedMessage,
+    ) -> Result<()> {
+        let mut frame = TransportFrame::new(self.local_id.clone(), to.clone(), message.clone());
+        if let Some(private_key) = &self.identity {
+            frame.sign(private_key)?;
+        }
+        let bytes = frame.to_bytes()?;
+        if bytes.len() > MAX_DATAGRAM_BYTES {
+            return Err(ChaincraftError::Network(NetworkError::MessageTooLarge {
+                size: bytes.len(),
+                max_size: MAX_DATAGRAM_BYTES,
+            }));
+        }
+        self.socket.send_to(&bytes, addr).await.map_err(|source| {
+            ChaincraftError::Network(NetworkError::ConnectionFailed { addr, source })
+        })?;
+        Ok(())
+    }
+
+    /// Wait for the next frame, returning its sender, source address and message
+    ///
+    /// Datagrams that do not decode as frames are skipped. The sender's
+    /// address is remembered for [`Transport::send`].
+    pub async fn recv_from(&self) -> Result<(PeerId, SocketAddr, SharedMessage)> {
+        let (addr, frame) = self.recv_frame().await?;
+        Ok((frame.from, addr, frame.message))
+    }
+
+    /// Wait for the next frame and the address it came from
+    ///
+    /// Like [`Self::recv_from`], but keeps the frame's signer. Frames with a
+    /// signature that does not verify are skipped, so a signer returned here
+    /// is verified.
+    pub async fn recv_frame(&self) -> Result<(SocketAddr, TransportFrame)> {
+        let mut buf = vec![0u8; MAX_DATAGRAM_BYTES];
+        loop {
+            let (len, addr) = self.socket.recv_from(&mut buf).await?;
+            let frame = TransportFrame::from_bytes(&buf[..len]).and_then(|frame| {
+                frame.verified_signer()?;
+                Ok(frame)
+            });
+            match frame {
+                Ok(frame) => {
+                    self.addresses.insert(frame.from.clone(), addr);
+                    return Ok((addr, frame));
+                },
+                Err(e) => tracing::debug!("Dropping datagram from {}: {}", addr, e),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for UdpTransport {
+    fn local_id(&self) -> &PeerId {
+        &self.local_id
+    }
+
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        let addr = self
+            .peer_addr(to)
+            .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))?;
+        self.send_to_addr(to, addr, message).await
+    }
+
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        let (from, _, message) = self.recv_from().await?;
+        Ok((from, message))
+    }
+}
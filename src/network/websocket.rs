@@ -0,0 +1,97 @@
+//! WebSocket transport for browser nodes
+//!
+//! Browsers cannot open raw sockets, so light nodes running in the playground
+//! connect to a relay over a WebSocket. Every [`TransportFrame`] is sent as a
+//! binary message; the relay forwards it to the endpoint named in `to`.
+
+use crate::{
+    error::{ChaincraftError, NetworkError, Result},
+    network::{
+        transport::{Transport, TransportFrame},
+        PeerId,
+    },
+    shared::SharedMessage,
+};
+use async_trait::async_trait;
+use tokio::sync::{mpsc, Mutex};
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+/// Transport endpoint backed by a browser WebSocket
+pub struct WebSocketTransport {
+    local_id: PeerId,
+    socket: WebSocket,
+    inbox: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+    // Kept alive for as long as the socket delivers messages
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl WebSocketTransport {
+    /// Open a connection to the relay at `url`
+    pub fn connect(url: &str, local_id: PeerId) -> Result<Self> {
+        let socket = WebSocket::new(url).map_err(|e| js_error("connect", e))?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Ok(buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+                let _ = sender.send(js_sys::Uint8Array::new(&buffer).to_vec());
+            }
+        });
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            local_id,
+            socket,
+            inbox: Mutex::new(receiver),
+            _on_message: on_message,
+        })
+    }
+
+    /// Whether the socket is open and ready to send
+    pub fn is_open(&self) -> bool {
+        self.socket.ready_state() == WebSocket::OPEN
+    }
+
+    /// Close the connection
+    pub fn close(&self) -> Result<()> {
+        self.socket.close().map_err(|e| js_error("close", e))
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport for WebSocketTransport {
+    fn local_id(&self) -> &PeerId {
+        &self.local_id
+    }
+
+    async fn send(&self, to: &PeerId, message: &SharedMessage) -> Result<()> {
+        if !self.is_open() {
+            return Err(ChaincraftError::Network(NetworkError::NoPeersAvailable));
+        }
+        let frame = TransportFrame::new(self.local_id.clone(), to.clone(), message.clone());
+        self.socket
+            .send_with_u8_array(&frame.to_bytes()?)
+            .map_err(|e| js_error("send", e))
+    }
+
+    async fn recv(&self) -> Result<(PeerId, SharedMessage)> {
+        let mut inbox = self.inbox.lock().await;
+        loop {
+            let bytes = inbox
+                .recv()
+                .await
+                .ok_or(ChaincraftError::Network(NetworkError::NoPeersAvailable))?;
+            let frame = TransportFrame::from_bytes(&bytes)?;
+            if frame.to == self.local_id {
+                return Ok((frame.from, frame.message));
+            }
+        }
+    }
+}
+
+fn js_error(operation: &str, error: wasm_bindgen::JsValue) -> ChaincraftError {
+    ChaincraftError::Network(NetworkError::InvalidMessage {
+        reason: format!("WebSocket {} failed: {:?}", operation, error),
+    })
+}
@@ -0,0 +1,239 @@
+//! Locally created messages waiting to reach the network
+//!
+//! A node that crashes right after storing a message it created would never
+//! gossip it: the message is stored, so nothing creates it again, and peers
+//! never heard of it. An [`Outbox`] closes that gap. The message is recorded
+//! as pending broadcast in storage before it is stored, and only leaves the
+//! outbox once [`OutboxConfig::required_confirmations`] peers acknowledged
+//! its hash. A peer's sync sequence confirms nothing: a peer with a long log
+//! need not hold our messages, and the sequence is whatever the peer claims.
+//!
+//! Until then the node gossips it again every [`OutboxConfig::retry_interval`],
+//! see [`ChaincraftNode::spawn_outbox`](crate::ChaincraftNode::spawn_outbox).
+//! A node restarted on the same storage picks up where it left off; a message
+//! the crash kept from being stored is stored before it is gossiped.
+
+use crate::{error::Result, network::PeerId, shared::SharedMessage, storage::Storage};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+const OUTBOX_KEY: &str = "outbox:pending";
+
+/// When outbox messages are gossiped again and when they count as delivered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxConfig {
+    /// Time between gossip rounds of unconfirmed messages
+    pub retry_interval: Duration,
+    /// Peers that must confirm a message before it leaves the outbox
+    pub required_confirmations: usize,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            retry_interval: Duration::from_secs(1),
+            required_confirmations: 1,
+        }
+    }
+}
+
+/// A message pending broadcast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub message: SharedMessage,
+    /// Length of the message log once the message was stored, 0 until then
+    pub sequence: u64,
+    /// Gossip rounds the message went out in
+    pub attempts: u32,
+    /// Peers that confirmed the message
+    pub confirmed_by: Vec<PeerId>,
+}
+
+impl OutboxEntry {
+    /// Whether the message made it into local storage
+    pub fn is_stored(&self) -> bool {
+        self.sequence > 0
+    }
+}
+
+/// Messages pending broadcast, persisted in a storage backend
+///
+/// Cloning is cheap and clones share the same entries.
+#[derive(Clone)]
+pub struct Outbox {
+    storage: Arc<dyn Storage>,
+    config: Arc<RwLock<OutboxConfig>>,
+    // Loaded from storage on first use
+    entries: Arc<Mutex<Option<Vec<OutboxEntry>>>>,
+}
+
+impl Outbox {
+    pub fn new(storage: Arc<dyn Storage>, config: OutboxConfig) -> Self {
+        Self {
+            storage,
+            config: Arc::new(RwLock::new(config)),
+            entries: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn config(&self) -> OutboxConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the settings of this outbox and its clones
+    pub fn set_config(&self, config: OutboxConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// Record a message as pending broadcast; a message already pending is kept
+    pub async fn add(&self, message: SharedMessage) -> Result<()> {
+        let mut guard = self.entries.lock().await;
+        let entries = self.load(&mut guard).await?;
+        if entries
+            .iter()
+            .any(|entry| entry.message.hash == message.hash)
+        {
+            return Ok(());
+        }
+        entries.push(OutboxEntry {
+            message,
+            sequence: 0,
+            attempts: 0,
+            confirmed_by: Vec::new(),
+        });
+        self.save(entries).await
+    }
+
+    /// Note that a message was stored and the message log reached `sequence`
+    pub async fn mark_stored(&self, hash: &str, sequence: u64) -> Result<()> {
+        self.update(|entries| {
+            let entry = entries
+                .iter_mut()
+                .find(|entry| entry.message.hash == hash)?;
+            entry.sequence = sequence.max(1);
+            Some(())
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Count a gossip round for each of `hashes`
+    pub async fn record_attempt(&self, hashes: &[String]) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        self.update(|entries| {
+            for entry in entries.iter_mut() {
+                if hashes.contains(&entry.message.hash) {
+                    entry.attempts += 1;
+                }
+            }
+            Some(())
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// Record that `peer_id` has a message; returns whether that delivered it
+    pub async fn confirm(&self, hash: &str, peer_id: &PeerId) -> Result<bool> {
+        let required = self.config().required_confirmations.max(1);
+        let delivered = self
+            .update(|entries| {
+                let position = entries
+                    .iter()
+                    .position(|entry| entry.message.hash == hash)?;
+                let entry = &mut entries[position];
+                if !entry.confirmed_by.contains(peer_id) {
+                    entry.confirmed_by.push(peer_id.clone());
+                }
+                if entry.confirmed_by.len() < required {
+                    return Some(false);
+                }
+                entries.remove(position);
+                Some(true)
+            })
+            .await?;
+        Ok(delivered == Some(true))
+    }
+
+    /// Drop a message without delivering it; returns whether it was pending
+    pub async fn discard(&self, hash: &str) -> Result<bool> {
+        let removed = self
+            .update(|entries| {
+                let position = entries
+                    .iter()
+                    .position(|entry| entry.message.hash == hash)?;
+                entries.remove(position);
+                Some(())
+            })
+            .await?;
+        Ok(removed.is_some())
+    }
+
+    /// Messages pending broadcast, oldest first
+    pub async fn pending(&self) -> Result<Vec<OutboxEntry>> {
+        let mut guard = self.entries.lock().await;
+        Ok(self.load(&mut guard).await?.clone())
+    }
+
+    /// Number of messages pending broadcast
+    pub async fn len(&self) -> Result<usize> {
+        let mut guard = self.entries.lock().await;
+        Ok(self.load(&mut guard).await?.len())
+    }
+
+    pub async fn is_empty(&self) -> Result<bool> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Apply `change` and save the entries if it returns `Some`
+    async fn update<R>(
+        &self,
+        change: impl FnOnce(&mut Vec<OutboxEntry>) -> Option<R>,
+    ) -> Result<Option<R>> {
+        let mut guard = self.entries.lock().await;
+        let entries = self.load(&mut guard).await?;
+        let result = change(entries);
+        if result.is_some() {
+            self.save(entries).await?;
+        }
+        Ok(result)
+    }
+
+    async fn load<'a>(
+        &self,
+        entries: &'a mut Option<Vec<OutboxEntry>>,
+    ) -> Result<&'a mut Vec<OutboxEntry>> {
+        if entries.is_none() {
+            let loaded = match self.storage.get(OUTBOX_KEY).await? {
+                Some(bytes) => serde_json::from_slice(&bytes)?,
+                None => Vec::new(),
+            };
+            *entries = Some(loaded);
+        }
+        Ok(entries.get_or_insert_with(Vec::new))
+    }
+
+    async fn save(&self, entries: &[OutboxEntry]) -> Result<()> {
+        if entries.is_empty() {
+            self.storage.delete(OUTBOX_KEY).await
+        } else {
+            self.storage
+                .put(OUTBOX_KEY, serde_json::to_vec(entries)?)
+                .await
+        }
+    }
+}
+
+impl std::fmt::Debug for Outbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outbox")
+            .field("config", &self.config())
+            .finish_non_exhaustive()
+    }
+}
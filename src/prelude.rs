@@ -0,0 +1,27 @@
+//! The stable API
+//!
+//! ```ignore
+//! use chaincraft_rust::prelude::*;
+//! ```
+//!
+//! brings in what an application needs to run a node and plug its own objects
+//! into it. These items keep their names and the signatures pinned by
+//! `tests/test_api_surface.rs` until the next major release; new error
+//! variants and events may be added in a minor release, which is why
+//! [`ChaincraftError`] and [`NodeEvent`] are non-exhaustive. Everything else
+//! the crate exports is reachable through its module and may change between
+//! minor releases.
+
+pub use crate::error::{
+    ChaincraftError, CryptoError, NetworkError, Result, SerializationError, StorageError,
+};
+pub use crate::events::NodeEvent;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::handle::NodeHandle;
+pub use crate::network::{PeerId, PeerInfo};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::node::{ChaincraftNode, ChaincraftNodeBuilder};
+pub use crate::shared::{MessageType, SharedMessage, SharedObjectId};
+pub use crate::shared_object::{ApplicationObject, ApplyOutcome};
+pub use crate::storage::{MemoryStorage, Storage};
+pub use async_trait::async_trait;
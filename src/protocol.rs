@@ -0,0 +1,339 @@
+//! Machine-readable description of the wire protocol
+//!
+//! [`describe`] reports what this build puts on the wire: the message
+//! envelope and how its hash is computed, every message kind with the JSON
+//! Schema of its data, the discovery messages, and the flows of the sync,
+//! delivery and keepalive protocols. Schemas come from the types the code
+//! sends and names from the constants it matches on, so alternative
+//! implementations can check conformance against the running code rather
+//! than against prose. `chaincraft-cli protocol dump` prints the description.
+//!
+//! The output is deterministic, so it can be committed and diffed.
+//! [`DESCRIPTION_FORMAT`] changes whenever the layout of the description does.
+
+use crate::{
+    consensus::poa::{PoaBlock, POA_BLOCK_MESSAGE_TYPE},
+    discovery::DiscoveryMessage,
+    network::{
+        bridge::{BridgedMessage, BRIDGE_MESSAGE_TYPE},
+        framing::{Frame, FRAME_MESSAGE_TYPE},
+        keepalive::{Keepalive, KEEPALIVE_MESSAGE_TYPE},
+        reliable::{Ack, ACK_MESSAGE_TYPE},
+        DEFAULT_NETWORK_ID, PROTOCOL_VERSION,
+    },
+    shared::{MessageType, SharedMessage},
+    sync::{SyncAnnouncement, SYNC_STATUS_MESSAGE_TYPE},
+    sync_batch::{SyncAck, SyncBatch, SYNC_ACK_MESSAGE_TYPE, SYNC_BATCH_MESSAGE_TYPE},
+};
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Version of the layout of [`ProtocolDescription`]
+pub const DESCRIPTION_FORMAT: u32 = 1;
+
+/// The wire protocol spoken by this build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolDescription {
+    /// [`DESCRIPTION_FORMAT`] of this description
+    pub format: u32,
+    /// [`PROTOCOL_VERSION`] peers exchange in their handshake
+    pub protocol_version: u32,
+    /// Crate name and version that produced the description
+    pub implementation: String,
+    pub default_network_id: String,
+    pub envelope: Envelope,
+    /// Every message type this build sends or understands
+    pub message_kinds: Vec<MessageKind>,
+    /// JSON Schema of the peer discovery messages
+    pub discovery: Value,
+    pub flows: Vec<Flow>,
+}
+
+/// The message every payload travels in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// JSON Schema of a [`SharedMessage`]
+    pub schema: Value,
+    /// Serializations of the envelope messages are exchanged in
+    pub encodings: Vec<String>,
+    pub hash: HashRecipe,
+}
+
+/// How the `hash` field of a message is computed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashRecipe {
+    pub algorithm: String,
+    /// Hashed fields in order, each with its canonical encoding
+    pub fields: Vec<HashedField>,
+    /// A message as serialized by this build, whose `hash` is the reference
+    pub example: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashedField {
+    pub field: String,
+    pub encoding: String,
+}
+
+/// A value of the envelope's `message_type`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageKind {
+    /// Standard type name, or the name of a custom type
+    pub name: String,
+    /// Whether the type is sent as `{ "Custom": name }`
+    pub custom: bool,
+    pub description: String,
+    /// JSON Schema of the message data, absent when it is free-form
+    pub data_schema: Option<Value>,
+}
+
+/// An exchange of messages between two parties
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flow {
+    pub name: String,
+    pub description: String,
+    pub steps: Vec<FlowStep>,
+}
+
+/// One message of a [`Flow`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowStep {
+    pub from: Party,
+    /// Name of a [`MessageKind`], or of a discovery message variant
+    pub message: String,
+    pub note: String,
+}
+
+/// Side of a [`Flow`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Party {
+    /// The party starting the exchange
+    Initiator,
+    /// The party answering it
+    Responder,
+}
+
+/// Describe the wire protocol of this build
+pub fn describe() -> ProtocolDescription {
+    ProtocolDescription {
+        format: DESCRIPTION_FORMAT,
+        protocol_version: PROTOCOL_VERSION,
+        implementation: format!("{} {}", crate::NAME, crate::VERSION),
+        default_network_id: DEFAULT_NETWORK_ID.to_string(),
+        envelope: envelope(),
+        message_kinds: message_kinds(),
+        discovery: schema_of::<DiscoveryMessage>(),
+        flows: flows(),
+    }
+}
+
+fn schema_of<T: schemars::JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).expect("schemas serialize to JSON")
+}
+
+fn envelope() -> Envelope {
+    let mut example =
+        SharedMessage::new(MessageType::Custom("example".to_string()), json!({ "n": 1 }));
+    example.id = "00000000-0000-4000-8000-000000000001"
+        .parse()
+        .expect("example id is a UUID");
+    example.timestamp = chrono::Utc
+        .with_ymd_and_hms(2024, 1, 1, 0, 0, 0)
+        .single()
+        .expect("example timestamp is valid");
+    example.hash = example.calculate_hash();
+
+    let field = |field: &str, encoding: &str| HashedField {
+        field: field.to_string(),
+        encoding: encoding.to_string(),
+    };
+    Envelope {
+        schema: schema_of::<SharedMessage>(),
+        encodings: vec!["json".to_string(), "bincode".to_string()],
+        hash: HashRecipe {
+            algorithm: "sha256, hex encoded".to_string(),
+            fields: vec![
+                field("id", "the 16 bytes of the UUID"),
+                field("message_type", "the type name, custom types by their name alone"),
+                field("data", "compact JSON with object keys sorted"),
+                field("timestamp", "seconds since the Unix epoch as i64, then nanoseconds as u32"),
+            ],
+            example: serde_json::to_value(&example).expect("messages serialize to JSON"),
+        },
+    }
+}
+
+fn standard_description(message_type: &MessageType) -> &'static str {
+    match message_type {
+        MessageType::PeerDiscovery => "Peer discovery message",
+        MessageType::RequestLocalPeers => "Request for local peers",
+        MessageType::LocalPeers => "Response with local peers",
+        MessageType::RequestSharedObjectUpdate => "Request for shared object update",
+        MessageType::SharedObjectUpdate => "Response with shared object data",
+        MessageType::Get => "Request to get an object",
+        MessageType::Set => "Request to set an object",
+        MessageType::Delete => "Request to delete an object",
+        MessageType::Response => "Response containing requested data",
+        MessageType::Notification => "Notification of changes",
+        MessageType::Heartbeat => "Heartbeat/ping message",
+        MessageType::Error => "Error response",
+        MessageType::Custom(_) => "Application message of a type named by the application",
+    }
+}
+
+fn message_kinds() -> Vec<MessageKind> {
+    let mut kinds: Vec<MessageKind> = MessageType::standard()
+        .iter()
+        .map(|message_type| MessageKind {
+            name: message_type.to_string(),
+            custom: false,
+            description: standard_description(message_type).to_string(),
+            data_schema: None,
+        })
+        .collect();
+    let control = |name: &str, description: &str, data_schema: Value| MessageKind {
+        name: name.to_string(),
+        custom: true,
+        description: description.to_string(),
+        data_schema: Some(data_schema),
+    };
+    kinds.extend([
+        control(
+            SYNC_STATUS_MESSAGE_TYPE,
+            "Length of the sender's message log and its object digests",
+            schema_of::<SyncAnnouncement>(),
+        ),
+        control(
+            SYNC_BATCH_MESSAGE_TYPE,
+            "Consecutive messages of the sender's log",
+            schema_of::<SyncBatch>(),
+        ),
+        control(
+            SYNC_ACK_MESSAGE_TYPE,
+            "Confirmation of a sync batch and the position needed next",
+            schema_of::<SyncAck>(),
+        ),
+        control(ACK_MESSAGE_TYPE, "Receipt of a message sent reliably", schema_of::<Ack>()),
+        control(FRAME_MESSAGE_TYPE, "Several messages sent as one", schema_of::<Frame>()),
+        control(
+            KEEPALIVE_MESSAGE_TYPE,
+            "Ping or pong checking that a connection is alive",
+            schema_of::<Keepalive>(),
+        ),
+        control(
+            BRIDGE_MESSAGE_TYPE,
+            "A message relayed from another network",
+            schema_of::<BridgedMessage>(),
+        ),
+        control(
+            POA_BLOCK_MESSAGE_TYPE,
+            "Block sealed by a proof-of-authority validator",
+            schema_of::<PoaBlock>(),
+        ),
+    ]);
+    kinds
+}
+
+fn step(from: Party, message: &str, note: &str) -> FlowStep {
+    FlowStep {
+        from,
+        message: message.to_string(),
+        note: note.to_string(),
+    }
+}
+
+fn flow(name: &str, description: &str, steps: Vec<FlowStep>) -> Flow {
+    Flow {
+        name: name.to_string(),
+        description: description.to_string(),
+        steps,
+    }
+}
+
+fn flows() -> Vec<Flow> {
+    use Party::{Initiator, Responder};
+
+    vec![
+        flow(
+            "sync_status",
+            "Nodes periodically tell each peer how far their message log reaches",
+            vec![step(
+                Initiator,
+                SYNC_STATUS_MESSAGE_TYPE,
+                "The responder compares the sequence with its own log, records the shards \
+                 the initiator follows and checks the digests for forks; nothing is answered",
+            )],
+        ),
+        flow(
+            "batched_sync",
+            "A node behind a peer fetches the messages it misses, in order",
+            vec![
+                step(
+                    Initiator,
+                    SYNC_BATCH_MESSAGE_TYPE,
+                    "Batches start at the cursor the responder needs; several may be in \
+                     flight up to a byte limit",
+                ),
+                step(
+                    Responder,
+                    SYNC_ACK_MESSAGE_TYPE,
+                    "Acknowledges the batch and every earlier one; the cursor tells where to \
+                     continue. The transfer ends with the batch reaching total",
+                ),
+            ],
+        ),
+        flow(
+            "reliable_delivery",
+            "Messages sent reliably are resent with backoff until acknowledged",
+            vec![
+                step(Initiator, "*", "Any message"),
+                step(
+                    Responder,
+                    ACK_MESSAGE_TYPE,
+                    "Sent for every copy received, retransmissions included",
+                ),
+            ],
+        ),
+        flow(
+            "framing",
+            "Gossip queued for a peer goes out in one message",
+            vec![step(
+                Initiator,
+                FRAME_MESSAGE_TYPE,
+                "The responder handles the framed messages one at a time, in order; a \
+                 single queued message is sent unframed",
+            )],
+        ),
+        flow(
+            "keepalive",
+            "Peers that leave pings unanswered become suspect, then dead",
+            vec![
+                step(Initiator, KEEPALIVE_MESSAGE_TYPE, "A ping, once per interval"),
+                step(Responder, KEEPALIVE_MESSAGE_TYPE, "A pong echoing the ping's nonce"),
+            ],
+        ),
+        flow(
+            "bridge",
+            "A bridge node forwards messages between two networks",
+            vec![step(
+                Initiator,
+                BRIDGE_MESSAGE_TYPE,
+                "Carries the original message unchanged with the networks it visited; \
+                 networks on the path do not receive it again",
+            )],
+        ),
+        flow(
+            "discovery",
+            "Nodes learn about each other through the discovery messages",
+            vec![
+                step(Initiator, "Announce", "Signed when the node has an identity key"),
+                step(Initiator, "PeerRequest", "Asks for up to max_peers known peers"),
+                step(Responder, "PeerResponse", "Best scoring peers the initiator lacks"),
+                step(Initiator, "Ping", "Checks that the peer is alive"),
+                step(Responder, "Pong", "Echoes the ping's timestamp"),
+            ],
+        ),
+    ]
+}
@@ -0,0 +1,78 @@
+//! Finding stored messages without knowing their hash
+//!
+//! Every stored message is indexed at write time by type, target object,
+//! verified sender and timestamp (see [`crate::index::MessageIndex`]), so a
+//! [`MessageFilter`] can be answered from the smallest matching index instead
+//! of decoding the whole log.
+
+use crate::shared::{MessageType, SharedMessage, SharedObjectId};
+use chrono::{DateTime, Utc};
+
+/// Criteria for [`crate::ChaincraftNode::query_messages`]; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageFilter {
+    pub message_type: Option<MessageType>,
+    pub target_id: Option<SharedObjectId>,
+    /// Hex public key of a signer whose signature verified
+    pub sender: Option<String>,
+    /// Earliest message timestamp, inclusive
+    pub since: Option<DateTime<Utc>>,
+    /// Latest message timestamp, inclusive
+    pub until: Option<DateTime<Utc>>,
+    /// Maximum number of results
+    pub limit: Option<usize>,
+}
+
+impl MessageFilter {
+    /// Filter matching every message
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn of_type(mut self, message_type: MessageType) -> Self {
+        self.message_type = Some(message_type);
+        self
+    }
+
+    pub fn targeting(mut self, target_id: SharedObjectId) -> Self {
+        self.target_id = Some(target_id);
+        self
+    }
+
+    pub fn from_sender(mut self, public_key_hex: impl Into<String>) -> Self {
+        self.sender = Some(public_key_hex.into());
+        self
+    }
+
+    pub fn since(mut self, time: DateTime<Utc>) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    pub fn until(mut self, time: DateTime<Utc>) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether a message satisfies the filter
+    pub fn matches(&self, message: &SharedMessage) -> bool {
+        self.message_type
+            .as_ref()
+            .is_none_or(|t| *t == message.message_type)
+            && self
+                .target_id
+                .as_ref()
+                .is_none_or(|t| Some(t) == message.target_id.as_ref())
+            && self
+                .sender
+                .as_deref()
+                .is_none_or(|s| Some(s) == message.verified_sender())
+            && self.since.is_none_or(|since| message.timestamp >= since)
+            && self.until.is_none_or(|until| message.timestamp <= until)
+    }
+}
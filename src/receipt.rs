@@ -0,0 +1,151 @@
+//! Transaction receipts
+//!
+//! Applying a message to the application objects produces a [`Receipt`]: the
+//! outcome each object reported, the gas the objects charged, the events they
+//! emitted and a hash over their digests before and after. Objects without a
+//! notion of gas or events simply report nothing through
+//! [`crate::ApplicationObject::take_execution`].
+
+use crate::shared::SharedObjectId;
+use crate::shared_object::{ApplyOutcome, OutcomeReason};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// An event emitted by an object while applying a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReceiptEvent {
+    /// Object that emitted the event
+    pub object_id: SharedObjectId,
+    /// Event name, e.g. `transfer`
+    pub name: String,
+    /// Event payload
+    pub data: serde_json::Value,
+}
+
+/// Gas and events recorded by an object for the last applied message
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    pub gas_used: u64,
+    /// Events as `(name, data)` pairs
+    pub events: Vec<(String, serde_json::Value)>,
+}
+
+impl Execution {
+    /// Add gas to the running total
+    pub fn charge(&mut self, gas: u64) {
+        self.gas_used = self.gas_used.saturating_add(gas);
+    }
+
+    /// Record an event
+    pub fn emit(&mut self, name: impl Into<String>, data: serde_json::Value) {
+        self.events.push((name.into(), data));
+    }
+}
+
+/// Overall result of a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiptStatus {
+    /// At least one object applied the message and none rejected it
+    Success,
+    /// No object changed its state
+    NoEffect,
+    /// An object rejected the message
+    Failed(OutcomeReason),
+}
+
+/// What applying a message actually did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Hash of the applied message
+    pub tx_hash: String,
+    pub status: ReceiptStatus,
+    /// Outcome reported by every object that accepted the message
+    pub outcomes: Vec<(SharedObjectId, ApplyOutcome)>,
+    /// Gas charged by all objects
+    pub gas_used: u64,
+    /// Events emitted by all objects, in processing order
+    pub events: Vec<ReceiptEvent>,
+    /// Hash over every object's digest before and after the message
+    pub state_delta_hash: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl Receipt {
+    /// Storage key of the receipt for `tx_hash`
+    pub fn storage_key(tx_hash: &str) -> String {
+        format!("receipt:{}", tx_hash)
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status == ReceiptStatus::Success
+    }
+}
+
+/// Accumulates a [`Receipt`] while objects process a message
+#[derive(Debug)]
+pub struct ReceiptBuilder {
+    tx_hash: String,
+    outcomes: Vec<(SharedObjectId, ApplyOutcome)>,
+    gas_used: u64,
+    events: Vec<ReceiptEvent>,
+    delta: Sha256,
+}
+
+impl ReceiptBuilder {
+    pub fn new(tx_hash: impl Into<String>) -> Self {
+        Self {
+            tx_hash: tx_hash.into(),
+            outcomes: Vec::new(),
+            gas_used: 0,
+            events: Vec::new(),
+            delta: Sha256::new(),
+        }
+    }
+
+    /// Record one object's result
+    pub fn record(
+        &mut self,
+        object_id: SharedObjectId,
+        outcome: ApplyOutcome,
+        execution: Execution,
+        digest_before: &str,
+        digest_after: &str,
+    ) {
+        self.delta.update(object_id.to_string().as_bytes());
+        self.delta.update(digest_before.as_bytes());
+        self.delta.update(digest_after.as_bytes());
+        self.gas_used = self.gas_used.saturating_add(execution.gas_used);
+        self.events.extend(
+            execution
+                .events
+                .into_iter()
+                .map(|(name, data)| ReceiptEvent {
+                    object_id: object_id.clone(),
+                    name,
+                    data,
+                }),
+        );
+        self.outcomes.push((object_id, outcome));
+    }
+
+    pub fn finish(self) -> Receipt {
+        let rejection = self.outcomes.iter().find_map(|(_, outcome)| match outcome {
+            ApplyOutcome::Rejected(reason) => Some(reason.clone()),
+            _ => None,
+        });
+        let status = match rejection {
+            Some(reason) => ReceiptStatus::Failed(reason),
+            None if self.outcomes.iter().any(|(_, o)| o.is_applied()) => ReceiptStatus::Success,
+            None => ReceiptStatus::NoEffect,
+        };
+        Receipt {
+            tx_hash: self.tx_hash,
+            status,
+            outcomes: self.outcomes,
+            gas_used: self.gas_used,
+            events: self.events,
+            state_delta_hash: hex::encode(self.delta.finalize()),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
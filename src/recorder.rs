@@ -0,0 +1,89 @@
+//! Recording inbound traffic for deterministic replay
+//!
+//! While recording, every message handed to
+//! [`ChaincraftNode::receive_message`](crate::ChaincraftNode::receive_message)
+//! is appended to a JSON-lines file together with the sending peer and the
+//! node clock's time, before quotas or validation see it. Replaying the file
+//! into a fresh node on a mock [`Clock`](crate::clock::Clock) runs the same
+//! messages through the same pipeline at the same instants and ends in the
+//! same state, which turns a one-off multi-node failure into a repeatable test.
+
+use crate::error::{ChaincraftError, Result};
+use crate::network::PeerId;
+use crate::shared::SharedMessage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// One inbound message as received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Node clock time at arrival
+    pub at: DateTime<Utc>,
+    /// Peer the message came from
+    pub from: PeerId,
+    pub message: SharedMessage,
+}
+
+/// Result of replaying a recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    /// Messages fed back into the node
+    pub replayed: usize,
+    /// Of those, messages the node refused (as it did when they were recorded)
+    pub refused: usize,
+}
+
+/// Appends inbound messages to a recording file
+#[derive(Debug)]
+pub struct Recorder {
+    file: tokio::fs::File,
+    count: usize,
+}
+
+impl Recorder {
+    /// Start a new recording at `path`, replacing any existing file
+    pub async fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let file = tokio::fs::File::create(path).await?;
+        Ok(Self { file, count: 0 })
+    }
+
+    /// Append one message
+    pub async fn record(&mut self, entry: &RecordedMessage) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).await?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Messages recorded so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Flush and close the recording, returning the number of messages
+    pub async fn finish(mut self) -> Result<usize> {
+        self.file.flush().await?;
+        Ok(self.count)
+    }
+}
+
+/// Read a recording written by a [`Recorder`]
+pub async fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedMessage>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut lines = BufReader::new(file).lines();
+    let mut entries = Vec::new();
+    let mut line_number = 0;
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| ChaincraftError::validation(format!("line {}: {}", line_number, e)))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
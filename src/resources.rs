@@ -0,0 +1,99 @@
+//! Resource limits for nodes sharing a machine
+//!
+//! A [`ResourceLimits`] caps what a single node may consume. Every limit is
+//! optional; when one is hit the operation fails with
+//! [`crate::ChaincraftError::ResourceExhausted`] instead of letting the node
+//! grow without bound.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A resource covered by [`ResourceLimits`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    /// Bytes written to storage
+    StorageBytes,
+    /// Message hashes held in the in-memory message log
+    CachedMessages,
+    /// Registered application objects
+    Objects,
+    /// Admitted peers
+    Peers,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::StorageBytes => write!(f, "storage bytes"),
+            Resource::CachedMessages => write!(f, "cached messages"),
+            Resource::Objects => write!(f, "objects"),
+            Resource::Peers => write!(f, "peers"),
+        }
+    }
+}
+
+/// Upper bounds on what a node may consume
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Total size of keys and values written through the node's storage
+    pub max_storage_bytes: Option<u64>,
+    /// Messages kept in the in-memory message log
+    pub max_cached_messages: Option<usize>,
+    /// Application objects registered at once
+    pub max_objects: Option<usize>,
+    /// Peers admitted at once
+    ///
+    /// Unlike `NodeConfig::max_peers`, which discovery aims for, this is a
+    /// hard cap checked whenever a peer is added.
+    pub max_peers: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// No limits
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_storage_bytes(mut self, bytes: u64) -> Self {
+        self.max_storage_bytes = Some(bytes);
+        self
+    }
+
+    pub fn with_max_cached_messages(mut self, messages: usize) -> Self {
+        self.max_cached_messages = Some(messages);
+        self
+    }
+
+    pub fn with_max_objects(mut self, objects: usize) -> Self {
+        self.max_objects = Some(objects);
+        self
+    }
+
+    pub fn with_max_peers(mut self, peers: usize) -> Self {
+        self.max_peers = Some(peers);
+        self
+    }
+
+    /// Configured limit for a resource
+    pub fn limit(&self, resource: Resource) -> Option<u64> {
+        match resource {
+            Resource::StorageBytes => self.max_storage_bytes,
+            Resource::CachedMessages => self.max_cached_messages.map(|n| n as u64),
+            Resource::Objects => self.max_objects.map(|n| n as u64),
+            Resource::Peers => self.max_peers.map(|n| n as u64),
+        }
+    }
+
+    /// Check whether `requested` units of a resource fit within its limit
+    pub fn check(&self, resource: Resource, requested: u64) -> crate::Result<()> {
+        match self.limit(resource) {
+            Some(limit) if requested > limit => Err(crate::ChaincraftError::ResourceExhausted {
+                resource,
+                requested,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
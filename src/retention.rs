@@ -0,0 +1,99 @@
+//! Retention of stored messages
+//!
+//! Without a policy a node keeps every message it stores. With
+//! [`NodeConfig::retention`](crate::node::NodeConfig::retention) set, a
+//! started node prunes every [`RetentionPolicy::prune_interval`], see
+//! [`ChaincraftNode::prune_messages`](crate::ChaincraftNode::prune_messages).
+//! A pass walks the message log from the oldest message and removes messages
+//! while more than [`RetentionPolicy::max_messages`] remain or while they are
+//! older than [`RetentionPolicy::max_age`]; it stops at the first message it
+//! keeps, so messages go in the order they were stored.
+//!
+//! Pruned messages are deleted from storage, the message cache and the
+//! indexes. The log keeps their hashes, so sequence numbers peers already
+//! saw stay valid; sync skips the missing messages. Application objects then
+//! get a [`Compaction`] through
+//! [`ApplicationObject::compact`](crate::shared_object::ApplicationObject::compact)
+//! to drop the messages they hold in memory.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long stored messages are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Prune messages timestamped longer ago than this; `None` keeps any age
+    pub max_age: Option<Duration>,
+    /// Keep at most this many stored messages; `None` keeps any number
+    pub max_messages: Option<usize>,
+    /// Time between pruning passes of a started node
+    pub prune_interval: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_messages: None,
+            prune_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// What objects should drop as of `now`
+    pub fn compaction(&self, now: DateTime<Utc>) -> Compaction {
+        Compaction {
+            older_than: self
+                .max_age
+                .and_then(|age| chrono::Duration::from_std(age).ok())
+                .map(|age| now - age),
+            max_messages: self.max_messages,
+        }
+    }
+}
+
+/// Messages an application object should drop from memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compaction {
+    /// Drop messages timestamped before this
+    pub older_than: Option<DateTime<Utc>>,
+    /// Keep at most this many of the newest messages
+    pub max_messages: Option<usize>,
+}
+
+impl Compaction {
+    /// Whether a message timestamped at `timestamp` is old enough to drop
+    pub fn is_expired(&self, timestamp: DateTime<Utc>) -> bool {
+        self.older_than.is_some_and(|cutoff| timestamp < cutoff)
+    }
+
+    /// How many of the oldest of `len` messages are over the count limit
+    pub fn excess(&self, len: usize) -> usize {
+        self.max_messages.map_or(0, |max| len.saturating_sub(max))
+    }
+
+    /// How many of `timestamps`, oldest first, to drop from the front
+    ///
+    /// Counts the messages over the limit, then any expired ones after them,
+    /// stopping at the first one kept.
+    pub fn prefix_to_drop(&self, timestamps: impl IntoIterator<Item = DateTime<Utc>>) -> usize {
+        let timestamps: Vec<DateTime<Utc>> = timestamps.into_iter().collect();
+        let excess = self.excess(timestamps.len());
+        excess
+            + timestamps[excess..]
+                .iter()
+                .take_while(|timestamp| self.is_expired(**timestamp))
+                .count()
+    }
+}
+
+/// What a pruning pass removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneSummary {
+    /// Stored messages deleted
+    pub messages: usize,
+    /// Messages application objects dropped from memory
+    pub compacted: usize,
+}
@@ -0,0 +1,124 @@
+//! Settings a running node can change
+//!
+//! Most of [`NodeConfig`](crate::node::NodeConfig) is fixed once a node is
+//! built. The settings here are read by their subsystem on every use, so
+//! [`ChaincraftNode::update_config`](crate::node::ChaincraftNode::update_config)
+//! can change them mid-run, e.g. to vary the gossip fanout during an
+//! experiment. `NodeConfig` keeps the values the node was built with;
+//! [`RuntimeConfig`] has the ones in effect.
+//!
+//! The log level belongs to the process rather than a node. It can only be
+//! changed once logging was set up with [`init_logging`].
+
+use crate::error::{ChaincraftError, Result};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{fmt as log_fmt, layer::SubscriberExt, reload, Registry};
+
+/// Settings in effect on a running node
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeConfig {
+    /// How often unconfirmed messages are gossiped again, `None` without an outbox
+    pub gossip_interval: Option<Duration>,
+    /// Peers a message is forwarded to
+    pub fanout: usize,
+    /// Messages accepted from one peer per quota window
+    pub rate_limit: u32,
+    /// Process log level, `None` unless logging came from [`init_logging`]
+    pub log_level: Option<LevelFilter>,
+    /// Audit entries kept in memory, `None` without an audit log
+    pub audit_retention: Option<usize>,
+}
+
+/// Changes to a [`RuntimeConfig`]; unset fields keep their value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigUpdate {
+    pub gossip_interval: Option<Duration>,
+    pub fanout: Option<usize>,
+    pub rate_limit: Option<u32>,
+    pub log_level: Option<LevelFilter>,
+    pub audit_retention: Option<usize>,
+}
+
+impl ConfigUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_gossip_interval(mut self, interval: Duration) -> Self {
+        self.gossip_interval = Some(interval);
+        self
+    }
+
+    pub fn with_fanout(mut self, fanout: usize) -> Self {
+        self.fanout = Some(fanout);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, messages_per_window: u32) -> Self {
+        self.rate_limit = Some(messages_per_window);
+        self
+    }
+
+    pub fn with_log_level(mut self, level: LevelFilter) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    pub fn with_audit_retention(mut self, entries: usize) -> Self {
+        self.audit_retention = Some(entries);
+        self
+    }
+
+    /// Refuse values no subsystem can run with
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.gossip_interval == Some(Duration::ZERO) {
+            return Err(ChaincraftError::config("gossip interval must be positive"));
+        }
+        if self.rate_limit == Some(0) {
+            return Err(ChaincraftError::config("rate limit must be positive"));
+        }
+        if self.audit_retention == Some(0) {
+            return Err(ChaincraftError::config("audit retention must be positive"));
+        }
+        if self.log_level.is_some() && log_level().is_none() {
+            return Err(ChaincraftError::config(
+                "log level can only be changed after init_logging",
+            ));
+        }
+        Ok(())
+    }
+}
+
+static LOG_LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+/// Log to stdout at `level`, keeping the level changeable at runtime
+///
+/// Fails if the process already installed a global subscriber.
+pub fn init_logging(level: LevelFilter) -> Result<()> {
+    let (filter, handle) = reload::Layer::new(level);
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(log_fmt::layer());
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| ChaincraftError::config(format!("logging already set up: {}", e)))?;
+    // Only the caller that installed the subscriber gets this far
+    let _ = LOG_LEVEL.set(handle);
+    Ok(())
+}
+
+/// Current log level, `None` unless [`init_logging`] set up logging
+pub fn log_level() -> Option<LevelFilter> {
+    LOG_LEVEL.get()?.clone_current()
+}
+
+/// Change the log level set up by [`init_logging`]
+pub fn set_log_level(level: LevelFilter) -> Result<()> {
+    let handle = LOG_LEVEL
+        .get()
+        .ok_or_else(|| ChaincraftError::config("logging was not set up with init_logging"))?;
+    handle
+        .reload(level)
+        .map_err(|e| ChaincraftError::config(e.to_string()))
+}
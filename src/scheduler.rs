@@ -0,0 +1,135 @@
+//! Messages submitted at a later time
+//!
+//! Protocols with deadlines, such as closing an auction or opening the reveal
+//! phase of a commit-reveal game, need a message to appear once a given time
+//! has come. A [`MessageSchedule`] keeps such messages in a [`Storage`]
+//! backend until they are due, so a schedule outlives the node that made it:
+//! a node restarted on the same storage submits whatever came due while it
+//! was down.
+//!
+//! Messages are built when they are scheduled, stamped with their due time,
+//! and only leave the schedule once they were submitted. A node that crashes
+//! in between submits the identical message again after the restart.
+
+use crate::error::{ChaincraftError, Result};
+use crate::shared::SharedMessage;
+use crate::storage::Storage;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const QUEUE_KEY: &str = "scheduler:queue";
+
+/// Pending messages ordered by due time, persisted in a storage backend
+///
+/// Cloning is cheap and clones share the same queue.
+#[derive(Clone)]
+pub struct MessageSchedule {
+    storage: Arc<dyn Storage>,
+    // Loaded from storage on first use
+    queue: Arc<Mutex<Option<Vec<SharedMessage>>>>,
+}
+
+impl MessageSchedule {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            queue: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Add a message, due at its timestamp
+    pub async fn add(&self, message: SharedMessage) -> Result<()> {
+        let mut guard = self.queue.lock().await;
+        let queue = self.load(&mut guard).await?;
+        if queue.iter().any(|pending| pending.hash == message.hash) {
+            return Err(ChaincraftError::validation(format!(
+                "message {} is already scheduled",
+                message.hash
+            )));
+        }
+        let position = queue.partition_point(|pending| pending.timestamp <= message.timestamp);
+        queue.insert(position, message);
+        self.save(queue).await
+    }
+
+    /// Remove a pending message; returns whether it was scheduled
+    pub async fn cancel(&self, hash: &str) -> Result<bool> {
+        let mut guard = self.queue.lock().await;
+        let queue = self.load(&mut guard).await?;
+        let before = queue.len();
+        queue.retain(|pending| pending.hash != hash);
+        if queue.len() == before {
+            return Ok(false);
+        }
+        self.save(queue).await?;
+        Ok(true)
+    }
+
+    /// Pending messages, earliest first
+    pub async fn pending(&self) -> Result<Vec<SharedMessage>> {
+        let mut guard = self.queue.lock().await;
+        Ok(self.load(&mut guard).await?.clone())
+    }
+
+    /// Pending messages due at or before `now`, earliest first
+    ///
+    /// They stay scheduled until [`Self::complete`] is called for them.
+    pub async fn due(&self, now: DateTime<Utc>) -> Result<Vec<SharedMessage>> {
+        let mut guard = self.queue.lock().await;
+        let queue = self.load(&mut guard).await?;
+        Ok(queue
+            .iter()
+            .take_while(|pending| pending.timestamp <= now)
+            .cloned()
+            .collect())
+    }
+
+    /// Due time of the earliest pending message
+    pub async fn next_due(&self) -> Result<Option<DateTime<Utc>>> {
+        let mut guard = self.queue.lock().await;
+        let queue = self.load(&mut guard).await?;
+        Ok(queue.first().map(|pending| pending.timestamp))
+    }
+
+    /// Remove messages that were submitted
+    pub async fn complete(&self, hashes: &[String]) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        let mut guard = self.queue.lock().await;
+        let queue = self.load(&mut guard).await?;
+        queue.retain(|pending| !hashes.contains(&pending.hash));
+        self.save(queue).await
+    }
+
+    async fn load<'a>(
+        &self,
+        queue: &'a mut Option<Vec<SharedMessage>>,
+    ) -> Result<&'a mut Vec<SharedMessage>> {
+        if queue.is_none() {
+            let loaded = match self.storage.get(QUEUE_KEY).await? {
+                Some(bytes) => serde_json::from_slice(&bytes)?,
+                None => Vec::new(),
+            };
+            *queue = Some(loaded);
+        }
+        Ok(queue.get_or_insert_with(Vec::new))
+    }
+
+    async fn save(&self, queue: &[SharedMessage]) -> Result<()> {
+        if queue.is_empty() {
+            self.storage.delete(QUEUE_KEY).await
+        } else {
+            self.storage
+                .put(QUEUE_KEY, serde_json::to_vec(queue)?)
+                .await
+        }
+    }
+}
+
+impl std::fmt::Debug for MessageSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageSchedule").finish_non_exhaustive()
+    }
+}
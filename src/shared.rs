@@ -1,9 +1,12 @@
 //! Shared objects and messages for distributed state management
 
+use crate::codec::consensus::ConsensusHasher;
 use crate::error::{ChaincraftError, CryptoError, Result, SerializationError};
+use crate::rng::RngProvider;
 use async_trait::async_trait;
 use bincode;
 use hex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::any::Any;
@@ -14,14 +17,24 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 
 /// Unique identifier for shared objects
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct SharedObjectId(Uuid);
+///
+/// `Display` and parsing use the full UUID; logs and `Debug` output use the
+/// [`short`](Self::short) form, which a registry or directory can resolve back
+/// while it is unambiguous.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[schemars(description = "UUID of a shared object or message")]
+pub struct SharedObjectId(#[schemars(with = "String")] Uuid);
 
 impl SharedObjectId {
     pub fn new() -> Self {
         Self(Uuid::new_v4())
     }
 
+    /// Create an identifier drawn from the given RNG provider
+    pub fn new_with_rng(rng: &RngProvider) -> Self {
+        Self(rng.next_uuid())
+    }
+
     pub fn as_uuid(&self) -> &Uuid {
         &self.0
     }
@@ -33,6 +46,16 @@ impl SharedObjectId {
     pub fn from_uuid(uuid: Uuid) -> Self {
         Self(uuid)
     }
+
+    /// First 8 hex digits, for logs and tables
+    pub fn short(&self) -> String {
+        let mut text = self.0.simple().to_string();
+        text.truncate(Self::SHORT_LEN);
+        text
+    }
+
+    /// Length of [`short`](Self::short) ids
+    pub const SHORT_LEN: usize = 8;
 }
 
 impl fmt::Display for SharedObjectId {
@@ -41,6 +64,29 @@ impl fmt::Display for SharedObjectId {
     }
 }
 
+impl fmt::Debug for SharedObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SharedObjectId({})", self.short())
+    }
+}
+
+impl std::str::FromStr for SharedObjectId {
+    type Err = uuid::Error;
+
+    /// Parse a full id; short ids need [`ObjectDirectory::resolve`](crate::directory::ObjectDirectory::resolve)
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
+impl TryFrom<&str> for SharedObjectId {
+    type Error = uuid::Error;
+
+    fn try_from(s: &str) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 impl Default for SharedObjectId {
     fn default() -> Self {
         Self::new()
@@ -159,6 +205,50 @@ impl<'de> Deserialize<'de> for MessageType {
     }
 }
 
+impl MessageType {
+    /// Every message type but [`MessageType::Custom`]
+    pub fn standard() -> [MessageType; 12] {
+        [
+            MessageType::PeerDiscovery,
+            MessageType::RequestLocalPeers,
+            MessageType::LocalPeers,
+            MessageType::RequestSharedObjectUpdate,
+            MessageType::SharedObjectUpdate,
+            MessageType::Get,
+            MessageType::Set,
+            MessageType::Delete,
+            MessageType::Response,
+            MessageType::Notification,
+            MessageType::Heartbeat,
+            MessageType::Error,
+        ]
+    }
+}
+
+impl JsonSchema for MessageType {
+    fn schema_name() -> String {
+        "MessageType".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let standard: Vec<String> = Self::standard().iter().map(ToString::to_string).collect();
+        serde_json::from_value(serde_json::json!({
+            "description": "A standard type name, or a custom one as { \"Custom\": name }; \
+                            unknown names are read as custom types",
+            "anyOf": [
+                { "type": "string", "enum": standard },
+                {
+                    "type": "object",
+                    "properties": { "Custom": { "type": "string" } },
+                    "required": ["Custom"],
+                    "additionalProperties": false
+                }
+            ]
+        }))
+        .expect("message type schema is valid")
+    }
+}
+
 impl fmt::Display for MessageType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -195,8 +285,58 @@ pub struct SharedMessage {
     /// Optional signature for authenticated messages
     #[serde(with = "serde_bytes", default)]
     pub signature: Option<Vec<u8>>,
+    /// Hex public key of the signer, set by [`Self::sign`]
+    #[serde(default)]
+    pub sender: Option<String>,
     /// Hash of the message content
     pub hash: String,
+    /// Anti-spam proof of work over the hash, see [`crate::crypto::pow::attach_pow`]
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
+    /// Set by a node in simulation mode on messages it accepted unsigned
+    ///
+    /// A local judgement: never serialized, so neither peers nor signatures
+    /// see it, and re-derived whenever a node loads the message.
+    #[serde(skip)]
+    pub simulated: bool,
+}
+
+// Serialized form of `SharedMessage`, for its JSON Schema. The schema derive
+// cannot see through `serde_bytes`, so the fields are repeated here with the
+// signature as the byte array it serializes to.
+#[derive(JsonSchema)]
+#[schemars(description = "A message shared between nodes")]
+struct EncodedSharedMessage {
+    /// Unique message identifier
+    id: SharedObjectId,
+    message_type: MessageType,
+    /// Target object ID (if applicable)
+    target_id: Option<SharedObjectId>,
+    /// Message payload
+    data: serde_json::Value,
+    /// Timestamp when message was created
+    timestamp: chrono::DateTime<chrono::Utc>,
+    /// Optional signature for authenticated messages
+    #[serde(default)]
+    signature: Option<Vec<u8>>,
+    /// Hex public key of the signer
+    #[serde(default)]
+    sender: Option<String>,
+    /// Hash of the message content
+    hash: String,
+    /// Anti-spam proof of work over the hash
+    #[serde(default)]
+    pow_nonce: Option<u64>,
+}
+
+impl JsonSchema for SharedMessage {
+    fn schema_name() -> String {
+        "SharedMessage".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        EncodedSharedMessage::json_schema(gen)
+    }
 }
 
 impl SharedMessage {
@@ -209,12 +349,27 @@ impl SharedMessage {
             data,
             timestamp: chrono::Utc::now(),
             signature: None,
+            sender: None,
             hash: String::new(),
+            pow_nonce: None,
+            simulated: false,
         };
         message.hash = message.calculate_hash();
         message
     }
 
+    /// Create a new shared message whose id is drawn from the given RNG provider
+    pub fn new_with_rng(
+        rng: &RngProvider,
+        message_type: MessageType,
+        data: serde_json::Value,
+    ) -> Self {
+        let mut message = Self::new(message_type, data);
+        message.id = SharedObjectId::new_with_rng(rng);
+        message.hash = message.calculate_hash();
+        message
+    }
+
     /// Create a new message with a target
     pub fn new_with_target(
         message_type: MessageType,
@@ -228,7 +383,10 @@ impl SharedMessage {
             data,
             timestamp: chrono::Utc::now(),
             signature: None,
+            sender: None,
             hash: String::new(),
+            pow_nonce: None,
+            simulated: false,
         };
         message.hash = message.calculate_hash();
         message
@@ -242,7 +400,10 @@ impl SharedMessage {
     }
 
     /// Sign this message with the given private key
+    ///
+    /// Also records the signer's public key as the message [`Self::sender`].
     pub fn sign(&mut self, private_key: &crate::crypto::PrivateKey) -> Result<()> {
+        self.sender = Some(private_key.public_key().to_hex());
         let message_bytes = self.to_bytes()?;
         let signature = private_key.sign(&message_bytes)?;
         self.signature = Some(signature.to_bytes());
@@ -286,22 +447,36 @@ impl SharedMessage {
                         .verify(&message_bytes, &sig_result.unwrap())
                         .is_ok())
                 },
+                crate::crypto::PublicKey::Bls12_381(pk) => {
+                    use crate::crypto::bls;
+                    let signature = bls::signature_from_bytes(sig_bytes)
+                        .ok_or(ChaincraftError::Crypto(CryptoError::InvalidSignature))?;
+                    Ok(bls::verify(pk, &message_bytes, &signature, bls::SIGNATURE_DST))
+                },
             }
         } else {
             Ok(false)
         }
     }
 
+    /// The sender, if the message carries a signature that verifies under it
+    pub fn verified_sender(&self) -> Option<&str> {
+        let sender = self.sender.as_deref()?;
+        let key_type = crate::crypto::KeyType::of_public_key_hex(sender);
+        let public_key = crate::crypto::PublicKey::from_hex(sender, key_type).ok()?;
+        self.verify_signature(&public_key)
+            .unwrap_or(false)
+            .then_some(sender)
+    }
+
     /// Calculate the hash of this message
     pub fn calculate_hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        if let Ok(id_bytes) = bincode::serialize(self.id.as_uuid()) {
-            hasher.update(&id_bytes);
-        }
-        hasher.update(self.message_type.to_string().as_bytes());
-        hasher.update(self.data.to_string().as_bytes());
-        hasher.update(self.timestamp.to_rfc3339().as_bytes());
-        hex::encode(hasher.finalize())
+        ConsensusHasher::new()
+            .bytes(self.id.as_uuid().as_bytes())
+            .str(&self.message_type.to_string())
+            .str(&self.data.to_string())
+            .timestamp(&self.timestamp)
+            .finish_hex()
     }
 
     /// Verify the message hash
@@ -449,15 +624,434 @@ impl StateDigest {
 
     /// Calculate a digest from messages
     pub fn from_messages(messages: &[SharedMessage]) -> Self {
-        let mut hasher = Sha256::new();
-        for message in messages {
-            hasher.update(message.hash.as_bytes());
-        }
-        let hash = hex::encode(hasher.finalize());
+        let hash = messages
+            .iter()
+            .fold(ConsensusHasher::new(), |hasher, message| hasher.str(&message.hash))
+            .finish_hex();
         Self::new(hash, messages.len() as u64)
     }
 }
 
+/// Default number of digests kept by a [`DigestHistory`]
+pub const DEFAULT_DIGEST_HISTORY: usize = 1024;
+
+/// Number of most recent digests a locator lists one by one
+const LOCATOR_DENSE: usize = 8;
+
+/// Bounded history of the digests an object has passed through
+///
+/// The newest digests are kept in a ring buffer with a hash index, so
+/// `contains` is constant time. Each entry records its position in the
+/// object's history as [`StateDigest::message_count`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestHistory {
+    capacity: usize,
+    entries: std::collections::VecDeque<StateDigest>,
+    index: HashMap<String, u64>,
+    next_sequence: u64,
+}
+
+impl Default for DigestHistory {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_DIGEST_HISTORY)
+    }
+}
+
+impl DigestHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a history keeping at most `capacity` digests
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: std::collections::VecDeque::new(),
+            index: HashMap::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Append a digest, evicting the oldest one when full
+    ///
+    /// Recording the current latest digest again is a no-op.
+    pub fn record(&mut self, hash: impl Into<String>) {
+        let hash = hash.into();
+        if self.latest().is_some_and(|d| d.hash == hash) {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            if let Some(oldest) = self.entries.pop_front() {
+                // The same hash may have been seen again later
+                if self.index.get(&oldest.hash) == Some(&oldest.message_count) {
+                    self.index.remove(&oldest.hash);
+                }
+            }
+        }
+        self.index.insert(hash.clone(), self.next_sequence);
+        self.entries
+            .push_back(StateDigest::new(hash, self.next_sequence));
+        self.next_sequence += 1;
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.index.contains_key(hash)
+    }
+
+    /// Most recent position of `hash` in the history
+    pub fn sequence_of(&self, hash: &str) -> Option<u64> {
+        self.index.get(hash).copied()
+    }
+
+    pub fn latest(&self) -> Option<&StateDigest> {
+        self.entries.back()
+    }
+
+    /// Digest reached after `message_count` messages, if still retained
+    pub fn at(&self, message_count: u64) -> Option<&StateDigest> {
+        let first = self.entries.front()?.message_count;
+        let offset = message_count.checked_sub(first)?;
+        self.entries.get(usize::try_from(offset).ok()?)
+    }
+
+    /// Digests recorded after `hash`, oldest first
+    pub fn since(&self, hash: &str) -> Option<Vec<&StateDigest>> {
+        let sequence = self.sequence_of(hash)?;
+        let first = self.entries.front()?.message_count;
+        Some(
+            self.entries
+                .iter()
+                .skip((sequence - first + 1) as usize)
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+        self.next_sequence = 0;
+    }
+
+    /// Compact summary of the history for [`DigestHistory::find_common`]
+    ///
+    /// Lists the newest digests one by one, then steps back twice as far each
+    /// time, ending with the oldest retained digest. A peer can answer with
+    /// the most recent digest both sides share while only a logarithmic
+    /// number of hashes crosses the wire.
+    pub fn locator(&self) -> Vec<String> {
+        let mut locator = Vec::new();
+        let mut step = 1;
+        let mut offset = 0;
+        while offset < self.entries.len() {
+            let position = self.entries.len() - 1 - offset;
+            locator.push(self.entries[position].hash.clone());
+            if locator.len() >= LOCATOR_DENSE {
+                step *= 2;
+            }
+            offset += step;
+        }
+        if let Some(oldest) = self.entries.front() {
+            if locator.last() != Some(&oldest.hash) {
+                locator.push(oldest.hash.clone());
+            }
+        }
+        locator
+    }
+
+    /// Most recent entry of a peer's locator that this history also contains
+    pub fn find_common(&self, locator: &[String]) -> Option<&StateDigest> {
+        let sequence = locator.iter().find_map(|hash| self.sequence_of(hash))?;
+        let first = self.entries.front()?.message_count;
+        self.entries.get((sequence - first) as usize)
+    }
+}
+
+/// Rolling digest over the hashes of applied messages
+///
+/// Each applied message moves the digest to `sha256(previous || hash)`, so two
+/// objects that applied the same messages in the same order agree on every
+/// intermediate digest. The accumulator remembers which digest was reached
+/// after each message and keeps the messages themselves, optionally only the
+/// most recent ones, to answer [`DigestAccumulator::messages_since`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestAccumulator {
+    digest: String,
+    applied: u64,
+    retention: Option<usize>,
+    /// Digest before the oldest retained message
+    base_digest: String,
+    /// Retained messages with the digest reached after each
+    entries: std::collections::VecDeque<(String, SharedMessage)>,
+    /// Number of applied messages at which each reachable digest was current
+    positions: HashMap<String, u64>,
+}
+
+impl Default for DigestAccumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DigestAccumulator {
+    /// Create an accumulator that keeps every applied message
+    pub fn new() -> Self {
+        let genesis = Self::genesis();
+        let mut positions = HashMap::new();
+        positions.insert(genesis.clone(), 0);
+        Self {
+            digest: genesis.clone(),
+            applied: 0,
+            retention: None,
+            base_digest: genesis,
+            entries: std::collections::VecDeque::new(),
+            positions,
+        }
+    }
+
+    /// Create an accumulator that keeps only the last `retention` messages
+    ///
+    /// Digests from before the retained window are forgotten.
+    pub fn with_retention(retention: usize) -> Self {
+        Self {
+            retention: Some(retention.max(1)),
+            ..Self::new()
+        }
+    }
+
+    /// Digest of an object that has applied nothing
+    pub fn genesis() -> String {
+        hex::encode(Sha256::digest(b""))
+    }
+
+    /// Fold an applied message into the digest and return the new digest
+    pub fn apply(&mut self, message: &SharedMessage) -> &str {
+        self.digest = ConsensusHasher::new()
+            .str(&self.digest)
+            .str(&message.hash)
+            .finish_hex();
+        self.applied += 1;
+        self.positions.insert(self.digest.clone(), self.applied);
+        self.entries
+            .push_back((self.digest.clone(), message.clone()));
+
+        if self.retention.is_some_and(|r| self.entries.len() > r) {
+            if let Some((digest, _)) = self.entries.pop_front() {
+                let base = self.applied - self.entries.len() as u64 - 1;
+                if self.positions.get(&self.base_digest) == Some(&base) {
+                    self.positions.remove(&self.base_digest);
+                }
+                self.base_digest = digest;
+            }
+        }
+        &self.digest
+    }
+
+    /// Current digest
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    /// Number of messages applied since creation or the last reset
+    pub fn applied(&self) -> u64 {
+        self.applied
+    }
+
+    /// Whether `digest` was reached within the retained window
+    pub fn contains(&self, digest: &str) -> bool {
+        self.positions.contains_key(digest)
+    }
+
+    /// Messages applied after `digest`, oldest first
+    ///
+    /// `None` if the digest is unknown or older than the retained window.
+    pub fn messages_since(&self, digest: &str) -> Option<Vec<SharedMessage>> {
+        let position = *self.positions.get(digest)?;
+        let base = self.applied - self.entries.len() as u64;
+        Some(
+            self.entries
+                .iter()
+                .skip((position - base) as usize)
+                .map(|(_, message)| message.clone())
+                .collect(),
+        )
+    }
+
+    /// All retained messages, oldest first
+    pub fn messages(&self) -> Vec<SharedMessage> {
+        self.entries.iter().map(|(_, m)| m.clone()).collect()
+    }
+
+    /// Messages to gossip to a peer at `digest`
+    ///
+    /// A peer with an unknown digest gets everything retained.
+    pub fn gossip(&self, digest: Option<&str>) -> Vec<SharedMessage> {
+        digest
+            .and_then(|d| self.messages_since(d))
+            .unwrap_or_else(|| self.messages())
+    }
+
+    /// Forget everything applied so far
+    pub fn reset(&mut self) {
+        *self = match self.retention {
+            Some(retention) => Self::with_retention(retention),
+            None => Self::new(),
+        };
+    }
+}
+
+/// Messages an object applied, with the digest each one led to
+///
+/// Unlike [`DigestAccumulator`], the log computes no digests: registries
+/// record every applied message here together with the digests the object
+/// reported before and after it. An object exposing a log through
+/// [`crate::shared_object::ApplicationObject::message_log`] gets gossip and
+/// catch-up for free. With a retention only the newest messages are kept and
+/// older digests are forgotten.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObjectMessageLog {
+    retention: Option<usize>,
+    applied: u64,
+    /// Digest before the oldest retained message
+    base_digest: Option<String>,
+    /// Retained messages with the digest reached after each
+    entries: std::collections::VecDeque<(String, SharedMessage)>,
+    /// Number of recorded messages at which each reachable digest was current
+    positions: HashMap<String, u64>,
+}
+
+impl ObjectMessageLog {
+    /// Create a log that keeps every recorded message
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a log that keeps only the last `retention` messages
+    pub fn with_retention(retention: usize) -> Self {
+        Self {
+            retention: Some(retention.max(1)),
+            ..Self::default()
+        }
+    }
+
+    /// Append a message that moved the object from `digest_before` to `digest_after`
+    pub fn record(&mut self, digest_before: &str, digest_after: &str, message: SharedMessage) {
+        if self.base_digest.is_none() {
+            self.base_digest = Some(digest_before.to_string());
+            self.positions
+                .entry(digest_before.to_string())
+                .or_insert(self.applied);
+        }
+        self.applied += 1;
+        self.positions
+            .insert(digest_after.to_string(), self.applied);
+        self.entries.push_back((digest_after.to_string(), message));
+
+        if self.retention.is_some_and(|r| self.entries.len() > r) {
+            if let Some((digest, _)) = self.entries.pop_front() {
+                let base = self.applied - self.entries.len() as u64 - 1;
+                if let Some(old) = self.base_digest.replace(digest) {
+                    if self.positions.get(&old) == Some(&base) {
+                        self.positions.remove(&old);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Maximum number of retained messages, if bounded
+    pub fn retention(&self) -> Option<usize> {
+        self.retention
+    }
+
+    /// Number of retained messages
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `digest` was reached within the retained window
+    pub fn contains(&self, digest: &str) -> bool {
+        self.positions.contains_key(digest)
+    }
+
+    /// Messages recorded after `digest`, oldest first
+    ///
+    /// `None` if the digest is unknown or older than the retained window.
+    pub fn messages_since(&self, digest: &str) -> Option<Vec<SharedMessage>> {
+        let base = self.applied - self.entries.len() as u64;
+        let skip = self.positions.get(digest)?.checked_sub(base)?;
+        Some(
+            self.entries
+                .iter()
+                .skip(skip as usize)
+                .map(|(_, message)| message.clone())
+                .collect(),
+        )
+    }
+
+    /// All retained messages, oldest first
+    pub fn messages(&self) -> Vec<SharedMessage> {
+        self.entries.iter().map(|(_, m)| m.clone()).collect()
+    }
+
+    /// Messages to gossip to a peer at `digest`
+    ///
+    /// A peer with an unknown digest gets everything retained.
+    pub fn gossip(&self, digest: Option<&str>) -> Vec<SharedMessage> {
+        digest
+            .and_then(|d| self.messages_since(d))
+            .unwrap_or_else(|| self.messages())
+    }
+
+    /// Up to `limit` retained messages recorded before number `before`, or
+    /// the newest ones, newest first
+    ///
+    /// Messages are numbered from 1 in the order they were recorded; each
+    /// comes with its number and the digest reached after it.
+    pub fn newest_before(
+        &self,
+        before: Option<u64>,
+        limit: usize,
+    ) -> Vec<(u64, &str, &SharedMessage)> {
+        let base = self.applied - self.entries.len() as u64;
+        let end = before
+            .map_or(self.applied, |before| before.saturating_sub(1).min(self.applied))
+            .saturating_sub(base) as usize;
+        self.entries
+            .iter()
+            .take(end)
+            .enumerate()
+            .rev()
+            .take(limit)
+            .map(|(i, (digest, message))| (base + i as u64 + 1, digest.as_str(), message))
+            .collect()
+    }
+
+    /// Number of the oldest retained message, see [`Self::newest_before`]
+    pub fn first_retained(&self) -> Option<u64> {
+        (!self.entries.is_empty()).then(|| self.applied - self.entries.len() as u64 + 1)
+    }
+
+    /// Forget every recorded message, keeping the retention
+    pub fn clear(&mut self) {
+        *self = Self {
+            retention: self.retention,
+            ..Self::default()
+        };
+    }
+}
+
 /// Registry for managing shared objects
 pub struct SharedObjectRegistry {
     objects: HashMap<SharedObjectId, Box<dyn SharedObject>>,
@@ -2,19 +2,163 @@
 
 pub use crate::shared::SharedObjectId;
 use crate::{
+    directory::{ObjectDirectory, ObjectSnapshot},
     error::{ChaincraftError, Result},
-    shared::{MessageType, SharedMessage, SharedObject},
+    index::IndexSpec,
+    receipt::{Execution, Receipt, ReceiptBuilder},
+    retention::Compaction,
+    shared::{
+        DigestAccumulator, DigestHistory, MessageType, ObjectMessageLog, SharedMessage,
+        SharedObject, StateDigest,
+    },
+    tasks::TaskSpec,
 };
 use async_trait::async_trait;
 use chrono;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
 use std::any::Any;
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// Machine-readable reason attached to an ignored or rejected message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutcomeReason {
+    /// Stable snake_case code, e.g. `invalid_signature`
+    pub code: String,
+    /// Human-readable explanation
+    pub detail: String,
+}
+
+impl OutcomeReason {
+    pub fn new(code: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for OutcomeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.detail)
+    }
+}
+
+/// Result of handing a message to an application object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApplyOutcome {
+    /// The message changed the object's state
+    Applied,
+    /// The message was well-formed but had no effect, e.g. a duplicate
+    Ignored(OutcomeReason),
+    /// The message broke the object's protocol rules
+    Rejected(OutcomeReason),
+}
+
+impl ApplyOutcome {
+    /// Create an ignored outcome
+    pub fn ignored(code: impl Into<String>, detail: impl Into<String>) -> Self {
+        ApplyOutcome::Ignored(OutcomeReason::new(code, detail))
+    }
+
+    /// Create a rejected outcome
+    pub fn rejected(code: impl Into<String>, detail: impl Into<String>) -> Self {
+        ApplyOutcome::Rejected(OutcomeReason::new(code, detail))
+    }
+
+    pub fn is_applied(&self) -> bool {
+        matches!(self, ApplyOutcome::Applied)
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        matches!(self, ApplyOutcome::Rejected(_))
+    }
+
+    /// Reason for an ignored or rejected message
+    pub fn reason(&self) -> Option<&OutcomeReason> {
+        match self {
+            ApplyOutcome::Applied => None,
+            ApplyOutcome::Ignored(reason) | ApplyOutcome::Rejected(reason) => Some(reason),
+        }
+    }
+}
+
+/// Where a page of [`ApplicationObject::history`] ends
+///
+/// The next page holds the entries with a lower [`HistoryEntry::sequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HistoryCursor(pub u64);
+
+/// A message an object handled, as shown in its history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Position among the messages the object recorded, from 1
+    pub sequence: u64,
+    pub hash: String,
+    pub message_type: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub outcome: ApplyOutcome,
+    /// Digest the object reached after the message
+    pub digest: String,
+}
+
+/// Bookkeeping the registry keeps for every object, whatever its type
+///
+/// Unlike [`ApplicationObject::get_state`], whose format is up to each
+/// object, these fields mean the same for all objects. The node persists
+/// them, so they survive a restart for objects registered under the same id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectInfo {
+    /// Messages the object applied
+    pub applied: u64,
+    /// Messages the object rejected
+    pub rejected: u64,
+    /// When the object last applied a message
+    pub last_applied: Option<chrono::DateTime<chrono::Utc>>,
+    /// Digest the object reached with its last applied message
+    pub last_digest: Option<String>,
+}
+
+impl ObjectInfo {
+    /// Storage key of the bookkeeping of object `id`
+    pub fn storage_key(id: &SharedObjectId) -> String {
+        format!("object_info:{}", id)
+    }
+}
+
+/// Whether a message may be gossiped on to other peers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RelayDecision {
+    /// Forward the message as usual
+    Relay,
+    /// Keep the message local; it is still stored and applied
+    Veto(OutcomeReason),
+}
+
+impl RelayDecision {
+    /// Create a veto
+    pub fn veto(code: impl Into<String>, detail: impl Into<String>) -> Self {
+        RelayDecision::Veto(OutcomeReason::new(code, detail))
+    }
+
+    pub fn is_relayed(&self) -> bool {
+        matches!(self, RelayDecision::Relay)
+    }
+
+    /// Reason for a veto
+    pub fn reason(&self) -> Option<&OutcomeReason> {
+        match self {
+            RelayDecision::Relay => None,
+            RelayDecision::Veto(reason) => Some(reason),
+        }
+    }
+}
+
 /// Enhanced shared object trait with application-specific functionality
 #[async_trait]
 pub trait ApplicationObject: Send + Sync + std::fmt::Debug {
@@ -28,7 +172,10 @@ pub trait ApplicationObject: Send + Sync + std::fmt::Debug {
     async fn is_valid(&self, message: &SharedMessage) -> Result<bool>;
 
     /// Add a validated message to the object
-    async fn add_message(&mut self, message: SharedMessage) -> Result<()>;
+    ///
+    /// Errors are reserved for failures of the object itself; messages that
+    /// have no effect or break the protocol are reported through the outcome.
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome>;
 
     /// Check if this object supports merkleized synchronization
     fn is_merkleized(&self) -> bool;
@@ -37,19 +184,119 @@ pub trait ApplicationObject: Send + Sync + std::fmt::Debug {
     async fn get_latest_digest(&self) -> Result<String>;
 
     /// Check if object has a specific digest
-    async fn has_digest(&self, digest: &str) -> Result<bool>;
+    ///
+    /// Defaults to a lookup in [`ApplicationObject::digest_history`].
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self
+            .digest_history()
+            .is_some_and(|history| history.contains(digest)))
+    }
 
     /// Validate if a digest is valid
     async fn is_valid_digest(&self, digest: &str) -> Result<bool>;
 
     /// Add a digest to the object
-    async fn add_digest(&mut self, digest: String) -> Result<bool>;
+    ///
+    /// Defaults to recording it in [`ApplicationObject::digest_history`].
+    async fn add_digest(&mut self, digest: String) -> Result<bool> {
+        match self.digest_history_mut() {
+            Some(history) => {
+                history.record(digest);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Digests this object has passed through, if it keeps them
+    ///
+    /// Registries record the latest digest here after every applied message.
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        None
+    }
+
+    /// Mutable access to the digest history
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        None
+    }
+
+    /// Messages this object applied, if it keeps a log of them
+    ///
+    /// Registries record every applied message here with the digest it led
+    /// to, which is what the default [`ApplicationObject::gossip_messages`]
+    /// and [`ApplicationObject::get_messages_since_digest`] serve from.
+    fn message_log(&self) -> Option<&ObjectMessageLog> {
+        None
+    }
+
+    /// Mutable access to the message log
+    fn message_log_mut(&mut self) -> Option<&mut ObjectMessageLog> {
+        None
+    }
 
     /// Get messages for gossip protocol
-    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>>;
+    ///
+    /// Defaults to the [`ApplicationObject::message_log`]: the messages after
+    /// `digest`, or all retained ones for a digest it does not know.
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self
+            .message_log()
+            .map(|log| log.gossip(digest))
+            .unwrap_or_default())
+    }
 
     /// Get messages since a specific digest
-    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>>;
+    ///
+    /// Defaults to a lookup in the [`ApplicationObject::message_log`], which
+    /// fails once the digest is older than the retained messages.
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        let Some(log) = self.message_log() else {
+            return Ok(Vec::new());
+        };
+        log.messages_since(digest).ok_or_else(|| {
+            ChaincraftError::validation(format!(
+                "digest {} is unknown or older than the {} retained messages",
+                digest,
+                log.len()
+            ))
+        })
+    }
+
+    /// Page through the messages this object handled, newest first
+    ///
+    /// Returns up to `limit` entries before `cursor`, or the newest ones
+    /// without a cursor, and the cursor of the next page while older entries
+    /// remain. Defaults to the [`ApplicationObject::message_log`], which only
+    /// records applied messages and drops those past its retention; objects
+    /// without a log have no history.
+    fn history(
+        &self,
+        cursor: Option<HistoryCursor>,
+        limit: usize,
+    ) -> (Vec<HistoryEntry>, Option<HistoryCursor>) {
+        let Some(log) = self.message_log() else {
+            return (Vec::new(), None);
+        };
+        let entries: Vec<HistoryEntry> = log
+            .newest_before(cursor.map(|cursor| cursor.0), limit)
+            .into_iter()
+            .map(|(sequence, digest, message)| HistoryEntry {
+                sequence,
+                hash: message.hash.clone(),
+                message_type: message.message_type.to_string(),
+                timestamp: message.timestamp,
+                outcome: ApplyOutcome::Applied,
+                digest: digest.to_string(),
+            })
+            .collect();
+        let next = match (entries.last(), log.first_retained()) {
+            (Some(oldest), Some(first)) if oldest.sequence > first => {
+                Some(HistoryCursor(oldest.sequence))
+            },
+            _ => None,
+        };
+        (entries, next)
+    }
 
     /// Get the current state as JSON
     async fn get_state(&self) -> Result<Value>;
@@ -57,6 +304,105 @@ pub trait ApplicationObject: Send + Sync + std::fmt::Debug {
     /// Reset the object to initial state
     async fn reset(&mut self) -> Result<()>;
 
+    /// Decide whether a message this object accepts may be gossiped further
+    ///
+    /// Asked only for messages [`ApplicationObject::is_valid`] accepts. Checks
+    /// should be cheap, e.g. a signature or a lookup, so spam can be stopped
+    /// at the first honest node instead of being flooded through the network.
+    async fn relay_policy(&self, _message: &SharedMessage) -> Result<RelayDecision> {
+        Ok(RelayDecision::Relay)
+    }
+
+    /// Hand over the gas and events recorded while applying the last message
+    ///
+    /// Called once after every [`ApplicationObject::add_message`]; objects that
+    /// meter execution should return what they recorded and start afresh.
+    fn take_execution(&mut self) -> Execution {
+        Execution::default()
+    }
+
+    /// Secondary indexes over stored messages this object wants the node to keep
+    ///
+    /// Declared when the object is added to a node; see [`crate::index`].
+    fn indexes(&self) -> Vec<IndexSpec> {
+        Vec::new()
+    }
+
+    /// Recurring tasks this object wants the node to run
+    ///
+    /// Scheduled when the object is added to a node; see [`crate::tasks`].
+    fn periodic_tasks(&self) -> Vec<TaskSpec> {
+        Vec::new()
+    }
+
+    /// Run the task named `name` from [`ApplicationObject::periodic_tasks`]
+    ///
+    /// `now` is the node clock. Returns the data of messages for the node to
+    /// create, as with `create_shared_message_with_data`.
+    async fn run_task(
+        &mut self,
+        _name: &str,
+        _now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Value>> {
+        Ok(Vec::new())
+    }
+
+    /// Receive a read-only view of the other objects in the registry
+    ///
+    /// Called when the object is registered. Objects that read sibling state
+    /// keep the handle; see [`crate::directory`].
+    fn attach_directory(&mut self, _directory: ObjectDirectory) {}
+
+    /// Version of the layout of [`ApplicationObject::get_state`]
+    ///
+    /// Bump it whenever the state changes shape, and teach
+    /// [`ApplicationObject::migrate`] to upgrade the previous version so
+    /// snapshots saved by older builds still load.
+    fn schema_version(&self) -> u32 {
+        1
+    }
+
+    /// Upgrade state saved at `from_version` to `from_version + 1`
+    ///
+    /// Called once per step by [`load_versioned_state`]; the default knows
+    /// no migrations.
+    fn migrate(&self, from_version: u32, _state: Value) -> Result<Value> {
+        Err(ChaincraftError::SchemaVersion {
+            type_name: self.type_name().to_string(),
+            found: from_version,
+            expected: self.schema_version(),
+            hint: format!(
+                "implement {}::migrate for from_version {}",
+                self.type_name(),
+                from_version
+            ),
+        })
+    }
+
+    /// Replace the object's state with one produced by [`ApplicationObject::get_state`]
+    ///
+    /// The state is already migrated to [`ApplicationObject::schema_version`].
+    async fn load_state(&mut self, _state: Value) -> Result<()> {
+        Err(ChaincraftError::config(format!(
+            "{} cannot be restored from saved state; implement {}::load_state",
+            self.type_name(),
+            self.type_name()
+        )))
+    }
+
+    /// Release resources before the object is dropped from a registry
+    async fn on_delete(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Drop in-memory messages the node's retention no longer keeps
+    ///
+    /// Called after each pruning pass, see [`crate::retention`]. Returns how
+    /// many messages were dropped; the default keeps everything.
+    async fn compact(&mut self, _compaction: &Compaction) -> Result<usize> {
+        Ok(0)
+    }
+
     /// Clone the object
     fn clone_box(&self) -> Box<dyn ApplicationObject>;
 
@@ -67,6 +413,61 @@ pub trait ApplicationObject: Send + Sync + std::fmt::Debug {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+/// Application object built from a serde config
+///
+/// Lets every node of a network, or every run of an experiment, build the
+/// object from the same parameters, e.g. read from a file, instead of
+/// constructor arguments repeated in code. See
+/// [`ApplicationObjectRegistry::register_with_config`] and
+/// [`ChaincraftNode::register_object_with_config`](crate::ChaincraftNode::register_object_with_config).
+pub trait ConfigurableObject: ApplicationObject + Sized + 'static {
+    /// Settings the object is built from
+    type Config: DeserializeOwned;
+
+    /// Build the object from its settings
+    fn from_config(config: Self::Config) -> Result<Self>;
+
+    /// Build the object from settings in JSON
+    ///
+    /// Settings that do not parse as [`ConfigurableObject::Config`] are a
+    /// [`ChaincraftError::Config`] naming the object type.
+    fn from_config_value(config: Value) -> Result<Self> {
+        let config = serde_json::from_value(config).map_err(|e| {
+            ChaincraftError::config(format!(
+                "invalid config for {}: {}",
+                std::any::type_name::<Self>(),
+                e
+            ))
+        })?;
+        Self::from_config(config)
+    }
+}
+
+/// Load state saved at schema version `version` into an object
+///
+/// State older than the object's [`ApplicationObject::schema_version`] is
+/// passed through [`ApplicationObject::migrate`] one version at a time. State
+/// from a newer version is refused, as objects cannot migrate backwards.
+pub async fn load_versioned_state(
+    object: &mut dyn ApplicationObject,
+    version: u32,
+    mut state: Value,
+) -> Result<()> {
+    let current = object.schema_version();
+    if version > current {
+        return Err(ChaincraftError::SchemaVersion {
+            type_name: object.type_name().to_string(),
+            found: version,
+            expected: current,
+            hint: "the state was saved by a newer build and cannot be migrated back".to_string(),
+        });
+    }
+    for from_version in version..current {
+        state = object.migrate(from_version, state)?;
+    }
+    object.load_state(state).await
+}
+
 /// Simple shared number object for testing (equivalent to Python SimpleSharedNumber)
 #[derive(Debug, Clone)]
 pub struct SimpleSharedNumber {
@@ -77,7 +478,8 @@ pub struct SimpleSharedNumber {
     locked: bool,
     messages: Vec<SharedMessage>,
     seen_hashes: HashSet<String>,
-    digests: Vec<String>,
+    history: DigestHistory,
+    accumulator: DigestAccumulator,
 }
 
 impl SimpleSharedNumber {
@@ -90,7 +492,8 @@ impl SimpleSharedNumber {
             locked: false,
             messages: Vec::new(),
             seen_hashes: HashSet::new(),
-            digests: Vec::new(),
+            history: DigestHistory::new(),
+            accumulator: DigestAccumulator::new(),
         }
     }
 
@@ -134,25 +537,26 @@ impl ApplicationObject for SimpleSharedNumber {
         Ok(message.data.is_i64())
     }
 
-    async fn add_message(&mut self, message: SharedMessage) -> Result<()> {
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
         // Deduplicate by hashing the message's data field
         let msg_hash = Self::calculate_message_hash(&message.data);
 
         if self.seen_hashes.contains(&msg_hash) {
-            // Already processed this data
-            return Ok(());
+            return Ok(ApplyOutcome::ignored("duplicate", "data already applied"));
         }
 
-        self.seen_hashes.insert(msg_hash);
-
         // Extract the integer value and add to our number
-        if let Some(value) = message.data.as_i64() {
-            self.number += value;
-            self.messages.push(message);
-            tracing::info!("SimpleSharedNumber: Added message with data: {}", value);
-        }
+        let Some(value) = message.data.as_i64() else {
+            return Ok(ApplyOutcome::rejected("not_an_integer", "data must be an integer"));
+        };
 
-        Ok(())
+        self.seen_hashes.insert(msg_hash);
+        self.number += value;
+        self.accumulator.apply(&message);
+        self.messages.push(message);
+        tracing::info!("SimpleSharedNumber: Added message with data: {}", value);
+
+        Ok(ApplyOutcome::Applied)
     }
 
     fn is_merkleized(&self) -> bool {
@@ -160,28 +564,33 @@ impl ApplicationObject for SimpleSharedNumber {
     }
 
     async fn get_latest_digest(&self) -> Result<String> {
-        Ok(self.number.to_string())
+        Ok(self.accumulator.digest().to_string())
     }
 
     async fn has_digest(&self, digest: &str) -> Result<bool> {
-        Ok(self.digests.contains(&digest.to_string()))
+        Ok(self.accumulator.contains(digest))
     }
 
     async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
         Ok(true)
     }
 
-    async fn add_digest(&mut self, digest: String) -> Result<bool> {
-        self.digests.push(digest);
-        Ok(true)
+    fn digest_history(&self) -> Option<&DigestHistory> {
+        Some(&self.history)
     }
 
-    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new())
+    fn digest_history_mut(&mut self) -> Option<&mut DigestHistory> {
+        Some(&mut self.history)
     }
 
-    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
-        Ok(Vec::new())
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.accumulator
+            .messages_since(digest)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown digest {}", digest)))
     }
 
     async fn get_state(&self) -> Result<Value> {
@@ -196,7 +605,8 @@ impl ApplicationObject for SimpleSharedNumber {
         self.number = 0;
         self.messages.clear();
         self.seen_hashes.clear();
-        self.digests.clear();
+        self.history.clear();
+        self.accumulator.reset();
         Ok(())
     }
 
@@ -213,11 +623,99 @@ impl ApplicationObject for SimpleSharedNumber {
     }
 }
 
+/// Deadline for an object to handle one message
+///
+/// An object that hangs in [`ApplicationObject::is_valid`] or
+/// [`ApplicationObject::add_message`] would hold the registry, and with it
+/// every other object, forever. With a deadline the registry gives up on the
+/// object after `limit`, and after `unhealthy_after` timeouts in a row stops
+/// routing messages to it. A message the object gave up on may have been
+/// partly applied. Not enforced on `wasm32`, which has no timers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessingTimeout {
+    /// Time an object gets to validate and apply one message
+    pub limit: Duration,
+    /// Timeouts in a row after which the object is marked unhealthy
+    pub unhealthy_after: u32,
+}
+
+impl ProcessingTimeout {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            unhealthy_after: 3,
+        }
+    }
+
+    pub fn with_unhealthy_after(mut self, unhealthy_after: u32) -> Self {
+        self.unhealthy_after = unhealthy_after.max(1);
+        self
+    }
+}
+
+/// How an object kept to its [`ProcessingTimeout`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectHealth {
+    /// Messages the object timed out on
+    pub timeouts: u64,
+    /// Timeouts since the object last handled a message in time
+    pub consecutive_timeouts: u32,
+    /// When the object was marked unhealthy; it receives no messages since
+    pub unhealthy_since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ObjectHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.unhealthy_since.is_none()
+    }
+}
+
+/// An object that did not handle a message within its deadline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectTimeout {
+    pub object_id: SharedObjectId,
+    pub type_name: String,
+    /// Hash of the message the object timed out on
+    pub hash: String,
+    /// Timeouts in a row, this one included
+    pub consecutive: u32,
+    /// Whether this timeout got the object marked unhealthy
+    pub now_unhealthy: bool,
+}
+
+/// Run `processing` to completion or until `limit`; `None` if it timed out
+async fn within<T>(limit: Option<Duration>, processing: impl Future<Output = T>) -> Option<T> {
+    match limit {
+        #[cfg(not(target_arch = "wasm32"))]
+        Some(limit) => tokio::time::timeout(limit, processing).await.ok(),
+        _ => Some(processing.await),
+    }
+}
+
+/// Shared handle to a typed object held in a node's registry
+pub type TypedRef<T> = tokio::sync::OwnedRwLockReadGuard<ApplicationObjectRegistry, T>;
+
+/// Exclusive handle to a typed object held in a node's registry
+pub type TypedMut<T> = tokio::sync::OwnedRwLockMappedWriteGuard<ApplicationObjectRegistry, T>;
+
 /// Registry for managing application objects
 #[derive(Debug)]
 pub struct ApplicationObjectRegistry {
     objects: HashMap<SharedObjectId, Box<dyn ApplicationObject>>,
     objects_by_type: HashMap<String, Vec<SharedObjectId>>,
+    last_active: HashMap<SharedObjectId, chrono::DateTime<chrono::Utc>>,
+    info: HashMap<SharedObjectId, ObjectInfo>,
+    directory: ObjectDirectory,
+    /// Objects whose published snapshot may be out of date
+    unpublished: HashSet<SharedObjectId>,
+    /// Messages held back from objects being replaced, see [`Self::begin_swap`]
+    swapping: HashMap<SharedObjectId, Vec<SharedMessage>>,
+    /// Deadline for each object to handle a message, if any
+    processing_timeout: Option<ProcessingTimeout>,
+    /// Timeouts of objects that missed their deadline at least once
+    health: HashMap<SharedObjectId, ObjectHealth>,
+    /// Timeouts not yet collected with [`Self::take_timeouts`]
+    timeouts: Vec<ObjectTimeout>,
 }
 
 impl ApplicationObjectRegistry {
@@ -225,28 +723,248 @@ impl ApplicationObjectRegistry {
         Self {
             objects: HashMap::new(),
             objects_by_type: HashMap::new(),
+            last_active: HashMap::new(),
+            info: HashMap::new(),
+            directory: ObjectDirectory::new(),
+            unpublished: HashSet::new(),
+            swapping: HashMap::new(),
+            processing_timeout: None,
+            health: HashMap::new(),
+            timeouts: Vec::new(),
+        }
+    }
+
+    /// Give up on objects that take longer than the deadline on a message
+    pub fn with_processing_timeout(mut self, timeout: ProcessingTimeout) -> Self {
+        self.processing_timeout = Some(timeout);
+        self
+    }
+
+    pub fn processing_timeout(&self) -> Option<ProcessingTimeout> {
+        self.processing_timeout
+    }
+
+    /// Timeouts of an object, `None` if it never missed its deadline
+    pub fn health(&self, id: &SharedObjectId) -> Option<&ObjectHealth> {
+        self.health.get(id)
+    }
+
+    /// Whether messages are routed to the object
+    pub fn is_healthy(&self, id: &SharedObjectId) -> bool {
+        self.health.get(id).is_none_or(ObjectHealth::is_healthy)
+    }
+
+    /// Objects no longer receiving messages after timing out too often
+    pub fn unhealthy_objects(&self) -> Vec<SharedObjectId> {
+        let mut ids: Vec<SharedObjectId> = self
+            .health
+            .iter()
+            .filter(|(_, health)| !health.is_healthy())
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    }
+
+    /// Route messages to an unhealthy object again, e.g. once it was fixed
+    ///
+    /// Returns whether the object was unhealthy.
+    pub fn restore_health(&mut self, id: &SharedObjectId) -> bool {
+        match self.health.get_mut(id) {
+            Some(health) if !health.is_healthy() => {
+                health.unhealthy_since = None;
+                health.consecutive_timeouts = 0;
+                true
+            },
+            _ => false,
         }
     }
 
+    /// Timeouts since the last call, oldest first
+    pub fn take_timeouts(&mut self) -> Vec<ObjectTimeout> {
+        std::mem::take(&mut self.timeouts)
+    }
+
     /// Register a new application object
-    pub fn register(&mut self, object: Box<dyn ApplicationObject>) -> SharedObjectId {
+    ///
+    /// The object receives the registry's [`ObjectDirectory`]; its own state
+    /// shows up there once published, see [`Self::publish_pending`].
+    pub fn register(&mut self, mut object: Box<dyn ApplicationObject>) -> SharedObjectId {
         let id = object.id().clone();
         let type_name = object.type_name().to_string();
+        object.attach_directory(self.directory.clone());
+        self.unpublished.insert(id.clone());
 
         self.objects_by_type
             .entry(type_name)
             .or_default()
             .push(id.clone());
 
+        self.last_active.insert(id.clone(), chrono::Utc::now());
+        self.info.entry(id.clone()).or_default();
         self.objects.insert(id.clone(), object);
         id
     }
 
+    /// Build an object of type `T` from `config` and register it
+    pub fn register_with_config<T: ConfigurableObject>(
+        &mut self,
+        config: Value,
+    ) -> Result<SharedObjectId> {
+        Ok(self.register(Box::new(T::from_config_value(config)?)))
+    }
+
+    /// Mark an object as active now
+    pub fn touch(&mut self, id: &SharedObjectId) {
+        if let Some(last_active) = self.last_active.get_mut(id) {
+            *last_active = chrono::Utc::now();
+        }
+    }
+
+    /// When an object last registered or processed a message
+    pub fn last_active(&self, id: &SharedObjectId) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.last_active.get(id).copied()
+    }
+
+    /// Applied and rejected message counts of an object, see [`ObjectInfo`]
+    pub fn info(&self, id: &SharedObjectId) -> Option<&ObjectInfo> {
+        self.info.get(id)
+    }
+
+    /// Carry on from bookkeeping saved earlier, e.g. before a restart
+    pub fn restore_info(&mut self, id: &SharedObjectId, info: ObjectInfo) {
+        if self.objects.contains_key(id) {
+            self.info.insert(id.clone(), info);
+        }
+    }
+
+    /// Objects that have been inactive for longer than `idle_timeout`
+    pub fn idle_objects(&self, idle_timeout: std::time::Duration) -> Vec<SharedObjectId> {
+        let Ok(idle_timeout) = chrono::Duration::from_std(idle_timeout) else {
+            return Vec::new();
+        };
+        let cutoff = chrono::Utc::now() - idle_timeout;
+        self.last_active
+            .iter()
+            .filter(|(_, last_active)| **last_active <= cutoff)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
     /// Get an object by ID
     pub fn get(&self, id: &SharedObjectId) -> Option<&dyn ApplicationObject> {
         self.objects.get(id).map(|obj| obj.as_ref())
     }
 
+    /// Get an object by ID, downcast to its concrete type
+    pub fn get_typed<T: ApplicationObject + 'static>(&self, id: &SharedObjectId) -> Option<&T> {
+        self.objects
+            .get(id)
+            .and_then(|obj| obj.as_any().downcast_ref::<T>())
+    }
+
+    /// Get a mutable object by ID, downcast to its concrete type
+    ///
+    /// The object's published snapshot is refreshed on the next
+    /// [`Self::publish_pending`] or message.
+    pub fn get_typed_mut<T: ApplicationObject + 'static>(
+        &mut self,
+        id: &SharedObjectId,
+    ) -> Option<&mut T> {
+        if self.objects.contains_key(id) {
+            self.unpublished.insert(id.clone());
+        }
+        self.objects
+            .get_mut(id)
+            .and_then(|obj| obj.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Run `f` on an object in place, if it exists and has type `T`
+    pub fn with_typed_mut<T: ApplicationObject + 'static, R>(
+        &mut self,
+        id: &SharedObjectId,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Option<R> {
+        let result = self.get_typed_mut::<T>(id).map(f);
+        if result.is_some() {
+            self.touch(id);
+        }
+        result
+    }
+
+    /// Run a periodic task of an object, see [`ApplicationObject::run_task`]
+    ///
+    /// `None` if no object has this id. The object counts as active and its
+    /// snapshot is refreshed on the next [`Self::publish_pending`].
+    pub async fn run_task(
+        &mut self,
+        id: &SharedObjectId,
+        name: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<Vec<Value>>> {
+        let Some(object) = self.objects.get_mut(id) else {
+            return Ok(None);
+        };
+        let created = object.run_task(name, now).await?;
+        self.unpublished.insert(id.clone());
+        self.touch(id);
+        Ok(Some(created))
+    }
+
+    /// Let every object drop the messages `compaction` covers
+    ///
+    /// Returns the number of messages dropped. Objects that dropped some get
+    /// their snapshot refreshed on the next [`Self::publish_pending`].
+    pub async fn compact(&mut self, compaction: &Compaction) -> Result<usize> {
+        let mut dropped = 0;
+        for (id, object) in self.objects.iter_mut() {
+            let count = object.compact(compaction).await?;
+            if count > 0 {
+                self.unpublished.insert(id.clone());
+                dropped += count;
+            }
+        }
+        Ok(dropped)
+    }
+
+    /// Get the IDs of all objects of a specific type
+    pub fn ids_by_type(&self, type_name: &str) -> Vec<SharedObjectId> {
+        self.objects_by_type
+            .get(type_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Locator over an object's digest history, see [`DigestHistory::locator`]
+    pub fn digest_locator(&self, id: &SharedObjectId) -> Option<Vec<String>> {
+        self.objects
+            .get(id)?
+            .digest_history()
+            .map(DigestHistory::locator)
+    }
+
+    /// Most recent digest of an object that also appears in a peer's locator
+    pub fn common_digest(&self, id: &SharedObjectId, locator: &[String]) -> Option<StateDigest> {
+        self.objects
+            .get(id)?
+            .digest_history()?
+            .find_common(locator)
+            .cloned()
+    }
+
+    /// Messages an object applied after `digest`, see
+    /// [`ApplicationObject::get_messages_since_digest`]
+    pub async fn messages_since_digest(
+        &self,
+        id: &SharedObjectId,
+        digest: &str,
+    ) -> Result<Vec<SharedMessage>> {
+        match self.objects.get(id) {
+            Some(object) => object.get_messages_since_digest(digest).await,
+            None => Err(ChaincraftError::config(format!("no object {} is registered", id.short()))),
+        }
+    }
+
     /// Get all objects of a specific type (returning owned clones for safety)
     pub fn get_by_type(&self, type_name: &str) -> Vec<Box<dyn ApplicationObject>> {
         self.objects_by_type
@@ -263,6 +981,12 @@ impl ApplicationObjectRegistry {
     /// Remove an object
     pub fn remove(&mut self, id: &SharedObjectId) -> Option<Box<dyn ApplicationObject>> {
         if let Some(object) = self.objects.remove(id) {
+            self.last_active.remove(id);
+            self.info.remove(id);
+            self.unpublished.remove(id);
+            self.swapping.remove(id);
+            self.health.remove(id);
+            self.directory.remove(id);
             let type_name = object.type_name().to_string();
             if let Some(type_list) = self.objects_by_type.get_mut(&type_name) {
                 type_list.retain(|obj_id| obj_id != id);
@@ -276,6 +1000,20 @@ impl ApplicationObjectRegistry {
         }
     }
 
+    /// Remove an object and let it release its resources
+    pub async fn deregister(
+        &mut self,
+        id: &SharedObjectId,
+    ) -> Result<Option<Box<dyn ApplicationObject>>> {
+        match self.remove(id) {
+            Some(mut object) => {
+                object.on_delete().await?;
+                Ok(Some(object))
+            },
+            None => Ok(None),
+        }
+    }
+
     /// Get all object IDs
     pub fn ids(&self) -> Vec<SharedObjectId> {
         self.objects.keys().cloned().collect()
@@ -295,34 +1033,275 @@ impl ApplicationObjectRegistry {
     pub fn clear(&mut self) {
         self.objects.clear();
         self.objects_by_type.clear();
+        self.last_active.clear();
+        self.info.clear();
+        self.unpublished.clear();
+        self.swapping.clear();
+        self.health.clear();
+        self.timeouts.clear();
+        self.directory.clear();
+    }
+
+    /// Start replacing an object with a new implementation of the same type
+    ///
+    /// Returns the object's schema version and state for the replacement to
+    /// load. Until [`Self::finish_swap`] or [`Self::abort_swap`], messages the
+    /// object accepts are queued instead of applied.
+    pub async fn begin_swap(
+        &mut self,
+        id: &SharedObjectId,
+        type_name: &str,
+    ) -> Result<(u32, Value)> {
+        let Some(object) = self.objects.get(id) else {
+            return Err(ChaincraftError::config(format!("no object {} is registered", id.short())));
+        };
+        if object.type_name() != type_name {
+            return Err(ChaincraftError::config(format!(
+                "a {} cannot be replaced by a {}",
+                object.type_name(),
+                type_name
+            )));
+        }
+        if self.swapping.contains_key(id) {
+            return Err(ChaincraftError::config(format!(
+                "object {} is already being replaced",
+                id.short()
+            )));
+        }
+        let state = (object.schema_version(), object.get_state().await?);
+        self.swapping.insert(id.clone(), Vec::new());
+        Ok(state)
+    }
+
+    /// Keep the old object after all, applying the messages queued meanwhile
+    pub async fn abort_swap(&mut self, id: &SharedObjectId) -> Result<Vec<Receipt>> {
+        let queued = self.swapping.remove(id).unwrap_or_default();
+        self.replay(id, queued).await
+    }
+
+    /// Put `replacement` in the place of the object being swapped
+    ///
+    /// The replacement inherits the old object's digest history when both
+    /// keep one and its [`ObjectInfo`], then receives the messages queued
+    /// during the swap. Returns
+    /// the old object and a receipt for every replayed message.
+    pub async fn finish_swap(
+        &mut self,
+        id: &SharedObjectId,
+        mut replacement: Box<dyn ApplicationObject>,
+    ) -> Result<(Box<dyn ApplicationObject>, Vec<Receipt>)> {
+        let Some(queued) = self.swapping.remove(id) else {
+            return Err(ChaincraftError::config(format!(
+                "object {} is not being replaced",
+                id.short()
+            )));
+        };
+        let info = self.info.get(id).cloned().unwrap_or_default();
+        let Some(old) = self.remove(id) else {
+            return Err(ChaincraftError::config(format!("no object {} is registered", id.short())));
+        };
+        if let (Some(history), Some(inherited)) =
+            (old.digest_history(), replacement.digest_history_mut())
+        {
+            *inherited = history.clone();
+        }
+        if let (Some(log), Some(inherited)) = (old.message_log(), replacement.message_log_mut()) {
+            *inherited = log.clone();
+        }
+        let new_id = self.register(replacement);
+        self.info.insert(new_id.clone(), info);
+        self.publish_pending().await?;
+        let receipts = self.replay(&new_id, queued).await?;
+        Ok((old, receipts))
+    }
+
+    async fn replay(
+        &mut self,
+        id: &SharedObjectId,
+        queued: Vec<SharedMessage>,
+    ) -> Result<Vec<Receipt>> {
+        let mut receipts = Vec::new();
+        for message in queued {
+            let mut receipt = ReceiptBuilder::new(message.hash.clone());
+            self.apply_to(id, &message, &mut receipt).await?;
+            receipts.push(receipt.finish());
+        }
+        Ok(receipts)
+    }
+
+    /// Read-only view of the registered objects, as handed to each of them
+    pub fn directory(&self) -> ObjectDirectory {
+        self.directory.clone()
+    }
+
+    /// Publish the state of objects registered or borrowed mutably since
+    /// their last snapshot
+    pub async fn publish_pending(&mut self) -> Result<()> {
+        for id in std::mem::take(&mut self.unpublished) {
+            if let Some(object) = self.objects.get(&id) {
+                let digest = object.get_latest_digest().await?;
+                Self::publish(&self.directory, &id, object.as_ref(), digest).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn publish(
+        directory: &ObjectDirectory,
+        id: &SharedObjectId,
+        object: &dyn ApplicationObject,
+        digest: String,
+    ) -> Result<()> {
+        directory.publish(ObjectSnapshot {
+            id: id.clone(),
+            type_name: object.type_name().to_string(),
+            state: object.get_state().await?,
+            digest,
+        });
+        Ok(())
+    }
+
+    /// Ask every object that accepts a message whether it may be relayed
+    ///
+    /// The first veto wins; a message no object accepts is relayed.
+    pub async fn relay_decision(&self, message: &SharedMessage) -> Result<RelayDecision> {
+        let mut ids: Vec<&SharedObjectId> = self.objects.keys().collect();
+        ids.sort_by_key(|id| id.to_string());
+        for id in ids {
+            if !self.is_healthy(id) {
+                continue;
+            }
+            let object = &self.objects[id];
+            if !object.is_valid(message).await? {
+                continue;
+            }
+            let decision = object.relay_policy(message).await?;
+            if !decision.is_relayed() {
+                return Ok(decision);
+            }
+        }
+        Ok(RelayDecision::Relay)
     }
 
     /// Process a message against all appropriate objects
-    pub async fn process_message(&mut self, message: SharedMessage) -> Result<Vec<SharedObjectId>> {
-        let mut processed_objects = Vec::new();
+    ///
+    /// Returns the outcome for every object that accepted the message as valid.
+    pub async fn process_message(
+        &mut self,
+        message: SharedMessage,
+    ) -> Result<Vec<(SharedObjectId, ApplyOutcome)>> {
+        Ok(self.execute_message(message).await?.outcomes)
+    }
+
+    /// Process a message and describe what it did in a [`Receipt`]
+    pub async fn execute_message(&mut self, message: SharedMessage) -> Result<Receipt> {
+        let mut receipt = ReceiptBuilder::new(message.hash.clone());
+        self.publish_pending().await?;
 
         // Get all object IDs first to avoid borrow checker issues
         let ids: Vec<SharedObjectId> = self.objects.keys().cloned().collect();
 
         // Process each object sequentially
         for id in ids {
-            // Check validity first
-            let is_valid = if let Some(object) = self.objects.get(&id) {
-                object.is_valid(&message).await?
-            } else {
-                false
-            };
-
-            // If valid, add the message
-            if is_valid {
-                if let Some(object) = self.objects.get_mut(&id) {
-                    object.add_message(message.clone()).await?;
-                    processed_objects.push(id);
+            if !self.is_healthy(&id) {
+                continue;
+            }
+            if let Some(queue) = self.swapping.get_mut(&id) {
+                // Held for the replacement, if the current logic accepts it
+                if self.objects[&id].is_valid(&message).await? {
+                    queue.push(message.clone());
                 }
+                continue;
             }
+            self.apply_to(&id, &message, &mut receipt).await?;
         }
 
-        Ok(processed_objects)
+        Ok(receipt.finish())
+    }
+
+    /// Apply a message to one object if it is valid there
+    async fn apply_to(
+        &mut self,
+        id: &SharedObjectId,
+        message: &SharedMessage,
+        receipt: &mut ReceiptBuilder,
+    ) -> Result<()> {
+        let Some(object) = self.objects.get_mut(id) else {
+            return Ok(());
+        };
+        let limit = self.processing_timeout.map(|timeout| timeout.limit);
+        let processed = within(limit, async {
+            if !object.is_valid(message).await? {
+                return Ok(None);
+            }
+            let digest_before = object.get_latest_digest().await?;
+            let outcome = object.add_message(message.clone()).await?;
+            Ok::<_, ChaincraftError>(Some((digest_before, outcome)))
+        })
+        .await;
+        let Some(processed) = processed else {
+            self.record_timeout(id, message);
+            return Ok(());
+        };
+        let Some((digest_before, outcome)) = processed? else {
+            return Ok(());
+        };
+        let execution = object.take_execution();
+        let digest_after = object.get_latest_digest().await?;
+        if outcome.is_applied() {
+            if let Some(history) = object.digest_history_mut() {
+                if history.is_empty() {
+                    history.record(digest_before.clone());
+                }
+                history.record(digest_after.clone());
+            }
+            if let Some(log) = object.message_log_mut() {
+                log.record(&digest_before, &digest_after, message.clone());
+            }
+            Self::publish(&self.directory, id, object.as_ref(), digest_after.clone()).await?;
+        }
+        let info = self.info.entry(id.clone()).or_default();
+        if outcome.is_applied() {
+            info.applied += 1;
+            info.last_applied = Some(chrono::Utc::now());
+            info.last_digest = Some(digest_after.clone());
+        } else if outcome.is_rejected() {
+            info.rejected += 1;
+        }
+        if let Some(health) = self.health.get_mut(id) {
+            health.consecutive_timeouts = 0;
+        }
+        receipt.record(id.clone(), outcome, execution, &digest_before, &digest_after);
+        self.touch(id);
+        Ok(())
+    }
+
+    /// Count a timeout of object `id` on `message`, marking it unhealthy
+    /// after too many in a row
+    fn record_timeout(&mut self, id: &SharedObjectId, message: &SharedMessage) {
+        let Some(timeout) = self.processing_timeout else {
+            return;
+        };
+        let type_name = self
+            .objects
+            .get(id)
+            .map(|object| object.type_name().to_string())
+            .unwrap_or_default();
+        let health = self.health.entry(id.clone()).or_default();
+        health.timeouts += 1;
+        health.consecutive_timeouts += 1;
+        let now_unhealthy =
+            health.is_healthy() && health.consecutive_timeouts >= timeout.unhealthy_after;
+        if now_unhealthy {
+            health.unhealthy_since = Some(chrono::Utc::now());
+        }
+        self.timeouts.push(ObjectTimeout {
+            object_id: id.clone(),
+            type_name,
+            hash: message.hash.clone(),
+            consecutive: health.consecutive_timeouts,
+            now_unhealthy,
+        });
     }
 }
 
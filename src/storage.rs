@@ -1,8 +1,11 @@
 //! Storage implementation for chain data
 
-use crate::error::Result;
+use crate::error::{ChaincraftError, Result, SerializationError, StorageError};
+use crate::shared::{MessageType, SharedMessage, SharedObjectId};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Trait for key-value storage backends
 #[async_trait]
@@ -13,6 +16,35 @@ pub trait Storage: Send + Sync {
     async fn exists(&self, key: &str) -> Result<bool>;
     async fn clear(&self) -> Result<()>;
     async fn initialize(&self) -> Result<()>;
+
+    /// Apply several writes at once; `None` deletes the key
+    ///
+    /// Backends that can take a single lock or transaction for the whole
+    /// batch should override this.
+    async fn write_batch(&self, batch: Vec<(String, Option<Vec<u8>>)>) -> Result<()> {
+        for (key, value) in batch {
+            match value {
+                Some(value) => self.put(&key, value).await?,
+                None => self.delete(&key).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Make buffered writes durable
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Keys starting with `prefix`, in no particular order
+    ///
+    /// Backends that cannot enumerate their keys keep the default, which
+    /// fails.
+    async fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        Err(ChaincraftError::Storage(StorageError::DatabaseOperation {
+            reason: format!("listing keys under '{}' is not supported", prefix),
+        }))
+    }
 }
 
 /// In-memory storage implementation
@@ -61,4 +93,360 @@ impl Storage for MemoryStorage {
         // In-memory storage doesn't need initialization
         Ok(())
     }
+
+    async fn write_batch(&self, batch: Vec<(String, Option<Vec<u8>>)>) -> Result<()> {
+        let mut data = self.data.write().await;
+        for (key, value) in batch {
+            match value {
+                Some(value) => data.insert(key, value),
+                None => data.remove(&key),
+            };
+        }
+        Ok(())
+    }
+
+    async fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let data = self.data.read().await;
+        Ok(data
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Storage wrapper refusing writes beyond a byte budget
+///
+/// Entries are counted as the size of each key plus its value. Overwrites are
+/// charged the difference. Entries already in the inner storage, such as a
+/// reopened database, are counted by [`Storage::initialize`].
+pub struct LimitedStorage {
+    inner: Arc<dyn Storage>,
+    max_bytes: u64,
+    usage: tokio::sync::Mutex<StorageUsage>,
+}
+
+#[derive(Debug, Default)]
+struct StorageUsage {
+    sizes: HashMap<String, u64>,
+    used: u64,
+}
+
+impl std::fmt::Debug for LimitedStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LimitedStorage")
+            .field("max_bytes", &self.max_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LimitedStorage {
+    pub fn new(inner: Arc<dyn Storage>, max_bytes: u64) -> Self {
+        Self {
+            inner,
+            max_bytes,
+            usage: tokio::sync::Mutex::new(StorageUsage::default()),
+        }
+    }
+
+    /// Bytes currently charged against the budget
+    pub async fn used_bytes(&self) -> u64 {
+        self.usage.lock().await.used
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+}
+
+#[async_trait]
+impl Storage for LimitedStorage {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.inner.get(key).await
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        let mut usage = self.usage.lock().await;
+        let size = (key.len() + value.len()) as u64;
+        let previous = usage.sizes.get(key).copied().unwrap_or(0);
+        let requested = usage.used - previous + size;
+        if requested > self.max_bytes {
+            return Err(ChaincraftError::ResourceExhausted {
+                resource: crate::resources::Resource::StorageBytes,
+                requested,
+                limit: self.max_bytes,
+            });
+        }
+        self.inner.put(key, value).await?;
+        usage.sizes.insert(key.to_string(), size);
+        usage.used = requested;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let mut usage = self.usage.lock().await;
+        self.inner.delete(key).await?;
+        if let Some(size) = usage.sizes.remove(key) {
+            usage.used -= size;
+        }
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut usage = self.usage.lock().await;
+        self.inner.clear().await?;
+        *usage = StorageUsage::default();
+        Ok(())
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let mut usage = self.usage.lock().await;
+        self.inner.initialize().await?;
+        let mut existing = StorageUsage::default();
+        for key in self.inner.keys_with_prefix("").await? {
+            if let Some(value) = self.inner.get(&key).await? {
+                let size = (key.len() + value.len()) as u64;
+                existing.used += size;
+                existing.sizes.insert(key, size);
+            }
+        }
+        *usage = existing;
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        self.inner.keys_with_prefix(prefix).await
+    }
+}
+
+/// Storage on disk in a sled database, for nodes that must survive restarts
+#[cfg(feature = "persistent")]
+#[derive(Debug, Clone)]
+pub struct SledStorage {
+    db: sled::Db,
+}
+
+#[cfg(feature = "persistent")]
+fn sled_error(e: sled::Error) -> ChaincraftError {
+    ChaincraftError::Storage(StorageError::DatabaseOperation {
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(feature = "persistent")]
+impl SledStorage {
+    /// Open the database in the directory `path`, creating it if needed
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let db = sled::open(path).map_err(sled_error)?;
+        Ok(Self { db })
+    }
+}
+
+#[cfg(feature = "persistent")]
+#[async_trait]
+impl Storage for SledStorage {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get(key).map_err(sled_error)?.map(|v| v.to_vec()))
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        self.db.insert(key, value).map_err(sled_error)?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.db.remove(key).map_err(sled_error)?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.db.contains_key(key).map_err(sled_error)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.db.clear().map_err(sled_error)
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn write_batch(&self, batch: Vec<(String, Option<Vec<u8>>)>) -> Result<()> {
+        let mut sled_batch = sled::Batch::default();
+        for (key, value) in batch {
+            match value {
+                Some(value) => sled_batch.insert(key.as_bytes(), value),
+                None => sled_batch.remove(key.as_bytes()),
+            }
+        }
+        self.db.apply_batch(sled_batch).map_err(sled_error)
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.db.flush_async().await.map_err(sled_error)?;
+        Ok(())
+    }
+
+    async fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        self.db
+            .scan_prefix(prefix)
+            .keys()
+            .map(|key| {
+                let key = key.map_err(sled_error)?;
+                String::from_utf8(key.to_vec()).map_err(|e| {
+                    ChaincraftError::Storage(StorageError::DatabaseOperation {
+                        reason: format!("key is not UTF-8: {}", e),
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+/// First byte of binary-encoded messages; JSON entries always start with `{`
+const BINARY_MAGIC: u8 = 0xB2;
+
+/// First byte of binary entries written before messages recorded their sender
+const BINARY_MAGIC_V1: u8 = 0xB1;
+
+/// How messages are written to storage
+///
+/// Reads detect the encoding of each entry, so a node can switch encodings
+/// without rewriting what it already stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageEncoding {
+    /// Human-readable JSON, as produced by [`SharedMessage::to_json`]
+    #[default]
+    Json,
+    /// Compact bincode record
+    Binary,
+}
+
+/// Bincode-friendly mirror of [`SharedMessage`]
+///
+/// Bincode cannot decode self-describing values, so the message type and
+/// payload are kept as JSON text while ids, hash and timestamp are packed.
+#[derive(Serialize, Deserialize)]
+struct BinaryMessage {
+    id: [u8; 16],
+    message_type: String,
+    target_id: Option<[u8; 16]>,
+    data: String,
+    timestamp_secs: i64,
+    timestamp_nanos: u32,
+    signature: Option<Vec<u8>>,
+    hash: Vec<u8>,
+    pow_nonce: Option<u64>,
+    sender: Option<String>,
+}
+
+/// Layout of [`BINARY_MAGIC_V1`] entries
+#[derive(Deserialize)]
+struct BinaryMessageV1 {
+    id: [u8; 16],
+    message_type: String,
+    target_id: Option<[u8; 16]>,
+    data: String,
+    timestamp_secs: i64,
+    timestamp_nanos: u32,
+    signature: Option<Vec<u8>>,
+    hash: Vec<u8>,
+    pow_nonce: Option<u64>,
+}
+
+impl From<BinaryMessageV1> for BinaryMessage {
+    fn from(v1: BinaryMessageV1) -> Self {
+        Self {
+            id: v1.id,
+            message_type: v1.message_type,
+            target_id: v1.target_id,
+            data: v1.data,
+            timestamp_secs: v1.timestamp_secs,
+            timestamp_nanos: v1.timestamp_nanos,
+            signature: v1.signature,
+            hash: v1.hash,
+            pow_nonce: v1.pow_nonce,
+            sender: None,
+        }
+    }
+}
+
+impl MessageEncoding {
+    /// Encoding of a stored entry
+    pub fn detect(bytes: &[u8]) -> Self {
+        if matches!(bytes.first(), Some(&BINARY_MAGIC) | Some(&BINARY_MAGIC_V1)) {
+            MessageEncoding::Binary
+        } else {
+            MessageEncoding::Json
+        }
+    }
+
+    /// Encode a message for storage
+    ///
+    /// Messages whose hash is not lowercase hex cannot be packed and fall back
+    /// to JSON.
+    pub fn encode(self, message: &SharedMessage) -> Result<Vec<u8>> {
+        let hash = match (self, hex::decode(&message.hash)) {
+            (MessageEncoding::Binary, Ok(hash)) if hex::encode(&hash) == message.hash => hash,
+            _ => return Ok(message.to_json()?.into_bytes()),
+        };
+        let record = BinaryMessage {
+            id: *message.id.as_uuid().as_bytes(),
+            message_type: serde_json::to_string(&message.message_type)?,
+            target_id: message
+                .target_id
+                .as_ref()
+                .map(|id| *id.as_uuid().as_bytes()),
+            data: serde_json::to_string(&message.data)?,
+            timestamp_secs: message.timestamp.timestamp(),
+            timestamp_nanos: message.timestamp.timestamp_subsec_nanos(),
+            signature: message.signature.clone(),
+            hash,
+            pow_nonce: message.pow_nonce,
+            sender: message.sender.clone(),
+        };
+        let mut bytes = vec![BINARY_MAGIC];
+        bincode::serialize_into(&mut bytes, &record)
+            .map_err(|e| ChaincraftError::Serialization(SerializationError::Binary(e)))?;
+        Ok(bytes)
+    }
+
+    /// Decode a stored message in either encoding
+    pub fn decode(bytes: &[u8]) -> Result<SharedMessage> {
+        if Self::detect(bytes) == MessageEncoding::Json {
+            return SharedMessage::from_json(&String::from_utf8_lossy(bytes));
+        }
+        let record: BinaryMessage = if bytes[0] == BINARY_MAGIC_V1 {
+            bincode::deserialize::<BinaryMessageV1>(&bytes[1..]).map(BinaryMessage::from)
+        } else {
+            bincode::deserialize(&bytes[1..])
+        }
+        .map_err(|e| ChaincraftError::Serialization(SerializationError::Binary(e)))?;
+        let timestamp =
+            chrono::DateTime::from_timestamp(record.timestamp_secs, record.timestamp_nanos)
+                .ok_or_else(|| ChaincraftError::validation("stored timestamp out of range"))?;
+        Ok(SharedMessage {
+            id: SharedObjectId::from_uuid(uuid::Uuid::from_bytes(record.id)),
+            message_type: serde_json::from_str::<MessageType>(&record.message_type)?,
+            target_id: record
+                .target_id
+                .map(|id| SharedObjectId::from_uuid(uuid::Uuid::from_bytes(id))),
+            data: serde_json::from_str(&record.data)?,
+            timestamp,
+            signature: record.signature,
+            sender: record.sender,
+            hash: hex::encode(record.hash),
+            pow_nonce: record.pow_nonce,
+            simulated: false,
+        })
+    }
 }
@@ -0,0 +1,233 @@
+//! Peer synchronization status
+//!
+//! Nodes periodically tell their peers how many messages their log holds. The
+//! [`SyncTracker`] compares those sequence numbers with the local log and
+//! classifies every peer as [`SyncStatus::Behind`], [`SyncStatus::InSync`] or
+//! [`SyncStatus::Ahead`], reporting transitions so callers can publish them as
+//! events instead of polling.
+//!
+//! Announcements also carry the latest digest of each object. Two nodes whose
+//! objects reached different digests after the same number of messages have
+//! forked, which in a deterministic protocol points at a bug; the tracker
+//! keeps a [`ForkReport`] for each one found.
+//!
+//! Objects that sync by Merkle root build on [`merkle`].
+
+pub mod merkle;
+
+use crate::network::PeerId;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+
+/// Custom message type announcing a node's message log length
+pub const SYNC_STATUS_MESSAGE_TYPE: &str = "SYNC_STATUS";
+
+/// Data of a [`SYNC_STATUS_MESSAGE_TYPE`] message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SyncAnnouncement {
+    /// Number of messages in the sender's log
+    pub sequence: u64,
+    /// Position of each object type whose digests are comparable across nodes
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub digests: HashMap<String, AnnouncedDigest>,
+    /// Gossip shards the sender follows, all if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shards: Option<Vec<u32>>,
+}
+
+/// An object's position in its digest history, as carried by sync announcements
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AnnouncedDigest {
+    pub message_count: u64,
+    /// Digests back from the latest one, thinning out with age
+    pub locator: Vec<String>,
+}
+
+/// Local node's position relative to a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStatus {
+    /// The peer holds messages we do not have yet
+    Behind { missing: u64 },
+    /// Both logs hold the same number of messages
+    InSync,
+    /// We hold messages the peer does not have yet
+    Ahead,
+}
+
+impl SyncStatus {
+    /// Compare the local sequence number with a peer's
+    pub fn compare(local: u64, remote: u64) -> Self {
+        if local < remote {
+            SyncStatus::Behind {
+                missing: remote - local,
+            }
+        } else if local == remote {
+            SyncStatus::InSync
+        } else {
+            SyncStatus::Ahead
+        }
+    }
+}
+
+/// Sync state of the local node against all tracked peers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// Messages in the local log
+    pub local_sequence: u64,
+    /// Status against each peer
+    pub peers: HashMap<PeerId, SyncStatus>,
+}
+
+impl SyncSummary {
+    /// Number of peers we are in sync with
+    pub fn in_sync(&self) -> usize {
+        self.count(|status| status == SyncStatus::InSync)
+    }
+
+    /// Number of peers holding messages we lack
+    pub fn behind(&self) -> usize {
+        self.count(|status| matches!(status, SyncStatus::Behind { .. }))
+    }
+
+    /// Number of peers lacking messages we hold
+    pub fn ahead(&self) -> usize {
+        self.count(|status| status == SyncStatus::Ahead)
+    }
+
+    /// Largest number of messages any peer has that we do not
+    pub fn max_missing(&self) -> u64 {
+        self.peers
+            .values()
+            .map(|status| match status {
+                SyncStatus::Behind { missing } => *missing,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether every tracked peer is in sync
+    pub fn is_converged(&self) -> bool {
+        self.in_sync() == self.peers.len()
+    }
+
+    fn count(&self, predicate: impl Fn(SyncStatus) -> bool) -> usize {
+        self.peers.values().filter(|s| predicate(**s)).count()
+    }
+}
+
+/// An object whose digest differs from a peer's at the same message count
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ForkReport {
+    pub peer_id: PeerId,
+    /// Type name of the diverging object
+    pub object_type: String,
+    /// Number of applied messages both digests claim
+    pub message_count: u64,
+    pub local_digest: String,
+    pub remote_digest: String,
+    /// Last message count at which both nodes still agreed, if known
+    pub common_count: Option<u64>,
+}
+
+impl ForkReport {
+    /// Message counts over which the two nodes diverged, if known
+    pub fn diverging_range(&self) -> Option<RangeInclusive<u64>> {
+        self.common_count
+            .map(|common| common + 1..=self.message_count)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PeerSync {
+    remote_sequence: u64,
+    status: SyncStatus,
+}
+
+/// Tracks the last announced sequence number of every peer
+#[derive(Debug, Default)]
+pub struct SyncTracker {
+    peers: Mutex<HashMap<PeerId, PeerSync>>,
+    forks: Mutex<Vec<ForkReport>>,
+}
+
+impl SyncTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a peer's sequence number; returns the new status if it changed
+    pub fn update_peer(&self, peer: &PeerId, remote: u64, local: u64) -> Option<SyncStatus> {
+        let status = SyncStatus::compare(local, remote);
+        let previous = self.peers.lock().unwrap().insert(
+            peer.clone(),
+            PeerSync {
+                remote_sequence: remote,
+                status,
+            },
+        );
+        (previous.map(|p| p.status) != Some(status)).then_some(status)
+    }
+
+    /// Re-evaluate all peers after the local log changed
+    ///
+    /// Returns the peers whose status changed.
+    pub fn update_local(&self, local: u64) -> Vec<(PeerId, SyncStatus)> {
+        let mut changed = Vec::new();
+        for (peer, sync) in self.peers.lock().unwrap().iter_mut() {
+            let status = SyncStatus::compare(local, sync.remote_sequence);
+            if status != sync.status {
+                sync.status = status;
+                changed.push((peer.clone(), status));
+            }
+        }
+        changed
+    }
+
+    /// Status against one peer, if it has announced its sequence
+    pub fn status(&self, peer: &PeerId) -> Option<SyncStatus> {
+        self.peers.lock().unwrap().get(peer).map(|p| p.status)
+    }
+
+    /// Stop tracking a peer
+    pub fn forget(&self, peer: &PeerId) {
+        self.peers.lock().unwrap().remove(peer);
+    }
+
+    /// Keep a fork report; returns false if the same fork was already known
+    pub fn record_fork(&self, fork: ForkReport) -> bool {
+        let mut forks = self.forks.lock().unwrap();
+        let known = forks.iter().any(|known| {
+            known.peer_id == fork.peer_id
+                && known.object_type == fork.object_type
+                && known.message_count == fork.message_count
+        });
+        if !known {
+            forks.push(fork);
+        }
+        !known
+    }
+
+    /// Forks detected so far, oldest first
+    pub fn forks(&self) -> Vec<ForkReport> {
+        self.forks.lock().unwrap().clone()
+    }
+
+    /// Summary of all tracked peers
+    pub fn summary(&self, local: u64) -> SyncSummary {
+        SyncSummary {
+            local_sequence: local,
+            peers: self
+                .peers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(peer, sync)| (peer.clone(), sync.status))
+                .collect(),
+        }
+    }
+}
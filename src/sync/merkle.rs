@@ -0,0 +1,373 @@
+//! Merkle trees over applied messages
+//!
+//! A [`MerkleTree`] commits to the hashes of the messages an object applied,
+//! in order, with the tree shape of RFC 9162 (Certificate Transparency): the
+//! root of `n` leaves splits them at the largest power of two below `n`.
+//! Appending a leaf costs `O(log n)` hashes, and the root after every prefix
+//! stays known, so:
+//!
+//! - two nodes whose roots match hold the same messages, whatever their count;
+//! - a node at a root the other has passed through needs exactly the messages
+//!   after it, see [`MerkleizedObject::messages_since`];
+//! - nodes that diverged find the longest prefix they share with
+//!   [`MerkleTree::common_prefix_len`], comparing `O(log n)` prefix roots
+//!   instead of replaying everything;
+//! - a single message can be shown to be part of a root with a
+//!   [`MerkleProof`] of `O(log n)` hashes.
+//!
+//! Objects embed a [`MerkleizedObject`] and answer the digest methods of
+//! [`ApplicationObject`](crate::shared_object::ApplicationObject) from it:
+//!
+//! ```ignore
+//! fn is_merkleized(&self) -> bool {
+//!     true
+//! }
+//! async fn get_latest_digest(&self) -> Result<String> {
+//!     Ok(self.merkle.digest().to_string())
+//! }
+//! async fn has_digest(&self, digest: &str) -> Result<bool> {
+//!     Ok(self.merkle.contains(digest))
+//! }
+//! async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+//!     self.merkle.messages_since_or_err(digest)
+//! }
+//! ```
+
+use crate::{
+    codec::consensus::ConsensusHasher,
+    error::{ChaincraftError, Result},
+    shared::SharedMessage,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Hash of a leaf holding a message hash
+fn leaf_hash(message_hash: &str) -> String {
+    ConsensusHasher::new()
+        .str("leaf")
+        .str(message_hash)
+        .finish_hex()
+}
+
+/// Hash of an inner node
+fn node_hash(left: &str, right: &str) -> String {
+    ConsensusHasher::new()
+        .str("node")
+        .str(left)
+        .str(right)
+        .finish_hex()
+}
+
+/// Largest power of two strictly below `n`, for `n >= 2`
+fn split_point(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// Root of a slice of leaf hashes
+fn subtree_root(leaves: &[String]) -> String {
+    match leaves.len() {
+        0 => MerkleTree::empty_root(),
+        1 => leaves[0].clone(),
+        n => {
+            let k = split_point(n);
+            node_hash(&subtree_root(&leaves[..k]), &subtree_root(&leaves[k..]))
+        },
+    }
+}
+
+/// Audit path of leaf `index` within a slice of leaf hashes, bottom up
+fn audit_path(index: usize, leaves: &[String], path: &mut Vec<String>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split_point(n);
+    if index < k {
+        audit_path(index, &leaves[..k], path);
+        path.push(subtree_root(&leaves[k..]));
+    } else {
+        audit_path(index - k, &leaves[k..], path);
+        path.push(subtree_root(&leaves[..k]));
+    }
+}
+
+/// Append-only Merkle tree over message hashes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerkleTree {
+    /// Leaf hashes, in the order they were appended
+    leaves: Vec<String>,
+    /// Roots of complete subtrees covering the leaves, with their heights,
+    /// largest first
+    peaks: Vec<(u32, String)>,
+    /// Root after each number of leaves, from 0 to `len`
+    roots: Vec<String>,
+    /// Number of leaves at which each root was current
+    sizes: HashMap<String, usize>,
+}
+
+impl Default for MerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleTree {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        let empty = Self::empty_root();
+        Self {
+            leaves: Vec::new(),
+            peaks: Vec::new(),
+            roots: vec![empty.clone()],
+            sizes: HashMap::from([(empty, 0)]),
+        }
+    }
+
+    /// Root of a tree without leaves
+    pub fn empty_root() -> String {
+        hex::encode(Sha256::digest(b""))
+    }
+
+    /// Append the hash of a message and return the new root
+    pub fn push(&mut self, message_hash: &str) -> &str {
+        let leaf = leaf_hash(message_hash);
+        self.leaves.push(leaf.clone());
+        self.peaks.push((0, leaf));
+        while let [.., (left_height, _), (right_height, _)] = self.peaks[..] {
+            if left_height != right_height {
+                break;
+            }
+            let (_, right) = self.peaks.pop().expect("two peaks");
+            let (height, left) = self.peaks.pop().expect("two peaks");
+            self.peaks.push((height + 1, node_hash(&left, &right)));
+        }
+
+        let mut peaks = self.peaks.iter().rev().map(|(_, hash)| hash);
+        let last = peaks.next().expect("at least one peak").clone();
+        let root = peaks.fold(last, |acc, peak| node_hash(peak, &acc));
+        self.sizes.insert(root.clone(), self.leaves.len());
+        self.roots.push(root);
+        self.root()
+    }
+
+    /// Current root
+    pub fn root(&self) -> &str {
+        self.roots.last().expect("the empty root is always kept")
+    }
+
+    /// Root the tree had with its first `size` leaves
+    pub fn root_at(&self, size: usize) -> Option<&str> {
+        self.roots.get(size).map(String::as_str)
+    }
+
+    /// Number of leaves at which `root` was current
+    pub fn size_of(&self, root: &str) -> Option<usize> {
+        self.sizes.get(root).copied()
+    }
+
+    /// Number of leaves
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Proof that leaf `index` is part of the current root
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        self.proof_at(index, self.len())
+    }
+
+    /// Proof that leaf `index` is part of the root at `size` leaves
+    pub fn proof_at(&self, index: usize, size: usize) -> Option<MerkleProof> {
+        if index >= size || size > self.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        audit_path(index, &self.leaves[..size], &mut path);
+        debug_assert_eq!(subtree_root(&self.leaves[..size]), self.roots[size]);
+        Some(MerkleProof {
+            index,
+            size,
+            leaf: self.leaves[index].clone(),
+            path,
+        })
+    }
+
+    /// Number of leading leaves this tree shares with a peer's
+    ///
+    /// `peer_root_at(n)` returns the peer's root after its first `n` leaves,
+    /// `None` if it cannot tell. Prefix roots match up to the point where the
+    /// trees diverge and differ after, so a binary search finds that point
+    /// asking for `O(log n)` roots.
+    pub fn common_prefix_len(
+        &self,
+        peer_len: usize,
+        mut peer_root_at: impl FnMut(usize) -> Option<String>,
+    ) -> usize {
+        // Invariant: the first `low` leaves match, the first `high + 1` do not
+        let mut low = 0;
+        let mut high = self.len().min(peer_len);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if peer_root_at(mid).as_deref() == self.root_at(mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    }
+
+    /// Remove every leaf
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Proof that a message is part of a Merkle root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Position of the message among the leaves
+    pub index: usize,
+    /// Number of leaves under the root the proof is for
+    pub size: usize,
+    /// Hash of the leaf
+    pub leaf: String,
+    /// Sibling hashes from the leaf up to the root
+    pub path: Vec<String>,
+}
+
+impl MerkleProof {
+    /// Whether the proof is for the message with this hash
+    pub fn proves(&self, message_hash: &str) -> bool {
+        self.leaf == leaf_hash(message_hash)
+    }
+
+    /// Whether the path leads from the leaf to `root`
+    ///
+    /// Follows the verification algorithm of RFC 9162, section 2.1.3.2.
+    pub fn verify(&self, root: &str) -> bool {
+        if self.index >= self.size {
+            return false;
+        }
+        let (mut index, mut last) = (self.index, self.size - 1);
+        let mut hash = self.leaf.clone();
+        for sibling in &self.path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                hash = node_hash(sibling, &hash);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                hash = node_hash(&hash, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        last == 0 && hash == root
+    }
+}
+
+/// Merkle tree and message store for objects that sync by root
+///
+/// Every applied message becomes a leaf; the root is the object's digest.
+/// Unlike a [`DigestAccumulator`](crate::shared::DigestAccumulator), whose
+/// digests only say whether two objects agree, the tree can prove single
+/// messages and locate where two objects diverged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MerkleizedObject {
+    tree: MerkleTree,
+    messages: Vec<SharedMessage>,
+    /// Leaf index of each message hash
+    positions: HashMap<String, usize>,
+}
+
+impl MerkleizedObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an applied message as the next leaf and return the new root
+    pub fn apply(&mut self, message: &SharedMessage) -> &str {
+        self.positions
+            .entry(message.hash.clone())
+            .or_insert(self.messages.len());
+        self.messages.push(message.clone());
+        self.tree.push(&message.hash)
+    }
+
+    /// Current root
+    pub fn digest(&self) -> &str {
+        self.tree.root()
+    }
+
+    /// Whether the object passed through root `digest`
+    pub fn contains(&self, digest: &str) -> bool {
+        self.tree.size_of(digest).is_some()
+    }
+
+    /// Messages applied after root `digest`, oldest first
+    ///
+    /// `None` if the object never had that root.
+    pub fn messages_since(&self, digest: &str) -> Option<Vec<SharedMessage>> {
+        let size = self.tree.size_of(digest)?;
+        Some(self.messages[size..].to_vec())
+    }
+
+    /// Like [`Self::messages_since`], failing with a validation error for an
+    /// unknown root
+    pub fn messages_since_or_err(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.messages_since(digest)
+            .ok_or_else(|| ChaincraftError::validation(format!("unknown Merkle root {}", digest)))
+    }
+
+    /// Messages after the first `size`, for a peer that shares that prefix
+    pub fn messages_after(&self, size: usize) -> Vec<SharedMessage> {
+        self.messages.get(size..).unwrap_or_default().to_vec()
+    }
+
+    /// Messages to gossip to a peer at `digest`
+    ///
+    /// A peer with an unknown root gets everything.
+    pub fn gossip(&self, digest: Option<&str>) -> Vec<SharedMessage> {
+        digest
+            .and_then(|d| self.messages_since(d))
+            .unwrap_or_else(|| self.messages.clone())
+    }
+
+    /// Proof that the message with this hash is part of the current root
+    pub fn proof(&self, message_hash: &str) -> Option<MerkleProof> {
+        self.tree.proof(*self.positions.get(message_hash)?)
+    }
+
+    /// All applied messages, oldest first
+    pub fn messages(&self) -> &[SharedMessage] {
+        &self.messages
+    }
+
+    /// The underlying tree
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    /// Number of applied messages
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Forget every applied message
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
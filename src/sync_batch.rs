@@ -0,0 +1,328 @@
+//! Paced transfer of a message log to a peer
+//!
+//! A new peer may be thousands of messages behind. Rather than sending them
+//! all at once, a [`SyncSender`] reads the log in batches of at most
+//! [`SyncBatchConfig::max_batch_messages`] messages and
+//! [`SyncBatchConfig::max_batch_bytes`] bytes, and stops once the batches the
+//! receiver has not acknowledged reach [`SyncBatchConfig::max_in_flight_bytes`].
+//! The receiver sets the pace: its [`SyncReceiver`] answers every batch with
+//! a [`SyncAck`], which frees room for the next ones.
+//!
+//! Progress is a [`SyncCursor`], the position in the sender's log the
+//! receiver needs next. The receiver can persist it and resume an
+//! interrupted transfer by starting a new sender at that cursor, so nothing
+//! already received is sent twice.
+//!
+//! A node applies received batches with
+//! [`ChaincraftNode::ingest_sync_batch`](crate::ChaincraftNode::ingest_sync_batch),
+//! which verifies the signatures of a batch in parallel before applying its
+//! messages in order.
+
+use crate::{
+    error::{ChaincraftError, Result},
+    shared::{MessageType, SharedMessage},
+};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Custom message type carrying a [`SyncBatch`]
+pub const SYNC_BATCH_MESSAGE_TYPE: &str = "SYNC_BATCH";
+
+/// Custom message type carrying a [`SyncAck`]
+pub const SYNC_ACK_MESSAGE_TYPE: &str = "SYNC_ACK";
+
+/// Size limits of a sync transfer
+#[derive(Debug, Clone)]
+pub struct SyncBatchConfig {
+    /// Most messages in one batch
+    pub max_batch_messages: usize,
+    /// Encoded size a batch may reach
+    pub max_batch_bytes: usize,
+    /// Encoded size of the batches sent but not yet acknowledged
+    pub max_in_flight_bytes: usize,
+}
+
+impl Default for SyncBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_messages: 500,
+            max_batch_bytes: 256 * 1024,
+            max_in_flight_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Position in a message log: the number of messages already transferred
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub struct SyncCursor {
+    pub sequence: u64,
+}
+
+impl SyncCursor {
+    pub fn new(sequence: u64) -> Self {
+        Self { sequence }
+    }
+}
+
+/// Log a sync transfer reads from, e.g. a node's message log
+#[async_trait]
+pub trait MessageSource: Send + Sync {
+    /// Number of messages in the log
+    async fn log_len(&self) -> Result<u64>;
+
+    /// Up to `limit` messages starting at position `start`
+    async fn read_log(&self, start: u64, limit: usize) -> Result<Vec<SharedMessage>>;
+}
+
+/// Consecutive messages of the sender's log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SyncBatch {
+    /// Number of the batch within its transfer, starting at 0
+    pub batch: u64,
+    /// Position of the first message
+    pub start: SyncCursor,
+    pub messages: Vec<SharedMessage>,
+    /// Length of the sender's log when the batch was read
+    pub total: u64,
+}
+
+impl SyncBatch {
+    /// Position after the last message
+    pub fn end(&self) -> SyncCursor {
+        SyncCursor::new(self.start.sequence + self.messages.len() as u64)
+    }
+
+    /// Whether the batch reaches the end of the sender's log
+    pub fn is_last(&self) -> bool {
+        self.end().sequence >= self.total
+    }
+
+    pub fn to_message(&self) -> Result<SharedMessage> {
+        Ok(SharedMessage::new(
+            MessageType::Custom(SYNC_BATCH_MESSAGE_TYPE.to_string()),
+            serde_json::to_value(self)?,
+        ))
+    }
+
+    pub fn from_message(message: &SharedMessage) -> Result<Self> {
+        Ok(serde_json::from_value(message.data.clone())?)
+    }
+}
+
+/// Receiver's confirmation of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SyncAck {
+    /// Batch being acknowledged; earlier batches count as acknowledged too
+    pub batch: u64,
+    /// Position the receiver needs next
+    pub cursor: SyncCursor,
+}
+
+impl SyncAck {
+    pub fn to_message(&self) -> Result<SharedMessage> {
+        Ok(SharedMessage::new(
+            MessageType::Custom(SYNC_ACK_MESSAGE_TYPE.to_string()),
+            serde_json::to_value(self)?,
+        ))
+    }
+
+    pub fn from_message(message: &SharedMessage) -> Result<Self> {
+        Ok(serde_json::from_value(message.data.clone())?)
+    }
+}
+
+#[derive(Debug)]
+struct InFlight {
+    batch: u64,
+    bytes: usize,
+}
+
+/// Sending side of a sync transfer
+#[derive(Debug)]
+pub struct SyncSender {
+    config: SyncBatchConfig,
+    next: SyncCursor,
+    acked: SyncCursor,
+    next_batch: u64,
+    in_flight: VecDeque<InFlight>,
+    in_flight_bytes: usize,
+    total: Option<u64>,
+    /// Messages read from the log from `next` on, not sent yet
+    read_ahead: VecDeque<SharedMessage>,
+}
+
+impl SyncSender {
+    /// Start sending from `cursor`, e.g. the one an interrupted transfer reached
+    pub fn new(config: SyncBatchConfig, cursor: SyncCursor) -> Self {
+        Self {
+            config,
+            next: cursor,
+            acked: cursor,
+            next_batch: 0,
+            in_flight: VecDeque::new(),
+            in_flight_bytes: 0,
+            total: None,
+            read_ahead: VecDeque::new(),
+        }
+    }
+
+    /// Position the receiver confirmed it needs next
+    pub fn acked(&self) -> SyncCursor {
+        self.acked
+    }
+
+    /// Encoded size of the batches awaiting acknowledgement
+    pub fn in_flight_bytes(&self) -> usize {
+        self.in_flight_bytes
+    }
+
+    /// Number of batches awaiting acknowledgement
+    pub fn in_flight_batches(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Whether the receiver acknowledged the whole log
+    pub fn is_finished(&self) -> bool {
+        self.in_flight.is_empty() && self.total.is_some_and(|total| self.acked.sequence >= total)
+    }
+
+    /// Read the next batch, if the in-flight limit leaves room for it
+    ///
+    /// Returns `None` when the receiver has to acknowledge earlier batches
+    /// first, or when everything has been sent. Up to a batch's worth of
+    /// messages is read ahead; the log is read again once those are sent, so
+    /// messages appended meanwhile are sent too. A single message larger than
+    /// the limits is sent alone once nothing is in flight.
+    pub async fn next_batch(&mut self, source: &dyn MessageSource) -> Result<Option<SyncBatch>> {
+        let room = self
+            .config
+            .max_in_flight_bytes
+            .saturating_sub(self.in_flight_bytes);
+        if room == 0 {
+            return Ok(None);
+        }
+        let total = source.log_len().await?;
+        self.total = Some(total);
+        if self.next.sequence >= total {
+            return Ok(None);
+        }
+
+        let max_messages = self.config.max_batch_messages.max(1);
+        if self.read_ahead.is_empty() {
+            self.read_ahead = source
+                .read_log(self.next.sequence, max_messages)
+                .await?
+                .into();
+        }
+
+        let limit = room.min(self.config.max_batch_bytes);
+        let mut messages = Vec::new();
+        let mut bytes = 0;
+        while let Some(message) = self.read_ahead.front() {
+            let size = serde_json::to_vec(message)?.len();
+            let fits = bytes + size <= limit || (messages.is_empty() && self.in_flight.is_empty());
+            if !fits || messages.len() == max_messages {
+                break;
+            }
+            bytes += size;
+            messages.extend(self.read_ahead.pop_front());
+        }
+        if messages.is_empty() {
+            return Ok(None);
+        }
+
+        let batch = SyncBatch {
+            batch: self.next_batch,
+            start: self.next,
+            messages,
+            total,
+        };
+        self.next = batch.end();
+        self.next_batch += 1;
+        self.in_flight.push_back(InFlight {
+            batch: batch.batch,
+            bytes,
+        });
+        self.in_flight_bytes += bytes;
+        Ok(Some(batch))
+    }
+
+    /// Free the room taken by acknowledged batches
+    pub fn on_ack(&mut self, ack: &SyncAck) -> Result<()> {
+        if ack.batch >= self.next_batch {
+            return Err(ChaincraftError::validation(format!(
+                "acknowledged batch {} was never sent",
+                ack.batch
+            )));
+        }
+        while self
+            .in_flight
+            .front()
+            .is_some_and(|sent| sent.batch <= ack.batch)
+        {
+            if let Some(sent) = self.in_flight.pop_front() {
+                self.in_flight_bytes -= sent.bytes;
+            }
+        }
+        self.acked = self.acked.max(ack.cursor);
+        Ok(())
+    }
+}
+
+/// What a node did with the messages of sync batches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngestSummary {
+    /// Messages stored and processed
+    pub applied: usize,
+    /// Messages the node already had
+    pub duplicates: usize,
+    /// Messages failing verification or rejected by an object
+    pub refused: usize,
+}
+
+/// Receiving side of a sync transfer
+#[derive(Debug, Clone, Default)]
+pub struct SyncReceiver {
+    cursor: SyncCursor,
+}
+
+impl SyncReceiver {
+    /// Expect messages from `cursor` on; a fresh receiver starts at zero
+    pub fn new(cursor: SyncCursor) -> Self {
+        Self { cursor }
+    }
+
+    /// Position to persist for resuming, and to start a sender at
+    pub fn cursor(&self) -> SyncCursor {
+        self.cursor
+    }
+
+    /// Take a batch, returning the messages not received before and the ack
+    ///
+    /// Batches repeated after a resend are acknowledged again without
+    /// returning their messages twice. A batch starting past the cursor
+    /// leaves a gap and is refused.
+    pub fn accept(&mut self, batch: SyncBatch) -> Result<(Vec<SharedMessage>, SyncAck)> {
+        if batch.start > self.cursor {
+            return Err(ChaincraftError::validation(format!(
+                "batch {} starts at {} but messages from {} are missing",
+                batch.batch, batch.start.sequence, self.cursor.sequence
+            )));
+        }
+        let already = (self.cursor.sequence - batch.start.sequence) as usize;
+        let end = batch.end().max(self.cursor);
+        let messages: Vec<SharedMessage> = batch.messages.into_iter().skip(already).collect();
+        self.cursor = end;
+        Ok((
+            messages,
+            SyncAck {
+                batch: batch.batch,
+                cursor: self.cursor,
+            },
+        ))
+    }
+}
@@ -0,0 +1,221 @@
+//! Recurring tasks of application objects
+//!
+//! Objects cannot spawn timers of their own, yet beacon rounds, presence
+//! heartbeats or retention sweeps all need to happen on a schedule. An object
+//! lists the tasks it needs in
+//! [`ApplicationObject::periodic_tasks`](crate::shared_object::ApplicationObject::periodic_tasks);
+//! the node keeps them in a [`TaskScheduler`] and calls
+//! [`ApplicationObject::run_task`](crate::shared_object::ApplicationObject::run_task)
+//! whenever one is due by the node clock, so a mock clock drives them in tests.
+//!
+//! A task runs once per [`TaskSpec::interval`], counted from registration or,
+//! for [aligned](TaskSpec::aligned) tasks, from the Unix epoch so that every
+//! node runs it at the same instants. Each run is delayed by a random amount
+//! below [`TaskSpec::jitter`] without shifting the runs after it. Runs missed
+//! while the node was busy or the clock jumped are not made up: the task runs
+//! once and resumes at its next regular time.
+
+use crate::{
+    error::{ChaincraftError, Result},
+    rng::RngProvider,
+    shared::SharedObjectId,
+};
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A recurring task an object asks the node to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSpec {
+    /// Name handed back to the object when the task runs, unique per object
+    pub name: String,
+    /// Time between runs
+    pub interval: Duration,
+    /// Upper bound of the random delay added to each run
+    pub jitter: Duration,
+    /// Whether runs fall on multiples of the interval since the Unix epoch
+    pub aligned: bool,
+}
+
+impl TaskSpec {
+    /// Task running every `interval`, without jitter
+    pub fn every(name: impl Into<String>, interval: Duration) -> Self {
+        Self {
+            name: name.into(),
+            interval,
+            jitter: Duration::ZERO,
+            aligned: false,
+        }
+    }
+
+    /// Delay each run by a random amount below `jitter`
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Run on multiples of the interval since the Unix epoch
+    pub fn aligned(mut self) -> Self {
+        self.aligned = true;
+        self
+    }
+
+    /// Check that the task can be scheduled
+    pub fn validate(&self) -> Result<()> {
+        if self.interval.is_zero() {
+            return Err(ChaincraftError::config(format!(
+                "task {} needs a non-zero interval",
+                self.name
+            )));
+        }
+        if self.jitter >= self.interval {
+            return Err(ChaincraftError::config(format!(
+                "jitter of task {} must be below its interval of {:?}",
+                self.name, self.interval
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A task registered with a scheduler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledTask {
+    pub object_id: SharedObjectId,
+    pub spec: TaskSpec,
+    /// Regular time of the next run, before jitter
+    pub nominal: DateTime<Utc>,
+    /// Time of the next run
+    pub next_run: DateTime<Utc>,
+}
+
+/// A task whose time has come
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueTask {
+    pub object_id: SharedObjectId,
+    pub name: String,
+    /// Time the run was scheduled for, jitter included
+    pub scheduled_for: DateTime<Utc>,
+}
+
+/// Recurring tasks of the objects of a node
+///
+/// Cloning is cheap and clones share the same tasks.
+#[derive(Debug, Clone, Default)]
+pub struct TaskScheduler {
+    rng: RngProvider,
+    tasks: Arc<Mutex<Vec<ScheduledTask>>>,
+}
+
+impl TaskScheduler {
+    /// Scheduler drawing jitter from `rng`
+    pub fn new(rng: RngProvider) -> Self {
+        Self {
+            rng,
+            tasks: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Schedule a task of an object, first due one interval after `now`
+    ///
+    /// A task of the same object and name is replaced.
+    pub fn register(
+        &self,
+        object_id: &SharedObjectId,
+        spec: TaskSpec,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        spec.validate()?;
+        let nominal = Self::next_nominal(&spec, now, now);
+        let task = ScheduledTask {
+            object_id: object_id.clone(),
+            next_run: nominal + self.jitter(&spec),
+            nominal,
+            spec,
+        };
+        let mut tasks = self.lock();
+        tasks.retain(|t| !(t.object_id == task.object_id && t.spec.name == task.spec.name));
+        tasks.push(task);
+        Ok(())
+    }
+
+    /// Drop one task of an object; returns whether it was scheduled
+    pub fn unregister(&self, object_id: &SharedObjectId, name: &str) -> bool {
+        let mut tasks = self.lock();
+        let before = tasks.len();
+        tasks.retain(|t| !(&t.object_id == object_id && t.spec.name == name));
+        tasks.len() < before
+    }
+
+    /// Drop every task of an object; returns how many there were
+    pub fn remove_object(&self, object_id: &SharedObjectId) -> usize {
+        let mut tasks = self.lock();
+        let before = tasks.len();
+        tasks.retain(|t| &t.object_id != object_id);
+        before - tasks.len()
+    }
+
+    /// Tasks due at or before `now`, earliest first
+    ///
+    /// Each is rescheduled to its next regular time after `now`.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<DueTask> {
+        let mut due = Vec::new();
+        for task in self.lock().iter_mut().filter(|t| t.next_run <= now) {
+            due.push(DueTask {
+                object_id: task.object_id.clone(),
+                name: task.spec.name.clone(),
+                scheduled_for: task.next_run,
+            });
+            task.nominal = Self::next_nominal(&task.spec, task.nominal, now);
+            task.next_run = task.nominal + self.jitter(&task.spec);
+        }
+        due.sort_by_key(|task| task.scheduled_for);
+        due
+    }
+
+    /// Time the earliest task is due
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        self.lock().iter().map(|t| t.next_run).min()
+    }
+
+    /// Scheduled tasks, earliest first
+    pub fn tasks(&self) -> Vec<ScheduledTask> {
+        let mut tasks = self.lock().clone();
+        tasks.sort_by_key(|t| t.next_run);
+        tasks
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// First regular run after `now`, counted from `from` or the epoch
+    fn next_nominal(spec: &TaskSpec, from: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = spec.interval.as_millis().max(1) as i64;
+        let origin = if spec.aligned {
+            0
+        } else {
+            from.timestamp_millis()
+        };
+        let elapsed = now.timestamp_millis() - origin;
+        let periods = elapsed.div_euclid(interval) + 1;
+        DateTime::from_timestamp_millis(origin + periods * interval).unwrap_or(now)
+    }
+
+    fn jitter(&self, spec: &TaskSpec) -> chrono::Duration {
+        let max = spec.jitter.as_millis() as u64;
+        if max == 0 {
+            return chrono::Duration::zero();
+        }
+        chrono::Duration::milliseconds((self.rng.next_u64_value() % max) as i64)
+    }
+
+    // Never held across an await; a poisoned lock still holds valid tasks
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ScheduledTask>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
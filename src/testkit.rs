@@ -0,0 +1,567 @@
+//! Test harness for custom application objects
+//!
+//! [`ObjectHarness`] drives a single [`ApplicationObject`] the way a node
+//! would, without any networking: messages are stamped with a mock clock and
+//! numbered by a seeded RNG, so a test runs the same way every time. After
+//! each message the harness records the object's digest, which lets a test
+//! check the digest rules every object has to follow (see
+//! [`ObjectHarness::assert_digests_monotonic`]).
+//!
+//! State assertions take a selector into the JSON returned by `get_state`:
+//! keys separated by dots with `[n]` for array elements, optionally starting
+//! with `$`, e.g. `$.chatrooms[0]` or `balances.alice`.
+//!
+//! [`assert_rejects_invalid`] is a conformance check any object can run: it
+//! feeds the object malformed, unsigned, tampered, expired and replayed
+//! messages around a corpus of valid ones and checks that none is applied.
+//!
+//! The assertion methods panic with a descriptive message, like `assert_eq!`,
+//! so they are meant for tests only.
+
+use crate::{
+    clock::Clock,
+    directory::{ObjectDirectory, ObjectSnapshot},
+    error::{ChaincraftError, Result},
+    rng::RngProvider,
+    shared::{MessageType, SharedMessage},
+    shared_object::{load_versioned_state, ApplicationObject, ApplyOutcome},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Seed of the RNG numbering harness messages
+pub const HARNESS_SEED: u64 = 42;
+
+/// Message type given to messages built from bare data
+pub const HARNESS_MESSAGE_TYPE: &str = "testkit";
+
+/// Time the harness clock starts at
+pub fn harness_epoch() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// One message fed through the harness
+#[derive(Debug, Clone)]
+pub struct FedMessage {
+    pub message: SharedMessage,
+    /// `None` if the object's `is_valid` refused the message
+    pub outcome: Option<ApplyOutcome>,
+    pub digest_before: String,
+    pub digest_after: String,
+}
+
+/// Saved harness state, see [`ObjectHarness::snapshot`]
+#[derive(Debug, Clone)]
+pub struct HarnessSnapshot<T> {
+    object: T,
+    now: DateTime<Utc>,
+    fed: usize,
+}
+
+/// Drives one application object in isolation
+#[derive(Debug)]
+pub struct ObjectHarness<T: ApplicationObject + Clone + 'static> {
+    object: T,
+    clock: Clock,
+    rng: RngProvider,
+    directory: ObjectDirectory,
+    fed: Vec<FedMessage>,
+}
+
+impl<T: ApplicationObject + Clone + 'static> ObjectHarness<T> {
+    /// Wrap an object; the clock starts at [`harness_epoch`]
+    pub fn new(mut object: T) -> Self {
+        let directory = ObjectDirectory::new();
+        object.attach_directory(directory.clone());
+        Self {
+            object,
+            clock: Clock::mock(harness_epoch()),
+            rng: RngProvider::seeded(HARNESS_SEED),
+            directory,
+            fed: Vec::new(),
+        }
+    }
+
+    pub fn object(&self) -> &T {
+        &self.object
+    }
+
+    pub fn object_mut(&mut self) -> &mut T {
+        &mut self.object
+    }
+
+    pub fn into_inner(self) -> T {
+        self.object
+    }
+
+    /// The mock clock stamping new messages
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        self.clock.advance(by);
+    }
+
+    /// Current harness time
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Every message fed so far, oldest first
+    pub fn history(&self) -> &[FedMessage] {
+        &self.fed
+    }
+
+    /// Directory handed to the object, holding its own state as of the last
+    /// applied message and any siblings added with [`Self::add_sibling`]
+    pub fn directory(&self) -> &ObjectDirectory {
+        &self.directory
+    }
+
+    /// Make a sibling's state readable through the object's directory
+    pub fn add_sibling(&self, snapshot: ObjectSnapshot) {
+        self.directory.publish(snapshot);
+    }
+
+    /// Build a message of the given type stamped with the harness time
+    pub fn message(&self, message_type: MessageType, data: Value) -> SharedMessage {
+        let mut message = SharedMessage::new_with_rng(&self.rng, message_type, data);
+        message.timestamp = self.clock.now();
+        message.hash = message.calculate_hash();
+        message
+    }
+
+    /// Feed bare data as a [`HARNESS_MESSAGE_TYPE`] message
+    pub async fn feed(&mut self, data: Value) -> Result<Option<ApplyOutcome>> {
+        let message = self.message(MessageType::Custom(HARNESS_MESSAGE_TYPE.to_string()), data);
+        self.feed_message(message).await
+    }
+
+    /// Feed a message as a node would: `is_valid` first, then `add_message`
+    ///
+    /// Returns `None` if the object does not accept the message.
+    pub async fn feed_message(&mut self, message: SharedMessage) -> Result<Option<ApplyOutcome>> {
+        let digest_before = self.object.get_latest_digest().await?;
+        if self.directory.snapshot(self.object.id()).await.is_none() {
+            self.publish(digest_before.clone()).await?;
+        }
+        let outcome = if self.object.is_valid(&message).await? {
+            let outcome = self.object.add_message(message.clone()).await?;
+            self.object.take_execution();
+            Some(outcome)
+        } else {
+            None
+        };
+        let digest_after = self.object.get_latest_digest().await?;
+        if outcome.as_ref().is_some_and(ApplyOutcome::is_applied) {
+            self.publish(digest_after.clone()).await?;
+        }
+
+        self.fed.push(FedMessage {
+            message,
+            outcome: outcome.clone(),
+            digest_before,
+            digest_after,
+        });
+        Ok(outcome)
+    }
+
+    /// Feed data and panic unless the object applied it
+    pub async fn apply(&mut self, data: Value) -> Result<()> {
+        match self.feed(data.clone()).await? {
+            Some(ApplyOutcome::Applied) => Ok(()),
+            other => panic!("expected {} to be applied, got {:?}", data, other),
+        }
+    }
+
+    /// Current state, as returned by `get_state`
+    pub async fn state(&self) -> Result<Value> {
+        self.object.get_state().await
+    }
+
+    /// Part of the state picked by a selector
+    pub async fn select(&self, selector: &str) -> Result<Value> {
+        let state = self.state().await?;
+        select(&state, selector).cloned().ok_or_else(|| {
+            ChaincraftError::validation(format!("selector '{}' matches nothing", selector))
+        })
+    }
+
+    /// Panic unless the selected part of the state equals `expected`
+    pub async fn assert_state(&self, selector: &str, expected: Value) {
+        let state = self.state().await.expect("get_state failed");
+        match select(&state, selector) {
+            Some(actual) => assert_eq!(
+                actual, &expected,
+                "state at '{}' differs; full state: {}",
+                selector, state
+            ),
+            None => panic!("selector '{}' matches nothing in {}", selector, state),
+        }
+    }
+
+    /// Save the object, clock and message history
+    pub fn snapshot(&self) -> HarnessSnapshot<T> {
+        HarnessSnapshot {
+            object: self.object.clone(),
+            now: self.clock.now(),
+            fed: self.fed.len(),
+        }
+    }
+
+    /// Go back to a snapshot taken from this harness
+    ///
+    /// Messages fed since the snapshot are dropped from the history.
+    pub async fn restore(&mut self, snapshot: HarnessSnapshot<T>) -> Result<()> {
+        self.object = snapshot.object;
+        self.object.attach_directory(self.directory.clone());
+        self.clock.set(snapshot.now);
+        self.fed.truncate(snapshot.fed);
+        let digest = self.object.get_latest_digest().await?;
+        self.publish(digest).await
+    }
+
+    /// Load state saved at schema `version`, migrating it like a node would
+    ///
+    /// Use it to check that snapshots from an earlier version of an object
+    /// still load; see [`load_versioned_state`].
+    pub async fn load_state(&mut self, version: u32, state: Value) -> Result<()> {
+        load_versioned_state(&mut self.object, version, state).await?;
+        let digest = self.object.get_latest_digest().await?;
+        self.publish(digest).await
+    }
+
+    /// Panic unless the recorded digests follow the rules every object obeys
+    ///
+    /// The digest moves exactly when a message is applied and never returns
+    /// to an earlier value.
+    pub fn assert_digests_monotonic(&self) {
+        let mut seen: Vec<&str> = Vec::new();
+        for (i, fed) in self.fed.iter().enumerate() {
+            let applied = fed.outcome.as_ref().is_some_and(ApplyOutcome::is_applied);
+            if i == 0 {
+                seen.push(&fed.digest_before);
+            }
+            if applied {
+                assert_ne!(
+                    fed.digest_before, fed.digest_after,
+                    "message #{} was applied but the digest did not change",
+                    i
+                );
+                assert!(
+                    !seen.contains(&fed.digest_after.as_str()),
+                    "message #{} moved the digest back to an earlier value {}",
+                    i,
+                    fed.digest_after
+                );
+                seen.push(&fed.digest_after);
+            } else {
+                assert_eq!(
+                    fed.digest_before, fed.digest_after,
+                    "message #{} was not applied ({:?}) but the digest changed",
+                    i, fed.outcome
+                );
+            }
+        }
+    }
+
+    async fn publish(&self, digest: String) -> Result<()> {
+        self.directory.publish(ObjectSnapshot {
+            id: self.object.id().clone(),
+            type_name: self.object.type_name().to_string(),
+            state: self.object.get_state().await?,
+            digest,
+        });
+        Ok(())
+    }
+}
+
+/// Pick part of a JSON value with a selector such as `$.rooms[0].name`
+pub fn select<'a>(value: &'a Value, selector: &str) -> Option<&'a Value> {
+    let path = selector.strip_prefix('$').unwrap_or(selector);
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, mut rest) = match segment.find('[') {
+            Some(at) => segment.split_at(at),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        while let Some(stripped) = rest.strip_prefix('[') {
+            let end = stripped.find(']')?;
+            let index: usize = stripped[..end].parse().ok()?;
+            current = current.get(index)?;
+            rest = &stripped[end + 1..];
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+    }
+    Some(current)
+}
+
+/// Kind of invalid input fed by [`assert_rejects_invalid`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InvalidKind {
+    /// Data that is not a message of the object; refused or rejected
+    Malformed,
+    /// A valid message with every `signature` field emptied; refused or rejected
+    Unsigned,
+    /// A valid message with every signature altered; refused or rejected
+    Tampered,
+    /// A message that was valid once but is too old now; not applied
+    Expired,
+    /// A valid message fed again after it was applied; not applied
+    Replayed,
+}
+
+impl InvalidKind {
+    /// Whether `outcome` is an acceptable answer to this kind of input
+    fn accepts(self, outcome: &Option<ApplyOutcome>) -> bool {
+        match self {
+            InvalidKind::Malformed | InvalidKind::Unsigned | InvalidKind::Tampered => {
+                matches!(outcome, None | Some(ApplyOutcome::Rejected(_)))
+            },
+            InvalidKind::Expired | InvalidKind::Replayed => {
+                !outcome.as_ref().is_some_and(ApplyOutcome::is_applied)
+            },
+        }
+    }
+}
+
+impl fmt::Display for InvalidKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InvalidKind::Malformed => "malformed",
+            InvalidKind::Unsigned => "unsigned",
+            InvalidKind::Tampered => "tampered",
+            InvalidKind::Expired => "expired",
+            InvalidKind::Replayed => "replayed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Messages [`assert_rejects_invalid`] builds its invalid input around
+#[derive(Debug, Clone, Default)]
+pub struct NegativeCorpus {
+    /// Messages the object applies in order
+    pub valid: Vec<Value>,
+    /// Malformed data specific to the object, fed after the built-in cases
+    pub malformed: Vec<Value>,
+    /// Correctly signed messages that are too old once `valid` is applied
+    pub expired: Vec<Value>,
+}
+
+impl NegativeCorpus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_valid(mut self, data: Value) -> Self {
+        self.valid.push(data);
+        self
+    }
+
+    pub fn with_malformed(mut self, data: Value) -> Self {
+        self.malformed.push(data);
+        self
+    }
+
+    pub fn with_expired(mut self, data: Value) -> Self {
+        self.expired.push(data);
+        self
+    }
+}
+
+/// Invalid input fed by [`assert_rejects_invalid`] and what the object made of it
+#[derive(Debug, Clone)]
+pub struct NegativeCase {
+    pub kind: InvalidKind,
+    pub data: Value,
+    /// `None` if the object's `is_valid` refused the data
+    pub outcome: Option<ApplyOutcome>,
+}
+
+/// Everything [`assert_rejects_invalid`] fed, in order
+#[derive(Debug, Clone, Default)]
+pub struct NegativeReport {
+    pub cases: Vec<NegativeCase>,
+}
+
+impl NegativeReport {
+    /// Number of cases fed of each kind
+    pub fn counts(&self) -> BTreeMap<InvalidKind, usize> {
+        let mut counts = BTreeMap::new();
+        for case in &self.cases {
+            *counts.entry(case.kind).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Reason codes the object gave, with how often it gave them
+    pub fn reasons(&self) -> BTreeMap<String, usize> {
+        let mut reasons = BTreeMap::new();
+        for case in &self.cases {
+            let code = match &case.outcome {
+                Some(outcome) => outcome.reason().map(|reason| reason.code.clone()),
+                None => Some("refused".to_string()),
+            };
+            if let Some(code) = code {
+                *reasons.entry(code).or_default() += 1;
+            }
+        }
+        reasons
+    }
+}
+
+/// Data every object has to refuse, assuming its messages are JSON objects
+fn malformed_inputs() -> Vec<Value> {
+    vec![
+        Value::Null,
+        json!(true),
+        json!(-1),
+        json!("not a message"),
+        json!([]),
+        json!({}),
+        json!({ "message_type": 7, "signature": [] }),
+    ]
+}
+
+/// `data` with every string `signature` field rewritten, `None` if it has none
+fn rewrite_signatures(data: &Value, rewrite: &dyn Fn(&str) -> String) -> Option<Value> {
+    match data {
+        Value::Object(map) => {
+            let mut changed = false;
+            let mut out = map.clone();
+            for (key, value) in out.iter_mut() {
+                match value {
+                    Value::String(signature) if key == "signature" => {
+                        *signature = rewrite(signature);
+                        changed = true;
+                    },
+                    _ => {
+                        if let Some(rewritten) = rewrite_signatures(value, rewrite) {
+                            *value = rewritten;
+                            changed = true;
+                        }
+                    },
+                }
+            }
+            changed.then_some(Value::Object(out))
+        },
+        Value::Array(items) => {
+            let mut changed = false;
+            let out: Vec<Value> = items
+                .iter()
+                .map(|item| match rewrite_signatures(item, rewrite) {
+                    Some(rewritten) => {
+                        changed = true;
+                        rewritten
+                    },
+                    None => item.clone(),
+                })
+                .collect();
+            changed.then_some(Value::Array(out))
+        },
+        _ => None,
+    }
+}
+
+/// Flip the last hex digit, keeping the signature well-formed
+fn tamper(signature: &str) -> String {
+    let mut tampered = signature.to_string();
+    match tampered.pop() {
+        Some('0') => tampered.push('1'),
+        Some(_) => tampered.push('0'),
+        None => tampered.push_str("00"),
+    }
+    tampered
+}
+
+/// Panic unless `object` refuses every kind of invalid input
+///
+/// The valid messages of the corpus are applied in order. Before each one is
+/// applied, a copy with its signatures emptied and one with them altered are
+/// fed in the very state the original is accepted in. After it is applied,
+/// the same data is fed again as a replay. Built-in and corpus-specific
+/// malformed data and the corpus' expired messages follow. Finally the digest
+/// rules of [`ObjectHarness::assert_digests_monotonic`] are checked, so no
+/// refused input may move the digest.
+///
+/// Messages without a `signature` field get no unsigned or tampered copies.
+pub async fn assert_rejects_invalid<T: ApplicationObject + Clone + 'static>(
+    object: T,
+    corpus: &NegativeCorpus,
+) -> Result<NegativeReport> {
+    let mut harness = ObjectHarness::new(object);
+    let mut report = NegativeReport::default();
+    let type_name = harness.object().type_name();
+
+    for data in &corpus.valid {
+        let copies = [
+            (InvalidKind::Unsigned, rewrite_signatures(data, &|_| String::new())),
+            (InvalidKind::Tampered, rewrite_signatures(data, &tamper)),
+        ];
+        for (kind, copy) in copies {
+            if let Some(copy) = copy {
+                feed_invalid(&mut harness, &mut report, kind, copy).await?;
+            }
+        }
+        match harness.feed(data.clone()).await? {
+            Some(ApplyOutcome::Applied) => {},
+            other => panic!("{} did not apply valid message {}: {:?}", type_name, data, other),
+        }
+        feed_invalid(&mut harness, &mut report, InvalidKind::Replayed, data.clone()).await?;
+    }
+    for data in malformed_inputs()
+        .into_iter()
+        .chain(corpus.malformed.clone())
+    {
+        feed_invalid(&mut harness, &mut report, InvalidKind::Malformed, data).await?;
+    }
+    for data in &corpus.expired {
+        feed_invalid(&mut harness, &mut report, InvalidKind::Expired, data.clone()).await?;
+    }
+
+    harness.assert_digests_monotonic();
+    Ok(report)
+}
+
+async fn feed_invalid<T: ApplicationObject + Clone + 'static>(
+    harness: &mut ObjectHarness<T>,
+    report: &mut NegativeReport,
+    kind: InvalidKind,
+    data: Value,
+) -> Result<()> {
+    let outcome = match harness.feed(data.clone()).await {
+        Ok(outcome) => outcome,
+        Err(e) => panic!(
+            "{} failed on {} input {} instead of answering with an outcome: {}",
+            harness.object().type_name(),
+            kind,
+            data,
+            e
+        ),
+    };
+    assert!(
+        kind.accepts(&outcome),
+        "{} answered {} input {} with {:?}",
+        harness.object().type_name(),
+        kind,
+        data,
+        outcome
+    );
+    report.cases.push(NegativeCase {
+        kind,
+        data,
+        outcome,
+    });
+    Ok(())
+}
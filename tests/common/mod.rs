@@ -0,0 +1,23 @@
+//! Fixtures shared by the integration tests
+//!
+//! Each test file that needs them declares `mod common;`, so not every file
+//! uses every fixture.
+#![allow(dead_code)]
+
+use chaincraft_rust::{crypto::ecdsa::ECDSASigner, rng::RngProvider, shared_object::ApplyOutcome};
+
+/// Seed derived from `name`, the same on every run
+pub fn seed(name: &str) -> u64 {
+    name.bytes()
+        .fold(0u64, |seed, b| seed.wrapping_mul(31).wrapping_add(b as u64))
+}
+
+/// Key of a validator, the same on every call
+pub fn key(name: &str) -> ECDSASigner {
+    ECDSASigner::new_with_rng(&RngProvider::seeded(seed(name))).unwrap()
+}
+
+/// Reason code of an outcome that was not applied
+pub fn code(outcome: &ApplyOutcome) -> Option<&str> {
+    outcome.reason().map(|r| r.code.as_str())
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "api")]
+
+use chaincraft_rust::{
+    api::{ApiConfig, ApiServer},
+    network::{PeerId, PeerInfo},
+    shared::{MessageType, SharedMessage},
+    shared_object::SimpleSharedNumber,
+    ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+fn any_port() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+/// Send a raw HTTP request and return the status code and JSON body
+async fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+/// Send raw bytes and return the status code of the response, `None` if the
+/// server closed the connection without one
+async fn raw_status(addr: SocketAddr, request: &[u8]) -> Option<u16> {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+        .split(' ')
+        .nth(1)
+        .map(|status| status.parse().unwrap())
+}
+
+#[tokio::test]
+async fn test_api_reports_status_peers_and_object_state() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let peer = PeerInfo::new(PeerId::new(), "127.0.0.1:7001".parse().unwrap());
+    node.add_peer(peer.clone()).await?;
+    node.create_shared_message_with_data(json!(5)).await?;
+
+    let api = ApiServer::bind(node.handle(), any_port()).await?;
+    let addr = api.local_addr();
+
+    let (status, body) = request(addr, "GET", "/status", "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body, node.get_state().await?);
+
+    let (status, body) = request(addr, "GET", "/peers", "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["address"], json!("127.0.0.1:7001"));
+
+    // Ids may be abbreviated
+    let prefix = &id.to_string()[..8];
+    let (status, body) = request(addr, "GET", &format!("/objects/{}/state", prefix), "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["id"], json!(id.to_string()));
+    assert_eq!(body["state"]["number"], json!(5));
+
+    let (status, body) = request(addr, "GET", "/objects/ffffffff/state", "").await;
+    assert_eq!(status, 404);
+    assert!(body["error"].is_string());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_submits_messages() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let api = ApiServer::bind(node.handle(), any_port()).await?;
+    let addr = api.local_addr();
+
+    let message = SharedMessage::new(MessageType::Custom("chat".to_string()), json!("hi"));
+    let body = serde_json::to_string(&message)?;
+    let (status, reply) = request(addr, "POST", "/messages", &body).await;
+    assert_eq!(status, 202);
+    assert_eq!(reply, json!({"accepted": true, "hash": message.hash}));
+    assert!(node.get_message(&message.hash).await?.is_some());
+
+    let mut tampered = message.clone();
+    tampered.data = json!("bye");
+    let (status, reply) =
+        request(addr, "POST", "/messages", &serde_json::to_string(&tampered)?).await;
+    assert_eq!(status, 422);
+    assert_eq!(reply["accepted"], json!(false));
+
+    let (status, _) = request(addr, "POST", "/messages", "{not json").await;
+    assert_eq!(status, 400);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_serves_receipts_by_hash() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let hash = node.create_shared_message_with_data(json!(5)).await?;
+    let api = ApiServer::bind(node.handle(), any_port()).await?;
+    let addr = api.local_addr();
+
+    let (status, body) = request(addr, "GET", &format!("/receipts/{}", hash), "").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["tx_hash"], json!(hash));
+    assert_eq!(body["status"], json!("Success"));
+    assert_eq!(request(addr, "GET", "/receipts/missing", "").await.0, 404);
+    assert_eq!(request(addr, "POST", "/receipts/missing", "").await.0, 405);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_rejects_unknown_routes_and_large_bodies() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let config = ApiConfig {
+        max_body_bytes: 16,
+        ..ApiConfig::default()
+    };
+    let api = ApiServer::bind_with_config(node.handle(), any_port(), config).await?;
+    let addr = api.local_addr();
+
+    assert_eq!(request(addr, "GET", "/nowhere", "").await.0, 404);
+    assert_eq!(request(addr, "DELETE", "/status", "").await.0, 405);
+    assert_eq!(request(addr, "GET", "/messages", "").await.0, 405);
+    assert_eq!(request(addr, "POST", "/messages", &"x".repeat(17)).await.0, 413);
+    // Chunked bodies count against the same limit
+    let chunked = format!(
+        "POST /messages HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n11\r\n{}\r\n0\r\n\r\n",
+        "x".repeat(17)
+    );
+    assert_eq!(raw_status(addr, chunked.as_bytes()).await, Some(413));
+
+    // The server stops with its handle
+    drop(api);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(TcpStream::connect(addr).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_refuses_malformed_and_oversized_requests() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let config = ApiConfig {
+        request_timeout: std::time::Duration::from_millis(100),
+        ..ApiConfig::default()
+    };
+    let api = ApiServer::bind_with_config(node.handle(), any_port(), config).await?;
+    let addr = api.local_addr();
+
+    assert_eq!(raw_status(addr, b"NOT HTTP AT ALL\r\n\r\n").await, Some(400));
+    let bad_header = b"GET /status HTTP/1.1\r\nbad header\r\n\r\n";
+    assert_eq!(raw_status(addr, bad_header).await, Some(400));
+    let bad_length = b"POST /messages HTTP/1.1\r\nContent-Length: lots\r\n\r\n";
+    assert_eq!(raw_status(addr, bad_length).await, Some(400));
+    let huge_length = b"POST /messages HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n";
+    assert_eq!(raw_status(addr, huge_length).await, Some(413));
+
+    let huge_head = format!("GET /status HTTP/1.1\r\nX-Fill: {}\r\n\r\n", "a".repeat(20_000));
+    assert_eq!(raw_status(addr, huge_head.as_bytes()).await, Some(431));
+    let many_headers = format!("GET /status HTTP/1.1\r\n{}\r\n", "X-A: 1\r\n".repeat(40));
+    assert_eq!(raw_status(addr, many_headers.as_bytes()).await, Some(431));
+
+    // A client that stops sending is cut off
+    assert_eq!(raw_status(addr, b"GET /status HTTP/1.1\r\n").await, None);
+    let short_body = b"POST /messages HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc";
+    assert_eq!(raw_status(addr, short_body).await, Some(408));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_caps_concurrent_connections() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let config = ApiConfig {
+        max_connections: 1,
+        ..ApiConfig::default()
+    };
+    let api = ApiServer::bind_with_config(node.handle(), any_port(), config).await?;
+    let addr = api.local_addr();
+
+    // An idle client holds the only connection, so the next request waits
+    let idle = TcpStream::connect(addr).await?;
+    let waiting = tokio::spawn(async move { request(addr, "GET", "/status", "").await });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(!waiting.is_finished());
+
+    drop(idle);
+    assert_eq!(waiting.await.unwrap().0, 200);
+    Ok(())
+}
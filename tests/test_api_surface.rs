@@ -0,0 +1,179 @@
+//! The stable API, as a downstream crate sees it
+//!
+//! Everything here goes through `chaincraft_rust::prelude` only. A change to a
+//! pinned signature, a new required method on `ApplicationObject` or an item
+//! leaving the prelude stops this file from compiling: make it deliberately,
+//! in a major release, and update the file along with it.
+
+use chaincraft_rust::prelude::*;
+use serde_json::{json, Value};
+use std::any::Any;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Implements exactly the required methods of the trait
+#[derive(Debug, Clone)]
+struct Counter {
+    id: SharedObjectId,
+    count: u64,
+}
+
+#[async_trait]
+impl ApplicationObject for Counter {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+    fn type_name(&self) -> &'static str {
+        "Counter"
+    }
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.is_u64())
+    }
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        self.count += message.data.as_u64().unwrap_or_default();
+        Ok(ApplyOutcome::Applied)
+    }
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.count.to_string())
+    }
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "count": self.count }))
+    }
+    async fn reset(&mut self) -> Result<()> {
+        self.count = 0;
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Pins the signatures of async methods; type-checked, not run
+#[allow(dead_code)]
+async fn async_signatures(
+    mut node: ChaincraftNode,
+    handle: NodeHandle,
+    object: Box<dyn ApplicationObject>,
+    message: SharedMessage,
+    peer: PeerInfo,
+) -> Result<()> {
+    let _: () = node.start().await?;
+    let id: SharedObjectId = node.add_shared_object(object).await?;
+    let hash: String = node.create_shared_message_with_data(Value::Null).await?;
+    let _: String = node.receive_message(&peer.id, message.clone()).await?;
+    let _: Option<SharedMessage> = node.get_message(&hash).await?;
+    let _: Value = node.get_state().await?;
+    let _: () = node.add_peer(peer).await?;
+    let _: Vec<PeerInfo> = node.get_peers().await;
+    let _: bool = node.remove_shared_object(&id).await?;
+    let _: () = node.stop().await?;
+
+    let _: bool = handle.is_running().await;
+    let _: String = handle.create_message(Value::Null).await?;
+    let _: String = handle.submit_message(message).await?;
+    let _: Option<SharedMessage> = handle.get_message(&hash).await?;
+    let _: Vec<PeerInfo> = handle.get_peers().await;
+
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let _: () = storage.put("key", b"value".to_vec()).await?;
+    let _: Option<Vec<u8>> = storage.get("key").await?;
+    Ok(())
+}
+
+/// Outside the prelude but built without the `unstable` feature: these may
+/// change in a minor release, not in a patch
+#[allow(dead_code)]
+async fn public_modules(storage: Arc<dyn Storage>) -> Result<()> {
+    use chaincraft_rust::batching::{BatchConfig, BatchingStorage};
+
+    let _: Arc<BatchingStorage> = BatchingStorage::open(storage, BatchConfig::default()).await?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+fn error_variants(error: ChaincraftError) -> Option<String> {
+    let _: ChaincraftError = NetworkError::NoPeersAvailable.into();
+    match error {
+        ChaincraftError::Network(e) => Some(e.to_string()),
+        ChaincraftError::Crypto(e) => Some(e.to_string()),
+        ChaincraftError::Storage(e) => Some(e.to_string()),
+        ChaincraftError::Serialization(e) => Some(e.to_string()),
+        ChaincraftError::Validation(reason) | ChaincraftError::Config(reason) => Some(reason),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn api_surface() -> Result<()> {
+    let _: fn() -> ChaincraftNodeBuilder = ChaincraftNode::builder;
+    let _: fn() -> ChaincraftNodeBuilder = ChaincraftNodeBuilder::new;
+    let _: fn(ChaincraftNodeBuilder) -> Result<ChaincraftNode> = ChaincraftNodeBuilder::build;
+    let _: fn(ChaincraftNodeBuilder, PeerId) -> ChaincraftNodeBuilder =
+        ChaincraftNodeBuilder::with_id;
+    let _: fn(ChaincraftNodeBuilder, Arc<dyn Storage>) -> ChaincraftNodeBuilder =
+        ChaincraftNodeBuilder::with_storage;
+    let _: fn(ChaincraftNodeBuilder, u16) -> ChaincraftNodeBuilder = ChaincraftNodeBuilder::port;
+    let _: fn(ChaincraftNodeBuilder, usize) -> ChaincraftNodeBuilder =
+        ChaincraftNodeBuilder::max_peers;
+    let _: fn(&ChaincraftNode) -> &PeerId = ChaincraftNode::id;
+    let _: fn(&ChaincraftNode) -> u16 = ChaincraftNode::port;
+    let _: fn(&ChaincraftNode) -> NodeHandle = ChaincraftNode::handle;
+    let _: fn(&ChaincraftNode) -> broadcast::Receiver<NodeEvent> = ChaincraftNode::subscribe;
+    let _: fn(&NodeHandle) -> &PeerId = NodeHandle::id;
+    let _: fn(&NodeHandle) -> broadcast::Receiver<NodeEvent> = NodeHandle::subscribe;
+    let _: fn(MessageType, Value) -> SharedMessage = SharedMessage::new;
+    let _: fn() -> SharedObjectId = SharedObjectId::new;
+    let _: fn() -> PeerId = PeerId::new;
+    let _: fn(PeerId, SocketAddr) -> PeerInfo = PeerInfo::new;
+    let _: fn() -> MemoryStorage = MemoryStorage::new;
+    let _: fn(String) -> ChaincraftError = ChaincraftError::validation::<String>;
+    let _: fn(String) -> ChaincraftError = ChaincraftError::config::<String>;
+
+    let message = SharedMessage::new(MessageType::Custom("note".to_string()), json!(1));
+    let SharedMessage {
+        id: _,
+        message_type: _,
+        target_id: _,
+        data: _,
+        timestamp: _,
+        signature: _,
+        sender: _,
+        hash: _,
+        ..
+    } = message;
+    let _: (&SharedObjectId, &Value, &String) = (&message.id, &message.data, &message.hash);
+
+    // The facade alone is enough to run a node and an object
+    let mut node = ChaincraftNode::builder()
+        .with_storage(Arc::new(MemoryStorage::new()))
+        .build()?;
+    let id = node
+        .add_shared_object(Box::new(Counter {
+            id: SharedObjectId::new(),
+            count: 0,
+        }))
+        .await?;
+    let mut events = node.subscribe();
+    let hash = node.create_shared_message_with_data(json!(5)).await?;
+    assert!(node.get_message(&hash).await?.is_some());
+    assert!(std::iter::from_fn(|| events.try_recv().ok())
+        .any(|event| matches!(event, NodeEvent::MessageProcessed { .. })));
+    assert_eq!(node.get_typed::<Counter>(&id).await.unwrap().count, 5);
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use chaincraft_rust::{
+    crypto::ecdsa::ECDSASigner,
+    events::NodeEvent,
+    examples::chatroom::{helpers, ChatroomObject},
+    examples::tendermint::{self, TendermintObject},
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome, SimpleSharedNumber},
+    ChaincraftError, ChaincraftNode, Result,
+};
+use serde_json::json;
+
+fn number(value: serde_json::Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("number".to_string()), value)
+}
+
+#[tokio::test]
+async fn test_simple_number_reports_duplicates() -> Result<()> {
+    let mut object = SimpleSharedNumber::new();
+    assert_eq!(object.add_message(number(json!(4))).await?, ApplyOutcome::Applied);
+
+    let outcome = object.add_message(number(json!(4))).await?;
+    assert_eq!(outcome.reason().map(|r| r.code.as_str()), Some("duplicate"));
+    assert!(!outcome.is_applied() && !outcome.is_rejected());
+
+    let outcome = object.add_message(number(json!("four"))).await?;
+    assert!(outcome.is_rejected());
+    assert_eq!(object.get_number(), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tendermint_ignores_votes_for_other_rounds() -> Result<()> {
+    let mut tendermint = TendermintObject::new()?;
+    let address = tendermint.my_validator_address.clone();
+    tendermint.add_validator(address.clone(), tendermint.validator_key(), 1);
+    let prevote = tendermint::helpers::create_prevote_message(
+        tendermint.current_height + 5,
+        0,
+        Some("block".to_string()),
+        address,
+        &tendermint.signer,
+    )?;
+    let message = SharedMessage::new(MessageType::Custom("tendermint".to_string()), prevote);
+    let outcome = tendermint.add_message(message).await?;
+    assert_eq!(outcome.reason().unwrap().code, "wrong_round");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_publishes_outcomes_and_surfaces_rejections() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let mut events = node.subscribe();
+
+    let hash = node.create_shared_message_with_data(json!(2)).await?;
+    assert_eq!(
+        events.try_recv().unwrap(),
+        NodeEvent::MessageProcessed {
+            hash,
+            object_id: id.clone(),
+            outcome: ApplyOutcome::Applied,
+        }
+    );
+
+    // A second message with the same value is a no-op, not an error
+    node.create_shared_message_with_data(json!(2)).await?;
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        NodeEvent::MessageProcessed {
+            outcome: ApplyOutcome::Ignored(_),
+            ..
+        }
+    ));
+    assert_eq!(node.metrics().get("messages_ignored"), 1);
+
+    // Posting to a chatroom that does not exist breaks the protocol
+    node.add_shared_object(Box::new(ChatroomObject::new()))
+        .await?;
+    let signer = ECDSASigner::new()?;
+    let post = helpers::create_post_message("nowhere".to_string(), "hi".to_string(), &signer)?;
+    match node.create_shared_message_with_data(post).await {
+        Err(ChaincraftError::Rejected { code, .. }) => assert_eq!(code, "unknown_chatroom"),
+        other => panic!("expected rejection, got {:?}", other),
+    }
+    assert_eq!(node.metrics().get("messages_rejected"), 1);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use chaincraft_rust::{
+    audit::{self, AuditConfig, AuditEvent, AuditLog, AUDIT_GENESIS_HASH},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, PeerId, PeerInfo, Result,
+};
+use chrono::Utc;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_node_events_are_chained_and_tampering_is_detected() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_audit_log(AuditConfig::default())
+        .build()?;
+    node.start().await?;
+    let peer = PeerId::new();
+    node.add_peer(PeerInfo::new(peer.clone(), "127.0.0.1:9000".parse().unwrap()))
+        .await?;
+    let hash = node
+        .create_shared_message_with_data(json!({ "n": 1 }))
+        .await?;
+    let mut forged = SharedMessage::new(MessageType::Custom("note".to_string()), json!(1));
+    forged.data = json!(2);
+    assert!(node.receive_message(&peer, forged).await.is_err());
+    node.set_port(9100);
+    node.remove_peer(&peer).await?;
+    node.stop().await?;
+
+    let events: Vec<AuditEvent> = node
+        .audit_log()
+        .unwrap()
+        .entries()
+        .into_iter()
+        .map(|entry| entry.event)
+        .collect();
+    assert_eq!(events.len(), 7);
+    assert_eq!(events[0], AuditEvent::NodeStarted);
+    assert!(matches!(&events[1], AuditEvent::PeerConnected { peer_id, .. } if *peer_id == peer));
+    assert!(
+        matches!(&events[2], AuditEvent::MessageAccepted { hash: h, from: None, .. } if *h == hash)
+    );
+    assert!(matches!(
+        &events[3],
+        AuditEvent::MessageRejected { from: Some(from), .. } if *from == peer
+    ));
+    assert_eq!(
+        events[4],
+        AuditEvent::ConfigChanged {
+            setting: "port".to_string(),
+            value: "9100".to_string()
+        }
+    );
+    assert!(matches!(&events[5], AuditEvent::PeerDisconnected { .. }));
+    assert_eq!(events[6], AuditEvent::NodeStopped);
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("audit.jsonl");
+    assert_eq!(node.export_audit_log(&path).await?, 7);
+    let verification = audit::verify_export(&path).await?;
+    assert_eq!(verification.entries, 7);
+    assert_eq!(verification.head, Some(node.audit_log().unwrap().head()));
+
+    // Editing a recorded setting breaks its hash
+    let text = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, text.replace("9100", "9200"))?;
+    assert!(audit::verify_export(&path).await.is_err());
+
+    // Dropping an entry breaks the link of the next one
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines.remove(3);
+    std::fs::write(&path, lines.join("\n"))?;
+    assert!(audit::verify_export(&path).await.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_audit_log_keeps_recent_entries_verifiable() -> Result<()> {
+    let log = AuditLog::new(AuditConfig { max_entries: 3 });
+    assert_eq!(log.head(), AUDIT_GENESIS_HASH);
+    for port in 0..5 {
+        log.append(
+            AuditEvent::ConfigChanged {
+                setting: "port".to_string(),
+                value: port.to_string(),
+            },
+            Utc::now(),
+        )?;
+    }
+
+    let entries = log.entries();
+    assert_eq!(log.len(), 5);
+    assert_eq!(entries.iter().map(|e| e.sequence).collect::<Vec<_>>(), [2, 3, 4]);
+    assert_eq!(log.verify()?.head, Some(log.head()));
+    assert_eq!(log.entries_since(4).len(), 1);
+
+    let mut reordered = entries.clone();
+    reordered.swap(0, 1);
+    assert!(audit::verify_chain(&reordered).is_err());
+    let parsed = audit::parse_json_lines(&log.to_json_lines()?)?;
+    assert_eq!(parsed, entries);
+    Ok(())
+}
@@ -55,7 +55,10 @@ async fn wait_for_propagation(
     let timeout = Duration::from_secs(timeout_secs);
 
     while start.elapsed() < timeout {
-        let counts: Vec<usize> = nodes.iter().map(|node| node.db_size()).collect();
+        let mut counts = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            counts.push(node.db_size_async().await.unwrap_or(0));
+        }
         println!("Current message counts: {:?}", counts);
 
         if counts.iter().all(|&count| count == expected_count) {
@@ -150,7 +153,7 @@ async fn test_object_creation_and_propagation() -> Result<()> {
 
     // Wait for propagation (simplified - in reality would need gossip implementation)
     // For now, just verify the message was created
-    assert!(nodes[0].has_object(&message_hash));
+    assert!(nodes[0].has_object_async(&message_hash).await?);
     let stored_message = nodes[0].get_object(&message_hash).await?;
     let value: serde_json::Value = serde_json::from_str(&stored_message).map_err(|e| {
         chaincraft_rust::error::ChaincraftError::Serialization(
@@ -247,8 +250,8 @@ async fn test_network_resilience() -> Result<()> {
     nodes.push(restarted_node);
 
     // Verify basic functionality
-    assert!(nodes[0].has_object(&initial_hash), "Initial message not found");
-    assert!(nodes[0].has_object(&new_hash), "New message not found");
+    assert!(nodes[0].has_object_async(&initial_hash).await?, "Initial message not found");
+    assert!(nodes[0].has_object_async(&new_hash).await?, "New message not found");
 
     // Cleanup
     for mut node in nodes {
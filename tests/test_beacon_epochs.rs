@@ -0,0 +1,136 @@
+mod common;
+
+use chaincraft_rust::{
+    examples::randomness_beacon::{helpers, BeaconValidator, RandomnessBeaconObject},
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    Result,
+};
+use common::key;
+
+fn validator(address: &str, stake: u64) -> BeaconValidator {
+    let key = key(address).get_public_key_pem().unwrap();
+    BeaconValidator {
+        address: address.to_string(),
+        public_key: key.clone(),
+        vrf_key: key,
+        stake,
+        active: true,
+        last_participation: None,
+    }
+}
+
+fn message(data: serde_json::Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("BEACON".to_string()), data)
+}
+
+/// Submit a VRF proof and partial signature for the current round
+async fn contribute(beacon: &mut RandomnessBeaconObject, validator: &str) -> Result<ApplyOutcome> {
+    let round = beacon.current_round;
+    let vrf = helpers::create_vrf_proof_message(
+        round,
+        "seed".to_string(),
+        validator.to_string(),
+        &key(validator),
+    )?;
+    let outcome = beacon.add_message(message(vrf)).await?;
+    if outcome.is_applied() {
+        let partial = helpers::create_partial_signature_message(
+            round,
+            validator.to_string(),
+            format!("partial-{}", validator),
+            &key(validator),
+        )?;
+        beacon.add_message(message(partial)).await?;
+    }
+    Ok(outcome)
+}
+
+fn genesis_beacon() -> RandomnessBeaconObject {
+    let mut beacon = RandomnessBeaconObject::new(60, 1).unwrap().with_epochs(2);
+    for (address, stake) in [("v1", 100), ("v2", 200), ("v3", 300)] {
+        beacon
+            .register_validator(validator(address, stake))
+            .unwrap();
+    }
+    beacon
+}
+
+#[tokio::test]
+async fn test_threshold_comes_from_epoch_snapshot() -> Result<()> {
+    let beacon = genesis_beacon();
+    assert_eq!(beacon.current_epoch(), Some(0));
+    let epoch = beacon.epoch_stats(0).unwrap();
+    assert_eq!(epoch.stakes.len(), 3);
+    assert_eq!(epoch.total_stake(), 600);
+    // Two thirds of three validators, plus one
+    assert_eq!(beacon.threshold, 3);
+
+    let plain = RandomnessBeaconObject::new(60, 1)?;
+    assert_eq!(plain.current_epoch(), None);
+    assert_eq!(plain.threshold, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_joins_and_exits_wait_for_next_epoch() -> Result<()> {
+    let mut beacon = genesis_beacon();
+
+    contribute(&mut beacon, "v1").await?;
+    // A validator joining mid-epoch cannot change the count round 1 waits for
+    beacon.register_validator(validator("v4", 400))?;
+    assert!(matches!(
+        contribute(&mut beacon, "v4").await?,
+        ApplyOutcome::Ignored(reason) if reason.code == "not_participating"
+    ));
+    assert_eq!(beacon.threshold, 3);
+    contribute(&mut beacon, "v2").await?;
+    contribute(&mut beacon, "v3").await?;
+    assert_eq!(beacon.current_round, 2);
+
+    // v3 leaves, but still belongs to the epoch-0 set
+    let exit = helpers::create_validator_exit("v3".to_string(), &key("v3"))?;
+    assert!(beacon.add_message(message(exit)).await?.is_applied());
+    for v in ["v1", "v2", "v3"] {
+        assert!(contribute(&mut beacon, v).await?.is_applied());
+    }
+
+    // Epoch 1: v4 joined, v3 left
+    assert_eq!(beacon.current_round, 3);
+    assert_eq!(beacon.current_epoch(), Some(1));
+    let epoch = beacon.epoch_stats(1).unwrap();
+    assert_eq!(epoch.start_round, 3);
+    assert!(epoch.contains("v4") && !epoch.contains("v3"));
+    assert_eq!(epoch.total_stake(), 700);
+    assert!(matches!(contribute(&mut beacon, "v3").await?, ApplyOutcome::Ignored(_)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_epoch_participation_statistics() -> Result<()> {
+    let mut beacon = RandomnessBeaconObject::new(60, 1)?.with_epochs(2);
+    for (address, stake) in [("v1", 100), ("v2", 100), ("v3", 100), ("v4", 100)] {
+        beacon.register_validator(validator(address, stake))?;
+    }
+    // Four validators need three contributions; v4 never shows up
+    for _ in 0..2 {
+        for v in ["v1", "v2", "v3"] {
+            contribute(&mut beacon, v).await?;
+        }
+    }
+
+    let epoch = beacon.epoch_stats(0).unwrap();
+    assert_eq!(epoch.rounds_finalized, 2);
+    assert_eq!(epoch.liveness("v1"), Some(1.0));
+    assert_eq!(epoch.liveness("v4"), Some(0.0));
+    assert_eq!(epoch.liveness("unknown"), None);
+    assert_eq!(epoch.absent_validators(), vec!["v4".to_string()]);
+    assert!((epoch.participation_rate() - 0.75).abs() < f64::EPSILON);
+    assert_eq!(beacon.validators["v1"].last_participation, Some(2));
+    assert_eq!(beacon.epoch_history.len(), 1);
+
+    let stats = beacon.get_beacon_stats();
+    assert_eq!(stats["epoch"], 1);
+    assert_eq!(stats["epoch_validators"], 4);
+    Ok(())
+}
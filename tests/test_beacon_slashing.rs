@@ -0,0 +1,156 @@
+mod common;
+
+use chaincraft_rust::{
+    crypto::ecdsa::ECDSASigner,
+    examples::randomness_beacon::{
+        helpers, BeaconValidator, CaseStatus, RandomnessBeaconObject, SlashingConfig, VrfProof,
+    },
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    Result,
+};
+use common::{code, key};
+
+fn message(data: serde_json::Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("BEACON".to_string()), data)
+}
+
+fn vrf_key(validator: &str) -> ECDSASigner {
+    key(&format!("{}-vrf", validator))
+}
+
+/// Submit a VRF proof and partial signature for the current round
+async fn contribute(beacon: &mut RandomnessBeaconObject, validator: &str) -> Result<ApplyOutcome> {
+    let round = beacon.current_round;
+    let vrf = helpers::create_vrf_proof_message(
+        round,
+        "seed".to_string(),
+        validator.to_string(),
+        &vrf_key(validator),
+    )?;
+    let outcome = beacon.add_message(message(vrf)).await?;
+    if outcome.is_applied() {
+        let partial = helpers::create_partial_signature_message(
+            round,
+            validator.to_string(),
+            format!("partial-{}", validator),
+            &key(validator),
+        )?;
+        beacon.add_message(message(partial)).await?;
+    }
+    Ok(outcome)
+}
+
+/// A beacon with two honest validators and Mallory
+fn beacon() -> Result<RandomnessBeaconObject> {
+    let mut beacon =
+        RandomnessBeaconObject::new(60, 2)?.with_slashing(SlashingConfig::new(50, 4, 2));
+    for address in ["v1", "v2", "mallory"] {
+        beacon.register_validator(BeaconValidator {
+            address: address.to_string(),
+            public_key: key(address).get_public_key_pem()?,
+            vrf_key: vrf_key(address).get_public_key_pem()?,
+            stake: 1000,
+            active: true,
+            last_participation: None,
+        })?;
+    }
+    Ok(beacon)
+}
+
+/// Mallory's correctly evaluated round 1 proof for `input`
+fn evaluated(vrf: &ECDSASigner, input: &str) -> Result<VrfProof> {
+    let mallory = RandomnessBeaconObject::with_keys(60, 2, ECDSASigner::new()?, vrf.clone())?;
+    let mut proof = mallory.generate_vrf_proof(input)?;
+    proof.validator = "mallory".to_string();
+    Ok(proof)
+}
+
+fn resign(mut proof: VrfProof, round: u64, vrf: &ECDSASigner) -> Result<VrfProof> {
+    let data = format!("vrf:{}:{}:{}:{}", round, proof.input, proof.proof, proof.output);
+    proof.signature = hex::encode(vrf.sign(data.as_bytes())?.to_bytes());
+    Ok(proof)
+}
+
+fn challenge(first: VrfProof, second: VrfProof) -> Result<SharedMessage> {
+    Ok(message(helpers::create_conflicting_outputs_challenge(
+        1,
+        "v1".to_string(),
+        "mallory".to_string(),
+        first,
+        second,
+        &key("v1"),
+    )?))
+}
+
+fn appeal(challenge: &SharedMessage) -> Result<SharedMessage> {
+    Ok(message(helpers::create_challenge_appeal(
+        challenge.hash.clone(),
+        "mallory".to_string(),
+        &key("mallory"),
+    )?))
+}
+
+#[tokio::test]
+async fn test_fabricated_output_is_slashed_after_appeal_window() -> Result<()> {
+    let vrf = vrf_key("mallory");
+    let mut beacon = beacon()?;
+    let honest = evaluated(&vrf, "seed")?;
+    let mut fabricated = honest.clone();
+    fabricated.output = "ff".repeat(16);
+    let fabricated = resign(fabricated, 1, &vrf)?;
+
+    // Evidence must be signed with Mallory's VRF key
+    let forged = resign(fabricated.clone(), 1, &ECDSASigner::new()?)?;
+    let outcome = beacon
+        .add_message(challenge(honest.clone(), forged)?)
+        .await?;
+    assert_eq!(code(&outcome), Some("invalid_evidence"));
+    assert!(!beacon.is_excluded("mallory"));
+
+    let upheld = challenge(honest, fabricated)?;
+    assert!(beacon.add_message(upheld.clone()).await?.is_applied());
+    assert!(beacon.is_excluded("mallory"));
+    assert_eq!(code(&contribute(&mut beacon, "mallory").await?), Some("excluded"));
+    assert_eq!(code(&beacon.add_message(upheld.clone()).await?), Some("already_challenged"));
+
+    // The made-up output does not match the VRF evaluation
+    let outcome = beacon.add_message(appeal(&upheld)?).await?;
+    assert_eq!(code(&outcome), Some("appeal_denied"));
+
+    for _ in 0..2 {
+        contribute(&mut beacon, "v1").await?;
+        contribute(&mut beacon, "v2").await?;
+    }
+    assert_eq!(beacon.current_round, 3);
+    let case = &beacon.cases[&upheld.hash];
+    assert_eq!(case.status, CaseStatus::Slashed);
+    assert_eq!(case.slashed, 500);
+    let mallory = &beacon.validators["mallory"];
+    assert_eq!(mallory.stake, 500);
+    assert!(!mallory.active);
+    assert!(!beacon.is_participant("mallory"));
+    assert_eq!(code(&beacon.add_message(appeal(&upheld)?).await?), Some("case_closed"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_appeal_clears_honest_evaluations() -> Result<()> {
+    let vrf = vrf_key("mallory");
+    let mut beacon = beacon()?;
+    let upheld = challenge(evaluated(&vrf, "a")?, evaluated(&vrf, "b")?)?;
+    assert!(beacon.add_message(upheld.clone()).await?.is_applied());
+    assert_eq!(beacon.cases_against("mallory").len(), 1);
+
+    assert!(beacon.add_message(appeal(&upheld)?).await?.is_applied());
+    assert_eq!(beacon.cases[&upheld.hash].status, CaseStatus::Dismissed);
+    assert!(!beacon.is_excluded("mallory"));
+    assert!(contribute(&mut beacon, "mallory").await?.is_applied());
+    assert_eq!(beacon.validators["mallory"].stake, 1000);
+
+    // Offences older than the evidence window can no longer be challenged
+    beacon.current_round = 6;
+    let late = challenge(evaluated(&vrf, "c")?, evaluated(&vrf, "d")?)?;
+    assert_eq!(code(&beacon.add_message(late).await?), Some("evidence_expired"));
+    Ok(())
+}
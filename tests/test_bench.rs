@@ -0,0 +1,77 @@
+#![cfg(feature = "grpc")]
+
+use chaincraft_rust::{
+    crypto::{utils::generate_keypair, KeyType},
+    network::bench::{run, synthetic_message, BenchConfig, BenchPath, BENCH_MESSAGE_TYPE},
+    network::grpc::{GrpcClient, GrpcTransport},
+    shared::MessageType,
+    ChaincraftError, ChaincraftNode, Result,
+};
+use std::time::Duration;
+
+fn any_port() -> std::net::SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+fn quick() -> BenchConfig {
+    BenchConfig::default()
+        .with_rate(50)
+        .with_size(300)
+        .with_duration(Duration::from_millis(400))
+}
+
+#[tokio::test]
+async fn test_bench_reports_accepted_load_and_node_metrics() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let transport = GrpcTransport::bind_node(&node, any_port()).await?;
+    let target = transport.local_addr().to_string();
+
+    let report = run(&target, &quick()).await?;
+    assert_eq!(report.node_id, node.id().to_string());
+    assert_eq!(report.sent, 20);
+    assert_eq!(report.accepted, 20, "{}", report);
+    assert_eq!(report.acceptance_rate(), 1.0);
+    assert_eq!(report.latencies.len(), 20);
+    assert!(report.latency(0.5) <= report.latency(0.99));
+    assert_eq!(report.latency(1.0), report.latencies.last().copied());
+    assert_eq!(report.metric_changes()["cached_messages"], (0, 20));
+    assert!(report.metrics_after.contains_key("process_rss_bytes"));
+    assert!(report.to_string().contains("20 accepted (100.0%)"));
+
+    let stored = node
+        .metrics()
+        .get(&format!("messages_type:{}", BENCH_MESSAGE_TYPE));
+    assert_eq!(stored, 20);
+
+    let key = generate_keypair(KeyType::Ed25519)?.0;
+    let message = synthetic_message(&key, 7, 300)?;
+    assert_eq!(message.message_type, MessageType::Custom(BENCH_MESSAGE_TYPE.to_string()));
+    assert_eq!(message.data.to_string().len(), 300);
+    assert!(message.verified_sender().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refusals_are_counted_with_their_reasons() -> Result<()> {
+    let node = ChaincraftNode::builder().with_read_replica(true).build()?;
+    let transport = GrpcTransport::bind_node(&node, any_port()).await?;
+    let target = transport.local_addr().to_string();
+
+    let report = run(&target, &quick()).await?;
+    assert_eq!(report.accepted, 0);
+    assert_eq!(report.rejected, report.sent);
+    assert_eq!(report.reasons.len(), 1);
+    assert_eq!(report.reasons.values().sum::<u64>(), report.sent);
+
+    // The peer path only checks the hash before queueing
+    let report = run(&target, &quick().with_path(BenchPath::Peer)).await?;
+    assert_eq!(report.accepted, report.sent);
+
+    let mut client = GrpcClient::connect(transport.local_addr()).await?;
+    assert_eq!(client.node_metrics().await?["cached_messages"], 0);
+
+    let error = run(&target, &quick().with_rate(0)).await.unwrap_err();
+    assert!(matches!(error, ChaincraftError::Config(_)));
+    assert!("udp".parse::<BenchPath>().is_err());
+    Ok(())
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use chaincraft_rust::{
+    examples::tendermint::{self, StateDifference, TendermintObject, ValidatorKey},
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome, SimpleSharedNumber},
+    Result,
+};
+use common::key;
+use serde_json::{json, Value};
+
+fn message(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("consensus".to_string()), data)
+}
+
+fn chain() -> Result<TendermintObject> {
+    let mut tendermint =
+        TendermintObject::new()?.with_application(Box::new(SimpleSharedNumber::new()));
+    for name in ["v1", "v2", "v3"] {
+        tendermint.add_validator(
+            name.to_string(),
+            ValidatorKey::new(key(name).public_key().clone()),
+            1,
+        );
+    }
+    Ok(tendermint)
+}
+
+/// Propose `transactions` and commit them with every validator's precommit
+async fn commit(tendermint: &mut TendermintObject, transactions: Vec<Value>) -> Result<()> {
+    let height = tendermint.current_height;
+    let block_hash = format!("block-{}", height);
+    let proposal = tendermint::helpers::create_block_proposal_message(
+        height,
+        0,
+        block_hash.clone(),
+        "v1".to_string(),
+        transactions,
+        &key("v1"),
+    )?;
+    tendermint.add_message(message(proposal)).await?;
+    for name in ["v1", "v2", "v3"] {
+        let precommit = tendermint::helpers::create_precommit_message(
+            height,
+            0,
+            Some(block_hash.clone()),
+            name.to_string(),
+            &key(name),
+        )?;
+        tendermint.add_message(message(precommit)).await?;
+    }
+    assert_eq!(tendermint.current_height, height + 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_committed_blocks_replay_to_their_state_root() -> Result<()> {
+    let mut tendermint = chain()?;
+    commit(&mut tendermint, vec![json!(5), json!(7)]).await?;
+    commit(&mut tendermint, vec![json!(1), json!(5)]).await?;
+
+    assert_eq!(tendermint.blocks[1].transactions, vec![json!(5), json!(7)]);
+    assert_eq!(tendermint.executed_height, 2);
+    let application = tendermint.application().unwrap();
+    assert_eq!(application.get_state().await?["number"], 13);
+    assert_eq!(
+        tendermint.blocks[2].app_state_root.as_deref(),
+        Some(application.get_latest_digest().await?.as_str())
+    );
+    assert_ne!(tendermint.blocks[1].app_state_root, tendermint.blocks[2].app_state_root);
+
+    for height in [1, 2] {
+        assert_eq!(tendermint.replay_block(height).await?, None);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_reports_where_state_diverged() -> Result<()> {
+    let mut tendermint = chain()?;
+    commit(&mut tendermint, vec![json!(5), json!(7)]).await?;
+    commit(&mut tendermint, vec![json!(1), json!(5)]).await?;
+
+    // As if block 1 had been executed differently by this node
+    tendermint.blocks[1].transactions[0] = json!(6);
+
+    let diff = tendermint.replay_block(1).await?.unwrap();
+    assert_eq!(diff.expected_root, tendermint.blocks[1].app_state_root.clone().unwrap());
+    assert!(!diff.diverged_earlier);
+    assert!(diff.differences.is_empty(), "block 1 is not the latest");
+
+    let diff = tendermint.replay_block(2).await?.unwrap();
+    assert!(diff.diverged_earlier);
+    assert_ne!(diff.actual_root, diff.expected_root);
+    // The second 5 is only a duplicate in the committed history
+    assert_eq!(diff.outcomes[0], ApplyOutcome::Applied);
+    assert_eq!(diff.outcomes[1], ApplyOutcome::Applied);
+    assert!(diff.differences.contains(&StateDifference {
+        path: "/number".to_string(),
+        committed: json!(13),
+        replayed: json!(19),
+    }));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replay_needs_an_application_and_every_earlier_block() -> Result<()> {
+    let mut tendermint = chain()?;
+    commit(&mut tendermint, vec![json!(5)]).await?;
+    assert!(tendermint.replay_block(0).await.is_err());
+    assert!(tendermint.replay_block(2).await.is_err());
+
+    tendermint.create_checkpoint()?;
+    commit(&mut tendermint, vec![json!(2)]).await?;
+    assert_eq!(tendermint.prune_to_checkpoint(), 1);
+    assert!(tendermint.replay_block(2).await.is_err());
+
+    let mut plain = TendermintObject::new()?;
+    plain.add_validator("v1".to_string(), ValidatorKey::new(key("v1").public_key().clone()), 1);
+    assert!(plain.application().is_none());
+    assert!(plain.replay_block(1).await.is_err());
+    Ok(())
+}
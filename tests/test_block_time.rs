@@ -0,0 +1,102 @@
+use chaincraft_rust::{
+    consensus::{
+        poa::{PoaConfig, PoaEngine},
+        BlockRate, BlockTimeConfig, BLOCK_RATE_WINDOW,
+    },
+    crypto::{utils, KeyType, PrivateKey},
+    examples::tendermint::TendermintObject,
+    shared_object::ApplicationObject,
+    ChaincraftNode, Result,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::json;
+use std::time::Duration;
+
+fn engine(block_time: BlockTimeConfig) -> PoaEngine {
+    let key: PrivateKey = utils::generate_keypair(KeyType::Ed25519).unwrap().0;
+    let config = PoaConfig {
+        authorities: vec![key.public_key().to_hex()],
+        block_period: Duration::ZERO,
+    };
+    PoaEngine::new(config)
+        .with_signer(key)
+        .with_block_time(block_time)
+}
+
+#[test]
+fn test_block_is_due_after_interval_unless_empty_and_skipped() {
+    let every_second = BlockTimeConfig::new(Duration::from_secs(1));
+    assert!(!every_second.is_due(Duration::from_millis(500), 3));
+    assert!(every_second.is_due(Duration::from_secs(1), 0));
+
+    let skipping = every_second.skip_empty_blocks(Duration::from_secs(10));
+    assert!(!skipping.is_due(Duration::from_millis(500), 3));
+    assert!(skipping.is_due(Duration::from_secs(1), 3));
+    assert!(!skipping.is_due(Duration::from_secs(9), 0));
+    assert!(skipping.is_due(Duration::from_secs(10), 0));
+}
+
+#[test]
+fn test_block_rate_averages_recent_blocks() {
+    assert_eq!(BlockRate::from_timestamps(&[]).average_interval, None);
+    assert_eq!(BlockRate::from_timestamps(&[Utc::now()]).blocks_per_minute(), 0.0);
+
+    let start = Utc::now();
+    // A slow start falls out of the window
+    let mut timestamps = vec![start - ChronoDuration::hours(1)];
+    timestamps.extend((0..30).map(|i| start + ChronoDuration::seconds(2 * i)));
+    let rate = BlockRate::from_timestamps(&timestamps);
+    assert_eq!(rate.blocks, BLOCK_RATE_WINDOW);
+    assert_eq!(rate.average_interval, Some(Duration::from_secs(2)));
+    assert_eq!(rate.blocks_per_minute(), 30.0);
+    assert_eq!(rate.to_json()["average_interval_ms"], json!(2000));
+}
+
+#[test]
+fn test_poa_skips_empty_blocks_until_max_interval() -> Result<()> {
+    let mut engine =
+        engine(BlockTimeConfig::new(Duration::ZERO).skip_empty_blocks(Duration::from_millis(50)));
+
+    engine.submit_transaction(json!({"from": "alice", "to": "bob"}));
+    let block = engine.produce_block_if_due()?.expect("transaction pending");
+    assert_eq!(block.transactions.len(), 1);
+    assert!(engine.produce_block_if_due()?.is_none());
+
+    std::thread::sleep(Duration::from_millis(60));
+    let empty = engine.produce_block_if_due()?.expect("max interval passed");
+    assert!(empty.transactions.is_empty());
+    assert_eq!(engine.block_rate().blocks, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_reports_block_rate_in_metrics() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_poa(engine(BlockTimeConfig::new(Duration::from_millis(40))))
+        .build()?;
+
+    node.start().await?;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    node.stop().await?;
+
+    assert!(node.metrics().get("poa_blocks_sealed") >= 2);
+    assert!(node.metrics().get("poa_block_interval_ms") >= 40);
+    assert!(node.metrics().get("poa_blocks_per_hour") > 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tendermint_proposes_on_its_block_time() -> Result<()> {
+    // Genesis was just produced, the default 5 second interval has not passed
+    assert!(!TendermintObject::new()?.should_propose(1));
+    let eager = TendermintObject::new()?.with_block_time(BlockTimeConfig::new(Duration::ZERO));
+    assert!(eager.should_propose(0));
+
+    let idle = TendermintObject::new()?.with_block_time(
+        BlockTimeConfig::new(Duration::ZERO).skip_empty_blocks(Duration::from_secs(60)),
+    );
+    assert!(!idle.should_propose(0));
+    assert!(idle.should_propose(1));
+    assert_eq!(idle.get_state().await?["block_rate"]["blocks"], json!(0));
+    Ok(())
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use chaincraft_rust::{
+    crypto::{
+        bls,
+        ecdsa::ECDSASigner,
+        utils::{generate_keypair, generate_keypair_with_rng},
+        KeyType, PrivateKey, PublicKey, Signature,
+    },
+    examples::tendermint::{self, TendermintObject, ValidatorInfo, ValidatorKey},
+    rng::RngProvider,
+    shared::{MessageType, SharedMessage},
+    shared_object::ApplicationObject,
+    Result,
+};
+
+/// BLS key of a validator, the same on every call
+fn key(address: &str) -> PrivateKey {
+    generate_keypair_with_rng(KeyType::Bls12_381, &mut RngProvider::seeded(common::seed(address)))
+        .unwrap()
+        .0
+}
+
+#[test]
+fn test_sign_verify_and_encoding() -> Result<()> {
+    let (private_key, public_key) = generate_keypair(KeyType::Bls12_381)?;
+    assert_eq!(public_key.key_type(), KeyType::Bls12_381);
+    assert_eq!(public_key.as_bytes().len(), 48);
+
+    let signature = private_key.sign(b"block-1")?;
+    assert_eq!(signature.to_bytes().len(), 96);
+    assert!(public_key.verify(b"block-1", &signature)?);
+    assert!(!public_key.verify(b"block-2", &signature)?);
+    assert!(!key("other").public_key().verify(b"block-1", &signature)?);
+
+    // Signing is deterministic
+    assert_eq!(private_key.sign(b"block-1")?, signature);
+
+    let restored = PrivateKey::from_hex(&private_key.to_hex(), KeyType::Bls12_381)?;
+    assert_eq!(restored.public_key(), public_key);
+    assert_eq!(PublicKey::from_hex(&public_key.to_hex(), KeyType::Bls12_381)?, public_key);
+    assert_eq!(KeyType::of_public_key_hex(&public_key.to_hex()), KeyType::Bls12_381);
+
+    // Serde tells the curves apart by length
+    let json = serde_json::to_string(&(public_key.clone(), signature.clone()))?;
+    let decoded: (PublicKey, Signature) = serde_json::from_str(&json)?;
+    assert_eq!(decoded, (public_key, signature));
+    Ok(())
+}
+
+#[test]
+fn test_aggregate_signature_verifies_against_aggregate_key() -> Result<()> {
+    let keys: Vec<PrivateKey> = ["v1", "v2", "v3"].into_iter().map(key).collect();
+    let public_keys: Vec<PublicKey> = keys.iter().map(|k| k.public_key()).collect();
+    let signatures = keys
+        .iter()
+        .map(|k| k.sign(b"commit"))
+        .collect::<Result<Vec<_>>>()?;
+
+    let aggregate = bls::aggregate_signatures(&signatures)?;
+    assert!(bls::verify_aggregate(&public_keys, b"commit", &aggregate)?);
+    assert!(bls::aggregate_public_keys(&public_keys)?.verify(b"commit", &aggregate)?);
+    // Every signer has to be there, and only them
+    assert!(!bls::verify_aggregate(&public_keys[..2], b"commit", &aggregate)?);
+    let partial = bls::aggregate_signatures(&signatures[..2])?;
+    assert!(!bls::verify_aggregate(&public_keys, b"commit", &partial)?);
+    assert!(!bls::verify_aggregate(&public_keys, b"other", &aggregate)?);
+
+    // Other curves cannot be aggregated
+    let (ed_key, ed_public) = generate_keypair(KeyType::Ed25519)?;
+    assert!(bls::aggregate_signatures(&[]).is_err());
+    assert!(bls::aggregate_signatures(&[ed_key.sign(b"commit")?]).is_err());
+    assert!(bls::aggregate_public_keys(&[public_keys[0].clone(), ed_public]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_proof_of_possession() -> Result<()> {
+    let alice = key("alice");
+    let proof = bls::prove_possession(&alice)?;
+    assert!(bls::verify_possession(&alice.public_key(), &proof));
+    assert!(!bls::verify_possession(&key("bob").public_key(), &proof));
+    // A proof is not a signature over the key bytes
+    let signed = alice.sign(&alice.public_key().as_bytes())?;
+    assert!(!bls::verify_possession(&alice.public_key(), &signed));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commit_stores_one_aggregate_signature() -> Result<()> {
+    let names = ["v1", "v2", "v3", "v4"];
+    let mut tendermint = TendermintObject::with_signer(ECDSASigner::from_private_key(key("v1")))?;
+    for name in names {
+        tendermint.add_validator(name.to_string(), ValidatorKey::new(key(name).public_key()), 1);
+    }
+
+    for name in &names[1..] {
+        let data = tendermint::helpers::create_precommit_message(
+            1,
+            0,
+            Some("block-1".to_string()),
+            name.to_string(),
+            &ECDSASigner::from_private_key(key(name)),
+        )?;
+        let message = SharedMessage::new(MessageType::Custom("consensus".to_string()), data);
+        assert!(tendermint.add_message(message).await?.is_applied());
+    }
+    assert_eq!(tendermint.current_height, 2);
+
+    let block = tendermint.blocks[1].clone();
+    assert!(block.commit_signatures.is_empty());
+    let certificate = block.commit_certificate.clone().unwrap();
+    assert_eq!(certificate.signers, ["v2", "v3", "v4"]);
+    assert!(tendermint.verify_commit_certificate(&block));
+
+    // Dropping a signer breaks the aggregate, and too few signers are no majority
+    let mut forged = block.clone();
+    forged.commit_certificate.as_mut().unwrap().signers.pop();
+    assert!(!tendermint.verify_commit_certificate(&forged));
+    tendermint.add_validator("v5".to_string(), ValidatorKey::new(key("v5").public_key()), 2);
+    assert!(!tendermint.verify_commit_certificate(&block));
+    Ok(())
+}
+
+/// Message announcing `validators` as the set at height 1
+fn validator_set(validators: Vec<ValidatorInfo>) -> Result<SharedMessage> {
+    let data = tendermint::helpers::create_validator_set_message(validators, 1)?;
+    Ok(SharedMessage::new(MessageType::Custom("consensus".to_string()), data))
+}
+
+#[tokio::test]
+async fn test_rogue_keys_cannot_join_without_possession() -> Result<()> {
+    use bls12_381::{G1Affine, G1Projective};
+
+    let honest: Vec<ValidatorInfo> = ["v1", "v2"]
+        .into_iter()
+        .map(|name| ValidatorInfo::with_possession(name.to_string(), &key(name), 1))
+        .collect::<Result<_>>()?;
+    let mut tendermint = TendermintObject::with_signer(ECDSASigner::from_private_key(key("v1")))?;
+    assert!(tendermint
+        .add_message(validator_set(honest.clone())?)
+        .await?
+        .is_applied());
+    assert!(!tendermint
+        .add_message(validator_set(honest.clone())?)
+        .await?
+        .is_applied());
+
+    // The rogue key is pk_evil - (pk_v1 + pk_v2), so the sum of all three
+    // keys is pk_evil and the attacker alone signs for everyone
+    let evil = key("evil");
+    let point = |key: &PublicKey| {
+        let bytes: [u8; 48] = key.as_bytes().try_into().unwrap();
+        G1Projective::from(G1Affine::from_compressed(&bytes).unwrap())
+    };
+    let others: G1Projective = honest
+        .iter()
+        .map(|v| point(v.public_key.public_key()))
+        .sum();
+    let rogue = G1Affine::from(point(&evil.public_key()) - others);
+    let rogue = PublicKey::from_hex(&hex::encode(rogue.to_compressed()), KeyType::Bls12_381)?;
+    let mut all_keys: Vec<PublicKey> = honest
+        .iter()
+        .map(|v| v.public_key.public_key().clone())
+        .collect();
+    all_keys.push(rogue.clone());
+    assert!(bls::verify_aggregate(&all_keys, b"commit", &evil.sign(b"commit")?)?);
+
+    // Without the rogue key's secret there is no proof for it
+    let mut rogue_validator = ValidatorInfo {
+        address: "rogue".to_string(),
+        public_key: ValidatorKey::new(rogue),
+        voting_power: 1,
+        active: true,
+        possession: None,
+    };
+    let outcome = tendermint
+        .add_message(validator_set(vec![rogue_validator.clone()])?)
+        .await?;
+    assert_eq!(outcome.reason().unwrap().code, "missing_possession");
+    rogue_validator.possession = Some(bls::prove_possession(&evil)?.to_hex());
+    let outcome = tendermint
+        .add_message(validator_set(vec![rogue_validator])?)
+        .await?;
+    assert_eq!(outcome.reason().unwrap().code, "invalid_possession");
+    assert!(!tendermint.validators.contains_key("rogue"));
+    Ok(())
+}
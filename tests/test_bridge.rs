@@ -0,0 +1,105 @@
+use chaincraft_rust::{
+    network::bridge::{Bridge, BridgeConfig, BridgeSide, BridgedMessage, RelayOutcome},
+    network::transport::{InMemoryNetwork, InMemoryTransport, Transport},
+    network::PeerId,
+    shared::{MessageType, SharedMessage},
+    Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn transfer_config() -> BridgeConfig {
+    BridgeConfig::new().allow(MessageType::Custom("transfer".to_string()))
+}
+
+fn bridge_between(
+    left: &InMemoryNetwork,
+    left_id: &str,
+    right: &InMemoryNetwork,
+    right_id: &str,
+) -> Bridge<InMemoryTransport, InMemoryTransport> {
+    Bridge::new(
+        left_id,
+        left.connect(PeerId::new()),
+        right_id,
+        right.connect(PeerId::new()),
+        transfer_config(),
+    )
+}
+
+#[tokio::test]
+async fn test_whitelisted_messages_are_relayed() -> Result<()> {
+    let chain_a = InMemoryNetwork::new();
+    let chain_b = InMemoryNetwork::new();
+    let alice = chain_a.connect(PeerId::new());
+    let bob = chain_b.connect(PeerId::new());
+    let bridge = Arc::new(bridge_between(&chain_a, "chain-a", &chain_b, "chain-b"));
+    bridge.add_peer("chain-b", bob.local_id().clone())?;
+    assert!(bridge.add_peer("chain-c", PeerId::new()).is_err());
+    let tasks = bridge.clone().spawn();
+
+    let transfer = SharedMessage::custom("transfer", json!({ "amount": 5 }))?;
+    alice
+        .send(bridge.local_id(BridgeSide::Left), &transfer)
+        .await?;
+    let chat = SharedMessage::custom("chat", json!("hi"))?;
+    alice.send(bridge.local_id(BridgeSide::Left), &chat).await?;
+
+    let (from, received) = tokio::time::timeout(Duration::from_secs(1), bob.recv())
+        .await
+        .expect("relayed message")?;
+    assert_eq!(&from, bridge.local_id(BridgeSide::Right));
+    let bridged = BridgedMessage::from_message(&received).expect("relay envelope");
+    assert_eq!(bridged.origin_network, "chain-a");
+    assert_eq!(bridged.path, vec!["chain-b".to_string()]);
+    assert_eq!(bridged.message.hash, transfer.hash);
+    assert!(bridged.message.verify_hash());
+
+    assert!(tokio::time::timeout(Duration::from_millis(50), bob.recv())
+        .await
+        .is_err());
+    let stats = bridge.stats();
+    assert_eq!(stats.relayed, 1);
+    assert_eq!(stats.not_allowed, 1);
+
+    for task in tasks {
+        task.abort();
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_duplicates_are_relayed_once() -> Result<()> {
+    let chain_a = InMemoryNetwork::new();
+    let chain_b = InMemoryNetwork::new();
+    let bridge = bridge_between(&chain_a, "chain-a", &chain_b, "chain-b");
+
+    let transfer = SharedMessage::custom("transfer", json!({ "amount": 1 }))?;
+    assert_eq!(
+        bridge.relay(BridgeSide::Left, transfer.clone()).await?,
+        RelayOutcome::Relayed { peers: 0 }
+    );
+    assert_eq!(bridge.relay(BridgeSide::Left, transfer).await?, RelayOutcome::Duplicate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cycle_of_bridges_does_not_loop() -> Result<()> {
+    // a -> b -> c -> a: the envelope must stop before re-entering a
+    let chain_a = InMemoryNetwork::new();
+    let chain_b = InMemoryNetwork::new();
+    let chain_c = InMemoryNetwork::new();
+    let ab = bridge_between(&chain_a, "a", &chain_b, "b");
+    let bc = bridge_between(&chain_b, "b", &chain_c, "c");
+    let ca = bridge_between(&chain_c, "c", &chain_a, "a");
+    bc.add_peer("c", ca.local_id(BridgeSide::Left).clone())?;
+    ab.add_peer("b", bc.local_id(BridgeSide::Left).clone())?;
+
+    let transfer = SharedMessage::custom("transfer", json!({ "amount": 9 }))?;
+    assert_eq!(ab.relay(BridgeSide::Left, transfer).await?, RelayOutcome::Relayed { peers: 1 });
+    assert_eq!(bc.relay_next(BridgeSide::Left).await?, RelayOutcome::Relayed { peers: 1 });
+    assert_eq!(ca.relay_next(BridgeSide::Left).await?, RelayOutcome::Loop);
+    assert_eq!(ca.stats().loops, 1);
+    Ok(())
+}
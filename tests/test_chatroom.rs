@@ -1,7 +1,8 @@
-use chaincraft_rust::examples::chatroom::{helpers, ChatroomObject};
+use chaincraft_rust::examples::chatroom::{helpers, ChatroomConfig, ChatroomObject, ReceiptKind};
 use chaincraft_rust::{
     crypto::ecdsa::{ECDSASigner, ECDSAVerifier},
     network::PeerId,
+    shared::{MessageType, SharedMessage},
     shared_object::ApplicationObject,
     storage::MemoryStorage,
     ChaincraftNode,
@@ -197,3 +198,167 @@ async fn test_chatroom_state() {
 
     node.close().await.unwrap();
 }
+
+async fn post_messages(chatroom: &mut ChatroomObject, signer: &ECDSASigner, count: usize) {
+    let create_msg = helpers::create_chatroom_message("paged_room".to_string(), signer).unwrap();
+    chatroom
+        .add_message(SharedMessage::new(MessageType::Custom("chat".to_string()), create_msg))
+        .await
+        .unwrap();
+
+    for i in 0..count {
+        let post_msg = helpers::create_post_message(
+            "paged_room".to_string(),
+            format!("message {}", i),
+            signer,
+        )
+        .unwrap();
+        chatroom
+            .add_message(SharedMessage::new(MessageType::Custom("chat".to_string()), post_msg))
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_chatroom_message_pagination() {
+    let signer = ECDSASigner::new().unwrap();
+    let mut chatroom = ChatroomObject::new();
+    post_messages(&mut chatroom, &signer, 5).await;
+
+    let first_page = chatroom.get_messages("paged_room", 0, 2);
+    assert_eq!(first_page.len(), 2);
+    assert_eq!(first_page[0].text.as_deref(), Some("message 0"));
+    assert_eq!(first_page[1].text.as_deref(), Some("message 1"));
+
+    let last_page = chatroom.get_messages("paged_room", 4, 10);
+    assert_eq!(last_page.len(), 1);
+    assert_eq!(last_page[0].text.as_deref(), Some("message 4"));
+
+    assert!(chatroom.get_messages("paged_room", 10, 10).is_empty());
+    assert!(chatroom.get_messages("missing_room", 0, 10).is_empty());
+}
+
+#[tokio::test]
+async fn test_chatroom_history_pruning_and_archive() {
+    let signer = ECDSASigner::new().unwrap();
+    let archive = Arc::new(MemoryStorage::new());
+    let mut pruned = ChatroomObject::with_config(ChatroomConfig {
+        max_messages_per_room: Some(2),
+    })
+    .with_archive(archive);
+    post_messages(&mut pruned, &signer, 5).await;
+
+    let room = pruned.get_chatroom("paged_room").unwrap();
+    assert_eq!(room.messages.len(), 2);
+    assert_eq!(room.pruned_count, 3);
+    assert_eq!(room.total_messages(), 5);
+
+    // Absolute offsets keep working after pruning
+    let page = pruned.get_messages("paged_room", 3, 10);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page[0].text.as_deref(), Some("message 3"));
+
+    // Pages are by sequence number: one over pruned positions comes back
+    // short instead of shifting onto newer messages
+    assert!(pruned.get_messages("paged_room", 0, 3).is_empty());
+    let straddling = pruned.get_messages("paged_room", 2, 2);
+    assert_eq!(straddling.len(), 1);
+    assert_eq!(straddling[0].text.as_deref(), Some("message 3"));
+    let mut history = pruned
+        .get_archived_messages("paged_room", 2, 2)
+        .await
+        .unwrap();
+    history.extend(straddling);
+    assert_eq!(history[0].text.as_deref(), Some("message 2"));
+
+    let archived = pruned
+        .get_archived_messages("paged_room", 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(archived.len(), 3);
+    assert_eq!(archived[0].text.as_deref(), Some("message 0"));
+    assert_eq!(archived[2].text.as_deref(), Some("message 2"));
+
+    let state = pruned.get_state().await.unwrap();
+    assert_eq!(state["total_messages"], 2);
+    assert_eq!(state["pruned_messages"], 3);
+}
+
+#[tokio::test]
+async fn test_chatroom_digest_stable_under_pruning() {
+    let signer = ECDSASigner::new().unwrap();
+    let create_msg = helpers::create_chatroom_message("paged_room".to_string(), &signer).unwrap();
+    let posts: Vec<_> = (0..4)
+        .map(|i| {
+            helpers::create_post_message("paged_room".to_string(), format!("m{}", i), &signer)
+                .unwrap()
+        })
+        .collect();
+
+    let mut full = ChatroomObject::new();
+    let mut pruned = ChatroomObject::with_config(ChatroomConfig {
+        max_messages_per_room: Some(1),
+    });
+
+    for data in std::iter::once(create_msg).chain(posts) {
+        let message = SharedMessage::new(MessageType::Custom("chat".to_string()), data);
+        full.add_message(message.clone()).await.unwrap();
+        pruned.add_message(message).await.unwrap();
+    }
+
+    assert_eq!(
+        full.get_latest_digest().await.unwrap(),
+        pruned.get_latest_digest().await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_chatroom_receipts_track_unread_posts() {
+    let admin = ECDSASigner::new().unwrap();
+    let bob = ECDSASigner::new().unwrap();
+    let outsider = ECDSASigner::new().unwrap();
+    let bob_key = bob.get_public_key_pem().unwrap();
+    let room = || "receipts_room".to_string();
+    let chat = |data| SharedMessage::new(MessageType::Custom("chat".to_string()), data);
+
+    let mut chatroom = ChatroomObject::new();
+    for data in [
+        helpers::create_chatroom_message(room(), &admin).unwrap(),
+        helpers::create_accept_member_message(room(), bob_key.clone(), &admin).unwrap(),
+    ] {
+        assert!(chatroom.add_message(chat(data)).await.unwrap().is_applied());
+    }
+    let post = chat(helpers::create_post_message(room(), "hello".to_string(), &admin).unwrap());
+    chatroom.add_message(post.clone()).await.unwrap();
+    let unread = chatroom.get_unread(&bob_key);
+    assert_eq!(unread.len(), 1);
+    assert_eq!(unread[0].hash.as_deref(), Some(post.hash.as_str()));
+    // Authors have nothing unread of their own
+    assert!(chatroom
+        .get_unread(&admin.get_public_key_pem().unwrap())
+        .is_empty());
+
+    let receipt = |kind, signer: &ECDSASigner, post: &str| {
+        chat(helpers::create_receipt_message(room(), post.to_string(), kind, signer).unwrap())
+    };
+    let delivered = receipt(ReceiptKind::Delivered, &bob, &post.hash);
+    assert!(chatroom.add_message(delivered).await.unwrap().is_applied());
+    assert_eq!(chatroom.get_unread(&bob_key).len(), 1);
+
+    let read = receipt(ReceiptKind::Read, &bob, &post.hash);
+    assert!(chatroom.add_message(read).await.unwrap().is_applied());
+    assert!(chatroom.get_unread(&bob_key).is_empty());
+    let receipts = chatroom.get_receipts(&room(), &post.hash).unwrap();
+    assert!(receipts.delivered.contains(&bob_key) && receipts.read.contains(&bob_key));
+
+    let again = receipt(ReceiptKind::Read, &bob, &post.hash);
+    let outcome = chatroom.add_message(again).await.unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "duplicate_receipt");
+    let stranger = receipt(ReceiptKind::Read, &outsider, &post.hash);
+    let outcome = chatroom.add_message(stranger).await.unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "not_member");
+    let dangling = receipt(ReceiptKind::Delivered, &bob, "no-such-post");
+    let outcome = chatroom.add_message(dangling).await.unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "unknown_post");
+}
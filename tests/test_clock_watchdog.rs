@@ -0,0 +1,108 @@
+use chaincraft_rust::{
+    clock::Clock,
+    clock_watchdog::{query_ntp, ClockAlert, ClockWatchdog, OffsetSource, WatchdogConfig},
+    events::NodeEvent,
+    network::PeerId,
+    ChaincraftNode, Result,
+};
+use chrono::{TimeZone, Utc};
+use std::time::Duration;
+
+fn ms(millis: i64) -> chrono::Duration {
+    chrono::Duration::milliseconds(millis)
+}
+
+#[test]
+fn test_estimate_alerts_once_per_crossing() {
+    let watchdog = ClockWatchdog::new(WatchdogConfig::new(Duration::from_millis(500)));
+    let (alice, bob, carol) = (PeerId::new(), PeerId::new(), PeerId::new());
+    assert_eq!(watchdog.offset(), None);
+
+    // One peer with a wrong clock is outvoted by the others
+    assert_eq!(
+        watchdog.record(OffsetSource::Peer(alice.clone()), ms(-9000)),
+        Some(ClockAlert::Exceeded { offset: ms(-9000) })
+    );
+    // Still exceeded: the median of two peers is halfway between them
+    assert_eq!(watchdog.record(OffsetSource::Peer(bob.clone()), ms(100)), None);
+    assert_eq!(watchdog.offset(), Some(ms(-4450)));
+    assert_eq!(
+        watchdog.record(OffsetSource::Peer(carol), ms(200)),
+        Some(ClockAlert::Recovered { offset: ms(100) })
+    );
+    assert!(!watchdog.is_alarmed());
+
+    // NTP overrules the peers
+    assert_eq!(
+        watchdog.record(OffsetSource::Ntp, ms(2000)),
+        Some(ClockAlert::Exceeded { offset: ms(2000) })
+    );
+    assert_eq!(watchdog.record(OffsetSource::Ntp, ms(2100)), None);
+    let local = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(watchdog.corrected(local), local + ms(2050));
+
+    watchdog.forget(&alice);
+    watchdog.forget(&bob);
+    assert_eq!(watchdog.offset(), Some(ms(2050)));
+}
+
+#[tokio::test]
+async fn test_node_warns_about_peers_ahead_of_its_clock() -> Result<()> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let alice = ChaincraftNode::builder()
+        .with_clock(Clock::mock(start + chrono::Duration::seconds(5)))
+        .build()?;
+    let bob = ChaincraftNode::builder()
+        .with_clock(Clock::mock(start))
+        .with_clock_watchdog(WatchdogConfig::new(Duration::from_secs(1)))
+        .build()?;
+    let mut events = bob.subscribe();
+
+    bob.receive_message(alice.id(), alice.sync_announcement().await)
+        .await?;
+    assert_eq!(bob.clock_offset(), Some(chrono::Duration::seconds(5)));
+    assert_eq!(bob.metrics().get("clock_offset_alerts"), 1);
+    assert_eq!(bob.metrics().get("clock_offset_abs_ms"), 5000);
+    assert_eq!(bob.get_state().await?["clock_offset_ms"], 5000);
+    let alert = std::iter::from_fn(|| events.try_recv().ok())
+        .find(|event| matches!(event, NodeEvent::ClockOffsetExceeded { .. }));
+    assert_eq!(
+        alert,
+        Some(NodeEvent::ClockOffsetExceeded {
+            offset: chrono::Duration::seconds(5)
+        })
+    );
+
+    // Nodes without a watchdog do not track offsets
+    alice
+        .receive_message(bob.id(), bob.sync_announcement().await)
+        .await?;
+    assert_eq!(alice.clock_offset(), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ntp_query_measures_server_offset() -> Result<()> {
+    // A server whose clock runs ten seconds ahead
+    let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await?;
+    let address = server.local_addr()?;
+    tokio::spawn(async move {
+        let mut request = [0u8; 48];
+        let (_, client) = server.recv_from(&mut request).await.unwrap();
+        let now = Utc::now() + chrono::Duration::seconds(10);
+        let seconds = (now.timestamp() + 2_208_988_800) as u32;
+        let fraction = ((now.timestamp_subsec_nanos() as u64) << 32) / 1_000_000_000;
+        let mut reply = [0u8; 48];
+        // Leap indicator 0, version 4, mode 4 (server)
+        reply[0] = 0x24;
+        for field in [32, 40] {
+            reply[field..field + 4].copy_from_slice(&seconds.to_be_bytes());
+            reply[field + 4..field + 8].copy_from_slice(&(fraction as u32).to_be_bytes());
+        }
+        server.send_to(&reply, client).await.unwrap();
+    });
+
+    let offset = query_ntp(&address.to_string(), &Clock::system(), Duration::from_secs(2)).await?;
+    assert!((offset - chrono::Duration::seconds(10)).abs() < chrono::Duration::milliseconds(200));
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use chaincraft_rust::{
+    crypto::ecdsa::ECDSASigner,
+    examples::chatroom::{self, ChatroomObject},
+    examples::randomness_beacon::{self, RandomnessBeaconObject},
+    examples::tendermint::{self, TendermintObject, ValidatorInfo, ValidatorKey},
+    rng::RngProvider,
+    shared_object::SimpleSharedNumber,
+    testkit::{assert_rejects_invalid, InvalidKind, NegativeCorpus, NegativeReport},
+    Result,
+};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Key named `name`, the same on every call
+fn key(name: &str) -> ECDSASigner {
+    let seed = name
+        .bytes()
+        .fold(0u64, |seed, b| seed.wrapping_mul(31).wrapping_add(b as u64));
+    ECDSASigner::new_with_rng(&RngProvider::seeded(seed)).unwrap()
+}
+
+fn pem(name: &str) -> String {
+    key(name).get_public_key_pem().unwrap()
+}
+
+fn assert_covers_every_kind(report: &NegativeReport) {
+    let counts = report.counts();
+    for kind in [
+        InvalidKind::Malformed,
+        InvalidKind::Unsigned,
+        InvalidKind::Tampered,
+        InvalidKind::Expired,
+        InvalidKind::Replayed,
+    ] {
+        assert!(counts.get(&kind).copied().unwrap_or(0) > 0, "no {} case", kind);
+    }
+    assert!(report.reasons().contains_key("invalid_signature"), "{:?}", report.reasons());
+}
+
+/// A chatroom post signed at `timestamp` seconds since the epoch
+fn post_at(room: &str, text: &str, timestamp: f64, signer: &ECDSASigner) -> Result<Value> {
+    let mut post = json!({
+        "message_type": "POST_MESSAGE",
+        "chatroom_name": room,
+        "public_key_pem": signer.get_public_key_pem()?,
+        "text": text,
+        "timestamp": timestamp,
+    });
+    let signature = signer.sign(serde_json::to_string(&post)?.as_bytes())?;
+    post["signature"] = json!(hex::encode(signature.to_bytes()));
+    Ok(post)
+}
+
+#[tokio::test]
+async fn test_chatroom_rejects_invalid_messages() -> Result<()> {
+    let (admin, bob) = (key("admin"), key("bob"));
+    let room = || "lobby".to_string();
+    let an_hour_ago = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+        - 3600.0;
+
+    let corpus = NegativeCorpus::new()
+        .with_valid(chatroom::helpers::create_chatroom_message(room(), &admin)?)
+        .with_valid(chatroom::helpers::create_accept_member_message(room(), pem("bob"), &admin)?)
+        .with_valid(chatroom::helpers::create_post_message(room(), "hi".to_string(), &admin)?)
+        .with_valid(chatroom::helpers::create_post_message(room(), "hey".to_string(), &bob)?)
+        .with_malformed(json!({ "message_type": "POST_MESSAGE", "chatroom_name": "lobby" }))
+        .with_malformed(json!({ "message_type": "SHOUT", "chatroom_name": "lobby" }))
+        .with_expired(post_at("lobby", "late", an_hour_ago, &bob)?);
+
+    let report = assert_rejects_invalid(ChatroomObject::new(), &corpus).await?;
+    assert_covers_every_kind(&report);
+    let reasons = report.reasons();
+    assert_eq!(reasons["replayed_message"], 3);
+    assert_eq!(reasons["stale_timestamp"], 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_randomness_beacon_rejects_invalid_messages() -> Result<()> {
+    let registration = |name: &str| {
+        randomness_beacon::helpers::create_validator_registration(
+            name.to_string(),
+            pem(name),
+            pem(name),
+            100,
+            &key(name),
+        )
+    };
+    let vrf_proof = |name: &str, input: &str| {
+        randomness_beacon::helpers::create_vrf_proof_message(
+            1,
+            input.to_string(),
+            name.to_string(),
+            &key(name),
+        )
+    };
+    let partial = |name: &str| {
+        randomness_beacon::helpers::create_partial_signature_message(
+            1,
+            name.to_string(),
+            format!("partial-{}", name),
+            &key(name),
+        )
+    };
+
+    let corpus = NegativeCorpus::new()
+        .with_valid(registration("v1")?)
+        .with_valid(registration("v2")?)
+        .with_valid(vrf_proof("v1", "seed")?)
+        .with_valid(partial("v1")?)
+        .with_valid(vrf_proof("v2", "seed")?)
+        .with_valid(partial("v2")?)
+        .with_valid(randomness_beacon::helpers::create_validator_exit(
+            "v2".to_string(),
+            &key("v2"),
+        )?)
+        .with_malformed(json!({ "VrfProof": { "round": "one" } }))
+        .with_malformed(json!({ "Vote": { "round": 1 } }))
+        // Round 1 was finalized by the two contributions above
+        .with_expired(vrf_proof("v1", "other seed")?);
+
+    let beacon = RandomnessBeaconObject::new(60, 2)?;
+    let report = assert_rejects_invalid(beacon, &corpus).await?;
+    assert_covers_every_kind(&report);
+    let reasons = report.reasons();
+    assert_eq!(reasons["already_registered"], 2);
+    assert_eq!(reasons["duplicate"], 3);
+    assert_eq!(reasons["wrong_round"], 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tendermint_rejects_invalid_messages() -> Result<()> {
+    let validators: Vec<ValidatorInfo> = ["v1", "v2", "v3"]
+        .iter()
+        .map(|name| ValidatorInfo {
+            address: name.to_string(),
+            public_key: ValidatorKey::new(key(name).public_key().clone()),
+            voting_power: 1,
+            active: true,
+            possession: None,
+        })
+        .collect();
+    let block = || Some("block-1".to_string());
+
+    let mut corpus = NegativeCorpus::new()
+        .with_valid(tendermint::helpers::create_validator_set_message(validators, 1)?)
+        .with_valid(tendermint::helpers::create_proposal_message(
+            1,
+            0,
+            "block-1".to_string(),
+            "v1".to_string(),
+            &key("v1"),
+        )?);
+    for name in ["v1", "v2", "v3"] {
+        corpus = corpus.with_valid(tendermint::helpers::create_prevote_message(
+            1,
+            0,
+            block(),
+            name.to_string(),
+            &key(name),
+        )?);
+    }
+    // The third precommit commits height 1
+    for name in ["v1", "v2", "v3"] {
+        corpus = corpus.with_valid(tendermint::helpers::create_precommit_message(
+            1,
+            0,
+            block(),
+            name.to_string(),
+            &key(name),
+        )?);
+    }
+    let corpus = corpus
+        .with_malformed(json!({ "Prevote": { "height": 1 } }))
+        .with_malformed(json!({ "Proposal": [] }))
+        .with_expired(tendermint::helpers::create_prevote_message(
+            1,
+            1,
+            None,
+            "v2".to_string(),
+            &key("v2"),
+        )?);
+
+    let report = assert_rejects_invalid(TendermintObject::new()?, &corpus).await?;
+    assert_covers_every_kind(&report);
+    let reasons = report.reasons();
+    assert_eq!(reasons["validator_set_unchanged"], 1);
+    assert_eq!(reasons["duplicate"], 6);
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic(expected = "answered malformed input -1 with Some(Applied)")]
+async fn test_objects_applying_malformed_data_fail_the_check() {
+    // The number object adds any number it is sent, negative ones included
+    let corpus = NegativeCorpus::new().with_valid(json!(5));
+    let _ = assert_rejects_invalid(SimpleSharedNumber::new(), &corpus).await;
+}
@@ -0,0 +1,48 @@
+use chaincraft_rust::{
+    codec::consensus::{
+        encode_bytes, encode_i64, encode_str, encode_u32, encode_u64, encode_usize, ConsensusHasher,
+    },
+    shared::{MessageType, SharedMessage, SharedObjectId},
+};
+use serde_json::json;
+
+#[test]
+fn test_integers_are_fixed_width_big_endian() {
+    assert_eq!(encode_u32(0x0102_0304), [1, 2, 3, 4]);
+    assert_eq!(encode_u64(1), [0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(encode_i64(-1), [0xff; 8]);
+    // usize is widened to 8 bytes, so 32-bit and 64-bit builds agree
+    assert_eq!(encode_usize(258), [0, 0, 0, 0, 0, 0, 1, 2]);
+    assert_eq!(encode_usize(u32::MAX as usize), encode_u64(u32::MAX as u64));
+}
+
+#[test]
+fn test_strings_are_length_prefixed() {
+    assert_eq!(encode_str("ab"), vec![0, 0, 0, 2, b'a', b'b']);
+    assert_eq!(encode_bytes(&[]), vec![0, 0, 0, 0]);
+
+    let split = |a: &str, b: &str| ConsensusHasher::new().str(a).str(b).finish_hex();
+    assert_ne!(split("ab", "c"), split("a", "bc"));
+}
+
+#[test]
+fn test_hasher_vectors() {
+    // Pinned vectors: these must be identical on every platform
+    assert_eq!(
+        ConsensusHasher::new().finish_hex(),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        ConsensusHasher::new().str("round").u64(7).finish_hex(),
+        "14f869b0c415af22a9d5323b843a607478fcb6b507a927d690185b6e3bcd3f40"
+    );
+
+    let mut message =
+        SharedMessage::new(MessageType::Custom("vector".to_string()), json!({"n": 1}));
+    message.id = SharedObjectId::from_uuid(uuid::Uuid::from_u128(1));
+    message.timestamp = chrono::DateTime::from_timestamp(1_700_000_000, 5).unwrap();
+    assert_eq!(
+        message.calculate_hash(),
+        "310a966c6d096029ea07a3d1f7ed50d4d2b69e9ce0db8769496a9b9bc3a8aabb"
+    );
+}
@@ -0,0 +1,89 @@
+use chaincraft_rust::{
+    clock::Clock,
+    convergence::{propagations, ConvergenceReport, FirstSeenLog},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, PeerId, Result,
+};
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use std::time::Duration;
+
+fn gossip(n: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("gossip".to_string()), json!(n))
+}
+
+#[tokio::test]
+async fn test_nodes_record_first_sighting() -> Result<()> {
+    let clock = Clock::mock(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+    let node = ChaincraftNode::builder()
+        .with_clock(clock.clone())
+        .build()?;
+    let message = gossip(1);
+    let seen_at = clock.now();
+    node.receive_message(&PeerId::new(), message.clone())
+        .await?;
+
+    // A later copy does not move the first sighting
+    clock.advance(Duration::from_secs(5));
+    let _ = node.receive_message(&PeerId::new(), message.clone()).await;
+    assert_eq!(node.metrics().first_seen(&message.hash), Some(seen_at));
+    assert!(!node.metrics().record_first_seen(&message.hash, clock.now()));
+
+    let log = node.first_seen_log();
+    assert_eq!(log.node, node.id().to_string());
+    assert_eq!(log.first_seen.len(), 1);
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("first_seen.json");
+    node.export_first_seen(&path).await?;
+    let read: FirstSeenLog = serde_json::from_slice(&std::fs::read(&path)?)?;
+    assert_eq!(read, log);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coverage_percentiles_across_simulated_network() -> Result<()> {
+    let clock = Clock::mock(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+    let mut nodes = Vec::new();
+    for _ in 0..4 {
+        nodes.push(
+            ChaincraftNode::builder()
+                .with_clock(clock.clone())
+                .build()?,
+        );
+    }
+    let hop = Duration::from_millis(100);
+    let origin = PeerId::new();
+
+    // Message 0 reaches one node per hop; message 1 stops after two nodes
+    let (first, second) = (gossip(0), gossip(1));
+    nodes[0].receive_message(&origin, first.clone()).await?;
+    nodes[1].receive_message(&origin, second.clone()).await?;
+    for (i, node) in nodes.iter().enumerate().skip(1) {
+        clock.advance(hop);
+        node.receive_message(&origin, first.clone()).await?;
+        if i == 2 {
+            node.receive_message(&origin, second.clone()).await?;
+        }
+    }
+
+    let logs: Vec<FirstSeenLog> = nodes.iter().map(|n| n.first_seen_log()).collect();
+    let spread = propagations(&logs);
+    assert_eq!(spread.len(), 2);
+    // Both start at the same time, so only the hash orders them
+    let of = |message: &SharedMessage| spread.iter().find(|p| p.hash == message.hash).unwrap();
+    assert_eq!(of(&first).time_to_coverage(0.5, 4), Some(hop));
+    assert_eq!(of(&first).time_to_coverage(1.0, 4), Some(hop * 3));
+    assert_eq!(of(&second).time_to_coverage(0.5, 4), Some(hop * 2));
+    assert_eq!(of(&second).time_to_coverage(0.95, 4), None);
+
+    let report = ConvergenceReport::from_logs(&logs);
+    assert_eq!(report.nodes, 4);
+    assert_eq!(report.messages, 2);
+    assert_eq!(report.fully_covered, 1);
+    assert_eq!(report.p50, Some(hop));
+    assert_eq!(report.p95, Some(hop * 3));
+    assert_eq!(report.max, Some(hop * 3));
+    assert!(report.to_string().contains("p95 coverage:  300.0 ms"));
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use chaincraft_rust::{
+    crypto::{
+        pool::{CpuPool, CpuPoolConfig},
+        pow::{PoWChallenge, PowPolicy, ProofOfWork},
+        utils::generate_keypair,
+        vdf::VerifiableDelayFunction,
+        verify_batch, KeyType, KeylessCryptoPrimitive,
+    },
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_pool_bounds_concurrency_and_reports_metrics() -> Result<()> {
+    let pool = CpuPool::new(CpuPoolConfig::new(2));
+    let active = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let jobs: Vec<_> = (0..6)
+        .map(|_| {
+            let (pool, active, peak) = (pool.clone(), active.clone(), peak.clone());
+            tokio::spawn(async move {
+                pool.run(move || {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+                .await
+            })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    assert!(pool.metrics().queued > 0);
+    for job in jobs {
+        job.await.unwrap()?;
+    }
+
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    let metrics = pool.metrics();
+    assert_eq!((metrics.queued, metrics.running, metrics.completed), (0, 0, 6));
+    assert!(metrics.longest >= Duration::from_millis(20));
+    assert!(metrics.mean_duration().unwrap() >= Duration::from_millis(20));
+    assert!(metrics.waited >= Duration::from_millis(20));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crypto_work_runs_on_the_given_pool() -> Result<()> {
+    let pool = CpuPool::new(CpuPoolConfig::new(2));
+
+    let pow = ProofOfWork::with_difficulty(2).with_pool(pool.clone());
+    let challenge = PoWChallenge::new("pooled");
+    let proof = pow.create_proof(challenge.clone()).await?;
+    assert!(pow.verify_proof(challenge, proof).await?);
+
+    let vdf = VerifiableDelayFunction::new().with_pool(pool.clone());
+    let output = vdf.evaluate(b"seed".to_vec(), 1000).await?;
+    assert_eq!(output, VerifiableDelayFunction::evaluate_sync(b"seed", 1000));
+    assert!(vdf.verify(b"seed".to_vec(), 1000, &output).await?);
+    assert!(!vdf.verify(b"seed".to_vec(), 999, &output).await?);
+
+    let mut batch = Vec::new();
+    for i in 0..5u8 {
+        let (private_key, public_key) = generate_keypair(KeyType::Ed25519)?;
+        let signature = private_key.sign(&[i])?;
+        // Every other entry is checked against the wrong message
+        let message = if i % 2 == 0 { vec![i] } else { vec![i, i] };
+        batch.push((public_key, message, signature));
+    }
+    assert_eq!(verify_batch(&pool, batch).await?, vec![true, false, true, false, true]);
+    assert!(pool.metrics().completed >= 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_mines_required_pow_on_its_pool() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_cpu_threads(1)
+        .with_pow_policy(PowPolicy::new(2)?)
+        .build()?;
+    assert_eq!(node.cpu_pool.threads(), 1);
+
+    node.create_shared_message_with_data(json!({ "mined": true }))
+        .await?;
+    assert_eq!(node.cpu_pool.metrics().completed, 1);
+    let state = node.get_state().await?;
+    assert_eq!(state["cpu_pool"]["completed"], 1);
+    assert_eq!(state["cpu_pool"]["threads"], 1);
+    Ok(())
+}
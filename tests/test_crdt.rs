@@ -0,0 +1,120 @@
+use chaincraft_rust::{
+    crdt::{
+        Crdt, CrdtObject, GCounter, GCounterObject, LwwRegister, OrSet, OrSetObject, PnCounter,
+    },
+    shared::{MessageType, SharedMessage},
+    shared_object::ApplicationObject,
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+
+fn state_message<C: Crdt>(state: &C) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("crdt".to_string()), CrdtObject::<C>::message(state))
+}
+
+/// Merge `deltas` into a fresh state in the given order
+fn merged<C: Crdt>(deltas: &[&C]) -> C {
+    let mut state = C::default();
+    for delta in deltas {
+        state.merge(delta);
+    }
+    state
+}
+
+#[test]
+fn test_merges_commute_and_are_idempotent() {
+    let mut a = PnCounter::default();
+    let mut b = PnCounter::default();
+    let d1 = a.increment("a", 5);
+    let d2 = b.decrement("b", 2);
+    let d3 = a.increment("a", 1);
+    assert_eq!(merged(&[&d1, &d2, &d3]), merged(&[&d3, &d2, &d1, &d3]));
+    assert_eq!(merged(&[&d1, &d2, &d3]).total(), 4);
+
+    let mut counter = GCounter::default();
+    counter.increment("a", 2);
+    let copy = counter.clone();
+    counter.merge(&copy);
+    assert_eq!(counter.total(), 2);
+
+    // Later timestamps win, then replica names
+    let now = chrono::Utc::now();
+    let mut register = LwwRegister::default();
+    let early = register.set(json!("first"), now, "z");
+    let late = register.set(json!("second"), now + chrono::Duration::seconds(1), "a");
+    let tie = LwwRegister::default().set(json!("tie"), now + chrono::Duration::seconds(1), "b");
+    assert_eq!(merged(&[&late, &tie, &early]), merged(&[&early, &tie, &late]));
+    assert_eq!(merged(&[&early, &late, &tie]).get(), Some(&json!("tie")));
+}
+
+#[test]
+fn test_or_set_add_wins_over_concurrent_remove() {
+    let mut alice = OrSet::default();
+    let add = alice.add("apple", "alice");
+    let mut bob = OrSet::default();
+    bob.merge(&add);
+
+    // Bob removes the apple he saw while Alice adds it again
+    let remove = bob.remove("apple");
+    let readd = alice.add("apple", "alice");
+    alice.merge(&remove);
+    bob.merge(&readd);
+    assert_eq!(alice, bob);
+    assert!(alice.contains("apple"));
+
+    let remove_all = alice.remove("apple");
+    bob.merge(&remove_all);
+    assert!(!bob.contains("apple"));
+    assert!(bob.elements().is_empty());
+}
+
+#[tokio::test]
+async fn test_objects_converge_whatever_the_order() -> Result<()> {
+    let mut set = OrSet::default();
+    let messages: Vec<SharedMessage> =
+        [set.add("x", "r1"), set.add("y", "r1"), set.remove("x"), set.add("z", "r2")]
+            .iter()
+            .map(state_message)
+            .collect();
+
+    let mut forward = OrSetObject::new();
+    let mut backward = OrSetObject::new();
+    for message in &messages {
+        assert!(forward.add_message(message.clone()).await?.is_applied());
+    }
+    for message in messages.iter().rev() {
+        backward.add_message(message.clone()).await?;
+    }
+    assert_eq!(forward.state(), backward.state());
+    assert_eq!(forward.get_state().await?["value"], json!(["y", "z"]));
+
+    // Replaying a state adds nothing
+    let outcome = forward.add_message(messages[0].clone()).await?;
+    assert_eq!(outcome.reason().unwrap().code, "no_change");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_routes_states_to_the_matching_object() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(GCounterObject::new()))
+        .await?;
+
+    let mut counter = GCounter::default();
+    for replica in ["alice", "bob", "alice"] {
+        let delta = counter.increment(replica, 2);
+        node.create_shared_message_with_data(GCounterObject::message(&delta))
+            .await?;
+    }
+    // A PN-counter state is not a G-counter state
+    let mut other = PnCounter::default();
+    let foreign = CrdtObject::<PnCounter>::message(&other.increment("carol", 9));
+    node.create_shared_message_with_data(foreign).await?;
+
+    let total = node
+        .with_typed::<GCounterObject, _>(&id, |object| object.state().total())
+        .await;
+    assert_eq!(total, Some(6));
+    Ok(())
+}
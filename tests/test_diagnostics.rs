@@ -0,0 +1,124 @@
+use chaincraft_rust::{
+    diagnostics::{DiagnosticBundle, DiagnosticsConfig, DumpReason},
+    network::{PeerId, PeerInfo},
+    shared_object::SimpleSharedNumber,
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+fn bundles(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut bundles: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    bundles.sort();
+    Ok(bundles)
+}
+
+#[tokio::test]
+async fn test_stopping_writes_a_bundle_of_the_node() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut node = ChaincraftNode::builder()
+        .with_network_id("lab-7")
+        .with_diagnostics(DiagnosticsConfig::new(dir.path()))
+        .build()?;
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let peer = PeerId::new();
+    node.add_peer(PeerInfo::new(peer.clone(), "127.0.0.1:7001".parse().unwrap()))
+        .await?;
+    for i in 0..3 {
+        node.create_shared_message_with_data(json!(i)).await?;
+    }
+    node.stop().await?;
+
+    let written = bundles(dir.path())?;
+    assert_eq!(written.len(), 1);
+    let name = written[0]
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    assert!(name.starts_with(&node.id().to_string()[..8]));
+    for file in ["summary.json", "config.txt", "peers.json", "objects.json", "events.json"] {
+        assert!(written[0].join(file).exists(), "{}", file);
+    }
+
+    let bundle = DiagnosticBundle::read(&written[0])?;
+    assert_eq!(bundle.reason, DumpReason::Shutdown);
+    assert_eq!(bundle.node_id, node.id().to_string());
+    assert!(bundle.unavailable.is_empty());
+    assert!(bundle.config.contains("lab-7"));
+    assert_eq!(bundle.peers.len(), 1);
+    assert_eq!(bundle.peers[0].id, peer);
+    assert_eq!(bundle.sync.local_sequence, 3);
+    assert_eq!(bundle.objects.len(), 1);
+    assert_eq!(bundle.objects[0].info.as_ref().unwrap().applied, 3);
+    assert_eq!(bundle.events.len(), 3);
+    assert!(bundle
+        .events
+        .iter()
+        .all(|e| e.starts_with("MessageProcessed")));
+
+    let printed = bundle.to_string();
+    assert!(printed.contains("on shutdown"));
+    assert!(printed.contains("Peers (1):"));
+    assert!(printed.contains("3 applied, 0 rejected"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bundles_on_request_keep_the_last_events() -> Result<()> {
+    let mut plain = ChaincraftNode::default();
+    assert!(plain.write_diagnostics().is_err());
+    plain.stop().await?;
+
+    let dir = tempfile::tempdir()?;
+    let mut node = ChaincraftNode::builder()
+        .with_diagnostics(DiagnosticsConfig::new(dir.path()).with_recent_events(2))
+        .build()?;
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let mut hashes = Vec::new();
+    for i in 0..5 {
+        hashes.push(node.create_shared_message_with_data(json!(i)).await?);
+    }
+
+    let bundle = DiagnosticBundle::read(node.write_diagnostics()?)?;
+    assert_eq!(bundle.reason, DumpReason::Requested);
+    assert_eq!(bundle.events.len(), 2);
+    assert!(bundle.events[0].contains(&hashes[3]));
+    assert!(bundle.events[1].contains(&hashes[4]));
+
+    // A section whose lock is held is skipped rather than waited for
+    let diagnostics = node.diagnostics.clone().unwrap();
+    let peers = node.peers.write().await;
+    let bundle = diagnostics.collect(DumpReason::Requested);
+    drop(peers);
+    assert_eq!(bundle.unavailable, vec!["peers"]);
+    assert_eq!(bundle.objects.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_panic_hook_writes_a_bundle() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let node = ChaincraftNode::builder()
+        .with_diagnostics(DiagnosticsConfig::new(dir.path()))
+        .build()?;
+    node.diagnostics.as_ref().unwrap().install_panic_hook();
+
+    let result = std::panic::catch_unwind(|| panic!("object state corrupted"));
+    let _ = std::panic::take_hook();
+    assert!(result.is_err());
+
+    let written = bundles(dir.path())?;
+    assert_eq!(written.len(), 1);
+    let bundle = DiagnosticBundle::read(&written[0])?;
+    let DumpReason::Panic { message, location } = &bundle.reason else {
+        panic!("expected a panic bundle, got {}", bundle.reason);
+    };
+    assert_eq!(message, "object state corrupted");
+    assert!(location.as_ref().unwrap().contains("test_diagnostics.rs"));
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use chaincraft_rust::{
+    consensus::ProofOfWorkConsensus,
+    crypto::pow::{BlockTiming, DifficultyAdjuster, RetargetAlgorithm},
+};
+use chrono::{Duration, Utc};
+
+fn timing(height: u64, secs: i64, difficulty: u32) -> BlockTiming {
+    BlockTiming {
+        height,
+        timestamp: chrono::DateTime::UNIX_EPOCH + Duration::seconds(secs),
+        difficulty,
+    }
+}
+
+#[test]
+fn test_window_retargets_at_interval_boundaries() {
+    let adjuster = DifficultyAdjuster::window(10, 5);
+    assert_eq!(adjuster.reference_height(3), None);
+    // The first window would start at genesis
+    assert_eq!(adjuster.reference_height(4), None);
+    assert_eq!(adjuster.reference_height(9), Some(4));
+    assert_eq!(adjuster.reference_height(10), None);
+
+    // Five blocks should take 50 seconds; a step needs a 4x miss
+    let reference = timing(4, 1_000, 3);
+    assert_eq!(adjuster.next_difficulty(&timing(9, 1_012, 3), &reference), 4);
+    assert_eq!(adjuster.next_difficulty(&timing(9, 1_013, 3), &reference), 3);
+    assert_eq!(adjuster.next_difficulty(&timing(9, 1_199, 3), &reference), 3);
+    assert_eq!(adjuster.next_difficulty(&timing(9, 1_200, 3), &reference), 2);
+    // Never more than one digit, never below the minimum
+    assert_eq!(adjuster.next_difficulty(&timing(9, 100_000, 3), &reference), 2);
+    let bounded = adjuster.with_bounds(3, 3);
+    assert_eq!(bounded.next_difficulty(&timing(9, 100_000, 3), &reference), 3);
+    assert_eq!(bounded.next_difficulty(&timing(9, 1_000, 3), &reference), 3);
+}
+
+#[test]
+fn test_asert_follows_the_schedule_from_the_anchor() {
+    let adjuster = DifficultyAdjuster::asert(10, 1, 60);
+    assert_eq!(adjuster.reference_height(0), None);
+    assert_eq!(adjuster.reference_height(1), Some(1));
+    assert_eq!(adjuster.reference_height(500), Some(1));
+
+    let anchor = timing(1, 0, 4);
+    // On schedule: block 101 exactly 1000 seconds after block 1
+    assert_eq!(adjuster.next_difficulty(&timing(101, 1_000, 4), &anchor), 4);
+    // A digit is four half-lives; rounding starts at two
+    assert_eq!(adjuster.next_difficulty(&timing(101, 1_119, 4), &anchor), 4);
+    assert_eq!(adjuster.next_difficulty(&timing(101, 1_120, 4), &anchor), 3);
+    assert_eq!(adjuster.next_difficulty(&timing(101, 1_480, 4), &anchor), 2);
+    assert_eq!(adjuster.next_difficulty(&timing(101, 760, 4), &anchor), 5);
+    // Unlike the window, the anchor's difficulty is the baseline
+    assert_eq!(adjuster.next_difficulty(&timing(101, 1_000, 9), &anchor), 4);
+
+    let json = serde_json::to_value(adjuster).unwrap();
+    assert_eq!(json["algorithm"]["algorithm"], "asert");
+    let parsed: DifficultyAdjuster = serde_json::from_value(json).unwrap();
+    assert_eq!(
+        parsed.algorithm,
+        RetargetAlgorithm::Asert {
+            anchor_height: 1,
+            half_life_secs: 60
+        }
+    );
+}
+
+#[test]
+fn test_consensus_retargets_with_an_adjuster() {
+    let now = Utc::now();
+    let parent = BlockTiming {
+        height: 9,
+        timestamp: now,
+        difficulty: 3,
+    };
+    let reference = BlockTiming {
+        height: 4,
+        timestamp: now - Duration::seconds(1),
+        difficulty: 3,
+    };
+
+    let mut fixed = ProofOfWorkConsensus::new(3);
+    assert_eq!(fixed.retarget(&parent, Some(&reference)), 3);
+
+    let mut consensus =
+        ProofOfWorkConsensus::new(3).with_adjuster(DifficultyAdjuster::window(10, 5));
+    assert_eq!(consensus.retarget(&parent, Some(&reference)), 4);
+    assert_eq!(consensus.difficulty(), 4);
+    assert_eq!(consensus.retarget(&parent, None), 3);
+}
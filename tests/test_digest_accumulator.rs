@@ -0,0 +1,108 @@
+use chaincraft_rust::{
+    crypto::ecdsa::ECDSASigner,
+    examples::chatroom::{helpers, ChatroomConfig, ChatroomObject},
+    shared::{DigestAccumulator, MessageType, SharedMessage},
+    ApplicationObject, Result, SimpleSharedNumber,
+};
+use serde_json::json;
+
+fn number(value: i64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("number".to_string()), json!(value))
+}
+
+#[test]
+fn test_accumulator_tracks_messages_since_digest() {
+    let messages: Vec<_> = (0..5).map(number).collect();
+    let mut accumulator = DigestAccumulator::new();
+    let genesis = accumulator.digest().to_string();
+    assert_eq!(genesis, DigestAccumulator::genesis());
+
+    let mut digests = Vec::new();
+    for message in &messages {
+        digests.push(accumulator.apply(message).to_string());
+    }
+    assert_eq!(accumulator.applied(), 5);
+    assert_eq!(accumulator.messages_since(&genesis).unwrap().len(), 5);
+    let since: Vec<String> = accumulator
+        .messages_since(&digests[2])
+        .unwrap()
+        .into_iter()
+        .map(|m| m.hash)
+        .collect();
+    assert_eq!(since, vec![messages[3].hash.clone(), messages[4].hash.clone()]);
+    assert!(accumulator.messages_since(&digests[4]).unwrap().is_empty());
+    assert!(accumulator.messages_since("unknown").is_none());
+    assert_eq!(accumulator.gossip(Some("unknown")).len(), 5);
+
+    // Same messages in the same order give the same digest
+    let mut replica = DigestAccumulator::with_retention(2);
+    for message in &messages {
+        replica.apply(message);
+    }
+    assert_eq!(replica.digest(), accumulator.digest());
+    assert!(!replica.contains(&digests[1]));
+    assert!(replica.contains(&digests[2]));
+    assert_eq!(replica.messages_since(&digests[2]).unwrap().len(), 2);
+    assert!(replica.messages_since(&genesis).is_none());
+}
+
+#[tokio::test]
+async fn test_objects_report_digests_and_messages_since() -> Result<()> {
+    let mut object = SimpleSharedNumber::new();
+    let start = object.get_latest_digest().await?;
+    object.add_message(number(1)).await?;
+    let middle = object.get_latest_digest().await?;
+    // Duplicates do not move the digest
+    object.add_message(number(1)).await?;
+    assert_eq!(object.get_latest_digest().await?, middle);
+    object.add_message(number(2)).await?;
+
+    assert!(object.has_digest(&start).await?);
+    assert!(object.has_digest(&middle).await?);
+    let since = object.get_messages_since_digest(&middle).await?;
+    assert_eq!(since.len(), 1);
+    assert_eq!(since[0].data, json!(2));
+    assert!(object.get_messages_since_digest("unknown").await.is_err());
+
+    object.reset().await?;
+    assert_eq!(object.get_latest_digest().await?, start);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chatroom_replicas_converge_and_catch_up() -> Result<()> {
+    let signer = ECDSASigner::new()?;
+    let mut messages = vec![helpers::create_chatroom_message("room".to_string(), &signer)?];
+    for i in 0..3 {
+        messages.push(helpers::create_post_message(
+            "room".to_string(),
+            format!("m{}", i),
+            &signer,
+        )?);
+    }
+    let messages: Vec<_> = messages
+        .into_iter()
+        .map(|data| SharedMessage::new(MessageType::Custom("chat".to_string()), data))
+        .collect();
+
+    let mut leader = ChatroomObject::new();
+    let mut follower = ChatroomObject::with_config(ChatroomConfig {
+        max_messages_per_room: Some(10),
+    });
+    for message in &messages[..2] {
+        leader.add_message(message.clone()).await?;
+        follower.add_message(message.clone()).await?;
+    }
+    for message in &messages[2..] {
+        leader.add_message(message.clone()).await?;
+    }
+
+    // The follower fetches exactly what it is missing
+    let behind = follower.get_latest_digest().await?;
+    assert!(leader.has_digest(&behind).await?);
+    for message in leader.get_messages_since_digest(&behind).await? {
+        follower.add_message(message).await?;
+    }
+    assert_eq!(follower.get_latest_digest().await?, leader.get_latest_digest().await?);
+    Ok(())
+}
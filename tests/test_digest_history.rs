@@ -0,0 +1,94 @@
+use chaincraft_rust::{
+    shared::{DigestAccumulator, DigestHistory},
+    ApplicationObject, ChaincraftNode, Result, SimpleSharedNumber,
+};
+use serde_json::json;
+
+fn history_of(range: std::ops::Range<u32>) -> DigestHistory {
+    let mut history = DigestHistory::with_capacity(64);
+    for i in range {
+        history.record(format!("d{}", i));
+    }
+    history
+}
+
+#[test]
+fn test_ring_buffer_evicts_oldest() {
+    let mut history = DigestHistory::with_capacity(3);
+    for digest in ["a", "b", "b", "c", "d"] {
+        history.record(digest);
+    }
+    assert_eq!(history.len(), 3);
+    assert!(!history.contains("a"));
+    assert!(history.contains("b") && history.contains("d"));
+    assert_eq!(history.sequence_of("d"), Some(3));
+    let since: Vec<&str> = history
+        .since("b")
+        .unwrap()
+        .iter()
+        .map(|d| d.hash.as_str())
+        .collect();
+    assert_eq!(since, vec!["c", "d"]);
+    assert!(history.since("a").is_none());
+}
+
+#[test]
+fn test_locator_finds_most_recent_common_digest() {
+    // Both sides share d0..d39, then diverge
+    let mut ours = history_of(0..40);
+    let mut theirs = history_of(0..40);
+    for i in 0..20 {
+        ours.record(format!("ours{}", i));
+        theirs.record(format!("theirs{}", i));
+    }
+
+    let locator = theirs.locator();
+    assert!(locator.len() < 20, "locator should be compact");
+    assert_eq!(locator.first().unwrap(), "theirs19");
+    assert_eq!(locator.last().unwrap(), "d0");
+
+    let common = ours.find_common(&locator).expect("shared prefix");
+    assert!(common.hash.starts_with('d'));
+    // Found within the doubling step of the true fork point
+    assert!(common.message_count >= 32 && common.message_count <= 39);
+
+    let unrelated = history_of(100..110);
+    assert!(unrelated.find_common(&locator).is_none());
+}
+
+#[tokio::test]
+async fn test_registry_records_digests_of_applied_messages() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+
+    let mut hashes = Vec::new();
+    for value in [1, 2, 2, 3] {
+        hashes.push(node.create_shared_message_with_data(json!(value)).await?);
+    }
+
+    // Replay the applied (non-duplicate) messages to predict each digest
+    let mut expected = DigestAccumulator::new();
+    let mut digests = vec![expected.digest().to_string()];
+    for hash in [&hashes[0], &hashes[1], &hashes[3]] {
+        let message = node.get_message(hash).await?.unwrap();
+        digests.push(expected.apply(&message).to_string());
+    }
+
+    // Initial state plus one digest per applied message, newest first
+    let locator = node.digest_locator(&id).await.unwrap();
+    let newest_first: Vec<String> = digests.iter().rev().cloned().collect();
+    assert_eq!(locator, newest_first);
+
+    let number = node.get_typed::<SimpleSharedNumber>(&id).await.unwrap();
+    assert!(number.has_digest(&digests[2]).await?);
+    assert!(!number.has_digest("unknown").await?);
+    drop(number);
+
+    let peer_locator = vec!["diverged".to_string(), digests[2].clone(), digests[0].clone()];
+    let common = node.common_digest(&id, &peer_locator).await.unwrap();
+    assert_eq!(common.hash, digests[2]);
+    assert_eq!(common.message_count, 2);
+    Ok(())
+}
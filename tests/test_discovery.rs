@@ -1,4 +1,11 @@
-use chaincraft_rust::{network::PeerId, storage::MemoryStorage, ChaincraftNode};
+use chaincraft_rust::{
+    discovery::{DiscoveryConfig, DiscoveryManager},
+    network::udp::UdpGossipConfig,
+    network::{PeerAdmission, PeerId, PeerInfo},
+    storage::MemoryStorage,
+    ChaincraftNode,
+};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
@@ -288,3 +295,124 @@ async fn test_network_scaling() {
         node.close().await.unwrap();
     }
 }
+
+async fn discovering_node(bootstrap: Option<&ChaincraftNode>) -> ChaincraftNode {
+    let bootstrap_peers: Vec<SocketAddr> = bootstrap
+        .map(|node| format!("{}:{}", node.host(), node.port()).parse().unwrap())
+        .into_iter()
+        .collect();
+    let mut node = ChaincraftNode::builder()
+        .port(0)
+        .with_udp_gossip(UdpGossipConfig::default())
+        .with_discovery(DiscoveryConfig {
+            announce_interval: 1,
+            ..DiscoveryConfig::default()
+        })
+        .with_bootstrap_peers(bootstrap_peers)
+        .build()
+        .unwrap();
+    node.start().await.unwrap();
+    node
+}
+
+async fn knows(node: &ChaincraftNode, peer: &ChaincraftNode) -> bool {
+    node.get_peers().await.iter().any(|p| &p.id == peer.id())
+}
+
+#[tokio::test]
+async fn test_started_nodes_discover_each_other_through_bootstrap_peers() {
+    let mut seed = discovering_node(None).await;
+    let mut alice = discovering_node(Some(&seed)).await;
+    let mut bob = discovering_node(Some(&seed)).await;
+
+    // Alice and Bob only know the seed, yet end up knowing each other
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while !(knows(&alice, &bob).await && knows(&bob, &alice).await && knows(&seed, &bob).await) {
+        assert!(std::time::Instant::now() < deadline, "peers were not discovered");
+        sleep(Duration::from_millis(50)).await;
+    }
+    // The bootstrap placeholder was re-keyed rather than kept alongside the seed
+    assert!(knows(&alice, &seed).await);
+    assert_eq!(alice.get_peers().await.len(), 2);
+    assert!(alice.metrics().get("peers_discovered") + bob.metrics().get("peers_discovered") >= 1);
+
+    for node in [&mut seed, &mut alice, &mut bob] {
+        node.close().await.unwrap();
+    }
+}
+
+#[test]
+fn test_discovery_needs_udp_gossip() {
+    let built = ChaincraftNode::builder()
+        .with_discovery(DiscoveryConfig::default())
+        .build();
+    assert!(built.is_err());
+}
+
+fn peer(port: u16) -> PeerInfo {
+    PeerInfo::new(PeerId::new(), format!("127.0.0.1:{}", port).parse().unwrap())
+}
+
+#[tokio::test]
+async fn test_full_node_rejects_or_evicts_new_peers() {
+    let rejecting = ChaincraftNode::builder().max_peers(2).build().unwrap();
+    rejecting.add_peer(peer(9001)).await.unwrap();
+    rejecting.add_peer(peer(9002)).await.unwrap();
+    assert!(rejecting.add_peer(peer(9003)).await.is_err());
+    assert_eq!(rejecting.get_peers().await.len(), 2);
+    assert_eq!(rejecting.metrics().get("peers_refused"), 1);
+
+    let evicting = ChaincraftNode::builder()
+        .max_peers(2)
+        .with_peer_admission(PeerAdmission::EvictLeastRecentlySeen)
+        .build()
+        .unwrap();
+    let mut stale = peer(9001);
+    stale.last_seen -= chrono::Duration::minutes(5);
+    let fresh = peer(9002);
+    evicting.add_peer(stale.clone()).await.unwrap();
+    evicting.add_peer(fresh.clone()).await.unwrap();
+    let newcomer = peer(9003);
+    evicting.add_peer(newcomer.clone()).await.unwrap();
+
+    let ids: Vec<PeerId> = evicting.get_peers().await.into_iter().map(|p| p.id).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(!ids.contains(&stale.id));
+    assert!(ids.contains(&fresh.id) && ids.contains(&newcomer.id));
+    assert_eq!(evicting.metrics().get("peers_evicted"), 1);
+}
+
+#[tokio::test]
+async fn test_discovery_admission_policy() {
+    let addr = "127.0.0.1:9000".parse().unwrap();
+    let rejecting = DiscoveryManager::new(
+        PeerId::new(),
+        addr,
+        DiscoveryConfig {
+            max_peers: 1,
+            admission: PeerAdmission::RejectNew,
+            ..DiscoveryConfig::default()
+        },
+    );
+    let first = peer(9001);
+    rejecting.add_peer(first.clone()).await.unwrap();
+    assert!(rejecting.add_peer(peer(9002)).await.is_err());
+    // Known peers are refreshed, not refused
+    rejecting.add_peer(first.clone()).await.unwrap();
+    assert_eq!(rejecting.get_peers().await.len(), 1);
+
+    let evicting = DiscoveryManager::new(
+        PeerId::new(),
+        addr,
+        DiscoveryConfig {
+            max_peers: 1,
+            ..DiscoveryConfig::default()
+        },
+    );
+    evicting.add_peer(first).await.unwrap();
+    let second = peer(9002);
+    evicting.add_peer(second.clone()).await.unwrap();
+    let known = evicting.get_peers().await;
+    assert_eq!(known.len(), 1);
+    assert_eq!(known[0].node_id, second.id);
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "grpc")]
+
+use chaincraft_rust::{
+    clock::Clock,
+    network::doctor::{diagnose, DoctorConfig, Severity},
+    network::grpc::GrpcTransport,
+    network::PROTOCOL_VERSION,
+    ChaincraftNode, Result,
+};
+use chrono::Utc;
+use std::time::Duration;
+
+fn any_port() -> std::net::SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_healthy_peer_passes_every_check() -> Result<()> {
+    let node = ChaincraftNode::builder().with_network_id("lab").build()?;
+    let transport = GrpcTransport::bind_node(&node, any_port()).await?;
+    let peer = transport.local_addr().to_string();
+
+    let config = DoctorConfig::default()
+        .with_network_id("lab")
+        .with_samples(3);
+    let report = diagnose(&peer, &config).await;
+    assert!(report.is_healthy(), "{}", report);
+    assert_eq!(report.severity(), Severity::Ok);
+    for check in ["resolve", "connect", "handshake", "protocol", "network", "latency", "clock"] {
+        assert_eq!(report.finding(check).unwrap().severity, Severity::Ok, "{}", check);
+    }
+
+    let remote = report.remote.as_ref().unwrap();
+    assert_eq!(remote.node_id, node.id().to_string());
+    assert_eq!(remote.protocol_version, PROTOCOL_VERSION);
+    assert!(report.rtt.unwrap() < Duration::from_secs(1));
+    assert!(report.clock_offset.unwrap().num_seconds().abs() < 1);
+    assert!(report.to_string().ends_with("0 error(s), 0 warning(s)"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrong_network_and_skewed_clock_are_reported() -> Result<()> {
+    let clock = Clock::mock(Utc::now() + chrono::Duration::minutes(5));
+    let node = ChaincraftNode::builder()
+        .with_network_id("classroom-b")
+        .with_clock(clock)
+        .build()?;
+    let transport = GrpcTransport::bind_node(&node, any_port()).await?;
+
+    let config = DoctorConfig::default().with_network_id("classroom-a");
+    let report = diagnose(&transport.local_addr().to_string(), &config).await;
+    assert!(!report.is_healthy());
+
+    let network = report.finding("network").unwrap();
+    assert_eq!(network.severity, Severity::Error);
+    assert!(network.message.contains("classroom-b"));
+    assert!(network.advice.is_some());
+
+    let clock = report.finding("clock").unwrap();
+    assert_eq!(clock.severity, Severity::Error);
+    assert!(clock.message.contains("ahead of"));
+    let offset = report.clock_offset.unwrap();
+    assert!((offset - chrono::Duration::minutes(5)).num_seconds().abs() <= 1);
+    assert_eq!(report.finding("protocol").unwrap().severity, Severity::Ok);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unreachable_peer_stops_after_connect() -> Result<()> {
+    // Bind and release a port so nothing listens on it
+    let port = std::net::TcpListener::bind(any_port())?
+        .local_addr()?
+        .port();
+    let config = DoctorConfig::default().with_timeout(Duration::from_secs(2));
+    let report = diagnose(&format!("127.0.0.1:{}", port), &config).await;
+
+    assert_eq!(report.finding("resolve").unwrap().severity, Severity::Ok);
+    let connect = report.finding("connect").unwrap();
+    assert_eq!(connect.severity, Severity::Error);
+    assert!(connect.advice.as_ref().unwrap().contains(&port.to_string()));
+    assert!(report.finding("handshake").is_none());
+
+    let report = diagnose("not-a-peer", &config).await;
+    assert_eq!(report.finding("resolve").unwrap().severity, Severity::Error);
+    assert_eq!(report.findings.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use chaincraft_rust::{
+    clock::Clock,
+    crypto::{utils, KeyType},
+    fan_in::{Confirmation, FanInConfig},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, PeerId, Result,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+fn price(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("price".to_string()), data)
+}
+
+fn node(confirmation: Confirmation) -> Result<ChaincraftNode> {
+    ChaincraftNode::builder()
+        .with_fan_in(FanInConfig::new().with_rule("price", confirmation))
+        .build()
+}
+
+#[tokio::test]
+async fn test_facts_apply_once_relayed_by_enough_peers() -> Result<()> {
+    let node = node(Confirmation::Peers(2))?;
+    let (p1, p2, p3) = (PeerId::new(), PeerId::new(), PeerId::new());
+    let rumor = price(json!({ "btc": 100 }));
+
+    // The same peer relaying twice is still one witness
+    for _ in 0..2 {
+        assert_eq!(node.receive_message(&p1, rumor.clone()).await?, rumor.hash);
+    }
+    assert!(node.get_message(&rumor.hash).await?.is_none());
+    let pending = node.pending_facts();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].witnesses, vec![p1.to_string()]);
+    assert_eq!(pending[0].message_hash, rumor.hash);
+
+    node.receive_message(&p2, rumor.clone()).await?;
+    assert!(node.get_message(&rumor.hash).await?.is_some());
+    assert!(node.pending_facts().is_empty());
+    assert_eq!(node.metrics().get("fan_in_confirmed"), 1);
+
+    node.receive_message(&p3, rumor.clone()).await?;
+    assert_eq!(node.metrics().get("fan_in_late_copies"), 1);
+
+    // Types without a rule apply on receipt
+    let other = SharedMessage::new(MessageType::Custom("chat".to_string()), json!("hi"));
+    node.receive_message(&p1, other.clone()).await?;
+    assert!(node.get_message(&other.hash).await?.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_facts_apply_once_signed_by_enough_keys() -> Result<()> {
+    let node = node(Confirmation::Signers(2))?;
+    let peer = PeerId::new();
+    let (alice, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    let (bob, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    let signed = |key| -> Result<SharedMessage> {
+        let mut message = price(json!({ "btc": 100 }));
+        message.sign(key)?;
+        Ok(message)
+    };
+
+    let first = signed(&alice)?;
+    node.receive_message(&peer, first.clone()).await?;
+    // Another copy by the same key and an unsigned one add no witness
+    node.receive_message(&peer, signed(&alice)?).await?;
+    node.receive_message(&peer, price(json!({ "btc": 100 })))
+        .await?;
+    assert_eq!(node.pending_facts()[0].witnesses.len(), 1);
+    // A different fact is tracked apart
+    node.receive_message(&peer, price(json!({ "btc": 90 })))
+        .await?;
+    assert_eq!(node.pending_facts().len(), 2);
+
+    let second = signed(&bob)?;
+    node.receive_message(&peer, second.clone()).await?;
+    // The first copy received is the one applied
+    assert!(node.get_message(&first.hash).await?.is_some());
+    assert!(node.get_message(&second.hash).await?.is_none());
+    assert_eq!(node.pending_facts().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unconfirmed_facts_expire() -> Result<()> {
+    let clock = Clock::mock(Utc::now());
+    let node = ChaincraftNode::builder()
+        .with_clock(clock.clone())
+        .with_fan_in(
+            FanInConfig::new()
+                .with_rule("price", Confirmation::Peers(2))
+                .with_pending_ttl(Duration::from_secs(30)),
+        )
+        .build()?;
+    let rumor = price(json!({ "btc": 100 }));
+    node.receive_message(&PeerId::new(), rumor.clone()).await?;
+    clock.advance(Duration::from_secs(30));
+
+    // The first witness was forgotten, so this one starts over
+    node.receive_message(&PeerId::new(), rumor.clone()).await?;
+    assert!(node.get_message(&rumor.hash).await?.is_none());
+    assert_eq!(node.pending_facts()[0].witnesses.len(), 1);
+    Ok(())
+}
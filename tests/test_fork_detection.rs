@@ -0,0 +1,71 @@
+use chaincraft_rust::{
+    events::NodeEvent, shared_object::SimpleSharedNumber, ChaincraftNode, Result, SharedMessage,
+};
+use serde_json::json;
+
+async fn node() -> Result<ChaincraftNode> {
+    let node = ChaincraftNode::default();
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    Ok(node)
+}
+
+/// Create a message on `from` and deliver it to `to`
+async fn share(from: &mut ChaincraftNode, to: &ChaincraftNode, n: i64) -> Result<()> {
+    let hash = from.create_shared_message_with_data(json!(n)).await?;
+    let message = SharedMessage::from_json(&from.get_object(&hash).await?)?;
+    to.receive_message(from.id(), message).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diverging_digests_are_reported() -> Result<()> {
+    let mut alice = node().await?;
+    let mut bob = node().await?;
+    share(&mut alice, &bob, 1).await?;
+    alice.create_shared_message_with_data(json!(2)).await?;
+    bob.create_shared_message_with_data(json!(3)).await?;
+    let mut events = bob.subscribe();
+
+    bob.receive_message(alice.id(), alice.sync_announcement().await)
+        .await?;
+    let forks = bob.detected_forks();
+    assert_eq!(forks.len(), 1);
+    let fork = &forks[0];
+    assert_eq!(&fork.peer_id, alice.id());
+    assert_eq!(fork.object_type, "SimpleSharedNumber");
+    assert_eq!(fork.message_count, 2);
+    assert_ne!(fork.local_digest, fork.remote_digest);
+    // Both applied the first message, then went separate ways
+    assert_eq!(fork.diverging_range(), Some(2..=2));
+    // Equal log lengths count as in sync; only the digests tell them apart
+    assert!(matches!(events.try_recv().unwrap(), NodeEvent::SyncStatusChanged { .. }));
+    assert_eq!(events.try_recv().unwrap(), NodeEvent::ForkDetected(fork.clone()));
+
+    // The same fork announced again is not reported twice
+    bob.receive_message(alice.id(), alice.sync_announcement().await)
+        .await?;
+    assert_eq!(bob.detected_forks().len(), 1);
+    assert_eq!(bob.metrics().get("forks_detected"), 1);
+    assert_eq!(bob.get_state().await?["forks"][0]["message_count"], json!(2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peers_on_the_same_history_do_not_fork() -> Result<()> {
+    let mut alice = node().await?;
+    let bob = node().await?;
+    share(&mut alice, &bob, 1).await?;
+    share(&mut alice, &bob, 2).await?;
+    // Alice is ahead; the digest she reached is beyond Bob's history
+    alice.create_shared_message_with_data(json!(3)).await?;
+
+    bob.receive_message(alice.id(), alice.sync_announcement().await)
+        .await?;
+    alice
+        .receive_message(bob.id(), bob.sync_announcement().await)
+        .await?;
+    assert!(bob.detected_forks().is_empty());
+    assert!(alice.detected_forks().is_empty());
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use chaincraft_rust::{
+    metrics::NodeMetrics,
+    network::framing::{FramingConfig, FramingTransport, FRAME_MESSAGE_TYPE},
+    network::transport::{InMemoryNetwork, Transport},
+    network::PeerId,
+    shared::{MessageType, SharedMessage},
+    Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn message(n: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("gossip".to_string()), json!(n))
+}
+
+fn config(interval: Duration, max_frame_bytes: usize) -> FramingConfig {
+    FramingConfig {
+        interval,
+        max_frame_bytes,
+    }
+}
+
+#[tokio::test]
+async fn test_queued_messages_travel_in_one_frame() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let metrics = Arc::new(NodeMetrics::new());
+    let alice = FramingTransport::with_config(
+        network.connect(PeerId::new()),
+        config(Duration::from_secs(60), 64 * 1024),
+    )
+    .with_metrics(metrics.clone());
+    let bob = FramingTransport::new(network.connect(PeerId::new()));
+
+    let sent: Vec<_> = (0..3).map(message).collect();
+    for message in &sent {
+        alice.send(bob.local_id(), message).await?;
+    }
+    assert_eq!(alice.queued_count(), 3);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), bob.recv())
+            .await
+            .is_err(),
+        "nothing goes out before the flush"
+    );
+
+    assert_eq!(alice.flush().await, 1);
+    assert_eq!(alice.queued_count(), 0);
+    for expected in &sent {
+        let (from, received) = bob.recv().await?;
+        assert_eq!(&from, alice.local_id());
+        assert_eq!(received.hash, expected.hash);
+    }
+    let stats = network.link_stats(alice.local_id(), bob.local_id());
+    assert_eq!(stats.sent, 1);
+    assert_eq!(metrics.get("gossip_frames"), 1);
+    assert_eq!(metrics.get("gossip_framed_messages"), 3);
+    assert_eq!(alice.messages_per_frame(), 3.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_full_frames_are_sent_early() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let size = serde_json::to_vec(&message(0))?.len();
+    // Room for two messages per frame
+    let alice = FramingTransport::with_config(
+        network.connect(PeerId::new()),
+        config(Duration::from_secs(60), size * 2),
+    );
+    let bob = network.connect(PeerId::new());
+
+    let sent: Vec<_> = (0..3).map(message).collect();
+    for message in &sent {
+        alice.send(bob.local_id(), message).await?;
+    }
+    let (_, frame) = bob.recv().await?;
+    assert_eq!(frame.message_type, MessageType::Custom(FRAME_MESSAGE_TYPE.to_string()));
+    assert_eq!(frame.data["messages"].as_array().unwrap().len(), 2);
+    assert_eq!(alice.queued_count(), 1);
+
+    // A lone message goes out as itself, readable without the wrapper
+    alice.flush().await;
+    let (_, plain) = bob.recv().await?;
+    assert_eq!(plain.hash, sent[2].hash);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_flushes_run_on_the_interval() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let alice = Arc::new(FramingTransport::with_config(
+        network.connect(PeerId::new()),
+        config(Duration::from_millis(10), 64 * 1024),
+    ));
+    let bob = FramingTransport::new(network.connect(PeerId::new()));
+    let flushes = alice.clone().spawn_flushes();
+
+    let sent = message(7);
+    alice.send(bob.local_id(), &sent).await?;
+    let (_, received) = tokio::time::timeout(Duration::from_secs(1), bob.recv())
+        .await
+        .expect("flushed on the interval")?;
+    assert_eq!(received.hash, sent.hash);
+    flushes.abort();
+    Ok(())
+}
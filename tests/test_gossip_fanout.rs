@@ -0,0 +1,102 @@
+use chaincraft_rust::{
+    discovery::{DiscoveryConfig, DiscoveryManager},
+    network::fanout::{FanoutCandidate, FanoutConfig, GossipFanout},
+    network::{PeerId, PeerInfo},
+    rng::RngProvider,
+    ChaincraftNode,
+};
+
+fn candidates(scores: &[(i64, bool)]) -> Vec<FanoutCandidate> {
+    scores
+        .iter()
+        .map(|&(score, banned)| FanoutCandidate::new(PeerId::new(), score, banned))
+        .collect()
+}
+
+#[test]
+fn test_fanout_prefers_trusted_peers_and_never_banned() {
+    let fanout = GossipFanout::new(FanoutConfig {
+        fanout: 3,
+        low_score_probability: 1.0,
+        ..FanoutConfig::default()
+    });
+    let peers = candidates(&[(-20, false), (5, false), (-500, true), (10, false), (0, false)]);
+
+    let decision = fanout.select("h", &peers, &RngProvider::seeded(1));
+    let ids =
+        |idx: &[usize]| -> Vec<PeerId> { idx.iter().map(|&i| peers[i].peer_id.clone()).collect() };
+
+    assert_eq!(decision.preferred, ids(&[3, 1, 4]));
+    assert!(decision.sampled.is_empty(), "fanout already full of trusted peers");
+    assert_eq!(decision.skipped, ids(&[0]));
+    assert_eq!(decision.banned, ids(&[2]));
+    assert!(!decision.includes(&peers[2].peer_id));
+    assert!(fanout.trace().is_empty(), "tracing is off by default");
+}
+
+#[test]
+fn test_low_score_peers_are_sampled_probabilistically() {
+    let peers = candidates(&[(-10, false); 40]);
+    let sampled_with = |p: f64, seed: u64| {
+        let fanout = GossipFanout::new(FanoutConfig {
+            fanout: 40,
+            low_score_probability: p,
+            ..FanoutConfig::default()
+        });
+        fanout
+            .select("h", &peers, &RngProvider::seeded(seed))
+            .sampled
+            .len()
+    };
+
+    assert_eq!(sampled_with(0.0, 7), 0);
+    assert_eq!(sampled_with(1.0, 7), 40);
+    let some = sampled_with(0.5, 7);
+    assert!(some > 5 && some < 35, "sampled {} of 40", some);
+    // Seeded runs pick the same peers
+    assert_eq!(sampled_with(0.5, 7), some);
+}
+
+#[tokio::test]
+async fn test_node_traces_fanout_using_discovery_scores() {
+    let mut node = ChaincraftNode::builder()
+        .with_seed(3)
+        .with_fanout(FanoutConfig {
+            fanout: 2,
+            low_score_probability: 0.0,
+            trace: true,
+            ..FanoutConfig::default()
+        })
+        .build()
+        .unwrap();
+    let discovery = DiscoveryManager::new(
+        node.id.clone(),
+        "127.0.0.1:9000".parse().unwrap(),
+        DiscoveryConfig::default(),
+    );
+
+    let good = PeerId::new();
+    let suspect = PeerId::new();
+    let banned = PeerId::new();
+    for (i, peer) in [&good, &suspect, &banned].into_iter().enumerate() {
+        let addr = format!("127.0.0.1:{}", 9100 + i).parse().unwrap();
+        node.add_peer(PeerInfo::new(peer.clone(), addr))
+            .await
+            .unwrap();
+    }
+    discovery.report_abuse(&suspect, 10).await;
+    discovery.report_abuse(&banned, 1000).await;
+    node.discovery = Some(discovery);
+
+    let decision = node.select_gossip_targets("abc").await;
+    assert_eq!(decision.targets(), vec![good.clone()]);
+    assert_eq!(decision.skipped, vec![suspect]);
+    assert_eq!(decision.banned, vec![banned]);
+
+    let trace = node.fanout.trace();
+    assert_eq!(trace.len(), 1);
+    assert_eq!(trace[0], decision);
+    assert_eq!(trace[0].message_hash, "abc");
+    node.fanout.clear_trace();
+    assert!(node.fanout.trace().is_empty());
+}
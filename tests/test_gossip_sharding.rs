@@ -0,0 +1,147 @@
+use chaincraft_rust::{
+    network::sharding::{GossipTopic, ShardSubscription, ShardingConfig},
+    network::{PeerId, PeerInfo},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::collections::{HashSet, VecDeque};
+
+fn chat(room: &str, n: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("chat".to_string()), json!({ "room": room, "n": n }))
+}
+
+#[test]
+fn test_topics_follow_the_shard_key() {
+    let config = ShardingConfig::new("chat", 4, "/room");
+    let topic = config.topic(&chat("lobby", 1));
+    assert_eq!(topic, config.topic(&chat("lobby", 2)));
+    assert!(matches!(topic, GossipTopic::Shard(shard) if shard < 4));
+    assert_eq!(topic.name("chat"), format!("chat/shard/{}", config.shard_of("lobby")));
+
+    let keyless = SharedMessage::new(MessageType::Custom("chat".to_string()), json!(1));
+    assert_eq!(config.topic(&keyless), GossipTopic::Unsharded);
+
+    // Accounts sharing a prefix share a shard
+    let accounts = ShardingConfig::new("bank", 16, "/account").with_prefix(2);
+    let account = |id: &str| {
+        SharedMessage::new(MessageType::Custom("tx".to_string()), json!({ "account": id }))
+    };
+    assert_eq!(accounts.topic(&account("ab12")), accounts.topic(&account("ab99")));
+
+    let subscription = ShardSubscription::only([1, 3]);
+    assert!(subscription.includes(GossipTopic::Shard(3)));
+    assert!(!subscription.includes(GossipTopic::Shard(2)));
+    assert!(subscription.includes(GossipTopic::Unsharded));
+    assert!(ShardSubscription::All.includes(GossipTopic::Shard(2)));
+}
+
+/// Flood a message from `origin` through relay targets until nobody is left to tell
+async fn gossip(
+    nodes: &[ChaincraftNode],
+    held: &mut [HashSet<String>],
+    origin: usize,
+    message: SharedMessage,
+) -> Result<()> {
+    let index_of = |id: &PeerId| nodes.iter().position(|node| node.id() == id).unwrap();
+    nodes[origin].submit_message(message.clone()).await?;
+    held[origin].insert(message.hash.clone());
+    let mut queue = VecDeque::from([origin]);
+    while let Some(from) = queue.pop_front() {
+        for target in nodes[from].select_relay_targets(&message).await?.targets() {
+            let to = index_of(&target);
+            if held[to].insert(message.hash.clone()) {
+                nodes[to]
+                    .receive_message(nodes[from].id(), message.clone())
+                    .await?;
+                queue.push_back(to);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shard_digests_converge_with_less_traffic() -> Result<()> {
+    let config = ShardingConfig::new("chat", 4, "/room");
+    let subscriptions = [[0, 1], [1, 2], [2, 3], [3, 0]];
+    let nodes: Vec<ChaincraftNode> = subscriptions
+        .iter()
+        .map(|shards| {
+            ChaincraftNode::builder()
+                .with_sharding(config.clone(), ShardSubscription::only(*shards))
+                .build()
+        })
+        .collect::<Result<_>>()?;
+
+    // Everyone peers with everyone and learns their shards from announcements
+    for (i, node) in nodes.iter().enumerate() {
+        for (j, other) in nodes.iter().enumerate() {
+            if i != j {
+                let address = format!("127.0.0.1:{}", 7000 + j).parse().unwrap();
+                node.add_peer(PeerInfo::new(other.id().clone(), address))
+                    .await?;
+                node.receive_message(other.id(), other.sync_announcement().await)
+                    .await?;
+            }
+        }
+    }
+
+    let mut held = vec![HashSet::new(); nodes.len()];
+    for n in 0..40u64 {
+        let message = chat(&format!("room-{}", n % 7), n);
+        let GossipTopic::Shard(shard) = config.topic(&message) else {
+            unreachable!()
+        };
+        // Posted from any node following the room's shard
+        let origin = subscriptions
+            .iter()
+            .position(|shards| shards.contains(&shard))
+            .unwrap();
+        gossip(&nodes, &mut held, origin, message).await?;
+    }
+
+    for shard in 0..4 {
+        let mut digests = Vec::new();
+        for (node, shards) in nodes.iter().zip(&subscriptions) {
+            let digest = node.shard_digest(shard).await?;
+            if shards.contains(&shard) {
+                digests.push(digest);
+            } else {
+                // Nothing of a shard a node does not follow reaches it
+                assert_eq!(digest, nodes[0].shard_digest(99).await?);
+            }
+        }
+        assert_eq!(digests.len(), 2);
+        assert_eq!(digests[0], digests[1], "shard {} diverged", shard);
+    }
+
+    for node in &nodes {
+        let stats = node.sharding_stats();
+        assert_eq!(stats.dropped, 0);
+        // Of three peers only the other subscriber of each shard is sent to
+        let reduction = stats.reduction().unwrap();
+        assert!((reduction - 2.0 / 3.0).abs() < 1e-9, "reduction {}", reduction);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unfollowed_shards_are_dropped_on_arrival() -> Result<()> {
+    let config = ShardingConfig::new("chat", 2, "/room");
+    let node = ChaincraftNode::builder()
+        .with_sharding(config.clone(), ShardSubscription::only([0]))
+        .build()?;
+    let room = (0..)
+        .map(|n| format!("room-{}", n))
+        .find(|room| config.shard_of(room) == 1)
+        .unwrap();
+
+    let hash = node.receive_message(&PeerId::new(), chat(&room, 1)).await?;
+    assert!(node.get_message(&hash).await?.is_none());
+    assert_eq!(node.sharding_stats().dropped, 1);
+
+    let announcement = node.sync_announcement().await;
+    assert_eq!(announcement.data["shards"], json!([0]));
+    Ok(())
+}
@@ -0,0 +1,169 @@
+#![cfg(feature = "grpc")]
+
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    network::grpc::{proto, GrpcClient, GrpcTransport},
+    network::transport::Transport,
+    network::{PeerId, PeerInfo},
+    query::MessageFilter,
+    shared::{MessageType, SharedMessage},
+    shared_object::SimpleSharedNumber,
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+
+fn any_port() -> std::net::SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+#[test]
+fn test_proto_message_roundtrip_keeps_hash_and_signature() -> Result<()> {
+    let (private_key, public_key) = utils::generate_keypair(KeyType::Ed25519)?;
+    let mut message = SharedMessage::new(
+        MessageType::Custom("chat".to_string()),
+        json!({"text": "hello", "n": 3}),
+    );
+    message.sign(&private_key)?;
+
+    let decoded = SharedMessage::try_from(proto::SharedMessage::from(&message))?;
+    assert_eq!(decoded.hash, message.hash);
+    assert!(decoded.verify_hash());
+    assert!(decoded.verify_signature(&public_key)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grpc_transport_delivers_messages() -> Result<()> {
+    let alice = GrpcTransport::bind(PeerId::new(), any_port()).await?;
+    let bob = GrpcTransport::bind(PeerId::new(), any_port()).await?;
+    alice.add_peer(bob.local_id().clone(), bob.local_addr());
+
+    let message = SharedMessage::new(MessageType::Heartbeat, json!({"seq": 1}));
+    alice.send(bob.local_id(), &message).await?;
+
+    let (from, received) = bob.recv().await?;
+    assert_eq!(&from, alice.local_id());
+    assert_eq!(received.hash, message.hash);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grpc_rejects_tampered_messages() -> Result<()> {
+    let alice = GrpcTransport::bind(PeerId::new(), any_port()).await?;
+    let bob = GrpcTransport::bind(PeerId::new(), any_port()).await?;
+    alice.add_peer(bob.local_id().clone(), bob.local_addr());
+
+    let mut message = SharedMessage::new(MessageType::Heartbeat, json!(1));
+    message.data = json!(2);
+    assert!(alice.send(bob.local_id(), &message).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grpc_discovery_and_sync_services() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    for i in 0..5 {
+        node.create_shared_message_with_data(json!(i)).await?;
+    }
+    let known = PeerInfo::new(PeerId::new(), "127.0.0.1:7001".parse().unwrap());
+    node.add_peer(known.clone()).await?;
+
+    let server = GrpcTransport::bind_node(&node, any_port()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+
+    let me = PeerId::new();
+    let peers = client
+        .announce(&me, "127.0.0.1:7002".parse().unwrap())
+        .await?;
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].id, known.id);
+    assert_eq!(node.get_peers().await.len(), 2);
+
+    let (first, next) = client.get_messages(0, 3).await?;
+    assert_eq!(first.len(), 3);
+    assert_eq!(next, 3);
+    let (rest, next) = client.get_messages(next, 10).await?;
+    assert_eq!(rest.len(), 2);
+    assert_eq!(next, 5);
+
+    let log = node.message_log.read().await.hashes();
+    let synced: Vec<String> = first.iter().chain(&rest).map(|m| m.hash.clone()).collect();
+    assert_eq!(synced, log);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grpc_query_messages() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let (private_key, public_key) = utils::generate_keypair(KeyType::Ed25519)?;
+    for i in 0..4 {
+        let mut message = SharedMessage::new(MessageType::Custom("chat".to_string()), json!(i));
+        if i % 2 == 0 {
+            message.sign(&private_key)?;
+        }
+        node.submit_message(message).await?;
+    }
+    node.submit_message(SharedMessage::new(MessageType::Heartbeat, json!(null)))
+        .await?;
+
+    let server = GrpcTransport::bind_node(&node, any_port()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+
+    let signed = MessageFilter::new()
+        .of_type(MessageType::Custom("chat".to_string()))
+        .from_sender(public_key.to_hex());
+    let messages = client.query_messages(&signed).await?;
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].data, json!(0));
+    assert!(messages[1].verify_signature(&public_key)?);
+
+    let all = client
+        .query_messages(&MessageFilter::new().limit(3))
+        .await?;
+    assert_eq!(all.len(), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grpc_message_stats() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let (private_key, public_key) = utils::generate_keypair(KeyType::Ed25519)?;
+    for i in 0..3 {
+        let mut message = SharedMessage::new(MessageType::Custom("chat".to_string()), json!(i));
+        message.sign(&private_key)?;
+        node.submit_message(message).await?;
+    }
+    node.submit_message(SharedMessage::new(MessageType::Heartbeat, json!(null)))
+        .await?;
+
+    let server = GrpcTransport::bind_node(&node, any_port()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+    let report = client.message_stats(0).await?;
+    assert_eq!(report.total, 4);
+    assert_eq!(report.unsigned, 1);
+    assert_eq!(report.by_type[0].message_type, "chat");
+    assert_eq!(report.top_senders.len(), 1);
+    assert_eq!(report.top_senders[0].sender, public_key.to_hex());
+    assert_eq!(report.top_senders[0].share, 0.75);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grpc_lists_object_states() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    node.create_shared_message_with_data(json!(7)).await?;
+
+    let server = GrpcTransport::bind_node(&node, any_port()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+    let objects = client.objects("").await?;
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].id, id);
+    assert_eq!(objects[0].type_name, "SimpleSharedNumber");
+    assert_eq!(objects[0].state["number"], json!(7));
+    assert_eq!(objects[0], node.object_directory().await.snapshot(&id).await.unwrap());
+    assert!(client.objects("TendermintBFT").await?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use chaincraft_rust::{
+    crypto::hd::{
+        generate_mnemonic, mnemonic_to_seed, DerivationPath, ExtendedPrivateKey, HdCurve, HdWallet,
+    },
+    examples::ledger::{wallet_accounts, LedgerObject, Transfer},
+    shared::{MessageType, SharedMessage},
+    shared_object::ApplicationObject,
+    Result,
+};
+use std::collections::HashSet;
+
+const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+fn key_at(master: &ExtendedPrivateKey, path: &str) -> Result<String> {
+    Ok(master.derive_path(&path.parse()?)?.private_key()?.to_hex())
+}
+
+#[test]
+fn test_bip32_and_slip10_vectors() -> Result<()> {
+    let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+
+    let master = ExtendedPrivateKey::master(&seed, HdCurve::Secp256k1)?;
+    assert_eq!(
+        key_at(&master, "m")?,
+        "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+    );
+    assert_eq!(
+        hex::encode(master.chain_code()),
+        "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508"
+    );
+    assert_eq!(
+        key_at(&master, "m/0'")?,
+        "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+    );
+    assert_eq!(
+        key_at(&master, "m/0'/1")?,
+        "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+    );
+
+    let master = ExtendedPrivateKey::master(&seed, HdCurve::Ed25519)?;
+    assert_eq!(
+        key_at(&master, "m")?,
+        "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+    );
+    assert_eq!(
+        key_at(&master, "m/0'")?,
+        "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+    );
+    // SLIP-10 has no normal Ed25519 children
+    assert!(key_at(&master, "m/0'/1").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_mnemonics_and_bip44_paths() -> Result<()> {
+    let seed = mnemonic_to_seed(
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        "TREZOR",
+    )?;
+    assert_eq!(
+        hex::encode(seed),
+        "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+    );
+    // A bad checksum is refused
+    assert!(mnemonic_to_seed(&"abandon ".repeat(12), "").is_err());
+
+    // The first development account of Ethereum tooling
+    let wallet = HdWallet::from_mnemonic(TEST_MNEMONIC, "", HdCurve::Secp256k1)?;
+    assert_eq!(wallet.path(0, 0).to_string(), "m/44'/60'/0'/0/0");
+    assert_eq!(
+        wallet.key(0)?.to_hex(),
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+    );
+
+    let path: DerivationPath = "m/44h/60'/0'/0/7".parse()?;
+    assert_eq!(path, DerivationPath::bip44(60, 0, 0, 7));
+    assert_eq!(serde_json::to_value(&path)?, "m/44'/60'/0'/0/7");
+    for invalid in ["", "44'/0", "m/x", "m/2147483648", "m//1"] {
+        assert!(invalid.parse::<DerivationPath>().is_err(), "{}", invalid);
+    }
+
+    let ed25519 = HdWallet::from_mnemonic(TEST_MNEMONIC, "", HdCurve::Ed25519)?;
+    assert_eq!(ed25519.path(0, 3).to_string(), "m/44'/60'/0'/0'/3'");
+    assert_eq!(ed25519.key(3)?.to_hex(), ed25519.key(3)?.to_hex());
+
+    let phrase = generate_mnemonic(12)?;
+    assert_eq!(phrase.split(' ').count(), 12);
+    mnemonic_to_seed(&phrase, "")?;
+    assert!(generate_mnemonic(13).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ledger_accounts_from_one_mnemonic() -> Result<()> {
+    let wallet = HdWallet::from_mnemonic(TEST_MNEMONIC, "", HdCurve::Secp256k1)?;
+    let accounts = wallet_accounts(&wallet, 5)?;
+    let addresses: HashSet<&String> = accounts.iter().map(|(_, address)| address).collect();
+    assert_eq!(addresses.len(), 5);
+    // The same mnemonic gives the same accounts
+    let again = wallet_accounts(&wallet, 5)?;
+    assert_eq!(again[4].1, accounts[4].1);
+
+    let mut ledger = LedgerObject::with_genesis([(accounts[0].1.clone(), 100)]);
+    for (to, amount) in [(1, 10), (2, 20)] {
+        let nonce = ledger.account(&accounts[0].1).nonce;
+        let transfer = Transfer::signed(&accounts[0].0, &accounts[to].1, amount, nonce)?;
+        let message =
+            SharedMessage::new(MessageType::Custom("transfer".to_string()), transfer.to_message());
+        assert!(ledger.add_message(message).await?.is_applied());
+    }
+    assert_eq!(ledger.balance(&accounts[0].1), 70);
+    assert_eq!(ledger.balance(&accounts[2].1), 20);
+    Ok(())
+}
@@ -1,4 +1,13 @@
-use chaincraft_rust::{network::PeerId, storage::MemoryStorage, ChaincraftNode};
+use chaincraft_rust::{
+    consensus::poa::{PoaBlock, BLOCK_HEIGHT_INDEX, POA_BLOCK_MESSAGE_TYPE},
+    crypto::ecdsa::ECDSASigner,
+    examples::chatroom::{helpers, ChatroomObject, CHATROOM_POSTS_INDEX},
+    index::{IndexKey, IndexSpec},
+    network::PeerId,
+    shared::{MessageType, SharedMessage},
+    storage::MemoryStorage,
+    ChaincraftNode, Result,
+};
 use serde_json::json;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
@@ -152,3 +161,110 @@ async fn test_timestamp_indexing() {
 
     node.close().await.unwrap();
 }
+
+fn transfer(from: &str, to: &str, amount: u64) -> serde_json::Value {
+    json!({ "kind": "transfer", "from": from, "to": to, "amount": amount })
+}
+
+fn amounts(messages: &[SharedMessage]) -> Vec<u64> {
+    messages
+        .iter()
+        .map(|m| m.data["amount"].as_u64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_declared_index_covers_old_and_new_messages() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    node.create_shared_message_with_data(transfer("alice", "bob", 1))
+        .await?;
+    node.create_shared_message_with_data(transfer("bob", "carol", 2))
+        .await?;
+
+    // One key per account the transfer touches
+    let accounts = IndexSpec::new("account", |message| {
+        ["/from", "/to"]
+            .iter()
+            .filter_map(|p| message.data.pointer(p)?.as_str())
+            .map(IndexKey::from)
+            .collect()
+    });
+    assert!(node.declare_index(accounts.clone()).await?);
+    assert!(!node.declare_index(accounts).await?);
+    node.create_shared_message_with_data(transfer("carol", "alice", 3))
+        .await?;
+
+    let bob = IndexKey::from("bob");
+    assert_eq!(amounts(&node.scan_index("account", bob.clone()..=bob).await?), vec![1, 2]);
+    let alice = IndexKey::from("alice");
+    assert_eq!(amounts(&node.scan_index("account", alice.clone()..=alice).await?), vec![1, 3]);
+    // Range over keys: accounts from "b" up to but excluding "c"
+    let b_accounts = node
+        .scan_index("account", IndexKey::from("b")..IndexKey::from("c"))
+        .await?;
+    assert_eq!(amounts(&b_accounts), vec![1, 2]);
+
+    let by_amount = IndexSpec::field("amount", "/amount");
+    node.declare_index(by_amount).await?;
+    let large = node.scan_index("amount", IndexKey::Number(2)..).await?;
+    assert_eq!(amounts(&large), vec![2, 3]);
+
+    assert!(node.scan_index("missing", ..).await.is_err());
+    node.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_chatroom_history_from_index() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    node.add_shared_object(Box::new(ChatroomObject::new()))
+        .await?;
+    let index = node.index.read().await.index_names().join(",");
+    assert!(index.contains(CHATROOM_POSTS_INDEX));
+
+    let admin = ECDSASigner::new()?;
+    for room in ["general", "random"] {
+        let create = helpers::create_chatroom_message(room.to_string(), &admin)?;
+        node.create_shared_message_with_data(create).await?;
+    }
+    for (room, text) in [("general", "hi"), ("random", "lol"), ("general", "bye")] {
+        let post = helpers::create_post_message(room.to_string(), text.to_string(), &admin)?;
+        node.create_shared_message_with_data(post).await?;
+    }
+
+    let general = IndexKey::from("general");
+    let history = node
+        .scan_index(CHATROOM_POSTS_INDEX, general.clone()..=general)
+        .await?;
+    let texts: Vec<&str> = history
+        .iter()
+        .map(|m| m.data["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(texts, vec!["hi", "bye"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_by_height() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    for number in [1u64, 3, 2] {
+        let block = SharedMessage::new(
+            MessageType::Custom(POA_BLOCK_MESSAGE_TYPE.to_string()),
+            json!({ "number": number, "hash": format!("block-{}", number) }),
+        );
+        node.submit_message(block).await?;
+    }
+    node.create_shared_message_with_data(json!({ "number": 2 }))
+        .await?;
+
+    let blocks = node
+        .scan_index(BLOCK_HEIGHT_INDEX, IndexKey::Number(2)..=IndexKey::Number(3))
+        .await?;
+    let hashes: Vec<&str> = blocks
+        .iter()
+        .map(|m| m.data["hash"].as_str().unwrap())
+        .collect();
+    assert_eq!(hashes, vec!["block-2", "block-3"]);
+    assert_eq!(PoaBlock::height_index().name(), BLOCK_HEIGHT_INDEX);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    integrity::{self, Corruption, QuarantinedMessage},
+    shared::{MessageType, SharedMessage},
+    storage::{MemoryStorage, MessageEncoding, Storage},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+fn note(n: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("note".to_string()), json!({ "n": n }))
+}
+
+#[tokio::test]
+async fn test_corrupt_messages_are_quarantined_on_start() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let writer = ChaincraftNode::builder()
+        .with_storage(storage.clone())
+        .build()?;
+    let (private_key, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    let mut signed = note(1);
+    signed.sign(&private_key)?;
+    let signed = writer.submit_message(signed).await?;
+    let garbled = writer.submit_message(note(2)).await?;
+    let edited = writer.submit_message(note(3)).await?;
+    let moved = writer.submit_message(note(4)).await?;
+    let intact = writer.submit_message(note(5)).await?;
+
+    // Tamper with the entries behind the node's back
+    let load = |hash: String| {
+        let storage = storage.clone();
+        async move { MessageEncoding::decode(&storage.get(&hash).await?.unwrap()) }
+    };
+    let mut message = load(signed.clone()).await?;
+    message.signature.as_mut().unwrap()[0] ^= 1;
+    storage
+        .put(&signed, MessageEncoding::Json.encode(&message)?)
+        .await?;
+    storage
+        .put(&garbled, b"\xB2 not a message".to_vec())
+        .await?;
+    let mut message = load(edited.clone()).await?;
+    message.data = json!({ "n": 300 });
+    storage
+        .put(&edited, MessageEncoding::Json.encode(&message)?)
+        .await?;
+    let renamed = "ab".repeat(32);
+    storage
+        .put(&renamed, storage.get(&moved).await?.unwrap())
+        .await?;
+
+    let mut node = ChaincraftNode::builder()
+        .with_storage(storage.clone())
+        .with_integrity_check(true)
+        .build()?;
+    node.start().await?;
+    assert_eq!(node.metrics().get("integrity_checked"), 6);
+    assert_eq!(node.metrics().get("integrity_quarantined"), 4);
+    let mut expected = vec![signed.clone(), garbled.clone(), edited.clone(), renamed.clone()];
+    expected.sort();
+    assert_eq!(node.quarantined_messages().await?, expected);
+
+    // Quarantined bytes are kept as they were, out of the message namespace
+    assert!(node.get_message(&garbled).await?.is_none());
+    assert_eq!(
+        storage.get(&integrity::quarantine_key(&garbled)).await?,
+        Some(b"\xB2 not a message".to_vec())
+    );
+    assert!(node.get_message(&intact).await?.is_some());
+    assert!(node.get_message(&moved).await?.is_some());
+    assert!(node.get_receipt(&intact).await?.is_some());
+
+    let report = node.check_integrity().await?;
+    assert!(report.is_clean());
+    assert_eq!(report.checked, 2);
+    node.stop().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_report_names_each_corruption() -> Result<()> {
+    let storage = MemoryStorage::new();
+    let message = note(1);
+    storage
+        .put(&message.hash, MessageEncoding::Binary.encode(&message)?)
+        .await?;
+    storage.put(&"0".repeat(64), b"{".to_vec()).await?;
+    storage.put("receipt:x", b"{".to_vec()).await?;
+
+    let report = integrity::check_storage(&storage).await?;
+    assert_eq!(report.checked, 2);
+    assert_eq!(
+        report.quarantined,
+        vec![QuarantinedMessage {
+            key: "0".repeat(64),
+            corruption: Corruption::Undecodable,
+        }]
+    );
+    assert_eq!(report.to_string(), "2 stored messages checked, 1 quarantined (1 undecodable)");
+    assert!(storage.exists("receipt:x").await?);
+    assert_eq!(
+        integrity::inspect(&message.hash, &MessageEncoding::Json.encode(&message)?),
+        None
+    );
+    assert_eq!(
+        integrity::inspect(&"1".repeat(64), &MessageEncoding::Json.encode(&message)?),
+        Some(Corruption::HashMismatch)
+    );
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use chaincraft_rust::{
+    network::keepalive::{KeepaliveConfig, KeepaliveTransport},
+    network::transport::{InMemoryNetwork, Transport},
+    network::PeerId,
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, PeerInfo, PeerStatus, Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn config(max_missed: u32) -> KeepaliveConfig {
+    KeepaliveConfig {
+        interval: Duration::from_secs(60),
+        max_missed,
+    }
+}
+
+/// Keep receiving so pings are answered and pongs recorded
+fn serve<T: Transport + Send + Sync + 'static>(transport: Arc<KeepaliveTransport<T>>) {
+    tokio::spawn(async move { while transport.recv().await.is_ok() {} });
+}
+
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(50)).await;
+}
+
+#[tokio::test]
+async fn test_unanswered_keepalives_mark_a_peer_dead() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let alice =
+        Arc::new(KeepaliveTransport::with_config(network.connect(PeerId::new()), config(2)));
+    let bob = KeepaliveTransport::new(network.connect(PeerId::new()));
+    let bob_id = bob.local_id().clone();
+    alice.liveness().track(&bob_id);
+    serve(alice.clone());
+
+    // Pings are answered without reaching the application
+    alice.ping_peers().await;
+    let note = SharedMessage::new(MessageType::Custom("note".to_string()), json!(1));
+    alice.send(&bob_id, &note).await?;
+    let (_, received) = bob.recv().await?;
+    assert_eq!(received.hash, note.hash);
+    settle().await;
+    assert!(alice.ping_peers().await.is_empty());
+    assert_eq!(alice.liveness().status(&bob_id), Some(PeerStatus::Alive));
+
+    // Bob stops reading: one missed round makes him suspect, two dead
+    settle().await;
+    assert!(alice.ping_peers().await.is_empty());
+    assert_eq!(alice.liveness().status(&bob_id), Some(PeerStatus::Suspect));
+    assert_eq!(alice.ping_peers().await, vec![bob_id.clone()]);
+    assert!(alice.liveness().is_dead(&bob_id));
+    // Dead peers are only reported once
+    assert!(alice.ping_peers().await.is_empty());
+    assert_eq!(alice.liveness().dead_peers(), vec![bob_id]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dead_peers_leave_gossip_until_they_answer() -> Result<()> {
+    let node = ChaincraftNode::builder()
+        .with_keepalive(config(2))
+        .build()?;
+    let network = InMemoryNetwork::new();
+    let transport = Arc::new(KeepaliveTransport::with_liveness(
+        network.connect(node.id().clone()),
+        node.liveness.clone(),
+    ));
+    serve(transport.clone());
+
+    let responsive = Arc::new(KeepaliveTransport::new(network.connect(PeerId::new())));
+    let silent = network.connect(PeerId::new());
+    let (responsive_id, silent_id) = (responsive.local_id().clone(), silent.local_id().clone());
+    serve(responsive.clone());
+    for (port, peer_id) in [(9001, &responsive_id), (9002, &silent_id)] {
+        let address = format!("127.0.0.1:{}", port).parse().unwrap();
+        node.add_peer(PeerInfo::new(peer_id.clone(), address))
+            .await?;
+    }
+
+    let mut died = Vec::new();
+    for _ in 0..3 {
+        died.extend(transport.ping_peers().await);
+        settle().await;
+    }
+    assert_eq!(died, vec![silent_id.clone()]);
+
+    let status = |peers: &[PeerInfo], id: &PeerId| {
+        peers
+            .iter()
+            .find(|peer| &peer.id == id)
+            .map(|peer| peer.status)
+    };
+    let peers = node.get_peers().await;
+    assert_eq!(peers.len(), 2);
+    assert_eq!(status(&peers, &responsive_id), Some(PeerStatus::Alive));
+    assert_eq!(status(&peers, &silent_id), Some(PeerStatus::Dead));
+    assert_eq!(node.select_gossip_targets("hash").await.targets(), vec![responsive_id.clone()]);
+
+    // Any frame from the dead peer brings it back
+    let note = SharedMessage::new(MessageType::Custom("note".to_string()), json!(1));
+    silent.send(node.id(), &note).await?;
+    settle().await;
+    let decision = node.select_gossip_targets("hash").await;
+    assert!(decision.includes(&silent_id));
+    assert!(decision.includes(&responsive_id));
+
+    node.remove_peer(&silent_id).await?;
+    assert_eq!(node.liveness.status(&silent_id), None);
+    Ok(())
+}
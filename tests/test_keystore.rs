@@ -0,0 +1,178 @@
+use chaincraft_rust::{
+    crypto::keystore::{KdfParams, KeyConfig, KeyFile, KeyRole, KeyStore},
+    crypto::{utils, KeyType},
+    error::{ChaincraftError, CryptoError},
+    examples::{randomness_beacon::RandomnessBeaconObject, tendermint::TendermintObject},
+    storage::{MemoryStorage, Storage},
+    ApplicationObject, ChaincraftNode, Result,
+};
+use std::sync::Arc;
+
+/// Cheap scrypt parameters, the defaults are too slow for debug builds
+const FAST_KDF: KdfParams = KdfParams {
+    log_n: 8,
+    r: 8,
+    p: 1,
+};
+
+fn is_decryption_failure(result: Result<impl std::fmt::Debug>) -> bool {
+    matches!(result, Err(ChaincraftError::Crypto(CryptoError::DecryptionFailed { .. })))
+}
+
+#[tokio::test]
+async fn test_keys_persist_with_roles() -> Result<()> {
+    let storage = Arc::new(MemoryStorage::new());
+    let keystore = KeyStore::new(storage.clone());
+    let key = keystore
+        .generate("validator", KeyRole::ValidatorConsensus, KeyType::Ed25519)
+        .await?;
+    assert!(keystore
+        .import("validator", KeyRole::ValidatorConsensus, &key)
+        .await
+        .is_err());
+
+    // A second store over the same storage sees the same key
+    let reopened = KeyStore::new(storage);
+    let loaded = reopened
+        .get("validator", KeyRole::ValidatorConsensus)
+        .await?
+        .expect("key persisted");
+    assert_eq!(loaded.to_hex(), key.to_hex());
+    assert!(reopened.get("validator", KeyRole::Node).await.is_err());
+    assert_eq!(
+        reopened.list().await?,
+        vec![("validator".to_string(), KeyRole::ValidatorConsensus)]
+    );
+
+    assert!(reopened.remove("validator").await?);
+    assert!(reopened.names().await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_objects_use_configured_keys() -> Result<()> {
+    let node = ChaincraftNode::builder()
+        .with_keys(KeyConfig {
+            consensus: Some("consensus".to_string()),
+            vrf: Some("vrf".to_string()),
+            ..KeyConfig::default()
+        })
+        .build()?;
+    assert!(node.role_signer(KeyRole::Node).await?.is_none());
+
+    let consensus = node
+        .role_signer(KeyRole::ValidatorConsensus)
+        .await?
+        .unwrap();
+    let again = node
+        .role_signer(KeyRole::ValidatorConsensus)
+        .await?
+        .unwrap();
+    assert_eq!(consensus.get_public_key_pem()?, again.get_public_key_pem()?);
+    let vrf = node.role_signer(KeyRole::ValidatorVrf).await?.unwrap();
+    assert_ne!(consensus.get_public_key_pem()?, vrf.get_public_key_pem()?);
+
+    let tendermint = TendermintObject::with_signer(consensus.clone())?;
+    assert_eq!(tendermint.my_validator_address, consensus.get_public_key_pem()?);
+    // Clones keep the key instead of generating a fresh one
+    let cloned = tendermint.clone_box();
+    let cloned = cloned.as_any().downcast_ref::<TendermintObject>().unwrap();
+    assert_eq!(cloned.my_validator_address, tendermint.my_validator_address);
+
+    let beacon = RandomnessBeaconObject::with_keys(10, 1, consensus.clone(), vrf.clone())?;
+    assert_eq!(beacon.my_validator_address, consensus.get_public_key_pem()?);
+    let proof = beacon.generate_vrf_proof("seed")?;
+    let again = beacon.generate_vrf_proof("seed")?;
+    assert_eq!(proof.output, again.output);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_passphrase_encrypts_keys_at_rest() -> Result<()> {
+    let storage = Arc::new(MemoryStorage::new());
+    let keystore = KeyStore::new(storage.clone())
+        .with_passphrase("correct horse")
+        .with_kdf(FAST_KDF);
+    let key = keystore
+        .generate("validator", KeyRole::ValidatorConsensus, KeyType::Secp256k1)
+        .await?;
+
+    let raw = storage.get("keystore:key:validator").await?.unwrap();
+    assert!(!String::from_utf8_lossy(&raw).contains(&key.to_hex()));
+
+    let loaded = KeyStore::new(storage.clone())
+        .with_passphrase("correct horse")
+        .get("validator", KeyRole::ValidatorConsensus)
+        .await?
+        .unwrap();
+    assert_eq!(loaded.to_hex(), key.to_hex());
+
+    assert!(KeyStore::new(storage.clone())
+        .get("validator", KeyRole::ValidatorConsensus)
+        .await
+        .is_err());
+    let wrong = KeyStore::new(storage.clone()).with_passphrase("battery staple");
+    assert!(is_decryption_failure(wrong.get("validator", KeyRole::ValidatorConsensus).await));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_keeps_identity_from_key_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("node.key");
+    let (key, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    KeyFile::seal(&key, KeyRole::Node, "hunter2", FAST_KDF)?
+        .save(&path)
+        .await?;
+
+    let file = KeyFile::load(&path).await?;
+    assert_eq!(file.public_key, key.public_key().to_hex());
+    assert_eq!(file.open("hunter2")?.to_hex(), key.to_hex());
+    assert!(is_decryption_failure(file.open("hunter3")));
+    // Relabelling the key breaks its authentication
+    let mut relabelled = file.clone();
+    relabelled.role = KeyRole::ValidatorConsensus;
+    assert!(is_decryption_failure(relabelled.open("hunter2")));
+
+    for _ in 0..2 {
+        let mut node = ChaincraftNode::builder()
+            .port(0)
+            .with_key_file(&path, "hunter2")
+            .build()?;
+        node.start().await?;
+        assert_eq!(node.identity_public_key(), Some(key.public_key().to_hex()));
+        node.close().await?;
+    }
+
+    let mut node = ChaincraftNode::builder()
+        .port(0)
+        .with_key_file(&path, "wrong")
+        .build()?;
+    assert!(node.start().await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_costly_kdf_parameters_are_refused() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("node.key");
+    let (key, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    let mut file = KeyFile::seal(&key, KeyRole::Node, "hunter2", FAST_KDF)?;
+    file.secret.kdf.log_n = 40;
+    file.save(&path).await?;
+    assert!(matches!(
+        KeyFile::open_or_generate(&path, "hunter2", KeyRole::Node, KeyType::Ed25519).await,
+        Err(ChaincraftError::Config(_))
+    ));
+
+    let too_costly = KdfParams {
+        p: KdfParams::MAX.p + 1,
+        ..FAST_KDF
+    };
+    assert!(KeyFile::seal(&key, KeyRole::Node, "hunter2", too_costly).is_err());
+    let keystore = KeyStore::new(Arc::new(MemoryStorage::new()))
+        .with_passphrase("hunter2")
+        .with_kdf(too_costly);
+    assert!(keystore.import("node", KeyRole::Node, &key).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use chaincraft_rust::{
+    crypto::{utils, KeyType, PrivateKey},
+    examples::ledger::{
+        address_of, LedgerObject, Transfer, MAX_PENDING_PER_ACCOUNT, MAX_PENDING_TRANSFERS,
+        TRANSFER_GAS,
+    },
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ConfigurableObject},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+
+fn account(key_type: KeyType) -> Result<(PrivateKey, String)> {
+    let (private_key, public_key) = utils::generate_keypair(key_type)?;
+    Ok((private_key, address_of(&public_key)))
+}
+
+fn message(transfer: &Transfer) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("transfer".to_string()), transfer.to_message())
+}
+
+#[tokio::test]
+async fn test_transfers_move_balances_and_bump_nonces() -> Result<()> {
+    let (alice, alice_address) = account(KeyType::Ed25519)?;
+    let (bob, bob_address) = account(KeyType::Secp256k1)?;
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(LedgerObject::with_genesis([(alice_address.clone(), 100)])))
+        .await?;
+
+    let transfer = Transfer::signed(&alice, &bob_address, 30, 0)?;
+    node.create_shared_message_with_data(transfer.to_message())
+        .await?;
+    let back = Transfer::signed(&bob, &alice_address, 5, 0)?;
+    node.create_shared_message_with_data(back.to_message())
+        .await?;
+
+    let (alice_account, bob_balance, supply) = node
+        .with_typed::<LedgerObject, _>(&id, |ledger| {
+            (
+                ledger.account(&alice_address),
+                ledger.balance(&bob_address),
+                ledger.total_supply(),
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(alice_account.balance, 75);
+    assert_eq!(alice_account.nonce, 1);
+    assert_eq!(bob_balance, 25);
+    assert_eq!(supply, 100);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_transfers_are_refused() -> Result<()> {
+    let (alice, alice_address) = account(KeyType::Ed25519)?;
+    let (mallory, _) = account(KeyType::Ed25519)?;
+    let (_, bob_address) = account(KeyType::Ed25519)?;
+    let mut ledger = LedgerObject::with_genesis([(alice_address.clone(), 10)]);
+
+    let code = |outcome: chaincraft_rust::shared_object::ApplyOutcome| {
+        outcome.reason().map(|reason| reason.code.clone())
+    };
+    let too_much = Transfer::signed(&alice, &bob_address, 11, 0)?;
+    assert_eq!(
+        code(ledger.add_message(message(&too_much)).await?),
+        Some("insufficient_balance".to_string())
+    );
+
+    let mut inflated = Transfer::signed(&alice, &bob_address, 1, 0)?;
+    inflated.amount = 10;
+    assert_eq!(
+        code(ledger.add_message(message(&inflated)).await?),
+        Some("invalid_signature".to_string())
+    );
+
+    // Mallory signs a transfer out of Alice's account with her own key
+    let mut stolen = Transfer::signed(&mallory, &bob_address, 10, 0)?;
+    stolen.from = alice_address.clone();
+    assert_eq!(
+        code(ledger.add_message(message(&stolen)).await?),
+        Some("wrong_sender".to_string())
+    );
+
+    let transfer = Transfer::signed(&alice, &bob_address, 4, 0)?;
+    assert!(ledger.add_message(message(&transfer)).await?.is_applied());
+    assert_eq!(
+        code(ledger.add_message(message(&transfer)).await?),
+        Some("stale_nonce".to_string())
+    );
+    assert_eq!(ledger.balance(&alice_address), 6);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_future_nonces_wait_for_the_gap() -> Result<()> {
+    let (alice, alice_address) = account(KeyType::Ed25519)?;
+    let (_, bob_address) = account(KeyType::Ed25519)?;
+    let mut ledger = LedgerObject::with_genesis([(alice_address.clone(), 10)]);
+
+    let second = Transfer::signed(&alice, &bob_address, 3, 1)?;
+    let outcome = ledger.add_message(message(&second)).await?;
+    assert_eq!(outcome.reason().unwrap().code, "future_nonce");
+    assert_eq!(ledger.pending_count(), 1);
+    assert_eq!(ledger.balance(&bob_address), 0);
+
+    let first = Transfer::signed(&alice, &bob_address, 2, 0)?;
+    assert!(ledger.add_message(message(&first)).await?.is_applied());
+    assert_eq!(ledger.pending_count(), 0);
+    assert_eq!(ledger.balance(&bob_address), 5);
+    assert_eq!(ledger.nonce(&alice_address), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_held_transfers_must_be_covered() -> Result<()> {
+    let (alice, alice_address) = account(KeyType::Ed25519)?;
+    let (_, bob_address) = account(KeyType::Ed25519)?;
+    let mut ledger = LedgerObject::with_genesis([(alice_address.clone(), 10)]);
+
+    let second = Transfer::signed(&alice, &bob_address, 6, 1)?;
+    let outcome = ledger.add_message(message(&second)).await?;
+    assert_eq!(outcome.reason().unwrap().code, "future_nonce");
+    // Together with the held transfer this is more than Alice holds
+    let third = Transfer::signed(&alice, &bob_address, 5, 2)?;
+    let outcome = ledger.add_message(message(&third)).await?;
+    assert_eq!(outcome.reason().unwrap().code, "insufficient_balance");
+    assert_eq!(ledger.pending_count(), 1);
+
+    // A sender with nothing cannot park transfers at all
+    let (broke, _) = account(KeyType::Ed25519)?;
+    let unfunded = Transfer::signed(&broke, &bob_address, 1, 1)?;
+    let outcome = ledger.add_message(message(&unfunded)).await?;
+    assert_eq!(outcome.reason().unwrap().code, "insufficient_balance");
+    assert_eq!(ledger.pending_count(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_held_transfers_are_capped_across_senders() -> Result<()> {
+    let senders = MAX_PENDING_TRANSFERS / MAX_PENDING_PER_ACCOUNT + 1;
+    let keys = (0..senders)
+        .map(|_| account(KeyType::Ed25519))
+        .collect::<Result<Vec<_>>>()?;
+    let (_, bob_address) = account(KeyType::Ed25519)?;
+    let mut ledger = LedgerObject::with_genesis(
+        keys.iter()
+            .map(|(_, address)| (address.clone(), MAX_PENDING_PER_ACCOUNT as u64)),
+    );
+
+    let mut last = None;
+    for (key, _) in &keys {
+        for nonce in 1..=MAX_PENDING_PER_ACCOUNT as u64 {
+            let transfer = Transfer::signed(key, &bob_address, 1, nonce)?;
+            last = Some(ledger.add_message(message(&transfer)).await?);
+        }
+    }
+    assert_eq!(ledger.pending_count(), MAX_PENDING_TRANSFERS);
+    assert_eq!(last.unwrap().reason().unwrap().code, "pending_full");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transfers_charge_gas_and_emit_events() -> Result<()> {
+    let (alice, alice_address) = account(KeyType::Ed25519)?;
+    let (_, bob_address) = account(KeyType::Ed25519)?;
+    let mut ledger = LedgerObject::with_genesis([(alice_address.clone(), 10)]);
+
+    let too_much = Transfer::signed(&alice, &bob_address, 11, 0)?;
+    assert!(ledger.add_message(message(&too_much)).await?.is_rejected());
+    assert_eq!(ledger.take_execution().gas_used, 0);
+
+    // A held transfer is charged to the message that lets it apply
+    let second = Transfer::signed(&alice, &bob_address, 3, 1)?;
+    ledger.add_message(message(&second)).await?;
+    assert!(ledger.take_execution().events.is_empty());
+    let first = Transfer::signed(&alice, &bob_address, 2, 0)?;
+    ledger.add_message(message(&first)).await?;
+    let execution = ledger.take_execution();
+    assert_eq!(execution.gas_used, 2 * TRANSFER_GAS);
+    assert_eq!(execution.events.len(), 2);
+    let (name, data) = &execution.events[0];
+    assert_eq!(name, "Transfer");
+    assert_eq!(
+        data,
+        &json!({ "from": alice_address, "to": bob_address, "amount": 2, "nonce": 0 })
+    );
+    assert_eq!(execution.events[1].1["nonce"], 1);
+    assert_eq!(ledger.take_execution().gas_used, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_state_root_proves_accounts() -> Result<()> {
+    let (alice, alice_address) = account(KeyType::Ed25519)?;
+    let (_, bob_address) = account(KeyType::Ed25519)?;
+    let mut ledger = LedgerObject::from_config_value(json!({
+        "genesis": { alice_address.clone(): 50 }
+    }))?;
+    let genesis_root = ledger.state_root();
+    let transfer = Transfer::signed(&alice, &bob_address, 20, 0)?;
+    ledger.add_message(message(&transfer)).await?;
+
+    let root = ledger.state_root();
+    assert_ne!(root, genesis_root);
+    assert_eq!(ledger.get_state().await?["state_root"], json!(root));
+
+    let bob = ledger.account(&bob_address);
+    let proof = ledger.account_proof(&bob_address).unwrap();
+    assert!(bob.is_proven_by(&bob_address, &proof, &root));
+    assert!(!bob.is_proven_by(&bob_address, &proof, &genesis_root));
+    let mut richer = bob;
+    richer.balance += 1;
+    assert!(!richer.is_proven_by(&bob_address, &proof, &root));
+
+    // Replicas that applied the same transfers agree on the root
+    let mut replica = ledger.clone();
+    replica.reset().await?;
+    assert_eq!(replica.state_root(), genesis_root);
+    replica.add_message(message(&transfer)).await?;
+    assert_eq!(replica.state_root(), root);
+
+    assert!(LedgerObject::from_config_value(json!({ "genesis": { "alice": 1 } })).is_err());
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    error::Result,
+    shared::{MessageType, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    sync::merkle::{MerkleTree, MerkleizedObject},
+    ChaincraftNode, PeerId,
+};
+use serde_json::{json, Value};
+use std::any::Any;
+
+/// Log of notes that syncs by Merkle root
+#[derive(Debug, Clone)]
+struct NoteLog {
+    id: SharedObjectId,
+    merkle: MerkleizedObject,
+}
+
+impl NoteLog {
+    fn new() -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            merkle: MerkleizedObject::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for NoteLog {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+    fn type_name(&self) -> &'static str {
+        "NoteLog"
+    }
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.get("note").is_some())
+    }
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        self.merkle.apply(&message);
+        Ok(ApplyOutcome::Applied)
+    }
+    fn is_merkleized(&self) -> bool {
+        true
+    }
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.merkle.digest().to_string())
+    }
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self.merkle.contains(digest))
+    }
+    async fn is_valid_digest(&self, digest: &str) -> Result<bool> {
+        Ok(self.merkle.contains(digest))
+    }
+    async fn get_messages_since_digest(&self, digest: &str) -> Result<Vec<SharedMessage>> {
+        self.merkle.messages_since_or_err(digest)
+    }
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "notes": self.merkle.len(), "root": self.merkle.digest() }))
+    }
+    async fn reset(&mut self) -> Result<()> {
+        self.merkle.reset();
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn note(n: usize) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("note".to_string()), json!({ "note": n }))
+}
+
+fn tree_of(hashes: &[String]) -> MerkleTree {
+    let mut tree = MerkleTree::new();
+    for hash in hashes {
+        tree.push(hash);
+    }
+    tree
+}
+
+#[test]
+fn test_every_leaf_proves_against_every_prefix_root() {
+    let hashes: Vec<String> = (0..20).map(|n| note(n).hash).collect();
+    let tree = tree_of(&hashes);
+    assert_eq!(tree.root_at(0), Some(MerkleTree::empty_root().as_str()));
+
+    for size in 1..=hashes.len() {
+        let root = tree.root_at(size).unwrap();
+        // Roots do not depend on how the tree grew
+        assert_eq!(root, tree_of(&hashes[..size]).root());
+        assert_eq!(tree.size_of(root), Some(size));
+        for (index, hash) in hashes[..size].iter().enumerate() {
+            let proof = tree.proof_at(index, size).unwrap();
+            assert!(proof.proves(hash));
+            assert!(proof.verify(root), "leaf {} of {}", index, size);
+            assert!(!proof.verify(tree.root_at(size - 1).unwrap()));
+        }
+    }
+
+    let mut forged = tree.proof(7).unwrap();
+    forged.index = 6;
+    assert!(!forged.verify(tree.root()));
+    assert!(tree.proof(20).is_none());
+}
+
+#[test]
+fn test_diverged_trees_find_their_common_prefix_in_few_lookups() {
+    let shared: Vec<String> = (0..1000).map(|n| note(n).hash).collect();
+    let mut ours = tree_of(&shared);
+    let mut theirs = tree_of(&shared);
+    for n in 0..24 {
+        ours.push(&note(10_000 + n).hash);
+    }
+    for n in 0..300 {
+        theirs.push(&note(20_000 + n).hash);
+    }
+
+    let mut lookups = 0;
+    let common = ours.common_prefix_len(theirs.len(), |size| {
+        lookups += 1;
+        theirs.root_at(size).map(str::to_string)
+    });
+    assert_eq!(common, 1000);
+    assert!(lookups <= 11, "{} lookups", lookups);
+
+    // A peer that is simply behind shares its whole tree
+    let behind = tree_of(&shared[..600]);
+    let common =
+        ours.common_prefix_len(behind.len(), |size| behind.root_at(size).map(str::to_string));
+    assert_eq!(common, 600);
+}
+
+#[tokio::test]
+async fn test_lagging_node_catches_up_from_its_merkle_root() -> Result<()> {
+    let mut ahead = ChaincraftNode::builder().build()?;
+    let behind = ChaincraftNode::builder().build()?;
+    let ahead_id = ahead.add_shared_object(Box::new(NoteLog::new())).await?;
+    let behind_id = behind.add_shared_object(Box::new(NoteLog::new())).await?;
+
+    let peer = PeerId::new();
+    let mut hashes = Vec::new();
+    for n in 0..5 {
+        let hash = ahead
+            .create_shared_message_with_data(json!({ "note": n }))
+            .await?;
+        if n < 3 {
+            let message = ahead.get_message(&hash).await?.unwrap();
+            behind.receive_message(&peer, message).await?;
+        }
+        hashes.push(hash);
+    }
+
+    let root = behind
+        .get_typed::<NoteLog>(&behind_id)
+        .await
+        .unwrap()
+        .merkle
+        .digest()
+        .to_string();
+    let missing = ahead.messages_since_digest(&ahead_id, &root).await?;
+    assert_eq!(missing.iter().map(|m| m.hash.clone()).collect::<Vec<_>>(), hashes[3..].to_vec());
+    for message in missing {
+        behind.receive_message(&peer, message).await?;
+    }
+
+    let ahead_log = ahead.get_typed::<NoteLog>(&ahead_id).await.unwrap();
+    let behind_log = behind.get_typed::<NoteLog>(&behind_id).await.unwrap();
+    assert_eq!(ahead_log.merkle.digest(), behind_log.merkle.digest());
+    // Either side can prove a message is part of the shared root
+    let proof = behind_log.merkle.proof(&hashes[1]).unwrap();
+    assert!(proof.verify(ahead_log.merkle.digest()));
+
+    assert!(ahead
+        .messages_since_digest(&ahead_id, "not-a-root")
+        .await
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use chaincraft_rust::{
+    message_cache::MessageCache,
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+
+#[test]
+fn test_cache_evicts_least_recently_used_by_size() {
+    let cache = MessageCache::new(100);
+    let messages: Vec<SharedMessage> = (0..3)
+        .map(|i| SharedMessage::new(MessageType::Custom("cache".to_string()), json!({ "n": i })))
+        .collect();
+
+    assert_eq!(cache.insert(&messages[0], 40), 0);
+    assert_eq!(cache.insert(&messages[1], 40), 0);
+    // Touch the first message so the second is the oldest
+    assert!(cache.get(&messages[0].hash).is_some());
+    assert_eq!(cache.insert(&messages[2], 40), 1);
+    assert!(cache.contains(&messages[0].hash));
+    assert!(!cache.contains(&messages[1].hash));
+    assert!(cache.get(&messages[1].hash).is_none());
+
+    // Too large for the whole budget
+    let huge = SharedMessage::new(MessageType::Custom("cache".to_string()), json!("huge"));
+    assert_eq!(cache.insert(&huge, 101), 0);
+    assert!(!cache.contains(&huge.hash));
+
+    assert!(cache.invalidate(&messages[0].hash));
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 1));
+    assert_eq!((stats.entries, stats.bytes), (1, 40));
+}
+
+#[tokio::test]
+async fn test_node_reads_through_cache() -> Result<()> {
+    let mut node = ChaincraftNode::builder().build()?;
+    let hash = node
+        .create_shared_message_with_data(json!({ "cached": true }))
+        .await?;
+
+    // Stored messages are written through to the cache
+    let message = node.get_message(&hash).await?.unwrap();
+    assert_eq!(message.data, json!({ "cached": true }));
+    assert_eq!(node.metrics.get("message_cache_hits"), 1);
+
+    node.message_cache.clear();
+    assert!(node.get_message(&hash).await?.is_some());
+    assert!(node.get_message(&hash).await?.is_some());
+    assert_eq!(node.metrics.get("message_cache_misses"), 1);
+    assert_eq!(node.metrics.get("message_cache_hits"), 2);
+
+    let state = node.get_state().await?;
+    assert_eq!(state["message_cache"]["entries"], json!(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deleted_messages_leave_the_cache() -> Result<()> {
+    let mut node = ChaincraftNode::builder().build()?;
+    let hash = node
+        .create_shared_message_with_data(json!({ "short": "lived" }))
+        .await?;
+    assert!(node.message_cache.contains(&hash));
+
+    assert!(node.delete_message(&hash).await?);
+    assert!(!node.message_cache.contains(&hash));
+    assert!(node.get_message(&hash).await?.is_none());
+    assert!(!node.delete_message(&hash).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cache_can_be_disabled() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_message_cache_bytes(0)
+        .build()?;
+    let hash = node.create_shared_message_with_data(json!(1)).await?;
+    assert!(node.get_message(&hash).await?.is_some());
+    assert_eq!(node.metrics.get("message_cache_hits"), 0);
+    assert_eq!(node.message_cache.stats().entries, 0);
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    query::MessageFilter,
+    shared::{MessageType, SharedMessage, SharedObjectId},
+    ChaincraftNode, Result,
+};
+use chrono::{Duration, TimeZone, Utc};
+use serde_json::json;
+
+fn chat(text: &str) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("chat".to_string()), json!({ "text": text }))
+}
+
+#[tokio::test]
+async fn test_filter_by_type_and_target() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let room = SharedObjectId::new();
+    let other = SharedObjectId::new();
+
+    node.submit_message(chat("hello")).await?;
+    let in_room = SharedMessage::new_with_target(
+        MessageType::Custom("chat".to_string()),
+        room.clone(),
+        json!({ "text": "in the room" }),
+    );
+    node.submit_message(in_room.clone()).await?;
+    node.submit_message(SharedMessage::new_with_target(
+        MessageType::Heartbeat,
+        room.clone(),
+        json!(null),
+    ))
+    .await?;
+    node.submit_message(SharedMessage::new_with_target(MessageType::Heartbeat, other, json!(null)))
+        .await?;
+
+    let chats = MessageFilter::new().of_type(MessageType::Custom("chat".to_string()));
+    assert_eq!(node.query_messages(&chats).await?.len(), 2);
+    let heartbeats = MessageFilter::new().of_type(MessageType::Heartbeat);
+    assert_eq!(node.query_messages(&heartbeats).await?.len(), 2);
+
+    let room_messages = node
+        .query_messages(&MessageFilter::new().targeting(room.clone()))
+        .await?;
+    assert_eq!(room_messages.len(), 2);
+    let room_chats = node.query_messages(&chats.targeting(room)).await?;
+    assert_eq!(room_chats.len(), 1);
+    assert_eq!(room_chats[0].hash, in_room.hash);
+
+    // No filter returns everything in log order
+    let all = node.query_messages(&MessageFilter::new()).await?;
+    let hashes: Vec<String> = all.into_iter().map(|m| m.hash).collect();
+    assert_eq!(hashes, node.message_log.read().await.hashes());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sender_must_have_signed() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let (alice, alice_public) = utils::generate_keypair(KeyType::Ed25519)?;
+    let (bob, bob_public) = utils::generate_keypair(KeyType::Secp256k1)?;
+
+    for (key, text) in [(&alice, "a1"), (&bob, "b1"), (&alice, "a2")] {
+        let mut message = chat(text);
+        message.sign(key)?;
+        node.submit_message(message).await?;
+    }
+    // Claiming alice as sender without her signature is refused
+    let mut forged = chat("forged");
+    forged.sender = Some(alice_public.to_hex());
+    forged.signature = Some(vec![0; 64]);
+    assert!(node.submit_message(forged).await.is_err());
+
+    let from_alice = node
+        .query_messages(&MessageFilter::new().from_sender(alice_public.to_hex()))
+        .await?;
+    let texts: Vec<&serde_json::Value> = from_alice.iter().map(|m| &m.data["text"]).collect();
+    assert_eq!(texts, vec!["a1", "a2"]);
+    assert!(from_alice[0].verify_signature(&alice_public)?);
+
+    let from_bob = node
+        .query_messages(&MessageFilter::new().from_sender(bob_public.to_hex()))
+        .await?;
+    assert_eq!(from_bob.len(), 1);
+    assert_eq!(from_bob[0].verified_sender(), Some(bob_public.to_hex().as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_time_range_and_limit() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    // Stored out of timestamp order to check results follow the log
+    for minute in [3, 0, 1, 4, 2] {
+        let mut message = chat(&minute.to_string());
+        message.timestamp = start + Duration::minutes(minute);
+        message.hash = message.calculate_hash();
+        node.submit_message(message).await?;
+    }
+
+    let window = MessageFilter::new()
+        .since(start + Duration::minutes(1))
+        .until(start + Duration::minutes(3));
+    let texts = |messages: Vec<SharedMessage>| -> Vec<String> {
+        messages
+            .into_iter()
+            .map(|m| m.data["text"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(texts(node.query_messages(&window).await?), vec!["3", "1", "2"]);
+    assert_eq!(texts(node.query_messages(&window.clone().limit(2)).await?), vec!["3", "1"]);
+
+    let later = MessageFilter::new().since(start + Duration::minutes(4));
+    assert_eq!(texts(node.query_messages(&later).await?), vec!["4"]);
+    let empty = MessageFilter::new()
+        .since(start + Duration::minutes(3))
+        .until(start);
+    assert!(node.query_messages(&empty).await?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use chaincraft_rust::examples::{self, chatroom, randomness_beacon, tendermint};
+
+#[test]
+fn test_every_example_publishes_deterministic_schemas() {
+    let schemas = examples::schemas();
+    assert_eq!(
+        schemas.keys().copied().collect::<Vec<_>>(),
+        ["chatroom", "ledger", "pow_chain", "randomness_beacon", "tendermint"]
+    );
+    assert_eq!(
+        serde_json::to_string(&schemas).unwrap(),
+        serde_json::to_string(&examples::schemas()).unwrap()
+    );
+
+    let beacon = &schemas["randomness_beacon"]["BeaconMessageType"];
+    assert_eq!(beacon["title"], "BeaconMessageType");
+    assert_eq!(beacon["oneOf"].as_array().unwrap().len(), 9);
+    assert!(randomness_beacon::schemas().contains_key("BiasEvidence"));
+    assert!(tendermint::schemas()["TendermintMessageType"]["definitions"]
+        .get("ValidatorInfo")
+        .is_some());
+}
+
+#[test]
+fn test_chatroom_schema_follows_the_wire_format() {
+    let schema = &chatroom::schemas()["ChatroomMessageType"];
+    let tags: Vec<_> = schema["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|variant| variant["properties"]["message_type"]["enum"][0].clone())
+        .collect();
+    assert!(tags.contains(&"POST_MESSAGE".into()));
+    assert!(tags.contains(&"READ_RECEIPT".into()));
+
+    // Fields with serde defaults are optional
+    let post = schema["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|variant| variant["properties"]["message_type"]["enum"][0] == "POST_MESSAGE")
+        .unwrap();
+    let required = post["required"].as_array().unwrap();
+    assert!(required.contains(&"text".into()));
+    assert!(!required.contains(&"signature".into()));
+}
@@ -0,0 +1,83 @@
+use chaincraft_rust::{
+    clock::Clock,
+    crypto::{utils::generate_keypair, KeyType},
+    message_stats::{MessageStats, MessageStatsConfig},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, Result,
+};
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use std::time::Duration;
+
+fn message(message_type: &str, sender: Option<&str>) -> SharedMessage {
+    let mut message = SharedMessage::new(MessageType::Custom(message_type.to_string()), json!(1));
+    message.sender = sender.map(str::to_string);
+    message
+}
+
+#[test]
+fn test_report_ranks_types_and_senders_within_the_window() {
+    let stats = MessageStats::new(MessageStatsConfig::new(Duration::from_secs(60)));
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+
+    // Old traffic from a sender that has gone quiet
+    for _ in 0..10 {
+        stats.record(&message("chat", Some("old")), start);
+    }
+    let later = start + chrono::Duration::seconds(90);
+    for _ in 0..3 {
+        stats.record(&message("chat", Some("alice")), later);
+    }
+    stats.record(&message("vote", Some("bob")), later);
+    stats.record(&message("vote", None), later);
+    stats.record(&message("ping", Some("carol")), later);
+
+    let report = stats.report(later, 2);
+    assert_eq!(report.window_secs, 60);
+    assert_eq!(report.total, 6);
+    assert_eq!(report.unsigned, 1);
+    let types: Vec<(&str, u64)> = report
+        .by_type
+        .iter()
+        .map(|t| (t.message_type.as_str(), t.count))
+        .collect();
+    assert_eq!(types, [("chat", 3), ("vote", 2), ("ping", 1)]);
+    assert_eq!(report.top_senders.len(), 2);
+    assert_eq!(report.top_senders[0].sender, "alice");
+    assert_eq!(report.top_senders[0].share, 0.5);
+    assert_eq!(report.top_senders[1].sender, "bob");
+
+    let idle = stats.report(later + chrono::Duration::seconds(61), 2);
+    assert_eq!(idle.total, 0);
+    assert!(idle.top_senders.is_empty());
+}
+
+#[tokio::test]
+async fn test_node_counts_stored_messages_by_type_and_sender() -> Result<()> {
+    let clock = Clock::mock(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+    let node = ChaincraftNode::builder()
+        .with_clock(clock.clone())
+        .build()?;
+    let (private_key, public_key) = generate_keypair(KeyType::Ed25519)?;
+    for i in 0..3 {
+        let mut message = SharedMessage::new(MessageType::Custom("chat".to_string()), json!(i));
+        message.sign(&private_key)?;
+        node.submit_message(message).await?;
+    }
+    node.submit_message(SharedMessage::new(MessageType::Heartbeat, json!(null)))
+        .await?;
+
+    let report = node.message_stats(5);
+    assert_eq!(report.total, 4);
+    assert_eq!(report.unsigned, 1);
+    assert_eq!(report.by_type[0].message_type, "chat");
+    assert_eq!(report.top_senders[0].sender, public_key.to_hex());
+    assert_eq!(report.top_senders[0].count, 3);
+    assert_eq!(node.metrics().get("messages_type:chat"), 3);
+    assert_eq!(node.handle().message_stats(5), report);
+
+    clock.advance(Duration::from_secs(301));
+    assert_eq!(node.message_stats(5).total, 0);
+    assert_eq!(node.metrics().get("messages_type:chat"), 3);
+    Ok(())
+}
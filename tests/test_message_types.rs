@@ -34,7 +34,10 @@ async fn wait_for_message_propagation(
     let timeout = Duration::from_secs(timeout_secs);
 
     while start.elapsed() < timeout {
-        let counts: Vec<usize> = nodes.iter().map(|node| node.db_size()).collect();
+        let mut counts = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            counts.push(node.db_size_async().await.unwrap_or(0));
+        }
         if counts.iter().all(|&count| count == expected_count) {
             return true;
         }
@@ -303,7 +306,7 @@ async fn test_simple_string_message() -> Result<()> {
 
     // For now, just verify the message was created
     // Real propagation would require gossip protocol implementation
-    assert_eq!(nodes[0].db_size(), 1);
+    assert_eq!(nodes[0].db_size_async().await?, 1);
 
     // Cleanup
     for mut node in nodes {
@@ -337,7 +340,7 @@ async fn test_simple_integer_message() -> Result<()> {
     let _hash = nodes[0].create_shared_message("42".to_string()).await?;
 
     // Verify the message was created
-    assert_eq!(nodes[0].db_size(), 1);
+    assert_eq!(nodes[0].db_size_async().await?, 1);
 
     // Cleanup
     for mut node in nodes {
@@ -381,7 +384,7 @@ async fn test_json_message() -> Result<()> {
         .await?;
 
     // Verify the message was created
-    assert_eq!(nodes[0].db_size(), 1);
+    assert_eq!(nodes[0].db_size_async().await?, 1);
 
     // Cleanup
     for mut node in nodes {
@@ -440,7 +443,7 @@ async fn test_complex_nested_message() -> Result<()> {
     let _hash = nodes[0].create_shared_message(block.to_string()).await?;
 
     // Verify the message was created
-    assert_eq!(nodes[0].db_size(), 1);
+    assert_eq!(nodes[0].db_size_async().await?, 1);
 
     // Cleanup
     for mut node in nodes {
@@ -0,0 +1,54 @@
+use chaincraft_rust::{
+    events::NodeEvent, query::MessageFilter, ChaincraftNode, NodeHandle, Result, SimpleSharedNumber,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_handles_share_the_node_across_tasks() -> Result<()> {
+    let node = ChaincraftNode::builder().build()?;
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let handle = node.handle();
+    let mut events = handle.subscribe();
+
+    let tasks: Vec<_> = (1..=4)
+        .map(|n| {
+            let handle: NodeHandle = handle.clone();
+            tokio::spawn(async move { handle.create_message(json!(n)).await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap()?;
+    }
+
+    // The node and its handles see the same objects, log and metrics
+    let state = node.object_directory().await.state(&id).await?;
+    assert_eq!(state["number"], json!(10));
+    let stored = handle.query_messages(&MessageFilter::default()).await?;
+    assert_eq!(stored.len(), 4);
+    assert!(node.get_message(&stored[0].hash).await?.is_some());
+    assert!(handle.get_receipt(&stored[0].hash).await?.is_some());
+    let processed = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| matches!(event, NodeEvent::MessageProcessed { .. }))
+        .count();
+    assert_eq!(processed, 4);
+    assert_eq!(handle.id(), node.id());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lifecycle_stays_with_the_owned_node() -> Result<()> {
+    let mut node = ChaincraftNode::new_default();
+    let handle = node.handle();
+    assert!(!handle.is_running().await);
+    node.start().await?;
+    assert!(handle.is_running().await);
+    node.close().await?;
+    assert!(!handle.is_running().await);
+
+    // Message handling keeps working on the shared state
+    handle.create_message(json!(1)).await?;
+    assert_eq!(node.message_log.read().await.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    error::CryptoError,
+    query::MessageFilter,
+    shared::{MessageType, SharedMessage},
+    ChaincraftError, ChaincraftNode, NodeMode, Result,
+};
+use serde_json::json;
+
+fn precommit(round: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("precommit".to_string()), json!({ "round": round }))
+}
+
+fn signed(round: u64) -> Result<SharedMessage> {
+    let (private_key, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    let mut message = precommit(round);
+    message.sign(&private_key)?;
+    Ok(message)
+}
+
+#[tokio::test]
+async fn test_permissive_mode_accepts_unsigned_untagged() -> Result<()> {
+    let node = ChaincraftNode::builder().build()?;
+    assert_eq!(node.mode(), NodeMode::Permissive);
+    let hash = node.submit_message(precommit(1)).await?;
+    assert!(!node.get_message(&hash).await?.unwrap().simulated);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_authenticated_mode_refuses_unsigned() -> Result<()> {
+    let node = ChaincraftNode::builder()
+        .with_mode(NodeMode::Authenticated)
+        .build()?;
+
+    let err = node.submit_message(precommit(1)).await;
+    assert!(matches!(err, Err(ChaincraftError::Crypto(CryptoError::MissingSignature))));
+
+    let mut forged = signed(2)?;
+    forged.signature = Some(vec![0; 64]);
+    let err = node.receive_message(node.id(), forged).await;
+    assert!(matches!(err, Err(ChaincraftError::Crypto(CryptoError::InvalidSignature))));
+    assert_eq!(node.metrics.get("unsigned_rejected"), 2);
+
+    let hash = node.receive_message(node.id(), signed(3)?).await?;
+    assert!(!node.get_message(&hash).await?.unwrap().simulated);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_every_mode_refuses_bad_signatures() -> Result<()> {
+    for mode in [NodeMode::Permissive, NodeMode::Simulation] {
+        let node = ChaincraftNode::builder().with_mode(mode).build()?;
+        let mut forged = signed(1)?;
+        forged.signature = Some(vec![0; 64]);
+        let err = node.receive_message(node.id(), forged).await;
+        assert!(matches!(err, Err(ChaincraftError::Crypto(CryptoError::InvalidSignature))));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulation_mode_tags_unsigned_messages() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_mode(NodeMode::Simulation)
+        .build()?;
+    let fake = node.submit_message(precommit(1)).await?;
+    let real = node.submit_message(signed(2)?).await?;
+    let created = node
+        .create_shared_message_with_data(json!({ "type": "precommit", "round": 3 }))
+        .await?;
+    assert_eq!(node.metrics.get("messages_simulated"), 2);
+
+    // Tags survive a reload from storage
+    node.message_cache.clear();
+    let messages = node
+        .query_messages(&MessageFilter::new().of_type(MessageType::Custom("precommit".into())))
+        .await?;
+    let tags: Vec<(String, bool)> = messages
+        .into_iter()
+        .map(|m| (m.hash, m.simulated))
+        .collect();
+    assert_eq!(tags, vec![(fake.clone(), true), (real, false), (created, true)]);
+
+    // The tag is local and never sent to peers
+    let json = node.get_object(&fake).await?;
+    assert!(!SharedMessage::from_json(&json)?.simulated);
+    assert_eq!(node.get_state().await?["mode"], json!("simulation"));
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use chaincraft_rust::{
+    examples::randomness_beacon::{BeaconConfig, RandomnessBeaconObject, SlashingConfig},
+    shared_object::{ApplicationObjectRegistry, ConfigurableObject},
+    ChaincraftError, ChaincraftNode, Result,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_nodes_build_objects_from_the_same_config_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("beacon.json");
+    let settings = json!({
+        "round_duration_secs": 5,
+        "threshold": 2,
+        "liveness": { "window": 10, "max_missed_fraction": 0.5, "jail_cooldown": 3 },
+        "slashing": { "slash_percent": 250, "evidence_max_age": 8, "appeal_rounds": 2 }
+    });
+    std::fs::write(&path, serde_json::to_vec_pretty(&settings)?)?;
+
+    for _ in 0..2 {
+        let node = ChaincraftNode::builder().build()?;
+        let config = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        let id = node
+            .handle()
+            .register_object_with_config::<RandomnessBeaconObject>(config)
+            .await?;
+        let beacon = node.get_typed::<RandomnessBeaconObject>(&id).await.unwrap();
+        assert_eq!(beacon.round_duration_secs, 5);
+        assert_eq!(beacon.threshold, 2);
+        assert!(beacon.liveness.is_some());
+        assert!(beacon.epoch_length.is_none());
+        // Settings go through the same checks as the builder methods
+        assert_eq!(beacon.slashing, SlashingConfig::new(100, 8, 2));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_object_config_is_refused() -> Result<()> {
+    let node = ChaincraftNode::builder().build()?;
+    let typo = json!({ "round_duration": 5 });
+    let error = node
+        .register_object_with_config::<RandomnessBeaconObject>(typo)
+        .await
+        .unwrap_err();
+    assert!(matches!(&error, ChaincraftError::Config(msg) if msg.contains("round_duration")));
+    assert_eq!(node.shared_object_count().await, 0);
+
+    // Missing settings take their defaults
+    let mut registry = ApplicationObjectRegistry::new();
+    let id = registry
+        .register_with_config::<RandomnessBeaconObject>(json!({ "rounds_per_epoch": 4 }))?;
+    let beacon = registry.get_typed::<RandomnessBeaconObject>(&id).unwrap();
+    assert_eq!(beacon.round_duration_secs, BeaconConfig::default().round_duration_secs);
+    assert_eq!(beacon.epoch_length, Some(4));
+    assert!(RandomnessBeaconObject::from_config_value(json!("fast")).is_err());
+    Ok(())
+}
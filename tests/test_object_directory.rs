@@ -0,0 +1,356 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    crypto::ecdsa::ECDSASigner,
+    directory::ObjectDirectory,
+    examples::randomness_beacon::{helpers, BeaconValidator, RandomnessBeaconObject},
+    shared::{DigestAccumulator, MessageType, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Sealed-bid auction breaking ties between equal bids with beacon randomness
+#[derive(Debug, Clone)]
+struct Auction {
+    id: SharedObjectId,
+    bids: Vec<(String, u64)>,
+    winner: Option<String>,
+    randomness: Option<String>,
+    directory: Option<ObjectDirectory>,
+    accumulator: DigestAccumulator,
+}
+
+impl Auction {
+    fn new() -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            bids: Vec::new(),
+            winner: None,
+            randomness: None,
+            directory: None,
+            accumulator: DigestAccumulator::new(),
+        }
+    }
+
+    async fn close(&mut self) -> Result<ApplyOutcome> {
+        let Some(directory) = &self.directory else {
+            return Ok(ApplyOutcome::rejected("detached", "auction is not registered"));
+        };
+        let Some(beacon) = directory.find_by_type("RandomnessBeacon").await else {
+            return Ok(ApplyOutcome::ignored("no_beacon", "no randomness beacon registered"));
+        };
+        let Some(randomness) = beacon.state["latest_randomness"].as_str() else {
+            return Ok(ApplyOutcome::ignored("no_randomness", "beacon has not finalized a round"));
+        };
+
+        let best = self
+            .bids
+            .iter()
+            .map(|(_, amount)| *amount)
+            .max()
+            .unwrap_or(0);
+        let tied: Vec<&String> = self
+            .bids
+            .iter()
+            .filter(|(_, amount)| *amount == best)
+            .map(|(bidder, _)| bidder)
+            .collect();
+        let draw = u64::from_str_radix(&randomness[..16], 16).unwrap();
+        self.winner = Some(tied[(draw % tied.len() as u64) as usize].clone());
+        self.randomness = Some(randomness.to_string());
+        Ok(ApplyOutcome::Applied)
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for Auction {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Auction"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.get("auction").is_some())
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        let outcome = match message.data["auction"].as_str() {
+            Some("bid") => {
+                let bidder = message.data["bidder"].as_str().unwrap_or_default();
+                let amount = message.data["amount"].as_u64().unwrap_or_default();
+                self.bids.push((bidder.to_string(), amount));
+                ApplyOutcome::Applied
+            },
+            Some("close") => self.close().await?,
+            _ => ApplyOutcome::rejected("unknown_action", "expected bid or close"),
+        };
+        if outcome.is_applied() {
+            self.accumulator.apply(&message);
+        }
+        Ok(outcome)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.accumulator.digest().to_string())
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn gossip_messages(&self, digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(self.accumulator.gossip(digest))
+    }
+
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "bids": self.bids.len(), "winner": self.winner }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.bids.clear();
+        self.winner = None;
+        Ok(())
+    }
+
+    fn attach_directory(&mut self, directory: ObjectDirectory) {
+        self.directory = Some(directory);
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+fn auction(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("auction".to_string()), data)
+}
+
+fn beacon_message(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("beacon".to_string()), data)
+}
+
+#[tokio::test]
+async fn test_auction_draws_tie_break_from_beacon() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let mut beacon = RandomnessBeaconObject::new(60, 1)?;
+    let v1 = ECDSASigner::new()?;
+    beacon.register_validator(BeaconValidator {
+        address: "v1".to_string(),
+        public_key: v1.get_public_key_pem()?,
+        vrf_key: v1.get_public_key_pem()?,
+        stake: 100,
+        active: true,
+        last_participation: None,
+    })?;
+    let beacon_id = node.add_shared_object(Box::new(beacon)).await?;
+    let auction_id = node.add_shared_object(Box::new(Auction::new())).await?;
+
+    let directory = node.object_directory().await;
+    assert_eq!(directory.ids_by_type("Auction").await, vec![auction_id.clone()]);
+    assert_eq!(directory.state(&beacon_id).await?["latest_randomness"], Value::Null);
+
+    for (bidder, amount) in [("alice", 30), ("bob", 50), ("carol", 50)] {
+        node.submit_message(auction(
+            json!({ "auction": "bid", "bidder": bidder, "amount": amount }),
+        ))
+        .await?;
+    }
+    // Nothing to draw from before the beacon finalizes a round
+    node.submit_message(auction(json!({ "auction": "close", "attempt": 1 })))
+        .await?;
+    assert_eq!(directory.state(&auction_id).await?["winner"], Value::Null);
+
+    node.submit_message(beacon_message(helpers::create_vrf_proof_message(
+        1,
+        "seed".to_string(),
+        "v1".to_string(),
+        &v1,
+    )?))
+    .await?;
+    node.submit_message(beacon_message(helpers::create_partial_signature_message(
+        1,
+        "v1".to_string(),
+        "partial".to_string(),
+        &v1,
+    )?))
+    .await?;
+    let randomness = node
+        .with_typed::<RandomnessBeaconObject, _>(&beacon_id, |b| b.get_latest_randomness())
+        .await
+        .flatten()
+        .unwrap();
+    assert_eq!(directory.state(&beacon_id).await?["latest_randomness"], json!(randomness));
+    assert_eq!(
+        directory.digest(&beacon_id).await?,
+        node.with_typed::<RandomnessBeaconObject, _>(&beacon_id, |b| b
+            .accumulator
+            .digest()
+            .to_string())
+            .await
+            .unwrap()
+    );
+
+    node.submit_message(auction(json!({ "auction": "close", "attempt": 2 })))
+        .await?;
+    let draw = u64::from_str_radix(&randomness[..16], 16).unwrap();
+    let expected = ["bob", "carol"][(draw % 2) as usize];
+    let (winner, used) = node
+        .with_typed::<Auction, _>(&auction_id, |a| (a.winner.clone(), a.randomness.clone()))
+        .await
+        .unwrap();
+    assert_eq!(winner.as_deref(), Some(expected));
+    assert_eq!(used, Some(randomness));
+    assert_eq!(directory.state(&auction_id).await?["winner"], json!(expected));
+    Ok(())
+}
+
+/// Reads its peer's and its own published state on every message
+#[derive(Debug, Clone)]
+struct Mirror {
+    id: SharedObjectId,
+    peer: Option<SharedObjectId>,
+    seen: Vec<Value>,
+    directory: Option<ObjectDirectory>,
+}
+
+#[async_trait]
+impl ApplicationObject for Mirror {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Mirror"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.get("mirror").is_some())
+    }
+
+    async fn add_message(&mut self, _message: SharedMessage) -> Result<ApplyOutcome> {
+        let directory = self.directory.as_ref().unwrap();
+        let peer = directory.state(self.peer.as_ref().unwrap()).await?;
+        let own = directory.state(&self.id).await?;
+        self.seen
+            .push(json!({ "peer": peer["seen"], "own": own["seen"] }));
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.seen.len().to_string())
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "seen": self.seen.len() }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.seen.clear();
+        Ok(())
+    }
+
+    fn attach_directory(&mut self, directory: ObjectDirectory) {
+        self.directory = Some(directory);
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn test_objects_reading_each_other_do_not_deadlock() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let (first, second) = (SharedObjectId::new(), SharedObjectId::new());
+    for (id, peer) in [(&first, &second), (&second, &first)] {
+        node.add_shared_object(Box::new(Mirror {
+            id: id.clone(),
+            peer: Some(peer.clone()),
+            seen: Vec::new(),
+            directory: None,
+        }))
+        .await?;
+    }
+
+    for round in 0..2 {
+        let message = SharedMessage::new(
+            MessageType::Custom("mirror".to_string()),
+            json!({ "mirror": round }),
+        );
+        tokio::time::timeout(Duration::from_secs(5), node.submit_message(message))
+            .await
+            .expect("objects reading each other must not deadlock")?;
+    }
+
+    // Each object saw its own state before applying and the other's as last published
+    let seen = |id: SharedObjectId| {
+        let node = &node;
+        async move {
+            node.with_typed::<Mirror, _>(&id, |m| m.seen.clone())
+                .await
+                .unwrap()
+        }
+    };
+    let (a, b) = (seen(first.clone()).await, seen(second.clone()).await);
+    for (i, entry) in a.iter().chain(&b).enumerate() {
+        assert_eq!(entry["own"], json!(i % 2));
+    }
+    assert_eq!(a.len() + b.len(), 4);
+    let directory = node.object_directory().await;
+    assert_eq!(directory.state(&first).await?["seen"], json!(2));
+
+    // Removed objects disappear from the directory
+    assert!(node.remove_shared_object(&first).await?);
+    assert!(directory.state(&first).await.is_err());
+    assert_eq!(directory.ids_by_type("Mirror").await, vec![second.clone()]);
+
+    // Objects changed in place are republished
+    node.with_typed_mut::<Mirror, _>(&second, |m| m.seen.push(Value::Null))
+        .await;
+    assert_eq!(directory.state(&second).await?["seen"], json!(3));
+    Ok(())
+}
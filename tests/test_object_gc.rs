@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    events::{NodeEvent, RemovalReason},
+    shared::{SharedMessage, SharedObjectId},
+    ApplicationObject, ApplicationObjectRegistry, ApplyOutcome, ChaincraftNode, Result,
+    SimpleSharedNumber,
+};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Object recording whether its delete hook ran
+#[derive(Debug, Clone)]
+struct Tracked {
+    id: SharedObjectId,
+    deleted: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl ApplicationObject for Tracked {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+    fn type_name(&self) -> &'static str {
+        "Tracked"
+    }
+    async fn is_valid(&self, _message: &SharedMessage) -> Result<bool> {
+        Ok(true)
+    }
+    async fn add_message(&mut self, _message: SharedMessage) -> Result<ApplyOutcome> {
+        Ok(ApplyOutcome::Applied)
+    }
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(String::new())
+    }
+    async fn has_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(false)
+    }
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+    async fn add_digest(&mut self, _digest: String) -> Result<bool> {
+        Ok(true)
+    }
+    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({"tracked": true}))
+    }
+    async fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+    async fn on_delete(&mut self) -> Result<()> {
+        self.deleted.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn test_deregister_runs_hook_and_clears_indexes() -> Result<()> {
+    let deleted = Arc::new(AtomicBool::new(false));
+    let mut registry = ApplicationObjectRegistry::new();
+    let id = registry.register(Box::new(Tracked {
+        id: SharedObjectId::new(),
+        deleted: deleted.clone(),
+    }));
+    assert!(registry.last_active(&id).is_some());
+
+    assert!(registry.deregister(&id).await?.is_some());
+    assert!(deleted.load(Ordering::SeqCst));
+    assert!(registry.get_by_type("Tracked").is_empty());
+    assert!(registry.last_active(&id).is_none());
+    assert!(registry.deregister(&id).await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remove_shared_object_persists_snapshot_and_emits_event() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    node.create_shared_message_with_data(json!(7)).await?;
+    let mut events = node.subscribe();
+
+    assert!(node.remove_shared_object(&id).await?);
+    assert_eq!(node.shared_object_count().await, 0);
+    assert!(!node.remove_shared_object(&id).await?);
+
+    let snapshot = node.object_snapshot(&id).await?.expect("snapshot stored");
+    assert_eq!(snapshot["type_name"], "SimpleSharedNumber");
+    assert_eq!(snapshot["state"]["number"], 7);
+    assert_eq!(
+        events.try_recv().unwrap(),
+        NodeEvent::ObjectRemoved {
+            id,
+            type_name: "SimpleSharedNumber".to_string(),
+            reason: RemovalReason::Deregistered,
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_idle_objects_are_collected() -> Result<()> {
+    let node = ChaincraftNode::builder()
+        .with_object_idle_timeout(Duration::from_millis(50))
+        .build()?;
+    let deleted = Arc::new(AtomicBool::new(false));
+    let idle = node
+        .add_shared_object(Box::new(Tracked {
+            id: SharedObjectId::new(),
+            deleted: deleted.clone(),
+        }))
+        .await?;
+    assert!(node.collect_idle_objects().await?.is_empty());
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let fresh = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let mut events = node.subscribe();
+
+    assert_eq!(node.collect_idle_objects().await?, vec![idle.clone()]);
+    assert!(deleted.load(Ordering::SeqCst));
+    assert_eq!(node.shared_object_count().await, 1);
+    assert!(node.app_objects.read().await.get(&fresh).is_some());
+    assert!(matches!(
+        events.try_recv().unwrap(),
+        NodeEvent::ObjectRemoved {
+            reason: RemovalReason::Idle,
+            ..
+        }
+    ));
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    events::{NodeEvent, RemovalReason},
+    shared::{MessageType, SharedMessage, SharedObjectId},
+    shared_object::{
+        load_versioned_state, ApplicationObject, ApplicationObjectRegistry, ApplyOutcome,
+        SimpleSharedNumber,
+    },
+    ChaincraftError, ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+
+/// Tally whose version 1 kept `{"count": n}` and added numbers as they came;
+/// version 2 keeps `{"total": n}` and counts every number twice
+#[derive(Debug, Clone)]
+struct Tally {
+    id: SharedObjectId,
+    version: u32,
+    total: i64,
+}
+
+impl Tally {
+    fn v1() -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            version: 1,
+            total: 0,
+        }
+    }
+
+    fn v2() -> Self {
+        Self {
+            version: 2,
+            ..Self::v1()
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for Tally {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Tally"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.is_i64())
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        let value = message.data.as_i64().unwrap_or_default();
+        self.total += if self.version == 1 { value } else { 2 * value };
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.total.to_string())
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(match self.version {
+            1 => json!({ "count": self.total }),
+            _ => json!({ "total": self.total }),
+        })
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.total = 0;
+        Ok(())
+    }
+
+    fn schema_version(&self) -> u32 {
+        self.version
+    }
+
+    fn migrate(&self, _from_version: u32, state: Value) -> Result<Value> {
+        Ok(json!({ "total": state["count"] }))
+    }
+
+    async fn load_state(&mut self, state: Value) -> Result<()> {
+        let field = if self.version == 1 { "count" } else { "total" };
+        self.total = state[field]
+            .as_i64()
+            .ok_or_else(|| ChaincraftError::validation(format!("{} must be an integer", field)))?;
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+fn number(value: i64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("NUMBER".to_string()), json!(value))
+}
+
+#[tokio::test]
+async fn test_replacement_takes_over_with_migrated_state() -> Result<()> {
+    let mut node = ChaincraftNode::builder().build()?;
+    let old_id = node.add_shared_object(Box::new(Tally::v1())).await?;
+    node.create_shared_message_with_data(json!(3)).await?;
+    let mut events = node.subscribe();
+
+    let new_id = node.replace_object(&old_id, Box::new(Tally::v2())).await?;
+    node.create_shared_message_with_data(json!(2)).await?;
+    let directory = node.object_directory().await;
+    assert_eq!(directory.state(&new_id).await?["total"], json!(7));
+    assert!(directory.state(&old_id).await.is_err());
+    assert_eq!(node.shared_object_count().await, 1);
+    assert_eq!(node.metrics().get("objects_replaced"), 1);
+
+    let snapshot = node.object_snapshot(&old_id).await?.unwrap();
+    assert_eq!(snapshot["schema_version"], json!(1));
+    let events: Vec<_> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+    assert!(events.contains(&NodeEvent::ObjectRemoved {
+        id: old_id.clone(),
+        type_name: "Tally".to_string(),
+        reason: RemovalReason::Replaced,
+    }));
+    assert!(events.contains(&NodeEvent::ObjectReplaced {
+        old_id,
+        new_id,
+        replayed: 0,
+    }));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_messages_during_swap_go_to_the_replacement() -> Result<()> {
+    let mut registry = ApplicationObjectRegistry::new();
+    let id = registry.register(Box::new(Tally::v1()));
+    registry.execute_message(number(3)).await?;
+
+    let (version, state) = registry.begin_swap(&id, "Tally").await?;
+    let err = registry.begin_swap(&id, "Tally").await.unwrap_err();
+    assert!(err.to_string().contains("already being replaced"));
+
+    // Held back rather than applied to the old logic
+    let receipt = registry.execute_message(number(5)).await?;
+    assert!(receipt.outcomes.is_empty());
+
+    let mut replacement = Tally::v2();
+    load_versioned_state(&mut replacement, version, state).await?;
+    let (old, receipts) = registry.finish_swap(&id, Box::new(replacement)).await?;
+    assert_eq!(old.get_state().await?, json!({ "count": 3 }));
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].outcomes[0].1, ApplyOutcome::Applied);
+
+    let new_id = registry.ids().remove(0);
+    assert_eq!(registry.directory().state(&new_id).await?, json!({ "total": 13 }));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_migration_keeps_the_old_object() -> Result<()> {
+    let mut node = ChaincraftNode::builder().build()?;
+    let id = node.add_shared_object(Box::new(Tally::v2())).await?;
+
+    // Version 1 cannot load state saved by version 2
+    let err = node.replace_object(&id, Box::new(Tally::v1())).await;
+    assert!(matches!(err, Err(ChaincraftError::SchemaVersion { .. })));
+    let err = node
+        .replace_object(&id, Box::new(SimpleSharedNumber::new()))
+        .await;
+    assert!(matches!(err, Err(ChaincraftError::Config(_))));
+
+    node.create_shared_message_with_data(json!(4)).await?;
+    let state = node.object_directory().await.state(&id).await?;
+    assert_eq!(state["total"], json!(8));
+
+    // An aborted swap hands queued messages to the old object
+    let mut registry = ApplicationObjectRegistry::new();
+    let id = registry.register(Box::new(Tally::v1()));
+    registry.begin_swap(&id, "Tally").await?;
+    registry.execute_message(number(6)).await?;
+    let receipts = registry.abort_swap(&id).await?;
+    assert_eq!(receipts[0].outcomes[0].0, id);
+    assert_eq!(registry.directory().state(&id).await?["count"], json!(6));
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use chaincraft_rust::{
+    shared::SharedObjectId, shared_object::SimpleSharedNumber, ChaincraftNode, Result,
+};
+use std::collections::HashMap;
+
+#[test]
+fn test_object_ids_parse_and_shorten() {
+    let id = SharedObjectId::new();
+    let full = id.to_string();
+    assert_eq!(full.parse::<SharedObjectId>().unwrap(), id);
+    assert_eq!(SharedObjectId::try_from(full.as_str()).unwrap(), id);
+    assert_eq!(id.short().len(), SharedObjectId::SHORT_LEN);
+    assert!(full.starts_with(&id.short()));
+    assert_eq!(format!("{:?}", id), format!("SharedObjectId({})", id.short()));
+    assert!(id.short().parse::<SharedObjectId>().is_err());
+    assert!("not-an-id".parse::<SharedObjectId>().is_err());
+}
+
+#[tokio::test]
+async fn test_short_ids_resolve_against_the_registry() -> Result<()> {
+    let node = ChaincraftNode::default();
+    // Seventeen ids cannot all start with a different hex digit
+    let mut ids = Vec::new();
+    for _ in 0..17 {
+        ids.push(
+            node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+                .await?,
+        );
+    }
+
+    for id in &ids {
+        assert_eq!(node.resolve_object_id(&id.short()).await?, *id);
+        assert_eq!(node.resolve_object_id(&id.to_string()).await?, *id);
+    }
+    let handle = node.handle();
+    assert_eq!(handle.resolve_object_id(&ids[0].short()).await?, ids[0]);
+
+    let mut by_digit: HashMap<String, usize> = HashMap::new();
+    for id in &ids {
+        *by_digit.entry(id.short()[..1].to_string()).or_default() += 1;
+    }
+    let (shared, count) = by_digit.into_iter().find(|(_, count)| *count > 1).unwrap();
+    let error = node.resolve_object_id(&shared).await.unwrap_err();
+    assert!(error.to_string().contains("ambiguous"), "{}", error);
+    assert!(error
+        .to_string()
+        .contains(&format!("{} objects match", count)));
+
+    let stranger = SharedObjectId::new();
+    assert!(node.resolve_object_id(&stranger.to_string()).await.is_err());
+    assert!(node.resolve_object_id("xyz").await.is_err());
+    Ok(())
+}
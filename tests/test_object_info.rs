@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    shared::{SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, ObjectInfo},
+    storage::{MemoryStorage, Storage},
+    ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Sums positive numbers and rejects the others
+#[derive(Debug, Clone)]
+struct Tally {
+    id: SharedObjectId,
+    total: i64,
+}
+
+impl Tally {
+    fn with_id(id: &SharedObjectId) -> Self {
+        Self {
+            id: id.clone(),
+            total: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for Tally {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Tally"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.get("tally").is_some_and(Value::is_i64))
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        match message.data["tally"].as_i64() {
+            Some(n) if n > 0 => {
+                self.total += n;
+                Ok(ApplyOutcome::Applied)
+            },
+            _ => Ok(ApplyOutcome::rejected("not_positive", "tally must be positive")),
+        }
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(format!("total-{}", self.total))
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "total": self.total }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.total = 0;
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+fn node_on(storage: Arc<dyn Storage>) -> Result<ChaincraftNode> {
+    ChaincraftNode::builder().with_storage(storage).build()
+}
+
+#[tokio::test]
+async fn test_object_info_counts_outcomes_and_survives_a_restart() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let id = SharedObjectId::new();
+    {
+        let mut node = node_on(storage.clone())?;
+        node.add_shared_object(Box::new(Tally::with_id(&id)))
+            .await?;
+        assert_eq!(node.object_info(&id).await, Some(ObjectInfo::default()));
+
+        node.create_shared_message_with_data(json!({ "tally": 2 }))
+            .await?;
+        node.create_shared_message_with_data(json!({ "tally": 3 }))
+            .await?;
+        assert!(node
+            .create_shared_message_with_data(json!({ "tally": -1 }))
+            .await
+            .is_err());
+        // Messages the object does not accept are not counted
+        node.create_shared_message_with_data(json!({ "other": 1 }))
+            .await?;
+
+        let info = node.object_info(&id).await.unwrap();
+        assert_eq!((info.applied, info.rejected), (2, 1));
+        assert_eq!(info.last_digest.as_deref(), Some("total-5"));
+        assert!(info.last_applied.is_some());
+        assert!(node.object_info(&SharedObjectId::new()).await.is_none());
+    }
+
+    // Counters carry on for an object registered again under the same id
+    let mut node = node_on(storage)?;
+    node.add_shared_object(Box::new(Tally::with_id(&id)))
+        .await?;
+    let restored = node.object_info(&id).await.unwrap();
+    assert_eq!((restored.applied, restored.rejected), (2, 1));
+    node.create_shared_message_with_data(json!({ "tally": 4 }))
+        .await?;
+    let info = node.handle().object_info(&id).await.unwrap();
+    assert_eq!(info.applied, 3);
+    assert!(info.last_applied >= restored.last_applied);
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn test_object_info_over_grpc() -> Result<()> {
+    use chaincraft_rust::network::grpc::{GrpcClient, GrpcTransport};
+
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(Tally::with_id(&SharedObjectId::new())))
+        .await?;
+    node.create_shared_message_with_data(json!({ "tally": 7 }))
+        .await?;
+    let server = GrpcTransport::bind_node(&node, "127.0.0.1:0".parse().unwrap()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+
+    let info = client.object_info(&id).await?;
+    assert_eq!(Some(info), node.object_info(&id).await);
+    assert!(client.object_info(&SharedObjectId::new()).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    shared::{MessageType, ObjectMessageLog, SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, HistoryCursor},
+    ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+
+/// Counter relying on the default gossip and catch-up backed by its log
+#[derive(Debug, Clone)]
+struct Counter {
+    id: SharedObjectId,
+    count: u64,
+    log: ObjectMessageLog,
+}
+
+impl Counter {
+    fn new(log: ObjectMessageLog) -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            count: 0,
+            log,
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for Counter {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Counter"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.is_u64())
+    }
+
+    async fn add_message(&mut self, _message: SharedMessage) -> Result<ApplyOutcome> {
+        self.count += 1;
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(format!("count-{}", self.count))
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn message_log(&self) -> Option<&ObjectMessageLog> {
+        Some(&self.log)
+    }
+
+    fn message_log_mut(&mut self) -> Option<&mut ObjectMessageLog> {
+        Some(&mut self.log)
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "count": self.count }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.count = 0;
+        self.log.clear();
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+fn data(messages: &[SharedMessage]) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|message| message.data.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_default_catch_up_serves_from_the_registry_log() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(Counter::new(ObjectMessageLog::new())))
+        .await?;
+    for n in 0..5u64 {
+        node.create_shared_message_with_data(json!(n)).await?;
+    }
+
+    let since = node.messages_since_digest(&id, "count-2").await?;
+    assert_eq!(data(&since), [json!(2), json!(3), json!(4)]);
+    let all = node.messages_since_digest(&id, "count-0").await?;
+    assert_eq!(all.len(), 5);
+    assert!(node.messages_since_digest(&id, "count-5").await?.is_empty());
+    assert!(node.messages_since_digest(&id, "unknown").await.is_err());
+
+    let gossip = node
+        .with_typed(&id, |counter: &Counter| counter.log.gossip(Some("count-3")))
+        .await
+        .unwrap();
+    assert_eq!(data(&gossip), [json!(3), json!(4)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retention_forgets_old_digests() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(Counter::new(ObjectMessageLog::with_retention(2))))
+        .await?;
+    for n in 0..5u64 {
+        node.create_shared_message_with_data(json!(n)).await?;
+    }
+
+    let error = node
+        .messages_since_digest(&id, "count-1")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("older than the 2 retained"), "{}", error);
+    let since = node.messages_since_digest(&id, "count-3").await?;
+    assert_eq!(data(&since), [json!(3), json!(4)]);
+    assert_eq!(data(&node.messages_since_digest(&id, "count-4").await?), [json!(4)]);
+    Ok(())
+}
+
+#[test]
+fn test_log_keeps_positions_across_pruning() {
+    let mut log = ObjectMessageLog::with_retention(3);
+    for n in 0..10u64 {
+        let message = SharedMessage::new(MessageType::Custom("n".to_string()), json!(n));
+        log.record(&format!("d{}", n), &format!("d{}", n + 1), message);
+    }
+    assert_eq!(log.len(), 3);
+    assert!(!log.contains("d6"));
+    assert!(log.contains("d7"));
+    assert_eq!(data(&log.messages_since("d7").unwrap()), [json!(7), json!(8), json!(9)]);
+    assert_eq!(log.gossip(Some("d2")).len(), 3);
+
+    log.clear();
+    assert!(log.is_empty());
+    assert_eq!(log.retention(), Some(3));
+}
+
+#[tokio::test]
+async fn test_history_pages_back_to_the_oldest_retained_message() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(Counter::new(ObjectMessageLog::with_retention(5))))
+        .await?;
+    for n in 0..7u64 {
+        node.create_shared_message_with_data(json!(n)).await?;
+    }
+
+    let (page, next) = node.object_history(&id, None, 2).await.unwrap();
+    let sequences: Vec<u64> = page.iter().map(|entry| entry.sequence).collect();
+    assert_eq!(sequences, [7, 6]);
+    assert_eq!(page[0].digest, "count-7");
+    assert!(page[0].outcome.is_applied());
+    assert_eq!(next, Some(HistoryCursor(6)));
+
+    let (page, next) = node.object_history(&id, next, 2).await.unwrap();
+    assert_eq!(page.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), [5, 4]);
+    // Messages 1 and 2 are past the retention, so the last page is short
+    let (page, next) = node.object_history(&id, next, 2).await.unwrap();
+    assert_eq!(page.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), [3]);
+    assert_eq!(next, None);
+
+    let (all, next) = node.handle().object_history(&id, None, 100).await.unwrap();
+    assert_eq!(all.len(), 5);
+    assert_eq!(next, None);
+    assert!(node
+        .object_history(&SharedObjectId::new(), None, 10)
+        .await
+        .is_none());
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn test_history_is_served_over_grpc() -> Result<()> {
+    use chaincraft_rust::network::grpc::{GrpcClient, GrpcTransport};
+
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(Counter::new(ObjectMessageLog::new())))
+        .await?;
+    for n in 0..3u64 {
+        node.create_shared_message_with_data(json!(n)).await?;
+    }
+
+    let server = GrpcTransport::bind_node(&node, "127.0.0.1:0".parse().unwrap()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+    let (remote, next) = client.object_history(&id, None, 2).await?;
+    let (local, local_next) = node.object_history(&id, None, 2).await.unwrap();
+    assert_eq!(remote, local);
+    assert_eq!(next, local_next);
+    let (rest, next) = client.object_history(&id, next, 2).await?;
+    assert_eq!(rest.len(), 1);
+    assert_eq!(next, None);
+    assert!(client
+        .object_history(&SharedObjectId::new(), None, 2)
+        .await
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    shared::{SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, SimpleSharedNumber},
+    testkit::ObjectHarness,
+    ChaincraftError, ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+
+/// Click counter whose state was `{"count": n}` in version 1
+#[derive(Debug, Clone)]
+struct Clicks {
+    id: SharedObjectId,
+    total: i64,
+}
+
+impl Clicks {
+    fn new() -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            total: 0,
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for Clicks {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Clicks"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.is_i64())
+    }
+
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        self.total += message.data.as_i64().unwrap_or_default();
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.total.to_string())
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "total": self.total, "unit": "clicks" }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.total = 0;
+        Ok(())
+    }
+
+    fn schema_version(&self) -> u32 {
+        2
+    }
+
+    fn migrate(&self, from_version: u32, state: Value) -> Result<Value> {
+        assert_eq!(from_version, 1);
+        Ok(json!({ "total": state["count"], "unit": "clicks" }))
+    }
+
+    async fn load_state(&mut self, state: Value) -> Result<()> {
+        self.total = state["total"]
+            .as_i64()
+            .ok_or_else(|| ChaincraftError::validation("total must be an integer"))?;
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn test_old_state_is_migrated_on_load() -> Result<()> {
+    let mut harness = ObjectHarness::new(Clicks::new());
+    harness.load_state(1, json!({ "count": 3 })).await?;
+    harness.assert_state("total", json!(3)).await;
+    harness.apply(json!(2)).await?;
+    harness
+        .assert_state("$", json!({ "total": 5, "unit": "clicks" }))
+        .await;
+
+    // Current state loads as is
+    harness
+        .load_state(2, json!({ "total": 9, "unit": "clicks" }))
+        .await?;
+    harness.assert_state("total", json!(9)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_version_errors_say_what_to_implement() {
+    let mut harness = ObjectHarness::new(Clicks::new());
+    let err = harness.load_state(3, json!({})).await.unwrap_err();
+    assert!(matches!(
+        err,
+        ChaincraftError::SchemaVersion {
+            found: 3,
+            expected: 2,
+            ..
+        }
+    ));
+
+    let mut harness = ObjectHarness::new(SimpleSharedNumber::new());
+    let err = harness
+        .load_state(1, json!({ "number": 1 }))
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("implement SimpleSharedNumber::load_state"));
+}
+
+#[tokio::test]
+async fn test_node_restores_snapshots_across_versions() -> Result<()> {
+    let mut node = ChaincraftNode::builder().build()?;
+    let id = node.add_shared_object(Box::new(Clicks::new())).await?;
+    node.create_shared_message_with_data(json!(4)).await?;
+    node.remove_shared_object(&id).await?;
+
+    let snapshot = node.object_snapshot(&id).await?.unwrap();
+    assert_eq!(snapshot["schema_version"], json!(2));
+    let restored = node
+        .restore_shared_object(Box::new(Clicks::new()), &snapshot)
+        .await?;
+    let state = node.object_directory().await.state(&restored).await?;
+    assert_eq!(state["total"], json!(4));
+
+    // Snapshots from before schema versions count as version 1
+    let legacy = json!({ "type_name": "Clicks", "state": { "count": 7 } });
+    let restored = node
+        .restore_shared_object(Box::new(Clicks::new()), &legacy)
+        .await?;
+    let state = node.object_directory().await.state(&restored).await?;
+    assert_eq!(state["total"], json!(7));
+
+    let err = node
+        .restore_shared_object(Box::new(SimpleSharedNumber::new()), &legacy)
+        .await;
+    assert!(matches!(err, Err(ChaincraftError::Config(_))));
+    Ok(())
+}
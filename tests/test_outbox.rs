@@ -0,0 +1,128 @@
+use chaincraft_rust::{
+    network::transport::{InMemoryNetwork, Transport},
+    outbox::{Outbox, OutboxConfig},
+    shared::{MessageType, SharedMessage},
+    storage::{MemoryStorage, Storage},
+    ChaincraftNode, PeerId, PeerInfo, Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn node_on(storage: Arc<dyn Storage>, config: OutboxConfig) -> Result<ChaincraftNode> {
+    ChaincraftNode::builder()
+        .with_storage(storage)
+        .with_outbox(config)
+        .build()
+}
+
+#[tokio::test]
+async fn test_pending_messages_are_gossiped_after_a_restart() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let hash = {
+        let mut node = node_on(storage.clone(), OutboxConfig::default())?;
+        // Stored, then the node goes down before gossiping it
+        node.create_shared_message_with_data(json!({ "n": 1 }))
+            .await?
+    };
+
+    let node = node_on(storage, OutboxConfig::default())?;
+    let pending = node.outbox_pending().await?;
+    assert_eq!(pending.len(), 1);
+    assert!(pending[0].is_stored());
+
+    let network = InMemoryNetwork::new();
+    let transport = network.connect(node.id().clone());
+    let peer = network.connect(PeerId::new());
+    node.add_peer(PeerInfo::new(peer.local_id().clone(), "127.0.0.1:9001".parse().unwrap()))
+        .await?;
+
+    assert_eq!(node.flush_outbox(&transport).await?, 1);
+    let (from, received) = peer.recv().await?;
+    assert_eq!(&from, node.id());
+    assert_eq!(received.hash, hash);
+    // Sent is not delivered: the message stays until the peer confirms it
+    assert_eq!(node.outbox_pending().await?[0].attempts, 1);
+
+    assert!(node.confirm_delivery(peer.local_id(), &hash).await?);
+    assert!(node.outbox_pending().await?.is_empty());
+    assert_eq!(node.flush_outbox(&transport).await?, 0);
+    assert_eq!(node.metrics().get("outbox_delivered"), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_message_unstored_at_crash_is_stored_then_confirmed_by_acks() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let config = OutboxConfig {
+        required_confirmations: 2,
+        ..OutboxConfig::default()
+    };
+    let message = SharedMessage::new(MessageType::Custom("note".to_string()), json!(1));
+    Outbox::new(storage.clone(), config.clone())
+        .add(message.clone())
+        .await?;
+
+    let node = node_on(storage, config)?;
+    let network = InMemoryNetwork::new();
+    let transport = network.connect(node.id().clone());
+    let peers = [PeerId::new(), PeerId::new()];
+    for (port, peer_id) in [9001, 9002].into_iter().zip(&peers) {
+        let _endpoint = network.connect(peer_id.clone());
+        let address = format!("127.0.0.1:{}", port).parse().unwrap();
+        node.add_peer(PeerInfo::new(peer_id.clone(), address))
+            .await?;
+    }
+
+    node.flush_outbox(&transport).await?;
+    assert!(node.get_object(&message.hash).await.is_ok());
+    assert_eq!(node.metrics().get("outbox_recovered"), 1);
+
+    // A peer announcing any log length confirms nothing; acks do
+    node.record_peer_sequence(&peers[0], u64::MAX).await;
+    assert_eq!(node.outbox_pending().await?[0].confirmed_by.len(), 0);
+    assert!(!node.confirm_delivery(&peers[0], &message.hash).await?);
+    assert!(!node.confirm_delivery(&peers[0], &message.hash).await?);
+    assert_eq!(node.outbox_pending().await?.len(), 1);
+    assert!(node.confirm_delivery(&peers[1], &message.hash).await?);
+    assert!(node.outbox_pending().await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_started_node_retries_until_confirmed() -> Result<()> {
+    let config = OutboxConfig {
+        retry_interval: Duration::from_millis(20),
+        ..OutboxConfig::default()
+    };
+    let mut node = node_on(Arc::new(MemoryStorage::new()), config)?;
+    let network = InMemoryNetwork::new();
+    let peer = network.connect(PeerId::new());
+    node.add_peer(PeerInfo::new(peer.local_id().clone(), "127.0.0.1:9001".parse().unwrap()))
+        .await?;
+    node.start().await?;
+    let task = node
+        .spawn_outbox(Arc::new(network.connect(node.id().clone())))
+        .unwrap();
+
+    let hash = node
+        .create_shared_message_with_data(json!({ "n": 2 }))
+        .await?;
+    for _ in 0..2 {
+        let (_, received) = tokio::time::timeout(Duration::from_secs(1), peer.recv())
+            .await
+            .expect("outbox gossips the message")?;
+        assert_eq!(received.hash, hash);
+    }
+    node.handle()
+        .confirm_delivery(peer.local_id(), &hash)
+        .await?;
+    assert!(node.outbox_pending().await?.is_empty());
+
+    node.stop().await?;
+    tokio::time::timeout(Duration::from_secs(1), task)
+        .await
+        .expect("outbox task stops with the node")
+        .unwrap();
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    error::NetworkError,
+    network::access::{PeerAccess, PeerPattern, Refusal},
+    network::{PeerId, PeerInfo},
+    ChaincraftError, ChaincraftNode, Result,
+};
+use std::net::IpAddr;
+
+fn ip(address: &str) -> Option<IpAddr> {
+    Some(address.parse().unwrap())
+}
+
+#[test]
+fn test_patterns_match_ranges_and_keys() -> Result<()> {
+    let lab: PeerPattern = "10.1.0.0/16".parse()?;
+    assert!(lab.matches(ip("10.1.200.7"), None));
+    assert!(lab.matches(ip("::ffff:10.1.0.1"), None));
+    assert!(!lab.matches(ip("10.2.0.1"), None));
+    assert!(!lab.matches(None, None));
+
+    let single: PeerPattern = "192.168.1.5".parse()?;
+    assert_eq!(single.to_string(), "192.168.1.5/32");
+    let everyone: PeerPattern = "0.0.0.0/0".parse()?;
+    assert!(everyone.matches(ip("8.8.8.8"), None));
+    assert!(!everyone.matches(ip("fd00::1"), None));
+    let v6: PeerPattern = "fd00::/8".parse()?;
+    assert!(v6.matches(ip("fd12::1"), None));
+
+    let key: PeerPattern = "AB01".parse()?;
+    assert!(key.matches(None, Some("ab01")));
+    assert!(!key.matches(ip("10.1.0.1"), Some("ab02")));
+
+    for invalid in ["10.0.0.0/33", "host.example", "10.0.0.0/x", ""] {
+        assert!(invalid.parse::<PeerPattern>().is_err(), "{}", invalid);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_denylist_wins_and_private_networks_need_allowlist() -> Result<()> {
+    let open = PeerAccess::new().deny("10.0.0.66".parse()?);
+    assert_eq!(open.check(ip("10.0.0.66"), None), Err(Refusal::Denied));
+    assert_eq!(open.check(ip("10.0.0.67"), None), Ok(()));
+
+    let classroom = PeerAccess::new()
+        .allow("10.0.0.0/24".parse()?)
+        .allow("ab01".parse()?)
+        .deny("10.0.0.66".parse()?)
+        .private_network();
+    assert_eq!(classroom.check(ip("10.0.0.12"), None), Ok(()));
+    assert_eq!(classroom.check(ip("172.16.0.1"), Some("ab01")), Ok(()));
+    assert_eq!(classroom.check(ip("172.16.0.1"), None), Err(Refusal::NotAllowed));
+    assert_eq!(classroom.check(ip("10.0.0.66"), Some("ab01")), Err(Refusal::Denied));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_private_node_refuses_unlisted_peers() -> Result<()> {
+    let (_, public_key) = utils::generate_keypair(KeyType::Ed25519)?;
+    let access = PeerAccess::new()
+        .allow("127.0.0.0/8".parse()?)
+        .allow(public_key.to_hex().parse()?)
+        .private_network();
+    let node = ChaincraftNode::builder().with_peer_access(access).build()?;
+
+    node.add_peer(PeerInfo::new(PeerId::new(), "127.0.0.1:7000".parse().unwrap()))
+        .await?;
+    let known = PeerInfo::new(PeerId::new(), "198.51.100.4:7000".parse().unwrap())
+        .with_public_key(public_key.to_hex());
+    node.add_peer(known).await?;
+
+    let stranger = PeerInfo::new(PeerId::new(), "198.51.100.5:7000".parse().unwrap());
+    let err = node.add_peer(stranger).await;
+    assert!(matches!(err, Err(ChaincraftError::Network(NetworkError::PeerRefused { .. }))));
+    assert_eq!(node.get_peers().await.len(), 2);
+    assert_eq!(node.metrics.get("peers_refused"), 1);
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn test_private_node_refuses_handshake_without_allowlisted_key() -> Result<()> {
+    use chaincraft_rust::network::grpc::{proto, GrpcClient, GrpcTransport};
+
+    let (private_key, public_key) = utils::generate_keypair(KeyType::Ed25519)?;
+    let access = PeerAccess::new()
+        .allow(public_key.to_hex().parse()?)
+        .private_network();
+    let node = ChaincraftNode::builder().with_peer_access(access).build()?;
+    let server = GrpcTransport::bind_node(&node, "127.0.0.1:0".parse().unwrap()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+
+    let hello = proto::Hello::new(&PeerId::new(), "chaincraft", chrono::Utc::now());
+    assert!(client.handshake(hello.clone()).await.is_err());
+
+    // A claimed key without a matching signature is not enough
+    let mut forged = hello.clone();
+    forged.public_key = public_key.to_hex();
+    assert!(client.handshake(forged).await.is_err());
+
+    let signed = hello.signed(&private_key)?;
+    assert_eq!(signed.verified_key(), Some(public_key.to_hex().as_str()));
+    assert_eq!(client.handshake(signed).await?.node_id, node.id().to_string());
+    Ok(())
+}
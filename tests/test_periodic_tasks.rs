@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    clock::Clock,
+    shared::{SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    tasks::{TaskScheduler, TaskSpec},
+    ChaincraftError, ChaincraftNode, Result, RngProvider,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Announces itself every 10 seconds and sweeps on every minute
+#[derive(Debug, Clone)]
+struct Presence {
+    id: SharedObjectId,
+    heartbeats: u64,
+    sweeps: Vec<DateTime<Utc>>,
+    jitter: Duration,
+}
+
+impl Presence {
+    fn new(jitter: Duration) -> Self {
+        Self {
+            id: SharedObjectId::new(),
+            heartbeats: 0,
+            sweeps: Vec::new(),
+            jitter,
+        }
+    }
+}
+
+#[async_trait]
+impl ApplicationObject for Presence {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Presence"
+    }
+
+    async fn is_valid(&self, message: &SharedMessage) -> Result<bool> {
+        Ok(message.data.get("heartbeat").is_some())
+    }
+
+    async fn add_message(&mut self, _message: SharedMessage) -> Result<ApplyOutcome> {
+        self.heartbeats += 1;
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.heartbeats.to_string())
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn periodic_tasks(&self) -> Vec<TaskSpec> {
+        vec![
+            TaskSpec::every("heartbeat", Duration::from_secs(10)),
+            TaskSpec::every("sweep", Duration::from_secs(60))
+                .aligned()
+                .with_jitter(self.jitter),
+        ]
+    }
+
+    async fn run_task(&mut self, name: &str, now: DateTime<Utc>) -> Result<Vec<Value>> {
+        match name {
+            "heartbeat" => Ok(vec![json!({ "heartbeat": now.timestamp() })]),
+            _ => {
+                self.sweeps.push(now);
+                Ok(Vec::new())
+            },
+        }
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "heartbeats": self.heartbeats, "sweeps": self.sweeps.len() }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.heartbeats = 0;
+        self.sweeps.clear();
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 3).unwrap()
+}
+
+async fn presence(node: &ChaincraftNode, id: &SharedObjectId) -> Presence {
+    node.with_typed(id, |presence: &Presence| presence.clone())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_mock_clock_drives_object_tasks() -> Result<()> {
+    let clock = Clock::mock(start());
+    let node = ChaincraftNode::builder()
+        .with_clock(clock.clone())
+        .with_seed(7)
+        .build()?;
+    let id = node
+        .add_shared_object(Box::new(Presence::new(Duration::ZERO)))
+        .await?;
+    assert_eq!(node.tasks.len(), 2);
+    assert!(node.run_due_tasks().await?.is_empty());
+
+    clock.advance(Duration::from_secs(10));
+    let created = node.run_due_tasks().await?;
+    assert_eq!(created.len(), 1);
+    assert_eq!(presence(&node, &id).await.heartbeats, 1);
+    assert!(presence(&node, &id).await.sweeps.is_empty());
+
+    // A minute later the missed heartbeats run once, and the sweep on the minute
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(node.run_due_tasks().await?.len(), 1);
+    let state = presence(&node, &id).await;
+    assert_eq!(state.heartbeats, 2);
+    assert_eq!(state.sweeps, vec![start() + chrono::Duration::seconds(70)]);
+    let next: Vec<_> = node
+        .tasks
+        .tasks()
+        .into_iter()
+        .map(|task| (task.spec.name, task.next_run))
+        .collect();
+    assert_eq!(
+        next,
+        vec![
+            ("heartbeat".to_string(), start() + chrono::Duration::seconds(80)),
+            ("sweep".to_string(), Utc.with_ymd_and_hms(2024, 1, 1, 12, 2, 0).unwrap()),
+        ]
+    );
+    assert_eq!(node.metrics().get("tasks_run"), 3);
+
+    node.remove_shared_object(&id).await?;
+    assert!(node.tasks.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_started_node_runs_tasks_in_the_background() -> Result<()> {
+    let clock = Clock::mock(start());
+    let mut node = ChaincraftNode::builder()
+        .with_clock(clock.clone())
+        .with_schedule_interval(Duration::from_millis(10))
+        .build()?;
+    let id = node
+        .add_shared_object(Box::new(Presence::new(Duration::ZERO)))
+        .await?;
+    node.start().await?;
+
+    clock.advance(Duration::from_secs(10));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(presence(&node, &id).await.heartbeats, 1);
+    node.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_jitter_stays_below_its_bound_and_is_validated() -> Result<()> {
+    let node = ChaincraftNode::default();
+    let error = node
+        .add_shared_object(Box::new(Presence::new(Duration::from_secs(60))))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ChaincraftError::Config(_)));
+    assert_eq!(node.shared_object_count().await, 0);
+
+    let scheduler = TaskScheduler::new(RngProvider::seeded(3));
+    let object = SharedObjectId::new();
+    let spec = TaskSpec::every("round", Duration::from_secs(60))
+        .aligned()
+        .with_jitter(Duration::from_secs(5));
+    scheduler.register(&object, spec, start())?;
+    let mut now = start();
+    for minute in 1..=20 {
+        let boundary = Utc.with_ymd_and_hms(2024, 1, 1, 12, minute, 0).unwrap();
+        let run = scheduler.next_run().unwrap();
+        assert!(run >= boundary && run < boundary + chrono::Duration::seconds(5));
+        now = now.max(run);
+        assert_eq!(scheduler.due(now).len(), 1);
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use chaincraft_rust::{
+    consensus::poa::{PoaConfig, PoaEngine, PoaVote, POA_BLOCK_MESSAGE_TYPE},
+    crypto::{utils, KeyType, PrivateKey},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn keys(n: usize) -> Vec<PrivateKey> {
+    (0..n)
+        .map(|_| utils::generate_keypair(KeyType::Ed25519).unwrap().0)
+        .collect()
+}
+
+fn config(keys: &[PrivateKey], period: Duration) -> PoaConfig {
+    PoaConfig {
+        authorities: keys.iter().map(|k| k.public_key().to_hex()).collect(),
+        block_period: period,
+    }
+}
+
+/// One engine per key, all following the same chain
+fn engines(keys: &[PrivateKey]) -> Vec<PoaEngine> {
+    keys.iter()
+        .map(|k| PoaEngine::new(config(keys, Duration::ZERO)).with_signer(k.clone()))
+        .collect()
+}
+
+/// Let whichever engine is in turn seal the next block and share it with the others
+fn step(engines: &mut [PoaEngine]) -> Result<()> {
+    let sealer = engines.iter().position(|e| e.is_my_turn()).unwrap();
+    let block = engines[sealer].seal_block()?;
+    for (i, engine) in engines.iter_mut().enumerate() {
+        if i != sealer {
+            engine.import_block(block.clone())?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_round_robin_sealing() -> Result<()> {
+    let keys = keys(3);
+    let mut engines = engines(&keys);
+
+    for _ in 0..6 {
+        step(&mut engines)?;
+    }
+
+    let chain = engines[0].chain();
+    assert_eq!(chain.len(), 7);
+    for block in &chain[1..] {
+        assert_eq!(Some(block.signer.as_str()), engines[0].expected_signer(block.number));
+    }
+    assert_eq!(engines[1].head(), engines[2].head());
+    Ok(())
+}
+
+#[test]
+fn test_out_of_turn_and_tampered_blocks_are_rejected() -> Result<()> {
+    let keys = keys(2);
+    let mut engines = engines(&keys);
+
+    let sealer = engines.iter().position(|e| e.is_my_turn()).unwrap();
+    let other = 1 - sealer;
+    assert!(engines[other].seal_block().is_err());
+
+    let mut block = engines[sealer].seal_block()?;
+    block.transactions.push(json!("forged"));
+    assert!(engines[other].import_block(block).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_majority_vote_adds_authority() -> Result<()> {
+    let keys = keys(3);
+    let mut engines = engines(&keys);
+    let candidate = utils::generate_keypair(KeyType::Ed25519)?.1.to_hex();
+
+    for engine in engines.iter_mut() {
+        engine.propose_vote(candidate.clone(), true);
+    }
+    // One vote is not a majority of three
+    step(&mut engines)?;
+    let vote = PoaVote {
+        candidate: candidate.clone(),
+        authorize: true,
+    };
+    assert_eq!(engines[0].vote_count(&vote), 1);
+    assert_eq!(engines[0].authorities().len(), 3);
+
+    step(&mut engines)?;
+    assert_eq!(engines[0].authorities().len(), 4);
+    assert!(engines[2].authorities().contains(&candidate));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validator_node_seals_blocks_on_timer() -> Result<()> {
+    let keys = keys(1);
+    let engine =
+        PoaEngine::new(config(&keys, Duration::from_millis(40))).with_signer(keys[0].clone());
+    let mut node = ChaincraftNode::builder().with_poa(engine).build()?;
+    assert!(node.is_validator().await);
+
+    node.start().await?;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    node.stop().await?;
+
+    let poa = node.poa.as_ref().unwrap().read().await;
+    assert!(poa.head().number >= 2);
+    assert_eq!(node.message_log.read().await.len() as u64, poa.head().number);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_follower_node_imports_blocks() -> Result<()> {
+    let keys = keys(1);
+    let mut validator = PoaEngine::new(config(&keys, Duration::ZERO)).with_signer(keys[0].clone());
+    let follower = ChaincraftNode::builder()
+        .with_poa(PoaEngine::new(config(&keys, Duration::ZERO)))
+        .build()?;
+    assert!(!follower.is_validator().await);
+
+    validator.submit_transaction(json!({"from": "alice", "to": "bob"}));
+    let block = validator.seal_block()?;
+    let message = SharedMessage::new(
+        MessageType::Custom(POA_BLOCK_MESSAGE_TYPE.to_string()),
+        serde_json::to_value(&block).unwrap(),
+    );
+    follower
+        .receive_message(follower.id(), message.clone())
+        .await?;
+
+    let head = follower.poa.as_ref().unwrap().read().await.head().clone();
+    assert_eq!(head, block);
+
+    // Replaying the same block does not extend the chain
+    let replay = SharedMessage::new(message.message_type.clone(), message.data.clone());
+    assert!(follower
+        .receive_message(follower.id(), replay)
+        .await
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use chaincraft_rust::{
+    crypto::{
+        pow::{PoWChallenge, ProofOfWork},
+        KeylessCryptoPrimitive,
+    },
+    examples::pow_chain::{PowBlock, PowChainConfig, PowChainObject},
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    ChaincraftNode, Result,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+
+fn config() -> PowChainConfig {
+    PowChainConfig {
+        initial_difficulty: 1,
+        target_block_time_secs: 10,
+        retarget_interval: 100,
+        ..PowChainConfig::default()
+    }
+}
+
+fn message(block: &PowBlock) -> SharedMessage {
+    SharedMessage::new(
+        MessageType::Custom("block".to_string()),
+        PowChainObject::block_message(block),
+    )
+}
+
+fn code(outcome: ApplyOutcome) -> Option<String> {
+    outcome.reason().map(|reason| reason.code.clone())
+}
+
+/// Mine `count` blocks on the tip of `chain`, `spacing` seconds apart
+async fn extend(
+    chain: &mut PowChainObject,
+    miner: &str,
+    count: usize,
+    start: DateTime<Utc>,
+    spacing: i64,
+) -> Result<Vec<PowBlock>> {
+    let mut blocks = Vec::new();
+    for i in 0..count {
+        let timestamp = start + Duration::seconds(spacing * i as i64);
+        let block = chain.mine_next(miner, vec![json!(i)], timestamp).await?;
+        assert!(chain.add_message(message(&block)).await?.is_applied());
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+#[tokio::test]
+async fn test_node_mines_and_validates_blocks() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(PowChainObject::with_config(config())))
+        .await?;
+
+    let miner = PowChainObject::with_config(config());
+    let block = miner
+        .mine_next("alice", vec![json!({"pay": "bob"})], Utc::now())
+        .await?;
+    assert!(block.has_valid_pow());
+    assert!(block.hash.starts_with('0'));
+    node.create_shared_message_with_data(PowChainObject::block_message(&block))
+        .await?;
+
+    let (tip, height) = node
+        .with_typed::<PowChainObject, _>(&id, |chain| (chain.tip().hash.clone(), chain.height()))
+        .await
+        .unwrap();
+    assert_eq!(tip, block.hash);
+    assert_eq!(height, 1);
+
+    // Changing the block after mining breaks the proof
+    let mut chain = PowChainObject::with_config(config());
+    let mut forged = block.clone();
+    forged.transactions = vec![json!({"pay": "mallory"})];
+    assert_eq!(
+        code(chain.add_message(message(&forged)).await?),
+        Some("invalid_pow".to_string())
+    );
+    assert!(chain.add_message(message(&block)).await?.is_applied());
+    assert_eq!(code(chain.add_message(message(&block)).await?), Some("duplicate".to_string()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_heavier_branch_causes_reorg() -> Result<()> {
+    let start = Utc::now();
+    let mut chain = PowChainObject::with_config(config());
+    let genesis = chain.tip().hash.clone();
+    let ours = extend(&mut chain, "alice", 1, start, 10).await?;
+
+    // A competing block at the same height does not move the tip
+    let theirs = chain
+        .mine_on(&genesis, "bob", vec![], start + Duration::seconds(1))
+        .await?;
+    assert!(chain.add_message(message(&theirs)).await?.is_applied());
+    assert_eq!(chain.tip().hash, ours[0].hash);
+    assert!(chain.reorgs().is_empty());
+
+    // Extending it gives that branch more work
+    let longer = chain
+        .mine_on(&theirs.hash, "bob", vec![], start + Duration::seconds(20))
+        .await?;
+    assert!(chain.add_message(message(&longer)).await?.is_applied());
+    assert_eq!(chain.tip().hash, longer.hash);
+    let reorg = &chain.reorgs()[0];
+    assert_eq!(reorg.old_tip, ours[0].hash);
+    assert_eq!(reorg.common_ancestor, genesis);
+    assert_eq!(reorg.depth, 1);
+
+    assert!(!chain.is_on_main_chain(&ours[0].hash));
+    assert_eq!(chain.confirmations(&ours[0].hash), 0);
+    assert_eq!(chain.confirmations(&theirs.hash), 2);
+    let hashes: Vec<&str> = chain.main_chain().iter().map(|b| b.hash.as_str()).collect();
+    assert_eq!(hashes, [genesis.as_str(), &theirs.hash, &longer.hash]);
+    assert_eq!(chain.block_count(), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_orphans_connect_when_parent_arrives() -> Result<()> {
+    let mut miner = PowChainObject::with_config(config());
+    let blocks = extend(&mut miner, "alice", 3, Utc::now(), 10).await?;
+
+    let mut chain = PowChainObject::with_config(config());
+    for block in blocks.iter().rev().take(2) {
+        assert_eq!(code(chain.add_message(message(block)).await?), Some("orphan".to_string()));
+    }
+    assert_eq!(chain.orphan_count(), 2);
+    assert_eq!(chain.height(), 0);
+
+    assert!(chain.add_message(message(&blocks[0])).await?.is_applied());
+    assert_eq!(chain.orphan_count(), 0);
+    assert_eq!(chain.tip().hash, blocks[2].hash);
+    assert_eq!(chain.main_chain().len(), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_full_orphan_pool_drops_the_oldest() -> Result<()> {
+    let config = || PowChainConfig {
+        max_orphans: 2,
+        ..config()
+    };
+    let mut miner = PowChainObject::with_config(config());
+    let blocks = extend(&mut miner, "alice", 4, Utc::now(), 10).await?;
+
+    let mut chain = PowChainObject::with_config(config());
+    for block in &blocks[1..] {
+        assert_eq!(code(chain.add_message(message(block)).await?), Some("orphan".to_string()));
+    }
+    // The pool keeps taking new orphans; blocks[1] made room for blocks[3]
+    assert_eq!(chain.orphan_count(), 2);
+    assert!(chain.add_message(message(&blocks[0])).await?.is_applied());
+    assert_eq!(chain.height(), 1);
+    assert_eq!(chain.orphan_count(), 2);
+
+    // Orphans below the minimum difficulty are not held at all
+    let mut cheap = blocks[3].clone();
+    cheap.difficulty = 0;
+    cheap.parent = "unknown".to_string();
+    let proof = ProofOfWork::with_difficulty(0)
+        .create_proof(PoWChallenge::new(cheap.header()))
+        .await?;
+    (cheap.nonce, cheap.hash) = (proof.nonce, proof.hash);
+    assert_eq!(
+        code(chain.add_message(message(&cheap)).await?),
+        Some("wrong_difficulty".to_string())
+    );
+    assert_eq!(chain.orphan_count(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_far_future_timestamps_are_refused() -> Result<()> {
+    let mut chain = PowChainObject::with_config(PowChainConfig {
+        initial_difficulty: 2,
+        asert_half_life_secs: Some(60),
+        ..config()
+    });
+    let start = Utc::now();
+    extend(&mut chain, "alice", 2, start, 10).await?;
+
+    // Under ASERT a timestamp years ahead would drop the next difficulty
+    // to the minimum
+    let tip = chain.tip().hash.clone();
+    let future = chain
+        .mine_next("mallory", vec![], start + Duration::days(365))
+        .await?;
+    assert_eq!(
+        code(chain.add_message(message(&future)).await?),
+        Some("timestamp_too_late".to_string())
+    );
+    assert_eq!(chain.tip().hash, tip);
+
+    // Up to `max_time_ahead_blocks` target block times ahead is fine
+    let late = chain
+        .mine_next("alice", vec![], start + Duration::seconds(700))
+        .await?;
+    assert!(chain.add_message(message(&late)).await?.is_applied());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_difficulty_retargets_to_block_times() -> Result<()> {
+    let mut chain = PowChainObject::with_config(PowChainConfig {
+        retarget_interval: 2,
+        ..config()
+    });
+    let start = Utc::now();
+
+    // Blocks one second apart against a ten second target: harder
+    let fast = extend(&mut chain, "alice", 4, start, 1).await?;
+    assert_eq!(fast[2].difficulty, 1);
+    assert_eq!(fast[3].difficulty, 2);
+
+    // Then far slower than target: back down
+    let slow = extend(&mut chain, "alice", 2, start + Duration::seconds(100), 100).await?;
+    assert_eq!(slow[1].difficulty, 1);
+    assert_eq!(chain.get_state().await?["next_difficulty"], json!(1));
+
+    // Blocks must carry the expected difficulty and a fresh timestamp
+    let tip = chain.tip().clone();
+    let mut cheap = PowBlock {
+        height: tip.height + 1,
+        parent: tip.hash.clone(),
+        timestamp: tip.timestamp + Duration::seconds(10),
+        difficulty: 0,
+        miner: "mallory".to_string(),
+        transactions: vec![],
+        nonce: 0,
+        hash: String::new(),
+    };
+    let proof = ProofOfWork::with_difficulty(0)
+        .create_proof(PoWChallenge::new(cheap.header()))
+        .await?;
+    (cheap.nonce, cheap.hash) = (proof.nonce, proof.hash);
+    assert_eq!(
+        code(chain.add_message(message(&cheap)).await?),
+        Some("wrong_difficulty".to_string())
+    );
+    let backdated = chain.mine_on(&tip.parent, "mallory", vec![], start).await?;
+    assert_eq!(
+        code(chain.add_message(message(&backdated)).await?),
+        Some("timestamp_too_early".to_string())
+    );
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use chaincraft_rust::{
+    crypto::pow::{attach_pow, verify_message_pow, PowPolicy, MAX_MESSAGE_POW_DIFFICULTY},
+    error::{ChaincraftError, CryptoError},
+    network::PeerId,
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+
+fn chat(text: &str) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("chat".to_string()), json!(text))
+}
+
+#[test]
+fn test_attach_pow_solves_puzzle_over_hash() -> Result<()> {
+    let mut message = chat("hello");
+    assert!(!verify_message_pow(&message, 2));
+    assert!(verify_message_pow(&message, 0));
+
+    attach_pow(&mut message, 2)?;
+    assert!(message.pow_nonce.is_some());
+    assert!(message.verify_hash());
+    assert!(verify_message_pow(&message, 2));
+
+    // Work done for another message does not transfer
+    let mut other = chat("other");
+    other.pow_nonce = message.pow_nonce;
+    assert!(!verify_message_pow(&other, 2));
+    Ok(())
+}
+
+#[test]
+fn test_policy_difficulty_per_message_type() -> Result<()> {
+    let policy = PowPolicy::new(1)?.with_type_difficulty(MessageType::Heartbeat, 0)?;
+    assert_eq!(policy.difficulty_for(&MessageType::Heartbeat), 0);
+    assert_eq!(policy.difficulty_for(&MessageType::Custom("chat".to_string())), 1);
+    assert!(policy.is_satisfied_by(&SharedMessage::new(MessageType::Heartbeat, json!(1))));
+    Ok(())
+}
+
+#[test]
+fn test_unsolvable_difficulties_are_refused() -> Result<()> {
+    let too_hard = MAX_MESSAGE_POW_DIFFICULTY + 1;
+    assert!(PowPolicy::new(too_hard).is_err());
+    assert!(PowPolicy::new(1)?
+        .with_type_difficulty(MessageType::Heartbeat, 65)
+        .is_err());
+    assert!(attach_pow(&mut chat("hello"), too_hard).is_err());
+
+    // A policy assembled by hand is checked when the node is built
+    let mut policy = PowPolicy::new(1)?;
+    policy.default_difficulty = u32::MAX;
+    assert!(ChaincraftNode::builder()
+        .with_pow_policy(policy)
+        .build()
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_rejects_unsolved_messages() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_pow_policy(PowPolicy::new(2)?)
+        .build()?;
+    let peer = PeerId::new();
+
+    let unsolved = chat("spam");
+    let err = node.receive_message(&peer, unsolved.clone()).await;
+    assert!(matches!(err, Err(ChaincraftError::Crypto(CryptoError::ProofOfWorkFailed))));
+    assert!(node.submit_message(unsolved).await.is_err());
+    assert_eq!(node.metrics().get("pow_rejected"), 2);
+
+    let mut solved = chat("ham");
+    attach_pow(&mut solved, 2)?;
+    let hash = node.receive_message(&peer, solved.clone()).await?;
+    assert_eq!(hash, solved.hash);
+
+    let mut submitted = chat("local");
+    attach_pow(&mut submitted, 2)?;
+    node.submit_message(submitted).await?;
+
+    // Messages the node creates itself carry their own work
+    let created = node.create_shared_message_with_data(json!(1)).await?;
+    let stored = SharedMessage::from_json(&node.get_object(&created).await?)?;
+    assert!(verify_message_pow(&stored, 2));
+    assert_eq!(node.metrics().get("pow_rejected"), 2);
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    events::NodeEvent,
+    shared::{SharedMessage, SharedObjectId},
+    shared_object::ProcessingTimeout,
+    ApplicationObject, ApplyOutcome, ChaincraftNode, Result, SimpleSharedNumber,
+};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Object that never finishes applying a message while `hang` is set
+#[derive(Debug, Clone)]
+struct Stuck {
+    id: SharedObjectId,
+    hang: Arc<AtomicBool>,
+    received: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl ApplicationObject for Stuck {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+    fn type_name(&self) -> &'static str {
+        "Stuck"
+    }
+    async fn is_valid(&self, _message: &SharedMessage) -> Result<bool> {
+        Ok(true)
+    }
+    async fn add_message(&mut self, _message: SharedMessage) -> Result<ApplyOutcome> {
+        self.received.fetch_add(1, Ordering::SeqCst);
+        if self.hang.load(Ordering::SeqCst) {
+            std::future::pending::<()>().await;
+        }
+        Ok(ApplyOutcome::Applied)
+    }
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(String::new())
+    }
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({}))
+    }
+    async fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct Setup {
+    node: ChaincraftNode,
+    number: SharedObjectId,
+    stuck: SharedObjectId,
+    hang: Arc<AtomicBool>,
+    received: Arc<AtomicUsize>,
+}
+
+async fn setup(unhealthy_after: u32) -> Result<Setup> {
+    let node = ChaincraftNode::builder()
+        .with_processing_timeout(
+            ProcessingTimeout::new(Duration::from_millis(50)).with_unhealthy_after(unhealthy_after),
+        )
+        .build()?;
+    let number = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    let hang = Arc::new(AtomicBool::new(true));
+    let received = Arc::new(AtomicUsize::new(0));
+    let stuck = node
+        .add_shared_object(Box::new(Stuck {
+            id: SharedObjectId::new(),
+            hang: hang.clone(),
+            received: received.clone(),
+        }))
+        .await?;
+    Ok(Setup {
+        node,
+        number,
+        stuck,
+        hang,
+        received,
+    })
+}
+
+#[tokio::test]
+async fn test_hanging_object_is_timed_out_then_skipped() -> Result<()> {
+    let Setup {
+        mut node,
+        number,
+        stuck,
+        received,
+        ..
+    } = setup(2).await?;
+    let mut events = node.subscribe();
+
+    let first = node.create_shared_message_with_data(json!(1)).await?;
+    let mut timed_out = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let NodeEvent::ObjectTimedOut { .. } = event {
+            timed_out.push(event);
+        }
+    }
+    assert_eq!(
+        timed_out,
+        vec![NodeEvent::ObjectTimedOut {
+            object_id: stuck.clone(),
+            hash: first,
+            consecutive: 1,
+        }]
+    );
+    let health = node.object_health(&stuck).await.unwrap();
+    assert_eq!((health.timeouts, health.consecutive_timeouts), (1, 1));
+    assert!(health.is_healthy());
+
+    node.create_shared_message_with_data(json!(2)).await?;
+    let unhealthy: Vec<NodeEvent> = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| matches!(event, NodeEvent::ObjectUnhealthy { .. }))
+        .collect();
+    assert_eq!(
+        unhealthy,
+        vec![NodeEvent::ObjectUnhealthy {
+            object_id: stuck.clone(),
+            type_name: "Stuck".to_string(),
+        }]
+    );
+    assert_eq!(node.unhealthy_objects().await, vec![stuck.clone()]);
+
+    // The unhealthy object is skipped; the others keep up
+    node.create_shared_message_with_data(json!(3)).await?;
+    assert_eq!(received.load(Ordering::SeqCst), 2);
+    let number = node.get_typed::<SimpleSharedNumber>(&number).await.unwrap();
+    assert_eq!(number.get_number(), 6);
+    drop(number);
+    assert_eq!(node.metrics().get("object_timeouts"), 2);
+    assert_eq!(node.metrics().get("objects_unhealthy"), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_messages_in_time_reset_the_count_and_health_can_be_restored() -> Result<()> {
+    let Setup {
+        mut node,
+        stuck,
+        hang,
+        received,
+        ..
+    } = setup(2).await?;
+
+    node.create_shared_message_with_data(json!(1)).await?;
+    hang.store(false, Ordering::SeqCst);
+    node.create_shared_message_with_data(json!(2)).await?;
+    hang.store(true, Ordering::SeqCst);
+    node.create_shared_message_with_data(json!(3)).await?;
+    let health = node.object_health(&stuck).await.unwrap();
+    assert_eq!((health.timeouts, health.consecutive_timeouts), (2, 1));
+    assert!(health.is_healthy());
+
+    node.create_shared_message_with_data(json!(4)).await?;
+    assert!(!node.object_health(&stuck).await.unwrap().is_healthy());
+
+    hang.store(false, Ordering::SeqCst);
+    assert!(node.restore_object_health(&stuck).await);
+    assert!(!node.restore_object_health(&stuck).await);
+    node.create_shared_message_with_data(json!(5)).await?;
+    assert_eq!(received.load(Ordering::SeqCst), 5);
+    assert!(node.unhealthy_objects().await.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_without_a_timeout_health_is_not_tracked() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    let hang = Arc::new(AtomicBool::new(false));
+    let stuck = node
+        .add_shared_object(Box::new(Stuck {
+            id: SharedObjectId::new(),
+            hang,
+            received: Arc::new(AtomicUsize::new(0)),
+        }))
+        .await?;
+    node.create_shared_message_with_data(json!(1)).await?;
+    assert!(node.object_health(&stuck).await.is_none());
+    assert!(node.app_objects.read().await.processing_timeout().is_none());
+    Ok(())
+}
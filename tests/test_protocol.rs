@@ -0,0 +1,94 @@
+use chaincraft_rust::{
+    network::{keepalive::KEEPALIVE_MESSAGE_TYPE, reliable::ACK_MESSAGE_TYPE, PROTOCOL_VERSION},
+    protocol::{describe, DESCRIPTION_FORMAT},
+    shared::{MessageType, SharedMessage},
+    sync::SYNC_STATUS_MESSAGE_TYPE,
+    sync_batch::{SYNC_ACK_MESSAGE_TYPE, SYNC_BATCH_MESSAGE_TYPE},
+    ChaincraftNode, Result,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+fn keys(value: &Value) -> BTreeSet<String> {
+    value
+        .as_object()
+        .map(|object| object.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_description_is_versioned_and_deterministic() -> Result<()> {
+    let description = describe();
+    assert_eq!(description.format, DESCRIPTION_FORMAT);
+    assert_eq!(description.protocol_version, PROTOCOL_VERSION);
+    assert_eq!(serde_json::to_string(&description)?, serde_json::to_string(&describe())?);
+
+    let names: Vec<&str> = description
+        .message_kinds
+        .iter()
+        .map(|kind| kind.name.as_str())
+        .collect();
+    assert_eq!(names.iter().collect::<BTreeSet<_>>().len(), names.len());
+    for message_type in MessageType::standard() {
+        assert!(names.contains(&message_type.to_string().as_str()));
+    }
+    for control in [
+        SYNC_STATUS_MESSAGE_TYPE,
+        SYNC_BATCH_MESSAGE_TYPE,
+        SYNC_ACK_MESSAGE_TYPE,
+        ACK_MESSAGE_TYPE,
+        KEEPALIVE_MESSAGE_TYPE,
+    ] {
+        let kind = description
+            .message_kinds
+            .iter()
+            .find(|kind| kind.name == control)
+            .unwrap();
+        assert!(kind.custom);
+        assert!(kind.data_schema.is_some());
+    }
+
+    // Flows only use described messages
+    let variants: Vec<String> = description.discovery["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|variant| keys(&variant["properties"]))
+        .collect();
+    for step in description.flows.iter().flat_map(|flow| &flow.steps) {
+        assert!(
+            step.message == "*"
+                || names.contains(&step.message.as_str())
+                || variants.contains(&step.message),
+            "{}",
+            step.message
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schemas_match_what_is_sent() -> Result<()> {
+    let description = describe();
+    let mut message = SharedMessage::new(MessageType::Custom("note".to_string()), json!(1));
+    message.pow_nonce = Some(7);
+    let envelope = &description.envelope.schema;
+    assert_eq!(keys(&serde_json::to_value(&message)?), keys(&envelope["properties"]));
+
+    // The example hash is the reference for other implementations
+    let example = SharedMessage::from_json(&description.envelope.hash.example.to_string())?;
+    assert!(example.verify_hash());
+
+    let node = ChaincraftNode::default();
+    let announcement = node.sync_announcement().await;
+    let sync_status = description
+        .message_kinds
+        .iter()
+        .find(|kind| kind.name == SYNC_STATUS_MESSAGE_TYPE)
+        .and_then(|kind| kind.data_schema.clone())
+        .unwrap();
+    let properties = keys(&sync_status["properties"]);
+    assert!(keys(&announcement.data).is_subset(&properties));
+    assert_eq!(sync_status["required"], json!(["sequence"]));
+    Ok(())
+}
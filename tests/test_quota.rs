@@ -0,0 +1,135 @@
+use chaincraft_rust::{
+    discovery::{DiscoveryConfig, DiscoveryManager},
+    error::{ChaincraftError, NetworkError},
+    network::quota::{InboundQuota, QuotaConfig, QuotaDecision},
+    network::{PeerId, PeerInfo},
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode,
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn strict_quota() -> QuotaConfig {
+    QuotaConfig {
+        max_messages_per_window: 3,
+        window: Duration::from_secs(60),
+        max_invalid_ratio: 0.5,
+        min_messages_for_ratio: 4,
+        max_throttled_windows: 3,
+        ..QuotaConfig::default()
+    }
+}
+
+fn message(n: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("test".to_string()), json!({ "n": n }))
+}
+
+fn node_with_discovery(quota: QuotaConfig) -> ChaincraftNode {
+    let mut node = ChaincraftNode::builder().with_quota(quota).build().unwrap();
+    node.discovery = Some(DiscoveryManager::new(
+        node.id.clone(),
+        "127.0.0.1:9000".parse().unwrap(),
+        DiscoveryConfig::default(),
+    ));
+    node
+}
+
+#[test]
+fn test_quota_throttles_over_rate() {
+    let quota = InboundQuota::new(strict_quota());
+    let peer = PeerId::new();
+
+    for _ in 0..3 {
+        assert_eq!(quota.admit(&peer), QuotaDecision::Accept);
+    }
+    assert_eq!(quota.admit(&peer), QuotaDecision::Throttle);
+
+    // Other peers have their own budget
+    assert_eq!(quota.admit(&PeerId::new()), QuotaDecision::Accept);
+}
+
+#[test]
+fn test_quota_disconnects_on_invalid_ratio() {
+    let quota = InboundQuota::new(QuotaConfig {
+        max_messages_per_window: 100,
+        ..strict_quota()
+    });
+    let peer = PeerId::new();
+
+    let mut decision = QuotaDecision::Accept;
+    for _ in 0..4 {
+        quota.admit(&peer);
+        decision = quota.report(&peer, false);
+    }
+    assert_eq!(decision, QuotaDecision::Disconnect);
+    assert_eq!(quota.traffic(&peer).unwrap().invalid, 4);
+}
+
+#[tokio::test]
+async fn test_node_throttles_and_reports_to_discovery() {
+    let node = node_with_discovery(strict_quota());
+    let peer = PeerId::new();
+
+    for n in 0..3 {
+        node.receive_message(&peer, message(n)).await.unwrap();
+    }
+    let err = node.receive_message(&peer, message(3)).await.unwrap_err();
+    assert!(matches!(err, ChaincraftError::Network(NetworkError::PeerThrottled { .. })));
+
+    assert_eq!(node.metrics().get("inbound_throttled"), 1);
+    assert!(node.discovery.as_ref().unwrap().peer_score(&peer).await < 0);
+}
+
+#[tokio::test]
+async fn test_node_disconnects_peer_sending_invalid_messages() {
+    let node = node_with_discovery(QuotaConfig {
+        max_messages_per_window: 100,
+        ..strict_quota()
+    });
+    let peer = PeerId::new();
+    node.add_peer(PeerInfo::new(peer.clone(), "127.0.0.1:9001".parse().unwrap()))
+        .await
+        .unwrap();
+
+    let mut last = None;
+    for n in 0..4 {
+        let mut bad = message(n);
+        bad.hash = "tampered".to_string();
+        last = Some(node.receive_message(&peer, bad).await);
+    }
+
+    assert!(matches!(
+        last.unwrap(),
+        Err(ChaincraftError::Network(NetworkError::PeerDisconnected { .. }))
+    ));
+    assert!(node.get_peers().await.is_empty());
+    assert_eq!(node.metrics().get("inbound_invalid"), 4);
+    assert_eq!(node.metrics().get("peers_disconnected"), 1);
+    assert!(node.discovery.as_ref().unwrap().is_banned(&peer).await);
+}
+
+#[tokio::test]
+async fn test_discovery_downranks_abusive_peers() {
+    let discovery = DiscoveryManager::new(
+        PeerId::new(),
+        "127.0.0.1:9000".parse().unwrap(),
+        DiscoveryConfig::default(),
+    );
+    let good = PeerId::new();
+    let noisy = PeerId::new();
+    let banned = PeerId::new();
+    for (i, peer) in [&good, &noisy, &banned].into_iter().enumerate() {
+        let addr = format!("127.0.0.1:{}", 9100 + i).parse().unwrap();
+        discovery
+            .add_peer(PeerInfo::new(peer.clone(), addr))
+            .await
+            .unwrap();
+    }
+
+    discovery.report_abuse(&noisy, 10).await;
+    discovery.report_abuse(&banned, 1000).await;
+
+    let peers = discovery.get_peers_for_discovery(&PeerId::new(), 10).await;
+    let ids: Vec<PeerId> = peers.into_iter().map(|p| p.node_id).collect();
+    assert_eq!(ids, vec![good, noisy]);
+}
@@ -307,35 +307,27 @@ async fn test_randomness_history() {
 #[tokio::test]
 async fn test_message_integration_with_node() {
     let (mut node, _beacon) = create_beacon_node().await;
-    let _signer = ECDSASigner::new().unwrap();
+    let signer = ECDSASigner::new().unwrap();
+    let validator = signer.get_public_key_pem().unwrap();
 
     // Create validator registration message and send to node
-    let reg_msg = BeaconMessageType::ValidatorRegistration {
-        validator: "validator1".to_string(),
-        public_key: "pubkey1".to_string(),
-        vrf_key: "vrfkey1".to_string(),
-        stake: 1000,
-        signature: "reg_sig".to_string(),
-    };
-
-    let reg_data = serde_json::to_value(reg_msg).unwrap();
+    let reg_data = helpers::create_validator_registration(
+        validator.clone(),
+        validator.clone(),
+        validator.clone(),
+        1000,
+        &signer,
+    )
+    .unwrap();
     node.create_shared_message_with_data(reg_data)
         .await
         .unwrap();
     sleep(Duration::from_millis(100)).await;
 
     // Create VRF proof message and send to node
-    let vrf_msg = BeaconMessageType::VrfProof {
-        round: 1,
-        input: "integration_input".to_string(),
-        proof: "integration_proof".to_string(),
-        output: "integration_output".to_string(),
-        validator: "validator1".to_string(),
-        signature: "vrf_sig".to_string(),
-        timestamp: chrono::Utc::now(),
-    };
-
-    let vrf_data = serde_json::to_value(vrf_msg).unwrap();
+    let vrf_data =
+        helpers::create_vrf_proof_message(1, "integration_input".to_string(), validator, &signer)
+            .unwrap();
     node.create_shared_message_with_data(vrf_data)
         .await
         .unwrap();
@@ -468,8 +460,6 @@ async fn test_beacon_helper_functions() -> Result<()> {
     let message = helpers::create_vrf_proof_message(
         1,
         "test_input".to_string(),
-        "test_proof".to_string(),
-        "test_output".to_string(),
         "validator1".to_string(),
         &_signer,
     )?;
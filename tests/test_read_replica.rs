@@ -0,0 +1,70 @@
+use chaincraft_rust::{
+    consensus::poa::{PoaConfig, PoaEngine},
+    crypto::{utils, KeyType},
+    shared::{MessageType, SharedMessage},
+    shared_object::SimpleSharedNumber,
+    ChaincraftError, ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn replica() -> Result<ChaincraftNode> {
+    ChaincraftNode::builder().with_read_replica(true).build()
+}
+
+#[tokio::test]
+async fn test_replica_follows_peers_but_originates_nothing() -> Result<()> {
+    let mut origin = ChaincraftNode::default();
+    let mut replica = replica()?;
+    replica
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+
+    let hash = origin.create_shared_message_with_data(json!(5)).await?;
+    let message = SharedMessage::from_json(&origin.get_object(&hash).await?)?;
+    replica.receive_message(origin.id(), message).await?;
+    assert!(replica.get_message(&hash).await?.is_some());
+    let state = replica.shared_objects().await[0].get_state().await?;
+    assert_eq!(state["number"], json!(5));
+
+    let refused = [
+        replica.create_shared_message_with_data(json!(1)).await,
+        replica.create_shared_message("hello".to_string()).await,
+        replica
+            .submit_message(SharedMessage::new(MessageType::Custom("number".to_string()), json!(2)))
+            .await,
+        replica.schedule_message(json!(3), chrono::Utc::now()).await,
+    ];
+    for result in refused {
+        assert!(matches!(result, Err(ChaincraftError::ReadReplica { .. })));
+    }
+    assert_eq!(replica.metrics().get("replica_refused"), 4);
+    assert_eq!(replica.message_log.read().await.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_replica_cannot_be_a_validator() -> Result<()> {
+    let (key, _) = utils::generate_keypair(KeyType::Ed25519)?;
+    let config = PoaConfig {
+        authorities: vec![key.public_key().to_hex()],
+        block_period: Duration::from_millis(10),
+    };
+
+    let authority = ChaincraftNode::builder()
+        .with_read_replica(true)
+        .with_poa(PoaEngine::new(config.clone()).with_signer(key))
+        .build();
+    assert!(matches!(authority, Err(ChaincraftError::ReadReplica { .. })));
+
+    // Following the chain without a key is fine
+    let mut follower = ChaincraftNode::builder()
+        .with_read_replica(true)
+        .with_poa(PoaEngine::new(config))
+        .build()?;
+    follower.start().await?;
+    assert!(!follower.is_validator().await);
+    assert!(follower.is_read_replica());
+    follower.stop().await?;
+    Ok(())
+}
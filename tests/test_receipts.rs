@@ -0,0 +1,176 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    examples::ledger::{address_of, LedgerObject, Transfer, TRANSFER_GAS},
+    receipt::{Execution, ReceiptStatus},
+    shared::{SharedMessage, SharedObjectId},
+    ApplicationObject, ApplicationObjectRegistry, ApplyOutcome, ChaincraftError, ChaincraftNode,
+    Result, SimpleSharedNumber,
+};
+use serde_json::{json, Value};
+use std::any::Any;
+
+/// Balance that charges one unit of gas per credit and emits an event for it
+#[derive(Debug, Clone)]
+struct Meter {
+    id: SharedObjectId,
+    balance: u64,
+    execution: Execution,
+}
+
+#[async_trait]
+impl ApplicationObject for Meter {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+    fn type_name(&self) -> &'static str {
+        "Meter"
+    }
+    async fn is_valid(&self, _message: &SharedMessage) -> Result<bool> {
+        Ok(true)
+    }
+    async fn add_message(&mut self, message: SharedMessage) -> Result<ApplyOutcome> {
+        let Some(amount) = message.data.get("credit").and_then(|a| a.as_u64()) else {
+            return Ok(ApplyOutcome::rejected("bad_credit", "missing credit amount"));
+        };
+        self.execution.charge(amount);
+        self.balance += amount;
+        self.execution
+            .emit("credited", json!({ "amount": amount, "balance": self.balance }));
+        Ok(ApplyOutcome::Applied)
+    }
+    fn take_execution(&mut self) -> Execution {
+        std::mem::take(&mut self.execution)
+    }
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok(self.balance.to_string())
+    }
+    async fn has_digest(&self, digest: &str) -> Result<bool> {
+        Ok(digest == self.balance.to_string())
+    }
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+    async fn add_digest(&mut self, _digest: String) -> Result<bool> {
+        Ok(true)
+    }
+    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "balance": self.balance }))
+    }
+    async fn reset(&mut self) -> Result<()> {
+        self.balance = 0;
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn meter() -> Meter {
+    Meter {
+        id: SharedObjectId::new(),
+        balance: 0,
+        execution: Execution::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_receipt_collects_gas_and_events() -> Result<()> {
+    let mut registry = ApplicationObjectRegistry::new();
+    let id = registry.register(Box::new(meter()));
+
+    let first = registry
+        .execute_message(SharedMessage::custom("credit", json!({ "credit": 3 }))?)
+        .await?;
+    assert!(first.succeeded());
+    assert_eq!(first.gas_used, 3);
+    assert_eq!(first.events.len(), 1);
+    assert_eq!(first.events[0].object_id, id);
+    assert_eq!(first.events[0].name, "credited");
+    assert_eq!(first.events[0].data["balance"], 3);
+
+    // Same amount again: the state moves on, so the delta differs
+    let second = registry
+        .execute_message(SharedMessage::custom("credit", json!({ "credit": 3 }))?)
+        .await?;
+    assert_eq!(second.events[0].data["balance"], 6);
+    assert_ne!(first.state_delta_hash, second.state_delta_hash);
+
+    let failed = registry
+        .execute_message(SharedMessage::custom("credit", json!({}))?)
+        .await?;
+    assert!(matches!(&failed.status, ReceiptStatus::Failed(r) if r.code == "bad_credit"));
+    assert_eq!(failed.gas_used, 0);
+    assert!(failed.events.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_stores_receipts_by_hash() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+
+    let hash = node.create_shared_message_with_data(json!(5)).await?;
+    let receipt = node.get_receipt(&hash).await?.expect("receipt stored");
+    assert_eq!(receipt.tx_hash, hash);
+    assert_eq!(receipt.status, ReceiptStatus::Success);
+    assert_eq!(receipt.outcomes.len(), 1);
+
+    let duplicate = node.create_shared_message_with_data(json!(5)).await?;
+    let receipt = node.get_receipt(&duplicate).await?.unwrap();
+    assert_eq!(receipt.status, ReceiptStatus::NoEffect);
+
+    // A second object that rejects anything without a credit amount
+    node.add_shared_object(Box::new(meter())).await?;
+    let rejected = SharedMessage::custom("credit", json!({}))?;
+    let rejected_hash = rejected.hash.clone();
+    assert!(matches!(
+        node.submit_message(rejected).await,
+        Err(ChaincraftError::Rejected { .. })
+    ));
+    let receipt = node.get_receipt(&rejected_hash).await?.unwrap();
+    assert!(matches!(receipt.status, ReceiptStatus::Failed(_)));
+
+    assert!(node.get_receipt("missing").await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ledger_transfer_receipt_by_hash() -> Result<()> {
+    let (alice, alice_public) = utils::generate_keypair(KeyType::Ed25519)?;
+    let alice_address = address_of(&alice_public);
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(LedgerObject::with_genesis([(alice_address.clone(), 10)])))
+        .await?;
+
+    let transfer = Transfer::signed(&alice, "0x00000000000000000000000000000000000000b0", 4, 0)?;
+    let hash = node
+        .create_shared_message_with_data(transfer.to_message())
+        .await?;
+    let receipt = node.get_receipt(&hash).await?.expect("receipt stored");
+    assert!(receipt.succeeded());
+    assert_eq!(receipt.gas_used, TRANSFER_GAS);
+    assert_eq!(receipt.events.len(), 1);
+    assert_eq!(receipt.events[0].object_id, id);
+    assert_eq!(receipt.events[0].name, "Transfer");
+    assert_eq!(receipt.events[0].data["from"], json!(alice_address));
+    assert_eq!(receipt.events[0].data["amount"], 4);
+    Ok(())
+}
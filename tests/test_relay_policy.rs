@@ -0,0 +1,89 @@
+use chaincraft_rust::{
+    crypto::ecdsa::ECDSASigner,
+    examples::chatroom::{helpers, ChatroomObject},
+    network::{PeerId, PeerInfo},
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, RelayDecision},
+    ChaincraftNode, Result,
+};
+use serde_json::Value;
+
+fn chat(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("chat".to_string()), data)
+}
+
+fn code(decision: &RelayDecision) -> Option<&str> {
+    decision.reason().map(|r| r.code.as_str())
+}
+
+#[tokio::test]
+async fn test_chatroom_vetoes_relay_of_unverifiable_messages() -> Result<()> {
+    let admin = ECDSASigner::new()?;
+    let stranger = ECDSASigner::new()?;
+    let mut chatroom = ChatroomObject::new();
+
+    // Room creation is relayed even though the room is new to this node
+    let create = chat(helpers::create_chatroom_message("lobby".to_string(), &admin)?);
+    assert!(chatroom.relay_policy(&create).await?.is_relayed());
+    chatroom.add_message(create).await?;
+
+    let post = |room: &str, signer: &ECDSASigner| {
+        helpers::create_post_message(room.to_string(), "hi".to_string(), signer)
+    };
+    assert!(chatroom
+        .relay_policy(&chat(post("lobby", &admin)?))
+        .await?
+        .is_relayed());
+
+    let mut forged = post("lobby", &admin)?;
+    forged["text"] = Value::String("forged".to_string());
+    let decision = chatroom.relay_policy(&chat(forged)).await?;
+    assert_eq!(code(&decision), Some("invalid_signature"));
+
+    let decision = chatroom
+        .relay_policy(&chat(post("elsewhere", &admin)?))
+        .await?;
+    assert_eq!(code(&decision), Some("unknown_chatroom"));
+
+    let decision = chatroom
+        .relay_policy(&chat(post("lobby", &stranger)?))
+        .await?;
+    assert_eq!(code(&decision), Some("not_member"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_gossips_only_relayable_messages() -> Result<()> {
+    let node = ChaincraftNode::default();
+    node.add_shared_object(Box::new(ChatroomObject::new()))
+        .await?;
+    let peers = [PeerId::new(), PeerId::new()];
+    for (i, peer) in peers.iter().enumerate() {
+        let addr = format!("127.0.0.1:{}", 9300 + i).parse().unwrap();
+        node.add_peer(PeerInfo::new(peer.clone(), addr)).await?;
+    }
+
+    let admin = ECDSASigner::new()?;
+    node.submit_message(chat(helpers::create_chatroom_message("lobby".to_string(), &admin)?))
+        .await?;
+
+    let valid =
+        chat(helpers::create_post_message("lobby".to_string(), "hello".to_string(), &admin)?);
+    let decision = node.select_relay_targets(&valid).await?;
+    assert_eq!(decision.targets().len(), peers.len());
+
+    let spam = chat(helpers::create_post_message(
+        "lobby".to_string(),
+        "buy now".to_string(),
+        &ECDSASigner::new()?,
+    )?);
+    let decision = node.select_relay_targets(&spam).await?;
+    assert!(decision.targets().is_empty());
+    assert_eq!(decision.message_hash, spam.hash);
+    assert_eq!(node.metrics().get("relay_vetoed"), 1);
+
+    // Messages no object claims keep flooding
+    let other = SharedMessage::new(MessageType::Custom("other".to_string()), Value::Null);
+    assert!(node.relay_decision(&other).await?.is_relayed());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use chaincraft_rust::{
+    network::reliable::{DeliveryEvent, ReliableConfig, ReliableTransport},
+    network::transport::{InMemoryNetwork, Transport},
+    network::PeerId,
+    shared::{MessageType, SharedMessage},
+    Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn fast_config() -> ReliableConfig {
+    ReliableConfig {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(40),
+        max_attempts: 3,
+    }
+}
+
+#[test]
+fn test_backoff_doubles_up_to_limit() {
+    let config = fast_config();
+    assert_eq!(config.backoff(1), Duration::from_millis(10));
+    assert_eq!(config.backoff(2), Duration::from_millis(20));
+    assert_eq!(config.backoff(3), Duration::from_millis(40));
+    assert_eq!(config.backoff(10), Duration::from_millis(40));
+}
+
+#[tokio::test]
+async fn test_acknowledged_message_is_delivered_once() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let alice =
+        Arc::new(ReliableTransport::with_config(network.connect(PeerId::new()), fast_config()));
+    let bob = ReliableTransport::with_config(network.connect(PeerId::new()), fast_config());
+    let mut events = alice.subscribe();
+
+    let message = SharedMessage::new(MessageType::Custom("request".to_string()), json!(1));
+    alice.send(bob.local_id(), &message).await?;
+
+    // Retransmit before the ack is read; bob must still deliver only once
+    tokio::time::sleep(Duration::from_millis(15)).await;
+    assert_eq!(alice.process_retries().await, 1);
+
+    let (from, received) = bob.recv().await?;
+    assert_eq!(&from, alice.local_id());
+    assert_eq!(received.hash, message.hash);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), bob.recv())
+            .await
+            .is_err(),
+        "duplicate should be suppressed"
+    );
+
+    let receiver = alice.clone();
+    let reader = tokio::spawn(async move { receiver.recv().await });
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("ack received")
+        .unwrap();
+    reader.abort();
+    assert_eq!(
+        event,
+        DeliveryEvent::Delivered {
+            peer_id: bob.local_id().clone(),
+            hash: message.hash.clone(),
+        }
+    );
+    assert_eq!(alice.pending_count(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unacknowledged_message_fails_after_max_attempts() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let alice = ReliableTransport::with_config(network.connect(PeerId::new()), fast_config());
+    let mut events = alice.subscribe();
+
+    let offline = PeerId::new();
+    let message = SharedMessage::new(MessageType::Heartbeat, json!({"seq": 1}));
+    alice.send(&offline, &message).await?;
+    assert!(alice.is_pending(&offline, &message.hash));
+
+    let mut resent = 0;
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        resent += alice.process_retries().await;
+        if alice.pending_count() == 0 {
+            break;
+        }
+    }
+    assert_eq!(resent, 2);
+    assert_eq!(
+        events.try_recv().unwrap(),
+        DeliveryEvent::DeliveryFailed {
+            peer_id: offline,
+            hash: message.hash,
+            attempts: 3,
+        }
+    );
+    Ok(())
+}
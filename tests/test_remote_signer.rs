@@ -0,0 +1,100 @@
+use chaincraft_rust::{
+    consensus::poa::{PoaConfig, PoaEngine},
+    crypto::{
+        signer::{serve_signer, LocalSigner, RemoteSigner, SocketSigner},
+        utils, KeyType, PrivateKey,
+    },
+    error::{ChaincraftError, CryptoError},
+    ChaincraftNode, Result,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn key() -> PrivateKey {
+    utils::generate_keypair(KeyType::Ed25519).unwrap().0
+}
+
+/// Run a signing process holding `key`, returning its address
+async fn spawn_signer(key: PrivateKey) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve_signer(listener, Arc::new(LocalSigner::new(key))));
+    addr
+}
+
+#[tokio::test]
+async fn test_local_signer_signs_without_exposing_key() -> Result<()> {
+    let key = key();
+    let signer = LocalSigner::new(key.clone());
+
+    let signature = signer.sign(b"vote").await?;
+    assert!(signer.public_key().verify(b"vote", &signature)?);
+    assert!(!format!("{:?}", signer).contains(&key.to_hex()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_socket_signer_checks_returned_signatures() -> Result<()> {
+    let key = key();
+    let addr = spawn_signer(key.clone()).await;
+
+    let signer = SocketSigner::new(addr, key.public_key());
+    let signature = signer.sign(b"precommit:7").await?;
+    assert!(key.public_key().verify(b"precommit:7", &signature)?);
+
+    // A signer answering with some other key is rejected
+    let impostor = SocketSigner::new(addr, self::key().public_key());
+    assert!(matches!(
+        impostor.sign(b"precommit:7").await,
+        Err(ChaincraftError::Crypto(CryptoError::InvalidSignature))
+    ));
+
+    // Nothing listening
+    let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let unreachable = closed.local_addr()?;
+    drop(closed);
+    let missing =
+        SocketSigner::new(unreachable, key.public_key()).with_timeout(Duration::from_millis(500));
+    assert!(missing.sign(b"x").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validator_seals_blocks_through_remote_signer() -> Result<()> {
+    let key = key();
+    let addr = spawn_signer(key.clone()).await;
+    let config = PoaConfig {
+        authorities: vec![key.public_key().to_hex()],
+        block_period: Duration::from_millis(40),
+    };
+
+    let mut engine = PoaEngine::new(config.clone())
+        .with_remote_signer(Arc::new(SocketSigner::new(addr, key.public_key())));
+    assert!(engine.is_authority());
+    assert!(engine.seal_block().is_err(), "sync sealing needs a local key");
+    let block = engine.seal_block_async().await?;
+    assert!(block.verify_seal()?);
+
+    let mut node = ChaincraftNode::builder()
+        .with_poa(PoaEngine::new(config))
+        .with_remote_signer(Arc::new(SocketSigner::new(addr, key.public_key())))
+        .build()?;
+    assert!(node.is_validator().await);
+
+    node.start().await?;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    node.stop().await?;
+
+    let poa = node.poa.as_ref().unwrap().read().await;
+    assert!(poa.head().number >= 2);
+    assert!(poa.chain()[1..].iter().all(|b| b.verify_seal().unwrap()));
+    drop(poa);
+
+    // A remote signer without an engine to sign for is a configuration error
+    assert!(ChaincraftNode::builder()
+        .with_remote_signer(Arc::new(LocalSigner::new(key)))
+        .build()
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use chaincraft_rust::{
+    events::NodeEvent,
+    network::{PeerId, PeerInfo},
+    node::ChaincraftNodeBuilder,
+    resources::{Resource, ResourceLimits},
+    storage::{LimitedStorage, MemoryStorage, Storage},
+    ChaincraftError, Result, SimpleSharedNumber,
+};
+use std::sync::Arc;
+
+fn exhausted(result: Result<impl std::fmt::Debug>) -> Resource {
+    match result {
+        Err(ChaincraftError::ResourceExhausted { resource, .. }) => resource,
+        other => panic!("expected ResourceExhausted, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_limited_storage_charges_overwrites_and_deletes() -> Result<()> {
+    let storage = LimitedStorage::new(Arc::new(MemoryStorage::new()), 10);
+    storage.put("a", vec![0; 5]).await?;
+    storage.put("a", vec![0; 9]).await?;
+    assert_eq!(storage.used_bytes().await, 10);
+    assert_eq!(exhausted(storage.put("b", vec![0; 1]).await), Resource::StorageBytes);
+    assert!(!storage.exists("b").await?);
+
+    storage.delete("a").await?;
+    storage.put("b", vec![0; 9]).await?;
+    assert_eq!(storage.used_bytes().await, 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limited_storage_counts_existing_entries() -> Result<()> {
+    let inner = Arc::new(MemoryStorage::new());
+    inner.put("a", vec![0; 7]).await?;
+    let storage = LimitedStorage::new(inner, 10);
+    storage.initialize().await?;
+    assert_eq!(storage.used_bytes().await, 8);
+    assert_eq!(exhausted(storage.put("b", vec![0; 2]).await), Resource::StorageBytes);
+    storage.put("a", vec![0; 9]).await?;
+    assert_eq!(storage.used_bytes().await, 10);
+    Ok(())
+}
+
+#[cfg(feature = "persistent")]
+#[tokio::test]
+async fn test_limited_storage_budget_survives_reopening() -> Result<()> {
+    use chaincraft_rust::storage::SledStorage;
+
+    let dir = tempfile::tempdir()?;
+    {
+        let storage = LimitedStorage::new(Arc::new(SledStorage::open(dir.path())?), 10);
+        storage.initialize().await?;
+        storage.put("a", vec![0; 5]).await?;
+        storage.flush().await?;
+    }
+    let storage = LimitedStorage::new(Arc::new(SledStorage::open(dir.path())?), 10);
+    storage.initialize().await?;
+    assert_eq!(storage.used_bytes().await, 6);
+    assert_eq!(exhausted(storage.put("b", vec![0; 4]).await), Resource::StorageBytes);
+    storage.put("b", vec![0; 3]).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_enforces_object_peer_and_message_limits() -> Result<()> {
+    let mut node = ChaincraftNodeBuilder::new()
+        .with_resource_limits(
+            ResourceLimits::unlimited()
+                .with_max_objects(1)
+                .with_max_peers(1)
+                .with_max_cached_messages(2),
+        )
+        .build()?;
+    let mut events = node.subscribe();
+
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    assert_eq!(
+        exhausted(
+            node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+                .await
+        ),
+        Resource::Objects
+    );
+    assert_eq!(
+        events.try_recv().unwrap(),
+        NodeEvent::ResourceExhausted {
+            resource: Resource::Objects,
+            limit: 1,
+        }
+    );
+
+    let peer = PeerInfo::new(PeerId::new(), "127.0.0.1:9000".parse().unwrap());
+    node.add_peer(peer.clone()).await?;
+    // Re-adding a known peer is not a new admission
+    node.add_peer(peer).await?;
+    let other = PeerInfo::new(PeerId::new(), "127.0.0.1:9001".parse().unwrap());
+    assert_eq!(exhausted(node.add_peer(other).await), Resource::Peers);
+
+    node.create_shared_message("one".to_string()).await?;
+    node.create_shared_message("two".to_string()).await?;
+    assert_eq!(
+        exhausted(node.create_shared_message("three".to_string()).await),
+        Resource::CachedMessages
+    );
+    assert_eq!(node.metrics().get("resource_exhausted"), 3);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_storage_budget() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let mut node = ChaincraftNodeBuilder::new()
+        .with_storage(storage)
+        .with_resource_limits(ResourceLimits::unlimited().with_max_storage_bytes(600))
+        .build()?;
+
+    node.create_shared_message("fits".to_string()).await?;
+    let result = node.create_shared_message("x".repeat(600)).await;
+    assert_eq!(exhausted(result), Resource::StorageBytes);
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use chaincraft_rust::{
+    clock::Clock,
+    crypto::ecdsa::ECDSASigner,
+    examples::chatroom::{helpers, ChatroomObject},
+    index::IndexSpec,
+    query::MessageFilter,
+    resources::ResourceLimits,
+    retention::RetentionPolicy,
+    storage::{MemoryStorage, Storage},
+    sync_batch::MessageSource,
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_pruning_keeps_the_newest_messages() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_retention(RetentionPolicy {
+            max_messages: Some(3),
+            ..RetentionPolicy::default()
+        })
+        .build()?;
+    let mut hashes = Vec::new();
+    for i in 0..5 {
+        hashes.push(node.create_shared_message_with_data(json!({ "n": i })).await?);
+    }
+
+    let summary = node.prune_messages().await?;
+    assert_eq!(summary.messages, 2);
+    for hash in &hashes[..2] {
+        assert!(node.get_message(hash).await?.is_none());
+    }
+    assert!(node.get_message(&hashes[2]).await?.is_some());
+    assert_eq!(node.db_size_async().await?, 3);
+    assert_eq!(node.query_messages(&MessageFilter::new()).await?.len(), 3);
+
+    // Sequence numbers peers saw stay valid, though the log only holds the
+    // retained hashes
+    assert_eq!(node.log_len().await?, 5);
+    assert_eq!(node.read_log(0, 10).await?.len(), 3);
+    assert_eq!(node.read_log(3, 10).await?[0].hash, hashes[3]);
+    let log = node.message_log.read().await.clone();
+    assert_eq!((log.base(), log.retained()), (2, 3));
+    assert_eq!(log.get(4), Some(&hashes[4]));
+
+    // Indexes declared later cover the retained messages
+    assert!(node.declare_index(IndexSpec::field("n", "/n")).await?);
+    assert_eq!(node.prune_messages().await?.messages, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pruned_messages_free_the_cache_limit() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_retention(RetentionPolicy {
+            max_messages: Some(2),
+            ..RetentionPolicy::default()
+        })
+        .with_resource_limits(ResourceLimits::unlimited().with_max_cached_messages(3))
+        .build()?;
+    for i in 0..9 {
+        node.create_shared_message_with_data(json!({ "n": i })).await?;
+        node.prune_messages().await?;
+    }
+    assert_eq!(node.log_len().await?, 9);
+    assert_eq!(node.db_size_async().await?, 2);
+    assert_eq!(node.message_log.read().await.retained(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_started_node_prunes_expired_messages() -> Result<()> {
+    let clock = Clock::mock(chrono::Utc::now());
+    let mut node = ChaincraftNode::builder()
+        .port(0)
+        .with_clock(clock.clone())
+        .with_retention(RetentionPolicy {
+            max_age: Some(Duration::from_secs(3600)),
+            prune_interval: Duration::from_millis(20),
+            ..RetentionPolicy::default()
+        })
+        .build()?;
+    node.start().await?;
+    let hash = node.create_shared_message_with_data(json!("old")).await?;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(node.get_message(&hash).await?.is_some());
+
+    clock.advance(Duration::from_secs(2 * 3600));
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while node.get_message(&hash).await?.is_some() {
+        assert!(std::time::Instant::now() < deadline, "message was not pruned");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(node.metrics().get("messages_pruned") >= 1);
+    node.close().await
+}
+
+#[tokio::test]
+async fn test_objects_compact_their_messages() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_retention(RetentionPolicy {
+            max_messages: Some(2),
+            ..RetentionPolicy::default()
+        })
+        .build()?;
+    let id = node
+        .add_shared_object(Box::new(ChatroomObject::new()))
+        .await?;
+    let admin = ECDSASigner::new()?;
+    node.create_shared_message_with_data(helpers::create_chatroom_message(
+        "room".to_string(),
+        &admin,
+    )?)
+    .await?;
+    for i in 0..4 {
+        let post = helpers::create_post_message("room".to_string(), format!("post {}", i), &admin)?;
+        node.create_shared_message_with_data(post).await?;
+    }
+
+    let summary = node.prune_messages().await?;
+    assert!(summary.compacted > 0);
+    let room = node
+        .with_typed::<ChatroomObject, _>(&id, |chat| chat.get_chatroom("room").cloned())
+        .await
+        .flatten()
+        .unwrap();
+    assert_eq!(room.messages.len(), 2);
+    assert_eq!(room.messages[1].text.as_deref(), Some("post 3"));
+    assert_eq!(room.pruned_count as usize, summary.compacted);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restarted_node_prunes_what_it_stored_before() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let policy = RetentionPolicy {
+        max_messages: Some(3),
+        ..RetentionPolicy::default()
+    };
+    let mut hashes = Vec::new();
+    {
+        let mut node = ChaincraftNode::builder()
+            .with_storage(storage.clone())
+            .with_retention(policy.clone())
+            .build()?;
+        node.start().await?;
+        for i in 0..4 {
+            hashes.push(node.create_shared_message_with_data(json!({ "n": i })).await?);
+        }
+        node.prune_messages().await?;
+        node.close().await?;
+    }
+
+    let mut node = ChaincraftNode::builder()
+        .with_storage(storage)
+        .with_retention(policy)
+        .build()?;
+    node.start().await?;
+    assert_eq!(node.log_len().await?, 4);
+    for i in 4..6 {
+        hashes.push(node.create_shared_message_with_data(json!({ "n": i })).await?);
+    }
+
+    // The messages stored before the restart are pruned first
+    node.prune_messages().await?;
+    assert_eq!(node.db_size_async().await?, 3);
+    for hash in &hashes[..3] {
+        assert!(node.get_message(hash).await?.is_none());
+    }
+    assert_eq!(node.read_log(0, 10).await?.len(), 3);
+    assert_eq!(node.log_len().await?, 6);
+    node.close().await?;
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use chaincraft_rust::{
+    crypto::{ecdsa::ECDSASigner, utils, KeyType},
+    network::PeerId,
+    shared::{MessageType, SharedMessage, SharedObjectId},
+    ChaincraftNode, Result, RngProvider,
+};
+use serde_json::json;
+
+#[test]
+fn test_seeded_ids_are_reproducible() {
+    let rng_a = RngProvider::seeded(7);
+    let rng_b = RngProvider::seeded(7);
+
+    for _ in 0..5 {
+        assert_eq!(SharedObjectId::new_with_rng(&rng_a), SharedObjectId::new_with_rng(&rng_b));
+        assert_eq!(PeerId::new_with_rng(&rng_a), PeerId::new_with_rng(&rng_b));
+    }
+
+    let other = RngProvider::seeded(8);
+    assert_ne!(
+        SharedObjectId::new_with_rng(&RngProvider::seeded(7)),
+        SharedObjectId::new_with_rng(&other)
+    );
+}
+
+#[test]
+fn test_os_provider_is_not_deterministic() {
+    let rng = RngProvider::os();
+    assert!(!rng.is_deterministic());
+    assert_ne!(rng.next_uuid(), rng.next_uuid());
+    assert!(RngProvider::seeded(1).is_deterministic());
+}
+
+#[test]
+fn test_seeded_keys_are_reproducible() -> Result<()> {
+    for key_type in [KeyType::Ed25519, KeyType::Secp256k1] {
+        let (private_a, public_a) =
+            utils::generate_keypair_with_rng(key_type, &mut RngProvider::seeded(42))?;
+        let (private_b, public_b) =
+            utils::generate_keypair_with_rng(key_type, &mut RngProvider::seeded(42))?;
+        assert_eq!(private_a.to_hex(), private_b.to_hex());
+        assert_eq!(public_a, public_b);
+    }
+
+    let signer_a = ECDSASigner::new_with_rng(&RngProvider::seeded(3))?;
+    let signer_b = ECDSASigner::new_with_rng(&RngProvider::seeded(3))?;
+    assert_eq!(signer_a.get_public_key_pem()?, signer_b.get_public_key_pem()?);
+
+    Ok(())
+}
+
+#[test]
+fn test_message_ids_follow_provider() {
+    let rng = RngProvider::seeded(11);
+    let first = SharedMessage::new_with_rng(&rng, MessageType::Heartbeat, json!(1));
+    let second = SharedMessage::new_with_rng(&rng, MessageType::Heartbeat, json!(1));
+    assert_ne!(first.id, second.id);
+    assert!(first.verify_hash());
+
+    let replay = RngProvider::seeded(11);
+    let replayed = SharedMessage::new_with_rng(&replay, MessageType::Heartbeat, json!(1));
+    assert_eq!(first.id, replayed.id);
+}
+
+#[tokio::test]
+async fn test_seeded_node_identity() -> Result<()> {
+    let node_a = ChaincraftNode::builder().with_seed(99).build()?;
+    let node_b = ChaincraftNode::builder().with_seed(99).build()?;
+    assert_eq!(node_a.id(), node_b.id());
+    assert!(node_a.rng().is_deterministic());
+
+    let node_c = ChaincraftNode::builder().build()?;
+    assert!(!node_c.rng().is_deterministic());
+    Ok(())
+}
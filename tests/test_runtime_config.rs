@@ -0,0 +1,147 @@
+use chaincraft_rust::{
+    audit::{AuditConfig, AuditEvent},
+    network::fanout::FanoutCandidate,
+    network::quota::QuotaDecision,
+    outbox::OutboxConfig,
+    runtime_config::{self, ConfigUpdate},
+    ChaincraftError, ChaincraftNode, PeerId, Result, RngProvider,
+};
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+
+fn changed_settings(node: &ChaincraftNode) -> Vec<(String, String)> {
+    node.audit_log()
+        .unwrap()
+        .entries()
+        .into_iter()
+        .filter_map(|entry| match entry.event {
+            AuditEvent::ConfigChanged { setting, value } => Some((setting, value)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_updates_reach_running_subsystems_and_are_audited() -> Result<()> {
+    let mut node = ChaincraftNode::builder()
+        .with_audit_log(AuditConfig::default())
+        .with_outbox(OutboxConfig::default())
+        .build()?;
+    node.start().await?;
+    let before = node.runtime_config();
+    assert_eq!(before.fanout, 8);
+    assert_eq!(before.gossip_interval, Some(Duration::from_secs(1)));
+
+    let after = node.handle().update_config(
+        ConfigUpdate::new()
+            .with_gossip_interval(Duration::from_millis(200))
+            .with_fanout(2)
+            .with_rate_limit(2)
+            .with_audit_retention(3),
+    )?;
+    assert_eq!(after, node.runtime_config());
+    assert_eq!(after.gossip_interval, Some(Duration::from_millis(200)));
+    assert_eq!((after.fanout, after.rate_limit), (2, 2));
+    assert_eq!(after.audit_retention, Some(3));
+
+    let peers: Vec<FanoutCandidate> = (0..5)
+        .map(|_| FanoutCandidate::new(PeerId::new(), 10, false))
+        .collect();
+    let decision = node.fanout.select("h", &peers, &RngProvider::seeded(1));
+    assert_eq!(decision.targets().len(), 2);
+
+    let peer = PeerId::new();
+    assert_eq!(node.quota.admit(&peer), QuotaDecision::Accept);
+    assert_eq!(node.quota.admit(&peer), QuotaDecision::Accept);
+    assert_eq!(node.quota.admit(&peer), QuotaDecision::Throttle);
+
+    // Retention dropped the older entries, keeping the last three changes
+    assert_eq!(
+        changed_settings(&node),
+        vec![
+            ("fanout".to_string(), "2".to_string()),
+            ("rate_limit".to_string(), "2".to_string()),
+            ("audit_retention".to_string(), "3".to_string()),
+        ]
+    );
+    assert!(node.audit_log().unwrap().verify().is_ok());
+    node.stop().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refused_updates_change_nothing() -> Result<()> {
+    let node = ChaincraftNode::builder()
+        .with_audit_log(AuditConfig::default())
+        .build()?;
+    let before = node.runtime_config();
+
+    let refused = [
+        ConfigUpdate::new().with_fanout(3).with_rate_limit(0),
+        ConfigUpdate::new()
+            .with_fanout(3)
+            .with_gossip_interval(Duration::ZERO),
+        // Without an outbox nothing is gossiped again
+        ConfigUpdate::new()
+            .with_fanout(3)
+            .with_gossip_interval(Duration::from_secs(1)),
+        ConfigUpdate::new().with_fanout(3).with_audit_retention(0),
+    ];
+    for update in refused {
+        let error = node.update_config(update).unwrap_err();
+        assert!(matches!(error, ChaincraftError::Config(_)), "{}", error);
+    }
+    assert_eq!(node.runtime_config(), before);
+    assert!(changed_settings(&node).is_empty());
+
+    let without_audit = ChaincraftNode::default();
+    assert!(without_audit
+        .update_config(ConfigUpdate::new().with_audit_retention(5))
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_log_level_changes_once_logging_is_reloadable() -> Result<()> {
+    let node = ChaincraftNode::builder()
+        .with_audit_log(AuditConfig::default())
+        .build()?;
+    let update = ConfigUpdate::new().with_log_level(LevelFilter::DEBUG);
+    assert!(node.update_config(update.clone()).is_err());
+    assert_eq!(node.runtime_config().log_level, None);
+
+    runtime_config::init_logging(LevelFilter::WARN)?;
+    assert_eq!(runtime_config::log_level(), Some(LevelFilter::WARN));
+    assert!(runtime_config::init_logging(LevelFilter::INFO).is_err());
+
+    assert_eq!(node.update_config(update)?.log_level, Some(LevelFilter::DEBUG));
+    assert_eq!(runtime_config::log_level(), Some(LevelFilter::DEBUG));
+    assert_eq!(changed_settings(&node), vec![("log_level".to_string(), "debug".to_string())]);
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+#[tokio::test]
+async fn test_config_over_grpc() -> Result<()> {
+    use chaincraft_rust::network::grpc::{GrpcClient, GrpcTransport};
+
+    let node = ChaincraftNode::builder()
+        .with_outbox(OutboxConfig::default())
+        .build()?;
+    let server = GrpcTransport::bind_node(&node, "127.0.0.1:0".parse().unwrap()).await?;
+    let mut client = GrpcClient::connect(server.local_addr()).await?;
+
+    assert_eq!(client.runtime_config().await?, node.runtime_config());
+    let update = ConfigUpdate::new()
+        .with_gossip_interval(Duration::from_millis(50))
+        .with_fanout(4);
+    let config = client.update_config(&update).await?;
+    assert_eq!(config, node.runtime_config());
+    assert_eq!(config.fanout, 4);
+    assert_eq!(config.audit_retention, None);
+
+    let refused = ConfigUpdate::new().with_audit_retention(10);
+    assert!(client.update_config(&refused).await.is_err());
+    assert_eq!(node.runtime_config().fanout, 4);
+    Ok(())
+}
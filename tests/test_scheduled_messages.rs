@@ -0,0 +1,91 @@
+use chaincraft_rust::{
+    clock::Clock, shared_object::SimpleSharedNumber, storage::MemoryStorage, ChaincraftNode, Result,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+}
+
+async fn node(storage: Arc<MemoryStorage>, clock: Clock) -> Result<ChaincraftNode> {
+    let node = ChaincraftNode::builder()
+        .with_storage(storage)
+        .with_clock(clock)
+        .with_schedule_interval(Duration::from_millis(10))
+        .build()?;
+    node.add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await?;
+    Ok(node)
+}
+
+async fn number_of(node: &ChaincraftNode) -> Result<serde_json::Value> {
+    let state = node.shared_objects().await[0].get_state().await?;
+    Ok(state["number"].clone())
+}
+
+#[tokio::test]
+async fn test_scheduled_message_is_submitted_when_due() -> Result<()> {
+    let clock = Clock::mock(start());
+    let node = node(Arc::new(MemoryStorage::new()), clock.clone()).await?;
+    let close = start() + chrono::Duration::minutes(5);
+
+    let hash = node.schedule_message(json!(7), close).await?;
+    assert!(node.submit_due_messages().await?.is_empty());
+    assert!(node.get_message(&hash).await?.is_none());
+    assert_eq!(node.scheduled_messages().await?.len(), 1);
+
+    clock.advance(Duration::from_secs(300));
+    assert_eq!(node.submit_due_messages().await?, vec![hash.clone()]);
+    assert_eq!(number_of(&node).await?, json!(7));
+    // The message carries its due time, not the time it was scheduled
+    assert_eq!(node.get_message(&hash).await?.unwrap().timestamp, close);
+    assert!(node.scheduled_messages().await?.is_empty());
+    assert_eq!(node.metrics().get("scheduled_submitted"), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schedule_survives_restart() -> Result<()> {
+    let storage = Arc::new(MemoryStorage::new());
+    let clock = Clock::mock(start());
+    let first = node(storage.clone(), clock.clone()).await?;
+    let hash = first
+        .schedule_message(json!(3), start() + chrono::Duration::hours(1))
+        .await?;
+    drop(first);
+
+    clock.advance(Duration::from_secs(7200));
+    let mut restarted = node(storage, clock).await?;
+    assert_eq!(restarted.scheduled_messages().await?[0].hash, hash);
+    restarted.start().await?;
+    for _ in 0..100 {
+        if restarted.scheduled_messages().await?.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(restarted.get_message(&hash).await?.is_some());
+    assert_eq!(number_of(&restarted).await?, json!(3));
+    restarted.stop().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancelled_message_is_never_submitted() -> Result<()> {
+    let clock = Clock::mock(start());
+    let node = node(Arc::new(MemoryStorage::new()), clock.clone()).await?;
+    let cancelled = node.schedule_message(json!(1), start()).await?;
+    let kept = node
+        .schedule_message(json!(2), start() + chrono::Duration::seconds(1))
+        .await?;
+
+    assert!(node.cancel_scheduled(&cancelled).await?);
+    assert!(!node.cancel_scheduled(&cancelled).await?);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(node.submit_due_messages().await?, vec![kept]);
+    assert!(node.get_message(&cancelled).await?.is_none());
+    Ok(())
+}
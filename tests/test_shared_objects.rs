@@ -169,3 +169,58 @@ async fn test_shared_object_state() {
     // Clean up
     node.close().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_typed_access_mutates_node_held_object() {
+    let mut node = ChaincraftNode::default();
+    let id = node
+        .add_shared_object(Box::new(SimpleSharedNumber::new()))
+        .await
+        .unwrap();
+    node.create_shared_message_with_data(serde_json::json!(5))
+        .await
+        .unwrap();
+
+    let number = node
+        .with_typed::<SimpleSharedNumber, _>(&id, |obj| obj.get_number())
+        .await;
+    assert_eq!(number, Some(5));
+
+    {
+        let mut handle = node.get_typed_mut::<SimpleSharedNumber>(&id).await.unwrap();
+        let message = chaincraft_rust::SharedMessage::new(
+            chaincraft_rust::shared::MessageType::Custom("number".to_string()),
+            serde_json::json!(3),
+        );
+        handle.add_message(message).await.unwrap();
+    }
+    assert_eq!(
+        node.get_typed::<SimpleSharedNumber>(&id)
+            .await
+            .unwrap()
+            .get_number(),
+        8
+    );
+
+    let messages = node
+        .with_typed_mut::<SimpleSharedNumber, _>(&id, |obj| obj.get_messages().len())
+        .await;
+    assert_eq!(messages, Some(2));
+    assert_eq!(
+        node.app_objects
+            .read()
+            .await
+            .ids_by_type("SimpleSharedNumber"),
+        vec![id.clone()]
+    );
+
+    // Wrong type or unknown id yield nothing
+    assert!(node
+        .get_typed::<chaincraft_rust::examples::chatroom::ChatroomObject>(&id)
+        .await
+        .is_none());
+    assert!(node
+        .with_typed::<SimpleSharedNumber, _>(&Default::default(), |_| ())
+        .await
+        .is_none());
+}
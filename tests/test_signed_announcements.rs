@@ -0,0 +1,202 @@
+use chaincraft_rust::{
+    crypto::{utils::generate_keypair, KeyType, PrivateKey},
+    discovery::{DiscoveryConfig, DiscoveryManager, DiscoveryMessage, PeerAnnouncement},
+    network::PeerId,
+    Result,
+};
+use std::net::SocketAddr;
+
+fn addr(port: u16) -> SocketAddr {
+    format!("127.0.0.1:{}", port).parse().unwrap()
+}
+
+fn key() -> PrivateKey {
+    generate_keypair(KeyType::Ed25519).unwrap().0
+}
+
+fn manager(id: PeerId, port: u16, config: DiscoveryConfig) -> DiscoveryManager {
+    DiscoveryManager::new(id, addr(port), config)
+}
+
+/// Deliver an announcement from the address it names
+async fn announce(discovery: &DiscoveryManager, announcement: DiscoveryMessage) -> Result<()> {
+    let DiscoveryMessage::Announce { socket_addr, .. } = &announcement else {
+        unreachable!()
+    };
+    let from = *socket_addr;
+    discovery.handle_message(announcement, from).await?;
+    Ok(())
+}
+
+fn refusal(result: Result<()>) -> String {
+    result.unwrap_err().to_string()
+}
+
+#[tokio::test]
+async fn test_announcements_are_bound_to_the_identity_key() -> Result<()> {
+    let receiver = manager(PeerId::new(), 9000, DiscoveryConfig::default());
+    let alice_id = PeerId::new();
+    let alice = manager(alice_id.clone(), 9001, DiscoveryConfig::default()).with_identity(key());
+
+    let announcement = alice.create_announcement()?;
+    announce(&receiver, announcement.clone()).await?;
+    assert_eq!(receiver.get_peers().await[0].socket_addr, addr(9001));
+    assert!(receiver.announced_key(&alice_id).await.is_some());
+
+    // Pointing Alice's id at another address breaks the signature
+    let DiscoveryMessage::Announce {
+        timestamp,
+        public_key,
+        signature,
+        ..
+    } = announcement
+    else {
+        unreachable!()
+    };
+    let spoofed = DiscoveryMessage::Announce {
+        node_id: alice_id.clone(),
+        socket_addr: addr(6666),
+        timestamp,
+        expires_at: timestamp + 300,
+        public_key: public_key.clone(),
+        signature: signature.clone(),
+    };
+    assert!(refusal(announce(&receiver, spoofed).await).contains("invalid signature"));
+    let replayed = DiscoveryMessage::Announce {
+        node_id: alice_id.clone(),
+        socket_addr: addr(9001),
+        timestamp,
+        expires_at: 1,
+        public_key,
+        signature,
+    };
+    assert!(refusal(announce(&receiver, replayed).await).contains("expired"));
+
+    // Neither a validly signed claim with another key nor an unsigned one
+    let mallory = manager(alice_id.clone(), 6666, DiscoveryConfig::default()).with_identity(key());
+    let claim = mallory.create_announcement()?;
+    assert!(refusal(announce(&receiver, claim).await).contains("different key"));
+    let unsigned = manager(alice_id, 6666, DiscoveryConfig::default());
+    let claim = unsigned.create_announcement()?;
+    assert!(refusal(announce(&receiver, claim).await).contains("not signed"));
+    assert_eq!(receiver.get_peers().await[0].socket_addr, addr(9001));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_strict_mode_drops_unsigned_announcements() -> Result<()> {
+    let legacy = manager(PeerId::new(), 9001, DiscoveryConfig::default());
+    let announcement = legacy.create_announcement()?;
+
+    let lenient = manager(PeerId::new(), 9000, DiscoveryConfig::default());
+    announce(&lenient, announcement.clone()).await?;
+    assert_eq!(lenient.get_peers().await.len(), 1);
+
+    let strict = manager(
+        PeerId::new(),
+        9000,
+        DiscoveryConfig {
+            strict_announcements: true,
+            ..DiscoveryConfig::default()
+        },
+    );
+    assert!(refusal(announce(&strict, announcement).await).contains("not signed"));
+    assert!(strict.get_peers().await.is_empty());
+    Ok(())
+}
+
+fn unsigned_entry(node_id: &PeerId, port: u16) -> PeerAnnouncement {
+    PeerAnnouncement {
+        node_id: node_id.clone(),
+        socket_addr: addr(port),
+        last_seen: 0,
+        announced_at: 0,
+        expires_at: 0,
+        public_key: String::new(),
+        signature: String::new(),
+    }
+}
+
+async fn respond(discovery: &DiscoveryManager, peers: Vec<PeerAnnouncement>) -> Result<()> {
+    discovery
+        .handle_message(DiscoveryMessage::PeerResponse { peers }, addr(2))
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forged_peer_response_cannot_move_a_pinned_peer() -> Result<()> {
+    let receiver = manager(PeerId::new(), 9000, DiscoveryConfig::default());
+    let alice_id = PeerId::new();
+    let alice = manager(alice_id.clone(), 9001, DiscoveryConfig::default()).with_identity(key());
+    announce(&receiver, alice.create_announcement()?).await?;
+
+    // Neither an unsigned entry nor one signed by another key moves Alice
+    let mallory = manager(alice_id.clone(), 6666, DiscoveryConfig::default()).with_identity(key());
+    let DiscoveryMessage::Announce {
+        timestamp,
+        expires_at,
+        public_key,
+        signature,
+        ..
+    } = mallory.create_announcement()?
+    else {
+        unreachable!()
+    };
+    let signed_by_mallory = PeerAnnouncement {
+        announced_at: timestamp,
+        expires_at,
+        public_key,
+        signature,
+        ..unsigned_entry(&alice_id, 6666)
+    };
+    respond(&receiver, vec![unsigned_entry(&alice_id, 6666), signed_by_mallory.clone()]).await?;
+    assert_eq!(receiver.get_peers().await[0].socket_addr, addr(9001));
+
+    // Even once Alice is forgotten her pinned key keeps the forgeries out
+    receiver.remove_peer(&alice_id).await?;
+    respond(&receiver, vec![unsigned_entry(&alice_id, 6666), signed_by_mallory]).await?;
+    assert!(receiver.get_peers().await.is_empty());
+
+    // Her own announcement, relayed by someone who knows her, is accepted
+    let relay = manager(PeerId::new(), 9002, DiscoveryConfig::default());
+    announce(&relay, alice.create_announcement()?).await?;
+    let relayed = relay.get_peers_for_discovery(&PeerId::new(), 10).await;
+    assert!(relayed[0].is_signed());
+    respond(&receiver, relayed).await?;
+    assert_eq!(receiver.get_peers().await[0].socket_addr, addr(9001));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_strict_mode_drops_unsigned_peer_response_entries() -> Result<()> {
+    let strict = manager(
+        PeerId::new(),
+        9000,
+        DiscoveryConfig {
+            strict_announcements: true,
+            ..DiscoveryConfig::default()
+        },
+    );
+    respond(&strict, vec![unsigned_entry(&PeerId::new(), 9001)]).await?;
+    assert!(strict.get_peers().await.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_the_announced_address_pins_a_key() -> Result<()> {
+    let receiver = manager(PeerId::new(), 9000, DiscoveryConfig::default());
+    let alice_id = PeerId::new();
+    let alice = manager(alice_id.clone(), 9001, DiscoveryConfig::default()).with_identity(key());
+
+    // Mallory relays Alice's announcement from elsewhere: accepted, not pinned
+    receiver
+        .handle_message(alice.create_announcement()?, addr(6666))
+        .await?;
+    assert_eq!(receiver.get_peers().await[0].socket_addr, addr(9001));
+    assert!(receiver.announced_key(&alice_id).await.is_none());
+
+    announce(&receiver, alice.create_announcement()?).await?;
+    assert!(receiver.announced_key(&alice_id).await.is_some());
+    Ok(())
+}
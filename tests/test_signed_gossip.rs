@@ -0,0 +1,146 @@
+use chaincraft_rust::{
+    crypto::{utils, KeyType, PrivateKey},
+    discovery::DiscoveryConfig,
+    network::{
+        transport::{Transport, TransportFrame},
+        udp::{UdpGossipConfig, UdpTransport},
+    },
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, PeerId, Result,
+};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+fn identity() -> Result<PrivateKey> {
+    Ok(utils::generate_keypair(KeyType::Ed25519)?.0)
+}
+
+fn note(text: &str) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("note".to_string()), json!(text))
+}
+
+async fn signed_node() -> Result<ChaincraftNode> {
+    let mut node = ChaincraftNode::builder()
+        .port(0)
+        .with_udp_gossip(UdpGossipConfig::default())
+        .with_discovery(DiscoveryConfig {
+            announce_interval: 1,
+            ..DiscoveryConfig::default()
+        })
+        .with_identity_key(identity()?)
+        .build()?;
+    node.start().await?;
+    Ok(node)
+}
+
+/// Wait until `node` stores `hash`
+async fn stored(node: &ChaincraftNode, hash: &str) -> Result<bool> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if node.get_message(hash).await?.is_some() {
+            return Ok(true);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    Ok(false)
+}
+
+/// Key `node` has pinned for `peer_id`
+async fn pinned_key(node: &ChaincraftNode, peer_id: &PeerId) -> Option<String> {
+    node.get_peers()
+        .await
+        .into_iter()
+        .find(|peer| &peer.id == peer_id)
+        .and_then(|peer| peer.public_key)
+}
+
+#[test]
+fn test_frame_signature_covers_peers_and_message() -> Result<()> {
+    let key = identity()?;
+    let mut frame = TransportFrame::new(PeerId::new(), PeerId::new(), note("hi"));
+    assert_eq!(frame.verified_signer()?, None);
+
+    frame.sign(&key)?;
+    let signer = key.public_key().to_hex();
+    assert_eq!(frame.verified_signer()?, Some(signer.as_str()));
+
+    let mut redirected = frame.clone();
+    redirected.to = PeerId::new();
+    assert!(redirected.verified_signer().is_err());
+
+    let mut tampered = frame.clone();
+    tampered.message.data = json!("bye");
+    assert!(tampered.verified_signer().is_err());
+
+    // Survives the wire
+    let decoded = TransportFrame::from_bytes(&frame.to_bytes()?)?;
+    assert_eq!(decoded.verified_signer()?, Some(signer.as_str()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transport_drops_frames_with_bad_signatures() -> Result<()> {
+    let key = identity()?;
+    let a = UdpTransport::bind(PeerId::new(), "127.0.0.1:0".parse().unwrap())
+        .await?
+        .with_identity(key.clone());
+    let b = UdpTransport::bind(PeerId::new(), "127.0.0.1:0".parse().unwrap()).await?;
+
+    let mut forged = TransportFrame::new(a.local_id().clone(), b.local_id().clone(), note("x"));
+    forged.sign(&key)?;
+    forged.message = note("forged");
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0")?;
+    socket.send_to(&forged.to_bytes()?, b.local_addr())?;
+
+    a.send_to_addr(b.local_id(), b.local_addr(), &note("real"))
+        .await?;
+    let (_, frame) = b.recv_frame().await?;
+    assert_eq!(frame.message.data, json!("real"));
+    assert_eq!(frame.signer, Some(key.public_key().to_hex()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nodes_pin_the_keys_peers_announce_with() -> Result<()> {
+    let mut a = signed_node().await?;
+    let mut b = signed_node().await?;
+    let addr = format!("127.0.0.1:{}", b.port()).parse().unwrap();
+
+    // An impostor claiming a's id signs first; its key is not pinned
+    let impostor = UdpTransport::bind(a.id().clone(), "127.0.0.1:0".parse().unwrap())
+        .await?
+        .with_identity(identity()?);
+    let early = note("from a, first");
+    impostor.send_to_addr(b.id(), addr, &early).await?;
+    assert!(stored(&b, &early.hash).await?);
+    assert_eq!(pinned_key(&b, a.id()).await, None);
+
+    // a's announcement from its own address pins a's key
+    a.connect_to_peer(&format!("127.0.0.1:{}", b.port()))
+        .await?;
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while pinned_key(&b, a.id()).await != a.identity_public_key() {
+        assert!(Instant::now() < deadline, "a's key was not pinned");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let hash = a.create_shared_message("signed".to_string()).await?;
+    assert!(stored(&b, &hash).await?);
+
+    // From then on the impostor is refused, signed or not
+    let forged = note("from a, honest");
+    impostor.send_to_addr(b.id(), addr, &forged).await?;
+    let unsigned = UdpTransport::bind(a.id().clone(), "127.0.0.1:0".parse().unwrap()).await?;
+    unsigned
+        .send_to_addr(b.id(), addr, &note("unsigned"))
+        .await?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while b.metrics().get("gossip_signer_rejected") < 2 {
+        assert!(Instant::now() < deadline, "impostor frames were not refused");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(b.get_message(&forged.hash).await?.is_none());
+
+    a.stop().await?;
+    b.stop().await
+}
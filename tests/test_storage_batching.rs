@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    batching::{BatchConfig, BatchingStorage},
+    error::StorageError,
+    storage::{MemoryStorage, Storage},
+    ChaincraftError, ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+const NEVER: Duration = Duration::from_secs(3600);
+
+#[tokio::test]
+async fn test_writes_flush_by_size() -> Result<()> {
+    let inner = Arc::new(MemoryStorage::new());
+    let config = BatchConfig::default()
+        .with_max_batch(3)
+        .with_max_delay(NEVER);
+    let storage = BatchingStorage::open(inner.clone(), config).await?;
+
+    storage.put("a", b"1".to_vec()).await?;
+    storage.put("b", b"2".to_vec()).await?;
+    storage.delete("b").await?;
+    // Buffered writes are visible to reads but not yet in the backend
+    assert_eq!(storage.get("a").await?, Some(b"1".to_vec()));
+    assert!(!storage.exists("b").await?);
+    assert!(inner.get("a").await?.is_none());
+    assert_eq!(storage.pending().await, 2);
+
+    storage.put("c", b"3".to_vec()).await?;
+    assert_eq!(storage.pending().await, 0);
+    assert_eq!(inner.get("a").await?, Some(b"1".to_vec()));
+    assert_eq!(inner.get("c").await?, Some(b"3".to_vec()));
+    assert!(inner.get("b").await?.is_none());
+
+    let stats = storage.stats().await;
+    assert_eq!((stats.writes, stats.batches, stats.flushed), (4, 1, 3));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_writes_flush_after_delay() -> Result<()> {
+    let inner = Arc::new(MemoryStorage::new());
+    let config = BatchConfig::default().with_max_delay(Duration::from_millis(10));
+    let storage = BatchingStorage::open(inner.clone(), config).await?;
+
+    storage.put("a", b"1".to_vec()).await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(inner.get("a").await?, Some(b"1".to_vec()));
+    assert_eq!(storage.stats().await.batches, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_ahead_log_recovers_unflushed_writes() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let wal = dir.path().join("storage.wal");
+    let config = BatchConfig::default().with_max_delay(NEVER).with_wal(&wal);
+
+    // Flushed writes leave the log empty
+    let flushed = Arc::new(MemoryStorage::new());
+    let storage = BatchingStorage::open(flushed.clone(), config.clone()).await?;
+    storage.put("kept", b"0".to_vec()).await?;
+    storage.flush().await?;
+    assert_eq!(std::fs::metadata(&wal)?.len(), 0);
+
+    storage.put("a", b"1".to_vec()).await?;
+    storage.put("b", b"2".to_vec()).await?;
+    storage.delete("kept").await?;
+    // Crash before the next flush, in the middle of appending a record
+    drop(storage);
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&wal)?
+        .write_all(b"{\"key\":\"c\",\"val")?;
+
+    let storage = BatchingStorage::open(flushed.clone(), config).await?;
+    assert_eq!(storage.stats().await.recovered, 3);
+    assert_eq!(flushed.get("a").await?, Some(b"1".to_vec()));
+    assert_eq!(flushed.get("b").await?, Some(b"2".to_vec()));
+    assert!(flushed.get("kept").await?.is_none());
+    assert!(flushed.get("c").await?.is_none());
+    assert_eq!(std::fs::metadata(&wal)?.len(), 0);
+    Ok(())
+}
+
+/// Memory backend whose writes never reach the disk
+struct UnflushableStorage(MemoryStorage);
+
+#[async_trait]
+impl Storage for UnflushableStorage {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.0.get(key).await
+    }
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        self.0.put(key, value).await
+    }
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.0.delete(key).await
+    }
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.0.exists(key).await
+    }
+    async fn clear(&self) -> Result<()> {
+        self.0.clear().await
+    }
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+    async fn flush(&self) -> Result<()> {
+        Err(ChaincraftError::Storage(StorageError::DatabaseOperation {
+            reason: "disk unavailable".to_string(),
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_write_ahead_log_outlives_a_failed_backend_flush() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let wal = dir.path().join("storage.wal");
+    let config = BatchConfig::default().with_max_delay(NEVER).with_wal(&wal);
+
+    let unflushable = Arc::new(UnflushableStorage(MemoryStorage::new()));
+    let storage = BatchingStorage::open(unflushable, config.clone()).await?;
+    storage.put("a", b"1".to_vec()).await?;
+    assert!(storage.flush().await.is_err());
+    // The backend took the batch but never made it durable, so it stays logged
+    assert!(std::fs::metadata(&wal)?.len() > 0);
+    drop(storage);
+
+    let recovered = Arc::new(MemoryStorage::new());
+    let storage = BatchingStorage::open(recovered.clone(), config).await?;
+    assert_eq!(storage.stats().await.recovered, 1);
+    assert_eq!(recovered.get("a").await?, Some(b"1".to_vec()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_flushes_batched_storage_on_close() -> Result<()> {
+    let inner = Arc::new(MemoryStorage::new());
+    let config = BatchConfig::default().with_max_delay(NEVER);
+    let storage = BatchingStorage::open(inner.clone(), config).await?;
+    let mut node = ChaincraftNode::builder().with_storage(storage).build()?;
+
+    let hash = node
+        .create_shared_message_with_data(json!({ "batched": true }))
+        .await?;
+    assert!(node.get_object(&hash).await.is_ok());
+    assert!(!inner.exists(&hash).await?);
+
+    node.close().await?;
+    assert!(inner.exists(&hash).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_key_listing_includes_buffered_writes() -> Result<()> {
+    let inner = Arc::new(MemoryStorage::new());
+    inner.put("log:1", b"1".to_vec()).await?;
+    inner.put("log:2", b"2".to_vec()).await?;
+    inner.put("other", b"3".to_vec()).await?;
+    let config = BatchConfig::default()
+        .with_max_batch(100)
+        .with_max_delay(NEVER);
+    let storage = BatchingStorage::open(inner, config).await?;
+
+    storage.put("log:3", b"3".to_vec()).await?;
+    storage.delete("log:1").await?;
+    let mut keys = storage.keys_with_prefix("log:").await?;
+    keys.sort();
+    assert_eq!(keys, vec!["log:2", "log:3"]);
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use chaincraft_rust::{
+    node::ChaincraftNodeBuilder,
+    shared::{MessageType, SharedMessage, SharedObjectId},
+    storage::{MemoryStorage, MessageEncoding, Storage},
+    Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+fn sample() -> SharedMessage {
+    let mut message = SharedMessage::new_with_target(
+        MessageType::Custom("transfer".to_string()),
+        SharedObjectId::new(),
+        json!({ "from": "alice", "to": "bob", "amount": 42, "memo": null }),
+    );
+    message.signature = Some(vec![7; 64]);
+    message.pow_nonce = Some(99);
+    message
+}
+
+#[test]
+fn test_binary_round_trip_is_smaller_than_json() -> Result<()> {
+    let message = sample();
+    let json = MessageEncoding::Json.encode(&message)?;
+    let binary = MessageEncoding::Binary.encode(&message)?;
+    assert_eq!(MessageEncoding::detect(&json), MessageEncoding::Json);
+    assert_eq!(MessageEncoding::detect(&binary), MessageEncoding::Binary);
+    assert!(binary.len() < json.len());
+
+    for bytes in [json, binary] {
+        let decoded = MessageEncoding::decode(&bytes)?;
+        assert_eq!(decoded.to_json()?, message.to_json()?);
+        assert!(decoded.verify_hash());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_builtin_types_survive_binary_encoding() -> Result<()> {
+    let message = SharedMessage::new(MessageType::Heartbeat, json!([1, 2.5, "x"]));
+    let decoded = MessageEncoding::decode(&MessageEncoding::Binary.encode(&message)?)?;
+    assert_eq!(decoded.message_type, MessageType::Heartbeat);
+    assert_eq!(decoded.data, message.data);
+    assert_eq!(decoded.timestamp, message.timestamp);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_reads_old_entries_and_migrates() -> Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let mut node = ChaincraftNodeBuilder::new()
+        .with_storage(storage.clone())
+        .build()?;
+    let old = node
+        .create_shared_message("written as json".to_string())
+        .await?;
+
+    // Switch encodings: new entries are binary, old ones still readable
+    node.config.message_encoding = MessageEncoding::Binary;
+    let new = node
+        .create_shared_message("written as binary".to_string())
+        .await?;
+    let stored = storage.get(&new).await?.unwrap();
+    assert_eq!(MessageEncoding::detect(&stored), MessageEncoding::Binary);
+    let message = SharedMessage::from_json(&node.get_object(&new).await?)?;
+    assert_eq!(message.hash, new);
+    assert_eq!(node.get_message(&old).await?.unwrap().hash, old);
+
+    assert_eq!(node.migrate_message_encoding().await?, 1);
+    let stored = storage.get(&old).await?.unwrap();
+    assert_eq!(MessageEncoding::detect(&stored), MessageEncoding::Binary);
+    assert_eq!(node.migrate_message_encoding().await?, 0);
+
+    let metrics = node.metrics();
+    assert!(metrics.get("storage_message_bytes") < metrics.get("storage_json_bytes"));
+    Ok(())
+}
+
+#[test]
+fn test_binary_entries_from_before_sender_still_decode() -> Result<()> {
+    let message = sample();
+    let mut bytes = MessageEncoding::Binary.encode(&message)?;
+    // The older layout is the current one without the trailing sender
+    assert_eq!(bytes.pop(), Some(0));
+    bytes[0] = 0xB1;
+    assert_eq!(MessageEncoding::detect(&bytes), MessageEncoding::Binary);
+    let decoded = MessageEncoding::decode(&bytes)?;
+    assert_eq!(decoded.to_json()?, message.to_json()?);
+    assert_eq!(decoded.sender, None);
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use chaincraft_rust::{
+    migrate::{
+        migrate, namespace, read_dump, verify_copy, write_dump, MigrationConfig, StorageLocation,
+        MESSAGES_NAMESPACE,
+    },
+    storage::{MemoryStorage, Storage},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+/// A memory node with a few messages, their receipts, its message log and
+/// some bookkeeping, dumped to `path`
+async fn dump_lab(path: &std::path::Path) -> Result<Vec<String>> {
+    let storage = Arc::new(MemoryStorage::new());
+    let mut node = ChaincraftNode::builder()
+        .with_storage(storage.clone())
+        .build()?;
+    let mut hashes = Vec::new();
+    for i in 0..7 {
+        hashes.push(node.create_shared_message_with_data(json!(i)).await?);
+    }
+    storage.put("outbox:pending", b"[]".to_vec()).await?;
+    storage.put("scheduler:queue", b"[]".to_vec()).await?;
+    assert_eq!(write_dump(storage.as_ref(), path).await?, 23);
+    Ok(hashes)
+}
+
+#[tokio::test]
+async fn test_migration_copies_every_namespace_and_reports_progress() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let from: StorageLocation = dir.path().join("lab.json").to_str().unwrap().parse()?;
+    let StorageLocation::Dump(path) = &from else {
+        panic!("a bare path is a dump");
+    };
+    let hashes = dump_lab(path).await?;
+    let to: StorageLocation = format!("json:{}", dir.path().join("copy.json").display()).parse()?;
+
+    let mut progress = Vec::new();
+    let config = MigrationConfig::default().with_batch_size(3);
+    let report = migrate(&from, &to, &config, |p| progress.push(p.clone())).await?;
+    assert!(report.is_verified(), "{}", report);
+    assert_eq!(report.copied, 23);
+    assert_eq!(report.sampled, 23);
+    assert_eq!(report.namespaces[MESSAGES_NAMESPACE].source, 7);
+    assert_eq!(report.namespaces[MESSAGES_NAMESPACE].target, 7);
+    assert_eq!(report.namespaces["receipt"].target, 7);
+    assert_eq!(report.namespaces["message_log"].target, 7);
+    assert_eq!(report.namespaces["outbox"].target, 1);
+
+    // Messages go in batches of three, then one batch per other namespace
+    let messages: Vec<usize> = progress
+        .iter()
+        .filter(|p| p.namespace == MESSAGES_NAMESPACE)
+        .map(|p| p.copied)
+        .collect();
+    assert_eq!(messages, vec![3, 6, 7]);
+    let last = progress.last().unwrap();
+    assert_eq!((last.copied_overall, last.total_overall), (23, 23));
+
+    let copy = read_dump(dir.path().join("copy.json")).await?;
+    for hash in &hashes {
+        assert!(copy.exists(hash).await?);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_migration_refuses_a_used_target_unless_overwriting() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let from_path = dir.path().join("lab.json");
+    let to_path = dir.path().join("copy.json");
+    dump_lab(&from_path).await?;
+    let stale = MemoryStorage::new();
+    stale.put("stale:key", b"old".to_vec()).await?;
+    write_dump(&stale, &to_path).await?;
+
+    let from = StorageLocation::Dump(from_path);
+    let to = StorageLocation::Dump(to_path.clone());
+    let refused = migrate(&from, &to, &MigrationConfig::default(), |_| {}).await;
+    assert!(refused
+        .unwrap_err()
+        .to_string()
+        .contains("already holds entries"));
+    assert!(read_dump(&to_path).await?.exists("stale:key").await?);
+
+    let config = MigrationConfig::default().with_overwrite(true);
+    let report = migrate(&from, &to, &config, |_| {}).await?;
+    assert!(report.is_verified());
+    assert!(!report.namespaces.contains_key("stale"));
+
+    assert!(migrate(&from, &from, &config, |_| {}).await.is_err());
+    let missing = StorageLocation::Dump(dir.path().join("missing.json"));
+    assert!(migrate(&missing, &to, &config, |_| {}).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verification_catches_missing_and_altered_entries() -> Result<()> {
+    let source = MemoryStorage::new();
+    let target = MemoryStorage::new();
+    for i in 0..4 {
+        let key = format!("receipt:{}", i);
+        source.put(&key, vec![i]).await?;
+        target.put(&key, vec![i]).await?;
+    }
+    assert!(verify_copy(&source, &target, 4).await?.is_verified());
+
+    target.put("receipt:1", vec![9]).await?;
+    target.delete("receipt:3").await?;
+    let report = verify_copy(&source, &target, 4).await?;
+    assert!(!report.is_verified());
+    assert_eq!(report.mismatched, vec!["receipt:1", "receipt:3"]);
+    assert_eq!(report.namespaces["receipt"].source, 4);
+    assert_eq!(report.namespaces["receipt"].target, 3);
+    assert!(report.to_string().contains("receipt: 4 -> 3 MISMATCH"));
+
+    assert_eq!(namespace("outbox:pending"), "outbox");
+    assert_eq!(namespace(&"ab".repeat(32)), MESSAGES_NAMESPACE);
+    Ok(())
+}
+
+#[cfg(feature = "persistent")]
+#[tokio::test]
+async fn test_migration_to_sled_survives_reopening() -> Result<()> {
+    use chaincraft_rust::storage::SledStorage;
+
+    let dir = tempfile::tempdir()?;
+    let dump = dir.path().join("lab.json");
+    let hashes = dump_lab(&dump).await?;
+    let sled_dir = dir.path().join("data");
+    let to: StorageLocation = format!("sled:{}", sled_dir.display()).parse()?;
+
+    let report =
+        migrate(&StorageLocation::Dump(dump), &to, &MigrationConfig::default(), |_| {}).await?;
+    assert!(report.is_verified(), "{}", report);
+
+    let sled = SledStorage::open(&sled_dir)?;
+    assert_eq!(sled.keys_with_prefix("").await?.len(), 23);
+    let node = ChaincraftNode::builder()
+        .with_storage(Arc::new(sled))
+        .build()?;
+    for hash in &hashes {
+        assert!(node.storage.exists(hash).await?);
+    }
+    Ok(())
+}
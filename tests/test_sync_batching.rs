@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    crypto::{utils, KeyType},
+    shared::{MessageType, SharedMessage},
+    sync_batch::{
+        IngestSummary, MessageSource, SyncAck, SyncBatch, SyncBatchConfig, SyncCursor,
+        SyncReceiver, SyncSender,
+    },
+    ChaincraftNode, NodeMode, PeerId, Result,
+};
+use serde_json::json;
+use std::collections::VecDeque;
+
+/// Log of `0..len`, generated on demand so only batches take up memory
+struct Numbers(u64);
+
+#[async_trait]
+impl MessageSource for Numbers {
+    async fn log_len(&self) -> Result<u64> {
+        Ok(self.0)
+    }
+
+    async fn read_log(&self, start: u64, limit: usize) -> Result<Vec<SharedMessage>> {
+        Ok((start..self.0.min(start + limit as u64))
+            .map(|n| SharedMessage::new(MessageType::Custom("n".to_string()), json!(n)))
+            .collect())
+    }
+}
+
+fn config() -> SyncBatchConfig {
+    SyncBatchConfig {
+        max_batch_messages: 1000,
+        max_batch_bytes: 16 * 1024,
+        max_in_flight_bytes: 64 * 1024,
+    }
+}
+
+/// Send batches until the window is full, checking it stays within bounds
+async fn fill_window(
+    sender: &mut SyncSender,
+    source: &dyn MessageSource,
+    wire: &mut VecDeque<SyncBatch>,
+) -> Result<()> {
+    while let Some(batch) = sender.next_batch(source).await? {
+        assert!(batch.messages.len() <= 1000);
+        assert!(sender.in_flight_bytes() <= 64 * 1024);
+        wire.push_back(batch);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_100k_messages_sync_within_the_in_flight_limit() -> Result<()> {
+    let source = Numbers(100_000);
+    let mut sender = SyncSender::new(config(), SyncCursor::default());
+    let mut receiver = SyncReceiver::default();
+    let mut wire = VecDeque::new();
+    let mut expected = 0;
+    let mut most_in_flight = 0;
+
+    fill_window(&mut sender, &source, &mut wire).await?;
+    while let Some(batch) = wire.pop_front() {
+        let (messages, ack) = receiver.accept(batch)?;
+        for message in messages {
+            assert_eq!(message.data, json!(expected));
+            expected += 1;
+        }
+        most_in_flight = most_in_flight.max(wire.len());
+        sender.on_ack(&ack)?;
+        fill_window(&mut sender, &source, &mut wire).await?;
+    }
+
+    assert_eq!(expected, 100_000);
+    assert_eq!(receiver.cursor(), SyncCursor::new(100_000));
+    assert!(sender.is_finished());
+    assert!(most_in_flight >= 1, "batches should overlap");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_interrupted_sync_resumes_from_the_cursor() -> Result<()> {
+    let source = Numbers(5000);
+    let mut sender = SyncSender::new(config(), SyncCursor::default());
+    let mut receiver = SyncReceiver::default();
+    let mut wire = VecDeque::new();
+    fill_window(&mut sender, &source, &mut wire).await?;
+
+    // Only the first batch arrives before the connection drops
+    let first = wire.pop_front().unwrap();
+    let (messages, _) = receiver.accept(first.clone())?;
+    let mut received = messages.len() as u64;
+    let gap = wire.pop_back().unwrap();
+    assert!(receiver.accept(gap).is_err());
+
+    // A repeated batch is acknowledged again but yields nothing new
+    let (repeated, ack) = receiver.accept(first)?;
+    assert!(repeated.is_empty());
+    assert_eq!(ack.cursor, receiver.cursor());
+
+    let mut sender = SyncSender::new(config(), receiver.cursor());
+    let mut wire = VecDeque::new();
+    fill_window(&mut sender, &source, &mut wire).await?;
+    assert_eq!(wire[0].start, SyncCursor::new(received));
+    while let Some(batch) = wire.pop_front() {
+        let (messages, ack) = receiver.accept(batch)?;
+        for message in messages {
+            assert_eq!(message.data, json!(received));
+            received += 1;
+        }
+        sender.on_ack(&ack)?;
+        fill_window(&mut sender, &source, &mut wire).await?;
+    }
+    assert_eq!(received, 5000);
+    assert!(sender
+        .on_ack(&SyncAck {
+            batch: 1000,
+            cursor: receiver.cursor()
+        })
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_node_log_syncs_in_batches_over_the_wire() -> Result<()> {
+    let mut node = ChaincraftNode::builder().build()?;
+    for n in 0..5 {
+        node.create_shared_message_with_data(json!({ "n": n }))
+            .await?;
+    }
+    let config = SyncBatchConfig {
+        max_batch_messages: 2,
+        ..SyncBatchConfig::default()
+    };
+    let mut sender = SyncSender::new(config, SyncCursor::default());
+    let mut receiver = SyncReceiver::default();
+
+    let mut hashes = Vec::new();
+    let mut batches = 0;
+    while let Some(batch) = sender.next_batch(&node).await? {
+        let batch = SyncBatch::from_message(&batch.to_message()?)?;
+        let last = batch.is_last();
+        let (messages, ack) = receiver.accept(batch)?;
+        hashes.extend(messages.into_iter().map(|message| message.hash));
+        sender.on_ack(&SyncAck::from_message(&ack.to_message()?)?)?;
+        batches += 1;
+        assert_eq!(last, batches == 3);
+    }
+    assert_eq!(hashes, node.message_log.read().await.hashes());
+    assert!(sender.is_finished());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_signed_batches_ingest_in_order_on_an_authenticated_node() -> Result<()> {
+    let key = utils::generate_keypair(KeyType::Ed25519)?.0;
+    let source = ChaincraftNode::builder().build()?;
+    let mut expected = Vec::new();
+    for n in 0..40 {
+        let mut message = SharedMessage::new(MessageType::Custom("n".to_string()), json!(n));
+        if n % 10 != 3 {
+            message.sign(&key)?;
+            expected.push(message.hash.clone());
+        }
+        source.submit_message(message).await?;
+    }
+
+    let node = ChaincraftNode::builder()
+        .with_mode(NodeMode::Authenticated)
+        .with_cpu_threads(4)
+        .build()?;
+    let peer = PeerId::new();
+    let config = SyncBatchConfig {
+        max_batch_messages: 16,
+        ..SyncBatchConfig::default()
+    };
+    let mut sender = SyncSender::new(config, SyncCursor::default());
+    let mut receiver = SyncReceiver::default();
+    let mut total = IngestSummary::default();
+    while let Some(batch) = sender.next_batch(&source).await? {
+        let (ack, summary) = node.ingest_sync_batch(&peer, &mut receiver, batch).await?;
+        sender.on_ack(&ack)?;
+        total.applied += summary.applied;
+        total.refused += summary.refused;
+    }
+    assert_eq!(total.applied, 36);
+    assert_eq!(total.refused, 4);
+    assert_eq!(node.message_log.read().await.hashes(), expected);
+
+    // Messages already stored are skipped
+    let again = source.query_messages(&Default::default()).await?;
+    let summary = node.ingest_messages(&peer, again).await?;
+    assert_eq!((summary.applied, summary.duplicates, summary.refused), (0, 36, 4));
+    Ok(())
+}
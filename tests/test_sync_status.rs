@@ -0,0 +1,80 @@
+use chaincraft_rust::{
+    events::NodeEvent,
+    network::PeerId,
+    sync::{SyncStatus, SyncTracker},
+    ChaincraftNode, Result,
+};
+use serde_json::json;
+
+#[test]
+fn test_status_compares_sequences() {
+    assert_eq!(SyncStatus::compare(3, 5), SyncStatus::Behind { missing: 2 });
+    assert_eq!(SyncStatus::compare(5, 5), SyncStatus::InSync);
+    assert_eq!(SyncStatus::compare(6, 5), SyncStatus::Ahead);
+
+    let tracker = SyncTracker::new();
+    let peer = PeerId::new();
+    assert_eq!(tracker.update_peer(&peer, 4, 4), Some(SyncStatus::InSync));
+    assert_eq!(tracker.update_peer(&peer, 4, 4), None);
+    assert_eq!(tracker.update_local(5), vec![(peer.clone(), SyncStatus::Ahead)]);
+    assert!(tracker.update_local(5).is_empty());
+}
+
+#[tokio::test]
+async fn test_nodes_converge_through_announcements() -> Result<()> {
+    let mut alice = ChaincraftNode::default();
+    let bob = ChaincraftNode::default();
+    for i in 0..3 {
+        alice.create_shared_message_with_data(json!(i)).await?;
+    }
+
+    // Bob learns he is behind from Alice's announcement, which is not stored
+    let announcement = alice.sync_announcement().await;
+    bob.receive_message(alice.id(), announcement).await?;
+    assert_eq!(bob.peer_sync_status(alice.id()), Some(SyncStatus::Behind { missing: 3 }));
+    assert_eq!(bob.message_log.read().await.len(), 0);
+
+    let mut events = bob.subscribe();
+    for hash in alice.message_log.read().await.iter() {
+        let message = chaincraft_rust::SharedMessage::from_json(&alice.get_object(hash).await?)?;
+        bob.receive_message(alice.id(), message).await?;
+    }
+
+    let summary = bob.sync_status().await;
+    assert!(summary.is_converged());
+    assert_eq!(summary.local_sequence, 3);
+    assert_eq!(summary.max_missing(), 0);
+
+    let mut changes = Vec::new();
+    while let Ok(NodeEvent::SyncStatusChanged { status, .. }) = events.try_recv() {
+        changes.push(status);
+    }
+    assert_eq!(
+        changes,
+        vec![
+            SyncStatus::Behind { missing: 2 },
+            SyncStatus::Behind { missing: 1 },
+            SyncStatus::InSync
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_summary_counts_peers_by_status() -> Result<()> {
+    let mut node = ChaincraftNode::default();
+    node.create_shared_message_with_data(json!(1)).await?;
+    let (behind, level, ahead) = (PeerId::new(), PeerId::new(), PeerId::new());
+    node.record_peer_sequence(&behind, 4).await;
+    node.record_peer_sequence(&level, 1).await;
+    node.record_peer_sequence(&ahead, 0).await;
+
+    let summary = node.sync_status().await;
+    assert_eq!((summary.behind(), summary.in_sync(), summary.ahead()), (1, 1, 1));
+    assert_eq!(summary.max_missing(), 3);
+    assert!(!summary.is_converged());
+
+    node.remove_peer(&behind).await?;
+    assert_eq!(node.peer_sync_status(&behind), None);
+    Ok(())
+}
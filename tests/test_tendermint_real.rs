@@ -1,18 +1,23 @@
 use chaincraft_rust::{
-    crypto::ecdsa::ECDSASigner,
+    crypto::{ecdsa::ECDSASigner, utils, KeyType},
     examples::tendermint::{
-        helpers, ConsensusState, TendermintMessageType, TendermintObject, ValidatorInfo,
+        helpers, ConsensusState, TendermintCheckpoint, TendermintMessageType, TendermintObject,
+        ValidatorInfo, ValidatorKey,
     },
     network::PeerId,
+    shared::{MessageType, SharedMessage},
     shared_object::ApplicationObject,
     storage::MemoryStorage,
     ChaincraftNode,
 };
 use serde_json::json;
-use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
+fn key() -> ValidatorKey {
+    ValidatorKey::new(utils::generate_keypair(KeyType::Ed25519).unwrap().1)
+}
+
 async fn create_tendermint_node() -> (ChaincraftNode, TendermintObject) {
     let id = PeerId::new();
     let storage = Arc::new(MemoryStorage::new());
@@ -47,9 +52,9 @@ async fn test_validator_management() {
     let (mut node, mut tendermint) = create_tendermint_node().await;
 
     // Add validators
-    tendermint.add_validator("validator1".to_string(), "pubkey1".to_string(), 100);
-    tendermint.add_validator("validator2".to_string(), "pubkey2".to_string(), 150);
-    tendermint.add_validator("validator3".to_string(), "pubkey3".to_string(), 200);
+    tendermint.add_validator("validator1".to_string(), key(), 100);
+    tendermint.add_validator("validator2".to_string(), key(), 150);
+    tendermint.add_validator("validator3".to_string(), key(), 200);
 
     assert_eq!(tendermint.validators.len(), 3);
     assert_eq!(tendermint.total_voting_power(), 450);
@@ -84,7 +89,7 @@ async fn test_prevote_processing() {
 
     // Add a validator
     let validator_addr = signer.get_public_key_pem().unwrap();
-    tendermint.add_validator(validator_addr.clone(), "pubkey1".to_string(), 100);
+    tendermint.add_validator(validator_addr.clone(), key(), 100);
 
     // Create and process a prevote
     let prevote_msg = helpers::create_prevote_message(
@@ -114,7 +119,7 @@ async fn test_precommit_processing() {
 
     // Add a validator
     let validator_addr = signer.get_public_key_pem().unwrap();
-    tendermint.add_validator(validator_addr.clone(), "pubkey1".to_string(), 100);
+    tendermint.add_validator(validator_addr.clone(), key(), 100);
 
     // Create and process a precommit
     let precommit_msg = helpers::create_precommit_message(
@@ -149,9 +154,9 @@ async fn test_consensus_round_completion() {
     let validator2 = signer2.get_public_key_pem().unwrap();
     let validator3 = signer3.get_public_key_pem().unwrap();
 
-    tendermint.add_validator(validator1.clone(), "pubkey1".to_string(), 100);
-    tendermint.add_validator(validator2.clone(), "pubkey2".to_string(), 100);
-    tendermint.add_validator(validator3.clone(), "pubkey3".to_string(), 100);
+    tendermint.add_validator(validator1.clone(), key(), 100);
+    tendermint.add_validator(validator2.clone(), key(), 100);
+    tendermint.add_validator(validator3.clone(), key(), 100);
 
     let block_hash = "consensus_block_hash";
 
@@ -199,15 +204,17 @@ async fn test_validator_set_message() {
     let validators = vec![
         ValidatorInfo {
             address: "validator1".to_string(),
-            public_key: "pubkey1".to_string(),
+            public_key: key(),
             voting_power: 100,
             active: true,
+            possession: None,
         },
         ValidatorInfo {
             address: "validator2".to_string(),
-            public_key: "pubkey2".to_string(),
+            public_key: key(),
             voting_power: 150,
             active: true,
+            possession: None,
         },
     ];
 
@@ -243,7 +250,7 @@ async fn test_voting_statistics() {
 
     // Add validator and create vote
     let validator_addr = signer.get_public_key_pem().unwrap();
-    tendermint.add_validator(validator_addr.clone(), "pubkey1".to_string(), 100);
+    tendermint.add_validator(validator_addr.clone(), key(), 100);
 
     let prevote_msg = helpers::create_prevote_message(
         1,
@@ -267,38 +274,158 @@ async fn test_voting_statistics() {
     node.close().await.unwrap();
 }
 
-#[tokio::test]
-async fn test_byzantine_fault_tolerance() {
-    let (mut node, mut tendermint) = create_tendermint_node().await;
+fn tendermint_message(data: serde_json::Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("tendermint".to_string()), data)
+}
 
-    // Create 4 validators (can tolerate 1 Byzantine)
-    for i in 1..=4 {
-        tendermint.add_validator(format!("validator{}", i), format!("pubkey{}", i), 100);
+/// Object with a validator of 100 voting power per signer
+fn with_validators(signers: &[ECDSASigner]) -> TendermintObject {
+    let mut tendermint = TendermintObject::new().unwrap();
+    for (i, signer) in signers.iter().enumerate() {
+        tendermint.add_validator(
+            format!("validator{}", i + 1),
+            ValidatorKey::new(signer.public_key().clone()),
+            100,
+        );
     }
+    tendermint
+}
 
-    // Simulate conflicting votes from Byzantine validator
-    let key = (tendermint.current_height, tendermint.current_round);
+fn signers(count: usize) -> Vec<ECDSASigner> {
+    (0..count).map(|_| ECDSASigner::new().unwrap()).collect()
+}
+
+fn prevote(block_hash: &str, validator: usize, signer: &ECDSASigner) -> SharedMessage {
+    tendermint_message(
+        helpers::create_prevote_message(
+            1,
+            0,
+            Some(block_hash.to_string()),
+            format!("validator{}", validator),
+            signer,
+        )
+        .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_byzantine_fault_tolerance() {
+    let signers = signers(4);
+    let mut tendermint = with_validators(&signers);
+    assert_eq!(tendermint.total_voting_power(), 400);
 
     // Byzantine validator votes for two different blocks
-    tendermint
-        .prevotes
-        .entry(key)
-        .or_insert_with(HashMap::new)
-        .insert(
-            "validator1".to_string(),
-            chaincraft_rust::examples::tendermint::Vote {
-                validator: "validator1".to_string(),
-                block_hash: Some("block_hash_1".to_string()),
-                signature: "sig1".to_string(),
-                timestamp: chrono::Utc::now(),
-            },
-        );
+    let first = prevote("block_hash_1", 1, &signers[0]);
+    assert!(tendermint
+        .add_message(first.clone())
+        .await
+        .unwrap()
+        .is_applied());
+    let outcome = tendermint.add_message(first).await.unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "duplicate");
+    let conflicting = prevote("block_hash_2", 1, &signers[0]);
+    assert!(tendermint
+        .add_message(conflicting)
+        .await
+        .unwrap()
+        .is_applied());
+
+    assert!(tendermint.is_tombstoned("validator1"));
+    assert_eq!(tendermint.equivocations.len(), 1);
+    assert_eq!(tendermint.equivocations[0].slashed_power, 100);
+    assert_eq!(tendermint.validators["validator1"].voting_power, 0);
+    assert_eq!(tendermint.total_voting_power(), 300);
+    let outcome = tendermint
+        .add_message(prevote("block_hash_3", 1, &signers[0]))
+        .await
+        .unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "tombstoned");
+
+    // A later validator set does not bring it back
+    let set = helpers::create_validator_set_message(
+        vec![ValidatorInfo {
+            address: "validator1".to_string(),
+            public_key: ValidatorKey::new(signers[0].public_key().clone()),
+            voting_power: 100,
+            active: true,
+            possession: None,
+        }],
+        1,
+    )
+    .unwrap();
+    let outcome = tendermint
+        .add_message(tendermint_message(set))
+        .await
+        .unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "validator_set_unchanged");
+
+    // The honest validators still commit on their own
+    for (i, signer) in signers.iter().enumerate().skip(1) {
+        let precommit = helpers::create_precommit_message(
+            1,
+            0,
+            Some("block_hash_1".to_string()),
+            format!("validator{}", i + 1),
+            signer,
+        )
+        .unwrap();
+        tendermint
+            .add_message(tendermint_message(precommit))
+            .await
+            .unwrap();
+    }
+    assert_eq!(tendermint.current_height, 2);
+    assert_eq!(tendermint.blocks.last().unwrap().hash, "block_hash_1");
 
-    // This shouldn't affect consensus if honest validators outnumber Byzantine ones
+    // Reset gives the power back for the replay to take again
+    tendermint.reset().await.unwrap();
+    assert!(!tendermint.is_tombstoned("validator1"));
     assert_eq!(tendermint.total_voting_power(), 400);
-    assert!(tendermint.has_majority(267)); // Need > 2/3
+}
 
-    node.close().await.unwrap();
+#[tokio::test]
+async fn test_double_sign_evidence_convinces_other_nodes() {
+    let signers = signers(4);
+    let mut witness = with_validators(&signers);
+    witness
+        .add_message(prevote("block_hash_1", 2, &signers[1]))
+        .await
+        .unwrap();
+    witness
+        .add_message(prevote("block_hash_2", 2, &signers[1]))
+        .await
+        .unwrap();
+    let evidence = witness.equivocations[0].evidence.clone();
+    assert_eq!(evidence.validator, "validator2");
+
+    // A node that saw only one of the votes acts on the evidence
+    let mut other = with_validators(&signers);
+    let report = tendermint_message(helpers::create_evidence_message(evidence.clone()).unwrap());
+
+    let mut tampered = evidence.clone();
+    tampered.second.block_hash = Some("block_hash_3".to_string());
+    let outcome = other
+        .add_message(tendermint_message(helpers::create_evidence_message(tampered).unwrap()))
+        .await
+        .unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "invalid_evidence");
+    let mut same = evidence.clone();
+    same.second = same.first.clone();
+    let outcome = other
+        .add_message(tendermint_message(helpers::create_evidence_message(same).unwrap()))
+        .await
+        .unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "invalid_evidence");
+
+    assert!(other
+        .add_message(report.clone())
+        .await
+        .unwrap()
+        .is_applied());
+    assert!(other.is_tombstoned("validator2"));
+    assert_eq!(other.total_voting_power(), 300);
+    let outcome = other.add_message(report).await.unwrap();
+    assert_eq!(outcome.reason().unwrap().code, "already_tombstoned");
 }
 
 #[tokio::test]
@@ -306,6 +433,20 @@ async fn test_message_integration_with_node() {
     let (mut node, _) = create_tendermint_node().await;
     let signer = ECDSASigner::new().unwrap();
 
+    // Proposals and votes are only accepted from validators of the set
+    let validator = ValidatorInfo {
+        address: signer.get_public_key_pem().unwrap(),
+        public_key: ValidatorKey::new(signer.public_key().clone()),
+        voting_power: 100,
+        active: true,
+        possession: None,
+    };
+    node.create_shared_message_with_data(
+        helpers::create_validator_set_message(vec![validator], 1).unwrap(),
+    )
+    .await
+    .unwrap();
+
     // Create a full proposal message and send to node
     let proposal_msg = helpers::create_proposal_message(
         1,
@@ -359,7 +500,7 @@ async fn test_tendermint_reset() {
     let (mut node, mut tendermint) = create_tendermint_node().await;
 
     // Add some state
-    tendermint.add_validator("validator1".to_string(), "pubkey1".to_string(), 100);
+    tendermint.add_validator("validator1".to_string(), key(), 100);
     tendermint.current_height = 5;
     tendermint.current_round = 2;
 
@@ -376,3 +517,93 @@ async fn test_tendermint_reset() {
 
     node.close().await.unwrap();
 }
+
+fn block_commit_message(height: u64, block_hash: &str) -> SharedMessage {
+    let commit = TendermintMessageType::BlockCommit {
+        height,
+        block_hash: block_hash.to_string(),
+        commit_signatures: vec![],
+        timestamp: chrono::Utc::now(),
+    };
+    SharedMessage::new(
+        MessageType::Custom("tendermint".to_string()),
+        serde_json::to_value(commit).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_checkpoint_and_prune_keeps_headers() {
+    let mut tendermint = TendermintObject::new().unwrap();
+    tendermint.add_validator("validator1".to_string(), key(), 100);
+    for i in 1..=4 {
+        tendermint.commit_block(format!("block_{}", i)).unwrap();
+    }
+
+    let checkpoint = tendermint.create_checkpoint().unwrap();
+    assert_eq!(checkpoint.height, 4);
+    assert_eq!(checkpoint.header.hash, "block_4");
+    assert_eq!(checkpoint.app_digest, tendermint.app_digest);
+    assert!(checkpoint.verify());
+
+    let pruned = tendermint.prune_to_checkpoint();
+    assert_eq!(pruned, 4); // genesis + blocks 1..=3
+    assert_eq!(tendermint.blocks.len(), 1);
+
+    let heights: Vec<u64> = tendermint.headers().iter().map(|h| h.height).collect();
+    assert_eq!(heights, vec![0, 1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_restore_from_checkpoint_matches_replay() {
+    let mut original = TendermintObject::new().unwrap();
+    original.add_validator("validator1".to_string(), key(), 100);
+    original.commit_block("block_1".to_string()).unwrap();
+    original.commit_block("block_2".to_string()).unwrap();
+    let checkpoint = original.create_checkpoint().unwrap();
+
+    let mut restored = TendermintObject::restore_from_checkpoint(checkpoint.clone()).unwrap();
+    assert_eq!(restored.current_height, 3);
+    assert_eq!(restored.validators.len(), 1);
+    assert_eq!(restored.latest_checkpoint(), Some(&checkpoint));
+
+    original.commit_block("block_3".to_string()).unwrap();
+    restored.commit_block("block_3".to_string()).unwrap();
+    assert_eq!(restored.app_digest, original.app_digest);
+    assert_eq!(restored.blocks.last().unwrap().previous_hash, "block_2");
+
+    let mut tampered = checkpoint;
+    tampered.app_digest = "forged".to_string();
+    assert!(TendermintObject::restore_from_checkpoint(tampered).is_err());
+}
+
+#[tokio::test]
+async fn test_periodic_checkpoints_are_persisted() {
+    let storage = Arc::new(MemoryStorage::new());
+    let mut tendermint = TendermintObject::new()
+        .unwrap()
+        .with_checkpoint_interval(2)
+        .with_checkpoint_store(storage.clone());
+
+    for height in 1..=5 {
+        tendermint
+            .add_message(block_commit_message(height, &format!("block_{}", height)))
+            .await
+            .unwrap();
+    }
+
+    let heights: Vec<u64> = tendermint.checkpoints.iter().map(|c| c.height).collect();
+    assert_eq!(heights, vec![2, 4]);
+
+    let latest = TendermintCheckpoint::load_latest(storage.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(latest.height, 4);
+    assert!(TendermintCheckpoint::load(storage.as_ref(), 2)
+        .await
+        .unwrap()
+        .is_some());
+
+    let restored = TendermintObject::restore_from_checkpoint(latest).unwrap();
+    assert_eq!(restored.current_height, 5);
+}
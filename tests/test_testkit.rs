@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use chaincraft_rust::{
+    shared::{SharedMessage, SharedObjectId},
+    shared_object::{ApplicationObject, ApplyOutcome, SimpleSharedNumber},
+    testkit::{harness_epoch, select, ObjectHarness},
+    Result,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[test]
+fn test_selector_walks_keys_and_indices() {
+    let state = json!({ "rooms": [{ "name": "lobby", "members": ["a", "b"] }], "count": 1 });
+    assert_eq!(select(&state, "$.rooms[0].name"), Some(&json!("lobby")));
+    assert_eq!(select(&state, "rooms[0].members[1]"), Some(&json!("b")));
+    assert_eq!(select(&state, "$"), Some(&state));
+    assert_eq!(select(&state, "count"), Some(&json!(1)));
+    assert_eq!(select(&state, "rooms[1]"), None);
+    assert_eq!(select(&state, "rooms[x]"), None);
+}
+
+#[tokio::test]
+async fn test_harness_feeds_snapshots_and_restores() -> Result<()> {
+    let mut harness = ObjectHarness::new(SimpleSharedNumber::new());
+    assert_eq!(harness.now(), harness_epoch());
+
+    harness.apply(json!(5)).await?;
+    harness.advance(Duration::from_secs(30));
+    harness.apply(json!(7)).await?;
+    assert_eq!(
+        harness.history()[1].message.timestamp,
+        harness_epoch() + chrono::Duration::seconds(30)
+    );
+    harness.assert_state("$.number", json!(12)).await;
+
+    // Duplicates are ignored and messages the object refuses are reported
+    assert_eq!(
+        harness
+            .feed(json!(5))
+            .await?
+            .unwrap()
+            .reason()
+            .unwrap()
+            .code,
+        "duplicate"
+    );
+    assert_eq!(harness.feed(json!("text")).await?, None);
+    harness.assert_digests_monotonic();
+
+    let saved = harness.snapshot();
+    harness.apply(json!(100)).await?;
+    assert_eq!(harness.select("number").await?, json!(112));
+    harness.restore(saved).await?;
+    harness.assert_state("number", json!(12)).await;
+    assert_eq!(harness.history().len(), 4);
+    assert_eq!(harness.now(), harness_epoch() + chrono::Duration::seconds(30));
+
+    let id = harness.object().id().clone();
+    assert_eq!(harness.directory().state(&id).await?["number"], json!(12));
+    Ok(())
+}
+
+/// Keeps the digest fixed no matter what it applies
+#[derive(Debug, Clone)]
+struct StuckDigest {
+    id: SharedObjectId,
+    applied: u64,
+}
+
+#[async_trait]
+impl ApplicationObject for StuckDigest {
+    fn id(&self) -> &SharedObjectId {
+        &self.id
+    }
+
+    fn type_name(&self) -> &'static str {
+        "StuckDigest"
+    }
+
+    async fn is_valid(&self, _message: &SharedMessage) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn add_message(&mut self, _message: SharedMessage) -> Result<ApplyOutcome> {
+        self.applied += 1;
+        Ok(ApplyOutcome::Applied)
+    }
+
+    fn is_merkleized(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_digest(&self) -> Result<String> {
+        Ok("stuck".to_string())
+    }
+
+    async fn is_valid_digest(&self, _digest: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn gossip_messages(&self, _digest: Option<&str>) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_messages_since_digest(&self, _digest: &str) -> Result<Vec<SharedMessage>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_state(&self) -> Result<Value> {
+        Ok(json!({ "applied": self.applied }))
+    }
+
+    async fn reset(&mut self) -> Result<()> {
+        self.applied = 0;
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn ApplicationObject> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+#[should_panic(expected = "digest did not change")]
+async fn test_harness_catches_digest_that_does_not_move() {
+    let mut harness = ObjectHarness::new(StuckDigest {
+        id: SharedObjectId::new(),
+        applied: 0,
+    });
+    harness.apply(json!({ "any": "thing" })).await.unwrap();
+    harness.assert_state("applied", json!(1)).await;
+    harness.assert_digests_monotonic();
+}
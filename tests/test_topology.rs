@@ -0,0 +1,101 @@
+#![cfg(feature = "grpc")]
+
+use chaincraft_rust::{
+    network::grpc::GrpcTransport,
+    network::topology::{crawl, TopologyConfig},
+    network::{PeerId, PeerInfo},
+    ChaincraftNode, Result,
+};
+use std::time::Duration;
+
+fn any_port() -> std::net::SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+async fn start() -> Result<(ChaincraftNode, GrpcTransport)> {
+    let node = ChaincraftNode::builder().build()?;
+    let transport = GrpcTransport::bind_node(&node, any_port()).await?;
+    Ok((node, transport))
+}
+
+async fn link(from: &ChaincraftNode, to: &ChaincraftNode, transport: &GrpcTransport) -> Result<()> {
+    from.add_peer(PeerInfo::new(to.id().clone(), transport.local_addr()))
+        .await
+}
+
+#[tokio::test]
+async fn test_crawl_follows_reported_peers() -> Result<()> {
+    // a -> b -> c, and c -> a: a ring the crawl reaches from any node
+    let (a, a_transport) = start().await?;
+    let (b, b_transport) = start().await?;
+    let (c, c_transport) = start().await?;
+    link(&a, &b, &b_transport).await?;
+    link(&b, &c, &c_transport).await?;
+    link(&c, &a, &a_transport).await?;
+
+    let snapshot = crawl(&b_transport.local_addr().to_string(), &TopologyConfig::default()).await?;
+    assert_eq!(snapshot.nodes.len(), 3);
+    assert!(snapshot.unreachable().is_empty());
+    let (a, b, c) = (a.id().to_string(), b.id().to_string(), c.id().to_string());
+    let mut edges = snapshot.edges();
+    edges.sort();
+    let mut expected =
+        vec![(a.as_str(), b.as_str()), (b.as_str(), c.as_str()), (c.as_str(), a.as_str())];
+    expected.sort();
+    assert_eq!(edges, expected);
+    assert_eq!(snapshot.nodes[&c].address, c_transport.local_addr().to_string());
+    assert_eq!(snapshot.to_string(), "3 nodes, 3 links");
+
+    let json = serde_json::to_value(&snapshot)?;
+    assert_eq!(json["nodes"][&a]["peers"], serde_json::json!([b]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unreachable_peers_are_kept_and_dashed() -> Result<()> {
+    let (seed, seed_transport) = start().await?;
+    // Bind and release a port so nothing listens on it
+    let port = std::net::TcpListener::bind(any_port())?
+        .local_addr()?
+        .port();
+    let gone = PeerId::new();
+    seed.add_peer(PeerInfo::new(gone.clone(), format!("127.0.0.1:{}", port).parse().unwrap()))
+        .await?;
+
+    let config = TopologyConfig::default().with_timeout(Duration::from_secs(2));
+    let snapshot = crawl(&seed_transport.local_addr().to_string(), &config).await?;
+    let gone = gone.to_string();
+    assert_eq!(snapshot.unreachable(), vec![gone.as_str()]);
+    assert!(snapshot.nodes[&gone].error.is_some());
+    assert!(snapshot.nodes[&seed.id().to_string()].is_reachable());
+    assert_eq!(snapshot.to_string(), "2 nodes, 1 links, 1 unreachable");
+
+    let dot = snapshot.to_dot();
+    assert!(dot.starts_with("digraph chaincraft {"));
+    assert!(dot.contains(&format!("\"{}\" -> \"{}\";", seed.id(), gone)));
+    assert!(dot
+        .lines()
+        .any(|line| line.contains(&gone) && line.contains("style=dashed")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_crawl_stops_at_max_nodes() -> Result<()> {
+    let (seed, seed_transport) = start().await?;
+    let mut others = Vec::new();
+    for _ in 0..3 {
+        let (node, transport) = start().await?;
+        link(&seed, &node, &transport).await?;
+        others.push((node, transport));
+    }
+
+    let config = TopologyConfig::default().with_max_nodes(2);
+    let snapshot = crawl(&seed_transport.local_addr().to_string(), &config).await?;
+    assert_eq!(snapshot.nodes.len(), 2);
+    // The seed still reports all its peers, found or not
+    assert_eq!(snapshot.nodes[&seed.id().to_string()].peers.len(), 3);
+
+    let unreachable = crawl("127.0.0.1:1", &config.with_timeout(Duration::from_secs(1))).await;
+    assert!(unreachable.is_err());
+    Ok(())
+}
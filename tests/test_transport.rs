@@ -0,0 +1,157 @@
+use chaincraft_rust::{
+    network::link::{Latency, LinkProfile},
+    network::transport::{InMemoryNetwork, InMemoryTransport, Transport, TransportFrame},
+    network::PeerId,
+    rng::RngProvider,
+    shared::{MessageType, SharedMessage},
+    Result,
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::{timeout, Instant};
+
+#[tokio::test]
+async fn test_in_memory_transport_delivers_messages() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let alice = network.connect(PeerId::new());
+    let bob = network.connect(PeerId::new());
+    assert_eq!(network.peers().len(), 2);
+
+    let message = SharedMessage::new(MessageType::Custom("hello".to_string()), json!("hi bob"));
+    alice.send(bob.local_id(), &message).await?;
+
+    let (from, received) = bob.recv().await?;
+    assert_eq!(&from, alice.local_id());
+    assert_eq!(received.hash, message.hash);
+    assert!(received.verify_hash());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_to_disconnected_peer_fails() {
+    let network = InMemoryNetwork::new();
+    let alice = network.connect(PeerId::new());
+    let bob = network.connect(PeerId::new());
+    network.disconnect(bob.local_id());
+
+    let message = SharedMessage::new(MessageType::Heartbeat, json!(null));
+    assert!(alice.send(bob.local_id(), &message).await.is_err());
+}
+
+#[test]
+fn test_transport_frame_roundtrip() -> Result<()> {
+    let frame = TransportFrame::new(
+        PeerId::new(),
+        PeerId::new(),
+        SharedMessage::new(MessageType::Heartbeat, json!({"seq": 1})),
+    );
+    let decoded = TransportFrame::from_bytes(&frame.to_bytes()?)?;
+    assert_eq!(decoded.to, frame.to);
+    assert_eq!(decoded.message.hash, frame.message.hash);
+
+    assert!(TransportFrame::from_bytes(b"not a frame").is_err());
+    Ok(())
+}
+
+fn numbered(n: u64) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("seq".to_string()), json!(n))
+}
+
+/// Everything `endpoint` receives until the network has been quiet for a minute
+async fn drain(endpoint: &InMemoryTransport) -> Vec<u64> {
+    let mut received = Vec::new();
+    while let Ok(Ok((_, message))) = timeout(Duration::from_secs(60), endpoint.recv()).await {
+        received.push(message.data.as_u64().unwrap());
+    }
+    received
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_link_latency_sets_round_trip_time() -> Result<()> {
+    let network = InMemoryNetwork::new();
+    let alice = network.connect(PeerId::new());
+    let bob = network.connect(PeerId::new());
+    let rtt = Duration::from_millis(200);
+    network.set_link_between(
+        alice.local_id(),
+        bob.local_id(),
+        LinkProfile::perfect().with_rtt(rtt),
+    );
+
+    let started = Instant::now();
+    alice.send(bob.local_id(), &numbered(1)).await?;
+    let (from, ping) = bob.recv().await?;
+    assert_eq!(started.elapsed(), rtt / 2);
+    bob.send(&from, &ping).await?;
+    alice.recv().await?;
+    assert_eq!(started.elapsed(), rtt);
+
+    // Adjusted at runtime; other links were never slowed down
+    network.reset_link(alice.local_id(), bob.local_id());
+    assert!(network
+        .link_profile(alice.local_id(), bob.local_id())
+        .is_perfect());
+    let started = Instant::now();
+    alice.send(bob.local_id(), &numbered(2)).await?;
+    bob.recv().await?;
+    assert_eq!(started.elapsed(), Duration::ZERO);
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_seeded_loss_is_reproducible() -> Result<()> {
+    let lossy = LinkProfile::perfect()
+        .with_latency_distribution(Latency::Normal {
+            mean: Duration::from_millis(100),
+            std_dev: Duration::from_millis(20),
+        })
+        .with_drop_rate(0.05);
+
+    let mut runs = Vec::new();
+    for _ in 0..2 {
+        let ids = RngProvider::seeded(1);
+        let network = InMemoryNetwork::with_seed(42);
+        network.set_default_link(lossy);
+        let alice = network.connect(PeerId::new_with_rng(&ids));
+        let bob = network.connect(PeerId::new_with_rng(&ids));
+        for n in 0..200 {
+            alice.send(bob.local_id(), &numbered(n)).await?;
+        }
+        let received = drain(&bob).await;
+        let stats = network.link_stats(alice.local_id(), bob.local_id());
+        assert_eq!(stats.sent, 200);
+        assert_eq!(stats.dropped as usize, 200 - received.len());
+        // Jittery latency does not reorder a link by itself
+        assert!(received.windows(2).all(|w| w[0] < w[1]));
+        runs.push(received);
+    }
+    assert_eq!(runs[0], runs[1]);
+    assert!((170..200).contains(&runs[0].len()));
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_reordered_frames_overtake_frames_in_flight() -> Result<()> {
+    let network = InMemoryNetwork::with_seed(3);
+    let alice = network.connect(PeerId::new());
+    let bob = network.connect(PeerId::new());
+    let profile = LinkProfile::perfect()
+        .with_latency(Duration::from_millis(50))
+        .with_jitter(Duration::from_millis(10))
+        .with_reorder_rate(0.3);
+    network.set_link(alice.local_id(), bob.local_id(), profile);
+    assert_eq!(network.link_profile(alice.local_id(), bob.local_id()), profile);
+    assert!(network
+        .link_profile(bob.local_id(), alice.local_id())
+        .is_perfect());
+
+    for n in 0..50 {
+        alice.send(bob.local_id(), &numbered(n)).await?;
+    }
+    let received = drain(&bob).await;
+    let stats = network.link_stats(alice.local_id(), bob.local_id());
+    assert_eq!(received.len(), 50);
+    assert!(stats.reordered > 0);
+    assert!(received.windows(2).any(|w| w[0] > w[1]));
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use chaincraft_rust::{
+    network::{
+        transport::Transport,
+        udp::{UdpGossipConfig, UdpTransport},
+    },
+    shared::{MessageType, SharedMessage},
+    ChaincraftNode, PeerId, Result,
+};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+async fn started_nodes(count: usize) -> Result<Vec<ChaincraftNode>> {
+    let mut nodes = Vec::new();
+    for _ in 0..count {
+        let mut node = ChaincraftNode::builder()
+            .port(0)
+            .with_udp_gossip(UdpGossipConfig::default())
+            .build()?;
+        node.start().await?;
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+/// Wait until every node stores `hash`
+async fn propagated(nodes: &[ChaincraftNode], hash: &str) -> Result<bool> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        let mut missing = false;
+        for node in nodes {
+            missing |= node.get_message(hash).await?.is_none();
+        }
+        if !missing {
+            return Ok(true);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    Ok(false)
+}
+
+#[tokio::test]
+async fn test_messages_reach_every_peer_of_a_mesh() -> Result<()> {
+    let mut nodes = started_nodes(3).await?;
+    assert!(nodes.iter().all(|node| node.port() != 0));
+    for i in 0..nodes.len() {
+        for j in 0..nodes.len() {
+            if i != j {
+                let addr = format!("{}:{}", nodes[j].host(), nodes[j].port());
+                nodes[i].connect_to_peer(&addr).await?;
+            }
+        }
+    }
+
+    let hash = nodes[0]
+        .create_shared_message("Hello, world!".to_string())
+        .await?;
+    assert!(propagated(&nodes, &hash).await?);
+
+    // Once heard from, a peer added by address carries its real id
+    let peers: Vec<PeerId> = nodes[1]
+        .get_peers()
+        .await
+        .into_iter()
+        .map(|peer| peer.id)
+        .collect();
+    assert_eq!(peers.len(), 2);
+    assert!(peers.contains(nodes[0].id()));
+
+    for node in &mut nodes {
+        node.stop().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_messages_are_relayed_along_a_line() -> Result<()> {
+    let mut nodes = started_nodes(3).await?;
+    // 0 - 1 - 2: node 2 only hears node 0's message through node 1
+    for (i, j) in [(0, 1), (1, 0), (1, 2), (2, 1)] {
+        let addr = format!("{}:{}", nodes[j].host(), nodes[j].port());
+        nodes[i].connect_to_peer(&addr).await?;
+    }
+
+    let hash = nodes[0]
+        .create_shared_message_with_data(json!({ "n": 1 }))
+        .await?;
+    assert!(propagated(&nodes, &hash).await?);
+    // Each node stored it once, however many copies arrived
+    for node in &nodes {
+        assert_eq!(node.message_log.read().await.len(), 1);
+    }
+    assert!(nodes[1].metrics().get("udp_gossip_received") >= 1);
+
+    for node in &mut nodes {
+        node.stop().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transport_round_trip_and_size_limit() -> Result<()> {
+    let a = UdpTransport::bind(PeerId::new(), "127.0.0.1:0".parse().unwrap()).await?;
+    let b = UdpTransport::bind(PeerId::new(), "127.0.0.1:0".parse().unwrap()).await?;
+    a.add_peer(b.local_id().clone(), b.local_addr());
+
+    let message = SharedMessage::new(MessageType::Custom("note".to_string()), json!("hi"));
+    a.send(b.local_id(), &message).await?;
+    let (from, received) = b.recv().await?;
+    assert_eq!(&from, a.local_id());
+    assert_eq!(received.hash, message.hash);
+    // b learned where a is from the datagram
+    assert_eq!(b.peer_addr(a.local_id()), Some(a.local_addr()));
+
+    let huge =
+        SharedMessage::new(MessageType::Custom("note".to_string()), json!("x".repeat(70_000)));
+    assert!(a.send(b.local_id(), &huge).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use chaincraft_rust::{
+    examples::liveness::{LivenessConfig, LivenessTracker},
+    examples::randomness_beacon::{self, BeaconValidator, RandomnessBeaconObject},
+    examples::tendermint::{self, TendermintObject, ValidatorKey},
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    Result,
+};
+use common::{code, key};
+
+fn message(data: serde_json::Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("consensus".to_string()), data)
+}
+
+#[test]
+fn test_tracker_jails_only_over_a_full_window() {
+    let mut tracker = LivenessTracker::new(LivenessConfig::new(4, 0.5, 3));
+    // Two misses out of four is not more than half
+    for (at, performed) in [(1, false), (2, true), (3, false), (4, true)] {
+        assert!(!tracker.record("v1", performed, at));
+    }
+    // The window slides: the oldest miss drops out as a new one comes in
+    assert!(!tracker.record("v1", false, 5));
+    assert!(tracker.record("v1", false, 6));
+    assert_eq!(tracker.jailed_until("v1"), Some(9));
+    assert_eq!(tracker.validator("v1").unwrap().missed, 4);
+
+    // Duties are not recorded while in jail
+    assert!(!tracker.record("v1", false, 7));
+    assert!(!tracker.unjail("v1", 8));
+    assert!(tracker.unjail("v1", 9));
+    assert!(!tracker.is_jailed("v1"));
+    assert!(tracker.validator("v1").unwrap().recent.is_empty());
+    assert!(tracker.jailed().is_empty());
+}
+
+/// Propose from the expected proposer and precommit from `online` validators
+async fn run_height(tendermint: &mut TendermintObject, online: &[&str]) -> Result<()> {
+    let height = tendermint.current_height;
+    let block_hash = format!("block-{}", height);
+    let proposer = tendermint.proposer_for(height, 0).unwrap();
+    if online.contains(&proposer.as_str()) {
+        let proposal = tendermint::helpers::create_proposal_message(
+            height,
+            0,
+            block_hash.clone(),
+            proposer.clone(),
+            &key(&proposer),
+        )?;
+        tendermint.add_message(message(proposal)).await?;
+    }
+    for validator in online {
+        let precommit = tendermint::helpers::create_precommit_message(
+            height,
+            0,
+            Some(block_hash.clone()),
+            validator.to_string(),
+            &key(validator),
+        )?;
+        tendermint.add_message(message(precommit)).await?;
+    }
+    assert_eq!(tendermint.current_height, height + 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tendermint_jails_offline_validator() -> Result<()> {
+    let mut tendermint = TendermintObject::new()?.with_liveness(LivenessConfig::new(2, 0.5, 2));
+    for address in ["v1", "v2", "v3", "v4"] {
+        let key = ValidatorKey::new(key(address).public_key().clone());
+        tendermint.add_validator(address.to_string(), key, 1);
+    }
+    let online = ["v1", "v2", "v3"];
+
+    run_height(&mut tendermint, &online).await?;
+    assert!(!tendermint.is_jailed("v4"));
+    run_height(&mut tendermint, &online).await?;
+    assert!(tendermint.is_jailed("v4"));
+    assert_eq!(tendermint.total_voting_power(), 3);
+    for round in 0..3 {
+        assert_ne!(
+            tendermint
+                .proposer_for(tendermint.current_height, round)
+                .as_deref(),
+            Some("v4")
+        );
+    }
+
+    let late_vote = tendermint::helpers::create_precommit_message(
+        tendermint.current_height,
+        0,
+        None,
+        "v4".to_string(),
+        &key("v4"),
+    )?;
+    assert_eq!(code(&tendermint.add_message(message(late_vote)).await?), Some("jailed"));
+
+    let unjail =
+        |height| tendermint::helpers::create_unjail_message("v4".to_string(), height, &key("v4"));
+    let outcome = tendermint
+        .add_message(message(unjail(tendermint.current_height)?))
+        .await?;
+    assert_eq!(code(&outcome), Some("jail_cooldown"));
+
+    // Three of three remaining validators keep committing meanwhile
+    run_height(&mut tendermint, &online).await?;
+    let outcome = tendermint
+        .add_message(message(unjail(tendermint.current_height)?))
+        .await?;
+    assert!(outcome.is_applied());
+    assert!(!tendermint.is_jailed("v4"));
+    assert_eq!(tendermint.total_voting_power(), 4);
+    Ok(())
+}
+
+async fn contribute(beacon: &mut RandomnessBeaconObject, validator: &str) -> Result<ApplyOutcome> {
+    let round = beacon.current_round;
+    let vrf = randomness_beacon::helpers::create_vrf_proof_message(
+        round,
+        "seed".to_string(),
+        validator.to_string(),
+        &key(validator),
+    )?;
+    let outcome = beacon.add_message(message(vrf)).await?;
+    if outcome.is_applied() {
+        let partial = randomness_beacon::helpers::create_partial_signature_message(
+            round,
+            validator.to_string(),
+            format!("partial-{}", validator),
+            &key(validator),
+        )?;
+        beacon.add_message(message(partial)).await?;
+    }
+    Ok(outcome)
+}
+
+#[tokio::test]
+async fn test_beacon_jails_validator_missing_rounds() -> Result<()> {
+    let mut beacon = RandomnessBeaconObject::new(60, 1)?
+        .with_epochs(100)
+        .with_liveness(LivenessConfig::new(2, 0.5, 2));
+    for address in ["v1", "v2", "v3", "v4", "v5"] {
+        beacon.register_validator(BeaconValidator {
+            address: address.to_string(),
+            public_key: key(address).get_public_key_pem()?,
+            vrf_key: key(address).get_public_key_pem()?,
+            stake: 100,
+            active: true,
+            last_participation: None,
+        })?;
+    }
+    assert_eq!(beacon.threshold, 4);
+
+    for _ in 0..2 {
+        for validator in ["v1", "v2", "v3", "v4"] {
+            contribute(&mut beacon, validator).await?;
+        }
+    }
+    assert_eq!(beacon.current_round, 3);
+    assert!(beacon.is_jailed("v5"));
+    // The jailed validator no longer counts towards the threshold
+    assert_eq!(beacon.threshold, 3);
+    assert_eq!(code(&contribute(&mut beacon, "v5").await?), Some("jailed"));
+
+    let unjail = randomness_beacon::helpers::create_unjail("v5".to_string(), &key("v5"))?;
+    let outcome = beacon.add_message(message(unjail.clone())).await?;
+    assert_eq!(code(&outcome), Some("jail_cooldown"));
+
+    for validator in ["v1", "v2", "v3"] {
+        contribute(&mut beacon, validator).await?;
+    }
+    assert_eq!(beacon.current_round, 4);
+    assert!(beacon.add_message(message(unjail)).await?.is_applied());
+    assert!(!beacon.is_jailed("v5"));
+    assert_eq!(beacon.threshold, 4);
+    assert!(contribute(&mut beacon, "v5").await?.is_applied());
+    Ok(())
+}
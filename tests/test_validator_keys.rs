@@ -0,0 +1,62 @@
+use chaincraft_rust::{
+    crypto::{ecdsa::ECDSASigner, utils, KeyType},
+    examples::tendermint::{TendermintObject, ValidatorInfo, ValidatorKey},
+    Result,
+};
+use serde_json::json;
+
+fn validator(key_type: KeyType) -> Result<TendermintObject> {
+    let private_key = utils::generate_keypair(key_type)?.0;
+    TendermintObject::with_signer(ECDSASigner::from_private_key(private_key))
+}
+
+#[test]
+fn test_validator_keys_are_tagged_with_their_curve() -> Result<()> {
+    for key_type in [KeyType::Ed25519, KeyType::Secp256k1] {
+        let key = ValidatorKey::new(utils::generate_keypair(key_type)?.1);
+        let info = ValidatorInfo {
+            address: "v1".to_string(),
+            public_key: key.clone(),
+            voting_power: 10,
+            active: true,
+            possession: None,
+        };
+        let encoded = serde_json::to_value(&info)?;
+        assert_eq!(encoded["public_key"], json!({ "key_type": key_type, "key": key.to_hex() }));
+        let decoded: ValidatorInfo = serde_json::from_value(encoded)?;
+        assert_eq!(decoded.public_key.key_type(), key_type);
+        assert_eq!(decoded, info);
+    }
+
+    // The tag decides how the key is read, not its length
+    let ed25519 = utils::generate_keypair(KeyType::Ed25519)?.1.to_hex();
+    let mislabeled = json!({ "key_type": "Secp256k1", "key": ed25519 });
+    assert!(serde_json::from_value::<ValidatorKey>(mislabeled).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_mixed_curve_validator_set_verifies_each_signature() -> Result<()> {
+    let ed = validator(KeyType::Ed25519)?;
+    let secp = validator(KeyType::Secp256k1)?;
+    let mut observer = TendermintObject::new()?;
+    observer.add_validator("ed".to_string(), ed.validator_key(), 1);
+    observer.add_validator("secp".to_string(), secp.validator_key(), 1);
+
+    let message = b"prevote:1:0:block";
+    let ed_signature = hex::encode(ed.signer.sign(message)?.to_bytes());
+    let secp_signature = hex::encode(secp.signer.sign(message)?.to_bytes());
+
+    assert!(observer.verify_validator_signature("ed", message, &ed_signature)?);
+    assert!(observer.verify_validator_signature("secp", message, &secp_signature)?);
+    assert!(!observer.verify_validator_signature("ed", b"tampered", &ed_signature)?);
+    // A signature of the other curve is not mistaken for one of the validator's
+    assert!(!matches!(
+        observer.verify_validator_signature("secp", message, &ed_signature),
+        Ok(true)
+    ));
+    assert!(observer
+        .verify_validator_signature("unknown", message, &ed_signature)
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use chaincraft_rust::{
+    crypto::{
+        ecdsa::ECDSASigner,
+        vdf::{VdfProof, VerifiableDelayFunction, MAX_VDF_ITERATIONS, VDF_PROOF_CUTOFF},
+    },
+    examples::randomness_beacon::{helpers, BeaconValidator, RandomnessBeaconObject},
+    shared::{MessageType, SharedMessage},
+    shared_object::ApplicationObject,
+    Result,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_proof_verifies_for_its_input_and_delay() -> Result<()> {
+    let vdf = VerifiableDelayFunction::new();
+    let proof = vdf.prove(b"seed".to_vec(), 1_000).await?;
+    assert_eq!(proof, VerifiableDelayFunction::prove_sync(b"seed", 1_000));
+    // 1000 -> 500 -> 250 -> 125 -> 63
+    assert_eq!(proof.midpoints.len(), 4);
+    assert_eq!(proof.output.len(), 512);
+
+    assert!(VerifiableDelayFunction::verify_proof(b"seed", 1_000, &proof));
+    assert!(!VerifiableDelayFunction::verify_proof(b"seed", 999, &proof));
+    assert!(!VerifiableDelayFunction::verify_proof(b"other", 1_000, &proof));
+    Ok(())
+}
+
+#[test]
+fn test_short_delays_need_no_midpoints() {
+    let proof = VerifiableDelayFunction::prove_sync(b"seed", VDF_PROOF_CUTOFF);
+    assert!(proof.midpoints.is_empty());
+    assert!(VerifiableDelayFunction::verify_proof(b"seed", VDF_PROOF_CUTOFF, &proof));
+
+    let zero = VerifiableDelayFunction::prove_sync(b"seed", 0);
+    let once = VerifiableDelayFunction::prove_sync(b"seed", 1);
+    assert_ne!(zero.output, once.output);
+    assert!(VerifiableDelayFunction::verify_proof(b"seed", 1, &once));
+}
+
+#[test]
+fn test_tampered_proofs_are_refused() {
+    let proof = VerifiableDelayFunction::prove_sync(b"seed", 300);
+    let valid = |proof: &VdfProof| VerifiableDelayFunction::verify_proof(b"seed", 300, proof);
+    assert!(valid(&proof));
+
+    let mut wrong_output = proof.clone();
+    wrong_output.output = VerifiableDelayFunction::prove_sync(b"seed", 299).output;
+    assert!(!valid(&wrong_output));
+
+    let mut wrong_midpoint = proof.clone();
+    wrong_midpoint.midpoints[0] = proof.midpoints[1].clone();
+    assert!(!valid(&wrong_midpoint));
+
+    let mut extra_midpoint = proof.clone();
+    extra_midpoint.midpoints.push(proof.output.clone());
+    assert!(!valid(&extra_midpoint));
+
+    // Elements are only accepted in canonical form, below N/2
+    let mut negated = proof.clone();
+    negated.output = "ff".repeat(256);
+    assert!(!valid(&negated));
+    let mut garbage = proof;
+    garbage.output = "not hex".to_string();
+    assert!(!valid(&garbage));
+}
+
+#[tokio::test]
+async fn test_delays_above_the_maximum_are_refused() {
+    let proof = VerifiableDelayFunction::prove_sync(b"seed", 300);
+    assert!(!VerifiableDelayFunction::verify_proof(b"seed", u64::MAX, &proof));
+    assert!(!VerifiableDelayFunction::verify_proof(b"seed", MAX_VDF_ITERATIONS + 1, &proof));
+    let vdf = VerifiableDelayFunction::new();
+    assert!(vdf.prove(b"seed".to_vec(), u64::MAX).await.is_err());
+}
+
+fn message(data: serde_json::Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("BEACON".to_string()), data)
+}
+
+#[tokio::test]
+async fn test_beacon_randomness_waits_for_the_delay() -> Result<()> {
+    let key = ECDSASigner::new()?;
+    let address = key.get_public_key_pem()?;
+    let mut beacon = RandomnessBeaconObject::new(60, 1)?.with_vdf(200);
+    beacon.register_validator(BeaconValidator {
+        address: address.clone(),
+        public_key: address.clone(),
+        vrf_key: address.clone(),
+        stake: 100,
+        active: true,
+        last_participation: None,
+    })?;
+
+    let vrf = helpers::create_vrf_proof_message(1, "seed".to_string(), address.clone(), &key)?;
+    beacon.add_message(message(vrf)).await?;
+    let partial =
+        helpers::create_partial_signature_message(1, address.clone(), "partial".to_string(), &key)?;
+    beacon.add_message(message(partial)).await?;
+
+    // The round closed, but only its seed is known
+    assert_eq!(beacon.current_round, 2);
+    assert_eq!(beacon.get_latest_randomness(), None);
+    let seed = beacon.delay_seed(1).unwrap().to_string();
+
+    let wrong = helpers::create_vdf_evaluation(1, &seed, 199).await?;
+    let outcome = beacon.add_message(message(wrong)).await?;
+    assert_eq!(outcome.reason().unwrap().code, "invalid_vdf_proof");
+
+    let evaluation = helpers::create_vdf_evaluation(1, &seed, 200).await?;
+    assert!(beacon
+        .add_message(message(evaluation.clone()))
+        .await?
+        .is_applied());
+    let randomness = beacon.get_latest_randomness().unwrap();
+    assert_ne!(randomness, seed);
+    assert!(beacon.awaiting_delay.is_empty());
+    assert_eq!(beacon.get_beacon_stats()["awaiting_delay"], json!([]));
+
+    let outcome = beacon.add_message(message(evaluation)).await?;
+    assert_eq!(outcome.reason().unwrap().code, "already_finalized");
+    Ok(())
+}
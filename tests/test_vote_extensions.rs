@@ -0,0 +1,164 @@
+mod common;
+
+use chaincraft_rust::{
+    examples::tendermint::{
+        self, TendermintMessageType, TendermintObject, ValidatorKey, VoteExtension,
+        VoteExtensionHandler,
+    },
+    shared::{MessageType, SharedMessage},
+    shared_object::{ApplicationObject, ApplyOutcome},
+    Result,
+};
+use common::{code, key};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+fn message(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("consensus".to_string()), data)
+}
+
+/// Validators report a price; observations off by more than 10% are refused
+struct PriceOracle {
+    price: u64,
+}
+
+impl VoteExtensionHandler for PriceOracle {
+    fn extend_vote(&self, height: u64, _block_hash: &str) -> Option<Value> {
+        Some(json!({ "price": self.price + height }))
+    }
+
+    fn verify_vote_extension(
+        &self,
+        _height: u64,
+        _validator: &str,
+        _block_hash: &str,
+        extension: &Value,
+    ) -> bool {
+        extension["price"]
+            .as_u64()
+            .is_some_and(|price| price.abs_diff(self.price) * 10 <= self.price)
+    }
+}
+
+fn oracle_chain() -> Result<TendermintObject> {
+    let mut tendermint = TendermintObject::with_signer(key("v1"))?
+        .with_vote_extensions(Arc::new(PriceOracle { price: 100 }));
+    for name in ["v1", "v2", "v3"] {
+        tendermint.add_validator(
+            name.to_string(),
+            ValidatorKey::new(key(name).public_key().clone()),
+            1,
+        );
+    }
+    Ok(tendermint)
+}
+
+async fn precommit(
+    tendermint: &mut TendermintObject,
+    name: &str,
+    block_hash: Option<&str>,
+    extension: Option<Value>,
+) -> Result<ApplyOutcome> {
+    let data = tendermint::helpers::create_extended_precommit_message(
+        tendermint.current_height,
+        0,
+        block_hash.map(str::to_string),
+        name.to_string(),
+        extension,
+        &key(name),
+    )?;
+    tendermint.add_message(message(data)).await
+}
+
+#[tokio::test]
+async fn test_extensions_are_aggregated_into_the_next_block() -> Result<()> {
+    let mut tendermint = oracle_chain()?;
+
+    for (name, price) in [("v2", 101), ("v1", 99), ("v3", 104)] {
+        let outcome =
+            precommit(&mut tendermint, name, Some("block-1"), Some(json!({ "price": price })))
+                .await?;
+        assert_eq!(outcome, ApplyOutcome::Applied);
+    }
+    assert_eq!(tendermint.current_height, 2);
+    assert!(tendermint.blocks[1].vote_extensions.is_empty());
+    let extension = |validator: &str, price: u64| VoteExtension {
+        validator: validator.to_string(),
+        height: 1,
+        extension: json!({ "price": price }),
+    };
+    let expected = vec![extension("v1", 99), extension("v2", 101), extension("v3", 104)];
+    assert_eq!(tendermint.pending_extensions, expected);
+
+    // A validator may precommit without an extension
+    for name in ["v1", "v2", "v3"] {
+        precommit(&mut tendermint, name, Some("block-2"), None).await?;
+    }
+    assert_eq!(tendermint.current_height, 3);
+    assert_eq!(tendermint.blocks[2].vote_extensions, expected);
+    assert!(tendermint.pending_extensions.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_extensions_are_rejected() -> Result<()> {
+    let mut tendermint = oracle_chain()?;
+
+    let outcome =
+        precommit(&mut tendermint, "v1", Some("block-1"), Some(json!({ "price": 150 }))).await?;
+    assert_eq!(code(&outcome), Some("invalid_extension"));
+    let outcome = precommit(&mut tendermint, "v1", None, Some(json!({ "price": 100 }))).await?;
+    assert_eq!(code(&outcome), Some("unexpected_extension"));
+
+    // The extension is covered by the signature
+    let mut data = tendermint::helpers::create_extended_precommit_message(
+        1,
+        0,
+        Some("block-1".to_string()),
+        "v2".to_string(),
+        Some(json!({ "price": 100 })),
+        &key("v2"),
+    )?;
+    data["Precommit"]["extension"] = json!({ "price": 109 });
+    let outcome = tendermint.add_message(message(data)).await?;
+    assert_eq!(code(&outcome), Some("invalid_signature"));
+
+    // Objects without a handler accept no extensions
+    let mut plain = TendermintObject::new()?;
+    plain.add_validator("v1".to_string(), ValidatorKey::new(key("v1").public_key().clone()), 1);
+    let outcome =
+        precommit(&mut plain, "v1", Some("block-1"), Some(json!({ "price": 100 }))).await?;
+    assert_eq!(code(&outcome), Some("unexpected_extension"));
+    assert_eq!(precommit(&mut plain, "v1", Some("block-1"), None).await?, ApplyOutcome::Applied);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_own_precommits_carry_the_handler_extension() -> Result<()> {
+    let mut tendermint = oracle_chain()?;
+    let me = tendermint.my_validator_address.clone();
+    tendermint.add_validator(me.clone(), tendermint.validator_key(), 1);
+
+    let TendermintMessageType::Precommit { extension, .. } =
+        tendermint.create_precommit(Some("block-1".to_string()))?
+    else {
+        panic!("not a precommit");
+    };
+    assert_eq!(extension, Some(json!({ "price": 101 })));
+    let nil = tendermint.create_precommit(None)?;
+    assert!(matches!(
+        nil,
+        TendermintMessageType::Precommit {
+            extension: None,
+            ..
+        }
+    ));
+
+    let own = tendermint.create_precommit(Some("block-1".to_string()))?;
+    let outcome = tendermint
+        .add_message(message(serde_json::to_value(own)?))
+        .await?;
+    assert_eq!(outcome, ApplyOutcome::Applied);
+    assert_eq!(tendermint.precommits[&(1, 0)][&me].extension, extension);
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use chaincraft_rust::{
+    crypto::{
+        ecdsa::ECDSASigner, utils, vrf::VerifiableRandomFunction as Vrf, KeyType, PrivateKey,
+    },
+    examples::randomness_beacon::{helpers, BeaconValidator, RandomnessBeaconObject},
+    shared::{MessageType, SharedMessage},
+    shared_object::ApplicationObject,
+    Result,
+};
+use serde_json::Value;
+
+fn beacon_message(data: Value) -> SharedMessage {
+    SharedMessage::new(MessageType::Custom("BEACON".to_string()), data)
+}
+
+/// Re-sign a VRF proof message after changing its fields
+fn resign(mut data: Value, vrf: &ECDSASigner) -> Result<Value> {
+    let proof = &data["VrfProof"];
+    let payload = format!(
+        "vrf:{}:{}:{}:{}",
+        proof["round"],
+        proof["input"].as_str().unwrap(),
+        proof["proof"].as_str().unwrap(),
+        proof["output"].as_str().unwrap()
+    );
+    data["VrfProof"]["signature"] = hex::encode(vrf.sign(payload.as_bytes())?.to_bytes()).into();
+    Ok(data)
+}
+
+#[test]
+fn test_ed25519_matches_rfc_9381_vector() -> Result<()> {
+    // RFC 9381, appendix B.3, example 16
+    let key = PrivateKey::from_hex(
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        KeyType::Ed25519,
+    )?;
+    let proof = Vrf::prove(&key, b"")?;
+    assert_eq!(
+        hex::encode(&proof),
+        "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f\
+         26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab12\
+         68a1b0db10836d9826a528ca76567805"
+    );
+    let output = Vrf::verify(&key.public_key(), b"", &proof)?;
+    assert_eq!(
+        hex::encode(output),
+        "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff\
+         66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_proofs_verify_only_for_their_key_and_input() -> Result<()> {
+    for key_type in [KeyType::Ed25519, KeyType::Secp256k1] {
+        let (key, public) = utils::generate_keypair(key_type)?;
+        let (_, other) = utils::generate_keypair(key_type)?;
+
+        let proof = Vrf::prove(&key, b"round 7")?;
+        // The output is a function of key and input alone
+        assert_eq!(proof, Vrf::prove(&key, b"round 7")?);
+        let output = Vrf::verify(&public, b"round 7", &proof)?;
+        assert_eq!(output, Vrf::proof_to_hash(&proof)?);
+        assert_ne!(output, Vrf::verify(&public, b"round 8", &Vrf::prove(&key, b"round 8")?)?);
+
+        assert!(Vrf::verify(&public, b"round 8", &proof).is_err());
+        assert!(Vrf::verify(&other, b"round 7", &proof).is_err());
+        for i in [0, proof.len() / 2, proof.len() - 1] {
+            let mut tampered = proof.clone();
+            tampered[i] ^= 1;
+            assert!(
+                Vrf::verify(&public, b"round 7", &tampered).is_err(),
+                "{:?} byte {}",
+                key_type,
+                i
+            );
+        }
+        assert!(Vrf::verify(&public, b"round 7", &proof[1..]).is_err());
+
+        let (proof, output) = Vrf::evaluate_hex(&key, b"seed")?;
+        assert!(Vrf::is_evaluation_hex(&public, b"seed", &proof, &output));
+        assert!(!Vrf::is_evaluation_hex(&public, b"seed", &proof, &"00".repeat(32)));
+        assert!(!Vrf::is_evaluation_hex(&public, b"seed", "not hex", &output));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_beacon_accepts_only_evaluated_outputs() -> Result<()> {
+    let vrf = ECDSASigner::new()?;
+    let mut beacon = RandomnessBeaconObject::new(60, 2)?;
+    beacon.register_validator(BeaconValidator {
+        address: "v1".to_string(),
+        public_key: ECDSASigner::new()?.get_public_key_pem()?,
+        vrf_key: vrf.get_public_key_pem()?,
+        stake: 100,
+        active: true,
+        last_participation: None,
+    })?;
+    let honest = helpers::create_vrf_proof_message(1, "seed".to_string(), "v1".to_string(), &vrf)?;
+
+    // A signed output the proof does not commit to is a picked output
+    let mut picked = honest.clone();
+    picked["VrfProof"]["output"] = "ff".repeat(64).into();
+    let outcome = beacon
+        .add_message(beacon_message(resign(picked, &vrf)?))
+        .await?;
+    assert_eq!(outcome.reason().map(|r| r.code.as_str()), Some("invalid_vrf_proof"));
+
+    // A proof is bound to its round and input
+    let mut moved = honest.clone();
+    moved["VrfProof"]["input"] = "other seed".into();
+    let outcome = beacon
+        .add_message(beacon_message(resign(moved, &vrf)?))
+        .await?;
+    assert_eq!(outcome.reason().map(|r| r.code.as_str()), Some("invalid_vrf_proof"));
+
+    assert!(beacon
+        .add_message(beacon_message(honest))
+        .await?
+        .is_applied());
+    assert_eq!(beacon.pending_vrf_proofs[&1].len(), 1);
+    Ok(())
+}